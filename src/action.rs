@@ -1,6 +1,9 @@
 #[derive(Debug, Clone, PartialEq)]
 pub enum Action {
+    /// `q`: quit from Normal, close the overlay anywhere else
     Quit,
+    /// Ctrl+C: twice within a second quits from any mode
+    Interrupt,
     NavigateUp,
     NavigateDown,
     NavigateLeft,
@@ -8,6 +11,8 @@ pub enum Action {
     Select,
     AddProject,
     AddWorktree,
+    /// worktree + `dev` session + attach, from one branch prompt
+    StartWork,
     AddSession,
     Delete,
     Clean,
@@ -22,15 +27,67 @@ pub enum Action {
     NextActive,
     SendCommand,
     SendCtrlC,
+    /// Restart the session's command in place: Ctrl+C and resend, or respawn the pane.
+    Respawn,
     EnterMove,
     JumpProjectDown,
     JumpProjectUp,
     SearchStart,
+    /// `n` / `N`: repeat the last search, or step through pending sessions when there is none.
+    SearchNext,
+    SearchPrev,
     GitPopup,
+    /// `f`: fetch the selected worktree now, in the background.
+    FetchNow,
+    SidebarShrink,
+    SidebarGrow,
+    ToggleOrder,
+    TogglePin,
+    /// `#`: set or clear the selected worktree's note.
+    EditNote,
+    KillStale,
+    ToggleWrap,
+    ToggleFollow,
+    /// Tree and preview titles: friendly names <-> raw tmux names and paths.
+    ToggleRawNames,
+    /// Preview paths in full instead of shortened to fit.
+    ToggleFullPaths,
+    /// Mouse capture on/off; off lets the terminal select text.
+    ToggleMouse,
+    /// Scroll the session preview by this many lines (negative = up).
+    ScrollPreview(i32),
+    /// `{` / `}`: preview (and attach to) the session's prev / next window.
+    CycleWindow(i32),
+    OpenWith,
+    /// Attach to the project's main checkout, making a shell session there if it has none.
+    MainShell,
+    /// `.`: the project's actions menu — pull main, clean, main shell, `action.name` entries.
+    ProjectActions,
+    Doctor,
+    /// Workspace-wide counts: projects, worktrees, sessions, git state.
+    Stats,
+    /// Tag the worktree's branch and remove it, listing it under "Archived" to restore later.
+    Archive,
+    /// `git worktree lock` the selected worktree (asking for a reason), or unlock it.
+    ToggleLock,
+    /// `+`: bring a worktree made outside wsx into its layout — move, alias, session.
+    Adopt,
+    /// `Ctrl+G`: select the worktree a pasted file path is in; pressed again, edit the file.
+    LocatePath,
+    /// Sessions that went away, with their last pane capture.
+    EndedSessions,
+    /// List the worktrees `worktree.hide` keeps out of the tree, dimmed, or hide them again.
+    ToggleHidden,
+    /// `git diff` of the selected worktree's modified files, one at a time.
+    ViewDiff,
     InputChar(char),
     InputBackspace,
     InputTab,
     InputEscape,
     MouseClick { col: u16, row: u16 },
+    /// left button moved while held
+    MouseDrag { col: u16, row: u16 },
+    MouseRelease,
+    MouseScroll { col: u16, row: u16, delta: i32 },
     None,
 }
@@ -1,29 +1,69 @@
 // App state machine and event loop.
 // ref: ratatui app patterns — https://ratatui.rs/concepts/application-patterns/
 
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::sync::mpsc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use ratatui::layout::{Position, Rect};
 
 use crate::{
     action::Action,
-    config::global::GlobalConfig,
+    cache::{self, CacheWriter, WorkspaceCache},
+    cli::Args,
+    config::global::{GlobalConfig, MainWorktree, ProjectEntry, ProjectOrder, SessionOrder},
+    doctor::{self, Finding},
     event::poll_event,
-    git::{info as git_info, ops as git_ops, worktree as git_worktree},
-    model::workspace::{flatten_tree, FlatEntry, Selection, WorkspaceState},
-    ops,
-    tmux::{capture, monitor, session},
+    events::{self, Event, EventSink},
+    launch,
+    history::CommandHistory,
+    hooks,
+    git::{
+        ops::{self as git_ops, StashOp},
+        queue::{RepoQueue, RepoTurn, QUEUE_TIMEOUT},
+        worktree::{CreateWorktreeError, NewWorktree, RemoteBranch},
+        CliGit, GitBackend,
+    },
+    model::{
+        attention::AttentionReason,
+        ended::EndedSession,
+        focus::{Focus, HIDDEN_ACTIVITY_INTERVAL},
+        frecency,
+        project_actions::{self, ProjectAction},
+        session_import::{self, InferredProject},
+        setup::{HookRun, WorktreeSetup},
+        stats::{workspace_stats, WorkspaceStats},
+        sticky_error::StickyError,
+        tree_nav,
+        workspace::{flatten_tree, FlatEntry, Project, Selection, StashEntry, WorkspaceState},
+    },
+    ops::{self, RegisterProjectError},
+    tmux::{capture, monitor::SessionStatus, naming, session, CliTmux, NoServer, TmuxBackend},
     tui::{self, Tui},
-    ui::{self, input::InputState},
+    ui::{
+        self,
+        input::{CompletionSource, FormState, InputState},
+        path::display_path,
+        picker::PickerState,
+        preview::SessionView,
+        scrollbar::scrollbar_target,
+        theme::Theme,
+    },
 };
 
 // ── Timer ─────────────────────────────────────────────────────────────────────
 
+/// tmux / git calls made for the preview, shown by `--debug`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PreviewStats {
+    pub captures: u64,
+    pub skipped_captures: u64,
+    pub git_info_loads: u64,
+}
+
 struct Timer {
     last: Instant,
     interval: Duration,
@@ -37,6 +77,10 @@ impl Timer {
         }
     }
 
+    fn set_interval(&mut self, interval_ms: u64) {
+        self.interval = Duration::from_millis(interval_ms);
+    }
+
     fn ready(&mut self) -> bool {
         if self.last.elapsed() >= self.interval {
             self.last = Instant::now();
@@ -48,35 +92,46 @@ impl Timer {
 }
 
 const TICK_MS: u64 = 100;
-const CAPTURE_INTERVAL_MS: u64 = 500;
-const RESCAN_INTERVAL_MS: u64 = 2000;
-const ACTIVITY_INTERVAL_MS: u64 = 1000;
+/// Captures and git reads wait until the selection has stayed put this long.
+const SELECTION_SETTLE_MS: u64 = 150;
 const FETCH_INTERVAL_SECS: u64 = 60;
 const GIT_LOCAL_INTERVAL_MS: u64 = 3000;
+/// status bar note while `M` has the mouse left to the terminal
+pub const MOUSE_OFF: &str = "mouse: off (terminal selection enabled)";
+/// how long Ctrl+R waits for Ctrl+C to bring a session back to its shell before asking
+/// to kill what it runs
+const RESPAWN_WAIT: Duration = Duration::from_secs(3);
+/// a second Ctrl+C within this quits
+const INTERRUPT_WINDOW: Duration = Duration::from_secs(1);
 pub use ops::IDLE_SECS;
 
 // ── Modes ─────────────────────────────────────────────────────────────────────
 
+/// Layers that act on one project, worktree or session name it by path or session
+/// name, like `InputContext`, and resolve it again on each key.
 pub enum Mode {
     Normal,
     Input {
         context: InputContext,
         state: InputState,
     },
+    Form {
+        context: FormContext,
+        form: FormState,
+    },
     Confirm {
         message: String,
         pending: PendingAction,
+        danger: bool, // escalated styling — e.g. sessions attached elsewhere
     },
     Config {
-        project_idx: usize,
+        project: PathBuf,
     },
     Move {
-        project_idx: usize,
+        project: PathBuf,
     },
     MoveSession {
-        project_idx: usize,
-        worktree_idx: usize,
-        session_idx: usize,
+        session: String,
     },
     Help,
     Search {
@@ -84,85 +139,423 @@ pub enum Mode {
         match_idx: usize,
     },
     GitPopup {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
+    },
+    /// stash manager for one worktree; `picker` rows match `entries`
+    Stashes {
+        worktree: PathBuf,
+        entries: Vec<StashEntry>,
+        picker: PickerState,
+    },
+    /// files a merge / rebase left conflicted in one worktree; `picker` rows match `files`
+    Conflicts {
+        worktree: PathBuf,
+        files: Vec<String>,
+        picker: PickerState,
+    },
+    /// scrollable read-only text: a stash diffstat, the env files a new worktree got
+    GitOutput {
+        title: String,
+        text: String,
+        scroll: u16,
+    },
+    /// choose one of `config.open_with` to run on `path`
+    OpenWith {
+        path: PathBuf,
+        picker: PickerState,
+    },
+    /// `.`: `actions` for the project at `project`; `picker` rows match `actions`
+    ProjectActions {
+        project: PathBuf,
+        actions: Vec<ProjectAction>,
+        picker: PickerState,
+    },
+    Doctor {
+        findings: Vec<Finding>,
+    },
+    /// first run: repos the running tmux sessions sit in, to register; `picker` rows
+    /// match `candidates`, `chosen` are the ticked ones
+    ImportProjects {
+        candidates: Vec<InferredProject>,
+        chosen: Vec<bool>,
+        picker: PickerState,
+    },
+    /// `I`: computed when opened, not kept up to date
+    Stats {
+        stats: WorkspaceStats,
+    },
+    /// `E`: recently ended sessions, newest first; `picker` rows match `entries` and
+    /// `scroll` is the selected one's capture (`u16::MAX` for its end)
+    Ended {
+        entries: Vec<EndedSession>,
+        picker: PickerState,
+        scroll: u16,
+    },
+    /// `V` with several modified files: which one's diff to open; `picker` rows match `files`
+    DiffFiles {
+        worktree: PathBuf,
+        files: Vec<String>,
+        picker: PickerState,
+    },
+    /// `V`: the diff of `files[index]` in `worktree`; `[` / `]` step through `files`
+    Diff {
+        worktree: PathBuf,
+        files: Vec<String>,
+        index: usize,
+        text: String,
+        scroll: u16,
     },
 }
 
+/// Prompts, forms and confirms name their target by project or worktree path and
+/// tmux session name rather than tree index: the rescan keeps running while one is
+/// open and can reorder or drop entries. `App::resolve_*` finds the target again.
 pub enum InputContext {
+    /// a local path, or a git URL to clone first
     AddProject,
-    AddWorktree {
-        project_idx: usize,
+    /// where to clone `url` before registering it
+    CloneProject {
+        url: String,
     },
-    AddSession {
-        project_idx: usize,
-        worktree_idx: usize,
+    /// new location for a project whose directory is missing
+    RelocateProject {
+        project: PathBuf,
     },
-    AddSessionCmd {
-        project_idx: usize,
-        worktree_idx: usize,
-        session_name: String,
+    AddWorktree {
+        project: PathBuf,
+    },
+    /// branch for `Space`: worktree, `dev` session, attach
+    StartWork {
+        project: PathBuf,
     },
     SetAlias {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
+    },
+    /// note for the worktree; empty clears it
+    SetNote {
+        worktree: PathBuf,
     },
     RenameSession {
-        project_idx: usize,
-        worktree_idx: usize,
-        session_idx: usize,
+        session: String,
     },
     SendCommand {
         session_name: String,
     },
     GitPullRebase {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
     },
     GitMergeFrom {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
     },
     GitMergeInto {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
+    },
+    /// branch for a new worktree that takes this one's uncommitted changes along
+    BranchOff {
+        worktree: PathBuf,
+    },
+    DefaultBranch {
+        project: PathBuf,
+    },
+    /// optional reason for `git worktree lock`
+    LockWorktree {
+        worktree: PathBuf,
     },
+    /// a file path, for the worktree it's in
+    LocatePath,
 }
 
 impl InputContext {
     pub fn title(&self) -> &'static str {
         match self {
-            InputContext::AddProject => "Add Project",
+            InputContext::AddProject => "Add Project — path or git URL",
+            InputContext::CloneProject { .. } => "Clone Into",
+            InputContext::RelocateProject { .. } => "Relocate Project",
             InputContext::AddWorktree { .. } => "Add Worktree",
-            InputContext::AddSession { .. } => "New Session — name",
-            InputContext::AddSessionCmd { .. } => "New Session — command",
+            InputContext::StartWork { .. } => "Start Work — worktree + dev session",
             InputContext::SetAlias { .. } => "Set Alias",
+            InputContext::SetNote { .. } => "Worktree Note — empty clears",
             InputContext::RenameSession { .. } => "Rename Session",
             InputContext::SendCommand { .. } => "Send Command",
             InputContext::GitPullRebase { .. } => "Pull Rebase — branch",
             InputContext::GitMergeFrom { .. } => "Merge From — branch",
             InputContext::GitMergeInto { .. } => "Merge Into — branch",
+            InputContext::BranchOff { .. } => "Branch Off With Changes",
+            InputContext::DefaultBranch { .. } => "Default Branch",
+            InputContext::LockWorktree { .. } => "Lock Worktree",
+            InputContext::LocatePath => "Find Worktree — file path",
+        }
+    }
+}
+
+pub enum FormContext {
+    /// fields: name, command
+    AddSession {
+        worktree: PathBuf,
+    },
+    /// fields: move to, alias, session — each skipped when blank
+    Adopt {
+        worktree: PathBuf,
+    },
+}
+
+impl FormContext {
+    pub fn title(&self) -> &'static str {
+        match self {
+            FormContext::AddSession { .. } => "New Session (+name adds a window)",
+            FormContext::Adopt { .. } => "Adopt Worktree (blank skips a step)",
         }
     }
 }
 
+/// What the clone worker reports back.
+enum CloneEvent {
+    Progress(u8),
+    Done(std::result::Result<(), String>),
+}
+
+/// A `git clone` from Add Project in flight; registered as a project when it finishes.
+pub struct Cloning {
+    pub name: String,
+    pub dest: PathBuf,
+    /// "Receiving objects" percentage, once git reports one
+    pub percent: Option<u8>,
+}
+
+/// What the postCreate worker reports back.
+enum HookEvent {
+    /// the running hook, for cancelling
+    Started(hooks::HookChild),
+    Line(String),
+    Done(std::result::Result<(), String>),
+}
+
+/// What a postCreate run was started for.
+#[derive(Clone, Copy, PartialEq)]
+enum PostCreateFor {
+    /// a worktree just made with `w` or restored from the archive
+    Create,
+    /// `h` in the git popup on an existing worktree
+    Rerun,
+    /// `Space`: the dev session starts once the hook is done, as its command usually
+    /// needs what the hook installs
+    StartWork,
+}
+
+/// A postCreate hook streaming into the output popup after creating a worktree (or
+/// run again from the git popup).
+pub struct PostCreateRun {
+    branch: String,
+    child: Option<hooks::HookChild>,
+    cancelled: bool,
+    /// keep the popup on the newest line until the user scrolls
+    follow: bool,
+    /// for the final status and the env-file summary
+    created: ops::CreatedWorktree,
+    command: String,
+    started: Instant,
+    /// the output's last lines, for the worktree's setup record
+    tail: Vec<String>,
+    started_for: PostCreateFor,
+}
+
+/// A Ctrl+R whose Ctrl+C went out; `tick` looks for the shell until `deadline`, then
+/// asks before killing what the pane runs.
+struct RespawnWait {
+    session: String,
+    display: String,
+    start_dir: PathBuf,
+    command: Option<String>,
+    deadline: Instant,
+}
+
+/// The scrollbar a held left button is dragging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScrollbarDrag {
+    Tree,
+    Preview,
+}
+
+/// The confirm dialog's button that Enter presses; ←/→ and Tab move it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfirmButton {
+    #[default]
+    Confirm,
+    Cancel,
+}
+
+/// How a key reads in a confirm dialog.
+#[derive(Debug, PartialEq)]
+enum ConfirmKey {
+    Yes,
+    No,
+    Focus(ConfirmButton),
+    /// worth a hint — the user is pressing keys that do nothing here
+    Unrelated,
+    Ignored,
+}
+
+/// Import picker rows: "[x] ~/code/api · 3 sessions".
+fn import_rows(candidates: &[InferredProject], chosen: &[bool]) -> Vec<String> {
+    candidates
+        .iter()
+        .zip(chosen)
+        .map(|(c, &on)| {
+            let n = c.sessions.len();
+            format!(
+                "[{}] {} · {} session{}",
+                if on { "x" } else { " " },
+                display_path(&c.repo, false, usize::MAX),
+                n,
+                if n == 1 { "" } else { "s" }
+            )
+        })
+        .collect()
+}
+
+/// Letters count in either case, so caps lock doesn't make the dialog look frozen.
+fn confirm_key(action: &Action, focus: ConfirmButton) -> ConfirmKey {
+    match action {
+        Action::InputChar('y' | 'Y') => ConfirmKey::Yes,
+        Action::InputChar('n' | 'N' | 'q' | 'Q') | Action::InputEscape => ConfirmKey::No,
+        Action::Select if focus == ConfirmButton::Confirm => ConfirmKey::Yes,
+        Action::Select => ConfirmKey::No,
+        Action::NavigateLeft => ConfirmKey::Focus(ConfirmButton::Confirm),
+        Action::NavigateRight => ConfirmKey::Focus(ConfirmButton::Cancel),
+        Action::InputTab => ConfirmKey::Focus(match focus {
+            ConfirmButton::Confirm => ConfirmButton::Cancel,
+            ConfirmButton::Cancel => ConfirmButton::Confirm,
+        }),
+        Action::InputChar(_)
+        | Action::InputBackspace
+        | Action::NavigateUp
+        | Action::NavigateDown
+        | Action::ScrollPreview(_) => ConfirmKey::Unrelated,
+        _ => ConfirmKey::Ignored,
+    }
+}
+
 pub enum PendingAction {
     DeleteProject {
-        project_idx: usize,
+        project: PathBuf,
     },
     DeleteWorktree {
-        project_idx: usize,
-        worktree_idx: usize,
+        worktree: PathBuf,
+        /// discard uncommitted changes (the confirm message said so)
+        force: bool,
+    },
+    /// Kill old idle shell sessions (tmux names) found by `ops::stale_sessions`.
+    KillSessions {
+        names: Vec<String>,
+    },
+    /// Clean a merged worktree; `force` once it was confirmed with uncommitted changes.
+    CleanWorktree {
+        worktree: PathBuf,
+        force: bool,
+    },
+    /// remove every merged worktree of the project
+    CleanProject {
+        project: PathBuf,
     },
     DeleteSession {
-        project_idx: usize,
-        worktree_idx: usize,
-        session_idx: usize,
+        session: String,
     },
     CreateWorktree {
-        project_idx: usize,
+        project: PathBuf,
+        branch: String,
+    },
+    /// create a worktree, then move `worktree`'s uncommitted changes into it
+    BranchOff {
+        worktree: PathBuf,
         branch: String,
     },
+    JumpToWorktree {
+        worktree: PathBuf,
+    },
+    DeleteRemoteBranches {
+        targets: Vec<RemoteBranch>,
+    },
+    AttachSession {
+        session: String,
+        window_idx: Option<usize>,
+    },
+    KillWindow {
+        target: String,
+        name: String,
+    },
+    /// Ctrl+C didn't stop the session's command: kill it and start a shell over
+    RestartPane {
+        session: String,
+        start_dir: PathBuf,
+        command: Option<String>,
+    },
+    DropStash {
+        worktree: PathBuf,
+        index: usize,
+    },
+    /// every conflict is resolved: commit the merge or continue the rebase
+    ContinueOperation {
+        worktree: PathBuf,
+    },
+    /// register the main repo of a worktree that was entered as a project
+    RegisterProject {
+        path: PathBuf,
+    },
+    /// write the aliases `alias.pattern` derives into the config
+    MaterializeAliases {
+        project: PathBuf,
+    },
+    ArchiveWorktree {
+        worktree: PathBuf,
+    },
+    /// recreate the worktree from an archive tag (and its sessions), then drop the tag
+    RestoreArchived {
+        project: PathBuf,
+        tag: String,
+    },
+    PurgeArchived {
+        project: PathBuf,
+        tag: String,
+    },
+    // The rest aren't confirmed: they're git operations queued for their repository.
+    GitOp {
+        worktree: PathBuf,
+        op: GitOp,
+    },
+    Stash {
+        worktree: PathBuf,
+        op: StashOp,
+        index: usize,
+    },
+    MarkResolved {
+        worktree: PathBuf,
+        file: String,
+        row: usize,
+    },
+    /// create the worktree and its dev session, then attach
+    StartWork {
+        project: PathBuf,
+        input: String,
+    },
+    Adopt {
+        worktree: PathBuf,
+        dest: Option<PathBuf>,
+        alias: String,
+        session: String,
+    },
+    /// lock with `reason`, or unlock with None
+    LockWorktree {
+        worktree: PathBuf,
+        reason: Option<String>,
+    },
+}
+
+/// A git operation waiting for its repository; `tick` starts it once the repository is free.
+struct QueuedGit {
+    repo: PathBuf,
+    label: &'static str,
+    pending: PendingAction,
+    since: Instant,
 }
 
 // ── App ──────────────────────────────────────────────────────────────────────
@@ -174,54 +567,281 @@ pub struct App {
     pub tree_visible_height: usize,
     pub tree_area: Rect,
     pub preview_area: Rect,
+    /// screen row of the first session line in the worktree preview (set while rendering)
+    pub preview_sessions_top: Option<u16>,
+    pub session_view: SessionView,
+    /// palette adaptation applied to every frame; detected once at startup
+    pub theme: Theme,
+    /// `v`: show tmux session names and worktree paths; not persisted
+    pub raw_names: bool,
+    /// `P`: paths in the preview unshortened; `y` copies full paths either way
+    pub full_paths: bool,
+    /// `M` / `mouse`: wsx captures the mouse; off, the terminal's own selection works
+    pub mouse: bool,
+    /// commands typed for new sessions and `S`, offered on ↑
+    pub history: CommandHistory,
+    /// `--events-fifo`: state changes for external scripts
+    pub events: EventSink,
+    /// tmux session wsx itself runs in; attaching to or killing it would pull wsx away
+    pub own_session: Option<String>,
+    /// `tmux_defaults` entries tmux refused on sessions wsx made
+    rejected_options: session::RejectedOptions,
+    /// the last tmux query found no server; the tree keeps the sessions it knew, greyed out
+    pub tmux_down: bool,
+    /// focused button of the open confirm dialog; back to Confirm once it closes
+    pub confirm_focus: ConfirmButton,
+    scrollbar_drag: Option<ScrollbarDrag>,
+    /// query of the last `/` search; `n` / `N` repeat it until Esc or a new search
+    pub last_search: Option<String>,
+    /// (worktree, file) the last `Ctrl+G` found; pressing it again next opens the file
+    located: Option<(PathBuf, PathBuf)>,
     pub mode: Mode,
+    /// layers under `mode` that Esc returns to, innermost last; empty in Normal
+    mode_stack: Vec<Mode>,
+    /// first Ctrl+C of a pending double press
+    interrupted_at: Option<Instant>,
+    quit_requested: bool,
     pub config: GlobalConfig,
     pub status_message: Option<String>,
     status_message_expires: Option<Instant>,
+    /// the last error, shown in a red banner until Esc or another error replaces it
+    pub error: Option<StickyError>,
     pub loading: bool,
+    /// serializes git operations per repository, background fetches included
+    pub repo_queue: Arc<RepoQueue>,
+    /// wsx's own git operations waiting for a busy repository, oldest first
+    git_queue: VecDeque<QueuedGit>,
+    /// a session to attach once the action that made it is done (a queued start work
+    /// has no terminal at hand), and the status to show after
+    attach_next: Option<(String, String)>,
     needs_redraw: bool,
     capture_timer: Timer,
+    /// selection (and window) seen by the last capture tick, and when it last moved
+    capture_seen: Option<(Selection, Option<usize>)>,
+    selection_moved_at: Instant,
+    /// moved and settled, but not captured yet
+    selection_pending: bool,
+    /// target and tmux activity timestamp of the last capture
+    last_capture: Option<(String, u64)>,
+    /// `{` / `}` on a session row: tmux session name → id of the window the preview shows
+    /// and Enter attaches to; absent means whichever window tmux has current
+    preview_windows: HashMap<String, String>,
+    pub preview_stats: PreviewStats,
+    /// `--debug`: show `preview_stats` in the status bar
+    pub debug: bool,
+    /// name of the `--config` / `WSX_CONFIG` profile, shown next to the version
+    pub profile: Option<String>,
+    /// `--debug`: sessions the last refresh couldn't place under any worktree
+    pub orphans: Vec<ops::OrphanSession>,
     rescan_timer: Timer,
     activity_timer: Timer,
+    /// `activity_timer`'s slower stand-in while hidden, for the event stream
+    hidden_activity_timer: Timer,
     git_local_timer: Timer,
+    /// nobody is looking at wsx: `tick` leaves the timers alone until it's shown again
+    focus: Focus,
     cached_flat: Vec<FlatEntry>,
     flat_dirty: bool,
     fetch_tx: mpsc::Sender<(PathBuf, bool)>,
     fetch_rx: mpsc::Receiver<(PathBuf, bool)>,
     fetch_pending: HashSet<PathBuf>,
+    clone_tx: mpsc::Sender<CloneEvent>,
+    clone_rx: mpsc::Receiver<CloneEvent>,
+    pub cloning: Option<Cloning>,
+    hook_tx: mpsc::Sender<HookEvent>,
+    hook_rx: mpsc::Receiver<HookEvent>,
+    pub post_create: Option<PostCreateRun>,
+    respawning: Option<RespawnWait>,
+    /// projects the startup thread has loaded, until the last one is in
+    sync_rx: Option<mpsc::Receiver<Project>>,
+    /// the startup setup checks, until they're in
+    doctor_rx: Option<mpsc::Receiver<Vec<Finding>>>,
+    /// first run: repos the tmux sessions run in, until they're worked out
+    import_rx: Option<mpsc::Receiver<Vec<InferredProject>>>,
+    /// failures of detached "open with" commands, reported when they exit
+    launch_tx: mpsc::Sender<String>,
+    launch_rx: mpsc::Receiver<String>,
+    /// Last `session_activity()` snapshot, reused by confirm dialogs.
+    activity: HashMap<String, SessionStatus>,
+    cache_writer: CacheWriter,
+    /// `--pick-path`: Enter on a worktree exits and reports its path.
+    pub pick_path: bool,
+    pub picked_path: Option<PathBuf>,
+    /// sidebar width changed this session; written to the config on quit
+    sidebar_dirty: bool,
+    git: Arc<dyn GitBackend>,
+    tmux: Arc<dyn TmuxBackend>,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub fn new(args: &Args) -> Result<Self> {
+        let git: Arc<dyn GitBackend> = Arc::new(CliGit);
         let config = GlobalConfig::load()?;
-        let mut workspace = ops::load_workspace(&config);
-        let tree_selected = crate::cache::apply_cache(&mut workspace);
+        // Drawn from the cache right away; `start_sync` loads the live data behind it.
+        let (mut workspace, tree_selected) = cache::cached_workspace(&config);
+        ops::order_projects(&mut workspace, &config);
+        let mut app = Self::with_workspace(
+            args,
+            config,
+            workspace,
+            tree_selected,
+            git,
+            Arc::new(CliTmux),
+        );
+        app.theme = Theme::detect(app.config.theme);
+        app.profile = GlobalConfig::profile_name();
+        app.history = CommandHistory::load();
+        app.own_session = session::current_session();
+        if let Some(path) = &args.events_fifo {
+            app.events = EventSink::open(path)
+                .with_context(|| format!("can't open event stream {}", path.display()))?;
+        }
+        app.start_doctor();
+        app.offer_session_import();
+        app.start_sync();
+        Ok(app)
+    }
+
+    /// The setup checks run tmux and git, so off the first frame; `poll_doctor` reports them.
+    fn start_doctor(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(doctor::doctor(&config));
+        });
+        self.doctor_rx = Some(rx);
+    }
+
+    fn poll_doctor(&mut self) {
+        let Some(rx) = &self.doctor_rx else { return };
+        match rx.try_recv() {
+            Ok(findings) => {
+                self.doctor_rx = None;
+                self.needs_redraw = true;
+                self.startup_checks(findings);
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.doctor_rx = None,
+        }
+    }
+
+    /// Pop up critical setup problems the first time they appear (unless another popup
+    /// is open by now); point at `D` otherwise.
+    fn startup_checks(&mut self, findings: Vec<Finding>) {
+        if findings.is_empty() {
+            return;
+        }
+        if matches!(self.mode, Mode::Normal) && doctor::critical_unseen(&findings) {
+            self.mode = Mode::Doctor { findings };
+        } else if let (1, Some(version)) = (findings.len(), crate::tmux::monitor::limited_tmux()) {
+            self.set_status(format!(
+                "{} — press D for doctor",
+                crate::tmux::monitor::limited_note(version)
+            ));
+        } else {
+            let n = findings.len();
+            self.set_status(format!(
+                "{} setup problem{} — press D for doctor",
+                n,
+                if n == 1 { "" } else { "s" }
+            ));
+        }
+    }
+
+    /// Build an App around an already-loaded workspace; git/tmux go through the given backends.
+    pub(crate) fn with_workspace(
+        args: &Args,
+        config: GlobalConfig,
+        workspace: WorkspaceState,
+        tree_selected: usize,
+        git: Arc<dyn GitBackend>,
+        tmux: Arc<dyn TmuxBackend>,
+    ) -> Self {
+        let mut workspace = workspace;
+        workspace.main_first = config.main_worktree == MainWorktree::Top;
+        workspace.recent_sessions = config.session_order == SessionOrder::Recent;
         let cached_flat = flatten_tree(&workspace);
         let (fetch_tx, fetch_rx) = mpsc::channel();
+        let (launch_tx, launch_rx) = mpsc::channel();
+        let (clone_tx, clone_rx) = mpsc::channel();
+        let (hook_tx, hook_rx) = mpsc::channel();
+        let timers = config.timers.clone();
 
-        Ok(Self {
+        Self {
             workspace,
             tree_selected,
             tree_scroll: 0,
             tree_visible_height: 20,
             tree_area: Rect::default(),
             preview_area: Rect::default(),
+            preview_sessions_top: None,
+            session_view: SessionView::default(),
+            theme: Theme::default(),
+            raw_names: false,
+            full_paths: false,
+            mouse: config.mouse(),
+            history: CommandHistory::disabled(),
+            events: EventSink::default(),
+            own_session: None,
+            rejected_options: session::RejectedOptions::default(),
+            tmux_down: false,
+            confirm_focus: ConfirmButton::default(),
+            scrollbar_drag: None,
+            last_search: None,
+            located: None,
             mode: Mode::Normal,
+            mode_stack: Vec::new(),
+            interrupted_at: None,
+            quit_requested: false,
             config,
             status_message: None,
+            error: None,
             status_message_expires: None,
             loading: false,
+            repo_queue: Arc::default(),
+            git_queue: VecDeque::new(),
+            attach_next: None,
             needs_redraw: true,
-            capture_timer: Timer::new(CAPTURE_INTERVAL_MS),
-            rescan_timer: Timer::new(RESCAN_INTERVAL_MS),
-            activity_timer: Timer::new(ACTIVITY_INTERVAL_MS),
+            capture_timer: Timer::new(timers.capture_ms()),
+            capture_seen: None,
+            selection_moved_at: Instant::now(),
+            selection_pending: true,
+            last_capture: None,
+            preview_windows: HashMap::new(),
+            preview_stats: PreviewStats::default(),
+            debug: args.debug,
+            profile: None,
+            orphans: Vec::new(),
+            rescan_timer: Timer::new(timers.rescan_ms()),
+            activity_timer: Timer::new(timers.activity_ms()),
+            hidden_activity_timer: Timer::new(HIDDEN_ACTIVITY_INTERVAL.as_millis() as u64),
             git_local_timer: Timer::new(GIT_LOCAL_INTERVAL_MS),
+            focus: Focus::default(),
             cached_flat,
             flat_dirty: false,
             fetch_tx,
             fetch_rx,
             fetch_pending: HashSet::new(),
-        })
+            clone_tx,
+            clone_rx,
+            cloning: None,
+            hook_tx,
+            hook_rx,
+            post_create: None,
+            respawning: None,
+            sync_rx: None,
+            doctor_rx: None,
+            import_rx: None,
+            launch_tx,
+            launch_rx,
+            activity: HashMap::new(),
+            cache_writer: CacheWriter::default(),
+            pick_path: args.pick_path,
+            picked_path: None,
+            sidebar_dirty: false,
+            git,
+            tmux,
+        }
     }
 
     fn set_status(&mut self, msg: impl Into<String>) {
@@ -229,6 +849,12 @@ impl App {
         self.status_message_expires = Some(Instant::now() + Duration::from_secs(4));
     }
 
+    /// Like `set_status`, but for a failure: it goes to the sticky banner instead.
+    fn set_error(&mut self, msg: impl Into<String>) {
+        StickyError::report(&mut self.error, msg.into(), frecency::now_secs());
+        self.needs_redraw = true;
+    }
+
     fn ensure_flat(&mut self) {
         if self.flat_dirty {
             self.cached_flat = flatten_tree(&self.workspace);
@@ -247,25 +873,51 @@ impl App {
     }
 
     pub fn run(&mut self, terminal: &mut Tui) -> Result<()> {
+        if !self.mouse {
+            tui::set_mouse_capture(terminal, false)?;
+        }
         loop {
+            if tui::terminate_requested() {
+                self.save_on_exit();
+                break;
+            }
             if self.needs_redraw {
                 self.ensure_flat();
                 tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
                 self.needs_redraw = false;
             }
 
-            let in_input = matches!(self.mode, Mode::Input { .. } | Mode::Search { .. } | Mode::GitPopup { .. });
+            let in_input = matches!(
+                self.mode,
+                Mode::Input { .. }
+                    | Mode::Form { .. }
+                    | Mode::Search { .. }
+                    | Mode::Confirm { .. }
+                    | Mode::Config { .. }
+                    | Mode::GitPopup { .. }
+                    | Mode::Stashes { .. }
+                    | Mode::Conflicts { .. }
+                    | Mode::GitOutput { .. }
+            );
             if let Some(action) = poll_event(Duration::from_millis(TICK_MS), in_input)? {
                 if action == Action::Quit && matches!(self.mode, Mode::Normal) {
-                    crate::cache::save_cache(&self.workspace, self.tree_selected);
+                    self.save_on_exit();
                     break;
                 }
                 self.needs_redraw = true;
+                // A key reached wsx, so it's on screen whatever the last probe said.
+                self.resume_if_hidden();
                 if let Err(e) = self.dispatch(action, terminal) {
-                    self.set_status(format!("Error: {}", e));
+                    self.set_error(format!("Error: {}", e));
+                }
+                self.attach_next(terminal)?;
+                if self.picked_path.is_some() || self.quit_requested {
+                    self.save_on_exit();
+                    break;
                 }
             } else {
                 self.tick()?;
+                self.attach_next(terminal)?;
             }
         }
         Ok(())
@@ -275,6 +927,17 @@ impl App {
         while let Ok((path, success)) = self.fetch_rx.try_recv() {
             self.apply_fetch_result(path, success);
         }
+        self.poll_sync();
+        self.poll_doctor();
+        self.poll_session_import();
+        self.poll_clone();
+        self.poll_post_create();
+        self.poll_respawn();
+        self.poll_git_queue();
+        while let Ok(msg) = self.launch_rx.try_recv() {
+            self.set_status(msg);
+            self.needs_redraw = true;
+        }
 
         if let Some(expires) = self.status_message_expires {
             if Instant::now() >= expires {
@@ -284,17 +947,31 @@ impl App {
             }
         }
 
+        if self.focus.hidden() && !self.probe_focus() {
+            if self.events.enabled() && self.hidden_activity_timer.ready() {
+                self.refresh_activity();
+            }
+            return Ok(());
+        }
+
+        let mut rescanned = false;
         if self.rescan_timer.ready() {
-            if let Err(e) = self.refresh_all() {
-                self.set_status(format!("Refresh error: {}", e));
+            // With manual_refresh this only picks up config edits; the tree waits for `R`.
+            let result = self.reload_config_if_changed().and_then(|_| {
+                rescanned = !self.config.timers.manual_refresh;
+                if rescanned { self.refresh_all() } else { Ok(()) }
+            });
+            if let Err(e) = result {
+                self.set_error(format!("Refresh error: {}", e));
             }
-            self.activity_timer.last = Instant::now(); // rescan subsumes activity check
-            self.needs_redraw = true;
-        } else if self.activity_timer.ready() {
-            if self.refresh_activity() {
+            if rescanned {
+                self.activity_timer.last = Instant::now(); // rescan subsumes activity check
                 self.needs_redraw = true;
             }
         }
+        if !rescanned && self.activity_timer.ready() && self.refresh_activity() {
+            self.needs_redraw = true;
+        }
 
         if self.git_local_timer.ready() {
             // Invalidate git_info for the selected worktree so local changes
@@ -308,13 +985,54 @@ impl App {
             }
         }
 
-        if self.capture_timer.ready() {
-            self.refresh_captures();
-        }
+        self.refresh_captures();
 
         Ok(())
     }
 
+    /// While nobody looks at wsx, the only poll is one tmux query every `PROBE_INTERVAL`:
+    /// is its session shown again? True once it is, after catching up. (With an event
+    /// stream open, `tick` also reads activity every `HIDDEN_ACTIVITY_INTERVAL`.)
+    fn probe_focus(&mut self) -> bool {
+        if !self.focus.probe_due(Instant::now()) {
+            return false;
+        }
+        let shown = self.own_session.as_deref().is_none_or(|s| self.tmux.session_attached(s));
+        if shown {
+            self.resume_if_hidden();
+        }
+        shown
+    }
+
+    /// Back on screen: one full refresh for everything the paused timers missed (just
+    /// activity with `manual_refresh`), then the timers count from here.
+    fn resume_if_hidden(&mut self) {
+        if !self.focus.regain() {
+            return;
+        }
+        let result = if self.config.timers.manual_refresh {
+            self.refresh_activity();
+            Ok(())
+        } else {
+            self.refresh_all()
+        };
+        if let Err(e) = result {
+            self.set_error(format!("Refresh error: {}", e));
+        }
+        self.rescan_timer.last = Instant::now();
+        self.activity_timer.last = Instant::now();
+        self.needs_redraw = true;
+    }
+
+    /// wsx's own session with no client attached: the user went elsewhere (tmux's own
+    /// session switcher, a detach), so the timers can rest.
+    fn note_focus(&mut self) {
+        let unseen = self.own_session.as_ref().and_then(|s| self.activity.get(s)).is_some_and(|s| !s.attached);
+        if unseen {
+            self.focus.lose(Instant::now());
+        }
+    }
+
     fn apply_fetch_result(&mut self, path: PathBuf, success: bool) {
         let completed_at = Instant::now();
         self.fetch_pending.remove(&path);
@@ -326,6 +1044,7 @@ impl App {
                     // Throttle fetch attempts after both success and failure.
                     wt.last_fetched = Some(completed_at);
                     if success {
+                        wt.last_fetch_ok = Some(completed_at);
                         wt.git_info = None; // invalidate so ahead/behind re-reads
                     }
                     self.needs_redraw = true;
@@ -335,28 +1054,330 @@ impl App {
         }
     }
 
+    /// Save the global config and pick up anything another instance merged in.
+    fn save_config(&mut self) -> Result<()> {
+        let key = self.selection_key();
+        self.config.save()?;
+        if ops::sync_projects(self.git.as_ref(), &mut self.workspace, &self.config) {
+            self.restore_selection(key);
+        }
+        Ok(())
+    }
+
+    /// Reload the global config if another instance wrote it. Skipped outside
+    /// Normal mode so in-flight edits (e.g. project reorder) aren't reset.
+    fn reload_config_if_changed(&mut self) -> Result<()> {
+        if !matches!(self.mode, Mode::Normal) || !self.config.changed_on_disk() {
+            return Ok(());
+        }
+        let sidebar_width = self.config.sidebar_width;
+        self.config = GlobalConfig::load()?;
+        if self.sidebar_dirty {
+            self.config.sidebar_width = sidebar_width;
+        }
+        self.apply_timers();
+        let key = self.selection_key();
+        let recent = self.config.session_order == SessionOrder::Recent;
+        let reordered = std::mem::replace(&mut self.workspace.recent_sessions, recent) != recent;
+        if ops::sync_projects(self.git.as_ref(), &mut self.workspace, &self.config) || reordered {
+            self.restore_selection(key);
+        }
+        Ok(())
+    }
+
+    /// Take the `[timers]` intervals from the config; the next tick uses them.
+    fn apply_timers(&mut self) {
+        let timers = &self.config.timers;
+        self.rescan_timer.set_interval(timers.rescan_ms());
+        self.activity_timer.set_interval(timers.activity_ms());
+        self.capture_timer.set_interval(timers.capture_ms());
+    }
+
+    fn action_toggle_order(&mut self) -> Result<()> {
+        self.config.project_order = self.config.project_order.toggled();
+        self.save_config()?;
+        self.set_status(match self.config.project_order {
+            ProjectOrder::Manual => "Project order: manual",
+            ProjectOrder::Frecency => "Project order: recently used",
+        });
+        Ok(())
+    }
+
+    /// Pin or unpin the selected worktree / session to the top of its siblings.
+    fn action_toggle_pin(&mut self) {
+        let pinned = match self.current_selection() {
+            Selection::Worktree(pi, wi) => self.workspace.worktree_mut(pi, wi).map(|w| &mut w.pinned),
+            Selection::Session(pi, wi, si) => self.workspace.session_mut(pi, wi, si).map(|s| &mut s.pinned),
+            _ => None,
+        };
+        let Some(pinned) = pinned else {
+            self.set_status("Select a worktree or session to pin");
+            return;
+        };
+        *pinned = !*pinned;
+        let msg = if *pinned { "Pinned" } else { "Unpinned" };
+        let key = self.selection_key();
+        self.restore_selection(key);
+        self.set_status(msg);
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+    }
+
+    fn action_edit_note(&mut self) {
+        let Selection::Worktree(pi, wi) = self.current_selection() else {
+            self.set_status("Select a worktree to note");
+            return;
+        };
+        let current = self.workspace.projects[pi].worktrees[wi].note.clone().unwrap_or_default();
+        self.mode = Mode::Input {
+            context: InputContext::SetNote { worktree: self.worktree_path(pi, wi) },
+            state: InputState::with_value("note: ", current),
+        };
+    }
+
+    /// `Ctrl+G`: ask for a file path — or, right after one was found, open it in $EDITOR
+    /// from its worktree.
+    fn action_locate_path(&mut self, located: Option<(PathBuf, PathBuf)>, terminal: &mut Tui) -> Result<()> {
+        if let Some((worktree, file)) = located {
+            if matches!(self.current_selection(), Selection::Worktree(pi, wi) if self.worktree_path(pi, wi) == worktree) {
+                return edit_file(terminal, &file, Some(&worktree));
+            }
+        }
+        self.mode = Mode::Input {
+            context: InputContext::LocatePath,
+            state: InputState::with_source("path: ", String::new(), CompletionSource::Path),
+        };
+        Ok(())
+    }
+
+    fn do_locate_path(&mut self, input: &str) {
+        if input.is_empty() {
+            return;
+        }
+        if !ops::expand_path(input).is_absolute() {
+            self.set_status("Give an absolute path — a relative one could be in any worktree");
+            return;
+        }
+        let Some(found) = ops::locate_path(&self.workspace, input) else {
+            self.set_status("No registered worktree contains this path");
+            return;
+        };
+        let (pi, wi) = (found.project_idx, found.worktree_idx);
+        self.select_worktree(pi, wi);
+        let wt = &self.workspace.projects[pi].worktrees[wi];
+        let place = if wt.hidden && !self.workspace.show_hidden {
+            format!("'{}' (hidden by worktree.hide — H shows it)", wt.name)
+        } else {
+            format!("'{}'", wt.name)
+        };
+        if found.relative.as_os_str().is_empty() {
+            self.set_status(format!("That's worktree {}", place));
+            return;
+        }
+        let file = wt.path.join(&found.relative);
+        let shown = format!("{}{}", found.relative.display(), found.position);
+        if file.is_file() {
+            self.located = Some((wt.path.clone(), file));
+            self.set_status(format!("In {}: {} — Ctrl+G again opens it", place, shown));
+        } else {
+            self.set_status(format!("In {}: {} (not on disk there)", place, shown));
+        }
+    }
+
+    fn do_set_note(&mut self, pi: usize, wi: usize, note: String) {
+        let wt = &mut self.workspace.projects[pi].worktrees[wi];
+        let msg = if note.is_empty() { "Note cleared" } else { "Note saved" };
+        wt.note = Some(note).filter(|n| !n.is_empty());
+        self.set_status(msg);
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+    }
+
+    fn action_toggle_hidden(&mut self) {
+        let count = self.workspace.projects.iter().flat_map(|p| &p.worktrees).filter(|w| w.hidden).count();
+        if count == 0 && !self.workspace.show_hidden {
+            self.set_status("No worktrees hidden by worktree.hide");
+            return;
+        }
+        let key = self.selection_key();
+        self.workspace.show_hidden = !self.workspace.show_hidden;
+        self.rebuild_flat();
+        self.restore_selection(key);
+        self.set_status(if self.workspace.show_hidden {
+            format!("Showing {} hidden worktree{} — d, A and c stay off", count, if count == 1 { "" } else { "s" })
+        } else {
+            "Hidden worktrees hidden again".to_string()
+        });
+    }
+
+    /// Offer to kill plain-shell sessions older than `stale_session_days` — in the
+    /// selected project, or everywhere when nothing is selected. Always confirmed.
+    fn action_kill_stale(&mut self) {
+        let days = self.config.stale_session_days();
+        if days == 0 {
+            self.set_status("Stale session cleanup is off (stale_session_days = 0)");
+            return;
+        }
+        let only = match self.current_selection() {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => Some(pi),
+            Selection::None => None,
+        };
+        let mut stale = ops::stale_sessions(&self.workspace, only, &self.activity, days.saturating_mul(86_400), frecency::now_secs());
+        stale.retain(|(name, _)| !self.is_own_session(name));
+        if stale.is_empty() {
+            self.set_status(format!("No idle shell sessions older than {}d", days));
+            return;
+        }
+        let display: Vec<&str> = stale.iter().map(|(_, d)| d.as_str()).collect();
+        self.mode = Mode::Confirm {
+            danger: false,
+            message: format!(
+                "Kill {} idle shell session{} older than {}d? ({})",
+                stale.len(),
+                if stale.len() == 1 { "" } else { "s" },
+                days,
+                display.join(", ")
+            ),
+            pending: PendingAction::KillSessions { names: stale.into_iter().map(|(n, _)| n).collect() },
+        };
+    }
+
+    /// Count a use of project `pi` (session attach, worktree creation).
+    fn bump_frecency(&mut self, pi: usize) {
+        let Some(project) = self.workspace.projects.get_mut(pi) else {
+            return;
+        };
+        project.frecency.bump(frecency::now_secs());
+        if self.config.project_order == ProjectOrder::Frecency {
+            let key = self.selection_key();
+            ops::order_projects(&mut self.workspace, &self.config);
+            self.restore_selection(key);
+        }
+    }
+
+    fn resize_sidebar(&mut self, steps: i16) {
+        if self.config.resize_sidebar(steps) {
+            self.sidebar_dirty = true;
+        }
+    }
+
+    /// Flush state that is only persisted on exit.
+    fn save_on_exit(&mut self) {
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+        if self.sidebar_dirty {
+            let _ = self.config.save();
+        }
+    }
+
     pub fn refresh_all(&mut self) -> Result<()> {
-        let sessions_with_paths = session::list_sessions_with_paths();
-        let activity = monitor::session_activity();
-        ops::refresh_workspace(
+        let live = self
+            .tmux
+            .list_sessions_with_paths()
+            .and_then(|sessions| Ok((sessions, self.tmux.session_activity()?)));
+        self.tmux_down = live.is_err();
+        let ended = match &live {
+            Ok((sessions, _)) => ops::ended_sessions(&self.workspace, sessions, frecency::now_secs()),
+            Err(_) => vec![],
+        };
+        // Without a server, keep the tree's sessions as they were until it comes back.
+        let (sessions_with_paths, activity) =
+            live.unwrap_or_else(|_| (self.workspace.session_paths(), self.activity.clone()));
+        let before = self.events.enabled().then(|| {
+            (events::worktree_set(&self.workspace), events::session_states(&self.workspace))
+        });
+        let errors = ops::refresh_workspace(
+            self.git.as_ref(),
             &mut self.workspace,
             &self.config,
             &sessions_with_paths,
             &activity,
         );
+        if self.debug {
+            self.orphans = ops::orphan_sessions(&self.workspace, &sessions_with_paths);
+        }
+        self.remember_ended(ended);
+        self.activity = activity;
+        self.prune_preview_windows();
+        self.note_focus();
+        // A missing project is already red in the tree. Only the first of the rest, so two
+        // failing projects don't keep replacing each other's count.
+        let missing = |name: &str| self.workspace.projects.iter().any(|p| p.name == name && p.missing);
+        if let Some((project, message)) = errors.iter().find(|(project, _)| !missing(project)) {
+            self.set_error(format!("refresh failing for project {}: {}", project, message));
+        }
+        if let Some((worktrees, sessions)) = before {
+            let errors = errors
+                .into_iter()
+                .map(|(project, message)| Event::RefreshError { project, message });
+            let worktrees = events::worktree_events(&worktrees, &events::worktree_set(&self.workspace));
+            let sessions = events::session_events(&sessions, &events::session_states(&self.workspace));
+            self.events.emit(errors.chain(worktrees).chain(sessions));
+        }
         self.rebuild_flat();
         self.clamp_selected();
-        crate::cache::save_cache(&self.workspace, self.tree_selected);
+        self.cache_writer.save(&self.workspace, self.tree_selected);
         Ok(())
     }
 
+    /// Keep the last capture of sessions that went away, within `ended_sessions_*`.
+    fn remember_ended(&mut self, ended: Vec<EndedSession>) {
+        let (max, max_age) = self.config.ended_sessions_limits();
+        for e in ended {
+            self.workspace.ended.record(e);
+        }
+        self.workspace.ended.expire(max, max_age, frecency::now_secs());
+    }
+
     fn refresh_activity(&mut self) -> bool {
-        let activity = monitor::session_activity();
-        ops::update_activity(&mut self.workspace, &activity)
+        match self.tmux.session_activity() {
+            // The server is back: reconcile the whole tree rather than just activity.
+            Ok(_) if self.tmux_down => return self.refresh_all().is_ok(),
+            Ok(activity) => self.activity = activity,
+            Err(NoServer) => {
+                self.tmux_down = true;
+                return false;
+            }
+        }
+        self.prune_preview_windows();
+        self.note_focus();
+        let before = self.events.enabled().then(|| events::session_states(&self.workspace));
+        let changed = ops::update_activity(&mut self.workspace, &self.activity);
+        if let Some(before) = before {
+            self.events.emit(events::session_events(&before, &events::session_states(&self.workspace)));
+        }
+        changed
+    }
+
+    /// Drop a worktree deleted through wsx from the tree, without waiting for a rescan.
+    fn remove_worktree_row(&mut self, pi: usize, wi: usize) {
+        let p = &mut self.workspace.projects[pi];
+        let wt = p.worktrees.remove(wi);
+        let event = Event::WorktreeRemoved { project: p.name.clone(), path: wt.path, branch: wt.branch };
+        self.events.emit([event]);
+        self.rebuild_flat();
+        self.clamp_selected();
     }
 
+    /// Runs every idle tick. While the selection moves (j/k held down) nothing is
+    /// read; once it settles, git info loads and the pane is captured right away,
+    /// then re-captured on `capture_timer` only if tmux saw new output.
     fn refresh_captures(&mut self) {
         let sel = self.current_selection();
+        let key = (sel.clone(), self.previewed_window());
+        if self.capture_seen.as_ref() != Some(&key) {
+            self.capture_seen = Some(key);
+            self.selection_moved_at = Instant::now();
+            self.selection_pending = true;
+            return;
+        }
+        if self.selection_moved_at.elapsed() < Duration::from_millis(SELECTION_SETTLE_MS) {
+            return;
+        }
+        let just_settled = std::mem::take(&mut self.selection_pending);
+        if !self.capture_timer.ready() && !just_settled {
+            return;
+        }
 
         // Load git info when a worktree or session is selected
         let (pi, wi) = match sel {
@@ -378,7 +1399,8 @@ impl App {
                 .map(|p| p.default_branch.clone())
                 .unwrap_or_else(|| "main".to_string());
 
-            if let Some(gi) = git_info::get_git_info(&path, &default_branch) {
+            self.preview_stats.git_info_loads += 1;
+            if let Some(gi) = self.git.git_info(&path, &default_branch) {
                 if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
                     wt.git_info = Some(gi);
                     self.needs_redraw = true;
@@ -396,21 +1418,24 @@ impl App {
             (stale && !in_flight, wt.path.clone())
         });
         if let Some((true, path)) = fetch_info {
-            self.fetch_pending.insert(path.clone());
-            let tx = self.fetch_tx.clone();
-            std::thread::spawn(move || {
-                let ok = git_info::git_fetch(&path);
-                let _ = tx.send((path, ok));
-            });
+            self.spawn_fetch(path);
         }
 
         // Capture pane for selected session
         if let Selection::Session(pi, wi, si) = sel {
             let sess_name = self.workspace.session(pi, wi, si).map(|s| s.name.clone());
+            let target = self.tmux_target(pi, wi, si, self.previewed_window());
 
-            if let Some(name) = sess_name {
-                if session::session_exists(&name) {
-                    if let Some(raw) = capture::capture_pane(&name) {
+            if let (Some(name), Some(target)) = (sess_name, target) {
+                let activity_ts = self.activity.get(&name).map_or(0, |s| s.last_activity_ts);
+                if !just_settled && !self.pane_may_have_changed(&target, activity_ts) {
+                    self.preview_stats.skipped_captures += 1;
+                    return;
+                }
+                if !self.tmux_down && self.tmux.session_exists(&name) {
+                    self.preview_stats.captures += 1;
+                    self.last_capture = Some((target.clone(), activity_ts));
+                    if let Some(raw) = self.tmux.capture_pane(&target) {
                         let trimmed = capture::trim_capture(&raw);
                         if let Some(s) = self.workspace.session_mut(pi, wi, si) {
                             if s.pane_capture.as_deref() != Some(&trimmed) {
@@ -424,67 +1449,119 @@ impl App {
         }
     }
 
+    /// tmux activity has only second resolution: output later in the second of the
+    /// last capture doesn't advance it, so a pane active within the last second is
+    /// re-captured anyway. Unknown activity (0) always re-captures.
+    fn pane_may_have_changed(&self, target: &str, activity_ts: u64) -> bool {
+        match &self.last_capture {
+            Some((last_target, last_ts)) if last_target == target => {
+                activity_ts == 0
+                    || activity_ts != *last_ts
+                    || frecency::now_secs().saturating_sub(activity_ts) <= 1
+            }
+            _ => true,
+        }
+    }
+
     pub fn current_selection(&self) -> Selection {
         self.workspace
             .get_selection(self.tree_selected, self.flat())
     }
 
-    fn clamp_selected(&mut self) {
-        let len = self.flat().len();
-        if len == 0 {
-            self.tree_selected = 0;
-        } else {
-            self.tree_selected = self.tree_selected.min(len - 1);
-        }
-    }
+    /// The current selection keyed by project path, so it survives projects being reordered.
+    fn selection_key(&mut self) -> Option<(PathBuf, Selection)> {
+        self.ensure_flat();
+        let sel = self.current_selection();
+        let pi = match sel {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => pi,
+            Selection::None => return None,
+        };
+        Some((self.workspace.projects.get(pi)?.path.clone(), sel))
+    }
 
-    // ── Navigation ────────────────────────────────────────────────────────────
+    /// Rebuild the tree and put the cursor back on the entry captured by `selection_key`.
+    fn restore_selection(&mut self, key: Option<(PathBuf, Selection)>) {
+        self.rebuild_flat();
+        let target = key.and_then(|(path, sel)| {
+            let pi = self.workspace.projects.iter().position(|p| p.path == path)?;
+            Some(tree_nav::entry_in(pi, &sel))
+        });
+        match target.and_then(|t| tree_nav::row_of(self.flat(), &t)) {
+            Some(pos) => self.select_row(Some(pos)),
+            None => self.clamp_selected(),
+        }
+    }
 
-    fn nav_up(&mut self) {
-        if self.tree_selected > 0 {
-            self.tree_selected -= 1;
+    fn clamp_selected(&mut self) {
+        self.tree_selected = tree_nav::clamp(self.tree_selected, self.flat().len());
+    }
+
+    /// Put the cursor on `row`, if there is one, and scroll it into view.
+    fn select_row(&mut self, row: Option<usize>) {
+        if let Some(pos) = row {
+            self.tree_selected = pos;
             self.update_scroll();
         }
     }
 
+    // ── Navigation ────────────────────────────────────────────────────────────
+
+    fn nav_up(&mut self) {
+        let row = tree_nav::step(self.tree_selected, self.flat().len(), -1);
+        self.select_row(Some(row));
+    }
+
     fn nav_down(&mut self) {
-        let max = self.flat().len().saturating_sub(1);
-        if self.tree_selected < max {
-            self.tree_selected += 1;
-            self.update_scroll();
-        }
+        let row = tree_nav::step(self.tree_selected, self.flat().len(), 1);
+        self.select_row(Some(row));
+    }
+
+    fn select_parent(&mut self, entry: &FlatEntry) {
+        let row = tree_nav::parent_row(self.flat(), entry);
+        self.select_row(row);
     }
 
     fn nav_left(&mut self) {
-        let entry = self.flat().get(self.tree_selected).cloned();
+        let Some(entry) = self.flat().get(self.tree_selected).cloned() else { return };
         match entry {
-            Some(FlatEntry::Project { idx }) => {
-                self.workspace.projects[idx].expanded = false;
+            FlatEntry::Project { idx } => {
+                let Some(project) = self.workspace.projects.get_mut(idx) else {
+                    return self.stale_selection();
+                };
+                project.expanded = false;
                 self.rebuild_flat();
                 self.clamp_selected();
             }
-            Some(FlatEntry::Worktree { project_idx: pi, worktree_idx: wi }) => {
-                if self.workspace.projects[pi].worktrees[wi].expanded {
-                    self.workspace.projects[pi].worktrees[wi].expanded = false;
+            FlatEntry::Worktree { project_idx: pi, worktree_idx: wi } => {
+                let Some(wt) = self.workspace.worktree_mut(pi, wi) else {
+                    return self.stale_selection();
+                };
+                if wt.expanded {
+                    wt.expanded = false;
                     self.rebuild_flat();
                     self.clamp_selected();
                 } else {
-                    // Jump to parent project
-                    if let Some(pos) = self.flat().iter().position(|e| matches!(e, FlatEntry::Project { idx } if *idx == pi)) {
-                        self.tree_selected = pos;
-                        self.update_scroll();
-                    }
+                    self.select_parent(&entry);
                 }
             }
-            Some(FlatEntry::Session { project_idx: pi, worktree_idx: wi, .. }) => {
-                if let Some(pos) = self.flat().iter().position(|e| {
-                    matches!(e, FlatEntry::Worktree { project_idx: p, worktree_idx: w } if *p == pi && *w == wi)
-                }) {
-                    self.tree_selected = pos;
-                    self.update_scroll();
+            FlatEntry::Session { .. } | FlatEntry::Window { .. } | FlatEntry::Archived { .. } => {
+                self.select_parent(&entry);
+            }
+            FlatEntry::ArchiveHeader { project_idx: pi } => {
+                let Some(project) = self.workspace.projects.get_mut(pi) else {
+                    return self.stale_selection();
+                };
+                if project.archived_expanded {
+                    project.archived_expanded = false;
+                    self.rebuild_flat();
+                    self.clamp_selected();
+                } else {
+                    self.select_parent(&entry);
                 }
             }
-            None => {}
         }
     }
 
@@ -492,10 +1569,13 @@ impl App {
         let entry = self.flat().get(self.tree_selected).cloned();
         match entry {
             Some(FlatEntry::Project { idx: pi }) => {
-                if !self.workspace.projects[pi].expanded {
-                    self.workspace.projects[pi].expanded = true;
+                let Some(project) = self.workspace.projects.get_mut(pi) else {
+                    return self.stale_selection();
+                };
+                if !project.expanded {
+                    project.expanded = true;
                     self.rebuild_flat();
-                } else if !self.workspace.projects[pi].worktrees.is_empty() {
+                } else if !project.worktrees.is_empty() || !project.archived.is_empty() {
                     self.tree_selected += 1;
                     self.update_scroll();
                 }
@@ -504,13 +1584,25 @@ impl App {
                 project_idx: pi,
                 worktree_idx: wi,
             }) => {
-                if !self.workspace.projects[pi].worktrees[wi].expanded {
-                    self.workspace.projects[pi].worktrees[wi].expanded = true;
+                let Some(wt) = self.workspace.worktree_mut(pi, wi) else {
+                    return self.stale_selection();
+                };
+                if !wt.expanded {
+                    wt.expanded = true;
                     self.rebuild_flat();
-                } else if !self.workspace.projects[pi].worktrees[wi]
-                    .sessions
-                    .is_empty()
-                {
+                } else if !wt.sessions.is_empty() {
+                    self.tree_selected += 1;
+                    self.update_scroll();
+                }
+            }
+            Some(FlatEntry::ArchiveHeader { project_idx: pi }) => {
+                let Some(project) = self.workspace.projects.get_mut(pi) else {
+                    return self.stale_selection();
+                };
+                if !project.archived_expanded {
+                    project.archived_expanded = true;
+                    self.rebuild_flat();
+                } else {
                     self.tree_selected += 1;
                     self.update_scroll();
                 }
@@ -519,31 +1611,94 @@ impl App {
         }
     }
 
+    /// A row or mode pointing past the workspace — a refresh removed what it named.
+    /// Resync the tree and say so rather than index out of bounds.
+    fn stale_selection(&mut self) {
+        self.mode = Mode::Normal;
+        self.rebuild_flat();
+        self.clamp_selected();
+        self.set_status("That item is gone — the workspace changed");
+    }
+
+    /// Index of the project at `path`, for a prompt or confirm that named it.
+    fn resolve_project(&self, path: &Path) -> Result<usize> {
+        self.workspace
+            .projects
+            .iter()
+            .position(|p| p.path == path)
+            .with_context(|| format!("project {} is gone — the workspace changed", display_path(path, false, usize::MAX)))
+    }
+
+    fn resolve_worktree(&self, path: &Path) -> Result<(usize, usize)> {
+        self.workspace
+            .find_worktree(path)
+            .with_context(|| format!("worktree {} is gone — the workspace changed", display_path(path, false, usize::MAX)))
+    }
+
+    fn resolve_session(&self, name: &str) -> Result<(usize, usize, usize)> {
+        self.workspace
+            .find_session(name)
+            .with_context(|| format!("session '{}' is gone — the workspace changed", name))
+    }
+
+    fn project_path(&self, pi: usize) -> PathBuf {
+        self.workspace.projects[pi].path.clone()
+    }
+
+    fn worktree_path(&self, pi: usize, wi: usize) -> PathBuf {
+        self.workspace.projects[pi].worktrees[wi].path.clone()
+    }
+
+    fn session_name(&self, pi: usize, wi: usize, si: usize) -> String {
+        self.workspace.projects[pi].worktrees[wi].sessions[si].name.clone()
+    }
+
     fn jump_project(&mut self, dir: isize) {
-        let flat = self.flat();
-        let current = self.tree_selected;
-        let target = if dir > 0 {
-            flat.iter()
-                .enumerate()
-                .find(|(i, e)| *i > current && matches!(e, FlatEntry::Project { .. }))
-                .map(|(i, _)| i)
-        } else {
-            flat.iter()
-                .enumerate()
-                .rev()
-                .find(|(i, e)| *i < current && matches!(e, FlatEntry::Project { .. }))
-                .map(|(i, _)| i)
-        };
-        if let Some(pos) = target {
-            self.tree_selected = pos;
-            self.update_scroll();
+        let row = tree_nav::jump_project(self.flat(), self.tree_selected, dir);
+        self.select_row(row);
+    }
+
+    /// Move the cursor to a worktree, expanding its project so it is visible.
+    fn select_worktree(&mut self, pi: usize, wi: usize) {
+        if let Some(p) = self.workspace.projects.get_mut(pi) {
+            p.expanded = true;
+        }
+        self.rebuild_flat();
+        let row = tree_nav::row_of(self.flat(), &FlatEntry::Worktree { project_idx: pi, worktree_idx: wi });
+        self.select_row(row);
+    }
+
+    /// Move the cursor to the project registered at `path`, if there is one.
+    fn select_project_at(&mut self, path: &Path) {
+        let Some(pi) = self.workspace.projects.iter().position(|p| p.path == path) else { return };
+        self.rebuild_flat();
+        let row = tree_nav::row_of(self.flat(), &FlatEntry::Project { idx: pi });
+        self.select_row(row);
+    }
+
+    /// Expand the session's parents and put the cursor on it — or on one of its
+    /// window rows, when `window` is given and the session lists its windows.
+    fn select_session(&mut self, pi: usize, wi: usize, si: usize, window: Option<usize>) {
+        if let Some(p) = self.workspace.projects.get_mut(pi) {
+            p.expanded = true;
+        }
+        if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
+            wt.expanded = true;
         }
+        self.rebuild_flat();
+        let target = FlatEntry::Session { project_idx: pi, worktree_idx: wi, session_idx: si };
+        let window_row = window.and_then(|window_idx| {
+            let row = FlatEntry::Window { project_idx: pi, worktree_idx: wi, session_idx: si, window_idx };
+            tree_nav::row_of(self.flat(), &row)
+        });
+        let row = window_row.or_else(|| tree_nav::row_of(self.flat(), &target));
+        self.select_row(row);
     }
 
     fn update_scroll(&mut self) {
         // tree_visible_height is set each frame from actual terminal size; fall back to 20
         let visible = self.tree_visible_height.max(1);
-        self.tree_scroll = crate::ui::workspace_tree::compute_scroll(
+        self.tree_scroll = tree_nav::compute_scroll(
             self.tree_selected,
             visible,
             self.tree_scroll,
@@ -553,37 +1708,133 @@ impl App {
     // ── Action dispatch ───────────────────────────────────────────────────────
 
     fn dispatch(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+        if action == Action::Interrupt {
+            self.interrupt();
+            return Ok(());
+        }
+        let result = self.dispatch_mode(action, terminal);
+        if matches!(self.mode, Mode::Normal) {
+            self.mode_stack.clear();
+        }
+        result
+    }
+
+    /// Ctrl+C: the first press only warns, a second within `INTERRUPT_WINDOW` quits.
+    fn interrupt(&mut self) {
+        if self.interrupted_at.is_some_and(|t| t.elapsed() < INTERRUPT_WINDOW) {
+            self.quit_requested = true;
+        } else {
+            self.interrupted_at = Some(Instant::now());
+            self.set_status("Press Ctrl+C again to quit");
+        }
+    }
+
+    /// Open `mode` over the current one, which Esc then returns to. Reopening a kind
+    /// already open (a list refreshed after an op) replaces it and drops what was above.
+    fn open_layer(&mut self, mode: Mode) {
+        let kind = std::mem::discriminant(&mode);
+        let current = std::mem::replace(&mut self.mode, mode);
+        if !matches!(current, Mode::Normal) && std::mem::discriminant(&current) != kind {
+            self.mode_stack.push(current);
+        }
+        if let Some(pos) = self.mode_stack.iter().position(|m| std::mem::discriminant(m) == kind) {
+            self.mode_stack.truncate(pos);
+        }
+    }
+
+    /// Esc: close the current layer, back to the one it was opened from — found again by
+    /// path, and skipped if a refresh has since removed its project or worktree.
+    fn back_out(&mut self) {
+        self.mode = Mode::Normal;
+        while let Some(mode) = self.mode_stack.pop() {
+            if self.layer_exists(&mode) {
+                self.mode = mode;
+                break;
+            }
+        }
+    }
+
+    /// Back out of the layer `is` picks and everything opened over it, e.g. a list an op
+    /// left empty; nothing if it isn't open.
+    fn close_layer(&mut self, is: fn(&Mode) -> bool) {
+        if is(&self.mode) {
+            self.back_out();
+        } else if let Some(pos) = self.mode_stack.iter().position(is) {
+            self.mode_stack.truncate(pos);
+            self.back_out();
+        }
+    }
+
+    fn layer_exists(&self, mode: &Mode) -> bool {
+        match mode {
+            Mode::Config { project } | Mode::Move { project } => self.resolve_project(project).is_ok(),
+            Mode::MoveSession { session } => self.resolve_session(session).is_ok(),
+            Mode::GitPopup { worktree }
+            | Mode::Stashes { worktree, .. }
+            | Mode::Conflicts { worktree, .. }
+            | Mode::DiffFiles { worktree, .. } => self.resolve_worktree(worktree).is_ok(),
+            _ => true,
+        }
+    }
+
+    /// The project the current layer names; None, after `stale_selection`, once a
+    /// refresh removed it.
+    fn layer_project(&mut self, path: &Path) -> Option<usize> {
+        let found = self.resolve_project(path).ok();
+        if found.is_none() {
+            self.stale_selection();
+        }
+        found
+    }
+
+    fn layer_worktree(&mut self, path: &Path) -> Option<(usize, usize)> {
+        let found = self.resolve_worktree(path).ok();
+        if found.is_none() {
+            self.stale_selection();
+        }
+        found
+    }
+
+    fn layer_session(&mut self, name: &str) -> Option<(usize, usize, usize)> {
+        let found = self.resolve_session(name).ok();
+        if found.is_none() {
+            self.stale_selection();
+        }
+        found
+    }
+
+    fn dispatch_mode(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
         self.ensure_flat();
         // Config mode handled first to avoid borrow conflicts
-        if let Mode::Config { project_idx } = &self.mode {
-            let pi = *project_idx;
-            if matches!(action, Action::InputEscape | Action::Quit | Action::Help) {
-                self.mode = Mode::Normal;
-            } else if action == Action::Edit {
-                let path = self
-                    .workspace
-                    .projects
-                    .get(pi)
-                    .map(|p| p.path.join(".gtrignore"));
-                if let Some(path) = path {
-                    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-                    tui::with_raw_mode_disabled(terminal, || {
-                        std::process::Command::new(&editor).arg(&path).status()?;
-                        Ok(())
-                    })?;
+        if let Mode::Config { project } = &self.mode {
+            let Some(pi) = self.layer_project(&project.clone()) else { return Ok(()) };
+            match action {
+                Action::InputEscape | Action::InputChar('q') | Action::InputChar('?') => self.back_out(),
+                Action::InputChar('e') => {
+                    let path = self
+                        .workspace
+                        .projects
+                        .get(pi)
+                        .map(|p| p.path.join(".gtrignore"));
+                    if let Some(path) = path {
+                        edit_file(terminal, &path, None)?;
+                    }
                 }
+                Action::InputChar('b') => self.action_edit_default_branch(pi),
+                Action::InputChar('a') => self.action_materialize_aliases(pi),
+                _ => {}
             }
             return Ok(());
         }
 
-        if let Mode::Move { project_idx } = &self.mode {
-            let pi = *project_idx;
+        if let Mode::Move { project } = &self.mode {
+            let Some(pi) = self.layer_project(&project.clone()) else { return Ok(()) };
             match action {
                 Action::NavigateDown => self.move_project_down(pi),
                 Action::NavigateUp => self.move_project_up(pi),
                 Action::Select | Action::InputEscape | Action::Quit | Action::EnterMove => {
                     self.sync_config_project_order();
-                    self.config.save()?;
+                    self.save_config()?;
                     self.mode = Mode::Normal;
                 }
                 _ => {}
@@ -591,18 +1842,13 @@ impl App {
             return Ok(());
         }
 
-        if let Mode::MoveSession {
-            project_idx,
-            worktree_idx,
-            session_idx,
-        } = &self.mode
-        {
-            let (pi, wi, si) = (*project_idx, *worktree_idx, *session_idx);
+        if let Mode::MoveSession { session } = &self.mode {
+            let Some((pi, wi, si)) = self.layer_session(&session.clone()) else { return Ok(()) };
             match action {
                 Action::NavigateDown => self.move_session(pi, wi, si, 1),
                 Action::NavigateUp => self.move_session(pi, wi, si, -1),
                 Action::Select | Action::InputEscape | Action::Quit | Action::EnterMove => {
-                    crate::cache::save_cache(&self.workspace, self.tree_selected);
+                    self.cache_writer.save_now(&self.workspace, self.tree_selected);
                     self.mode = Mode::Normal;
                 }
                 _ => {}
@@ -610,27 +1856,104 @@ impl App {
             return Ok(());
         }
 
-        if let Mode::GitPopup { project_idx, worktree_idx } = &self.mode {
-            let (pi, wi) = (*project_idx, *worktree_idx);
+        if let Mode::GitPopup { worktree } = &self.mode {
+            let Some((pi, wi)) = self.layer_worktree(&worktree.clone()) else { return Ok(()) };
             return self.dispatch_git_popup(pi, wi, action, terminal);
         }
 
+        if matches!(self.mode, Mode::OpenWith { .. }) {
+            return self.dispatch_open_with(action);
+        }
+
+        if matches!(self.mode, Mode::ProjectActions { .. }) {
+            return self.dispatch_project_actions(action, terminal);
+        }
+
+        if matches!(self.mode, Mode::ImportProjects { .. }) {
+            return self.dispatch_import_projects(action);
+        }
+
+        if matches!(self.mode, Mode::Stashes { .. }) {
+            return self.dispatch_stashes(action);
+        }
+
+        if matches!(self.mode, Mode::Conflicts { .. }) {
+            return self.dispatch_conflicts(action, terminal);
+        }
+
+        if matches!(self.mode, Mode::GitOutput { .. }) {
+            self.dispatch_git_output(action);
+            return Ok(());
+        }
+
+        if matches!(self.mode, Mode::Ended { .. }) {
+            self.dispatch_ended(action);
+            return Ok(());
+        }
+
+        if matches!(self.mode, Mode::DiffFiles { .. }) {
+            self.dispatch_diff_files(action);
+            return Ok(());
+        }
+
+        if matches!(self.mode, Mode::Diff { .. }) {
+            self.dispatch_diff(action);
+            return Ok(());
+        }
+
         match &self.mode {
             Mode::Normal => self.dispatch_normal(action, terminal)?,
             Mode::Input { .. } => self.dispatch_input(action, terminal)?,
+            Mode::Form { .. } => self.dispatch_form(action)?,
             Mode::Confirm { .. } => self.dispatch_confirm(action, terminal)?,
             Mode::Help => {
                 if matches!(action, Action::InputEscape | Action::Quit | Action::Help) {
-                    self.mode = Mode::Normal;
+                    self.back_out();
+                }
+            }
+            Mode::Doctor { .. } => {
+                if matches!(
+                    action,
+                    Action::InputEscape | Action::Quit | Action::Select | Action::Doctor
+                ) {
+                    self.back_out();
+                }
+            }
+            Mode::Stats { .. } => {
+                if matches!(
+                    action,
+                    Action::InputEscape | Action::Quit | Action::Select | Action::Stats
+                ) {
+                    self.back_out();
                 }
             }
             Mode::Search { .. } => self.dispatch_search(action, terminal)?,
-            Mode::Config { .. } | Mode::Move { .. } | Mode::MoveSession { .. } | Mode::GitPopup { .. } => unreachable!(),
+            Mode::Config { .. }
+            | Mode::Move { .. }
+            | Mode::MoveSession { .. }
+            | Mode::GitPopup { .. }
+            | Mode::Stashes { .. }
+            | Mode::Conflicts { .. }
+            | Mode::GitOutput { .. }
+            | Mode::Ended { .. }
+            | Mode::DiffFiles { .. }
+            | Mode::Diff { .. }
+            | Mode::OpenWith { .. }
+            | Mode::ProjectActions { .. }
+            | Mode::ImportProjects { .. } => unreachable!(),
         }
         Ok(())
     }
 
     fn dispatch_normal(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+        if needs_live_git(&action) {
+            if let Some(name) = self.still_syncing() {
+                self.set_status(format!("'{}' is still loading — try again in a moment", name));
+                return Ok(());
+            }
+        }
+        // Only a `Ctrl+G` straight after the one that found a file opens it.
+        let located = self.located.take();
         match action {
             Action::NavigateUp => self.nav_up(),
             Action::NavigateDown => self.nav_down(),
@@ -639,6 +1962,7 @@ impl App {
             Action::Select => self.action_select(terminal)?,
             Action::AddProject => self.action_add_project()?,
             Action::AddWorktree => self.action_add_worktree()?,
+            Action::StartWork => self.action_start_work(),
             Action::AddSession => self.action_add_session()?,
             Action::Delete => self.action_delete()?,
             Action::Clean => self.action_clean()?,
@@ -650,29 +1974,164 @@ impl App {
             }
             Action::NextAttention => self.action_next_attention(1),
             Action::PrevAttention => self.action_next_attention(-1),
+            Action::SearchNext if self.last_search.is_some() => self.search_repeat(1),
+            Action::SearchPrev if self.last_search.is_some() => self.search_repeat(-1),
+            Action::SearchNext => self.action_next_attention(1),
+            Action::SearchPrev => self.action_next_attention(-1),
             Action::DismissAttention => self.action_dismiss_attention(),
             Action::NextActive => self.action_next_active(),
             Action::SendCommand => self.action_send_command(),
             Action::SendCtrlC => self.action_send_ctrl_c()?,
-            Action::EnterMove => self.action_enter_move(),
+            Action::Respawn => self.action_respawn()?,
+            Action::EnterMove => self.action_enter_move()?,
             Action::JumpProjectDown => self.jump_project(1),
             Action::JumpProjectUp => self.jump_project(-1),
             Action::SearchStart => {
+                self.last_search = None;
                 self.mode = Mode::Search {
                     query: String::new(),
                     match_idx: 0,
                 };
             }
             Action::GitPopup => self.action_git_popup(),
+            Action::FetchNow => self.action_fetch_now(),
+            Action::SidebarShrink => self.resize_sidebar(-1),
+            Action::SidebarGrow => self.resize_sidebar(1),
+            Action::ToggleOrder => self.action_toggle_order()?,
+            Action::TogglePin => self.action_toggle_pin(),
+            Action::EditNote => self.action_edit_note(),
+            Action::KillStale => self.action_kill_stale(),
+            Action::OpenWith => self.action_open_with(),
+            Action::MainShell => self.action_main_shell(terminal)?,
+            Action::ProjectActions => self.action_project_actions(),
+            Action::Doctor => {
+                let mut findings = doctor::doctor(&self.config);
+                findings.extend(doctor::project_config_findings(&self.workspace));
+                findings.extend(doctor::orphan_session_findings(&self.orphans));
+                self.mode = Mode::Doctor { findings };
+            }
+            Action::Stats => {
+                self.mode = Mode::Stats { stats: workspace_stats(&self.workspace) };
+            }
+            Action::Archive => self.action_archive(),
+            Action::ToggleLock => self.action_toggle_lock()?,
+            Action::Adopt => self.action_adopt(),
+            Action::LocatePath => self.action_locate_path(located, terminal)?,
+            Action::EndedSessions => self.action_ended_sessions(),
+            Action::ViewDiff => self.action_view_diff(),
+            Action::ToggleHidden => self.action_toggle_hidden(),
+            Action::ToggleWrap => self.action_toggle_wrap(),
+            Action::ToggleFollow => self.action_toggle_follow(),
+            Action::ToggleRawNames => {
+                self.raw_names = !self.raw_names;
+                self.set_status(if self.raw_names { "Raw names: tmux sessions and paths" } else { "Friendly names" });
+            }
+            Action::ToggleFullPaths => {
+                self.full_paths = !self.full_paths;
+                self.set_status(if self.full_paths { "Full paths" } else { "Paths shortened to fit" });
+            }
+            Action::ToggleMouse => {
+                self.mouse = !self.mouse;
+                tui::set_mouse_capture(terminal, self.mouse)?;
+                self.set_status(if self.mouse { "mouse: on" } else { MOUSE_OFF });
+            }
+            // `y` outside a confirm dialog
+            Action::ConfirmYes => self.action_yank(terminal)?,
+            Action::InputEscape if self.error.is_some() => self.error = None,
+            Action::InputEscape if self.last_search.is_some() => {
+                self.last_search = None;
+                self.set_status("Search cleared — n / N step through pending sessions");
+            }
+            Action::ScrollPreview(delta) => self.scroll_preview(delta),
+            Action::CycleWindow(step) => self.cycle_window(step),
             Action::MouseClick { col, row } => self.handle_mouse_click(col, row, terminal)?,
+            Action::MouseDrag { col, row } => {
+                if let Some(hit) = self.scrollbar_hit(Position { x: col, y: row }, self.scrollbar_drag) {
+                    self.drag_scrollbar(hit);
+                }
+            }
+            Action::MouseRelease => self.scrollbar_drag = None,
+            Action::MouseScroll { col, row, delta }
+                if self.preview_area.contains(Position { x: col, y: row }) =>
+            {
+                self.scroll_preview(delta)
+            }
             _ => {}
         }
         Ok(())
     }
 
+    fn action_toggle_wrap(&mut self) {
+        if !matches!(self.current_selection(), Selection::Session(..)) {
+            self.set_status("Select a session to change its preview");
+            return;
+        }
+        self.session_view.wrap = !self.session_view.wrap;
+        self.set_status(if self.session_view.wrap { "Preview: wrap long lines" } else { "Preview: clip long lines" });
+    }
+
+    fn action_toggle_follow(&mut self) {
+        if !matches!(self.current_selection(), Selection::Session(..)) {
+            self.set_status("Select a session to change its preview");
+            return;
+        }
+        self.session_view.toggle_follow();
+        self.set_status(if self.session_view.follow { "Preview: following output" } else { "Preview: paused" });
+    }
+
+    /// What `y` copies for the selected row: display names, or with `v` the raw
+    /// tmux target / filesystem path.
+    fn yank_value(&self) -> Option<String> {
+        match self.current_selection() {
+            Selection::Project(pi) => {
+                let p = self.workspace.projects.get(pi)?;
+                Some(if self.raw_names || self.full_paths { p.path.display().to_string() } else { p.name.clone() })
+            }
+            Selection::Worktree(pi, wi) => {
+                let wt = self.workspace.worktree(pi, wi)?;
+                Some(if self.raw_names || self.full_paths {
+                    wt.path.display().to_string()
+                } else {
+                    wt.display_name().to_string()
+                })
+            }
+            Selection::Session(pi, wi, si) => {
+                if self.raw_names {
+                    self.selected_target()
+                } else {
+                    self.workspace.worktree(pi, wi)?.sessions.get(si).map(|s| s.display_name.clone())
+                }
+            }
+            Selection::Archive(pi, ai) => {
+                let archived = self.workspace.projects.get(pi)?.archived.get(ai?)?;
+                Some(if self.raw_names { archived.tag.clone() } else { archived.branch.clone() })
+            }
+            Selection::None => None,
+        }
+    }
+
+    fn action_yank(&mut self, terminal: &mut Tui) -> Result<()> {
+        let Some(value) = self.yank_value() else {
+            self.set_status("Nothing to copy");
+            return Ok(());
+        };
+        tui::copy_to_clipboard(terminal, &value)?;
+        self.set_status(format!("Copied: {}", value));
+        Ok(())
+    }
+
+    fn scroll_preview(&mut self, delta: i32) {
+        if matches!(self.current_selection(), Selection::Session(..)) {
+            self.session_view.scroll(delta);
+        }
+    }
+
     fn handle_mouse_click(&mut self, col: u16, row: u16, terminal: &mut Tui) -> Result<()> {
         let pos = Position { x: col, y: row };
-        if self.tree_area.contains(pos) {
+        if let Some(hit) = self.scrollbar_hit(pos, None) {
+            self.scrollbar_drag = Some(hit.0);
+            self.drag_scrollbar(hit);
+        } else if self.tree_area.contains(pos) {
             // Content starts after top border (y+1), ends before bottom border (y+height-1)
             let content_top = self.tree_area.y + 1;
             let content_bottom = self.tree_area.y + self.tree_area.height.saturating_sub(1);
@@ -688,18 +2147,69 @@ impl App {
                 }
             }
         } else if self.preview_area.contains(pos) {
-            if matches!(self.current_selection(), Selection::Session(..)) {
-                self.action_select(terminal)?;
+            match self.current_selection() {
+                Selection::Session(..) => self.action_select(terminal)?,
+                Selection::Worktree(pi, wi) => self.click_preview_session(pi, wi, row),
+                _ => {}
             }
         }
         Ok(())
     }
 
+    /// The scrollbar under `pos` and the position on it. While `dragging` one, any row
+    /// counts, so the thumb follows a pointer that drifts off the column.
+    fn scrollbar_hit(&self, pos: Position, dragging: Option<ScrollbarDrag>) -> Option<(ScrollbarDrag, usize)> {
+        let tree = || {
+            let rows = self.flat().len();
+            if rows <= self.tree_visible_height {
+                return None;
+            }
+            let clamp = dragging == Some(ScrollbarDrag::Tree);
+            scrollbar_target(self.tree_area, pos, rows - 1, clamp).map(|i| (ScrollbarDrag::Tree, i))
+        };
+        let preview = || {
+            let max = self.session_view.max_offset as usize;
+            if !matches!(self.current_selection(), Selection::Session(..)) || max == 0 {
+                return None;
+            }
+            let clamp = dragging == Some(ScrollbarDrag::Preview);
+            scrollbar_target(self.session_view.scrollbar_area, pos, max, clamp).map(|o| (ScrollbarDrag::Preview, o))
+        };
+        match dragging {
+            Some(ScrollbarDrag::Tree) => tree(),
+            Some(ScrollbarDrag::Preview) => preview(),
+            None => tree().or_else(preview),
+        }
+    }
+
+    /// The tree scrolls by moving the selection: the viewport always follows it.
+    fn drag_scrollbar(&mut self, (bar, at): (ScrollbarDrag, usize)) {
+        match bar {
+            ScrollbarDrag::Tree => {
+                self.tree_selected = at;
+                self.update_scroll();
+            }
+            ScrollbarDrag::Preview => self.session_view.scroll_to(at),
+        }
+    }
+
+    /// A click on a session row of the worktree preview selects that session in the tree.
+    fn click_preview_session(&mut self, pi: usize, wi: usize, row: u16) {
+        let Some(top) = self.preview_sessions_top else { return };
+        let Some(row) = row.checked_sub(top).map(usize::from) else { return };
+        let order = self.workspace.worktree(pi, wi).map(|wt| self.workspace.session_order(wt)).unwrap_or_default();
+        let Some(&si) = order.get(row) else { return };
+        if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
+            wt.expanded = true;
+        }
+        self.rebuild_flat();
+        let row = tree_nav::row_of(self.flat(), &FlatEntry::Session { project_idx: pi, worktree_idx: wi, session_idx: si });
+        self.select_row(row);
+    }
+
     fn dispatch_input(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
         match action {
-            Action::InputEscape | Action::Quit => {
-                self.mode = Mode::Normal;
-            }
+            Action::InputEscape | Action::Quit => self.back_out(),
             Action::Select => {
                 self.confirm_input(terminal)?;
             }
@@ -723,22 +2233,19 @@ impl App {
                     state.cursor_right();
                 }
             }
-            Action::InputTab | Action::NavigateDown => {
-                if let Mode::Input {
-                    context: InputContext::AddProject,
-                    state,
-                } = &mut self.mode
-                {
-                    state.select_next();
+            Action::InputTab => {
+                if let Mode::Input { state, .. } = &mut self.mode {
+                    state.tab();
+                }
+            }
+            Action::NavigateDown => {
+                if let Mode::Input { state, .. } = &mut self.mode {
+                    state.down();
                 }
             }
             Action::NavigateUp => {
-                if let Mode::Input {
-                    context: InputContext::AddProject,
-                    state,
-                } = &mut self.mode
-                {
-                    state.select_prev();
+                if let Mode::Input { state, .. } = &mut self.mode {
+                    state.up();
                 }
             }
             _ => {}
@@ -746,22 +2253,77 @@ impl App {
         Ok(())
     }
 
-    fn dispatch_confirm(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+    fn dispatch_form(&mut self, action: Action) -> Result<()> {
+        let Mode::Form { form, .. } = &mut self.mode else { return Ok(()) };
         match action {
-            Action::ConfirmYes | Action::Select => self.confirm_action(terminal)?,
-            Action::NextAttention | Action::InputEscape | Action::Quit => {
+            Action::InputEscape | Action::Quit => self.back_out(),
+            Action::Select => self.submit_form()?,
+            Action::InputChar(c) => form.insert_char(c),
+            Action::InputBackspace => form.backspace(),
+            Action::NavigateLeft => form.cursor_left(),
+            Action::NavigateRight => form.cursor_right(),
+            Action::InputTab => form.tab(),
+            Action::NavigateDown => form.down(),
+            Action::NavigateUp => form.up(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Enter in a form: a validation error keeps the form open with everything typed.
+    fn submit_form(&mut self) -> Result<()> {
+        let Mode::Form { context, form } = &mut self.mode else { return Ok(()) };
+        match context {
+            FormContext::AddSession { worktree } => {
+                let name = form.value(0).trim().to_string();
+                let command = form.value(1).trim().to_string();
+                if let Some(err) = ops::session_name_error(&name) {
+                    form.fail(0, err);
+                    return Ok(());
+                }
+                let worktree = std::mem::take(worktree);
                 self.mode = Mode::Normal;
+                self.history.record(&command);
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                let cmd = if command.is_empty() { None } else { Some(command) };
+                self.do_create_session(pi, wi, name, cmd)?;
             }
-            _ => {}
+            FormContext::Adopt { worktree } => {
+                let [dest, alias, session] = [0, 1, 2].map(|i| form.value(i).trim().to_string());
+                let worktree = worktree.clone();
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                let dest = Some(ops::expand_path(&dest)).filter(|d| !dest.is_empty() && *d != worktree);
+                if let Some((field, err)) = self.adopt_problem(pi, wi, dest.as_deref(), &alias, &session) {
+                    if let Mode::Form { form, .. } = &mut self.mode {
+                        form.fail(field, err);
+                    }
+                    return Ok(());
+                }
+                self.mode = Mode::Normal;
+                self.run_git(PendingAction::Adopt { worktree, dest, alias, session })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys arrive raw, so `n` here is only ever "no".
+    fn dispatch_confirm(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+        match confirm_key(&action, self.confirm_focus) {
+            ConfirmKey::Yes => self.confirm_action(terminal)?,
+            ConfirmKey::No => self.back_out(),
+            ConfirmKey::Focus(button) => self.confirm_focus = button,
+            ConfirmKey::Unrelated => self.set_status("Press y to confirm or n to cancel"),
+            ConfirmKey::Ignored => {}
+        }
+        if !matches!(self.mode, Mode::Confirm { .. }) {
+            self.confirm_focus = ConfirmButton::default();
         }
         Ok(())
     }
 
     fn dispatch_search(&mut self, action: Action, _terminal: &mut Tui) -> Result<()> {
         match action {
-            Action::InputEscape | Action::Quit => {
-                self.mode = Mode::Normal;
-            }
+            Action::InputEscape | Action::Quit => self.end_search(),
             Action::InputChar(c) => {
                 if let Mode::Search {
                     ref mut query,
@@ -799,7 +2361,8 @@ impl App {
             } => {
                 let wt = &self.workspace.projects[*pi].worktrees[*wi];
                 let alias = wt.alias.as_deref().unwrap_or("");
-                format!("{} {} {}", wt.branch, alias, wt.name).to_lowercase()
+                let note = wt.note.as_deref().unwrap_or("");
+                format!("{} {} {} {}", wt.branch, alias, wt.name, note).to_lowercase()
             }
             FlatEntry::Session {
                 project_idx: pi,
@@ -808,6 +2371,18 @@ impl App {
             } => self.workspace.projects[*pi].worktrees[*wi].sessions[*si]
                 .display_name
                 .to_lowercase(),
+            FlatEntry::Window {
+                project_idx: pi,
+                worktree_idx: wi,
+                session_idx: si,
+                window_idx: wn,
+            } => self.workspace.projects[*pi].worktrees[*wi].sessions[*si].windows[*wn]
+                .name
+                .to_lowercase(),
+            FlatEntry::ArchiveHeader { .. } => String::new(),
+            FlatEntry::Archived { project_idx: pi, archive_idx: ai } => {
+                self.workspace.projects[*pi].archived[*ai].branch.to_lowercase()
+            }
         }
     }
 
@@ -845,7 +2420,7 @@ impl App {
         };
         let matches = self.search_matches(&query);
         if matches.is_empty() {
-            self.mode = Mode::Normal;
+            self.end_search();
             return;
         }
         let next = (match_idx + 1) % matches.len();
@@ -859,33 +2434,270 @@ impl App {
         self.update_scroll();
     }
 
-    // ── Actions ───────────────────────────────────────────────────────────────
+    /// Leave search mode, keeping a non-empty query for `n` / `N`.
+    fn end_search(&mut self) {
+        if let Mode::Search { query, .. } = std::mem::replace(&mut self.mode, Mode::Normal) {
+            self.last_search = Some(query).filter(|q| !q.is_empty());
+        }
+    }
 
-    fn action_select(&mut self, terminal: &mut Tui) -> Result<()> {
-        match self.current_selection() {
+    /// `n` / `N` after a search: the next match after (or before) the cursor, wrapping.
+    fn search_repeat(&mut self, dir: isize) {
+        let Some(query) = self.last_search.clone() else { return };
+        let matches = self.search_matches(&query);
+        if matches.is_empty() {
+            self.set_status(format!("No matches for '{}'", query));
+            return;
+        }
+        let pos = if dir >= 0 {
+            matches.iter().position(|&i| i > self.tree_selected).unwrap_or(0)
+        } else {
+            matches.iter().rposition(|&i| i < self.tree_selected).unwrap_or(matches.len() - 1)
+        };
+        self.tree_selected = matches[pos];
+        self.update_scroll();
+        self.set_status(format!("match {}/{} for '{}'", pos + 1, matches.len(), query));
+    }
+
+    // ── Actions ───────────────────────────────────────────────────────────────
+
+    fn action_select(&mut self, terminal: &mut Tui) -> Result<()> {
+        match self.current_selection() {
             Selection::Session(pi, wi, si) => {
-                self.attach_session(pi, wi, si, terminal)?;
+                let window = self.previewed_window();
+                if self.refuse_own_session(pi, wi, si) {
+                    return Ok(());
+                }
+                if let Some(message) = self.attach_warning(pi, wi, si) {
+                    self.mode = Mode::Confirm {
+                        message,
+                        pending: PendingAction::AttachSession {
+                            session: self.session_name(pi, wi, si),
+                            window_idx: window,
+                        },
+                        danger: false,
+                    };
+                    return Ok(());
+                }
+                self.attach_session(pi, wi, si, window, terminal)?;
             }
             Selection::Project(pi) => {
-                self.workspace.projects[pi].expanded = !self.workspace.projects[pi].expanded;
+                let Some(project) = self.workspace.projects.get_mut(pi) else {
+                    self.stale_selection();
+                    return Ok(());
+                };
+                if project.missing {
+                    self.action_relocate_project(pi);
+                    return Ok(());
+                }
+                project.expanded = !project.expanded;
                 self.rebuild_flat();
                 self.clamp_selected();
             }
             Selection::Worktree(pi, wi) => {
-                self.workspace.projects[pi].worktrees[wi].expanded =
-                    !self.workspace.projects[pi].worktrees[wi].expanded;
+                let Some(wt) = self.workspace.worktree_mut(pi, wi) else {
+                    self.stale_selection();
+                    return Ok(());
+                };
+                if self.pick_path {
+                    self.picked_path = Some(wt.path.clone());
+                    return Ok(());
+                }
+                wt.expanded = !wt.expanded;
+                self.rebuild_flat();
+                self.clamp_selected();
+            }
+            Selection::Archive(pi, None) => {
+                let Some(project) = self.workspace.projects.get_mut(pi) else {
+                    self.stale_selection();
+                    return Ok(());
+                };
+                project.archived_expanded = !project.archived_expanded;
                 self.rebuild_flat();
                 self.clamp_selected();
             }
+            Selection::Archive(pi, Some(ai)) => {
+                let Some(archived) = self.workspace.projects.get(pi).and_then(|p| p.archived.get(ai)) else {
+                    self.stale_selection();
+                    return Ok(());
+                };
+                let sessions = match archived.sessions.len() {
+                    0 => String::new(),
+                    1 => " and its session".to_string(),
+                    n => format!(" and its {} sessions", n),
+                };
+                self.mode = Mode::Confirm {
+                    danger: false,
+                    message: format!("Restore worktree '{}'{} from {}?", archived.branch, sessions, archived.tag),
+                    pending: PendingAction::RestoreArchived {
+                        project: self.workspace.projects[pi].path.clone(),
+                        tag: archived.tag.clone(),
+                    },
+                };
+            }
             Selection::None => {}
         }
         Ok(())
     }
 
-    fn attach_to_session(&self, name: &str, terminal: &mut Tui) -> Result<()> {
-        session::apply_session_defaults(name);
-        match session::attach_session_cmd(name) {
-            session::AttachCommand::SwitchClient(n) => session::switch_client(&n)?,
+    fn is_own_session(&self, name: &str) -> bool {
+        self.own_session.as_deref() == Some(name)
+    }
+
+    /// Switching the client to wsx's own session (or another of its windows) would
+    /// take wsx off screen; says so instead.
+    fn refuse_own_session(&mut self, pi: usize, wi: usize, si: usize) -> bool {
+        let Some(sess) = self.workspace.session(pi, wi, si).filter(|s| self.is_own_session(&s.name)) else {
+            return false;
+        };
+        let msg = format!("'{}' is the session wsx runs in — already here", sess.display_name);
+        self.set_status(msg);
+        true
+    }
+
+    fn action_main_shell(&mut self, terminal: &mut Tui) -> Result<()> {
+        if self.select_main_shell()? {
+            self.action_select(terminal)?;
+        }
+        Ok(())
+    }
+
+    /// `!`: put the cursor on the first session of the project's main worktree,
+    /// creating a plain shell session there first if it has none — pressing it
+    /// again reuses that session. False when there's nothing to attach to.
+    fn select_main_shell(&mut self) -> Result<bool> {
+        let pi = match self.current_selection() {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => pi,
+            Selection::None => {
+                self.set_status("Select a project first");
+                return Ok(false);
+            }
+        };
+        self.select_main_shell_of(pi)
+    }
+
+    fn select_main_shell_of(&mut self, pi: usize) -> Result<bool> {
+        let Some(wi) = self.main_worktree(pi) else { return Ok(false) };
+        match self.workspace.session_order(&self.workspace.projects[pi].worktrees[wi]).first() {
+            Some(&si) => self.select_session(pi, wi, si, None),
+            None => self.do_create_session(pi, wi, "shell".into(), None)?,
+        }
+        Ok(matches!(self.current_selection(), Selection::Session(..)))
+    }
+
+    /// Index of the project's main worktree; None (with a status) when it has none.
+    fn main_worktree(&mut self, pi: usize) -> Option<usize> {
+        let wi = self.workspace.projects[pi].worktrees.iter().position(|w| w.is_main);
+        if wi.is_none() {
+            self.set_status("No main worktree");
+        }
+        wi
+    }
+
+    /// With `confirm_attach_running`, the confirm text for a session running an
+    /// interactive app; None means attach straight away.
+    fn attach_warning(&self, pi: usize, wi: usize, si: usize) -> Option<String> {
+        if !self.config.confirm_attach_running {
+            return None;
+        }
+        let sess = self.workspace.session(pi, wi, si)?;
+        let app = self.activity.get(&sess.name)?.interactive_app()?;
+        Some(format!("Session '{}' is running {} — attach?", sess.display_name, app))
+    }
+
+    /// Index into the session's `windows` when the cursor is on a window row.
+    fn selected_window(&self) -> Option<usize> {
+        match self.flat().get(self.tree_selected) {
+            Some(FlatEntry::Window { window_idx, .. }) => Some(*window_idx),
+            _ => None,
+        }
+    }
+
+    /// The window the preview shows: the window row under the cursor, or the one `{` / `}`
+    /// picked for the selected session.
+    pub fn previewed_window(&self) -> Option<usize> {
+        if let Some(window) = self.selected_window() {
+            return Some(window);
+        }
+        let Selection::Session(pi, wi, si) = self.current_selection() else {
+            return None;
+        };
+        let sess = self.workspace.session(pi, wi, si)?;
+        let id = self.preview_windows.get(&sess.name)?;
+        sess.windows.iter().position(|w| &w.id == id)
+    }
+
+    /// Forget `{` / `}` picks whose window or session has closed.
+    fn prune_preview_windows(&mut self) {
+        let activity = &self.activity;
+        self.preview_windows
+            .retain(|name, id| activity.get(name).is_some_and(|s| s.windows.iter().any(|w| &w.id == id)));
+    }
+
+    /// `{` / `}`: step the selected session's preview through tmux's current window and
+    /// then each window in turn.
+    fn cycle_window(&mut self, step: i32) {
+        let Selection::Session(pi, wi, si) = self.current_selection() else {
+            self.set_status("Select a session to cycle its windows");
+            return;
+        };
+        if self.selected_window().is_some() {
+            self.set_status("On a window row — Enter attaches to it");
+            return;
+        }
+        let Some(sess) = self.workspace.session(pi, wi, si) else { return };
+        let count = sess.windows.len();
+        if count < 2 {
+            self.set_status(format!("'{}' has only one window", sess.display_name));
+            return;
+        }
+        // Slot 0 is tmux's current window, slot w + 1 the session's `windows[w]`.
+        let slot = self.previewed_window().map_or(0, |w| w + 1) as i32;
+        let next = (slot + step).rem_euclid(count as i32 + 1) as usize;
+        let name = sess.name.clone();
+        let status = match next.checked_sub(1).and_then(|w| sess.windows.get(w)) {
+            Some(win) => format!("Previewing window {}: {} — Enter attaches there", win.index, win.name),
+            None => "Previewing the current window".to_string(),
+        };
+        match next.checked_sub(1).and_then(|w| sess.windows.get(w)) {
+            Some(win) => self.preview_windows.insert(name, win.id.clone()),
+            None => self.preview_windows.remove(&name),
+        };
+        self.set_status(status);
+        self.needs_redraw = true;
+    }
+
+    /// tmux target for a session, or `session:index` for one of its windows.
+    fn tmux_target(&self, pi: usize, wi: usize, si: usize, window: Option<usize>) -> Option<String> {
+        let sess = self.workspace.session(pi, wi, si)?;
+        Some(match window.and_then(|w| sess.windows.get(w)) {
+            Some(win) => session::window_target(&sess.name, win.index),
+            None => sess.name.clone(),
+        })
+    }
+
+    /// Target of the session (or window row) under the cursor.
+    fn selected_target(&self) -> Option<String> {
+        match self.current_selection() {
+            Selection::Session(pi, wi, si) => self.tmux_target(pi, wi, si, self.previewed_window()),
+            _ => None,
+        }
+    }
+
+    /// Attach to `target` (session `name`, or one of its windows) and count the use:
+    /// in the attach log, and towards project `pi`'s frecency.
+    fn attach_to_session(&mut self, pi: usize, name: &str, target: &str, terminal: &mut Tui) -> Result<()> {
+        self.record_attach(pi, name);
+        match session::attach_session_cmd(target) {
+            session::AttachCommand::SwitchClient(n) => {
+                session::switch_client(&n)?;
+                if self.own_session.is_some() {
+                    self.focus.lose(Instant::now());
+                }
+            }
             session::AttachCommand::Attach(n) => {
                 tui::with_raw_mode_disabled(terminal, || session::attach_foreground(&n))?;
             }
@@ -893,16 +2705,32 @@ impl App {
         Ok(())
     }
 
+    fn record_attach(&mut self, pi: usize, name: &str) {
+        let now = frecency::now_secs();
+        self.workspace.attaches.record(name, now);
+        if let Some(sess) = self.workspace.find_session(name).and_then(|(p, w, s)| self.workspace.session_mut(p, w, s)) {
+            sess.acknowledge_bell(now);
+        }
+        self.bump_frecency(pi);
+        if self.workspace.recent_sessions {
+            let key = self.selection_key();
+            self.restore_selection(key);
+        }
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+    }
+
     fn attach_session(
         &mut self,
         pi: usize,
         wi: usize,
         si: usize,
+        window: Option<usize>,
         terminal: &mut Tui,
     ) -> Result<()> {
         let name = self.workspace.session(pi, wi, si).map(|s| s.name.clone());
+        let target = self.tmux_target(pi, wi, si, window);
 
-        let Some(name) = name else {
+        let (Some(name), Some(target)) = (name, target) else {
             self.set_status("Session not found");
             return Ok(());
         };
@@ -917,7 +2745,7 @@ impl App {
             session::set_session_opt(&name, "status-right", &label);
         }
 
-        self.attach_to_session(&name, terminal)?;
+        self.attach_to_session(pi, &name, &target, terminal)?;
 
         // Invalidate git info so it's re-fetched after returning from the session.
         if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
@@ -934,23 +2762,396 @@ impl App {
         Ok(())
     }
 
-    fn action_add_worktree(&mut self) -> Result<()> {
-        let pi = match self.current_selection() {
-            Selection::Project(pi) | Selection::Worktree(pi, _) | Selection::Session(pi, _, _) => {
-                pi
+    /// Second step of Add Project for a URL: where to put the clone.
+    fn action_clone_project(&mut self, url: String) {
+        let dir = ops::expand_path(self.config.clone_dir.as_deref().unwrap_or("~/"));
+        let dest = dir.join(git_ops::clone_dir_name(&url));
+        self.mode = Mode::Input {
+            context: InputContext::CloneProject { url },
+            state: InputState::new_path("path: ", display_path(&dest, false, usize::MAX)),
+        };
+    }
+
+    /// Clone on a worker thread; `poll_clone` registers the result.
+    fn start_clone(&mut self, url: String, dest: PathBuf) {
+        if let Some(c) = &self.cloning {
+            self.set_status(format!("Still cloning {} — one clone at a time", c.name));
+            return;
+        }
+        if dest.read_dir().is_ok_and(|mut d| d.next().is_some()) {
+            self.set_status(format!("{} already exists and isn't empty", display_path(&dest, false, usize::MAX)));
+            return;
+        }
+        let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        self.cloning = Some(Cloning { name, dest: dest.clone(), percent: None });
+        self.needs_redraw = true;
+        let tx = self.clone_tx.clone();
+        let git = Arc::clone(&self.git);
+        std::thread::spawn(move || {
+            let progress = |pct| {
+                let _ = tx.send(CloneEvent::Progress(pct));
+            };
+            let result = git.clone_repo(&url, &dest, &progress).map_err(|e| e.to_string());
+            let _ = tx.send(CloneEvent::Done(result));
+        });
+    }
+
+    /// The selected project (any, with nothing selected) while it's drawn from the cache.
+    fn still_syncing(&self) -> Option<String> {
+        let syncing = match self.current_selection() {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => self.workspace.projects.get(pi).filter(|p| p.syncing),
+            Selection::None => self.workspace.projects.iter().find(|p| p.syncing),
+        };
+        syncing.map(|p| p.name.clone())
+    }
+
+    /// Load every project's live git data on a thread, top of the tree first; `poll_sync`
+    /// swaps each one in for its cached stand-in as it lands.
+    fn start_sync(&mut self) {
+        let entries: Vec<ProjectEntry> = self
+            .workspace
+            .projects
+            .iter()
+            .filter_map(|p| self.config.projects.iter().find(|e| e.path == p.path).cloned())
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let git = Arc::clone(&self.git);
+        std::thread::spawn(move || {
+            let cache = WorkspaceCache::load();
+            for entry in entries {
+                let mut project = ops::load_project(git.as_ref(), &entry);
+                cache::apply_to_project(&cache, &mut project);
+                if tx.send(project).is_err() {
+                    return;
+                }
             }
-            Selection::None => {
-                self.set_status("Select a project first (press p to add one)");
-                return Ok(());
+        });
+        self.sync_rx = Some(rx);
+    }
+
+    fn poll_sync(&mut self) {
+        let Some(rx) = &self.sync_rx else { return };
+        let (mut loaded, mut stopped) = (Vec::new(), false);
+        loop {
+            match rx.try_recv() {
+                Ok(project) => loaded.push(project),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    stopped = true;
+                    break;
+                }
+            }
+        }
+        if !loaded.is_empty() {
+            self.ensure_flat();
+            let anchor = self.selection_anchor();
+            for project in loaded {
+                if let Some(err) = project.config.as_ref().and_then(|c| c.alias_pattern_error.as_ref()) {
+                    self.set_status(format!("project '{}': {} — press D for doctor", project.name, err));
+                }
+                ops::adopt_loaded(&mut self.workspace, project);
+            }
+            self.rebuild_flat();
+            self.restore_anchor(anchor);
+        }
+        let syncing = self.workspace.projects.iter().any(|p| p.syncing);
+        if syncing && !stopped {
+            self.needs_redraw = true; // the rows' spinner
+            return;
+        }
+        // Done (or the thread died: the rescan loads what's left as usual).
+        self.sync_rx = None;
+        for p in &mut self.workspace.projects {
+            p.syncing = false;
+        }
+        // Sessions in worktrees the cache didn't know about yet.
+        if let Err(e) = self.refresh_all() {
+            self.set_error(format!("Refresh error: {}", e));
+        }
+        self.needs_redraw = true;
+    }
+
+    /// The selected row by what it shows rather than its index: the worktree's path
+    /// for rows under one.
+    fn selection_anchor(&self) -> Option<(FlatEntry, Option<PathBuf>)> {
+        let entry = self.flat().get(self.tree_selected)?.clone();
+        let worktree = match entry {
+            FlatEntry::Worktree { project_idx, worktree_idx }
+            | FlatEntry::Session { project_idx, worktree_idx, .. }
+            | FlatEntry::Window { project_idx, worktree_idx, .. } => {
+                self.workspace.worktree(project_idx, worktree_idx).map(|w| w.path.clone())
+            }
+            _ => None,
+        };
+        Some((entry, worktree))
+    }
+
+    /// Back onto the row `selection_anchor` described, after worktrees above it came or went.
+    fn restore_anchor(&mut self, anchor: Option<(FlatEntry, Option<PathBuf>)>) {
+        let Some((mut entry, worktree)) = anchor else { return };
+        if let Some(found) = worktree.and_then(|path| self.workspace.find_worktree(&path)) {
+            match &mut entry {
+                FlatEntry::Worktree { project_idx, worktree_idx }
+                | FlatEntry::Session { project_idx, worktree_idx, .. }
+                | FlatEntry::Window { project_idx, worktree_idx, .. } => {
+                    (*project_idx, *worktree_idx) = found;
+                }
+                _ => {}
+            }
+        }
+        match tree_nav::row_of(self.flat(), &entry) {
+            Some(row) => self.select_row(Some(row)),
+            None => self.clamp_selected(),
+        }
+    }
+
+    fn poll_clone(&mut self) {
+        while let Ok(event) = self.clone_rx.try_recv() {
+            self.needs_redraw = true;
+            match event {
+                CloneEvent::Progress(pct) => {
+                    if let Some(c) = &mut self.cloning {
+                        c.percent = Some(pct);
+                    }
+                }
+                CloneEvent::Done(result) => {
+                    let Some(c) = self.cloning.take() else { continue };
+                    match result {
+                        Ok(()) => match self.do_register_project(c.dest.clone()) {
+                            Ok(()) if self.workspace.projects.iter().any(|p| p.path == c.dest) => {
+                                self.select_project_at(&c.dest);
+                                self.set_status(format!("Cloned and registered {}", c.name));
+                            }
+                            Ok(()) => {}
+                            Err(e) => self.set_status(format!("Cloned to {}, but: {}", display_path(&c.dest, false, usize::MAX), e)),
+                        },
+                        Err(e) => self.set_error(format!("clone failed: {}", e)),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stream `created`'s postCreate hook into an output popup; Esc kills it.
+    fn start_post_create(&mut self, branch: String, cmd: String, created: ops::CreatedWorktree, started_for: PostCreateFor) {
+        self.mode = Mode::GitOutput {
+            title: format!("postCreate · {}", branch),
+            text: format!("$ {}\n", cmd),
+            scroll: 0,
+        };
+        let tx = self.hook_tx.clone();
+        let dir = created.path.clone();
+        let command = cmd.clone();
+        std::thread::spawn(move || {
+            let result = hooks::run_post_create(
+                &dir,
+                &command,
+                |child| {
+                    let _ = tx.send(HookEvent::Started(child));
+                },
+                |line| {
+                    let _ = tx.send(HookEvent::Line(line));
+                },
+            );
+            let _ = tx.send(HookEvent::Done(result.map_err(|e| e.to_string())));
+        });
+        if started_for == PostCreateFor::Rerun {
+            self.set_status(format!("Running postCreate in {} (Esc cancels)", branch));
+        } else {
+            self.set_status(format!("Created worktree: {} — running postCreate (Esc cancels)", branch));
+        }
+        self.post_create = Some(PostCreateRun {
+            branch,
+            child: None,
+            cancelled: false,
+            follow: true,
+            created,
+            command: cmd,
+            started: Instant::now(),
+            tail: Vec::new(),
+            started_for,
+        });
+    }
+
+    fn poll_post_create(&mut self) {
+        while let Ok(event) = self.hook_rx.try_recv() {
+            self.needs_redraw = true;
+            let Some(run) = &mut self.post_create else { continue };
+            let follow = run.follow;
+            let output = match event {
+                HookEvent::Started(child) => {
+                    if run.cancelled {
+                        hooks::cancel_post_create(&child);
+                    }
+                    run.child = Some(child);
+                    continue;
+                }
+                HookEvent::Line(line) => {
+                    HookRun::push_line(&mut run.tail, &line);
+                    line + "\n"
+                }
+                HookEvent::Done(result) => {
+                    let Some(run) = self.post_create.take() else { continue };
+                    self.finish_post_create(run, result)
+                }
+            };
+            if let Mode::GitOutput { text, scroll, .. } = &mut self.mode {
+                text.push_str(&output);
+                if follow {
+                    *scroll = u16::MAX; // clamped to the last page when drawn
+                }
+            }
+        }
+    }
+
+    /// Status for a finished postCreate run; returns the popup's closing lines.
+    fn finish_post_create(&mut self, run: PostCreateRun, result: std::result::Result<(), String>) -> String {
+        let PostCreateRun { branch, cancelled, mut created, command, started, tail, started_for, .. } = run;
+        let rerun = started_for == PostCreateFor::Rerun;
+        let outcome = match &result {
+            _ if cancelled => "✗ cancelled — the worktree is there, postCreate didn't finish".to_string(),
+            Ok(()) => "✓ postCreate finished".to_string(),
+            Err(e) => format!("✗ {}", e),
+        };
+        let error = match &result {
+            _ if cancelled => Some("cancelled".to_string()),
+            Ok(()) => None,
+            Err(e) => Some(e.clone()),
+        };
+        let hook = HookRun { command, ts: frecency::now_secs(), secs: started.elapsed().as_secs(), error, tail };
+        let env_files = match self.workspace.setup.get(&created.path) {
+            Some(setup) if rerun => setup.env_files,
+            _ => env_files(&created.copy),
+        };
+        self.record_setup(&created.path, WorktreeSetup { env_files, hook: Some(hook) });
+        if rerun {
+            match result {
+                _ if cancelled => self.set_status(format!("postCreate in {} cancelled", branch)),
+                Ok(()) => self.set_status(format!("postCreate finished in {}", branch)),
+                Err(e) => self.set_error(format!("postCreate in {}: {}", branch, e)),
             }
+            return outcome;
+        }
+        if cancelled {
+            self.set_status(format!("Created worktree: {} — postCreate cancelled, hook incomplete", branch));
+        } else {
+            if let Err(e) = result {
+                created.warning = Some(format!("Warning: postCreate: {}", e));
+            }
+            self.set_status(created_status(format!("Created worktree: {}", branch), &created));
+            if started_for == PostCreateFor::StartWork {
+                self.start_dev_session(&branch, &created);
+            }
+        }
+        let report = copy_report(&created.copy);
+        if report.is_empty() { outcome } else { format!("{}\n\n{}", outcome, report) }
+    }
+
+    /// Remember what setting up the worktree at `path` did, for its preview.
+    fn record_setup(&mut self, path: &Path, setup: WorktreeSetup) {
+        self.workspace.setup.insert(path.to_path_buf(), setup);
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+    }
+
+    /// `h` in the git popup: the project's postCreate hook again, streamed like after `w`.
+    fn rerun_post_create(&mut self, pi: usize, wi: usize) {
+        let Some(wt) = self.workspace.worktree(pi, wi) else { return };
+        let (branch, path) = (wt.display_name().to_string(), wt.path.clone());
+        let hook = self.workspace.projects[pi].config.as_ref().and_then(|c| c.post_create.clone());
+        let Some(cmd) = hook else {
+            self.set_status("No postCreate hook in .gtrconfig");
+            return;
+        };
+        if self.post_create.is_some() {
+            self.set_status("A postCreate hook is already running");
+            return;
+        }
+        let created = ops::CreatedWorktree { path, copy: Default::default(), warning: None, post_create: None, carried: None };
+        self.start_post_create(branch, cmd, created, PostCreateFor::Rerun);
+    }
+
+    /// `H` in the git popup: what the worktree's last postCreate run printed.
+    fn show_last_post_create(&mut self, pi: usize, wi: usize) {
+        let Some(wt) = self.workspace.worktree(pi, wi) else { return };
+        let Some(hook) = self.workspace.setup.get(&wt.path).and_then(|s| s.hook.as_ref()) else {
+            self.set_status(format!("No postCreate run recorded for {}", wt.display_name()));
+            return;
         };
+        let outcome = match &hook.error {
+            None => format!("✓ finished in {}s", hook.secs),
+            Some(e) => format!("✗ {} after {}s", e, hook.secs),
+        };
+        let mut text = format!("$ {}\n", hook.command);
+        if hook.tail.is_empty() {
+            text.push_str("(no output)\n");
+        }
+        for line in &hook.tail {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push('\n');
+        text.push_str(&outcome);
+        let title = format!("Last postCreate · {}", wt.display_name());
+        self.open_layer(Mode::GitOutput { title, text, scroll: u16::MAX });
+    }
+
+    /// The path prompt starts at the closest directory that still exists.
+    fn action_relocate_project(&mut self, pi: usize) {
+        let Some(project) = self.workspace.projects.get(pi) else { return };
+        let start = project.path.ancestors().skip(1).find(|p| p.is_dir()).unwrap_or(Path::new("/"));
+        let mut initial = display_path(start, false, usize::MAX);
+        if !initial.ends_with('/') {
+            initial.push('/');
+        }
         self.mode = Mode::Input {
-            context: InputContext::AddWorktree { project_idx: pi },
-            state: InputState::new("branch: "),
+            context: InputContext::RelocateProject { project: project.path.clone() },
+            state: InputState::new_path("new path: ", initial),
         };
+    }
+
+    fn action_add_worktree(&mut self) -> Result<()> {
+        if let Some(pi) = self.project_for_new_worktree() {
+            self.prompt_branch(InputContext::AddWorktree { project: self.project_path(pi) }, pi);
+        }
         Ok(())
     }
 
+    fn action_start_work(&mut self) {
+        if let Some(pi) = self.project_for_new_worktree() {
+            self.prompt_branch(InputContext::StartWork { project: self.project_path(pi) }, pi);
+        }
+    }
+
+    fn project_for_new_worktree(&mut self) -> Option<usize> {
+        match self.current_selection() {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => Some(pi),
+            Selection::None => {
+                self.set_status("Select a project first (press p to add one)");
+                None
+            }
+        }
+    }
+
+    fn prompt_branch(&mut self, context: InputContext, pi: usize) {
+        self.mode = Mode::Input {
+            context,
+            state: InputState::with_source(
+                "branch (@ref: detached): ",
+                String::new(),
+                CompletionSource::Words(self.free_branches(pi)),
+            ),
+        };
+    }
+
     fn action_add_session(&mut self) -> Result<()> {
         let (pi, wi) = match self.current_selection() {
             Selection::Worktree(pi, wi) | Selection::Session(pi, wi, _) => (pi, wi),
@@ -959,29 +3160,45 @@ impl App {
                 return Ok(());
             }
         };
-        self.mode = Mode::Input {
-            context: InputContext::AddSession {
-                project_idx: pi,
-                worktree_idx: wi,
-            },
-            state: InputState::new("name (optional): "),
+        self.mode = Mode::Form {
+            context: FormContext::AddSession { worktree: self.worktree_path(pi, wi) },
+            form: FormState::new(vec![
+                ("name", InputState::new("")),
+                ("command", self.command_input("")),
+            ]),
         };
         Ok(())
     }
 
     fn action_delete(&mut self) -> Result<()> {
         match self.current_selection() {
+            Selection::Session(pi, wi, si) if self.selected_window().is_some() => {
+                let window = self.selected_window();
+                let sess = &self.workspace.projects[pi].worktrees[wi].sessions[si];
+                let name = window
+                    .and_then(|w| sess.windows.get(w))
+                    .map(|w| w.name.clone())
+                    .unwrap_or_default();
+                let message = format!("Close window '{}' of '{}'?", name, sess.display_name);
+                if let Some(target) = self.tmux_target(pi, wi, si, window) {
+                    self.mode = Mode::Confirm {
+                        danger: false,
+                        message,
+                        pending: PendingAction::KillWindow { target, name },
+                    };
+                }
+            }
             Selection::Session(pi, wi, si) => {
-                let display_name = self.workspace.projects[pi].worktrees[wi].sessions[si]
-                    .display_name
-                    .clone();
+                let sess = &self.workspace.projects[pi].worktrees[wi].sessions[si];
+                let own = self.is_own_session(&sess.name);
+                let mut message = format!("Kill session '{}'?", sess.display_name);
+                if own {
+                    message.push_str(" This will terminate wsx itself!");
+                }
                 self.mode = Mode::Confirm {
-                    message: format!("Kill session '{}'?", display_name),
-                    pending: PendingAction::DeleteSession {
-                        project_idx: pi,
-                        worktree_idx: wi,
-                        session_idx: si,
-                    },
+                    danger: own,
+                    message,
+                    pending: PendingAction::DeleteSession { session: sess.name.clone() },
                 };
             }
             Selection::Worktree(pi, wi) => {
@@ -990,116 +3207,563 @@ impl App {
                     self.set_status("Cannot delete main worktree");
                     return Ok(());
                 }
-                let merged = git_worktree::is_branch_merged(
-                    &self.workspace.projects[pi].path,
-                    &wt.branch,
-                    &self.workspace.projects[pi].default_branch,
-                );
-                let msg = if merged {
-                    format!("Delete worktree '{}'?", wt.name)
+                if wt.hidden {
+                    self.set_status(hidden_text(&wt.branch));
+                    return Ok(());
+                }
+                if let Some(reason) = &wt.locked {
+                    self.set_status(locked_text(&wt.branch, reason));
+                    return Ok(());
+                }
+                // A detached worktree has no branch, but commits made on it that nothing
+                // else references go with it.
+                let unreferenced = if wt.detached { self.git.unreferenced_commits(&wt.path) } else { Some(0) };
+                let merged = if wt.detached {
+                    unreferenced == Some(0)
                 } else {
-                    format!(
-                        "Delete UNMERGED worktree '{}'? Changes will be lost!",
-                        wt.name
+                    self.git.is_branch_merged(
+                        &self.workspace.projects[pi].path,
+                        &wt.branch,
+                        &self.workspace.projects[pi].default_branch,
                     )
                 };
+                let session_names: Vec<String> =
+                    wt.sessions.iter().map(|s| s.name.clone()).collect();
+                let (mut message, mut danger) =
+                    ops::delete_worktree_message(&wt.name, merged, &session_names, &self.activity);
+                if let Some(divergence) = wt.git_info.as_ref().and_then(|g| g.divergence.as_ref()) {
+                    message.push_str(&format!(" It {}.", ops::divergence_text(divergence, frecency::now_secs())));
+                }
+                if let Some(n) = unreferenced.filter(|&n| n > 0) {
+                    message.push_str(&format!(" {} commit(s) on its HEAD are on no branch or tag.", n));
+                }
+                let changes = self.git.uncommitted_changes(&wt.path);
+                match &changes {
+                    Ok(0) => {}
+                    Ok(n) => message.push_str(&format!(" {} lost!", uncommitted_text(*n))),
+                    Err(_) => message.push_str(" Couldn't check it for uncommitted changes — any will be lost!"),
+                }
+                danger |= !matches!(changes, Ok(0));
+                if session_names.iter().any(|n| self.is_own_session(n)) {
+                    message.push_str(" This will terminate wsx itself!");
+                    danger = true;
+                }
                 self.mode = Mode::Confirm {
-                    message: msg,
+                    message,
+                    danger,
                     pending: PendingAction::DeleteWorktree {
-                        project_idx: pi,
-                        worktree_idx: wi,
+                        worktree: wt.path.clone(),
+                        force: !matches!(changes, Ok(0)),
                     },
                 };
             }
             Selection::Project(pi) => {
                 let name = self.workspace.projects[pi].name.clone();
                 self.mode = Mode::Confirm {
+                    danger: false,
                     message: format!("Unregister project '{}'? (files not deleted)", name),
-                    pending: PendingAction::DeleteProject { project_idx: pi },
+                    pending: PendingAction::DeleteProject { project: self.project_path(pi) },
+                };
+            }
+            Selection::Archive(pi, Some(ai)) => {
+                let project = &self.workspace.projects[pi];
+                let archived = &project.archived[ai];
+                self.mode = Mode::Confirm {
+                    danger: true,
+                    message: format!(
+                        "Purge archived '{}'? Deletes tag {} — commits only it kept will be lost!",
+                        archived.branch, archived.tag
+                    ),
+                    pending: PendingAction::PurgeArchived { project: project.path.clone(), tag: archived.tag.clone() },
                 };
             }
+            Selection::Archive(_, None) => self.set_status("Select an archived worktree to purge"),
             Selection::None => {}
         }
         Ok(())
     }
 
-    fn action_clean(&mut self) -> Result<()> {
-        match self.current_selection() {
-            Selection::Worktree(pi, wi) => {
-                let (repo, wt_path, branch, default_branch, is_main, session_names) = {
-                    let p = &self.workspace.projects[pi];
-                    let wt = &p.worktrees[wi];
-                    let names: Vec<String> = wt.sessions.iter().map(|s| s.name.clone()).collect();
-                    (
-                        p.path.clone(),
-                        wt.path.clone(),
-                        wt.branch.clone(),
-                        p.default_branch.clone(),
-                        wt.is_main,
-                        names,
-                    )
-                };
-                if is_main {
-                    self.set_status("Cannot clean main worktree");
-                    return Ok(());
-                }
-                if !git_worktree::is_branch_merged(&repo, &branch, &default_branch) {
-                    self.set_status(format!("'{}' not merged into {}", branch, default_branch));
-                    return Ok(());
-                }
-                ops::delete_worktree(&repo, &wt_path, &branch, &session_names)?;
-                self.workspace.projects[pi].worktrees.remove(wi);
-                self.rebuild_flat();
-                self.clamp_selected();
-                self.set_status(format!("Cleaned: {}", branch));
+    /// `A`: archive the selected worktree — its branch is tagged, then the worktree and
+    /// its sessions go as with `d`. Only a clean worktree on a branch qualifies.
+    fn action_archive(&mut self) {
+        let Selection::Worktree(pi, wi) = self.current_selection() else {
+            self.set_status("Select a worktree to archive");
+            return;
+        };
+        let Some(wt) = self.workspace.worktree(pi, wi) else {
+            return self.stale_selection();
+        };
+        if wt.is_main {
+            self.set_status("Cannot archive main worktree");
+            return;
+        }
+        if wt.hidden {
+            self.set_status(hidden_text(&wt.branch));
+            return;
+        }
+        if let Some(reason) = &wt.locked {
+            self.set_status(locked_text(&wt.branch, reason));
+            return;
+        }
+        if wt.detached {
+            self.set_status(format!("'{}' is detached — no branch to archive; d deletes it", wt.branch));
+            return;
+        }
+        match self.git.uncommitted_changes(&wt.path) {
+            Ok(0) => {}
+            Ok(n) => {
+                self.set_status(format!("'{}' has {} — commit or stash first", wt.branch, uncommitted_text(n)));
+                return;
+            }
+            Err(e) => {
+                self.set_status(format!("Couldn't check '{}' for uncommitted changes: {}", wt.branch, e));
+                return;
+            }
+        }
+        let mut message = format!("Archive worktree '{}'? Its branch is kept as a tag", wt.display_name());
+        let count = wt.sessions.len();
+        if count > 0 {
+            message.push_str(&format!("; kills {} session{}", count, if count == 1 { "" } else { "s" }));
+        }
+        message.push('.');
+        let own = wt.sessions.iter().any(|s| self.is_own_session(&s.name));
+        if own {
+            message.push_str(" This will terminate wsx itself!");
+        }
+        self.mode = Mode::Confirm {
+            danger: own,
+            message,
+            pending: PendingAction::ArchiveWorktree { worktree: wt.path.clone() },
+        };
+    }
+
+    /// `L`: unlock a locked worktree at once; otherwise ask for an optional reason and lock it.
+    fn action_toggle_lock(&mut self) -> Result<()> {
+        let Selection::Worktree(pi, wi) = self.current_selection() else {
+            self.set_status("Select a worktree to lock");
+            return Ok(());
+        };
+        let Some(wt) = self.workspace.worktree(pi, wi) else {
+            self.stale_selection();
+            return Ok(());
+        };
+        if wt.is_main {
+            self.set_status("Cannot lock main worktree");
+        } else if wt.locked.is_some() {
+            self.run_git(PendingAction::LockWorktree { worktree: wt.path.clone(), reason: None })?;
+        } else {
+            self.mode = Mode::Input {
+                context: InputContext::LockWorktree { worktree: wt.path.clone() },
+                state: InputState::new("reason (optional): "),
+            };
+        }
+        Ok(())
+    }
+
+    /// `+` on a worktree made outside wsx: where wsx would have put it, an alias (its
+    /// directory name, often what it was called) and a `dev` session, each editable.
+    fn action_adopt(&mut self) {
+        let Selection::Worktree(pi, wi) = self.current_selection() else {
+            self.set_status("Select a worktree to adopt");
+            return;
+        };
+        let Some(wt) = self.workspace.worktree(pi, wi) else {
+            self.stale_selection();
+            return;
+        };
+        if wt.is_main {
+            self.set_status("The main worktree stays where it is");
+            return;
+        }
+        if !wt.external {
+            self.set_status(format!("'{}' already follows the {{repo}}-{{branch}} layout", wt.display_name()));
+            return;
+        }
+        // A worktree with sessions can't move out from under them.
+        let dest = if wt.sessions.is_empty() {
+            crate::git::worktree::conventional_path(&self.project_path(pi), &wt.branch).map(|p| display_path(&p, false, usize::MAX))
+        } else {
+            None
+        };
+        let alias = match &wt.alias {
+            Some(alias) => alias.clone(),
+            None if wt.detached => String::new(),
+            None => wt.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        };
+        let session = if wt.sessions.is_empty() { "dev" } else { "" };
+        self.mode = Mode::Form {
+            context: FormContext::Adopt { worktree: wt.path.clone() },
+            form: FormState::new(vec![
+                ("move to", InputState::new_path("", dest.unwrap_or_default())),
+                ("alias", InputState::with_value("", alias)),
+                ("session", InputState::with_value("", session.to_string())),
+            ]),
+        };
+    }
+
+    /// What keeps the adopt form from going ahead, as (field, message).
+    fn adopt_problem(
+        &self,
+        pi: usize,
+        wi: usize,
+        dest: Option<&Path>,
+        alias: &str,
+        session: &str,
+    ) -> Option<(usize, String)> {
+        let project = &self.workspace.projects[pi];
+        let wt = &project.worktrees[wi];
+        if let Some(dest) = dest {
+            if !wt.sessions.is_empty() {
+                return Some((0, "its sessions run in this directory — leave blank or kill them first".into()));
+            }
+            if dest.exists() {
+                return Some((0, format!("{} already exists", display_path(dest, false, usize::MAX))));
+            }
+        }
+        if !alias.is_empty() && wt.detached {
+            return Some((1, "a detached worktree takes no alias".into()));
+        }
+        if let Some(other) = (!alias.is_empty()).then(|| project.alias_conflict(wi, alias)).flatten() {
+            return Some((1, format!("clashes with worktree '{}'", other.display_name())));
+        }
+        ops::session_name_error(session).map(|e| (2, e.to_string()))
+    }
+
+    /// Adopt in steps — move, alias, session — stopping at the first that fails; the
+    /// banner then says which one and what had already been done.
+    fn do_adopt(&mut self, pi: usize, wi: usize, dest: Option<PathBuf>, alias: String, session: String) -> Result<()> {
+        let name = self.workspace.projects[pi].worktrees[wi].display_name().to_string();
+        let mut path = self.worktree_path(pi, wi);
+        let mut done: Vec<String> = Vec::new();
+        let stopped = |step: &str, e: anyhow::Error, done: &[String]| {
+            let so_far = if done.is_empty() { "nothing changed".to_string() } else { format!("done: {}", done.join(", ")) };
+            format!("Adopting '{}' stopped at the {}: {} — {}", name, step, e, so_far)
+        };
+
+        if let Some(dest) = dest {
+            if let Err(e) = self.git.move_worktree(&self.project_path(pi), &path, &dest) {
+                self.set_error(stopped("move", e, &done));
+                return Ok(());
+            }
+            // Carry what wsx keeps per worktree (pin, note, setup) over to the new path.
+            let wt = &mut self.workspace.projects[pi].worktrees[wi];
+            wt.path = dest.clone();
+            if let Some(setup) = self.workspace.setup.remove(&path) {
+                self.workspace.setup.insert(dest.clone(), setup);
+            }
+            done.push(format!("moved to {}", display_path(&dest, false, usize::MAX)));
+            path = dest;
+        }
+
+        let wt = &self.workspace.projects[pi].worktrees[wi];
+        if !alias.is_empty() && (wt.alias.as_deref() != Some(alias.as_str()) || wt.alias_derived) {
+            let (proj_path, branch) = (self.project_path(pi), self.workspace.projects[pi].worktrees[wi].branch.clone());
+            ops::set_alias(&mut self.config, &proj_path, &branch, &alias);
+            if let Err(e) = self.save_config() {
+                self.set_error(stopped("alias", e, &done));
+                return Ok(());
+            }
+            done.push(format!("alias '{}'", alias));
+        }
+
+        if !session.is_empty() {
+            let (pi, wi) = self.resolve_worktree(&path)?;
+            let command = self.workspace.projects[pi].config.as_ref().and_then(|c| c.default_command.clone());
+            if let Err(e) = self.do_create_session(pi, wi, session.clone(), command) {
+                self.set_error(stopped("session", e, &done));
+                return Ok(());
             }
-            Selection::Project(pi) | Selection::Session(pi, _, _) => {
-                let (path, branch) = {
-                    let p = &self.workspace.projects[pi];
-                    (p.path.clone(), p.default_branch.clone())
+            done.push(format!("session '{}'", session));
+        }
+
+        self.refresh_all()?;
+        if let Some((pi, wi)) = self.workspace.find_worktree(&path) {
+            self.select_worktree(pi, wi);
+        }
+        self.set_status(if done.is_empty() {
+            format!("Nothing to adopt '{}' with — every step was left blank", name)
+        } else {
+            format!("Adopted '{}': {}", name, done.join(", "))
+        });
+        Ok(())
+    }
+
+    fn do_lock_worktree(&mut self, pi: usize, wi: usize, reason: String) -> Result<()> {
+        let repo = self.project_path(pi);
+        let wt = &mut self.workspace.projects[pi].worktrees[wi];
+        self.git.lock_worktree(&repo, &wt.path, Some(&reason))?;
+        let msg = format!("Locked '{}'", wt.display_name());
+        wt.locked = Some(reason);
+        self.set_status(msg);
+        Ok(())
+    }
+
+    fn do_unlock_worktree(&mut self, pi: usize, wi: usize) -> Result<()> {
+        let repo = self.project_path(pi);
+        let wt = &mut self.workspace.projects[pi].worktrees[wi];
+        self.git.unlock_worktree(&repo, &wt.path)?;
+        wt.locked = None;
+        let msg = format!("Unlocked '{}'", wt.display_name());
+        self.set_status(msg);
+        Ok(())
+    }
+
+    fn action_clean(&mut self) -> Result<()> {
+        match self.current_selection() {
+            Selection::Worktree(pi, wi) => {
+                let p = &self.workspace.projects[pi];
+                let wt = &p.worktrees[wi];
+                if wt.is_main {
+                    self.set_status("Cannot clean main worktree");
+                    return Ok(());
+                }
+                if wt.hidden {
+                    self.set_status(hidden_text(&wt.branch));
+                    return Ok(());
+                }
+                if let Some(reason) = &wt.locked {
+                    self.set_status(locked_text(&wt.branch, reason));
+                    return Ok(());
+                }
+                if wt.detached {
+                    self.set_status(format!("'{}' is detached — nothing to merge; d deletes it", wt.branch));
+                    return Ok(());
+                }
+                if !self.git.is_branch_merged(&p.path, &wt.branch, &p.default_branch) {
+                    self.set_status(format!("'{}' not merged into {}", wt.branch, p.default_branch));
+                    return Ok(());
+                }
+                let changes = match self.git.uncommitted_changes(&wt.path) {
+                    Ok(0) => None,
+                    Ok(n) => Some(format!("has {}", uncommitted_text(n))),
+                    Err(_) => Some("couldn't be checked for uncommitted changes".to_string()),
                 };
-                let removed = git_worktree::clean_merged(&path, &branch)?;
-                self.set_status(if removed.is_empty() {
-                    "No merged worktrees to clean".into()
-                } else {
-                    format!("Cleaned: {}", removed.join(", "))
-                });
-                self.refresh_all()?;
+                if let Some(changes) = changes {
+                    self.mode = Mode::Confirm {
+                        danger: true,
+                        message: format!("Worktree '{}' {} — delete anyway?", wt.name, changes),
+                        pending: PendingAction::CleanWorktree { worktree: wt.path.clone(), force: true },
+                    };
+                    return Ok(());
+                }
+                self.run_git(PendingAction::CleanWorktree { worktree: wt.path.clone(), force: false })?;
+            }
+            Selection::Project(pi) | Selection::Session(pi, _, _) | Selection::Archive(pi, _) => {
+                self.run_git(PendingAction::CleanProject { project: self.project_path(pi) })?;
             }
             Selection::None => {
                 let snapshots: Vec<_> = self
                     .workspace
                     .projects
                     .iter()
-                    .map(|p| (p.path.clone(), p.default_branch.clone()))
+                    .map(|p| (p.path.clone(), p.default_branch.clone(), p.config.clone()))
                     .collect();
                 let mut total = 0usize;
-                for (path, branch) in snapshots {
-                    if let Ok(r) = git_worktree::clean_merged(&path, &branch) {
-                        total += r.len();
+                let (mut skipped, mut locked) = (0usize, 0usize);
+                let mut remotes = Vec::new();
+                let mut failed = Vec::new();
+                let mut queued = 0usize;
+                for (path, branch, config) in &snapshots {
+                    let Some(_turn) = self.turn_now(path, "clean") else {
+                        self.queue_git(path.clone(), "clean", PendingAction::CleanProject { project: path.clone() });
+                        queued += 1;
+                        continue;
+                    };
+                    if let Ok(r) = ops::clean_merged(self.git.as_ref(), path, branch, config.as_ref()) {
+                        total += r.removed.len();
+                        skipped += r.skipped_dirty;
+                        locked += r.skipped_locked;
+                        remotes.extend(r.removed.into_iter().filter_map(|c| c.remote));
+                        failed.extend(r.failed);
                     }
                 }
-                self.set_status(format!("Cleaned {} merged worktrees", total));
+                self.report_clean_failures(&failed);
+                let queued = match queued {
+                    0 => String::new(),
+                    n => format!(" — {} project{} queued behind other git operations", n, if n == 1 { "" } else { "s" }),
+                };
+                self.set_status(format!(
+                    "Cleaned {} merged worktrees{}{}",
+                    total,
+                    skipped_text(skipped, locked),
+                    queued
+                ));
                 self.refresh_all()?;
+                for (path, _, _) in &snapshots {
+                    self.reload_git_info_at(path);
+                }
+                self.offer_remote_cleanup(remotes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove every merged worktree of the project (`c` on its row).
+    fn do_clean_project(&mut self, pi: usize) -> Result<()> {
+        let (path, branch, config) = {
+            let p = &self.workspace.projects[pi];
+            (p.path.clone(), p.default_branch.clone(), p.config.clone())
+        };
+        let report = ops::clean_merged(self.git.as_ref(), &path, &branch, config.as_ref())?;
+        let status = if report.removed.is_empty() {
+            "No merged worktrees to clean".to_string()
+        } else {
+            let names: Vec<&str> = report.removed.iter().map(|c| c.branch.as_str()).collect();
+            format!("Cleaned: {}", names.join(", "))
+        };
+        self.set_status(status + &skipped_text(report.skipped_dirty, report.skipped_locked));
+        self.report_clean_failures(&report.failed);
+        self.refresh_all()?;
+        self.reload_git_info_at(&path);
+        self.offer_remote_cleanup(report.removed.into_iter().filter_map(|c| c.remote).collect());
+        Ok(())
+    }
+
+    /// Merged worktrees git wouldn't remove go to the error banner, first one named.
+    fn report_clean_failures(&mut self, failed: &[(String, String)]) {
+        let Some((branch, error)) = failed.first() else { return };
+        let more = match failed.len() {
+            1 => String::new(),
+            n => format!(" (and {} more)", n - 1),
+        };
+        self.set_error(format!("Couldn't clean '{}': {}{}", branch, error, more));
+    }
+
+    /// Remove a merged worktree (and its sessions), then offer to delete its remote branch.
+    fn do_clean_worktree(&mut self, pi: usize, wi: usize, force: bool) -> Result<()> {
+        let (repo, wt_path, branch, session_names) = {
+            let p = &self.workspace.projects[pi];
+            let wt = &p.worktrees[wi];
+            let names: Vec<String> = wt.sessions.iter().map(|s| s.name.clone()).collect();
+            (p.path.clone(), wt.path.clone(), wt.branch.clone(), names)
+        };
+        let remote = self.git.remote_branch(&repo, &branch);
+        ops::delete_worktree(
+            self.git.as_ref(),
+            self.tmux.as_ref(),
+            &repo,
+            &wt_path,
+            Some(&branch),
+            &session_names,
+            force,
+        )?;
+        self.remove_worktree_row(pi, wi);
+        self.reload_git_info_at(&repo);
+        self.set_status(format!("Cleaned: {}", branch));
+        self.offer_remote_cleanup(remote.into_iter().collect());
+        Ok(())
+    }
+
+    /// After a local clean, ask whether to delete the branches' remote copies too
+    /// (only when `delete_remote_on_clean` is enabled).
+    fn offer_remote_cleanup(&mut self, targets: Vec<RemoteBranch>) {
+        if !self.config.delete_remote_on_clean || targets.is_empty() {
+            return;
+        }
+        let message = match targets.as_slice() {
+            [one] => format!("Also delete {}?", one),
+            many => {
+                let names: Vec<String> = many.iter().map(|t| t.to_string()).collect();
+                format!("Also delete {} remote branches? {}", many.len(), names.join(", "))
+            }
+        };
+        self.mode = Mode::Confirm {
+            message,
+            pending: PendingAction::DeleteRemoteBranches { targets },
+            danger: false,
+        };
+    }
+
+    fn do_delete_remote_branches(&mut self, targets: Vec<RemoteBranch>) -> Result<()> {
+        let mut deleted = 0usize;
+        let mut failed = Vec::new();
+        for t in &targets {
+            match self.git.delete_remote_branch(&t.repo, &t.remote, &t.branch) {
+                Ok(()) => deleted += 1,
+                Err(e) => failed.push(format!("{} ({})", t, e)),
             }
         }
+        self.set_status(match (deleted, failed.is_empty()) {
+            (_, true) => format!(
+                "Deleted {} remote branch{}",
+                deleted,
+                if deleted == 1 { "" } else { "es" }
+            ),
+            (0, false) => format!("Remote delete failed: {}", failed.join(", ")),
+            (_, false) => format!("Deleted {} remote; failed: {}", deleted, failed.join(", ")),
+        });
         Ok(())
     }
 
     fn action_edit(&mut self) -> Result<()> {
         let pi = match self.current_selection() {
-            Selection::Project(pi) | Selection::Worktree(pi, _) | Selection::Session(pi, _, _) => {
-                pi
-            }
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => pi,
             Selection::None => {
                 self.set_status("Select a project or worktree");
                 return Ok(());
             }
         };
-        self.mode = Mode::Config { project_idx: pi };
+        self.mode = Mode::Config { project: self.project_path(pi) };
         Ok(())
     }
 
+    /// Offer to write the aliases `alias.pattern` derives into the config.
+    fn action_materialize_aliases(&mut self, pi: usize) {
+        let Some(project) = self.workspace.projects.get(pi) else { return };
+        let n = project.worktrees.iter().filter(|w| w.alias_derived).count();
+        if n == 0 {
+            self.set_status("No aliases derived from alias.pattern");
+            return;
+        }
+        self.open_layer(Mode::Confirm {
+            danger: false,
+            message: format!(
+                "Save {} alias{} from alias.pattern to the config?",
+                n,
+                if n == 1 { "" } else { "es" }
+            ),
+            pending: PendingAction::MaterializeAliases { project: self.project_path(pi) },
+        });
+    }
+
+    fn action_edit_default_branch(&mut self, pi: usize) {
+        let Some(project) = self.workspace.projects.get(pi) else { return };
+        let branches = self.git.local_branches(&project.path);
+        let mode = Mode::Input {
+            context: InputContext::DefaultBranch { project: project.path.clone() },
+            state: InputState::with_source(
+                "branch (empty: detect): ",
+                project.default_branch.clone(),
+                CompletionSource::Words(branches),
+            ),
+        };
+        self.open_layer(mode);
+    }
+
+    /// Empty `value` drops the override and goes back to the detected branch.
+    fn do_set_default_branch(&mut self, pi: usize, value: String) -> Result<()> {
+        let Some(path) = self.workspace.projects.get(pi).map(|p| p.path.clone()) else {
+            return Ok(());
+        };
+        let branch = Some(value.trim().to_string()).filter(|b| !b.is_empty());
+        if let Some(b) = &branch {
+            if !self.git.branch_exists(&path, b) {
+                self.set_status(format!("No local branch '{}'", b));
+                return Ok(());
+            }
+        }
+        self.config.set_default_branch(&path, branch.clone());
+        if let Some(project) = self.workspace.projects.get_mut(pi) {
+            ops::set_default_branch(self.git.as_ref(), project, branch);
+            let msg = format!("Default branch: {}", project.default_branch);
+            self.set_status(msg);
+        }
+        self.save_config()
+    }
+
     fn active_candidates(&self) -> Vec<usize> {
         self.flat()
             .iter()
@@ -1127,118 +3791,150 @@ impl App {
             .collect()
     }
 
+    /// Local branches not checked out in any worktree — the ones `w` can open.
+    fn free_branches(&self, pi: usize) -> Vec<String> {
+        let Some(project) = self.workspace.projects.get(pi) else {
+            return vec![];
+        };
+        self.git
+            .local_branches(&project.path)
+            .into_iter()
+            .filter(|b| !project.worktrees.iter().any(|w| w.branch == *b))
+            .collect()
+    }
+
+    fn command_input(&self, prompt: &str) -> InputState {
+        InputState::with_source(prompt, String::new(), CompletionSource::History(self.history.entries.clone()))
+    }
+
     fn action_send_command(&mut self) {
-        if let Selection::Session(pi, wi, si) = self.current_selection() {
-            if let Some(sess) = self.workspace.session(pi, wi, si) {
-                let name = sess.name.clone();
-                self.mode = Mode::Input {
-                    context: InputContext::SendCommand { session_name: name },
-                    state: InputState::new("cmd: "),
-                };
-            }
+        if let Some(target) = self.selected_target() {
+            self.mode = Mode::Input {
+                context: InputContext::SendCommand { session_name: target },
+                state: self.command_input("cmd: "),
+            };
         }
     }
 
     fn action_send_ctrl_c(&mut self) -> Result<()> {
-        if let Selection::Session(pi, wi, si) = self.current_selection() {
-            if let Some(sess) = self.workspace.session(pi, wi, si) {
-                session::send_ctrl_c(&sess.name)?;
-            }
+        if let Some(target) = self.selected_target() {
+            self.tmux.send_ctrl_c(&target)?;
         }
         Ok(())
     }
 
-    fn action_next_active(&mut self) {
-        let candidates = self.active_candidates();
-        if candidates.is_empty() {
-            self.set_status("No active sessions");
-            return;
+    /// Ctrl+R: restart the selected session's command, keeping the session itself.
+    fn action_respawn(&mut self) -> Result<()> {
+        let Selection::Session(pi, wi, si) = self.current_selection() else {
+            self.set_status("Select a session to respawn");
+            return Ok(());
+        };
+        let Some(sess) = self.workspace.session(pi, wi, si) else { return Ok(()) };
+        if self.is_own_session(&sess.name) {
+            self.set_status(format!("'{}' is the session wsx runs in — not respawning it", sess.display_name));
+            return Ok(());
         }
-        let next = candidates
-            .iter()
-            .find(|&&i| i > self.tree_selected)
-            .or_else(|| candidates.first())
-            .copied()
-            .unwrap_or(candidates[0]);
-        self.tree_selected = next;
-        self.update_scroll();
+        let wait = RespawnWait {
+            session: sess.name.clone(),
+            display: sess.display_name.clone(),
+            command: sess.origin.init_command.clone(),
+            start_dir: sess.origin.start_dir.clone().unwrap_or_else(|| self.worktree_path(pi, wi)),
+            deadline: Instant::now() + RESPAWN_WAIT,
+        };
+        let result = ops::respawn_session(self.tmux.as_ref(), &wait.session, &wait.start_dir, wait.command.as_deref())?;
+        if result == ops::Respawn::Stopping {
+            self.set_status(format!("Sent Ctrl+C to '{}' — waiting for its shell", wait.display));
+            self.respawning = Some(wait);
+            return Ok(());
+        }
+        self.respawned(&wait, result);
+        Ok(())
     }
 
-    fn attention_candidates(&self) -> Vec<usize> {
-        self.flat()
-            .iter()
-            .enumerate()
-            .filter_map(|(i, entry)| {
-                let FlatEntry::Session {
-                    project_idx: pi,
-                    worktree_idx: wi,
-                    session_idx: si,
-                } = entry
-                else {
-                    return None;
+    /// Each tick of a pending Ctrl+R: re-run the command once the shell is back; past
+    /// `RESPAWN_WAIT`, ask before killing what still runs (once Normal is free to ask).
+    fn poll_respawn(&mut self) {
+        let Some(wait) = &self.respawning else { return };
+        let settled =
+            ops::respawn_settled(self.tmux.as_ref(), &wait.session, &wait.start_dir, wait.command.as_deref());
+        let overdue = Instant::now() >= wait.deadline && matches!(self.mode, Mode::Normal);
+        let wait = match settled {
+            Ok(None) if !overdue => return,
+            _ => self.respawning.take().expect("checked above"),
+        };
+        self.needs_redraw = true;
+        match settled {
+            Ok(Some(result)) => self.respawned(&wait, result),
+            Ok(None) => {
+                let cmd = wait.command.as_deref().map_or("a shell".to_string(), |c| format!("`{}`", c));
+                self.mode = Mode::Confirm {
+                    danger: true,
+                    message: format!(
+                        "'{}' is still running after Ctrl+C. Kill it and start {} over?",
+                        wait.display, cmd
+                    ),
+                    pending: PendingAction::RestartPane {
+                        session: wait.session,
+                        start_dir: wait.start_dir,
+                        command: wait.command,
+                    },
                 };
-                let sess = self.workspace.session(*pi, *wi, *si)?;
-                let currently_active = sess
-                    .last_activity
-                    .map(|t| t.elapsed().as_secs() < IDLE_SECS)
-                    .unwrap_or(false);
-                let needs_attention = !sess.muted
-                    && !currently_active
-                    && sess.has_running_app
-                    && !sess.running_app_suppressed;
-                if needs_attention {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
-            .collect()
+            }
+            Err(e) => self.set_error(format!("Respawn failed: {}", e)),
+        }
     }
 
-    fn action_next_attention(&mut self, dir: isize) {
-        let candidates = self.attention_candidates();
+    fn respawned(&mut self, wait: &RespawnWait, result: ops::Respawn) {
+        let (display, cmd) = (&wait.display, wait.command.as_deref().unwrap_or("a shell"));
+        self.set_status(match result {
+            ops::Respawn::Resent => format!("Respawned '{}': Ctrl+C, then re-ran `{}`", display, cmd),
+            ops::Respawn::Interrupted => format!("Sent Ctrl+C to '{}' — it has no start command to re-run", display),
+            ops::Respawn::Revived => format!("Respawned '{}': its pane had exited, restarted {}", display, cmd),
+            ops::Respawn::Stopping => return,
+        });
+        self.refresh_activity();
+    }
 
+    fn action_next_active(&mut self) {
+        let candidates = self.active_candidates();
         if candidates.is_empty() {
-            self.set_status("No sessions need attention");
+            self.set_status("No active sessions");
             return;
         }
+        let row = tree_nav::next_after(&candidates, self.tree_selected);
+        self.select_row(row);
+    }
 
-        let next = if dir >= 0 {
-            candidates
-                .iter()
-                .find(|&&i| i > self.tree_selected)
-                .or_else(|| candidates.first())
-                .copied()
-                .unwrap()
-        } else {
-            candidates
-                .iter()
-                .rev()
-                .find(|&&i| i < self.tree_selected)
-                .or_else(|| candidates.last())
-                .copied()
-                .unwrap()
-        };
-
-        // ensure parent project + worktree are expanded so the session is visible
-        if let Some(FlatEntry::Session {
-            project_idx: pi,
-            worktree_idx: wi,
-            ..
-        }) = self.flat().get(next).cloned()
-        {
-            self.workspace.projects[pi].expanded = true;
-            self.workspace.projects[pi].worktrees[wi].expanded = true;
-            self.rebuild_flat();
-        }
-
-        self.tree_selected = next;
-        self.update_scroll();
+    /// `n` / `N`: step through sessions needing attention, the most starved first —
+    /// expanding the rows a collapsed one is under.
+    fn action_next_attention(&mut self, dir: isize) {
+        let current = match self.current_selection() {
+            Selection::Session(pi, wi, si) => Some((pi, wi, si)),
+            _ => None,
+        };
+        let Some((pi, wi, si)) = tree_nav::cycle(&tree_nav::attention_ring(&self.workspace), current, dir) else {
+            self.set_status("No sessions need attention");
+            return;
+        };
+        self.select_session(pi, wi, si, None);
     }
 
     fn action_dismiss_attention(&mut self) {
         if let Selection::Session(pi, wi, si) = self.current_selection() {
             if let Some(sess) = self.workspace.session_mut(pi, wi, si) {
+                // One reason per press: the bell, then a finished or quiet app.
+                if sess.attention.contains(&AttentionReason::Bell) {
+                    sess.acknowledge_bell(frecency::now_secs());
+                    self.set_status("Bell acknowledged");
+                    self.cache_writer.save_now(&self.workspace, self.tree_selected);
+                    return;
+                }
+                if sess.attention.contains(&AttentionReason::Finished) {
+                    sess.attention.remove(&AttentionReason::Finished);
+                    sess.track_attention();
+                    self.set_status("Dismissed: finished");
+                    return;
+                }
                 let active = sess
                     .last_activity
                     .map(|t| t.elapsed().as_secs() < IDLE_SECS)
@@ -1246,15 +3942,18 @@ impl App {
                 if active {
                     return;
                 }
-                if sess.has_running_app && !sess.running_app_suppressed {
+                let quiet = sess.attention.iter().any(|r| matches!(r, AttentionReason::RunningAppIdle(_)));
+                let msg = if quiet {
                     sess.running_app_suppressed = true;
-                    self.set_status("Dismissed");
-                    return;
-                }
-                // Idle session — toggle mute
-                sess.muted = !sess.muted;
-                let msg = if sess.muted { "Muted" } else { "Unmuted" };
+                    sess.track_attention();
+                    "Dismissed"
+                } else {
+                    // Idle session — toggle mute
+                    sess.set_muted(!sess.muted);
+                    if sess.muted { "Muted" } else { "Unmuted" }
+                };
                 self.set_status(msg);
+                self.cache_writer.save_now(&self.workspace, self.tree_selected);
                 return;
             }
         }
@@ -1269,23 +3968,25 @@ impl App {
                     .clone()
                     .unwrap_or_default();
                 self.mode = Mode::Input {
-                    context: InputContext::SetAlias {
-                        project_idx: pi,
-                        worktree_idx: wi,
-                    },
-                    state: InputState::with_value("alias: ", current),
+                    context: InputContext::SetAlias { worktree: self.worktree_path(pi, wi) },
+                    state: InputState::with_source(
+                        "alias: ",
+                        current,
+                        CompletionSource::Words(self.alias_suggestions(pi, wi)),
+                    ),
                 };
             }
             Selection::Session(pi, wi, si) => {
-                let current = self.workspace.projects[pi].worktrees[wi].sessions[si]
-                    .display_name
-                    .clone();
+                let sess = &self.workspace.projects[pi].worktrees[wi].sessions[si];
+                // `dev_2` whose `dev` is gone is offered the clean name back.
+                let current = match sess.name_parts() {
+                    (base, Some(_)) if naming::clean_name(&sess.name, |n| self.tmux.session_exists(n)).is_some() => {
+                        base.to_string()
+                    }
+                    _ => sess.display_name.clone(),
+                };
                 self.mode = Mode::Input {
-                    context: InputContext::RenameSession {
-                        project_idx: pi,
-                        worktree_idx: wi,
-                        session_idx: si,
-                    },
+                    context: InputContext::RenameSession { session: self.session_name(pi, wi, si) },
                     state: InputState::with_value("name: ", current),
                 };
             }
@@ -1303,80 +4004,99 @@ impl App {
         if let Mode::Input { context, state } = mode {
             let value = state.value().trim().to_string();
             match context {
+                InputContext::AddProject if git_ops::is_clone_url(&value) => self.action_clone_project(value),
                 InputContext::AddProject => self.do_register_project(ops::expand_path(&value))?,
-                InputContext::AddWorktree { project_idx } => {
+                InputContext::CloneProject { url } => self.start_clone(url, ops::expand_path(&value)),
+                InputContext::RelocateProject { project } => {
+                    let pi = self.resolve_project(&project)?;
+                    self.do_relocate_project(pi, ops::expand_path(&value))?
+                }
+                InputContext::AddWorktree { project } => {
                     if !value.is_empty() {
+                        let p = &self.workspace.projects[self.resolve_project(&project)?];
+                        let proj_config = p.config.clone().unwrap_or_default();
+                        let target = NewWorktree::parse(&value, &p.default_branch);
                         self.mode = Mode::Confirm {
-                            message: format!("Create worktree '{}'?", value),
-                            pending: PendingAction::CreateWorktree {
-                                project_idx,
-                                branch: value,
-                            },
+                            danger: false,
+                            message: ops::create_worktree_message(&p.path, &proj_config, &target),
+                            pending: PendingAction::CreateWorktree { project, branch: value },
                         };
                         return Ok(());
                     }
                 }
-                InputContext::AddSession {
-                    project_idx,
-                    worktree_idx,
-                } => {
-                    // Step 1: got name, now ask for command
-                    self.mode = Mode::Input {
-                        context: InputContext::AddSessionCmd {
-                            project_idx,
-                            worktree_idx,
-                            session_name: value,
-                        },
-                        state: InputState::new("command (optional): "),
-                    };
-                    return Ok(());
-                }
-                InputContext::AddSessionCmd {
-                    project_idx,
-                    worktree_idx,
-                    session_name,
-                } => {
-                    let cmd = if value.is_empty() { None } else { Some(value) };
-                    self.do_create_session(project_idx, worktree_idx, session_name, cmd)?;
-                }
-                InputContext::SetAlias {
-                    project_idx,
-                    worktree_idx,
-                } => {
-                    self.do_apply_alias(project_idx, worktree_idx, value)?;
-                }
-                InputContext::RenameSession {
-                    project_idx,
-                    worktree_idx,
-                    session_idx,
-                } => {
+                InputContext::BranchOff { worktree } => {
                     if !value.is_empty() {
-                        self.do_rename_session(project_idx, worktree_idx, session_idx, value)?;
+                        let (pi, wi) = self.resolve_worktree(&worktree)?;
+                        let p = &self.workspace.projects[pi];
+                        let proj_config = p.config.clone().unwrap_or_default();
+                        let target = NewWorktree::parse(&value, &p.default_branch);
+                        let message = format!(
+                            "{} Takes the uncommitted changes in {} along.",
+                            ops::create_worktree_message(&p.path, &proj_config, &target),
+                            p.worktrees[wi].name
+                        );
+                        self.mode = Mode::Confirm {
+                            danger: false,
+                            message,
+                            pending: PendingAction::BranchOff { worktree, branch: value },
+                        };
+                        return Ok(());
                     }
                 }
-                InputContext::SendCommand { session_name } => {
+                InputContext::StartWork { project } => {
                     if !value.is_empty() {
-                        session::send_keys(&session_name, &value)?;
+                        self.run_git(PendingAction::StartWork { project, input: value })?;
                     }
                 }
-                InputContext::GitPullRebase { project_idx, worktree_idx } => {
-                    if !value.is_empty() {
-                        self.do_git_pull_rebase(project_idx, worktree_idx, value, terminal)?;
-                        return Ok(());
+                InputContext::SetAlias { worktree } => {
+                    let (pi, wi) = self.resolve_worktree(&worktree)?;
+                    self.do_apply_alias(pi, wi, value)?;
+                }
+                InputContext::SetNote { worktree } => {
+                    let (pi, wi) = self.resolve_worktree(&worktree)?;
+                    self.do_set_note(pi, wi, value);
+                }
+                InputContext::LockWorktree { worktree } => {
+                    self.run_git(PendingAction::LockWorktree { worktree, reason: Some(value) })?;
+                }
+                InputContext::LocatePath => self.do_locate_path(&value),
+                InputContext::RenameSession { session } => {
+                    if !value.is_empty() {
+                        let (pi, wi, si) = self.resolve_session(&session)?;
+                        self.do_rename_session(pi, wi, si, value)?;
+                    }
+                }
+                InputContext::SendCommand { session_name } => {
+                    if !value.is_empty() {
+                        self.history.record(&value);
+                        self.tmux.send_keys(&session_name, &value)?;
                     }
                 }
-                InputContext::GitMergeFrom { project_idx, worktree_idx } => {
+                InputContext::GitPullRebase { worktree } => {
                     if !value.is_empty() {
-                        self.do_git_merge_from(project_idx, worktree_idx, value, terminal)?;
+                        let (pi, wi) = self.resolve_worktree(&worktree)?;
+                        self.do_git_op(pi, wi, GitOp::PullRebase(value), terminal)?;
                         return Ok(());
                     }
                 }
-                InputContext::GitMergeInto { project_idx, worktree_idx } => {
+                InputContext::GitMergeFrom { worktree } => {
                     if !value.is_empty() {
-                        self.do_git_merge_into(project_idx, worktree_idx, value, terminal)?;
+                        let (pi, wi) = self.resolve_worktree(&worktree)?;
+                        self.do_git_op(pi, wi, GitOp::MergeFrom(value), terminal)?;
                         return Ok(());
                     }
                 }
+                InputContext::GitMergeInto { worktree } => {
+                    if !value.is_empty() {
+                        let (pi, wi) = self.resolve_worktree(&worktree)?;
+                        self.do_git_op(pi, wi, GitOp::MergeInto(value), terminal)?;
+                        return Ok(());
+                    }
+                }
+                InputContext::DefaultBranch { project } => {
+                    let pi = self.resolve_project(&project)?;
+                    self.do_set_default_branch(pi, value)?;
+                }
             }
         }
         Ok(())
@@ -1384,24 +4104,29 @@ impl App {
 
     fn confirm_action(&mut self, terminal: &mut Tui) -> Result<()> {
         let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        if let Mode::Confirm { pending: PendingAction::AttachSession { session, window_idx }, .. } = mode {
+            // No loading overlay: the terminal is handed to tmux right away.
+            let (pi, wi, si) = self.resolve_session(&session)?;
+            return self.attach_session(pi, wi, si, window_idx, terminal);
+        }
         if let Mode::Confirm { pending, .. } = mode {
             self.loading = true;
             tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
             let result = match pending {
-                PendingAction::DeleteProject { project_idx } => self.do_delete_project(project_idx),
-                PendingAction::DeleteWorktree {
-                    project_idx,
-                    worktree_idx,
-                } => self.do_delete_worktree(project_idx, worktree_idx),
-                PendingAction::DeleteSession {
-                    project_idx,
-                    worktree_idx,
-                    session_idx,
-                } => self.do_delete_session(project_idx, worktree_idx, session_idx),
-                PendingAction::CreateWorktree {
-                    project_idx,
-                    branch,
-                } => self.do_create_worktree(project_idx, branch),
+                // One turn per repository the branches are in.
+                PendingAction::DeleteRemoteBranches { targets } => {
+                    let mut by_repo: Vec<Vec<RemoteBranch>> = Vec::new();
+                    for t in targets {
+                        match by_repo.iter_mut().find(|g| g[0].repo == t.repo) {
+                            Some(group) => group.push(t),
+                            None => by_repo.push(vec![t]),
+                        }
+                    }
+                    by_repo
+                        .into_iter()
+                        .try_for_each(|targets| self.run_git(PendingAction::DeleteRemoteBranches { targets }))
+                }
+                pending => self.run_git(pending),
             };
             self.loading = false;
             result?;
@@ -1409,36 +4134,353 @@ impl App {
         Ok(())
     }
 
+    /// Carry out a confirmed action on whatever its target is now called in the tree.
+    fn run_pending(&mut self, pending: PendingAction) -> Result<()> {
+        match pending {
+            PendingAction::DeleteProject { project } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_delete_project(pi)
+            }
+            PendingAction::DeleteWorktree { worktree, force } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_delete_worktree(pi, wi, force)
+            }
+            PendingAction::KillSessions { names } => self.do_kill_sessions(names),
+            PendingAction::CleanWorktree { worktree, force } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_clean_worktree(pi, wi, force)
+            }
+            PendingAction::CleanProject { project } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_clean_project(pi)
+            }
+            PendingAction::DeleteSession { session } => {
+                let (pi, wi, si) = self.resolve_session(&session)?;
+                self.do_delete_session(pi, wi, si)
+            }
+            PendingAction::CreateWorktree { project, branch } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_create_worktree(pi, branch)
+            }
+            PendingAction::BranchOff { worktree, branch } => {
+                let (pi, _) = self.resolve_worktree(&worktree)?;
+                self.create_worktree_carrying(pi, branch, Some(worktree))
+            }
+            PendingAction::JumpToWorktree { worktree } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.select_worktree(pi, wi);
+                Ok(())
+            }
+            PendingAction::DeleteRemoteBranches { targets } => self.do_delete_remote_branches(targets),
+            PendingAction::AttachSession { .. } => unreachable!("handled in confirm_action"),
+            PendingAction::KillWindow { target, name } => {
+                self.tmux.kill_window(&target)?;
+                self.set_status(format!("Closed window: {}", name));
+                self.refresh_all()
+            }
+            PendingAction::RestartPane { session, start_dir, command } => {
+                let (pi, wi, si) = self.resolve_session(&session)?;
+                ops::restart_pane(self.tmux.as_ref(), &session, &start_dir, command.as_deref())?;
+                let display = self.workspace.projects[pi].worktrees[wi].sessions[si].display_name.clone();
+                self.set_status(format!("Respawned '{}': killed it and started over", display));
+                self.refresh_activity();
+                Ok(())
+            }
+            PendingAction::DropStash { worktree, index } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_stash(pi, wi, StashOp::Drop, index)
+            }
+            PendingAction::ContinueOperation { worktree } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_continue_operation(pi, wi);
+                Ok(())
+            }
+            PendingAction::RegisterProject { path } => self.do_register_project(path),
+            PendingAction::MaterializeAliases { project } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_materialize_aliases(pi)
+            }
+            PendingAction::ArchiveWorktree { worktree } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_archive_worktree(pi, wi)
+            }
+            PendingAction::RestoreArchived { project, tag } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_restore_archived(pi, tag)
+            }
+            PendingAction::PurgeArchived { project, tag } => {
+                let pi = self.resolve_project(&project)?;
+                self.do_purge_archived(pi, tag)
+            }
+            PendingAction::GitOp { worktree, op } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.run_git_op(pi, wi, op);
+                Ok(())
+            }
+            PendingAction::Stash { worktree, op, index } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_stash(pi, wi, op, index)
+            }
+            PendingAction::MarkResolved { worktree, file, row } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_mark_resolved(pi, wi, &file, row);
+                Ok(())
+            }
+            PendingAction::StartWork { project, input } => {
+                let pi = self.resolve_project(&project)?;
+                self.start_work(pi, &input);
+                Ok(())
+            }
+            PendingAction::Adopt { worktree, dest, alias, session } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                self.do_adopt(pi, wi, dest, alias, session)
+            }
+            PendingAction::LockWorktree { worktree, reason } => {
+                let (pi, wi) = self.resolve_worktree(&worktree)?;
+                match reason {
+                    Some(reason) => self.do_lock_worktree(pi, wi, reason),
+                    None => self.do_unlock_worktree(pi, wi),
+                }
+            }
+        }
+    }
+
     // ── Dispatch to ops ───────────────────────────────────────────────────────
 
     fn do_register_project(&mut self, path: PathBuf) -> Result<()> {
-        let project = ops::register_project(path, &mut self.config)?;
+        let project = match ops::register_project(self.git.as_ref(), path, &mut self.config) {
+            Ok(project) => project,
+            Err(e) => {
+                match e.downcast_ref() {
+                    Some(RegisterProjectError::AlreadyRegistered { name, path }) => {
+                        self.set_status(format!("Already registered as '{}'", name));
+                        self.select_project_at(path);
+                    }
+                    Some(RegisterProjectError::LinkedWorktree { path, main }) => {
+                        let registered =
+                            self.config.projects.iter().find(|p| ops::same_dir(&p.path, main));
+                        if let Some(entry) = registered {
+                            let (name, main) = (entry.name.clone(), entry.path.clone());
+                            self.set_status(format!(
+                                "{} is a worktree of '{}' — it's listed there",
+                                display_path(path, false, usize::MAX),
+                                name
+                            ));
+                            match self.workspace.find_worktree(path) {
+                                Some((pi, wi)) => self.select_worktree(pi, wi),
+                                None => self.select_project_at(&main),
+                            }
+                        } else {
+                            self.mode = Mode::Confirm {
+                                message: format!("{} — register the main repo?", e),
+                                pending: PendingAction::RegisterProject { path: main.clone() },
+                                danger: false,
+                            };
+                        }
+                    }
+                    None => return Err(e),
+                }
+                return Ok(());
+            }
+        };
+        let pattern_error = project.config.as_ref().and_then(|c| c.alias_pattern_error.clone());
         self.workspace.projects.push(project);
         self.rebuild_flat();
-        self.config.save()?;
-        self.set_status("Project registered");
+        self.save_config()?;
+        match pattern_error {
+            Some(err) => self.set_status(format!("Project registered — {}", err)),
+            None => self.set_status("Project registered"),
+        }
+        Ok(())
+    }
+
+    fn do_relocate_project(&mut self, pi: usize, path: PathBuf) -> Result<()> {
+        let Some(project) = self.workspace.projects.get_mut(pi) else { return Ok(()) };
+        let old = project.path.clone();
+        if let Err(e) = ops::relocate_project(self.git.as_ref(), &mut self.config, project, path) {
+            if let Some(RegisterProjectError::AlreadyRegistered { name, .. }) = e.downcast_ref() {
+                self.set_status(format!("That repository is already registered as '{}'", name));
+                return Ok(());
+            }
+            return Err(e);
+        }
+        self.cache_writer.relocate(&old, project);
+        let msg = format!("'{}' now at {}", project.name, display_path(&project.path, false, usize::MAX));
+        self.rebuild_flat();
+        self.save_config()?;
+        self.set_status(msg);
         Ok(())
     }
 
-    fn do_create_worktree(&mut self, pi: usize, branch: String) -> Result<()> {
-        let (repo_path, default_branch, proj_config) = {
+    /// `input` is what was typed at the branch prompt; `@ref` makes a detached worktree.
+    fn do_create_worktree(&mut self, pi: usize, input: String) -> Result<()> {
+        self.create_worktree_carrying(pi, input, None)
+    }
+
+    /// `do_create_worktree`, moving the uncommitted changes in `carry_from` to the new
+    /// worktree before env files are copied and postCreate runs. Changes that don't apply
+    /// stay in a stash, and the error banner says which.
+    fn create_worktree_carrying(&mut self, pi: usize, input: String, carry_from: Option<PathBuf>) -> Result<()> {
+        let (repo_path, target, proj_config) = {
             let p = &self.workspace.projects[pi];
             (
                 p.path.clone(),
-                p.default_branch.clone(),
+                NewWorktree::parse(&input, &p.default_branch),
                 p.config.clone().unwrap_or_default(),
             )
         };
-        let (_wt_path, warning) =
-            ops::create_worktree(&repo_path, &default_branch, &proj_config, &branch)?;
-        if let Some(w) = warning {
-            self.set_status(w);
+        let branch = target.name().to_string();
+        let mut created =
+            match ops::create_worktree(self.git.as_ref(), &repo_path, &proj_config, &target, carry_from.as_deref()) {
+                Ok(created) => created,
+                Err(e) => {
+                    if let Some(CreateWorktreeError::AlreadyCheckedOut { path, .. }) =
+                        e.downcast_ref()
+                    {
+                        if self.workspace.projects[pi].worktrees.iter().any(|w| &w.path == path) {
+                            self.mode = Mode::Confirm {
+                                message: format!(
+                                    "Branch '{}' is already checked out at {} — jump there instead?",
+                                    branch,
+                                    display_path(path, false, usize::MAX)
+                                ),
+                                pending: PendingAction::JumpToWorktree { worktree: path.clone() },
+                                danger: false,
+                            };
+                            return Ok(());
+                        }
+                    }
+                    return Err(e);
+                }
+            };
+        let mut done = format!("Created worktree: {}", branch);
+        if let (Some(from), Some(carried)) = (&carry_from, created.carried.take()) {
+            let name = from.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            match carried {
+                Ok(files) => done = format!("{} with {} uncommitted file(s) from {}", done, files, name),
+                Err(e) => self.set_error(format!("Branch off from {}: {}", name, e)),
+            }
         }
+        self.bump_frecency(pi);
         self.refresh_all()?;
-        self.set_status(format!("Created worktree: {}", branch));
+        if let Some((pi, wi)) = carry_from.and_then(|from| self.workspace.find_worktree(&from)) {
+            self.reload_git_info(pi, wi);
+        }
+        if let Some((pi, wi)) = self.workspace.find_worktree(&created.path) {
+            self.select_worktree(pi, wi);
+            self.reload_git_info(pi, wi);
+        }
+        if let Some(cmd) = created.post_create.take() {
+            self.start_post_create(branch, cmd, created, PostCreateFor::Create);
+            return Ok(());
+        }
+        self.record_setup(&created.path, WorktreeSetup { env_files: env_files(&created.copy), hook: None });
+        let copy = &created.copy;
+        if copy.copied.len() + copy.rendered.len() + copy.skipped.len() > COPY_LIST_MAX {
+            self.mode = Mode::GitOutput {
+                title: format!("Env files for {}", branch),
+                text: copy_report(copy),
+                scroll: 0,
+            };
+        }
+        self.set_status(created_status(done, &created));
+        Ok(())
+    }
+
+    /// Attach the session `attach_next` holds, once whatever made it is done; the status
+    /// waits for the detach, since hook and copy warnings weren't visible until then.
+    fn attach_next(&mut self, terminal: &mut Tui) -> Result<()> {
+        let Some((session, status)) = self.attach_next.take() else { return Ok(()) };
+        let (pi, wi, si) = self.resolve_session(&session)?;
+        if let Err(e) = self.attach_session(pi, wi, si, None, terminal) {
+            self.set_status(format!("{} — but attaching failed: {}", status, e));
+            return Ok(());
+        }
+        self.set_status(status);
         Ok(())
     }
 
+    /// `Space`: create the worktree, then a `dev` session in it running `session.defaultCommand`
+    /// for `attach_next` to attach — once postCreate is done, when the project has one. A
+    /// step that fails stops there, and the status says what exists.
+    fn start_work(&mut self, pi: usize, input: &str) {
+        let (repo_path, target, proj_config) = {
+            let p = &self.workspace.projects[pi];
+            (p.path.clone(), NewWorktree::parse(input, &p.default_branch), p.config.clone().unwrap_or_default())
+        };
+        let branch = target.name().to_string();
+        let mut created = match ops::create_worktree(self.git.as_ref(), &repo_path, &proj_config, &target, None) {
+            Ok(created) => created,
+            Err(e) => {
+                self.set_status(format!("Start work: no worktree for '{}': {}", branch, e));
+                return;
+            }
+        };
+        self.bump_frecency(pi);
+        if let Some(cmd) = created.post_create.take() {
+            if let Some((pi, wi)) = self.refresh_all().ok().and(self.workspace.find_worktree(&created.path)) {
+                self.select_worktree(pi, wi);
+                self.reload_git_info(pi, wi);
+            }
+            self.start_post_create(branch, cmd, created, PostCreateFor::StartWork);
+            return;
+        }
+        self.record_setup(&created.path, WorktreeSetup { env_files: env_files(&created.copy), hook: None });
+        self.start_dev_session(&branch, &created);
+    }
+
+    /// The rest of `start_work`, for the worktree it `created`.
+    fn start_dev_session(&mut self, branch: &str, created: &ops::CreatedWorktree) {
+        let made = format!("Created worktree {}", branch);
+        let placed = self.refresh_all().ok().and(self.workspace.find_worktree(&created.path));
+        let Some((pi, wi)) = placed else {
+            self.set_status(format!("{}, but it isn't listed yet — press R", made));
+            return;
+        };
+        self.select_worktree(pi, wi);
+        self.reload_git_info(pi, wi);
+
+        let (proj_name, session_base, env, command) = {
+            let p = &self.workspace.projects[pi];
+            let wt = &p.worktrees[wi];
+            let command = p.config.as_ref().and_then(|c| c.default_command.clone());
+            (p.name.clone(), self.workspace.session_base(pi, wi), ops::session_env(p.config.as_ref(), &wt.path, &wt.branch), command)
+        };
+        let session = ops::create_session(
+            self.tmux.as_ref(),
+            &proj_name,
+            &session_base,
+            &created.path,
+            Some("dev".into()),
+            command,
+            &env,
+        );
+        let tmux_name = match session {
+            Ok((tmux_name, _)) => {
+                self.apply_tmux_defaults(&tmux_name);
+                tmux_name
+            }
+            Err(e) => {
+                self.set_status(format!("{}, but the session failed: {}", made, e));
+                return;
+            }
+        };
+        let placed = self.refresh_all().ok().and(self.workspace.find_session(&tmux_name));
+        let Some((pi, wi, si)) = placed else {
+            self.set_status(format!("{} and session {}, but it isn't listed yet — press R", made, tmux_name));
+            return;
+        };
+        self.select_session(pi, wi, si, None);
+        self.attach_next = Some((tmux_name, created_status(format!("Started work on {}", branch), created)));
+    }
+
+    /// `tmux_defaults` on a session wsx just made; what tmux refuses waits in
+    /// `rejected_options` for the next session-created status.
+    fn apply_tmux_defaults(&mut self, session: &str) {
+        let rejected = self.tmux.set_options(session, &self.config.tmux_defaults());
+        self.rejected_options.record(rejected);
+    }
+
     fn do_create_session(
         &mut self,
         pi: usize,
@@ -1446,39 +4488,191 @@ impl App {
         session_name: String,
         command: Option<String>,
     ) -> Result<()> {
-        let (proj_name, wt_path, wt_slug) = {
+        let (proj_name, wt_path, session_base, env) = {
             let p = &self.workspace.projects[pi];
             let wt = &p.worktrees[wi];
-            (p.name.clone(), wt.path.clone(), wt.session_slug(&p.name))
+            let env = ops::session_env(p.config.as_ref(), &wt.path, &wt.branch);
+            (p.name.clone(), wt.path.clone(), self.workspace.session_base(pi, wi), env)
         };
+        if let Some(window_name) = session_name.strip_prefix('+') {
+            let wt = &self.workspace.projects[pi].worktrees[wi];
+            let primary = self.workspace.session_order(wt).first().map(|&si| &wt.sessions[si]);
+            if let Some(primary) = primary.map(|s| (s.name.clone(), s.display_name.clone())) {
+                return self.do_create_window(pi, wi, primary, window_name, command);
+            }
+            // No session to add a window to yet — the window becomes the first session.
+            return self.do_create_session(pi, wi, window_name.to_string(), command);
+        }
         let explicit_name = if session_name.is_empty() {
             None
         } else {
             Some(session_name)
         };
-        let (_tmux_name, display_name) =
-            ops::create_session(&proj_name, &wt_slug, &wt_path, explicit_name, command)?;
-        self.set_status(format!("Session '{}' created", display_name));
+        let (tmux_name, display_name) =
+            ops::create_session(
+            self.tmux.as_ref(),
+            &proj_name,
+            &session_base,
+            &wt_path,
+            explicit_name,
+            command,
+            &env,
+        )?;
+        self.apply_tmux_defaults(&tmux_name);
+        let rejected = self.rejected_options.take_unreported();
+        if rejected.is_empty() {
+            self.set_status(format!("Session '{}' created", display_name));
+        } else {
+            self.set_status(format!(
+                "Session '{}' created; tmux rejected tmux_defaults: {}",
+                display_name,
+                rejected.join(", ")
+            ));
+        }
         self.refresh_all()?;
-        // Auto-expand the worktree so the new session is visible
-        if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
-            wt.expanded = true;
+        if let Some((pi, wi, si)) = self.workspace.find_session(&tmux_name) {
+            self.select_session(pi, wi, si, None);
+        }
+        Ok(())
+    }
+
+    /// New window in the worktree's primary (first) session.
+    fn do_create_window(
+        &mut self,
+        pi: usize,
+        wi: usize,
+        (session_name, session_display): (String, String),
+        window_name: &str,
+        command: Option<String>,
+    ) -> Result<()> {
+        let wt_path = self.workspace.projects[pi].worktrees[wi].path.clone();
+        let index = ops::create_window(self.tmux.as_ref(), &session_name, &wt_path, window_name, command)?;
+        self.set_status(format!("Window {} added to '{}'", index, session_display));
+        self.refresh_all()?;
+        if let Some((pi, wi, si)) = self.workspace.find_session(&session_name) {
+            let window = self.workspace.projects[pi].worktrees[wi].sessions[si]
+                .windows
+                .iter()
+                .position(|w| w.index == index);
+            self.select_session(pi, wi, si, window);
         }
         Ok(())
     }
 
-    fn do_delete_worktree(&mut self, pi: usize, wi: usize) -> Result<()> {
-        let (repo, path, branch, session_names) = {
+    fn do_delete_worktree(&mut self, pi: usize, wi: usize, force: bool) -> Result<()> {
+        let (repo, path, branch, detached, session_names) = {
             let p = &self.workspace.projects[pi];
             let wt = &p.worktrees[wi];
             let names: Vec<String> = wt.sessions.iter().map(|s| s.name.clone()).collect();
-            (p.path.clone(), wt.path.clone(), wt.branch.clone(), names)
+            (p.path.clone(), wt.path.clone(), wt.branch.clone(), wt.detached, names)
+        };
+        ops::delete_worktree(
+            self.git.as_ref(),
+            self.tmux.as_ref(),
+            &repo,
+            &path,
+            (!detached).then_some(branch.as_str()),
+            &session_names,
+            force,
+        )?;
+        self.remove_worktree_row(pi, wi);
+        self.set_status(format!("Deleted: {}", branch));
+        Ok(())
+    }
+
+    fn do_archive_worktree(&mut self, pi: usize, wi: usize) -> Result<()> {
+        let (repo, path, branch, session_names, display_names) = {
+            let p = &self.workspace.projects[pi];
+            let wt = &p.worktrees[wi];
+            let names: Vec<String> = wt.sessions.iter().map(|s| s.name.clone()).collect();
+            let display: Vec<String> =
+                self.workspace.session_order(wt).iter().map(|&si| wt.sessions[si].display_name.clone()).collect();
+            (p.path.clone(), wt.path.clone(), wt.branch.clone(), names, display)
+        };
+        let (tag, warning) = ops::archive_worktree(
+            self.git.as_ref(),
+            self.tmux.as_ref(),
+            &repo,
+            &path,
+            &branch,
+            &session_names,
+        )?;
+        let project = &mut self.workspace.projects[pi];
+        ops::reload_archived(self.git.as_ref(), project);
+        if let Some(archived) = project.archived.iter_mut().find(|a| a.tag == tag) {
+            archived.sessions = display_names;
+        }
+        self.remove_worktree_row(pi, wi);
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+        self.set_status(warning.unwrap_or_else(|| format!("Archived: {} as {}", branch, tag)));
+        Ok(())
+    }
+
+    fn do_restore_archived(&mut self, pi: usize, tag: String) -> Result<()> {
+        let Some(archived) = self.workspace.projects[pi].archived.iter().find(|a| a.tag == tag).cloned() else {
+            self.stale_selection();
+            return Ok(());
+        };
+        let (repo, proj_config) = {
+            let p = &self.workspace.projects[pi];
+            (p.path.clone(), p.config.clone().unwrap_or_default())
+        };
+        let mut created = ops::restore_archived(self.git.as_ref(), &repo, &proj_config, &archived)?;
+        self.refresh_all()?;
+        let Some((pi, wi)) = self.workspace.find_worktree(&created.path) else {
+            self.set_status(format!("Restored: {} — but it isn't listed yet", archived.branch));
+            return Ok(());
+        };
+        let (proj_name, session_base, env) = {
+            let p = &self.workspace.projects[pi];
+            let wt = &p.worktrees[wi];
+            (p.name.clone(), self.workspace.session_base(pi, wi), ops::session_env(p.config.as_ref(), &wt.path, &wt.branch))
         };
-        ops::delete_worktree(&repo, &path, &branch, &session_names)?;
-        self.workspace.projects[pi].worktrees.remove(wi);
+        let mut failed = 0;
+        for name in &archived.sessions {
+            let made = ops::create_session(
+                self.tmux.as_ref(),
+                &proj_name,
+                &session_base,
+                &created.path,
+                Some(name.clone()),
+                None,
+                &env,
+            );
+            match made {
+                Ok((tmux_name, _)) => self.apply_tmux_defaults(&tmux_name),
+                Err(_) => failed += 1,
+            }
+        }
+        if !archived.sessions.is_empty() {
+            self.refresh_all()?;
+        }
+        self.select_worktree(pi, wi);
+        self.reload_git_info(pi, wi);
+        if failed > 0 {
+            created.warning = Some(format!(
+                "Warning: {} of {} sessions couldn't be made",
+                failed,
+                archived.sessions.len()
+            ));
+        }
+        if let Some(cmd) = created.post_create.take() {
+            self.start_post_create(archived.branch, cmd, created, PostCreateFor::Create);
+            return Ok(());
+        }
+        self.record_setup(&created.path, WorktreeSetup { env_files: env_files(&created.copy), hook: None });
+        self.set_status(created_status(format!("Restored worktree: {}", archived.branch), &created));
+        Ok(())
+    }
+
+    fn do_purge_archived(&mut self, pi: usize, tag: String) -> Result<()> {
+        let repo = self.workspace.projects[pi].path.clone();
+        self.git.delete_tag(&repo, &tag)?;
+        self.workspace.projects[pi].archived.retain(|a| a.tag != tag);
         self.rebuild_flat();
         self.clamp_selected();
-        self.set_status(format!("Deleted: {}", branch));
+        self.cache_writer.save_now(&self.workspace, self.tree_selected);
+        self.set_status(format!("Purged: {}", tag));
         Ok(())
     }
 
@@ -1490,7 +4684,7 @@ impl App {
         self.workspace.projects.remove(pi);
         self.rebuild_flat();
         ops::unregister_project(&path, &mut self.config);
-        self.config.save()?;
+        self.save_config()?;
         self.clamp_selected();
         self.set_status(format!("Unregistered: {}", name));
         Ok(())
@@ -1500,31 +4694,98 @@ impl App {
         let sess = &self.workspace.projects[pi].worktrees[wi].sessions[si];
         let tmux_name = sess.name.clone();
         let display_name = sess.display_name.clone();
-        ops::delete_session(&tmux_name)?;
+        ops::delete_session(self.tmux.as_ref(), &tmux_name)?;
         self.workspace.projects[pi].worktrees[wi]
             .sessions
             .remove(si);
         self.rebuild_flat();
         self.clamp_selected();
-        self.set_status(format!("Killed session: {}", display_name));
+        // The lowest `{name}_N` left behind can have the name back.
+        let heir = self.workspace.projects[pi].worktrees[wi]
+            .sessions
+            .iter()
+            .filter(|s| naming::split_suffix(&s.name).0 == tmux_name)
+            .filter_map(|s| s.name_parts().1.map(|n| (n, s.shown_name())))
+            .min();
+        self.set_status(match heir {
+            Some((_, heir)) => format!("Killed session: {} — r on '{}' gives it the name back", display_name, heir),
+            None => format!("Killed session: {}", display_name),
+        });
+        Ok(())
+    }
+
+    fn do_kill_sessions(&mut self, names: Vec<String>) -> Result<()> {
+        let killed = names.iter().filter(|n| ops::delete_session(self.tmux.as_ref(), n).is_ok()).count();
+        self.refresh_all()?;
+        self.set_status(format!("Killed {} stale session{}", killed, if killed == 1 { "" } else { "s" }));
+        Ok(())
+    }
+
+    /// Aliases used anywhere in the config, for consistent naming across projects —
+    /// minus those that would clash inside project `pi`.
+    fn alias_suggestions(&self, pi: usize, wi: usize) -> Vec<String> {
+        let project = &self.workspace.projects[pi];
+        let mut aliases: Vec<String> = self
+            .config
+            .projects
+            .iter()
+            .flat_map(|p| p.aliases.values().cloned())
+            .filter(|a| project.alias_conflict(wi, a).is_none())
+            .collect();
+        aliases.sort();
+        aliases.dedup();
+        aliases
+    }
+
+    fn do_materialize_aliases(&mut self, pi: usize) -> Result<()> {
+        let Some(project) = self.workspace.projects.get_mut(pi) else { return Ok(()) };
+        let mut n = 0;
+        for wt in project.worktrees.iter_mut().filter(|w| w.alias_derived) {
+            if let Some(alias) = &wt.alias {
+                ops::set_alias(&mut self.config, &project.path, &wt.branch, alias);
+                wt.alias_derived = false;
+                n += 1;
+            }
+        }
+        self.save_config()?;
+        self.set_status(format!("Saved {} alias{}", n, if n == 1 { "" } else { "es" }));
         Ok(())
     }
 
     fn do_apply_alias(&mut self, pi: usize, wi: usize, alias: String) -> Result<()> {
         let branch = self.workspace.projects[pi].worktrees[wi].branch.clone();
         let proj_path = self.workspace.projects[pi].path.clone();
+        if !alias.is_empty() {
+            if let Some(other) = self.workspace.projects[pi].alias_conflict(wi, &alias) {
+                self.set_status(format!(
+                    "Alias '{}' clashes with worktree '{}' — pick another",
+                    alias,
+                    other.display_name()
+                ));
+                return Ok(());
+            }
+        }
 
         ops::set_alias(&mut self.config, &proj_path, &branch, &alias);
-        self.config.save()?;
+        self.save_config()?;
 
-        let new_alias = if alias.is_empty() {
-            None
-        } else {
-            Some(alias.clone())
-        };
+        // Clearing falls back to what alias.pattern makes of the branch.
+        let aliases = self
+            .config
+            .projects
+            .iter()
+            .find(|e| e.path == proj_path)
+            .map(|e| e.aliases.clone())
+            .unwrap_or_default();
+        let (new_alias, derived) = crate::git::worktree::effective_alias(
+            &aliases,
+            self.workspace.projects[pi].config.as_ref(),
+            &branch,
+        );
 
         let wt = &mut self.workspace.projects[pi].worktrees[wi];
         wt.alias = new_alias;
+        wt.alias_derived = derived;
 
         self.set_status(if alias.is_empty() {
             format!("Alias cleared for '{}'", branch)
@@ -1544,10 +4805,12 @@ impl App {
         let old_tmux_name = self.workspace.projects[pi].worktrees[wi].sessions[si]
             .name
             .clone();
-        let proj_name = self.workspace.projects[pi].name.clone();
-        let wt_slug = self.workspace.projects[pi].worktrees[wi].session_slug(&proj_name);
-        let new_tmux_name = format!("{}-{}-{}", proj_name, wt_slug, new_name);
-        ops::rename_session(&old_tmux_name, &new_tmux_name)?;
+        let new_tmux_name = format!("{}-{}", self.workspace.session_base(pi, wi), new_name);
+        ops::rename_session(self.tmux.as_ref(), &old_tmux_name, &new_tmux_name)?;
+        if self.is_own_session(&old_tmux_name) {
+            self.own_session = Some(new_tmux_name.clone());
+        }
+        self.workspace.attaches.rename(&old_tmux_name, &new_tmux_name);
         let sess = &mut self.workspace.projects[pi].worktrees[wi].sessions[si];
         sess.name = new_tmux_name;
         sess.display_name = new_name.clone();
@@ -1557,43 +4820,46 @@ impl App {
 
     // ── Move project ──────────────────────────────────────────────────────────
 
-    fn action_enter_move(&mut self) {
+    fn action_enter_move(&mut self) -> Result<()> {
+        if matches!(self.current_selection(), Selection::Project(_))
+            && self.config.project_order == ProjectOrder::Frecency
+        {
+            // Move edits the manual order, so show it.
+            self.config.project_order = ProjectOrder::Manual;
+            self.save_config()?;
+        }
+        if matches!(self.current_selection(), Selection::Session(..))
+            && self.config.session_order == SessionOrder::Recent
+        {
+            ops::adopt_recent_order(&mut self.workspace);
+            self.workspace.recent_sessions = false;
+            self.config.session_order = SessionOrder::Manual;
+            self.save_config()?;
+        }
         match self.current_selection() {
             Selection::Project(pi) => {
-                self.mode = Mode::Move { project_idx: pi };
+                self.mode = Mode::Move { project: self.project_path(pi) };
                 self.set_status("MOVE: j/k to reorder  Enter/Esc to confirm");
             }
             Selection::Session(pi, wi, si) => {
-                self.mode = Mode::MoveSession {
-                    project_idx: pi,
-                    worktree_idx: wi,
-                    session_idx: si,
-                };
+                self.mode = Mode::MoveSession { session: self.session_name(pi, wi, si) };
                 self.set_status("MOVE: j/k to reorder  Enter/Esc to confirm");
             }
             _ => self.set_status("Select a project or session to move"),
         }
+        Ok(())
     }
 
     fn move_project(&mut self, pi: usize, delta: isize) {
         let new_pi = (pi as isize + delta) as usize;
         let len = self.workspace.projects.len();
-        if new_pi >= len {
+        if pi >= len || new_pi >= len {
             return;
         }
         self.workspace.projects.swap(pi, new_pi);
-        self.mode = Mode::Move {
-            project_idx: new_pi,
-        };
         self.rebuild_flat();
-        if let Some(pos) = self
-            .flat()
-            .iter()
-            .position(|e| matches!(e, FlatEntry::Project { idx } if *idx == new_pi))
-        {
-            self.tree_selected = pos;
-            self.update_scroll();
-        }
+        let row = tree_nav::row_of(self.flat(), &FlatEntry::Project { idx: new_pi });
+        self.select_row(row);
     }
 
     fn move_project_down(&mut self, pi: usize) {
@@ -1608,26 +4874,25 @@ impl App {
         }
     }
 
+    /// Pins come first, so a session only trades places with its nearest
+    /// neighbour of the same pin state — manual order applies within each group.
     fn move_session(&mut self, pi: usize, wi: usize, si: usize, delta: isize) {
-        let new_si = (si as isize + delta) as usize;
-        let sessions = &mut self.workspace.projects[pi].worktrees[wi].sessions;
-        if new_si >= sessions.len() {
+        let Some(wt) = self.workspace.worktree_mut(pi, wi) else { return };
+        let sessions = &mut wt.sessions;
+        if si >= sessions.len() {
             return;
         }
-        sessions.swap(si, new_si);
-        self.mode = Mode::MoveSession {
-            project_idx: pi,
-            worktree_idx: wi,
-            session_idx: new_si,
+        let same_group = |i: &usize| sessions[*i].pinned == sessions[si].pinned;
+        let neighbour = if delta < 0 {
+            (0..si).rev().find(same_group)
+        } else {
+            (si + 1..sessions.len()).find(same_group)
         };
+        let Some(new_si) = neighbour else { return };
+        sessions.swap(si, new_si);
         self.rebuild_flat();
-        if let Some(pos) = self.flat().iter().position(|e| {
-            matches!(e, FlatEntry::Session { project_idx: p, worktree_idx: w, session_idx: s }
-                if *p == pi && *w == wi && *s == new_si)
-        }) {
-            self.tree_selected = pos;
-            self.update_scroll();
-        }
+        let row = tree_nav::row_of(self.flat(), &FlatEntry::Session { project_idx: pi, worktree_idx: wi, session_idx: new_si });
+        self.select_row(row);
     }
 
     fn sync_config_project_order(&mut self) {
@@ -1648,6 +4913,147 @@ impl App {
 
     // ── Git popup ─────────────────────────────────────────────────────────────
 
+    fn spawn_fetch(&mut self, path: PathBuf) {
+        self.fetch_pending.insert(path.clone());
+        let tx = self.fetch_tx.clone();
+        let git = Arc::clone(&self.git);
+        let (queue, repo) = (Arc::clone(&self.repo_queue), self.repo_of(&path));
+        std::thread::spawn(move || {
+            let ok = queue.acquire(&repo, "fetch", QUEUE_TIMEOUT).is_some_and(|_turn| git.fetch(&path));
+            let _ = tx.send((path, ok));
+        });
+    }
+
+    /// The repository a worktree belongs to: its project's path, what `repo_queue` keys on.
+    fn repo_of(&self, worktree: &Path) -> PathBuf {
+        match self.workspace.find_worktree(worktree) {
+            Some((pi, _)) => self.project_path(pi),
+            None => worktree.to_path_buf(),
+        }
+    }
+
+    /// `repo`'s turn if it's free right now and none of wsx's own operations wait for it.
+    fn turn_now(&self, repo: &Path, label: &str) -> Option<RepoTurn> {
+        if self.git_queue.iter().any(|q| q.repo == repo) {
+            return None;
+        }
+        self.repo_queue.acquire(repo, label, Duration::ZERO)
+    }
+
+    /// Run `pending` holding its repository's turn: now if the repository is free, else
+    /// from `tick` once the operations ahead of it are done. The rest just run.
+    fn run_git(&mut self, pending: PendingAction) -> Result<()> {
+        let Some((repo, label)) = self.pending_repo(&pending) else {
+            return self.run_pending(pending);
+        };
+        match self.turn_now(&repo, label) {
+            Some(_turn) => self.run_pending(pending),
+            None => {
+                self.queue_git(repo, label, pending);
+                Ok(())
+            }
+        }
+    }
+
+    /// Put `pending` behind what's busy with `repo`, closing the popup it came from —
+    /// an operation that reopens one does so when it runs.
+    fn queue_git(&mut self, repo: PathBuf, label: &'static str, pending: PendingAction) {
+        let ahead = self.repo_queue.ahead(&repo) + self.git_queue.iter().filter(|q| q.repo == repo).count();
+        self.set_status(format!(
+            "{}: queued behind {} git operation{} on {}",
+            label,
+            ahead,
+            if ahead == 1 { "" } else { "s" },
+            display_path(&repo, false, usize::MAX)
+        ));
+        self.git_queue.push_back(QueuedGit { repo, label, pending, since: Instant::now() });
+        self.mode = Mode::Normal;
+    }
+
+    /// How many of wsx's own git operations wait for a busy repository.
+    pub fn queued_git(&self) -> usize {
+        self.git_queue.len()
+    }
+
+    /// `tick`: start queued operations whose repository came free, oldest first, while
+    /// no popup is open for them to disturb. One still waiting after `QUEUE_TIMEOUT` is
+    /// dropped with a status.
+    fn poll_git_queue(&mut self) {
+        let mut blocked: Vec<PathBuf> = Vec::new();
+        let mut i = 0;
+        while i < self.git_queue.len() && matches!(self.mode, Mode::Normal) {
+            let (repo, label) = (self.git_queue[i].repo.clone(), self.git_queue[i].label);
+            if blocked.contains(&repo) {
+                i += 1;
+                continue;
+            }
+            let turn = self.repo_queue.acquire(&repo, label, Duration::ZERO);
+            if turn.is_none() && self.git_queue[i].since.elapsed() < QUEUE_TIMEOUT {
+                blocked.push(repo);
+                i += 1;
+                continue;
+            }
+            let queued = self.git_queue.remove(i).expect("in bounds");
+            self.needs_redraw = true;
+            if turn.is_none() {
+                self.set_status(format!(
+                    "{} is still busy with another git operation — {} dropped, try again",
+                    display_path(&repo, false, usize::MAX),
+                    label
+                ));
+            } else if let Err(e) = self.run_pending(queued.pending) {
+                self.set_error(format!("{} failed: {}", label, e));
+            }
+        }
+    }
+
+    /// Which repository a confirmed action runs git in, and what to call it in the queue.
+    fn pending_repo(&self, pending: &PendingAction) -> Option<(PathBuf, &'static str)> {
+        match pending {
+            PendingAction::DeleteWorktree { worktree, .. } => Some((self.repo_of(worktree), "remove worktree")),
+            PendingAction::CleanWorktree { worktree, .. } => Some((self.repo_of(worktree), "clean")),
+            PendingAction::CleanProject { project } => Some((project.clone(), "clean")),
+            PendingAction::DropStash { worktree, .. } => Some((self.repo_of(worktree), "drop stash")),
+            PendingAction::ContinueOperation { worktree } => Some((self.repo_of(worktree), "continue")),
+            PendingAction::ArchiveWorktree { worktree } => Some((self.repo_of(worktree), "archive")),
+            PendingAction::CreateWorktree { project, .. } => Some((project.clone(), "add worktree")),
+            PendingAction::BranchOff { worktree, .. } => Some((self.repo_of(worktree), "branch off")),
+            PendingAction::RestoreArchived { project, .. } => Some((project.clone(), "restore")),
+            PendingAction::PurgeArchived { project, .. } => Some((project.clone(), "purge archive")),
+            PendingAction::DeleteRemoteBranches { targets } => {
+                targets.first().map(|t| (t.repo.clone(), "delete remote branch"))
+            }
+            PendingAction::GitOp { worktree, op } => Some((self.repo_of(worktree), op.verb())),
+            PendingAction::Stash { worktree, op, .. } => Some((self.repo_of(worktree), op.verb())),
+            PendingAction::MarkResolved { worktree, .. } => Some((self.repo_of(worktree), "mark resolved")),
+            PendingAction::StartWork { project, .. } => Some((project.clone(), "start work")),
+            PendingAction::Adopt { worktree, dest: Some(_), .. } => Some((self.repo_of(worktree), "move worktree")),
+            PendingAction::LockWorktree { worktree, reason } => {
+                Some((self.repo_of(worktree), if reason.is_some() { "lock" } else { "unlock" }))
+            }
+            _ => None,
+        }
+    }
+
+    /// `f`: fetch regardless of the throttle; the remote line updates when it lands.
+    fn action_fetch_now(&mut self) {
+        let wt = match self.current_selection() {
+            Selection::Worktree(pi, wi) | Selection::Session(pi, wi, _) => self.workspace.worktree(pi, wi),
+            _ => None,
+        };
+        let Some(wt) = wt else {
+            self.set_status("Select a worktree");
+            return;
+        };
+        let (path, name) = (wt.path.clone(), wt.display_name().to_string());
+        if self.fetch_pending.contains(&path) {
+            self.set_status(format!("Already fetching {}", name));
+            return;
+        }
+        self.spawn_fetch(path);
+        self.set_status(format!("Fetching {}…", name));
+    }
+
     fn action_git_popup(&mut self) {
         let (pi, wi) = match self.current_selection() {
             Selection::Worktree(pi, wi) | Selection::Session(pi, wi, _) => (pi, wi),
@@ -1656,7 +5062,7 @@ impl App {
                 return;
             }
         };
-        self.mode = Mode::GitPopup { project_idx: pi, worktree_idx: wi };
+        self.mode = Mode::GitPopup { worktree: self.worktree_path(pi, wi) };
     }
 
     fn dispatch_git_popup(
@@ -1667,151 +5073,2826 @@ impl App {
         terminal: &mut Tui,
     ) -> Result<()> {
         match action {
-            Action::InputChar('p') => self.do_git_pull(pi, wi, terminal)?,
-            Action::InputChar('P') => self.do_git_push(pi, wi, terminal)?,
+            Action::InputChar('p') => self.do_git_op(pi, wi, GitOp::Pull, terminal)?,
+            Action::InputChar('P') => self.do_git_op(pi, wi, GitOp::Push, terminal)?,
             Action::InputChar('r') => {
                 let default = self.workspace.projects[pi].default_branch.clone();
-                self.mode = Mode::Input {
-                    context: InputContext::GitPullRebase { project_idx: pi, worktree_idx: wi },
+                self.open_layer(Mode::Input {
+                    context: InputContext::GitPullRebase { worktree: self.worktree_path(pi, wi) },
                     state: InputState::with_value("branch: ", default),
-                };
+                });
             }
             Action::InputChar('m') => {
                 let default = self.workspace.projects[pi].default_branch.clone();
-                self.mode = Mode::Input {
-                    context: InputContext::GitMergeFrom { project_idx: pi, worktree_idx: wi },
+                self.open_layer(Mode::Input {
+                    context: InputContext::GitMergeFrom { worktree: self.worktree_path(pi, wi) },
                     state: InputState::with_value("branch: ", default),
-                };
+                });
             }
             Action::InputChar('M') => {
                 let default = self.workspace.projects[pi].default_branch.clone();
-                self.mode = Mode::Input {
-                    context: InputContext::GitMergeInto { project_idx: pi, worktree_idx: wi },
+                self.open_layer(Mode::Input {
+                    context: InputContext::GitMergeInto { worktree: self.worktree_path(pi, wi) },
                     state: InputState::with_value("branch: ", default),
-                };
+                });
+            }
+            Action::InputChar('b') => {
+                let path = self.worktree_path(pi, wi);
+                if matches!(self.git.uncommitted_changes(&path), Ok(0)) {
+                    self.set_status("No uncommitted changes to branch off with");
+                    return Ok(());
+                }
+                self.open_layer(Mode::Input {
+                    context: InputContext::BranchOff { worktree: path },
+                    state: InputState::with_source(
+                        "branch (@ref: detached): ",
+                        String::new(),
+                        CompletionSource::Words(self.free_branches(pi)),
+                    ),
+                });
             }
-            Action::InputEscape | Action::Quit => self.mode = Mode::Normal,
+            Action::InputChar('s') => self.open_stashes(pi, wi),
+            Action::InputChar('c') => self.open_conflicts_at(pi, wi, 0),
+            Action::InputChar('h') => self.rerun_post_create(pi, wi),
+            Action::InputChar('H') => self.show_last_post_create(pi, wi),
+            Action::InputEscape | Action::InputChar('q') => self.back_out(),
             _ => {}
         }
         Ok(())
     }
 
-    fn git_worktree_path(&self, pi: usize, wi: usize) -> Option<std::path::PathBuf> {
-        self.workspace.projects.get(pi)?.worktrees.get(wi).map(|wt| wt.path.clone())
+    // ── Stashes ───────────────────────────────────────────────────────────────
+
+    /// Open the stash manager on a fresh `git stash list`, keeping the cursor on row `keep`.
+    fn open_stashes_at(&mut self, pi: usize, wi: usize, keep: usize) {
+        let Some(path) = self.git_worktree_path(pi, wi) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let Some(wt) = self.workspace.worktree(pi, wi) else { return };
+        let branch = if wt.detached { "(no branch)".to_string() } else { wt.branch.clone() };
+        let entries = self.git.stash_list(&path, &branch);
+        if entries.is_empty() {
+            self.close_layer(|m| matches!(m, Mode::Stashes { .. }));
+            self.set_status("No stashes");
+            return;
+        }
+        let now = frecency::now_secs();
+        let items = entries
+            .iter()
+            .map(|s| {
+                let age = frecency::fmt_idle(Duration::from_secs(now.saturating_sub(s.ts)));
+                format!("{}  {}  · {} ago", s.refname(), s.message, age)
+            })
+            .collect();
+        let mut picker = PickerState::new("Stashes — a apply · p pop · d drop · s show", items);
+        picker.list_state.select(Some(keep.min(entries.len() - 1)));
+        self.open_layer(Mode::Stashes { worktree: path, entries, picker });
     }
 
-    fn invalidate_git_info(&mut self, pi: usize, wi: usize) {
-        if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
-            wt.git_info = None;
+    fn open_stashes(&mut self, pi: usize, wi: usize) {
+        self.open_stashes_at(pi, wi, 0);
+    }
+
+    fn dispatch_stashes(&mut self, action: Action) -> Result<()> {
+        let Mode::Stashes { worktree, .. } = &self.mode else { return Ok(()) };
+        let Some((pi, wi)) = self.layer_worktree(&worktree.clone()) else { return Ok(()) };
+        let Mode::Stashes { entries, picker, .. } = &mut self.mode else { return Ok(()) };
+        let chosen = picker.selected().and_then(|i| entries.get(i)).cloned();
+        match action {
+            Action::NavigateDown | Action::InputChar('j') => picker.navigate_down(),
+            Action::NavigateUp | Action::InputChar('k') => picker.navigate_up(),
+            Action::InputChar('a') | Action::InputChar('p') | Action::InputChar('s') | Action::Select => {
+                let Some(stash) = chosen else { return Ok(()) };
+                let op = match action {
+                    Action::InputChar('a') => StashOp::Apply,
+                    Action::InputChar('p') => StashOp::Pop,
+                    _ => StashOp::Show,
+                };
+                if op == StashOp::Show {
+                    self.do_stash(pi, wi, op, stash.index)?;
+                } else {
+                    self.run_git(PendingAction::Stash { worktree: self.worktree_path(pi, wi), op, index: stash.index })?;
+                }
+            }
+            Action::InputChar('d') => {
+                let Some(stash) = chosen else { return Ok(()) };
+                let pending = PendingAction::DropStash { worktree: self.worktree_path(pi, wi), index: stash.index };
+                self.open_layer(Mode::Confirm {
+                    message: format!("Drop {} ({})? Its changes are lost.", stash.refname(), stash.message),
+                    pending,
+                    danger: true,
+                });
+            }
+            Action::InputEscape | Action::InputChar('q') => self.back_out(),
+            _ => {}
         }
+        Ok(())
     }
 
-    fn do_git_pull(&mut self, pi: usize, wi: usize, terminal: &mut Tui) -> Result<()> {
-        let path = match self.git_worktree_path(pi, wi) {
-            Some(p) => p,
-            None => { self.set_status("Worktree not found"); return Ok(()); }
+    /// Run a stash op; `Show` opens the diffstat, the others report and return to the list.
+    fn do_stash(&mut self, pi: usize, wi: usize, op: StashOp, index: usize) -> Result<()> {
+        let Some(path) = self.git_worktree_path(pi, wi) else {
+            self.set_status("Worktree not found");
+            return Ok(());
         };
-        self.loading = true;
-        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
-        let result = git_ops::pull(&path);
-        self.loading = false;
-        self.mode = Mode::Normal;
-        self.invalidate_git_info(pi, wi);
+        let refname = format!("stash@{{{}}}", index);
+        let result = self.git.stash(&path, op, index);
+        if op == StashOp::Show {
+            match result {
+                Ok(text) => {
+                    self.open_layer(Mode::GitOutput { title: format!("{} --stat", refname), text, scroll: 0 });
+                }
+                Err(e) => self.set_error(format!("stash show failed: {}", e)),
+            }
+            return Ok(());
+        }
+        self.reload_git_info(pi, wi);
+        self.open_stashes_at(pi, wi, index);
         match result {
-            Ok(msg) => self.set_status(format!("pull: {}", first_line(&msg))),
-            Err(e) => self.set_status(format!("pull failed: {}", e)),
+            Ok(_) => self.set_status(format!("stash {}: {}", op.verb(), refname)),
+            Err(e) => self.set_error(format!("stash {} failed: {}", op.verb(), e)),
         }
         Ok(())
     }
 
-    fn do_git_push(&mut self, pi: usize, wi: usize, terminal: &mut Tui) -> Result<()> {
-        let path = match self.git_worktree_path(pi, wi) {
-            Some(p) => p,
-            None => { self.set_status("Worktree not found"); return Ok(()); }
+    // ── Conflicts ─────────────────────────────────────────────────────────────
+
+    /// List the worktree's conflicted files, cursor on row `keep`. With none left but a
+    /// merge or rebase still open, offer to finish it instead.
+    fn open_conflicts_at(&mut self, pi: usize, wi: usize, keep: usize) {
+        let Some(path) = self.git_worktree_path(pi, wi) else {
+            self.mode = Mode::Normal;
+            return;
         };
-        self.loading = true;
-        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
-        let result = git_ops::push(&path);
-        self.loading = false;
-        self.mode = Mode::Normal;
-        self.invalidate_git_info(pi, wi);
-        match result {
-            Ok(msg) => self.set_status(format!("push: {}", first_line(&msg))),
-            Err(e) => self.set_status(format!("push failed: {}", e)),
+        let files = self.git.conflicted_files(&path);
+        if files.is_empty() {
+            self.close_layer(|m| matches!(m, Mode::Conflicts { .. }));
+            match self.git.in_progress(&path) {
+                Some(op) => {
+                    self.open_layer(Mode::Confirm {
+                        message: format!("No conflicts left — run `{}`?", op.continue_command()),
+                        pending: PendingAction::ContinueOperation { worktree: self.worktree_path(pi, wi) },
+                        danger: false,
+                    });
+                }
+                None => self.set_status("No conflicted files"),
+            }
+            return;
+        }
+        let mut picker = PickerState::new("Conflicts — Enter edit · a mark resolved", files.clone());
+        picker.list_state.select(Some(keep.min(files.len() - 1)));
+        self.open_layer(Mode::Conflicts { worktree: path, files, picker });
+    }
+
+    /// After a failed merge or rebase: open the conflict list if that's why it failed.
+    fn open_conflicts_if_any(&mut self, pi: usize, wi: usize) {
+        let conflicted = self
+            .git_worktree_path(pi, wi)
+            .is_some_and(|path| !self.git.conflicted_files(&path).is_empty());
+        if conflicted {
+            self.open_conflicts_at(pi, wi, 0);
+        }
+    }
+
+    fn dispatch_conflicts(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+        let Mode::Conflicts { worktree, .. } = &self.mode else { return Ok(()) };
+        let Some((pi, wi)) = self.layer_worktree(&worktree.clone()) else { return Ok(()) };
+        let Mode::Conflicts { files, picker, .. } = &mut self.mode else { return Ok(()) };
+        let row = picker.selected().unwrap_or(0);
+        let chosen = files.get(row).cloned();
+        match action {
+            Action::NavigateDown | Action::InputChar('j') => picker.navigate_down(),
+            Action::NavigateUp | Action::InputChar('k') => picker.navigate_up(),
+            Action::Select | Action::InputChar('e') => {
+                let (Some(file), Some(path)) = (chosen, self.git_worktree_path(pi, wi)) else {
+                    return Ok(());
+                };
+                edit_file(terminal, Path::new(&file), Some(&path))?;
+                self.open_conflicts_at(pi, wi, row);
+            }
+            Action::InputChar('a') => {
+                let Some(file) = chosen else { return Ok(()) };
+                self.run_git(PendingAction::MarkResolved { worktree: self.worktree_path(pi, wi), file, row })?;
+            }
+            Action::InputEscape | Action::InputChar('q') => self.back_out(),
+            _ => {}
         }
         Ok(())
     }
 
-    fn do_git_pull_rebase(
-        &mut self,
-        pi: usize,
-        wi: usize,
-        branch: String,
-        terminal: &mut Tui,
-    ) -> Result<()> {
-        let path = match self.git_worktree_path(pi, wi) {
-            Some(p) => p,
-            None => { self.set_status("Worktree not found"); return Ok(()); }
+    fn do_mark_resolved(&mut self, pi: usize, wi: usize, file: &str, row: usize) {
+        let Some(path) = self.git_worktree_path(pi, wi) else {
+            self.set_status("Worktree not found");
+            return;
         };
-        self.loading = true;
-        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
-        let result = git_ops::pull_rebase(&path, &branch);
-        self.loading = false;
-        self.invalidate_git_info(pi, wi);
+        let result = self.git.mark_resolved(&path, file);
+        self.reload_git_info(pi, wi);
+        self.open_conflicts_at(pi, wi, row);
         match result {
-            Ok(msg) => self.set_status(format!("rebase: {}", first_line(&msg))),
-            Err(e) => self.set_status(format!("rebase failed: {}", e)),
+            Ok(_) => self.set_status(format!("Marked {} resolved", file)),
+            Err(e) => self.set_error(format!("git add failed: {}", e)),
         }
-        Ok(())
     }
 
-    fn do_git_merge_from(
-        &mut self,
-        pi: usize,
-        wi: usize,
-        branch: String,
-        terminal: &mut Tui,
-    ) -> Result<()> {
-        let path = match self.git_worktree_path(pi, wi) {
-            Some(p) => p,
-            None => { self.set_status("Worktree not found"); return Ok(()); }
+    fn do_continue_operation(&mut self, pi: usize, wi: usize) {
+        let Some(path) = self.git_worktree_path(pi, wi) else {
+            self.set_status("Worktree not found");
+            return;
         };
-        self.loading = true;
-        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
-        let result = git_ops::merge_from(&path, &branch);
-        self.loading = false;
-        self.invalidate_git_info(pi, wi);
+        let result = self.git.continue_operation(&path);
+        self.reload_git_info(pi, wi);
+        self.mode = Mode::Normal;
         match result {
-            Ok(msg) => self.set_status(format!("merge: {}", first_line(&msg))),
-            Err(e) => self.set_status(format!("merge failed: {}", e)),
+            Ok(msg) => self.set_status(format!("continue: {}", first_line(&msg))),
+            Err(e) => {
+                self.set_error(format!("continue failed: {}", e));
+                // A rebase can stop again on the next commit.
+                self.open_conflicts_if_any(pi, wi);
+            }
+        }
+    }
+
+    fn dispatch_git_output(&mut self, action: Action) {
+        let Mode::GitOutput { text, scroll, .. } = &mut self.mode else {
+            return;
+        };
+        if let Some(run) = &mut self.post_create {
+            match action {
+                Action::InputEscape | Action::Quit | Action::InputChar('q') => {
+                    if !run.cancelled {
+                        run.cancelled = true;
+                        if let Some(child) = &run.child {
+                            hooks::cancel_post_create(child);
+                        }
+                        text.push_str("cancelling…\n");
+                        self.needs_redraw = true;
+                    }
+                    return;
+                }
+                Action::Select => return, // the popup stays until the hook is gone
+                _ => run.follow = false,
+            }
+        }
+        let last = text.lines().count().saturating_sub(1) as u16;
+        match action {
+            Action::NavigateDown | Action::InputChar('j') => *scroll = (*scroll + 1).min(last),
+            Action::NavigateUp | Action::InputChar('k') => *scroll = scroll.saturating_sub(1),
+            Action::ScrollPreview(delta) => {
+                *scroll = (*scroll as i32 + delta).clamp(0, last as i32) as u16;
+            }
+            Action::InputEscape | Action::Select | Action::InputChar('q') => self.back_out(),
+            _ => {}
+        }
+    }
+
+    // ── Open with ─────────────────────────────────────────────────────────────
+
+    fn action_open_with(&mut self) {
+        let path = match self.current_selection() {
+            Selection::Project(pi) | Selection::Archive(pi, _) => {
+                self.workspace.projects.get(pi).map(|p| p.path.clone())
+            }
+            Selection::Worktree(pi, wi) | Selection::Session(pi, wi, _) => {
+                self.workspace.worktree(pi, wi).map(|wt| wt.path.clone())
+            }
+            Selection::None => None,
+        };
+        let Some(path) = path else {
+            self.set_status("Select a project or worktree");
+            return;
+        };
+        if self.config.open_with.is_empty() {
+            self.set_status("No open_with commands in config.toml");
+            return;
+        }
+        let names = self.config.open_with.iter().map(|o| o.name.clone()).collect();
+        self.mode = Mode::OpenWith {
+            path,
+            picker: PickerState::new("Open with", names),
+        };
+    }
+
+    /// `E`: the selected worktree's recently ended sessions, or every project's when a
+    /// project or nothing is selected.
+    fn action_ended_sessions(&mut self) {
+        let (max, max_age) = self.config.ended_sessions_limits();
+        self.workspace.ended.expire(max, max_age, frecency::now_secs());
+        let under = match self.current_selection() {
+            Selection::Worktree(pi, wi) | Selection::Session(pi, wi, _) => {
+                self.workspace.worktree(pi, wi).map(|wt| wt.path.clone())
+            }
+            _ => None,
+        };
+        let entries = self.workspace.ended.recent(under.as_deref());
+        if entries.is_empty() {
+            self.set_status(match (max, &under) {
+                (0, _) => "Ended sessions aren't kept (ended_sessions_days = 0)",
+                (_, Some(_)) => "No recently ended sessions in this worktree",
+                (_, None) => "No recently ended sessions",
+            });
+            return;
+        }
+        let items = ui::ended::ended_items(&entries, frecency::now_secs());
+        self.mode = Mode::Ended {
+            entries,
+            picker: PickerState::new("Recently ended", items),
+            scroll: u16::MAX,
+        };
+    }
+
+    fn dispatch_ended(&mut self, action: Action) {
+        let Mode::Ended { picker, scroll, .. } = &mut self.mode else { return };
+        match action {
+            Action::NavigateDown => {
+                picker.navigate_down();
+                *scroll = u16::MAX;
+            }
+            Action::NavigateUp => {
+                picker.navigate_up();
+                *scroll = u16::MAX;
+            }
+            Action::ScrollPreview(delta) | Action::MouseScroll { delta, .. } => {
+                *scroll = scroll.saturating_add_signed(delta as i16)
+            }
+            Action::InputEscape | Action::Quit | Action::Select | Action::EndedSessions => self.back_out(),
+            _ => {}
+        }
+    }
+
+    fn action_view_diff(&mut self) {
+        let Selection::Worktree(pi, wi) = self.current_selection() else {
+            self.set_status("Select a worktree to see its changes");
+            return;
+        };
+        let Some(wt) = self.workspace.worktree(pi, wi) else { return };
+        let Some(info) = &wt.git_info else {
+            self.set_status("Git info not loaded yet");
+            return;
+        };
+        if info.modified_files.is_empty() {
+            self.set_status("No modified files");
+            return;
+        }
+        let files: Vec<String> = info.modified_files.iter().map(|f| ops::status_path(f).to_string()).collect();
+        let worktree = wt.path.clone();
+        if files.len() == 1 {
+            self.open_diff(worktree, files, 0);
+            return;
+        }
+        let picker = PickerState::new("Modified files — Enter diff", files.clone());
+        self.open_layer(Mode::DiffFiles { worktree, files, picker });
+    }
+
+    fn open_diff(&mut self, worktree: PathBuf, files: Vec<String>, index: usize) {
+        let text = diff_text(self.git.as_ref(), &worktree, &files[index]);
+        self.open_layer(Mode::Diff { worktree, files, index, text, scroll: 0 });
+    }
+
+    fn dispatch_diff_files(&mut self, action: Action) {
+        let Mode::DiffFiles { worktree, files, picker } = &mut self.mode else { return };
+        match action {
+            Action::NavigateDown => picker.navigate_down(),
+            Action::NavigateUp => picker.navigate_up(),
+            Action::Select => {
+                let Some(index) = picker.selected() else { return };
+                let (worktree, files) = (worktree.clone(), files.clone());
+                self.open_diff(worktree, files, index);
+            }
+            Action::InputEscape | Action::Quit | Action::ViewDiff => self.back_out(),
+            _ => {}
+        }
+    }
+
+    fn dispatch_diff(&mut self, action: Action) {
+        let git = Arc::clone(&self.git);
+        let Mode::Diff { worktree, files, index, text, scroll } = &mut self.mode else { return };
+        let step = match action {
+            Action::JumpProjectDown => 1,
+            Action::JumpProjectUp => files.len() - 1,
+            Action::NavigateDown => {
+                *scroll = scroll.saturating_add(1);
+                return;
+            }
+            Action::NavigateUp => {
+                *scroll = scroll.saturating_sub(1);
+                return;
+            }
+            Action::ScrollPreview(delta) | Action::MouseScroll { delta, .. } => {
+                *scroll = scroll.saturating_add_signed(delta as i16);
+                return;
+            }
+            Action::InputEscape | Action::Quit | Action::Select | Action::ViewDiff => {
+                self.back_out();
+                return;
+            }
+            _ => return,
+        };
+        *index = (*index + step) % files.len();
+        *scroll = 0;
+        *text = diff_text(git.as_ref(), worktree, &files[*index]);
+    }
+
+    fn dispatch_open_with(&mut self, action: Action) -> Result<()> {
+        let Mode::OpenWith { path, picker } = &mut self.mode else {
+            return Ok(());
+        };
+        match action {
+            Action::NavigateDown => picker.navigate_down(),
+            Action::NavigateUp => picker.navigate_up(),
+            Action::Select => {
+                let chosen = picker.selected().and_then(|i| self.config.open_with.get(i)).cloned();
+                let path = path.clone();
+                self.mode = Mode::Normal;
+                if let Some(entry) = chosen {
+                    launch::spawn(&entry.name, &entry.cmd, &path, self.launch_tx.clone())?;
+                    self.set_status(format!("Opened in {}", entry.name));
+                }
+            }
+            Action::InputEscape | Action::Quit | Action::OpenWith => self.back_out(),
+            _ => {}
         }
         Ok(())
     }
 
-    fn do_git_merge_into(
-        &mut self,
-        pi: usize,
-        wi: usize,
-        branch: String,
-        terminal: &mut Tui,
-    ) -> Result<()> {
-        let path = match self.git_worktree_path(pi, wi) {
-            Some(p) => p,
-            None => { self.set_status("Worktree not found"); return Ok(()); }
+    // ── Import from tmux ──────────────────────────────────────────────────────
+
+    /// First run: nothing registered, but tmux sessions already running in git repos.
+    /// Offer those repos as projects, all ticked, instead of an empty tree.
+    fn offer_session_import(&mut self) {
+        if !self.config.projects.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let (git, tmux) = (Arc::clone(&self.git), Arc::clone(&self.tmux));
+        // A git call per session directory: off the UI thread, like the project loader.
+        std::thread::spawn(move || {
+            let Ok(sessions) = tmux.list_sessions_with_paths() else { return };
+            let _ = tx.send(session_import::infer_projects(
+                &sessions,
+                |dir| git.toplevel(dir),
+                crate::git::worktree::main_repo_of,
+            ));
+        });
+        self.import_rx = Some(rx);
+    }
+
+    /// The import picker, once the candidates are in — unless a project was added or
+    /// another popup opened meanwhile.
+    fn poll_session_import(&mut self) {
+        let Some(rx) = &self.import_rx else { return };
+        let candidates = match rx.try_recv() {
+            Ok(candidates) => candidates,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Vec::new(),
         };
-        self.loading = true;
-        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
-        let result = git_ops::merge_into(&path, &branch);
-        self.loading = false;
-        self.invalidate_git_info(pi, wi);
-        match result {
-            Ok(msg) => self.set_status(msg),
-            Err(e) => self.set_status(format!("merge failed: {}", e)),
+        self.import_rx = None;
+        if candidates.is_empty() || !self.config.projects.is_empty() || !matches!(self.mode, Mode::Normal) {
+            return;
+        }
+        self.needs_redraw = true;
+        let chosen = vec![true; candidates.len()];
+        let picker = PickerState::new(
+            "Projects from your tmux sessions — Space toggles, Enter adds",
+            import_rows(&candidates, &chosen),
+        );
+        self.mode = Mode::ImportProjects { candidates, chosen, picker };
+    }
+
+    fn dispatch_import_projects(&mut self, action: Action) -> Result<()> {
+        let Mode::ImportProjects { candidates, chosen, picker } = &mut self.mode else {
+            return Ok(());
+        };
+        match action {
+            Action::NavigateDown => picker.navigate_down(),
+            Action::NavigateUp => picker.navigate_up(),
+            Action::StartWork => {
+                if let Some(i) = picker.selected() {
+                    chosen[i] = !chosen[i];
+                    picker.items = import_rows(candidates, chosen);
+                }
+            }
+            Action::Select => {
+                let repos: Vec<PathBuf> = candidates
+                    .iter()
+                    .zip(chosen.iter())
+                    .filter(|(_, &on)| on)
+                    .map(|(c, _)| c.repo.clone())
+                    .collect();
+                self.mode = Mode::Normal;
+                self.do_import_projects(repos)?;
+            }
+            Action::InputEscape | Action::Quit => {
+                self.back_out();
+                self.set_status("Nothing imported — p adds a project");
+            }
+            _ => {}
         }
         Ok(())
     }
-}
 
-fn first_line(s: &str) -> &str {
-    s.lines().next().unwrap_or(s)
+    /// Register each repo the way `p` does, saving the config once at the end.
+    fn do_import_projects(&mut self, repos: Vec<PathBuf>) -> Result<()> {
+        if repos.is_empty() {
+            self.set_status("Nothing imported — p adds a project");
+            return Ok(());
+        }
+        let mut added = 0;
+        let mut failed = Vec::new();
+        for repo in repos {
+            match ops::register_project(self.git.as_ref(), repo.clone(), &mut self.config) {
+                Ok(project) => {
+                    self.workspace.projects.push(project);
+                    added += 1;
+                }
+                Err(e) => failed.push(format!("{}: {}", display_path(&repo, false, usize::MAX), e)),
+            }
+        }
+        if added > 0 {
+            self.rebuild_flat();
+            self.save_config()?;
+            let s = if added == 1 { "" } else { "s" };
+            self.set_status(format!("Added {} project{} from tmux sessions", added, s));
+        }
+        if !failed.is_empty() {
+            self.set_error(format!("Not imported — {}", failed.join("; ")));
+        }
+        Ok(())
+    }
+
+    // ── Project actions ───────────────────────────────────────────────────────
+
+    fn action_project_actions(&mut self) {
+        let pi = match self.current_selection() {
+            Selection::Project(pi)
+            | Selection::Worktree(pi, _)
+            | Selection::Session(pi, _, _)
+            | Selection::Archive(pi, _) => pi,
+            Selection::None => {
+                self.set_status("Select a project first");
+                return;
+            }
+        };
+        let p = &self.workspace.projects[pi];
+        let actions = project_actions::project_actions(p);
+        if actions.is_empty() {
+            self.set_status(format!("'{}' is missing — nothing to run", p.name));
+            return;
+        }
+        let labels = actions.iter().map(|a| a.label(&p.default_branch)).collect();
+        self.mode = Mode::ProjectActions {
+            project: p.path.clone(),
+            picker: PickerState::new(format!("{} actions", p.name), labels),
+            actions,
+        };
+    }
+
+    fn dispatch_project_actions(&mut self, action: Action, terminal: &mut Tui) -> Result<()> {
+        let Mode::ProjectActions { project, actions, picker } = &mut self.mode else {
+            return Ok(());
+        };
+        match action {
+            Action::NavigateDown => picker.navigate_down(),
+            Action::NavigateUp => picker.navigate_up(),
+            Action::Select => {
+                let chosen = picker.selected().and_then(|i| actions.get(i)).cloned();
+                let project = project.clone();
+                self.mode = Mode::Normal;
+                if let Some(chosen) = chosen {
+                    let pi = self.resolve_project(&project)?;
+                    self.run_project_action(pi, chosen, terminal)?;
+                }
+            }
+            Action::InputEscape | Action::Quit | Action::ProjectActions => self.back_out(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Hand a menu entry to the flow its key would run.
+    fn run_project_action(&mut self, pi: usize, chosen: ProjectAction, terminal: &mut Tui) -> Result<()> {
+        match chosen {
+            ProjectAction::CleanMerged => self.run_git(PendingAction::CleanProject { project: self.project_path(pi) })?,
+            ProjectAction::PullMain => {
+                if let Some(wi) = self.main_worktree(pi) {
+                    self.do_git_op(pi, wi, GitOp::Pull, terminal)?;
+                }
+            }
+            ProjectAction::MainShell => {
+                if self.select_main_shell_of(pi)? {
+                    self.action_select(terminal)?;
+                }
+            }
+            ProjectAction::Run { label, command } => {
+                if let Some(wi) = self.main_worktree(pi) {
+                    // `exit` ends the session with the command; `E` keeps its last output.
+                    let name = project_actions::run_session_name(&label);
+                    self.do_create_session(pi, wi, name, Some(format!("{}; exit", command)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn git_worktree_path(&self, pi: usize, wi: usize) -> Option<std::path::PathBuf> {
+        self.workspace.projects.get(pi)?.worktrees.get(wi).map(|wt| wt.path.clone())
+    }
+
+    /// Re-read a worktree's git info now, so the preview shows what an operation just did
+    /// instead of waiting for the next capture tick to notice.
+    fn reload_git_info(&mut self, pi: usize, wi: usize) {
+        let Some(path) = self.git_worktree_path(pi, wi) else { return };
+        let default_branch = self.workspace.projects[pi].default_branch.clone();
+        let info = self.git.git_info(&path, &default_branch);
+        if let Some(wt) = self.workspace.worktree_mut(pi, wi) {
+            wt.git_info = info;
+        }
+        self.needs_redraw = true;
+    }
+
+    fn reload_git_info_at(&mut self, path: &Path) {
+        if let Some((pi, wi)) = self.workspace.find_worktree(path) {
+            self.reload_git_info(pi, wi);
+        }
+    }
+
+    fn do_git_op(&mut self, pi: usize, wi: usize, op: GitOp, terminal: &mut Tui) -> Result<()> {
+        if self.git_worktree_path(pi, wi).is_none() {
+            self.set_status("Worktree not found");
+            return Ok(());
+        }
+        self.loading = true;
+        tui::draw_sync(terminal, |frame| ui::render(frame, self))?;
+        let result = self.run_git(PendingAction::GitOp { worktree: self.worktree_path(pi, wi), op });
+        self.loading = false;
+        result
+    }
+
+    /// Run a git popup operation, then reload the git info of every worktree it touched.
+    fn run_git_op(&mut self, pi: usize, wi: usize, op: GitOp) {
+        let Some(path) = self.git_worktree_path(pi, wi) else { return };
+        let result = match &op {
+            GitOp::Pull => self.git.pull(&path),
+            GitOp::Push => self.git.push(&path),
+            GitOp::PullRebase(branch) => self.git.pull_rebase(&path, branch),
+            GitOp::MergeFrom(branch) => self.git.merge_from(&path, branch),
+            GitOp::MergeInto(branch) => self.git.merge_into(&path, branch),
+        };
+        self.loading = false;
+        self.mode = Mode::Normal;
+        let talked_to_remote = matches!(op, GitOp::Pull | GitOp::Push) && result.is_ok();
+        if let Some(wt) = self.workspace.worktree_mut(pi, wi).filter(|_| talked_to_remote) {
+            // Pull fetched, push updated the tracking branch — ahead/behind is fresh.
+            let now = Instant::now();
+            wt.last_fetched = Some(now);
+            wt.last_fetch_ok = Some(now);
+            wt.fetch_failed = false;
+        }
+        self.reload_git_info(pi, wi);
+        if let GitOp::MergeInto(branch) = &op {
+            // The target branch moved in whichever worktree has it checked out.
+            let target = self.workspace.projects[pi].worktrees.iter().position(|w| &w.branch == branch);
+            if let Some(twi) = target {
+                self.reload_git_info(pi, twi);
+            }
+        }
+        let verb = op.verb();
+        match result {
+            Ok(msg) if matches!(op, GitOp::MergeInto(_)) => self.set_status(msg),
+            Ok(msg) => self.set_status(format!("{}: {}", verb, first_line(&msg))),
+            Err(e) => {
+                self.set_error(format!("{} failed: {}", verb, e));
+                if matches!(op, GitOp::PullRebase(_) | GitOp::MergeFrom(_)) {
+                    self.open_conflicts_if_any(pi, wi);
+                }
+            }
+        }
+    }
+}
+
+/// What the git popup (and its branch prompts) can run on a worktree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitOp {
+    Pull,
+    Push,
+    PullRebase(String),
+    MergeFrom(String),
+    MergeInto(String),
+}
+
+impl GitOp {
+    fn verb(&self) -> &'static str {
+        match self {
+            GitOp::Pull => "pull",
+            GitOp::Push => "push",
+            GitOp::PullRebase(_) => "rebase",
+            GitOp::MergeFrom(_) | GitOp::MergeInto(_) => "merge",
+        }
+    }
+}
+
+/// Open `file` in $EDITOR (vi by default) with the TUI suspended, from `dir` when given.
+fn edit_file(terminal: &mut Tui, file: &Path, dir: Option<&Path>) -> Result<()> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    tui::with_raw_mode_disabled(terminal, || {
+        let mut cmd = std::process::Command::new(&editor);
+        cmd.arg(file);
+        if let Some(dir) = dir {
+            cmd.current_dir(dir);
+        }
+        cmd.status()?;
+        Ok(())
+    })
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or(s)
+}
+
+fn uncommitted_text(changes: usize) -> String {
+    format!("{} uncommitted change{}", changes, if changes == 1 { "" } else { "s" })
+}
+
+/// After creating a worktree: a hook or copy warning if there was one, else `done`
+/// and a short note of the files copied.
+fn created_status(done: String, created: &ops::CreatedWorktree) -> String {
+    let detail = created.copy.describe(3);
+    match (&created.warning, detail.is_empty()) {
+        (Some(w), _) => w.clone(),
+        (None, true) => done,
+        (None, false) => format!("{} · {}", done, detail),
+    }
+}
+
+/// Files the env copy brought into a new worktree, templates included.
+fn env_files(copy: &hooks::CopySummary) -> usize {
+    copy.copied.len() + copy.rendered.len()
+}
+
+/// More env files than this are listed in a popup after creating a worktree.
+const COPY_LIST_MAX: usize = 5;
+
+fn copy_report(copy: &hooks::CopySummary) -> String {
+    let mut out = vec![];
+    for (heading, paths) in [
+        ("Copied", &copy.copied),
+        ("Rendered from templates", &copy.rendered),
+        ("Kept existing (copy.overwrite is off)", &copy.skipped),
+        ("Excluded", &copy.excluded),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        out.push(format!("{} ({}):", heading, paths.len()));
+        out.extend(paths.iter().map(|p| format!("  {}", p.display())));
+    }
+    out.join("\n")
+}
+
+fn skipped_text(dirty: usize, locked: usize) -> String {
+    let parts: Vec<String> = [(dirty, "dirty"), (locked, "locked")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{} {}", n, what))
+        .collect();
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" (skipped {})", parts.join(", "))
+    }
+}
+
+/// Actions that need a project's live worktree list, locks or .gtrconfig, which a
+/// project still drawn from the startup cache doesn't have yet.
+fn needs_live_git(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::AddWorktree
+            | Action::StartWork
+            | Action::Delete
+            | Action::Clean
+            | Action::Edit
+            | Action::GitPopup
+            | Action::Archive
+            | Action::ToggleLock
+            | Action::Adopt
+            | Action::ViewDiff
+            | Action::ProjectActions
+    )
+}
+
+/// Why `d`, `A` and `c` leave a `worktree.hide` worktree alone.
+fn hidden_text(branch: &str) -> String {
+    format!("'{}' is hidden by worktree.hide — wsx leaves it alone", branch)
+}
+
+/// Why `d`, `A` and `c` leave a locked worktree alone.
+fn locked_text(branch: &str, reason: &str) -> String {
+    if reason.is_empty() {
+        format!("'{}' is locked — unlock first (L)", branch)
+    } else {
+        format!("'{}' locked: {} — unlock first (L)", branch, reason)
+    }
+}
+
+/// The diff popup's text for `file`, cut to `DIFF_MAX_LINES`.
+fn diff_text(git: &dyn GitBackend, worktree: &Path, file: &str) -> String {
+    match git.file_diff(worktree, file) {
+        Ok(raw) => ops::diff_text(&raw, ops::DIFF_MAX_LINES),
+        Err(e) => format!("git diff failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        confirm_key, App, ConfirmButton, ConfirmKey, FormContext, GitOp, InputContext, Mode, PendingAction, ScrollbarDrag,
+    };
+    use crate::action::Action;
+    use crate::cache::CacheWriter;
+    use crate::cli::Args;
+    use crate::config::global::{GlobalConfig, OpenWith};
+    use crate::git::{
+        fake::FakeGit,
+        ops::{InProgress, StashOp},
+        GitBackend,
+    };
+    use crate::model::fixtures;
+    use crate::model::focus::{Focus, HIDDEN_ACTIVITY_INTERVAL, PROBE_INTERVAL};
+    use crate::model::workspace::{Project, ProjectConfig, Selection, WorkspaceState};
+    use crate::ui::input::{FormState, InputState};
+    use crate::tmux::{fake::FakeTmux, TmuxBackend};
+    use crate::tmux::monitor::SessionStatus;
+    use ratatui::layout::{Position, Rect};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    const REPO: &str = "/tmp/wsx-test/app";
+
+    fn running() -> SessionStatus {
+        SessionStatus { has_running_app: true, ..Default::default() }
+    }
+
+    /// One project with a main worktree and `feature`, sessions `a`, `b`, `c` on the main
+    /// worktree (a and c running something) and `d` on feature.
+    fn fixture() -> (Arc<FakeGit>, Arc<FakeTmux>) {
+        let git = Arc::new(FakeGit::default());
+        let tmux = Arc::new(FakeTmux::default());
+        let repo = Path::new(REPO);
+        git.add_repo(repo, "main");
+        let feature = git.add_worktree(repo, "feature");
+        for name in ["a", "b", "c"] {
+            tmux.add_session(&format!("app-app-{}", name), repo);
+        }
+        tmux.add_session("app-feature-d", &feature);
+        tmux.set_status("app-app-a", running());
+        tmux.set_status("app-app-c", running());
+        (git, tmux)
+    }
+
+    fn app(git: &Arc<FakeGit>, tmux: &Arc<FakeTmux>) -> App {
+        let mut config = GlobalConfig::default();
+        config.add_project("app".into(), PathBuf::from(REPO));
+        let workspace = WorkspaceState {
+            projects: vec![Project { path: PathBuf::from(REPO), ..fixtures::project("app") }],
+            ..Default::default()
+        };
+        let mut app = App::with_workspace(
+            &Args::default(),
+            config,
+            workspace,
+            0,
+            git.clone(),
+            tmux.clone(),
+        );
+        app.cache_writer = CacheWriter::disabled();
+        app.refresh_all().unwrap();
+        app
+    }
+
+    fn selected_session(app: &App) -> Option<String> {
+        match app.current_selection() {
+            Selection::Session(pi, wi, si) => {
+                app.workspace.session(pi, wi, si).map(|s| s.display_name.clone())
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn next_attention_cycles_running_sessions() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("a"));
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"));
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("a"), "wraps around");
+        app.action_next_attention(-1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"), "prev wraps too");
+    }
+
+    #[test]
+    fn next_attention_visits_the_longest_waiting_session_first() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let ago = |secs| std::time::Instant::now().checked_sub(std::time::Duration::from_secs(secs));
+        let since = |app: &App, si: usize| app.workspace.projects[0].worktrees[0].sessions[si].attention_since;
+        assert!(since(&app, 0).is_some() && since(&app, 1).is_none(), "only sessions needing attention are clocked");
+        app.workspace.projects[0].worktrees[0].sessions[0].attention_since = ago(60);
+        app.workspace.projects[0].worktrees[0].sessions[2].attention_since = ago(600);
+        app.refresh_all().unwrap();
+        assert!(since(&app, 2).unwrap().elapsed().as_secs() >= 600, "kept through a refresh");
+
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"), "waiting 10m beats 1m");
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("a"));
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"), "wraps around");
+
+        app.action_dismiss_attention();
+        assert_eq!(since(&app, 2), None, "dismissing stops the clock");
+        let now = crate::model::frecency::now_secs();
+        tmux.set_status("app-app-a", SessionStatus { last_activity_ts: now, ..running() });
+        app.refresh_activity();
+        assert_eq!(since(&app, 0), None, "output resumed");
+    }
+
+    #[test]
+    fn next_attention_expands_collapsed_rows_and_lands_on_the_session() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        tmux.set_status("app-feature-d", running());
+        app.refresh_all().unwrap();
+        app.workspace.projects[0].worktrees[0].sessions[0].attention_since = None;
+        app.workspace.projects[0].worktrees[0].sessions[2].attention_since = None;
+        let feature = app.workspace.find_worktree(&Path::new(REPO).with_file_name("app-feature")).unwrap().1;
+        app.workspace.projects[0].worktrees[0].expanded = false;
+        app.workspace.projects[0].worktrees[feature].expanded = false;
+        app.rebuild_flat();
+        app.tree_selected = 0;
+
+        // d waits longest; it and its worktree's rows sit below main's collapsed sessions.
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("d"));
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("a"), "expanding main shifts d down; the cursor follows a");
+        app.workspace.projects[0].expanded = false;
+        app.rebuild_flat();
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("d"), "reached through a collapsed project, from the top");
+    }
+
+    #[test]
+    fn ended_sessions_keep_their_last_capture() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.workspace.projects[0].worktrees[0].sessions[0].pane_capture = Some("panic: boom".into());
+        tmux.kill_session("app-app-a").unwrap();
+        tmux.kill_session("app-app-b").unwrap(); // never captured
+        app.refresh_all().unwrap();
+        assert_eq!(app.workspace.projects[0].worktrees[0].sessions.len(), 1);
+
+        app.tree_selected = 3; // feature worktree
+        app.action_ended_sessions();
+        assert_eq!(app.status_message.as_deref(), Some("No recently ended sessions in this worktree"));
+        app.tree_selected = 1; // main
+        app.action_ended_sessions();
+        let Mode::Ended { entries, scroll, .. } = &app.mode else { panic!("expected the ended list") };
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].display_name.as_str(), entries[0].capture.as_str()), ("a", "panic: boom"));
+        assert_eq!(*scroll, u16::MAX, "opens at the end of the capture");
+        app.dispatch_ended(Action::InputEscape);
+        assert!(matches!(app.mode, Mode::Normal));
+
+        app.config.ended_sessions_days = Some(0);
+        app.action_ended_sessions();
+        assert!(app.workspace.ended.is_empty());
+        assert_eq!(app.status_message.as_deref(), Some("Ended sessions aren't kept (ended_sessions_days = 0)"));
+    }
+
+    #[test]
+    fn dismissed_session_stays_dismissed_until_new_activity() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+
+        app.action_next_attention(1);
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Dismissed"));
+        app.refresh_all().unwrap();
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"));
+        app.action_next_attention(1);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"), "a is no longer a candidate");
+
+        // Fresh output in `a` clears the dismissal.
+        let now = crate::model::frecency::now_secs();
+        tmux.set_status("app-app-a", SessionStatus { last_activity_ts: now, ..running() });
+        app.refresh_activity();
+        let a = app.workspace.projects[0].worktrees[0].sessions.iter().find(|s| s.display_name == "a");
+        assert!(!a.unwrap().running_app_suppressed);
+    }
+
+    #[test]
+    fn attention_reasons_clear_one_dismiss_at_a_time() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let now = crate::model::frecency::now_secs();
+        let a = |app: &App| app.workspace.projects[0].worktrees[0].sessions[0].clone();
+        let pytest = SessionStatus {
+            has_bell: true,
+            last_activity_ts: now - 360,
+            commands: vec!["pytest".into()],
+            ..running()
+        };
+        tmux.set_status("app-app-a", pytest.clone());
+        app.refresh_activity();
+        assert_eq!(crate::ui::preview::attention_text(&a(&app)).as_deref(), Some("bell, running 'pytest' idle 6m"));
+
+        // pytest exits with nobody attached: the shell prompt is fresh output, but it's finished.
+        tmux.set_status("app-app-a", SessionStatus { has_running_app: false, commands: vec![], last_activity_ts: now, ..pytest });
+        app.refresh_activity();
+        app.refresh_all().unwrap();
+        assert_eq!(crate::ui::preview::attention_text(&a(&app)).as_deref(), Some("bell, finished"));
+
+        app.tree_selected = 2;
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Bell acknowledged"));
+        assert!(a(&app).needs_attention(), "still finished");
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Dismissed: finished"));
+        assert!(a(&app).attention.is_empty());
+        app.refresh_activity();
+        assert!(a(&app).attention.is_empty(), "only another exit finishes it again");
+    }
+
+    #[test]
+    fn dismiss_on_idle_session_toggles_mute() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        // Flat order: project, main, a, b, c, feature, d
+        app.tree_selected = 3;
+        assert_eq!(selected_session(&app).as_deref(), Some("b"));
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Muted"));
+        tmux.set_status("app-app-b", running());
+        app.refresh_all().unwrap();
+        app.action_next_attention(1);
+        app.action_next_attention(1);
+        assert_ne!(selected_session(&app).as_deref(), Some("b"), "muted sessions never need attention");
+        app.tree_selected = 3;
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Unmuted"));
+    }
+
+    #[test]
+    fn muting_clears_a_latched_bell_until_unmuted() {
+        let (git, tmux) = fixture();
+        tmux.set_status("app-app-b", SessionStatus { has_bell: true, ..Default::default() });
+        let mut app = app(&git, &tmux);
+        let b = |app: &App| app.workspace.projects[0].worktrees[0].sessions[1].clone();
+        assert!(b(&app).has_activity);
+
+        app.tree_selected = 3;
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Bell acknowledged"));
+        let ring = |app: &mut App| {
+            tmux.set_status("app-app-b", SessionStatus::default());
+            app.refresh_activity();
+            tmux.set_status("app-app-b", SessionStatus { has_bell: true, ..Default::default() });
+            app.refresh_activity();
+        };
+        ring(&mut app);
+        assert!(b(&app).has_activity, "a newer bell");
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Bell acknowledged"));
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Muted"));
+        assert!(b(&app).muted && !b(&app).has_activity, "bell dropped when muting");
+        app.refresh_activity();
+        app.refresh_all().unwrap();
+        assert!(!b(&app).has_activity, "tmux still rings, but muted sessions ignore it");
+        assert!(!app.workspace.projects[0].worktrees[0].sessions.iter().any(|s| s.has_activity));
+
+        app.tree_selected = 3;
+        app.action_dismiss_attention();
+        assert_eq!(app.status_message.as_deref(), Some("Unmuted"));
+        ring(&mut app);
+        assert!(b(&app).has_activity, "live state returns after unmuting");
+    }
+
+    #[test]
+    fn acknowledged_bells_stay_cleared_until_the_flag_goes_up_again() {
+        let (git, tmux) = fixture();
+        let now = crate::model::frecency::now_secs();
+        let bell = |ts| SessionStatus { has_bell: true, last_activity_ts: ts, ..Default::default() };
+        tmux.set_status("app-app-b", bell(now - 600));
+        let mut app = app(&git, &tmux);
+        let b = |app: &App| app.workspace.projects[0].worktrees[0].sessions[1].clone();
+        assert!(b(&app).has_activity);
+
+        // x: tmux keeps the alert latched, wsx no longer shows it.
+        app.tree_selected = 3;
+        app.action_dismiss_attention();
+        assert!(!b(&app).has_activity && b(&app).bell_ack.is_some());
+        app.refresh_activity();
+        app.refresh_all().unwrap();
+        assert!(!b(&app).has_activity, "still latched in tmux, already seen");
+
+        // Output after the acknowledgment isn't a bell.
+        tmux.set_status("app-app-b", bell(now + 60));
+        app.refresh_activity();
+        assert!(!b(&app).has_activity, "output isn't a new bell");
+
+        // A bell acknowledged before a restart stays acknowledged.
+        let sess = &mut app.workspace.projects[0].worktrees[0].sessions[1];
+        sess.bell_since = None;
+        app.refresh_activity();
+        assert!(!b(&app).has_activity);
+
+        // The flag dropping and going up again is a new bell.
+        tmux.set_status("app-app-b", SessionStatus::default());
+        app.refresh_activity();
+        tmux.set_status("app-app-b", bell(now));
+        app.refresh_activity();
+        assert!(b(&app).has_activity);
+
+        // Attaching acknowledges every window's bell at once.
+        app.record_attach(0, "app-app-b");
+        assert!(!b(&app).has_activity);
+        app.refresh_all().unwrap();
+        assert!(!b(&app).has_activity);
+
+        // Once tmux drops the alert the acknowledgment goes with it.
+        tmux.set_status("app-app-b", SessionStatus::default());
+        app.refresh_activity();
+        assert_eq!(b(&app).bell_ack, None);
+        tmux.set_status("app-app-b", bell(now - 600));
+        app.refresh_activity();
+        assert!(b(&app).has_activity, "a fresh latch after the last one cleared");
+    }
+
+    #[test]
+    fn captures_wait_for_the_selection_to_settle_and_skip_quiet_panes() {
+        let (git, tmux) = fixture();
+        tmux.set_status("app-app-a", SessionStatus { last_activity_ts: 100, ..Default::default() });
+        let mut app = app(&git, &tmux);
+        app.refresh_activity();
+        let settle = |app: &mut App| {
+            app.selection_moved_at -= std::time::Duration::from_secs(1);
+            app.capture_timer.last -= std::time::Duration::from_secs(1);
+            app.refresh_captures();
+        };
+        for row in [4, 3, 2] {
+            app.tree_selected = row; // j/k held down
+            app.refresh_captures();
+        }
+        assert_eq!(app.preview_stats.captures, 0);
+        assert_eq!(app.preview_stats.git_info_loads, 0);
+
+        settle(&mut app);
+        assert_eq!(app.preview_stats.captures, 1);
+        assert_eq!(app.preview_stats.git_info_loads, 1);
+        assert!(app.workspace.projects[0].worktrees[0].sessions[0].pane_capture.is_some());
+
+        settle(&mut app);
+        assert_eq!(app.preview_stats.captures, 1, "no output since the last capture");
+        tmux.set_status("app-app-a", SessionStatus { last_activity_ts: 200, ..Default::default() });
+        app.refresh_activity();
+        settle(&mut app);
+        assert_eq!(app.preview_stats.captures, 2);
+    }
+
+    #[test]
+    fn pins_float_to_top_and_unpin_restores_order() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let names = |app: &mut App| -> Vec<String> {
+            app.rebuild_flat();
+            (0..app.flat().len())
+                .map(|i| match app.workspace.get_selection(i, app.flat()) {
+                    Selection::Session(pi, wi, si) => app.workspace.session(pi, wi, si).unwrap().display_name.clone(),
+                    Selection::Worktree(pi, wi) => app.workspace.projects[pi].worktrees[wi].branch.clone(),
+                    _ => "app".to_string(),
+                })
+                .collect()
+        };
+        // Flat order: project, main, a, b, c, feature, d
+        app.tree_selected = 4;
+        app.action_toggle_pin();
+        assert_eq!(selected_session(&app).as_deref(), Some("c"), "cursor follows the pinned row");
+        app.tree_selected = 5;
+        app.action_toggle_pin();
+        assert_eq!(names(&mut app), ["app", "feature", "d", "main", "c", "a", "b"]);
+
+        app.refresh_all().unwrap();
+        assert_eq!(names(&mut app), ["app", "feature", "d", "main", "c", "a", "b"], "pins survive refresh");
+
+        // Manual moves stay inside their group: a swaps with b, never above c.
+        app.move_session(0, 0, 0, -1);
+        app.move_session(0, 0, 0, 1);
+        assert_eq!(names(&mut app), ["app", "feature", "d", "main", "c", "b", "a"]);
+
+        app.tree_selected = 4;
+        app.action_toggle_pin();
+        assert_eq!(names(&mut app), ["app", "feature", "d", "main", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn clean_project_removes_merged_and_offers_remote_delete() {
+        let (git, tmux) = fixture();
+        let repo = Path::new(REPO);
+        git.add_worktree(repo, "wip");
+        git.set_merged(repo, "feature");
+        git.set_remote(repo, "feature");
+        let mut app = app(&git, &tmux);
+        app.config.delete_remote_on_clean = true;
+
+        app.tree_selected = 0;
+        app.action_clean().unwrap();
+        assert_eq!(git.branches(repo), ["main", "wip"]);
+        let names: Vec<&str> =
+            app.workspace.projects[0].worktrees.iter().map(|w| w.branch.as_str()).collect();
+        assert_eq!(names, ["main", "wip"], "tree refreshed after clean");
+
+        let Mode::Confirm { pending: PendingAction::DeleteRemoteBranches { targets }, .. } =
+            std::mem::replace(&mut app.mode, Mode::Normal)
+        else {
+            panic!("expected remote-delete confirmation");
+        };
+        assert_eq!(targets.len(), 1);
+        app.do_delete_remote_branches(targets).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Deleted 1 remote branch"));
+    }
+
+    #[test]
+    fn clean_refuses_unmerged_worktree() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 5; // feature
+        app.action_clean().unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("'feature' not merged into main"));
+        assert_eq!(git.branches(Path::new(REPO)), ["main", "feature"]);
+    }
+
+    #[test]
+    fn clean_keeps_dirty_worktrees_unless_confirmed() {
+        let (git, tmux) = fixture();
+        let repo = Path::new(REPO);
+        git.set_merged(repo, "feature");
+        let mut app = app(&git, &tmux);
+        git.set_dirty(&app.workspace.projects[0].worktrees[1].path, 4);
+
+        app.tree_selected = 0;
+        app.action_clean().unwrap();
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("No merged worktrees to clean (skipped 1 dirty)")
+        );
+        assert_eq!(git.branches(repo), ["main", "feature"]);
+
+        app.tree_selected = 5; // feature
+        app.action_clean().unwrap();
+        let Mode::Confirm { message, danger: true, pending: pending @ PendingAction::CleanWorktree { force: true, .. } } =
+            std::mem::replace(&mut app.mode, Mode::Normal)
+        else {
+            panic!("expected escalated confirmation");
+        };
+        assert!(message.contains("has 4 uncommitted changes — delete anyway?"), "{}", message);
+        app.run_pending(pending).unwrap();
+        assert_eq!(git.branches(repo), ["main"]);
+    }
+
+    #[test]
+    fn attach_warning_only_for_interactive_apps_when_enabled() {
+        let (git, tmux) = fixture();
+        let nvim = SessionStatus { commands: vec!["nvim".into()], ..running() };
+        tmux.set_status("app-app-a", nvim);
+        let mut app = app(&git, &tmux);
+        // Flat order: project, main, a, b, c, feature, d
+        assert_eq!(app.attach_warning(0, 0, 0), None, "off by default");
+        app.config.confirm_attach_running = true;
+        assert_eq!(
+            app.attach_warning(0, 0, 0).as_deref(),
+            Some("Session 'a' is running nvim — attach?")
+        );
+        assert_eq!(app.attach_warning(0, 0, 2), None, "running, but not interactive");
+    }
+
+    #[test]
+    fn open_with_picks_selected_worktree_path() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 5; // feature
+        app.action_open_with();
+        assert_eq!(app.status_message.as_deref(), Some("No open_with commands in config.toml"));
+
+        app.config.open_with = ["VS Code", "Finder"]
+            .map(|name| OpenWith { name: name.into(), cmd: "true {path}".into() })
+            .to_vec();
+        app.action_open_with();
+        let feature = app.workspace.projects[0].worktrees[1].path.clone();
+        let Mode::OpenWith { path, picker } = &mut app.mode else {
+            panic!("expected the open-with picker");
+        };
+        assert_eq!(path, &feature);
+        picker.navigate_up();
+        assert_eq!(picker.selected(), Some(1), "wraps to the last entry");
+        app.dispatch_open_with(Action::InputEscape).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn project_actions_menu_runs_a_configured_entry_at_main() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.workspace.projects[0].config =
+            Some(ProjectConfig { actions: vec![("Seed DB".into(), "make seed".into())], ..Default::default() });
+        app.tree_selected = 5; // feature: the menu is still the project's
+        app.dispatch(Action::ProjectActions, &mut term).unwrap();
+        let Mode::ProjectActions { project, picker, .. } = &mut app.mode else {
+            panic!("expected the project actions picker");
+        };
+        assert_eq!(project, Path::new(REPO));
+        assert_eq!(
+            picker.items,
+            ["Pull main", "Clean merged worktrees", "Shell in the main worktree", "Seed DB · make seed"]
+        );
+        picker.navigate_up(); // wraps to the last entry
+        app.dispatch(Action::Select, &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("Session 'seed-db' created"));
+        let (session, keys) = tmux.sent.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(keys, "make seed; exit");
+        assert_eq!(app.workspace.find_session(&session).map(|(_, wi, _)| wi), Some(0), "in the main worktree");
+    }
+
+    #[test]
+    fn preview_click_selects_session_in_collapsed_worktree() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.workspace.projects[0].worktrees[0].expanded = false;
+        app.rebuild_flat();
+        app.tree_selected = 1; // main, collapsed
+        app.preview_sessions_top = Some(10);
+        app.click_preview_session(0, 0, 9);
+        assert_eq!(app.tree_selected, 1, "click above the sessions is ignored");
+        app.click_preview_session(0, 0, 13);
+        assert_eq!(app.tree_selected, 1, "click below the last session is ignored");
+        app.click_preview_session(0, 0, 12);
+        assert_eq!(selected_session(&app).as_deref(), Some("c"));
+        assert!(app.workspace.projects[0].worktrees[0].expanded);
+    }
+
+    #[test]
+    fn cursor_follows_created_worktree_and_session() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.workspace.projects[0].worktrees[1].expanded = false;
+        app.do_create_worktree(0, "zzz".into()).unwrap();
+        // Flat order: project, main, a, b, c, feature, zzz
+        assert_eq!(app.tree_selected, 6);
+        assert_eq!(app.current_selection(), Selection::Worktree(0, 2));
+
+        app.do_create_session(0, 1, "dev".into(), None).unwrap();
+        // …, feature (expanded again), d, dev, zzz
+        assert_eq!(app.tree_selected, 7);
+        assert_eq!(selected_session(&app).as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn tmux_defaults_are_set_on_new_sessions_and_rejections_reported_once() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.config.tmux_defaults = Some(vec!["mouse on".into(), "status-left '#S | %H:%M'".into(), "bogus 1".into()]);
+        tmux.unknown_options.lock().unwrap().push("bogus".into());
+
+        app.do_create_session(0, 0, "one".into(), None).unwrap();
+        let status = app.status_message.clone().unwrap();
+        assert!(status.ends_with("tmux rejected tmux_defaults: bogus 1"), "{}", status);
+        let options = tmux.options.lock().unwrap().clone();
+        assert_eq!(options[1].1, ["status-left", "#S | %H:%M"]);
+
+        app.do_create_session(0, 0, "two".into(), None).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Session 'two' created"));
+        assert_eq!(tmux.options.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn start_work_makes_a_dev_session_running_the_default_command() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.workspace.projects[0].config =
+            Some(ProjectConfig { default_command: Some("npm run dev".into()), ..Default::default() });
+        app.start_work(0, "zzz");
+        let (name, status) = app.attach_next.take().unwrap();
+        assert_eq!(status, "Started work on zzz");
+        let (pi, wi, si) = app.workspace.find_session(&name).unwrap();
+        let wt = &app.workspace.projects[pi].worktrees[wi];
+        assert_eq!(wt.branch, "zzz");
+        assert_eq!(wt.sessions[si].display_name, "dev");
+        assert_eq!(tmux.sent.lock().unwrap().last(), Some(&(name, "npm run dev".to_string())));
+        assert_eq!(selected_session(&app).as_deref(), Some("dev"));
+
+        // The branch is taken now: nothing is made, and the status says why.
+        app.start_work(0, "zzz");
+        assert!(app.attach_next.is_none());
+        assert!(app.status_message.as_deref().unwrap().starts_with("Start work: no worktree for 'zzz'"));
+
+        // With a postCreate hook the session waits for it, and the UI doesn't.
+        let hooked = PathBuf::from(REPO).with_file_name("app-installed");
+        std::fs::create_dir_all(&hooked).unwrap();
+        app.workspace.projects[0].config = Some(ProjectConfig {
+            default_command: Some("npm run dev".into()),
+            post_create: Some("sleep 0.2; echo installed".into()),
+            ..Default::default()
+        });
+        app.start_work(0, "installed");
+        assert!(app.attach_next.is_none());
+        assert!(app.post_create.is_some());
+        let start = std::time::Instant::now();
+        while app.post_create.is_some() && start.elapsed() < std::time::Duration::from_secs(10) {
+            app.poll_post_create();
+        }
+        let Mode::GitOutput { text, .. } = &app.mode else { panic!("expected hook output") };
+        assert!(text.contains("installed\n"), "{}", text);
+        let (name, status) = app.attach_next.take().unwrap();
+        assert_eq!(status, "Started work on installed");
+        assert_eq!(tmux.sent.lock().unwrap().last(), Some(&(name, "npm run dev".to_string())));
+        std::fs::remove_dir_all(&hooked).unwrap();
+    }
+
+    #[test]
+    fn kill_stale_offers_only_old_idle_shells() {
+        let (git, tmux) = fixture();
+        let now = super::frecency::now_secs();
+        let old = now - 10 * 86_400;
+        tmux.set_status("app-app-b", SessionStatus { created_ts: old, ..Default::default() });
+        tmux.set_status("app-app-c", SessionStatus { created_ts: old, ..running() });
+        tmux.set_status("app-feature-d", SessionStatus { created_ts: now - 86_400, ..Default::default() });
+        let mut app = app(&git, &tmux);
+        app.refresh_all().unwrap();
+
+        app.tree_selected = 0;
+        app.action_kill_stale();
+        let Mode::Confirm { message, pending: PendingAction::KillSessions { names }, .. } =
+            std::mem::replace(&mut app.mode, Mode::Normal)
+        else {
+            panic!("expected confirmation, got {:?}", app.status_message);
+        };
+        assert_eq!(message, "Kill 1 idle shell session older than 7d? (b)");
+        assert_eq!(names, ["app-app-b"]);
+        app.do_kill_sessions(names).unwrap();
+        assert!(!tmux.session_names().iter().any(|n| n == "app-app-b"));
+        assert_eq!(app.status_message.as_deref(), Some("Killed 1 stale session"));
+
+        app.config.stale_session_days = Some(0);
+        app.action_kill_stale();
+        assert!(matches!(app.mode, Mode::Normal), "never without the setting");
+    }
+
+    #[test]
+    fn alias_refuses_clashes_and_suggests_others() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.config.add_project("other".into(), PathBuf::from("/tmp/wsx-test/other"));
+        app.config.set_alias(&PathBuf::from("/tmp/wsx-test/other"), "x", "auth");
+        app.config.set_alias(&PathBuf::from("/tmp/wsx-test/other"), "y", "feature");
+        assert_eq!(app.alias_suggestions(0, 0), ["auth"], "'feature' is the other worktree's slug");
+
+        app.do_apply_alias(0, 0, "feature".into()).unwrap();
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Alias 'feature' clashes with worktree 'app-feature' — pick another")
+        );
+        assert_eq!(app.workspace.projects[0].worktrees[0].alias, None);
+        assert!(app.config.projects[0].aliases.is_empty(), "nothing saved");
+    }
+
+    #[test]
+    fn plus_name_adds_window_to_primary_session() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.do_create_session(0, 0, "+logs".into(), Some("tail -f log".into())).unwrap();
+        assert_eq!(tmux.session_names().len(), 4, "no new session");
+        assert_eq!(app.status_message.as_deref(), Some("Window 1 added to 'a'"));
+        assert_eq!(tmux.sent.lock().unwrap()[0], ("app-app-a:1".into(), "tail -f log".into()));
+
+        // Flat order: project, main, a, a:0, a:1, b, …
+        assert_eq!(app.tree_selected, 4, "cursor lands on the new window");
+        assert_eq!(selected_session(&app).as_deref(), Some("a"), "window rows select their session");
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a:1"));
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, pending: PendingAction::KillWindow { target, .. }, .. } = &app.mode
+        else {
+            panic!("expected kill-window confirmation");
+        };
+        assert_eq!(message, "Close window 'logs' of 'a'?");
+        assert_eq!(target, "app-app-a:1");
+    }
+
+    #[test]
+    fn first_run_offers_the_repos_tmux_sessions_run_in() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.offer_session_import();
+        assert!(app.import_rx.is_none(), "projects already registered");
+
+        app.config.projects.clear();
+        app.offer_session_import();
+        while app.import_rx.is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.poll_session_import();
+        }
+        let Mode::ImportProjects { picker, .. } = &app.mode else {
+            panic!("expected the import picker");
+        };
+        assert_eq!(picker.items.len(), 2, "the main repo and the feature worktree (not linked on disk)");
+        assert!(picker.items[0].starts_with("[x] ") && picker.items[0].ends_with("app · 3 sessions"), "{}", picker.items[0]);
+
+        app.dispatch(Action::StartWork, &mut term).unwrap();
+        app.dispatch(Action::NavigateDown, &mut term).unwrap();
+        app.dispatch(Action::StartWork, &mut term).unwrap();
+        let Mode::ImportProjects { picker, chosen, .. } = &app.mode else {
+            panic!("expected the import picker");
+        };
+        assert_eq!(chosen, &[false, false]);
+        assert!(picker.items[0].starts_with("[ ] "));
+        app.dispatch(Action::Select, &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("Nothing imported — p adds a project"));
+        assert!(app.config.projects.is_empty());
+    }
+
+    #[test]
+    fn braces_cycle_the_previewed_window() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.do_create_session(0, 0, "+logs".into(), None).unwrap();
+        app.tree_selected = 2;
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a"), "tmux's current window");
+
+        app.dispatch(Action::CycleWindow(1), &mut term).unwrap();
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a:0"));
+        app.dispatch(Action::CycleWindow(1), &mut term).unwrap();
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a:1"));
+        assert_eq!(app.status_message.as_deref(), Some("Previewing window 1: logs — Enter attaches there"));
+        app.dispatch(Action::CycleWindow(1), &mut term).unwrap();
+        assert_eq!(app.previewed_window(), None, "wraps back to the current window");
+        app.dispatch(Action::CycleWindow(-1), &mut term).unwrap();
+        assert_eq!(app.previewed_window(), Some(1));
+
+        app.tree_selected = 5;
+        app.dispatch(Action::CycleWindow(1), &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("'b' has only one window"));
+        app.tree_selected = 2;
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a:1"), "kept per session");
+
+        tmux.kill_window("app-app-a:0").unwrap();
+        app.refresh_activity();
+        assert_eq!(app.selected_target().as_deref(), Some("app-app-a:1"), "the same window, not the same slot");
+        tmux.kill_window("app-app-a:1").unwrap();
+        app.refresh_activity();
+        assert!(app.preview_windows.is_empty(), "forgotten once the window closes");
+    }
+
+    #[test]
+    fn git_layers_follow_their_worktree_when_a_refresh_reorders_the_tree() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let feature = Path::new(REPO).with_file_name("app-feature");
+        git.add_stash(&feature, "WIP on feature: wip");
+        let (pi, fi) = app.workspace.find_worktree(&feature).unwrap();
+        app.select_worktree(pi, fi);
+        app.dispatch(Action::GitPopup, &mut term).unwrap();
+        app.workspace.projects[pi].worktrees.swap(0, fi);
+        app.dispatch(Action::InputChar('s'), &mut term).unwrap();
+        let Mode::Stashes { worktree, entries, .. } = &app.mode else { panic!("expected the stash list") };
+        assert_eq!(worktree, &feature);
+        assert_eq!(entries.len(), 1);
+
+        app.workspace.projects[pi].worktrees.retain(|w| w.path != feature);
+        app.dispatch(Action::InputChar('j'), &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("That item is gone — the workspace changed"));
+    }
+
+    #[test]
+    fn esc_finds_stacked_layers_by_path_after_a_refresh() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let feature = Path::new(REPO).with_file_name("app-feature");
+        git.add_stash(&feature, "WIP on feature: wip");
+        let (pi, fi) = app.workspace.find_worktree(&feature).unwrap();
+        app.select_worktree(pi, fi);
+        app.dispatch(Action::GitPopup, &mut term).unwrap();
+        app.dispatch(Action::InputChar('s'), &mut term).unwrap();
+        app.dispatch(Action::InputChar('s'), &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::GitOutput { .. }));
+
+        app.workspace.projects[pi].worktrees.swap(0, fi);
+        app.dispatch(Action::InputEscape, &mut term).unwrap();
+        let Mode::Stashes { worktree, .. } = &app.mode else { panic!("back to the stash list") };
+        assert_eq!(worktree, &feature);
+        app.dispatch(Action::InputChar('s'), &mut term).unwrap();
+
+        app.workspace.projects[pi].worktrees.retain(|w| w.path != feature);
+        app.dispatch(Action::InputEscape, &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Normal), "the list and popup of a removed worktree are skipped");
+    }
+
+    #[test]
+    fn stash_manager_pops_drops_and_shows() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let repo = Path::new(REPO);
+        app.open_stashes(0, 0);
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("No stashes"));
+
+        git.add_stash(repo, "On main: older");
+        git.add_stash(repo, "WIP on feature: a sibling worktree's");
+        git.add_stash(repo, "WIP on main: newer");
+        app.open_stashes(0, 0);
+        app.dispatch_stashes(Action::InputChar('s')).unwrap();
+        let Mode::GitOutput { title, text, .. } = &app.mode else { panic!("expected diffstat") };
+        assert_eq!(title, "stash@{0} --stat");
+        assert!(text.contains("1 file changed"));
+        app.dispatch_git_output(Action::InputEscape);
+
+        app.open_stashes(0, 0);
+        app.dispatch_stashes(Action::NavigateDown).unwrap();
+        app.dispatch_stashes(Action::InputChar('p')).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("stash pop: stash@{2}"));
+        let Mode::Stashes { entries, .. } = &app.mode else { panic!("list stays open") };
+        assert_eq!(entries.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["WIP on main: newer"]);
+
+        app.dispatch_stashes(Action::InputChar('d')).unwrap();
+        let Mode::Confirm { pending: PendingAction::DropStash { index: 0, .. }, danger: true, .. } = app.mode
+        else {
+            panic!("drop asks first");
+        };
+        app.do_stash(0, 0, StashOp::Drop, 0).unwrap();
+        assert_eq!(git.stash_list(repo, "feature").len(), 1, "only main's stashes were listed");
+        assert!(git.stash_list(repo, "main").is_empty());
+        assert!(matches!(app.mode, Mode::Normal), "popup closes when the last stash goes");
+    }
+
+    #[test]
+    fn diff_popup_steps_through_the_modified_files() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 1;
+        app.action_view_diff();
+        assert_eq!(app.status_message.as_deref(), Some("Git info not loaded yet"));
+
+        git.set_tracking(Path::new(REPO), 0, 0);
+        let mut info = git.git_info(Path::new(REPO), "main").unwrap();
+        app.workspace.projects[0].worktrees[0].git_info = Some(info.clone());
+        app.action_view_diff();
+        assert_eq!(app.status_message.as_deref(), Some("No modified files"));
+
+        info.modified_files = vec!["src/a.rs".into(), "\"logo.png\"".into(), "old.rs -> new.rs".into()];
+        app.workspace.projects[0].worktrees[0].git_info = Some(info);
+        app.action_view_diff();
+        let Mode::DiffFiles { files, .. } = &app.mode else { panic!("expected the file pick") };
+        assert_eq!(files, &["src/a.rs", "logo.png", "new.rs"], "a rename shows its new name");
+        app.dispatch_diff_files(Action::NavigateDown);
+        app.dispatch_diff_files(Action::Select);
+        let shown = |app: &App| {
+            let Mode::Diff { files, index, text, .. } = &app.mode else { panic!("expected the diff popup") };
+            (files[*index].clone(), text.clone())
+        };
+        assert_eq!(shown(&app), ("logo.png".into(), "Binary file — no text diff".into()));
+        app.dispatch_diff(Action::JumpProjectDown);
+        assert_eq!(shown(&app).0, "new.rs");
+        app.dispatch_diff(Action::JumpProjectDown);
+        assert_eq!(shown(&app), ("src/a.rs".into(), "diff --git a/src/a.rs b/src/a.rs\n+src/a.rs changed".into()));
+        app.dispatch_diff(Action::JumpProjectUp);
+        assert_eq!(shown(&app).0, "new.rs", "wraps around");
+        app.dispatch_diff(Action::InputEscape);
+        assert!(matches!(app.mode, Mode::DiffFiles { .. }), "Esc goes back to the file pick");
+        app.dispatch_diff_files(Action::InputEscape);
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    /// A terminal drawing nowhere, for driving `dispatch` the way the run loop does.
+    fn terminal() -> crate::tui::Tui {
+        let backend = ratatui::backend::CrosstermBackend::new(Box::new(std::io::sink()) as Box<dyn std::io::Write>);
+        let viewport = ratatui::Viewport::Fixed(Rect::new(0, 0, 80, 24));
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport }).unwrap()
+    }
+
+    #[test]
+    fn escape_backs_out_one_layer_and_q_closes_popups() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        git.add_stash(Path::new(REPO), "WIP on main: wip");
+        app.tree_selected = 1;
+        let mut press = |app: &mut App, action| app.dispatch(action, &mut term).unwrap();
+
+        press(&mut app, Action::GitPopup);
+        press(&mut app, Action::InputChar('s'));
+        press(&mut app, Action::InputChar('s'));
+        assert!(matches!(app.mode, Mode::GitOutput { .. }));
+        press(&mut app, Action::InputEscape);
+        assert!(matches!(app.mode, Mode::Stashes { .. }), "Esc returns to the stash list the diffstat came from");
+        press(&mut app, Action::InputChar('d'));
+        press(&mut app, Action::InputChar('n'));
+        assert!(matches!(app.mode, Mode::Stashes { .. }), "declining a confirm keeps the list");
+        press(&mut app, Action::InputChar('d'));
+        press(&mut app, Action::InputChar('y'));
+        assert!(matches!(app.mode, Mode::GitPopup { .. }), "dropping the last stash closes only the list");
+        press(&mut app, Action::InputChar('r'));
+        press(&mut app, Action::InputEscape);
+        assert!(matches!(app.mode, Mode::GitPopup { .. }), "a cancelled prompt returns to the popup");
+        press(&mut app, Action::InputChar('q'));
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(app.mode_stack.is_empty());
+
+        press(&mut app, Action::Edit);
+        press(&mut app, Action::InputChar('b'));
+        press(&mut app, Action::InputEscape);
+        assert!(matches!(app.mode, Mode::Config { .. }));
+        press(&mut app, Action::InputChar('q'));
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(!app.quit_requested, "q only quits from the tree, in the run loop");
+    }
+
+    #[test]
+    fn mouse_capture_follows_the_config_and_m() {
+        let (git, tmux) = fixture();
+        let mut config = GlobalConfig::default();
+        config.mouse = Some(false);
+        let mut app = App::with_workspace(&Args::default(), config, WorkspaceState::default(), 0, git.clone(), tmux.clone());
+        let mut term = terminal();
+        assert!(!app.mouse);
+        app.dispatch(Action::ToggleMouse, &mut term).unwrap();
+        assert!(app.mouse);
+        assert_eq!(app.status_message.as_deref(), Some("mouse: on"));
+        app.dispatch(Action::ToggleMouse, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("mouse: off (terminal selection enabled)"));
+        assert!(!app.config.mouse(), "the config keeps what the file says");
+    }
+
+    #[test]
+    fn respawn_reruns_the_start_command_in_the_same_session() {
+        let (git, tmux) = fixture();
+        tmux.set_session_option("app-app-a", "@wsx_cmd", "npm run dev");
+        tmux.set_pane("app-app-a", "node", false);
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.tree_selected = 1;
+        app.dispatch(Action::Respawn, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Select a session to respawn"));
+
+        app.tree_selected = 2;
+        app.dispatch(Action::Respawn, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Sent Ctrl+C to 'a' — waiting for its shell"));
+        app.poll_respawn();
+        assert_eq!(app.status_message.as_deref(), Some("Respawned 'a': Ctrl+C, then re-ran `npm run dev`"));
+        let sent: Vec<String> = tmux.sent.lock().unwrap().iter().map(|(_, k)| k.clone()).collect();
+        assert_eq!(sent, ["C-c", "npm run dev"]);
+        assert_eq!(selected_session(&app).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn respawn_asks_before_killing_a_command_that_ignores_ctrl_c() {
+        let (git, tmux) = fixture();
+        tmux.set_session_option("app-app-a", "@wsx_cmd", "nvim");
+        tmux.set_pane("app-app-a", "nvim", false);
+        tmux.ignores_ctrl_c.lock().unwrap().push("app-app-a".into());
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.tree_selected = 2;
+        app.dispatch(Action::Respawn, &mut term).unwrap();
+        app.poll_respawn();
+        assert!(matches!(app.mode, Mode::Normal), "still within the wait");
+
+        app.respawning.as_mut().unwrap().deadline = Instant::now();
+        app.poll_respawn();
+        let Mode::Confirm { danger: true, message, .. } = &app.mode else { panic!("expected a danger confirm") };
+        assert_eq!(message, "'a' is still running after Ctrl+C. Kill it and start `nvim` over?");
+        assert!(tmux.respawned.lock().unwrap().is_empty(), "nothing killed before the answer");
+        app.dispatch(Action::InputChar('y'), &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Respawned 'a': killed it and started over"));
+        assert_eq!(tmux.respawned.lock().unwrap().len(), 1);
+        let sent: Vec<String> = tmux.sent.lock().unwrap().iter().map(|(_, k)| k.clone()).collect();
+        assert_eq!(sent, ["C-c", "nvim"], "typed into the new shell");
+    }
+
+    #[test]
+    fn branch_off_moves_the_changes_into_the_new_worktree() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let main = PathBuf::from(REPO);
+        app.tree_selected = 1;
+        let mut press = |app: &mut App, action| app.dispatch(action, &mut term).unwrap();
+
+        press(&mut app, Action::GitPopup);
+        press(&mut app, Action::InputChar('b'));
+        assert_eq!(app.status_message.as_deref(), Some("No uncommitted changes to branch off with"));
+        git.set_dirty(&main, 3);
+        press(&mut app, Action::InputChar('b'));
+        assert!(matches!(app.mode, Mode::Input { context: InputContext::BranchOff { .. }, .. }));
+        for c in "spike".chars() {
+            press(&mut app, Action::InputChar(c));
+        }
+        press(&mut app, Action::Select);
+        let Mode::Confirm { message, .. } = &app.mode else { panic!("expected a confirm") };
+        assert_eq!(message, "Create worktree 'spike'? Takes the uncommitted changes in app along.");
+        press(&mut app, Action::InputChar('y'));
+
+        let spike = main.with_file_name("app-spike");
+        assert_eq!(app.status_message.as_deref(), Some("Created worktree: spike with 3 uncommitted file(s) from app"));
+        assert_eq!((git.uncommitted_changes(&main).unwrap(), git.uncommitted_changes(&spike).unwrap()), (0, 3));
+        let (pi, wi) = app.workspace.find_worktree(&spike).unwrap();
+        assert_eq!(app.current_selection(), Selection::Worktree(pi, wi));
+        assert!(app.error.is_none());
+    }
+
+    #[test]
+    fn ctrl_c_twice_quits_from_any_mode() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.tree_selected = 1;
+        app.dispatch(Action::GitPopup, &mut term).unwrap();
+        app.dispatch(Action::InputChar('r'), &mut term).unwrap();
+        app.dispatch(Action::Interrupt, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Press Ctrl+C again to quit"));
+        assert!(!app.quit_requested);
+        assert!(matches!(app.mode, Mode::Input { .. }), "the first press leaves the prompt open");
+
+        app.interrupted_at = Some(std::time::Instant::now() - std::time::Duration::from_secs(2));
+        app.dispatch(Action::Interrupt, &mut term).unwrap();
+        assert!(!app.quit_requested, "a press after the window starts over");
+        app.dispatch(Action::Interrupt, &mut term).unwrap();
+        assert!(app.quit_requested);
+    }
+
+    #[test]
+    fn git_ops_wait_for_their_repository() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let feature = PathBuf::from(REPO).with_file_name("app-feature");
+        git.set_merged(Path::new(REPO), "feature");
+        let fetch = app.repo_queue.acquire(Path::new(REPO), "fetch", Duration::ZERO).unwrap();
+        app.do_git_op(0, 1, GitOp::Pull, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("pull: queued behind 1 git operation on /tmp/wsx-test/app"));
+        app.tree_selected = 5; // feature worktree
+        app.action_clean().unwrap();
+        assert_eq!(app.queued_git(), 2, "clean waits behind the pull, not running alongside it");
+        assert!(!app.loading, "nothing blocks while they wait");
+
+        app.poll_git_queue();
+        assert_eq!(app.queued_git(), 2);
+        drop(fetch);
+        app.poll_git_queue();
+        assert_eq!(app.queued_git(), 0);
+        assert!(app.workspace.find_worktree(&feature).is_none(), "the clean ran after the pull");
+        assert!(app.repo_queue.lanes().is_empty());
+
+        let _stuck = app.repo_queue.acquire(Path::new(REPO), "fetch", Duration::ZERO).unwrap();
+        app.do_git_op(0, 0, GitOp::Push, &mut term).unwrap();
+        app.git_queue[0].since -= crate::git::queue::QUEUE_TIMEOUT;
+        app.poll_git_queue();
+        assert_eq!(app.queued_git(), 0);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("/tmp/wsx-test/app is still busy with another git operation — push dropped, try again")
+        );
+    }
+
+    #[test]
+    fn git_ops_reload_the_worktrees_they_touch() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let repo = Path::new(REPO);
+        let feature = repo.with_file_name("app-feature");
+        let fi = app.workspace.find_worktree(&feature).unwrap().1;
+        let ahead_behind = |app: &App, path: &Path| {
+            let (pi, wi) = app.workspace.find_worktree(path).unwrap();
+            app.workspace.worktree(pi, wi).unwrap().git_info.as_ref().map(|i| (i.ahead, i.behind))
+        };
+        git.set_tracking(repo, 0, 0);
+        git.set_tracking(&feature, 1, 2);
+        app.reload_git_info(0, 0);
+        app.reload_git_info(0, fi);
+
+        app.run_git_op(0, fi, GitOp::Pull);
+        assert_eq!(ahead_behind(&app, &feature), Some((1, 0)));
+        assert!(app.workspace.worktree(0, fi).unwrap().last_fetch_ok.is_some(), "a pull counts as a fetch");
+        app.run_git_op(0, fi, GitOp::Push);
+        assert_eq!(ahead_behind(&app, &feature), Some((0, 0)));
+        app.run_git_op(0, fi, GitOp::MergeFrom("main".into()));
+        assert_eq!(ahead_behind(&app, &feature), Some((1, 0)));
+        git.set_tracking(&feature, 1, 3);
+        app.run_git_op(0, fi, GitOp::PullRebase("main".into()));
+        assert_eq!(ahead_behind(&app, &feature), Some((1, 0)));
+        app.run_git_op(0, fi, GitOp::MergeInto("main".into()));
+        assert_eq!(ahead_behind(&app, repo), Some((1, 0)), "the target worktree reloads too");
+
+        git.add_stash(&feature, "WIP on feature: wip");
+        app.reload_git_info(0, fi);
+        let (_, fi) = app.workspace.find_worktree(&feature).unwrap();
+        assert_eq!(app.workspace.worktree(0, fi).unwrap().git_info.as_ref().unwrap().stashes.len(), 1);
+        app.do_stash(0, fi, StashOp::Drop, 0).unwrap();
+        let (_, fi) = app.workspace.find_worktree(&feature).unwrap();
+        assert!(app.workspace.worktree(0, fi).unwrap().git_info.as_ref().unwrap().stashes.is_empty());
+
+        let created = repo.with_file_name("app-new");
+        git.set_tracking(&created, 0, 0);
+        app.do_create_worktree(0, "new".into()).unwrap();
+        assert_eq!(ahead_behind(&app, &created), Some((0, 0)), "loaded without a capture tick");
+
+        git.set_merged(repo, "feature");
+        git.set_tracking(repo, 0, 0);
+        app.do_clean_worktree(0, fi, false).unwrap();
+        assert_eq!(ahead_behind(&app, repo), Some((0, 0)));
+    }
+
+    #[test]
+    fn clone_runs_one_at_a_time_and_reports_failure() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        // The fake refuses to clone over a repo it already has, like a non-empty directory.
+        app.start_clone("git@example.com:me/app.git".into(), PathBuf::from(REPO));
+        app.start_clone("git@example.com:me/other.git".into(), PathBuf::from("/tmp/wsx-test/other"));
+        assert_eq!(app.status_message.as_deref(), Some("Still cloning app — one clone at a time"));
+
+        let start = std::time::Instant::now();
+        while app.cloning.is_some() && start.elapsed() < std::time::Duration::from_secs(5) {
+            app.poll_clone();
+        }
+        assert!(app.cloning.is_none());
+        let error = &app.error.as_ref().unwrap().message;
+        assert!(error.starts_with("clone failed: fatal: destination path"), "{}", error);
+        assert_eq!(app.workspace.projects.len(), 1);
+    }
+
+    #[test]
+    fn cached_projects_wait_for_their_live_load() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let live = app.workspace.projects[0].clone();
+        // The cache only knew `feature`; its session `d` is selected.
+        let cached = &mut app.workspace.projects[0];
+        cached.syncing = true;
+        cached.worktrees.remove(0);
+        app.rebuild_flat();
+        app.tree_selected = 2;
+        assert_eq!(selected_session(&app).as_deref(), Some("d"));
+        app.dispatch(Action::Clean, &mut term).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("'app' is still loading — try again in a moment"));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        app.sync_rx = Some(rx);
+        tx.send(live).unwrap();
+        app.poll_sync();
+        assert!(app.sync_rx.is_none(), "every project is in");
+        let p = &app.workspace.projects[0];
+        assert!(!p.syncing);
+        assert_eq!(p.worktrees.len(), 2);
+        assert_eq!(selected_session(&app).as_deref(), Some("d"), "the cursor stays on its row");
+        assert_eq!(app.tree_selected, 6);
+    }
+
+    #[test]
+    fn setup_checks_report_when_they_finish() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let (tx, rx) = std::sync::mpsc::channel();
+        app.doctor_rx = Some(rx);
+        app.poll_doctor();
+        assert!(app.doctor_rx.is_some() && app.status_message.is_none(), "still running");
+
+        let finding = crate::doctor::Finding { severity: crate::doctor::Severity::Warning, message: "$EDITOR is not set".into() };
+        tx.send(vec![finding]).unwrap();
+        app.poll_doctor();
+        assert!(app.doctor_rx.is_none());
+        assert_eq!(app.status_message.as_deref(), Some("1 setup problem — press D for doctor"));
+    }
+
+    #[test]
+    fn repeated_refresh_errors_stick_and_count_until_esc() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        // exists on disk, but the fake has no repo there
+        let api = std::env::temp_dir();
+        let mut project = app.workspace.projects[0].clone();
+        (project.name, project.path, project.worktrees) = ("api".into(), api.clone(), vec![]);
+        app.workspace.projects.push(project);
+        for _ in 0..3 {
+            app.refresh_all().unwrap();
+        }
+        let error = app.error.clone().unwrap();
+        assert_eq!(error.message, format!("refresh failing for project api: not a git repository: {}", api.display()));
+        assert_eq!(error.count, 3);
+        assert!(app.status_message.is_none(), "not the 4-second status line");
+
+        app.last_search = Some("app".into());
+        app.dispatch(Action::InputEscape, &mut term).unwrap();
+        assert!(app.error.is_none());
+        assert!(app.last_search.is_some(), "the first Esc only dismisses the error");
+    }
+
+    #[test]
+    fn post_create_streams_into_a_popup_and_esc_cancels_it() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let wait = |app: &mut App| {
+            let start = std::time::Instant::now();
+            while app.post_create.is_some() && start.elapsed() < std::time::Duration::from_secs(10) {
+                app.poll_post_create();
+            }
+        };
+        let (hooked, slow) = (PathBuf::from(REPO).with_file_name("app-hooked"), PathBuf::from(REPO).with_file_name("app-slow"));
+        for dir in [&hooked, &slow] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+
+        let hook = |cmd: &str| Some(ProjectConfig { post_create: Some(cmd.into()), ..Default::default() });
+        app.workspace.projects[0].config = hook("echo installing; echo warn >&2");
+        app.do_create_worktree(0, "hooked".into()).unwrap();
+        wait(&mut app);
+        let Mode::GitOutput { title, text, .. } = &app.mode else { panic!("expected hook output") };
+        assert_eq!(title, "postCreate · hooked");
+        assert!(text.starts_with("$ echo installing;"), "{}", text);
+        assert!(text.contains("installing\n") && text.contains("warn\n"), "{}", text);
+        assert!(text.ends_with("✓ postCreate finished"), "{}", text);
+        assert_eq!(app.status_message.as_deref(), Some("Created worktree: hooked"));
+        app.dispatch_git_output(Action::InputEscape);
+        assert!(matches!(app.mode, Mode::Normal));
+
+        app.workspace.projects[0].config = hook("echo waiting; sleep 30");
+        app.do_create_worktree(0, "slow".into()).unwrap();
+        app.dispatch_git_output(Action::InputEscape);
+        assert!(matches!(app.mode, Mode::GitOutput { .. }), "stays open until the hook is gone");
+        wait(&mut app);
+        assert!(app.post_create.is_none());
+        assert_eq!(app.status_message.as_deref(), Some("Created worktree: slow — postCreate cancelled, hook incomplete"));
+        for dir in [&hooked, &slow] {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn post_create_runs_are_recorded_and_can_be_rerun() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let wait = |app: &mut App| {
+            let start = std::time::Instant::now();
+            while app.post_create.is_some() && start.elapsed() < std::time::Duration::from_secs(10) {
+                app.poll_post_create();
+            }
+        };
+        let dir = PathBuf::from(REPO).with_file_name("app-rerun");
+        std::fs::create_dir_all(&dir).unwrap();
+        let hook = |cmd: &str| Some(ProjectConfig { post_create: Some(cmd.into()), ..Default::default() });
+        app.workspace.projects[0].config = hook("echo first");
+        app.do_create_worktree(0, "rerun".into()).unwrap();
+        wait(&mut app);
+        app.back_out();
+        let hook_run = |app: &App| app.workspace.setup[&dir].hook.clone().unwrap();
+        assert_eq!((hook_run(&app).command.as_str(), hook_run(&app).tail), ("echo first", vec!["first".to_string()]));
+
+        let wi = app.workspace.projects[0].worktrees.iter().position(|w| w.path == dir).unwrap();
+        app.workspace.projects[0].config = hook("echo again; exit 3");
+        app.rerun_post_create(0, wi);
+        wait(&mut app);
+        let run = hook_run(&app);
+        assert_eq!(run.tail, ["again"]);
+        assert!(run.error.is_some(), "a non-zero exit is recorded");
+        assert!(app.error.is_some(), "a failed re-run goes to the banner");
+        app.back_out();
+
+        app.show_last_post_create(0, wi);
+        let Mode::GitOutput { title, text, .. } = &app.mode else { panic!("expected the last run") };
+        assert_eq!(title, "Last postCreate · app-rerun");
+        assert!(text.starts_with("$ echo again; exit 3\nagain\n\n✗ "), "{}", text);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hidden_worktrees_stay_out_of_the_tree_and_out_of_reach() {
+        let (git, tmux) = fixture();
+        let repo = Path::new(REPO);
+        git.add_worktree(repo, "ci-cache-1");
+        git.set_merged(repo, "ci-cache-1");
+        let mut app = app(&git, &tmux);
+        app.workspace.projects[0].config =
+            Some(ProjectConfig { worktree_hide: vec!["app-ci-cache-*".into()], ..Default::default() });
+        app.refresh_all().unwrap();
+        assert_eq!(app.flat().len(), 7, "project, main + 3 sessions, feature + d");
+        assert!(app.workspace.projects[0].worktrees[2].hidden, "matched by directory name");
+
+        app.tree_selected = 0;
+        app.action_clean().unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("No merged worktrees to clean"));
+
+        app.action_toggle_hidden();
+        assert_eq!(app.status_message.as_deref(), Some("Showing 1 hidden worktree — d, A and c stay off"));
+        assert_eq!(app.flat().len(), 8);
+        app.tree_selected = 7;
+        app.action_delete().unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("'ci-cache-1' is hidden by worktree.hide — wsx leaves it alone"));
+        app.action_toggle_hidden();
+        assert_eq!(app.flat().len(), 7);
+        assert_eq!(git.branches(repo), ["main", "feature", "ci-cache-1"]);
+    }
+
+    #[test]
+    fn locked_worktrees_are_refused_up_front_until_unlocked() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        git.set_merged(Path::new(REPO), "feature");
+        app.tree_selected = 5; // feature worktree
+
+        app.action_toggle_lock().unwrap();
+        let Mode::Input { context: InputContext::LockWorktree { worktree }, .. } =
+            std::mem::replace(&mut app.mode, Mode::Normal)
+        else {
+            panic!("expected the reason prompt")
+        };
+        let (pi, wi) = app.resolve_worktree(&worktree).unwrap();
+        app.do_lock_worktree(pi, wi, "on a USB disk".into()).unwrap();
+        app.refresh_all().unwrap();
+        assert_eq!(app.workspace.projects[0].worktrees[1].locked.as_deref(), Some("on a USB disk"), "read back from git");
+
+        let refused = Some("'feature' locked: on a USB disk — unlock first (L)");
+        app.action_delete().unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), refused);
+        app.action_archive();
+        assert_eq!(app.status_message.as_deref(), refused);
+        app.action_clean().unwrap();
+        assert_eq!(app.status_message.as_deref(), refused);
+        app.tree_selected = 0;
+        app.action_clean().unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("No merged worktrees to clean (skipped 1 locked)"));
+
+        app.tree_selected = 5;
+        app.action_toggle_lock().unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Unlocked 'app-feature'"));
+        assert!(app.workspace.projects[0].worktrees[1].locked.is_none());
+        app.action_delete().unwrap();
+        assert!(matches!(app.mode, Mode::Confirm { .. }));
+    }
+
+    #[test]
+    fn archived_worktrees_restore_with_their_sessions_or_purge() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let repo = Path::new(REPO);
+        let feature = PathBuf::from(REPO).with_file_name("app-feature");
+        app.tree_selected = 5; // feature worktree
+
+        git.set_dirty(&feature, 2);
+        app.action_archive();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert_eq!(app.status_message.as_deref(), Some("'feature' has 2 uncommitted changes — commit or stash first"));
+        git.set_dirty(&feature, 0);
+        app.action_archive();
+        let Mode::Confirm { pending: pending @ PendingAction::ArchiveWorktree { .. }, .. } =
+            std::mem::replace(&mut app.mode, Mode::Normal)
+        else {
+            panic!("expected an archive confirm")
+        };
+        app.run_pending(pending).unwrap();
+        let tag = "wsx/archive/feature-20260101";
+        assert_eq!(app.status_message.as_deref(), Some(&*format!("Archived: feature as {}", tag)));
+        assert!(app.workspace.find_worktree(&feature).is_none());
+        assert!(!tmux.session_exists("app-feature-d"));
+        assert_eq!(git.deleted_branches(repo), ["feature"]);
+        assert_eq!(app.workspace.projects[0].archived[0].sessions, ["d"]);
+
+        // The "Archived" row follows the main worktree's sessions, collapsed.
+        assert_eq!(app.current_selection(), Selection::Archive(0, None));
+        app.nav_right();
+        app.nav_down();
+        assert_eq!(app.current_selection(), Selection::Archive(0, Some(0)));
+
+        app.do_restore_archived(0, tag.into()).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Restored worktree: feature"));
+        let (pi, wi) = app.workspace.find_worktree(&feature).expect("worktree is back");
+        assert_eq!(app.current_selection(), Selection::Worktree(pi, wi));
+        assert_eq!(app.workspace.projects[pi].worktrees[wi].sessions[0].display_name, "d");
+        assert!(app.workspace.projects[0].archived.is_empty());
+        assert!(git.archived_worktrees(repo).is_empty());
+
+        app.do_archive_worktree(pi, wi).unwrap();
+        app.do_purge_archived(0, tag.into()).unwrap();
+        assert!(app.workspace.projects[0].archived.is_empty());
+        assert!(git.archived_worktrees(repo).is_empty());
+        assert!(app.do_purge_archived(0, tag.into()).is_err(), "the tag is gone");
+    }
+
+    #[test]
+    fn a_branch_that_outlives_its_archive_blocks_the_restore() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let repo = Path::new(REPO);
+        let feature = PathBuf::from(REPO).with_file_name("app-feature");
+        git.set_undeletable(repo, "feature");
+        let (pi, wi) = app.workspace.find_worktree(&feature).unwrap();
+        app.do_archive_worktree(pi, wi).unwrap();
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Warning: branch 'feature' kept: error: cannot delete branch 'feature'")
+        );
+
+        let tag = "wsx/archive/feature-20260101";
+        let err = app.do_restore_archived(0, tag.into()).unwrap_err();
+        assert!(err.to_string().starts_with("A branch named 'feature' exists"), "{}", err);
+        assert!(app.workspace.find_worktree(&feature).is_none());
+        assert_eq!(git.archived_worktrees(repo)[0].tag, tag, "the tag still holds the commits");
+    }
+
+    #[test]
+    fn raw_names_switch_what_y_copies() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 2; // session a
+        assert_eq!(app.yank_value().as_deref(), Some("a"));
+        app.raw_names = true;
+        assert_eq!(app.yank_value().as_deref(), Some("app-app-a"));
+        app.tree_selected = 5; // feature worktree
+        assert_eq!(app.yank_value().as_deref(), Some("/tmp/wsx-test/app-feature"));
+        app.raw_names = false;
+        assert_eq!(app.yank_value().as_deref(), Some("app-feature"));
+        app.full_paths = true;
+        assert_eq!(app.yank_value().as_deref(), Some("/tmp/wsx-test/app-feature"));
+    }
+
+    #[test]
+    fn prompts_complete_from_free_branches_and_command_history() {
+        let (git, tmux) = fixture();
+        git.set_merged(Path::new(REPO), "old-topic");
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 0;
+        app.action_add_worktree().unwrap();
+        let Mode::Input { state, .. } = &app.mode else { panic!("expected input") };
+        assert_eq!(state.completions, ["old-topic"], "branches with a worktree are left out");
+
+        app.history.record("cargo test");
+        app.tree_selected = 2;
+        app.action_send_command();
+        let Mode::Input { state, .. } = &mut app.mode else { panic!("expected input") };
+        state.up();
+        assert_eq!(state.value(), "cargo test");
+    }
+
+    #[test]
+    fn new_session_form_keeps_both_fields_on_a_bad_name() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 1; // main worktree
+        app.action_add_session().unwrap();
+        let type_text = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.dispatch_form(Action::InputChar(c)).unwrap();
+            }
+        };
+        type_text(&mut app, "a.b");
+        app.dispatch_form(Action::InputTab).unwrap();
+        type_text(&mut app, "npm run dev");
+        app.dispatch_form(Action::Select).unwrap();
+        let Mode::Form { form, .. } = &app.mode else { panic!("form closed on a bad name") };
+        assert_eq!((form.focus, form.value(1)), (0, "npm run dev"));
+        assert!(form.error.as_deref().is_some_and(|e| e.contains("'.'")));
+
+        for _ in 0..3 {
+            app.dispatch_form(Action::InputBackspace).unwrap();
+        }
+        type_text(&mut app, "web");
+        app.dispatch_form(Action::Select).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(tmux.session_names().contains(&"app-app-web".to_string()));
+        assert_eq!(app.history.entries, ["npm run dev"]);
+    }
+
+    #[test]
+    fn prompts_and_confirms_follow_their_target_through_a_refresh() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let lib = PathBuf::from(REPO).with_file_name("lib");
+        git.add_repo(&lib, "main");
+        // Another project lands ahead of "app" while a prompt is open, shifting every index.
+        let shift = |app: &mut App| {
+            let mut project = app.workspace.projects[0].clone();
+            project.name = "lib".into();
+            project.path = lib.clone();
+            project.worktrees.clear();
+            app.workspace.projects.insert(0, project);
+            app.refresh_all().unwrap();
+        };
+
+        app.tree_selected = 5; // feature worktree
+        app.action_add_session().unwrap();
+        shift(&mut app);
+        app.dispatch_form(Action::InputChar('x')).unwrap();
+        app.dispatch_form(Action::Select).unwrap();
+        let (pi, wi, _) = app.workspace.find_session("app-feature-x").expect("made in feature");
+        assert_eq!((app.workspace.projects[pi].name.as_str(), wi), ("app", 1));
+
+        let (pi, wi, si) = app.workspace.find_session("app-feature-d").unwrap();
+        app.select_session(pi, wi, si, None);
+        app.action_delete().unwrap();
+        let Mode::Confirm { pending, .. } = std::mem::replace(&mut app.mode, Mode::Normal) else {
+            panic!("expected a kill confirm")
+        };
+        app.workspace.projects.remove(0);
+        app.refresh_all().unwrap();
+        app.run_pending(pending).unwrap();
+        assert!(!tmux.session_exists("app-feature-d"));
+        assert!(tmux.session_exists("app-feature-x") && tmux.session_exists("app-app-a"));
+
+        app.tree_selected = 2; // session a
+        app.action_delete().unwrap();
+        let Mode::Confirm { pending, .. } = std::mem::replace(&mut app.mode, Mode::Normal) else {
+            panic!("expected a kill confirm")
+        };
+        tmux.kill_session("app-app-a").unwrap();
+        app.refresh_all().unwrap();
+        let err = app.run_pending(pending).unwrap_err().to_string();
+        assert_eq!(err, "session 'app-app-a' is gone — the workspace changed");
+        assert!(tmux.session_exists("app-app-b"), "nothing else is killed in its place");
+    }
+
+    #[test]
+    fn own_session_is_never_attached_and_killing_it_warns() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.own_session = Some("app-app-a".into());
+        assert!(app.refuse_own_session(0, 0, 0));
+        assert_eq!(app.status_message.as_deref(), Some("'a' is the session wsx runs in — already here"));
+        assert!(!app.refuse_own_session(0, 0, 1));
+
+        app.tree_selected = 2; // session a
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, danger, .. } = &app.mode else { panic!("expected confirm") };
+        assert!(message.ends_with("This will terminate wsx itself!") && *danger);
+        app.mode = Mode::Normal;
+        app.tree_selected = 3; // session b
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, danger, .. } = &app.mode else { panic!("expected confirm") };
+        assert_eq!((message.as_str(), *danger), ("Kill session 'b'?", false));
+    }
+
+    #[test]
+    fn sessions_keep_their_names_when_the_worktree_gets_an_alias() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let feature = Path::new(REPO).with_file_name("app-feature");
+        let fi = app.workspace.find_worktree(&feature).unwrap().1;
+        app.do_create_session(0, fi, "dev".into(), None).unwrap();
+        let names = |app: &App| -> Vec<(String, String)> {
+            let (pi, wi) = app.workspace.find_worktree(&feature).unwrap();
+            let wt = app.workspace.worktree(pi, wi).unwrap();
+            wt.sessions.iter().map(|s| (s.name.clone(), s.display_name.clone())).collect()
+        };
+        let before = names(&app);
+        assert!(before.iter().any(|(_, display)| display == "dev"), "{:?}", before);
+
+        // not `do_apply_alias`, which saves the config file
+        crate::ops::set_alias(&mut app.config, &PathBuf::from(REPO), "feature", "login");
+        app.refresh_all().unwrap();
+        let (pi, wi) = app.workspace.find_worktree(&feature).unwrap();
+        assert_eq!(app.workspace.worktree(pi, wi).unwrap().alias.as_deref(), Some("login"));
+        assert_eq!(names(&app), before, "tmux and display names both stay");
+    }
+
+    #[test]
+    fn a_pasted_path_selects_its_worktree() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.dispatch(Action::LocatePath, &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Input { context: InputContext::LocatePath, .. }));
+        app.mode = Mode::Normal;
+
+        app.do_locate_path("/tmp/wsx-test/app-feature/src/main.rs:12:5");
+        assert!(matches!(app.current_selection(), Selection::Worktree(0, wi) if app.workspace.projects[0].worktrees[wi].branch == "feature"));
+        assert_eq!(app.status_message.as_deref(), Some("In 'app-feature': src/main.rs:12:5 (not on disk there)"));
+        app.do_locate_path("/tmp/wsx-test/app-gone/src/main.rs");
+        assert_eq!(app.status_message.as_deref(), Some("No registered worktree contains this path"));
+        app.do_locate_path("src/main.rs");
+        assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Give an absolute path")));
+
+        let scratch = std::env::temp_dir().join(format!("wsx-locate-app-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch).unwrap();
+        std::fs::write(scratch.join("notes.md"), "").unwrap();
+        git.add_worktree_at(Path::new(REPO), "scratch", &scratch);
+        app.refresh_all().unwrap();
+        app.do_locate_path(&format!("{}/notes.md", scratch.display()));
+        assert_eq!(app.located, Some((scratch.clone(), scratch.join("notes.md"))));
+        assert!(app.status_message.as_deref().is_some_and(|m| m.ends_with("notes.md — Ctrl+G again opens it")));
+        app.dispatch(Action::NavigateDown, &mut term).unwrap();
+        assert_eq!(app.located, None, "only the very next key opens it");
+        std::fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    #[test]
+    fn delete_confirm_says_how_long_ago_the_branch_forked() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let feature = Path::new(REPO).with_file_name("app-feature");
+        let fi = app.workspace.find_worktree(&feature).unwrap().1;
+        git.set_tracking(&feature, 0, 0);
+        git.set_diverged(&feature, "main", crate::model::frecency::now_secs() - 47 * 86_400, 23);
+        app.reload_git_info(0, fi);
+        app.select_worktree(0, fi);
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, .. } = &app.mode else { panic!("expected confirm") };
+        assert!(
+            message.contains("Kills 1 session. It diverged from main 47 days ago, 23 commits on main since."),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn polling_rests_while_wsx_is_hidden_and_catches_up_when_shown() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let sessions = |app: &App| app.workspace.projects[0].worktrees[0].sessions.len();
+        app.own_session = Some("app-app-a".into());
+        app.refresh_activity();
+        assert!(app.focus.hidden(), "no client on wsx's session");
+
+        tmux.add_session("app-app-e", Path::new(REPO));
+        app.rescan_timer.last = Instant::now() - Duration::from_secs(60);
+        app.tick().unwrap();
+        assert_eq!(sessions(&app), 3, "no rescan while hidden");
+
+        // The first probe finds it still hidden; the next one finds a client back on it.
+        let long_ago = Instant::now() - PROBE_INTERVAL * 2;
+        app.focus = Focus::default();
+        app.focus.lose(long_ago);
+        app.tick().unwrap();
+        assert!(app.focus.hidden() && sessions(&app) == 3);
+        tmux.set_status("app-app-a", SessionStatus { attached: true, ..running() });
+        app.focus = Focus::default();
+        app.focus.lose(long_ago);
+        app.tick().unwrap();
+        assert!(!app.focus.hidden());
+        assert_eq!(sessions(&app), 4, "one full refresh on the way back");
+        assert!(app.rescan_timer.last.elapsed() < Duration::from_secs(5), "the timers restart");
+    }
+
+    #[test]
+    fn the_event_stream_keeps_flowing_while_wsx_is_hidden() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let dir = std::env::temp_dir().join(format!("wsx-app-hidden-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+        std::fs::write(&log, "").unwrap();
+        app.own_session = Some("app-app-a".into());
+        app.refresh_activity();
+        assert!(app.focus.hidden(), "no client on wsx's session");
+
+        tmux.set_status("app-app-b", SessionStatus { has_bell: true, ..Default::default() });
+        app.hidden_activity_timer.last = Instant::now() - HIDDEN_ACTIVITY_INTERVAL;
+        app.tick().unwrap();
+        assert!(std::fs::read_to_string(&log).unwrap().is_empty(), "no listener, no polling");
+
+        app.events = crate::events::EventSink::open(&log).unwrap();
+        app.hidden_activity_timer.last = Instant::now() - HIDDEN_ACTIVITY_INTERVAL;
+        app.tick().unwrap();
+        assert!(app.focus.hidden(), "still hidden");
+        let text = std::fs::read_to_string(&log).unwrap();
+        assert!(text.contains(r#""event":"session_attention""#) && text.contains(r#""session":"app-app-b""#), "{}", text);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_duplicate_is_offered_the_clean_name_once_the_original_is_gone() {
+        let (git, tmux) = fixture();
+        tmux.add_session("app-app-a_2", Path::new(REPO));
+        tmux.set_session_option("app-app-a_2", "@wsx_made", "app-app-a");
+        let mut app = app(&git, &tmux);
+        let index = |app: &App, name: &str| {
+            app.workspace.projects[0].worktrees[0].sessions.iter().position(|s| s.name == name).unwrap()
+        };
+        let a = index(&app, "app-app-a");
+        app.do_delete_session(0, 0, a).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Killed session: a — r on 'a ·2' gives it the name back"));
+
+        let a2 = index(&app, "app-app-a_2");
+        app.select_session(0, 0, a2, None);
+        app.action_set_alias().unwrap();
+        let Mode::Input { state, .. } = &app.mode else { panic!("expected the rename prompt") };
+        assert_eq!(state.value(), "a");
+    }
+
+    #[test]
+    fn adopting_a_hand_made_worktree_moves_it_and_starts_a_session() {
+        let (git, tmux) = fixture();
+        let repo = Path::new(REPO);
+        let (by_hand, side) = (repo.with_file_name("elsewhere"), repo.with_file_name("side"));
+        git.add_worktree_at(repo, "hotfix", &by_hand);
+        git.add_worktree_at(repo, "spike", &side);
+        git.add_worktree_at(repo, "squatter", &repo.with_file_name("app-spike"));
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        let wi = |app: &App, path: &Path| app.workspace.find_worktree(path).unwrap().1;
+        assert!(app.workspace.projects[0].worktrees[wi(&app, &by_hand)].external);
+        assert!(!app.workspace.projects[0].worktrees[1].external, "app-feature follows the layout");
+
+        app.select_worktree(0, wi(&app, &by_hand));
+        app.dispatch(Action::Adopt, &mut term).unwrap();
+        let Mode::Form { form, .. } = &app.mode else { panic!("expected the adopt form") };
+        let home = repo.with_file_name("app-hotfix");
+        assert_eq!([form.value(0), form.value(1), form.value(2)], [&*home.to_string_lossy(), "elsewhere", "dev"]);
+        // An alias that clashes keeps the form open; a blank one skips the step.
+        let fill = |app: &mut App, worktree: &Path, values: [&str; 3]| {
+            let fields = ["move to", "alias", "session"].into_iter().zip(values);
+            app.mode = Mode::Form {
+                context: FormContext::Adopt { worktree: worktree.to_path_buf() },
+                form: FormState::new(fields.map(|(l, v)| (l, InputState::with_value("", v.into()))).collect()),
+            };
+            app.submit_form().unwrap();
+        };
+        fill(&mut app, &by_hand, ["", "feature", ""]);
+        assert!(matches!(app.mode, Mode::Form { .. }), "alias clashes with the feature worktree");
+        fill(&mut app, &by_hand, [&home.to_string_lossy(), "", "dev"]);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some(format!("Adopted 'elsewhere': moved to {}, session 'dev'", home.display()).as_str())
+        );
+        let adopted = &app.workspace.projects[0].worktrees[wi(&app, &home)];
+        assert!(!adopted.external);
+        assert_eq!(adopted.sessions.len(), 1);
+
+        // The move fails, so no session is started either.
+        let sessions = tmux.session_names().len();
+        fill(&mut app, &side, [&repo.with_file_name("app-spike").to_string_lossy(), "", "dev"]);
+        let error = app.error.as_ref().map(|e| e.message.clone()).unwrap_or_default();
+        assert!(error.starts_with("Adopting 'side' stopped at the move: ") && error.ends_with(" — nothing changed"), "{}", error);
+        assert_eq!(tmux.session_names().len(), sessions);
+    }
+
+    #[test]
+    fn confirm_keys_work_in_either_case_and_without_letters() {
+        use ConfirmButton::{Cancel, Confirm};
+        for c in ['y', 'Y'] {
+            assert_eq!(confirm_key(&Action::InputChar(c), Confirm), ConfirmKey::Yes);
+        }
+        for c in ['n', 'N', 'q'] {
+            assert_eq!(confirm_key(&Action::InputChar(c), Confirm), ConfirmKey::No);
+        }
+        assert_eq!(confirm_key(&Action::InputEscape, Confirm), ConfirmKey::No);
+        assert_eq!(confirm_key(&Action::Select, Confirm), ConfirmKey::Yes);
+        assert_eq!(confirm_key(&Action::Select, Cancel), ConfirmKey::No);
+        assert_eq!(confirm_key(&Action::NavigateRight, Confirm), ConfirmKey::Focus(Cancel));
+        assert_eq!(confirm_key(&Action::NavigateLeft, Cancel), ConfirmKey::Focus(Confirm));
+        assert_eq!(confirm_key(&Action::InputTab, Cancel), ConfirmKey::Focus(Confirm));
+        assert_eq!(confirm_key(&Action::InputChar('z'), Confirm), ConfirmKey::Unrelated);
+        assert_eq!(confirm_key(&Action::None, Confirm), ConfirmKey::Ignored, "resizes stay quiet");
+    }
+
+    #[test]
+    fn sessions_stay_greyed_while_the_tmux_server_is_gone() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let sessions = |app: &App| -> Vec<String> {
+            app.workspace.session_paths().into_iter().map(|(name, _)| name).collect()
+        };
+        let before = sessions(&app);
+
+        tmux.kill_server();
+        app.refresh_all().unwrap();
+        assert!(app.tmux_down);
+        assert_eq!(sessions(&app), before, "last-known sessions are kept");
+        assert!(!app.refresh_activity());
+
+        tmux.create_session("app-app-a", Path::new(REPO), &[]).unwrap();
+        assert!(app.refresh_activity(), "server is back: the tree is reconciled");
+        assert!(!app.tmux_down);
+        assert_eq!(sessions(&app), ["app-app-a"]);
+    }
+
+    #[test]
+    fn worktree_notes_are_set_searched_and_cleared() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let mut term = terminal();
+        app.tree_selected = 5; // feature
+        app.dispatch(Action::EditNote, &mut term).unwrap();
+        for c in "Waiting on review".chars() {
+            app.dispatch(Action::InputChar(c), &mut term).unwrap();
+        }
+        app.dispatch(Action::Select, &mut term).unwrap();
+        let note = |app: &App| app.workspace.projects[0].worktrees[1].note.clone();
+        assert_eq!(note(&app).as_deref(), Some("Waiting on review"));
+
+        app.refresh_all().unwrap();
+        assert_eq!(note(&app).as_deref(), Some("Waiting on review"), "kept across a rescan");
+        assert_eq!(app.search_matches("on review"), [5]);
+
+        app.dispatch(Action::EditNote, &mut term).unwrap();
+        for _ in 0.."Waiting on review".len() {
+            app.dispatch(Action::InputBackspace, &mut term).unwrap();
+        }
+        app.dispatch(Action::Select, &mut term).unwrap();
+        assert_eq!(note(&app), None);
+        assert_eq!(app.status_message.as_deref(), Some("Note cleared"));
+
+        app.tree_selected = 2; // a session
+        app.dispatch(Action::EditNote, &mut term).unwrap();
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn n_repeats_the_last_search_with_wrap() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.mode = Mode::Search { query: "app".into(), match_idx: 0 };
+        app.end_search();
+        assert_eq!(app.last_search.as_deref(), Some("app"));
+        let matches = app.search_matches("app");
+        assert!(matches.len() >= 2, "{:?}", matches);
+
+        app.tree_selected = *matches.last().unwrap();
+        app.search_repeat(1);
+        assert_eq!(app.tree_selected, matches[0]);
+        assert_eq!(app.status_message.as_deref(), Some(format!("match 1/{} for 'app'", matches.len()).as_str()));
+        app.search_repeat(-1);
+        assert_eq!(app.tree_selected, *matches.last().unwrap());
+
+        app.mode = Mode::Search { query: String::new(), match_idx: 0 };
+        app.end_search();
+        assert_eq!(app.last_search, None);
+    }
+
+    #[test]
+    fn dragging_the_tree_scrollbar_moves_the_selection() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_area = Rect::new(0, 0, 30, 5); // rows 1..=3 inside the borders
+        app.tree_visible_height = 3;
+        app.tree_selected = 0;
+        let at = |x, y| Position { x, y };
+        assert_eq!(app.scrollbar_hit(at(28, 3), None), None, "left of the scrollbar");
+
+        let hit = app.scrollbar_hit(at(29, 3), None).unwrap();
+        assert_eq!(hit, (ScrollbarDrag::Tree, app.flat().len() - 1));
+        app.drag_scrollbar(hit);
+        assert_eq!(app.tree_selected, 6);
+        // Dragged off the column and above the tree, it pins to the top.
+        let hit = app.scrollbar_hit(at(5, 0), Some(ScrollbarDrag::Tree)).unwrap();
+        app.drag_scrollbar(hit);
+        assert_eq!((app.tree_selected, app.tree_scroll), (0, 0));
+
+        app.tree_visible_height = 20;
+        assert_eq!(app.scrollbar_hit(at(29, 3), None), None, "everything fits: no scrollbar");
+    }
+
+    #[test]
+    fn main_shell_reuses_the_main_worktrees_first_session() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 0;
+        assert!(app.select_main_shell().unwrap());
+        assert_eq!(app.current_selection(), Selection::Session(0, 0, 0), "session a");
+
+        // A project whose main checkout has no session gets a shell, once.
+        for name in ["app-app-a", "app-app-b", "app-app-c"] {
+            tmux.kill_session(name).unwrap();
+        }
+        app.refresh_all().unwrap();
+        app.tree_selected = 0;
+        assert!(app.select_main_shell().unwrap());
+        app.tree_selected = 0;
+        assert!(app.select_main_shell().unwrap());
+        let names: Vec<&str> =
+            app.workspace.projects[0].worktrees[0].sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["app-app-shell"]);
+        assert_eq!(app.current_selection(), Selection::Session(0, 0, 0));
+    }
+
+    #[test]
+    fn stale_rows_resync_instead_of_panicking() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        app.tree_selected = 5; // feature worktree
+        app.workspace.projects[0].worktrees.truncate(1); // a refresh dropped it; flat not rebuilt yet
+        app.nav_right();
+        assert!(app.status_message.as_deref().is_some_and(|m| m.contains("gone")));
+        assert!(app.tree_selected < app.flat().len());
+
+        app.move_session(0, 1, 0, 1); // worktree 1 no longer exists
+        app.workspace.projects.clear();
+        app.move_project(0, 1);
+    }
+
+    #[test]
+    fn resolving_the_last_conflict_offers_to_continue() {
+        let (git, tmux) = fixture();
+        let wt = Path::new("/tmp/wsx-test/app-feature");
+        git.add_conflict(wt, InProgress::Merge, "src/a.rs");
+        git.add_conflict(wt, InProgress::Merge, "src/b.rs");
+        let mut app = app(&git, &tmux);
+        app.open_conflicts_at(0, 1, 0);
+        let Mode::Conflicts { files, .. } = &app.mode else { panic!("expected the conflict list") };
+        assert_eq!(files, &["src/a.rs", "src/b.rs"]);
+
+        app.do_mark_resolved(0, 1, "src/a.rs", 0);
+        let Mode::Conflicts { files, .. } = &app.mode else { panic!("list stays open") };
+        assert_eq!(files, &["src/b.rs"]);
+        app.do_mark_resolved(0, 1, "src/b.rs", 0);
+        let Mode::Confirm { message, pending: PendingAction::ContinueOperation { worktree }, .. } = &app.mode
+        else {
+            panic!("expected the continue prompt");
+        };
+        assert_eq!(worktree, wt);
+        assert!(message.contains("git commit --no-edit"), "{}", message);
+        app.do_continue_operation(0, 1);
+        assert_eq!(git.in_progress(wt), None);
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn detached_worktrees_skip_clean_and_branch_deletion() {
+        let (git, tmux) = fixture();
+        git.set_merged(Path::new(REPO), "v1.2.3");
+        let mut app = app(&git, &tmux);
+        app.do_create_worktree(0, "@v1.2.3".into()).unwrap();
+        let Selection::Worktree(pi, wi) = app.current_selection() else { panic!("cursor on the new worktree") };
+        let wt = &app.workspace.projects[pi].worktrees[wi];
+        assert!(wt.detached);
+        assert_eq!(wt.path, Path::new("/tmp/wsx-test/app-v1.2.3"));
+        assert_eq!(wt.session_slug("app"), "v1-2-3");
+        let wt_path = wt.path.clone();
+
+        app.action_clean().unwrap();
+        assert!(matches!(app.mode, Mode::Normal), "no clean offered");
+        assert!(app.status_message.as_deref().is_some_and(|m| m.contains("detached")));
+        let report = crate::ops::clean_merged(git.as_ref(), Path::new(REPO), "main", None).unwrap();
+        assert!(report.removed.is_empty());
+
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, .. } = &app.mode else { panic!("expected a confirm") };
+        assert_eq!(message, "Delete worktree 'app-v1.2.3'?");
+        git.set_unreferenced(&wt_path, 2);
+        app.action_delete().unwrap();
+        let Mode::Confirm { message, .. } = &app.mode else { panic!("expected a confirm") };
+        assert!(message.starts_with("Delete UNMERGED worktree") && message.ends_with("2 commit(s) on its HEAD are on no branch or tag."), "{}", message);
+
+        app.do_delete_worktree(pi, wi, false).unwrap();
+        assert!(app.workspace.find_worktree(Path::new("/tmp/wsx-test/app-v1.2.3")).is_none());
+        assert!(git.deleted_branches(Path::new(REPO)).is_empty());
+    }
+
+    #[test]
+    fn state_changes_are_written_to_the_event_stream() {
+        let (git, tmux) = fixture();
+        let mut app = app(&git, &tmux);
+        let dir = std::env::temp_dir().join(format!("wsx-app-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+        std::fs::write(&log, "").unwrap();
+        app.events = crate::events::EventSink::open(&log).unwrap();
+
+        git.add_worktree(Path::new(REPO), "extra");
+        tmux.set_status("app-app-b", SessionStatus { has_bell: true, ..Default::default() });
+        app.refresh_all().unwrap();
+        let (pi, wi) = app.workspace.find_worktree(Path::new("/tmp/wsx-test/app-feature")).unwrap();
+        app.do_delete_worktree(pi, wi, true).unwrap();
+        app.workspace.projects[0].path = PathBuf::from("/tmp/wsx-test/gone");
+        app.refresh_all().unwrap();
+
+        let text = std::fs::read_to_string(&log).unwrap();
+        let kinds: Vec<&str> = text
+            .lines()
+            .map(|l| l.split('"').nth(3).unwrap())
+            .collect();
+        // The vanished project becomes a placeholder: its worktrees go, and the error is reported.
+        assert_eq!(
+            kinds,
+            ["worktree_created", "session_attention", "worktree_removed", "refresh_error", "worktree_removed", "worktree_removed"]
+        );
+        assert!(app.workspace.projects[0].missing);
+        assert!(text.contains(r#""branch":"extra""#));
+        assert!(text.contains(r#""session":"app-app-b""#));
+        assert!(text.contains(r#""path":"/tmp/wsx-test/app-feature""#));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,105 +1,485 @@
-// Startup cache — persists last known sessions + expand state.
-// Loaded before first refresh_all() so the tree is populated immediately.
+// Startup cache — persists last known worktrees, sessions + expand state.
+// The first frame is drawn from it while the live load runs in the background.
 
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
-use crate::model::workspace::{session_display_name_from_tmux, SessionInfo, WorkspaceState};
+use crate::persist;
+use crate::model::attach_log::AttachLog;
+use crate::model::ended::EndedLog;
+use crate::model::frecency::Frecency;
+use crate::model::setup::WorktreeSetup;
+use crate::config::global::GlobalConfig;
+use crate::git::worktree::{self as git_worktree, WorktreeEntry};
+use crate::model::workspace::{session_display_name_from_tmux, Project, SessionInfo, WorkspaceState};
+
+/// Bump when the cache layout changes; add a step to `migrate`.
+const CACHE_VERSION: u32 = 2;
+
+/// Periodic saves (from the rescan loop) are coalesced to at most one per window.
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(30);
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct WorkspaceCache {
+    /// schema version — missing in files written before versioning (treated as 0)
+    #[serde(default)]
+    pub version: u32,
     /// worktree path → session names
-    pub sessions: HashMap<String, Vec<String>>,
+    pub sessions: BTreeMap<String, Vec<String>>,
     /// worktree path → expanded
-    pub worktree_expanded: HashMap<String, bool>,
+    pub worktree_expanded: BTreeMap<String, bool>,
     /// project path → expanded
-    pub project_expanded: HashMap<String, bool>,
+    pub project_expanded: BTreeMap<String, bool>,
     /// last cursor position in the flat tree
     pub tree_selected: usize,
     /// session names where the user dismissed the running-app notification
     #[serde(default)]
-    pub suppressed_sessions: HashSet<String>,
+    pub suppressed_sessions: BTreeSet<String>,
     /// session names the user has muted (no activity updates, shown as ⊘)
     #[serde(default)]
-    pub muted_sessions: HashSet<String>,
+    pub muted_sessions: BTreeSet<String>,
+    /// session name → unix seconds its latched tmux bell was acknowledged
+    #[serde(default)]
+    pub bell_acks: BTreeMap<String, u64>,
+    /// project path → usage score for recently-used ordering
+    #[serde(default)]
+    pub frecency: BTreeMap<String, Frecency>,
+    /// worktree paths pinned to the top of their project
+    #[serde(default)]
+    pub pinned_worktrees: BTreeSet<String>,
+    /// session names pinned to the top of their worktree
+    #[serde(default)]
+    pub pinned_sessions: BTreeSet<String>,
+    /// worktree path → note set with `#`
+    #[serde(default)]
+    pub worktree_notes: BTreeMap<String, String>,
+    /// worktree path → what its env copy and postCreate hook did when wsx created it
+    #[serde(default)]
+    pub worktree_setup: BTreeMap<String, WorktreeSetup>,
+    /// recent session attaches, oldest first
+    #[serde(default, skip_serializing_if = "AttachLog::is_empty")]
+    pub attaches: AttachLog,
+    /// sessions that went away, with their last pane capture, oldest first
+    #[serde(default, skip_serializing_if = "EndedLog::is_empty")]
+    pub ended: EndedLog,
+    /// project path → archive tag → session display names to make again on restore
+    #[serde(default)]
+    pub archived_sessions: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    /// project path → its worktrees and default branch, drawn until the live load lands
+    #[serde(default)]
+    pub projects: BTreeMap<String, CachedProject>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedProject {
+    pub default_branch: String,
+    pub worktrees: Vec<CachedWorktree>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedWorktree {
+    pub name: String,
+    pub path: PathBuf,
+    pub branch: String,
+    pub is_main: bool,
+    #[serde(default)]
+    pub detached: bool,
 }
 
 impl WorkspaceCache {
     pub fn load() -> Self {
-        let Ok(content) = std::fs::read_to_string(cache_path()) else {
+        Self::load_from(&cache_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
             return Self::default();
         };
-        toml::from_str(&content).unwrap_or_default()
+        toml::from_str::<Self>(&content)
+            .map(Self::migrate)
+            .unwrap_or_default()
     }
 
-    pub fn save(&self) {
-        let path = cache_path();
-        if let Some(dir) = path.parent() {
-            let _ = std::fs::create_dir_all(dir);
+    /// Upgrade an older on-disk cache to `CACHE_VERSION`.
+    fn migrate(mut self) -> Self {
+        if self.version > CACHE_VERSION {
+            // Written by a newer wsx — the fields we know about still parsed; keep them.
+            return self;
+        }
+        // 0 → 1: only the version field was added.
+        // 1 → 2: path keys are canonicalized.
+        if self.version < 2 {
+            self.sessions = rekey(self.sessions);
+            self.worktree_expanded = rekey(self.worktree_expanded);
+            self.project_expanded = rekey(self.project_expanded);
+            self.frecency = rekey(self.frecency);
         }
-        if let Ok(s) = toml::to_string(self) {
-            let _ = std::fs::write(path, s);
+        self.version = CACHE_VERSION;
+        self
+    }
+
+    /// Overlay `ours` on this (on-disk) cache. Keys we track take our value; keys only
+    /// another instance knows are kept while their paths still exist.
+    fn merge(mut self, ours: WorkspaceCache) -> WorkspaceCache {
+        let exists = |k: &String| Path::new(k).exists();
+        self.sessions.retain(|k, _| exists(k));
+        self.worktree_expanded.retain(|k, _| exists(k));
+        self.project_expanded.retain(|k, _| exists(k));
+        self.frecency.retain(|k, _| exists(k));
+        self.pinned_worktrees
+            .retain(|k| exists(k) && !ours.worktree_expanded.contains_key(k));
+        self.worktree_notes
+            .retain(|k, _| exists(k) && !ours.worktree_expanded.contains_key(k));
+        self.worktree_setup.retain(|k, _| exists(k));
+        self.archived_sessions
+            .retain(|k, _| exists(k) && !ours.project_expanded.contains_key(k));
+        self.projects.retain(|k, _| exists(k));
+
+        let known: HashSet<String> = ours.sessions.values().flatten().cloned().collect();
+        self.suppressed_sessions.retain(|n| !known.contains(n));
+        self.muted_sessions.retain(|n| !known.contains(n));
+        self.pinned_sessions.retain(|n| !known.contains(n));
+        self.bell_acks.retain(|n, _| !known.contains(n));
+
+        self.sessions.extend(ours.sessions);
+        self.worktree_expanded.extend(ours.worktree_expanded);
+        self.project_expanded.extend(ours.project_expanded);
+        self.frecency.extend(ours.frecency);
+        self.archived_sessions.extend(ours.archived_sessions);
+        self.projects.extend(ours.projects);
+        self.suppressed_sessions.extend(ours.suppressed_sessions);
+        self.muted_sessions.extend(ours.muted_sessions);
+        self.pinned_worktrees.extend(ours.pinned_worktrees);
+        self.worktree_notes.extend(ours.worktree_notes);
+        self.worktree_setup.extend(ours.worktree_setup);
+        self.pinned_sessions.extend(ours.pinned_sessions);
+        self.bell_acks.extend(ours.bell_acks);
+        self.attaches.merge(ours.attaches);
+        self.ended.merge(ours.ended);
+        self.version = ours.version;
+        self.tree_selected = ours.tree_selected;
+        self
+    }
+
+    /// Re-key everything at or under `old` to the same place under `new`.
+    fn relocate(&mut self, old: &str, new: &str) {
+        self.sessions = rekey_under(std::mem::take(&mut self.sessions), old, new);
+        self.worktree_expanded = rekey_under(std::mem::take(&mut self.worktree_expanded), old, new);
+        self.project_expanded = rekey_under(std::mem::take(&mut self.project_expanded), old, new);
+        self.frecency = rekey_under(std::mem::take(&mut self.frecency), old, new);
+        self.archived_sessions = rekey_under(std::mem::take(&mut self.archived_sessions), old, new);
+        self.worktree_notes = rekey_under(std::mem::take(&mut self.worktree_notes), old, new);
+        self.worktree_setup = rekey_under(std::mem::take(&mut self.worktree_setup), old, new);
+        self.projects = rekey_under(std::mem::take(&mut self.projects), old, new);
+        for wt in self.projects.values_mut().flat_map(|p| p.worktrees.iter_mut()) {
+            wt.path = PathBuf::from(moved_key(wt.path.to_string_lossy().into_owned(), old, new));
         }
+        self.pinned_worktrees = std::mem::take(&mut self.pinned_worktrees)
+            .into_iter()
+            .map(|k| moved_key(k, old, new))
+            .collect();
+    }
+}
+
+/// Coalesces cache writes: identical content is never rewritten, and periodic
+/// saves are debounced to `SAVE_DEBOUNCE`. The cache's maps are ordered, so the same
+/// state always serializes — and hashes — the same.
+#[derive(Default)]
+pub struct CacheWriter {
+    last_hash: Option<u64>,
+    last_write: Option<Instant>,
+    /// never touch disk (tests)
+    disabled: bool,
+    /// where to write instead of `cache_path()` (tests)
+    path: Option<PathBuf>,
+}
+
+impl CacheWriter {
+    #[cfg(test)]
+    pub fn disabled() -> Self {
+        Self { disabled: true, ..Default::default() }
+    }
+
+    #[cfg(test)]
+    fn at(path: PathBuf) -> Self {
+        Self { path: Some(path), ..Default::default() }
+    }
+
+    /// Periodic save — skipped if another write happened within the debounce window.
+    pub fn save(&mut self, workspace: &WorkspaceState, tree_selected: usize) {
+        let recent = self
+            .last_write
+            .map(|t| t.elapsed() < SAVE_DEBOUNCE)
+            .unwrap_or(false);
+        if !recent {
+            self.save_now(workspace, tree_selected);
+        }
+    }
+
+    /// Immediate save for quit and user-initiated changes (mute, dismiss, reorder).
+    pub fn save_now(&mut self, workspace: &WorkspaceState, tree_selected: usize) {
+        if self.disabled {
+            return;
+        }
+        let ours = build_cache(workspace, tree_selected);
+        let Ok(content) = toml::to_string(&ours) else {
+            return;
+        };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.last_hash == Some(hash) {
+            return;
+        }
+        let path = self.path.clone().unwrap_or_else(cache_path);
+        let written = persist::with_lock(&path, || {
+            // Another instance may have written since our last save — keep its keys.
+            let merged = WorkspaceCache::load_from(&path).merge(ours);
+            let text = toml::to_string(&merged).map_err(std::io::Error::other)?;
+            persist::write_atomic(&path, &text)
+        });
+        if matches!(written, Ok(Ok(()))) {
+            self.last_hash = Some(hash);
+            self.last_write = Some(Instant::now());
+        }
+    }
+
+    /// A project moved from `old`: carry its cached state (and its worktrees' state,
+    /// when they lived under it) over to the new path, and apply it to `project`.
+    pub fn relocate(&mut self, old: &Path, project: &mut Project) {
+        if self.disabled {
+            return;
+        }
+        let path = cache_path();
+        let (old, new) = (cache_key(old), cache_key(&project.path));
+        let relocated = persist::with_lock(&path, || {
+            let mut cache = WorkspaceCache::load();
+            cache.relocate(&old, &new);
+            let text = toml::to_string(&cache).map_err(std::io::Error::other)?;
+            persist::write_atomic(&path, &text)?;
+            Ok::<_, std::io::Error>(cache)
+        });
+        if let Ok(Ok(cache)) = relocated {
+            apply_to_project(&cache, project);
+        }
+        self.last_hash = None;
+    }
+}
+
+/// Paths are keyed canonicalized, so `~/a/../b`, symlinks and trailing slashes
+/// all hit the same entry. Falls back to the path as given if it's gone.
+fn cache_key(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn rekey<V>(map: BTreeMap<String, V>) -> BTreeMap<String, V> {
+    map.into_iter().map(|(k, v)| (cache_key(Path::new(&k)), v)).collect()
+}
+
+fn moved_key(key: String, old: &str, new: &str) -> String {
+    match key.strip_prefix(old) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", new, rest),
+        _ => key,
     }
 }
 
-fn cache_path() -> PathBuf {
+fn rekey_under<V>(map: BTreeMap<String, V>, old: &str, new: &str) -> BTreeMap<String, V> {
+    map.into_iter().map(|(k, v)| (moved_key(k, old, new), v)).collect()
+}
+
+pub(crate) fn cache_path() -> PathBuf {
     dirs::cache_dir()
         .unwrap_or_else(|| PathBuf::from("/tmp"))
         .join("wsx")
-        .join("workspace.toml")
+        .join(cache_file_name(GlobalConfig::profile_path()))
+}
+
+/// `workspace.toml`, or `workspace-<hash of the config path>.toml` under a profile, so
+/// two profiles keep their own expansion state and session metadata. FNV-1a rather than
+/// `DefaultHasher`, whose output may change between Rust releases.
+pub(crate) fn cache_file_name(profile: Option<&Path>) -> String {
+    let Some(profile) = profile else {
+        return "workspace.toml".to_string();
+    };
+    let hash = profile
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3));
+    format!("workspace-{:016x}.toml", hash)
 }
 
-/// Pre-populate workspace with cached state before first live sync.
-/// Returns the last saved cursor position.
-pub fn apply_cache(workspace: &mut WorkspaceState) -> usize {
+/// The workspace as the cache last saw it, for the first frame: every registered project
+/// with its cached worktrees and sessions, marked `syncing` until `ops::load_project`
+/// replaces it. Also returns the last saved cursor position.
+pub fn cached_workspace(config: &GlobalConfig) -> (WorkspaceState, usize) {
     let cache = WorkspaceCache::load();
-    for project in &mut workspace.projects {
-        let proj_key = project.path.to_string_lossy().to_string();
-        if let Some(&expanded) = cache.project_expanded.get(&proj_key) {
-            project.expanded = expanded;
-        }
-        for wt in &mut project.worktrees {
-            let key = wt.path.to_string_lossy().to_string();
-            if let Some(&expanded) = cache.worktree_expanded.get(&key) {
-                wt.expanded = expanded;
-            }
-            if let Some(names) = cache.sessions.get(&key) {
-                wt.sessions = names.iter().map(|name| {
-                    let display_name = session_display_name_from_tmux(
-                        name,
-                        &project.name,
-                        &wt.path,
-                        &wt.branch,
-                        wt.alias.as_deref(),
-                    );
-                    SessionInfo {
-                        name: name.clone(),
-                        display_name,
-                        has_activity: false,
-                        pane_capture: None,
-                        last_activity: None,
-                        has_running_app: false,
-                        running_app_suppressed: cache.suppressed_sessions.contains(name),
-                        muted: cache.muted_sessions.contains(name),
-                    }
-                }).collect();
+    let projects = config
+        .projects
+        .iter()
+        .map(|entry| {
+            let cached = cache.projects.get(&cache_key(&entry.path));
+            let entries = cached
+                .map(|c| c.worktrees.iter().cloned().map(CachedWorktree::into_entry).collect())
+                .unwrap_or_default();
+            let mut project = Project {
+                name: entry.name.clone(),
+                path: entry.path.clone(),
+                default_branch: entry
+                    .default_branch
+                    .clone()
+                    .or_else(|| cached.map(|c| c.default_branch.clone()))
+                    .unwrap_or_else(|| "main".into()),
+                default_branch_override: entry.default_branch.is_some(),
+                missing: false,
+                syncing: true,
+                worktrees: git_worktree::to_worktree_infos(&entry.path, entries, &entry.aliases, None),
+                config: None,
+                expanded: true,
+                frecency: Default::default(),
+                archived: vec![],
+                archived_expanded: false,
+            };
+            apply_to_project(&cache, &mut project);
+            project
+        })
+        .collect();
+    let setup = cache.worktree_setup.into_iter().map(|(k, v)| (PathBuf::from(k), v)).collect();
+    let workspace =
+        WorkspaceState { projects, attaches: cache.attaches, ended: cache.ended, setup, ..Default::default() };
+    (workspace, cache.tree_selected)
+}
+
+impl CachedWorktree {
+    fn into_entry(self) -> WorktreeEntry {
+        WorktreeEntry {
+            name: self.name,
+            path: self.path,
+            branch: self.branch,
+            is_main: self.is_main,
+            detached: self.detached,
+            locked: None,
+        }
+    }
+}
+
+/// Expand state, pins, notes and cached sessions for `project`'s worktrees.
+pub fn apply_to_project(cache: &WorkspaceCache, project: &mut Project) {
+    let proj_key = cache_key(&project.path);
+    if let Some(&expanded) = cache.project_expanded.get(&proj_key) {
+        project.expanded = expanded;
+    }
+    if let Some(&frecency) = cache.frecency.get(&proj_key) {
+        project.frecency = frecency;
+    }
+    if let Some(by_tag) = cache.archived_sessions.get(&proj_key) {
+        for archived in &mut project.archived {
+            if let Some(names) = by_tag.get(&archived.tag) {
+                archived.sessions = names.clone();
             }
         }
     }
-    cache.tree_selected
+    for wt in &mut project.worktrees {
+        let key = cache_key(&wt.path);
+        if let Some(&expanded) = cache.worktree_expanded.get(&key) {
+            wt.expanded = expanded;
+        }
+        wt.pinned = cache.pinned_worktrees.contains(&key);
+        wt.note = cache.worktree_notes.get(&key).cloned();
+        if let Some(names) = cache.sessions.get(&key) {
+            wt.sessions = names.iter().map(|name| {
+                let display_name = session_display_name_from_tmux(
+                    name,
+                    &project.name,
+                    &wt.path,
+                    &wt.branch,
+                    wt.alias.as_deref(),
+                );
+                SessionInfo {
+                    name: name.clone(),
+                    display_name,
+                    has_activity: false,
+                    pane_capture: None,
+                    last_activity: None,
+                    has_running_app: false,
+                    running_app_suppressed: cache.suppressed_sessions.contains(name),
+                    muted: cache.muted_sessions.contains(name),
+                    commands: Vec::new(),
+                    windows: Vec::new(),
+                    pinned: cache.pinned_sessions.contains(name),
+                    created_ts: 0,
+                    attention_since: None,
+                    origin: Default::default(),
+                    bell_ack: cache.bell_acks.get(name).copied(),
+                    bell_since: None,
+                    attention: Default::default(),
+                }
+            }).collect();
+        }
+    }
 }
 
-/// Persist session names, expand states, and cursor position.
-pub fn save_cache(workspace: &WorkspaceState, tree_selected: usize) {
-    let mut cache = WorkspaceCache::default();
-    cache.tree_selected = tree_selected;
-    for project in &workspace.projects {
-        let proj_key = project.path.to_string_lossy().to_string();
+/// Snapshot session names, expand states, and cursor position.
+fn build_cache(workspace: &WorkspaceState, tree_selected: usize) -> WorkspaceCache {
+    let mut cache = WorkspaceCache {
+        version: CACHE_VERSION,
+        tree_selected,
+        attaches: workspace.attaches.clone(),
+        ended: workspace.ended.clone(),
+        worktree_setup: workspace
+            .setup
+            .iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, setup)| (cache_key(path), setup.clone()))
+            .collect(),
+        ..Default::default()
+    };
+    // A project still syncing only has what the cache gave it; its entries on disk stay.
+    for project in workspace.projects.iter().filter(|p| !p.syncing) {
+        let proj_key = cache_key(&project.path);
+        if !project.missing {
+            let worktrees = project
+                .worktrees
+                .iter()
+                .map(|wt| CachedWorktree {
+                    name: wt.name.clone(),
+                    path: wt.path.clone(),
+                    branch: wt.branch.clone(),
+                    is_main: wt.is_main,
+                    detached: wt.detached,
+                })
+                .collect();
+            let cached = CachedProject { default_branch: project.default_branch.clone(), worktrees };
+            cache.projects.insert(proj_key.clone(), cached);
+        }
+        if project.frecency != Frecency::default() {
+            cache.frecency.insert(proj_key.clone(), project.frecency);
+        }
+        let by_tag: BTreeMap<String, Vec<String>> = project
+            .archived
+            .iter()
+            .filter(|a| !a.sessions.is_empty())
+            .map(|a| (a.tag.clone(), a.sessions.clone()))
+            .collect();
+        if !by_tag.is_empty() {
+            cache.archived_sessions.insert(proj_key.clone(), by_tag);
+        }
         cache.project_expanded.insert(proj_key, project.expanded);
         for wt in &project.worktrees {
-            let key = wt.path.to_string_lossy().to_string();
+            let key = cache_key(&wt.path);
+            if wt.pinned {
+                cache.pinned_worktrees.insert(key.clone());
+            }
+            if let Some(note) = &wt.note {
+                cache.worktree_notes.insert(key.clone(), note.clone());
+            }
             cache.sessions.insert(key.clone(), wt.sessions.iter().map(|s| s.name.clone()).collect());
             cache.worktree_expanded.insert(key, wt.expanded);
             for s in &wt.sessions {
@@ -109,8 +489,141 @@ pub fn save_cache(workspace: &WorkspaceState, tree_selected: usize) {
                 if s.muted {
                     cache.muted_sessions.insert(s.name.clone());
                 }
+                if s.pinned {
+                    cache.pinned_sessions.insert(s.name.clone());
+                }
+                if let Some(ack) = s.bell_ack {
+                    cache.bell_acks.insert(s.name.clone(), ack);
+                }
             }
         }
     }
-    cache.save();
+    cache
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_cache, cache_file_name, cache_key, CacheWriter, CachedWorktree, WorkspaceCache, CACHE_VERSION};
+    use crate::config::global::resolve_profile;
+    use crate::model::fixtures;
+    use crate::scratch::TempDir;
+    use crate::model::workspace::{Project, WorkspaceState};
+    use std::path::Path;
+
+    #[test]
+    fn unversioned_cache_migrates() {
+        let old = r#"
+tree_selected = 4
+
+[sessions]
+"/tmp/wsx" = ["wsx-wsx-dev"]
+
+[worktree_expanded]
+
+[project_expanded]
+"/tmp/wsx" = false
+"#;
+        let cache = toml::from_str::<WorkspaceCache>(old).unwrap().migrate();
+        assert_eq!(cache.version, CACHE_VERSION);
+        assert_eq!(cache.tree_selected, 4);
+        let key = cache_key(std::path::Path::new("/tmp/wsx"));
+        assert_eq!(cache.project_expanded.get(&key), Some(&false));
+    }
+
+    #[test]
+    fn cache_key_canonicalizes_paths_with_spaces_and_brackets() {
+        let dir = std::env::temp_dir().join(format!("wsx cache [{}]", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let canonical = std::fs::canonicalize(&dir).unwrap();
+        assert_eq!(cache_key(&dir.join("sub/..")), canonical.to_string_lossy());
+        assert_eq!(cache_key(&dir), cache_key(&canonical));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn relocating_moves_keys_under_the_old_path_only() {
+        let mut cache = WorkspaceCache::default();
+        for key in ["/old/api", "/old/api/sub", "/old/api-feature", "/other"] {
+            cache.worktree_expanded.insert(key.to_string(), true);
+        }
+        cache.pinned_worktrees.insert("/old/api/sub".into());
+        cache.worktree_notes.insert("/old/api/sub".into(), "blocked".into());
+        cache.relocate("/old/api", "/new/api");
+        let mut keys: Vec<&str> = cache.worktree_expanded.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["/new/api", "/new/api/sub", "/old/api-feature", "/other"]);
+        assert!(cache.pinned_worktrees.contains("/new/api/sub"));
+        assert_eq!(cache.worktree_notes["/new/api/sub"], "blocked");
+    }
+
+    fn project(name: &str, syncing: bool) -> Project {
+        let path = fixtures::project(name).path;
+        let main = CachedWorktree { name: name.into(), path: path.clone(), branch: "trunk".into(), is_main: true, detached: false };
+        Project {
+            default_branch: "trunk".into(),
+            syncing,
+            worktrees: crate::git::worktree::to_worktree_infos(&path, vec![main.into_entry()], &Default::default(), None),
+            ..fixtures::project(name)
+        }
+    }
+
+    #[test]
+    fn remembers_worktree_lists_but_not_for_projects_still_syncing() {
+        let workspace = WorkspaceState { projects: vec![project("api", false), project("web", true)], ..Default::default() };
+        let cache = build_cache(&workspace, 0);
+        let text = toml::to_string(&cache).unwrap();
+        let cache = toml::from_str::<WorkspaceCache>(&text).unwrap();
+        let api = &cache.projects["/w/api"];
+        assert_eq!(api.default_branch, "trunk");
+        assert_eq!(api.worktrees.iter().map(|w| w.branch.as_str()).collect::<Vec<_>>(), ["trunk"]);
+        assert!(!cache.projects.contains_key("/w/web"), "left to what's on disk");
+        assert!(!cache.project_expanded.contains_key("/w/web"));
+    }
+
+    #[test]
+    fn saving_the_same_state_twice_writes_once() {
+        let dir = std::env::temp_dir().join(format!("wsx-cache-dedup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("workspace.toml");
+        let names = ["api", "web", "cli", "docs", "infra", "site"];
+        let workspace =
+            WorkspaceState { projects: names.iter().map(|n| project(n, false)).collect(), ..Default::default() };
+        let mut writer = CacheWriter::at(path.clone());
+        writer.save_now(&workspace, 0);
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+        // a rebuilt cache serializes identically, so the second save is skipped
+        writer.save_now(&workspace, 0);
+        assert!(!path.exists(), "identical state was written again");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn each_profile_gets_its_own_cache_file() {
+        assert_eq!(cache_file_name(None), "workspace.toml");
+        let work = cache_file_name(Some(Path::new("/home/u/.config/wsx/work.toml")));
+        let personal = cache_file_name(Some(Path::new("/home/u/.config/wsx/personal.toml")));
+        assert!(work.starts_with("workspace-") && work.ends_with(".toml") && work.len() == 31, "{}", work);
+        assert_ne!(work, personal);
+        assert_eq!(work, cache_file_name(Some(Path::new("/home/u/.config/wsx/work.toml"))), "stable across runs");
+    }
+
+    #[test]
+    fn a_profile_spelled_differently_keeps_its_cache() {
+        let dir = TempDir::new("wsx-profile");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let cache_of = |spelled: &str| {
+            let profile = resolve_profile(Some(spelled.into()), None, &dir.join("sub")).unwrap();
+            cache_file_name(Some(&profile))
+        };
+        // not written yet, as on a first run
+        let work = cache_of("../work.toml");
+        assert_eq!(cache_of(".././work.toml"), work);
+        assert_eq!(cache_of(&format!("{}/work.toml", dir.display())), work);
+        std::fs::write(dir.join("work.toml"), "").unwrap();
+        assert_eq!(cache_of("../work.toml"), work, "creating the file doesn't move its cache");
+        std::os::unix::fs::symlink(dir.join("work.toml"), dir.join("sub/work.toml")).unwrap();
+        assert_eq!(cache_of("work.toml"), work, "a symlink to it");
+        assert_eq!(cache_of("./work.toml"), work);
+    }
 }
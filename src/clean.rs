@@ -0,0 +1,251 @@
+// `wsx clean --all`: the TUI's `c` for every registered project, without the TUI — for
+// cron and CI. Same plan and removal code as `c`, so the two can't drift apart.
+
+use std::path::PathBuf;
+
+use crate::config::{global::GlobalConfig, project::load_project_config};
+use crate::events::quote;
+use crate::git::GitBackend;
+use crate::model::frecency::now_secs;
+use crate::model::workspace::Divergence;
+use crate::ui::path::display_path;
+use crate::ops;
+
+/// Exit status when some planned removal (or a whole project) failed.
+pub const EXIT_FAILED: i32 = 2;
+
+/// One project's outcome.
+#[derive(Debug, Default)]
+pub struct ProjectClean {
+    pub project: String,
+    pub path: PathBuf,
+    /// (worktree, branch) removed — or that would be, with `--dry-run`
+    pub removed: Vec<(PathBuf, String)>,
+    /// (branch, error) for removals git refused
+    pub failed: Vec<(String, String)>,
+    pub skipped_dirty: usize,
+    pub skipped_locked: usize,
+    /// the project directory is gone; nothing was tried
+    pub missing: bool,
+    /// merged branches couldn't be listed
+    pub error: Option<String>,
+    /// worktrees left because their branch isn't merged, oldest fork first; only
+    /// `--dry-run` lists them
+    pub unmerged: Vec<Unmerged>,
+}
+
+/// A worktree staying put because its branch isn't merged, with how long ago it forked —
+/// a branch that left main months ago is likely abandoned rather than in progress.
+#[derive(Debug, PartialEq)]
+pub struct Unmerged {
+    pub path: PathBuf,
+    pub branch: String,
+    /// None when git couldn't find a merge base
+    pub divergence: Option<Divergence>,
+}
+
+impl ProjectClean {
+    fn failed(&self) -> bool {
+        self.error.is_some() || !self.failed.is_empty()
+    }
+}
+
+/// Plan (and unless `dry_run`, carry out) the clean of every project in `config`, in
+/// config order, with the default branch and `.gtrconfig` the tree would load.
+pub fn clean_all(git: &dyn GitBackend, config: &GlobalConfig, dry_run: bool) -> Vec<ProjectClean> {
+    config
+        .projects
+        .iter()
+        .map(|entry| {
+            let mut out = ProjectClean { project: entry.name.clone(), path: entry.path.clone(), ..Default::default() };
+            if !entry.path.exists() {
+                out.missing = true;
+                return out;
+            }
+            let trunk = entry.default_branch.clone().unwrap_or_else(|| ops::detect_default_branch(git, &entry.path));
+            let proj_config = load_project_config(&entry.path);
+            let plan = match ops::clean_plan(git, &entry.path, &trunk, Some(&proj_config)) {
+                Ok(plan) => plan,
+                Err(e) => {
+                    out.error = Some(e.to_string());
+                    return out;
+                }
+            };
+            out.skipped_dirty = plan.skipped_dirty;
+            out.skipped_locked = plan.skipped_locked;
+            if dry_run {
+                out.removed = plan.remove;
+                out.unmerged = plan
+                    .unmerged
+                    .into_iter()
+                    .map(|(path, branch)| {
+                        let divergence = git.divergence(&path, &trunk);
+                        Unmerged { path, branch, divergence }
+                    })
+                    .collect();
+                out.unmerged.sort_by_key(|u| u.divergence.as_ref().map_or(u64::MAX, |d| d.base_ts));
+            } else {
+                let report = ops::execute_clean(git, &entry.path, plan);
+                out.removed = report.removed.into_iter().map(|c| (c.path, c.branch)).collect();
+                out.failed = report.failed;
+            }
+            out
+        })
+        .collect()
+}
+
+/// 0, or `EXIT_FAILED` when anything failed.
+pub fn exit_code(results: &[ProjectClean]) -> i32 {
+    if results.iter().any(ProjectClean::failed) { EXIT_FAILED } else { 0 }
+}
+
+/// One block per project: what went (or would go), what stayed and why.
+pub fn text_report(results: &[ProjectClean], dry_run: bool) -> String {
+    if results.is_empty() {
+        return "wsx clean: no projects registered".to_string();
+    }
+    let verb = if dry_run { "would remove" } else { "removed" };
+    let now = now_secs();
+    let mut lines = Vec::new();
+    for r in results {
+        lines.push(format!("{} ({})", r.project, display_path(&r.path, false, usize::MAX)));
+        if r.missing {
+            lines.push("  path missing — skipped".to_string());
+        }
+        if let Some(e) = &r.error {
+            lines.push(format!("  ✖ {}", e));
+        }
+        for (path, branch) in &r.removed {
+            lines.push(format!("  {} {} ({})", verb, branch, display_path(path, false, usize::MAX)));
+        }
+        for (branch, e) in &r.failed {
+            lines.push(format!("  ✖ {}: {}", branch, e));
+        }
+        for u in &r.unmerged {
+            let why = u.divergence.as_ref().map_or("unmerged".to_string(), |d| ops::divergence_text(d, now));
+            lines.push(format!("  kept {} — {}", u.branch, why));
+        }
+        for (n, what) in [(r.skipped_dirty, "dirty"), (r.skipped_locked, "locked")] {
+            if n > 0 {
+                lines.push(format!("  skipped {} {}", n, what));
+            }
+        }
+        if !r.missing && r.error.is_none() && r.removed.is_empty() && r.failed.is_empty() {
+            lines.push("  nothing merged to clean".to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// `--json`: one object, `{"dry_run":…,"projects":[…]}`.
+pub fn json_report(results: &[ProjectClean], dry_run: bool) -> String {
+    let projects: Vec<String> = results
+        .iter()
+        .map(|r| {
+            let removed: Vec<String> = r
+                .removed
+                .iter()
+                .map(|(path, branch)| {
+                    format!("{{\"branch\":{},\"path\":{}}}", quote(branch), quote(&path.to_string_lossy()))
+                })
+                .collect();
+            let failed: Vec<String> = r
+                .failed
+                .iter()
+                .map(|(branch, e)| format!("{{\"branch\":{},\"error\":{}}}", quote(branch), quote(e)))
+                .collect();
+            let unmerged: Vec<String> = r
+                .unmerged
+                .iter()
+                .map(|u| {
+                    let (base_ts, trunk_since) = match &u.divergence {
+                        Some(d) => (d.base_ts.to_string(), d.trunk_since.to_string()),
+                        None => ("null".to_string(), "null".to_string()),
+                    };
+                    format!(
+                        "{{\"branch\":{},\"path\":{},\"base_ts\":{},\"trunk_since\":{}}}",
+                        quote(&u.branch),
+                        quote(&u.path.to_string_lossy()),
+                        base_ts,
+                        trunk_since
+                    )
+                })
+                .collect();
+            let error = r.error.as_deref().map_or("null".to_string(), quote);
+            format!(
+                "{{\"project\":{},\"path\":{},\"missing\":{},\"error\":{},\"removed\":[{}],\"failed\":[{}],\"skipped_dirty\":{},\"skipped_locked\":{},\"unmerged\":[{}]}}",
+                quote(&r.project),
+                quote(&r.path.to_string_lossy()),
+                r.missing,
+                error,
+                removed.join(","),
+                failed.join(","),
+                r.skipped_dirty,
+                r.skipped_locked,
+                unmerged.join(",")
+            )
+        })
+        .collect();
+    format!("{{\"dry_run\":{},\"projects\":[{}]}}", dry_run, projects.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean_all, exit_code, json_report, text_report, EXIT_FAILED};
+    use crate::config::global::GlobalConfig;
+    use crate::git::{fake::FakeGit, GitBackend};
+    use crate::model::frecency::now_secs;
+    use crate::scratch::TempDir;
+    use std::path::Path;
+
+    #[test]
+    fn dry_run_plans_and_the_real_run_removes() {
+        let repo = TempDir::new("wsx-clean-cli");
+        let git = FakeGit::default();
+        git.add_repo(&repo, "main");
+        let done = git.add_worktree(&repo, "done");
+        let dirty = git.add_worktree(&repo, "dirty");
+        let wip = git.add_worktree(&repo, "wip");
+        let old = git.add_worktree(&repo, "old");
+        git.set_diverged(&wip, "main", now_secs() - 3 * 86_400, 4);
+        git.set_diverged(&old, "main", now_secs() - 47 * 86_400, 23);
+        git.set_merged(&repo, "done");
+        git.set_merged(&repo, "dirty");
+        git.set_dirty(&dirty, 2);
+        let mut config = GlobalConfig::default();
+        config.add_project("clean".into(), repo.to_path_buf());
+        config.add_project("gone".into(), Path::new("/nonexistent/wsx-gone").to_path_buf());
+
+        let planned = clean_all(&git, &config, true);
+        assert_eq!(planned[0].removed, [(done.clone(), "done".to_string())]);
+        assert_eq!(planned[0].skipped_dirty, 1);
+        assert!(planned[1].missing);
+        assert_eq!(exit_code(&planned), 0);
+        assert_eq!(git.list_worktrees(&repo).unwrap().len(), 5, "dry run removes nothing");
+        let kept: Vec<&str> = planned[0].unmerged.iter().map(|u| u.branch.as_str()).collect();
+        assert_eq!(kept, ["old", "wip"], "oldest fork first");
+        let text = text_report(&planned, true);
+        assert!(text.contains("  would remove done (") && text.contains("  skipped 1 dirty"), "{}", text);
+        assert!(
+            text.contains("  kept old — diverged from main 47 days ago, 23 commits on main since\n  kept wip — diverged"),
+            "{}",
+            text
+        );
+        assert!(text.contains("gone (/nonexistent/wsx-gone)\n  path missing — skipped"), "{}", text);
+
+        let mut cleaned = clean_all(&git, &config, false);
+        assert_eq!(cleaned[0].removed, [(done, "done".to_string())]);
+        assert_eq!(git.list_worktrees(&repo).unwrap().len(), 4);
+        assert_eq!(exit_code(&cleaned), 0);
+        assert!(cleaned[0].unmerged.is_empty(), "only the dry run lists what stays");
+
+        cleaned[0].failed.push(("stuck".into(), "permission \"denied\"".into()));
+        assert_eq!(exit_code(&cleaned), EXIT_FAILED);
+        let json = json_report(&cleaned[..1], false);
+        assert!(json.starts_with("{\"dry_run\":false,\"projects\":[{\"project\":\"clean\","), "{}", json);
+        assert!(json.contains("\"failed\":[{\"branch\":\"stuck\",\"error\":\"permission \\\"denied\\\"\"}]"), "{}", json);
+        let json = json_report(&planned[..1], true);
+        assert!(json.contains("\"unmerged\":[{\"branch\":\"old\",\"path\":"), "{}", json);
+        assert!(json.contains("\"trunk_since\":23}"), "{}", json);
+    }
+}
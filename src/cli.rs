@@ -0,0 +1,98 @@
+// Command-line flags. Hand-rolled — wsx has very few of them.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Enter on a worktree exits and prints its path to stdout (for `cd "$(wsx --pick-path)"`).
+    pub pick_path: bool,
+    /// `wsx doctor`: print setup checks and exit.
+    pub doctor: bool,
+    /// write newline-delimited JSON events here (a FIFO is created if missing)
+    pub events_fifo: Option<PathBuf>,
+    /// show preview capture / git read counters in the status bar
+    pub debug: bool,
+    /// `wsx clean --all`: remove every project's merged worktrees and exit.
+    pub clean: Option<CleanArgs>,
+    /// `--config <path>`: a profile to use instead of the default config
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CleanArgs {
+    /// list what would go, remove nothing
+    pub dry_run: bool,
+    /// print the results as one JSON object
+    pub json: bool,
+}
+
+pub fn parse() -> Result<Args> {
+    let mut parsed = Args::default();
+    let mut args = std::env::args().skip(1);
+    let mut all = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pick-path" | "--print-on-exit" => parsed.pick_path = true,
+            "doctor" => parsed.doctor = true,
+            "clean" => parsed.clean = Some(CleanArgs::default()),
+            "--all" | "--dry-run" | "--json" => {
+                let clean = parsed
+                    .clean
+                    .as_mut()
+                    .with_context(|| format!("{} only goes with clean\n\n{}", arg, USAGE))?;
+                match arg.as_str() {
+                    "--all" => all = true,
+                    "--dry-run" => clean.dry_run = true,
+                    _ => clean.json = true,
+                }
+            }
+            "--debug" => parsed.debug = true,
+            "--config" => {
+                let path = args.next().context("--config needs a path")?;
+                parsed.config = Some(PathBuf::from(path));
+            }
+            "--events-fifo" => {
+                let path = args.next().context("--events-fifo needs a path")?;
+                parsed.events_fifo = Some(PathBuf::from(path));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "-V" | "--version" => {
+                println!("wsx {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            other => bail!("unknown argument: {}\n\n{}", other, USAGE),
+        }
+    }
+    if parsed.clean.is_some() && !all {
+        bail!("clean needs --all (c in the tree cleans one project)\n\n{}", USAGE);
+    }
+    Ok(parsed)
+}
+
+const USAGE: &str = "\
+usage: wsx [--config <path>] [--pick-path] [--events-fifo <path>] [--debug]
+       wsx [--config <path>] doctor
+       wsx [--config <path>] clean --all [--dry-run] [--json]
+
+  --config         Use <path> instead of ~/.config/wsx/config.toml (also
+                   WSX_CONFIG; the flag wins). Each profile keeps its own
+                   cache, and the status bar shows its name.
+  --pick-path      Enter on a worktree exits and prints its path to stdout
+                   (alias: --print-on-exit). q exits 1 without output.
+  --events-fifo    Write session_attention, session_idle, worktree_created,
+                   worktree_removed and refresh_error events to <path> as JSON
+                   lines; a FIFO is created if nothing is there. Events are
+                   dropped while no one reads.
+  --debug          Show pane capture and git read counters in the status bar,
+                   and list sessions that matched no worktree under D.
+  doctor           Check tmux/git versions, config and cache dirs, and project
+                   paths; exits 1 on a critical problem.
+  clean --all      Remove the merged worktrees of every project, as c does in
+                   the tree: dirty, locked and hidden ones stay. --dry-run only
+                   lists them, --json prints one JSON object. Exits 2 when a
+                   removal or a project failed.";
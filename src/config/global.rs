@@ -1,61 +1,380 @@
-// ~/.config/wsx/config.toml
+// ~/.config/wsx/config.toml, or the profile `--config` / `WSX_CONFIG` names
 // ref: toml crate — https://docs.rs/toml/
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::persist;
+
+/// The config file `--config` or `WSX_CONFIG` chose, fixed at startup; unset means the
+/// default one.
+static PROFILE: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct GlobalConfig {
     #[serde(default)]
     pub projects: Vec<ProjectEntry>,
+    /// after cleaning merged worktrees, offer to delete their remote branches too
+    #[serde(default)]
+    pub delete_remote_on_clean: bool,
+    /// ask before attaching to a session whose foreground app is interactive (vim, ssh, …)
+    #[serde(default)]
+    pub confirm_attach_running: bool,
+    /// wsx takes mouse clicks, drags and the wheel; false leaves them to the terminal so
+    /// click-drag selects text (default true; `M` toggles for the session)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mouse: Option<bool>,
+    /// tree pane width in columns; adjusted with `<`/`>` and saved on quit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidebar_width: Option<u16>,
+    /// project order in the tree: as listed here, or most recently used first
+    #[serde(default)]
+    pub project_order: ProjectOrder,
+    /// session order within a worktree: as arranged, or most recently attached first
+    #[serde(default)]
+    pub session_order: SessionOrder,
+    /// colour palette: follow the terminal background, or pin one (NO_COLOR always wins)
+    #[serde(default)]
+    pub theme: ThemeSetting,
+    /// the main worktree among a project's worktrees: where git lists it, always on top
+    /// (above pinned ones), or greyed out next to the feature worktrees
+    #[serde(default)]
+    pub main_worktree: MainWorktree,
+    /// session markers as distinct shapes (! ◆ ✔ · ⊘) that also differ in bold, reverse or
+    /// underline, for when the coloured dots are hard to tell apart
+    #[serde(default)]
+    pub accessible_markers: bool,
+    /// sessions older than this many days show their age in the tree, and `K` offers
+    /// to kill the idle plain-shell ones; 0 turns both off (default 7)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_session_days: Option<u64>,
+    /// ahead/behind older than this many minutes since the last successful fetch is
+    /// flagged in the preview as possibly out of date (default 60)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_stale_mins: Option<u64>,
+    /// how many days a session that went away stays in the `E` list with its last pane
+    /// capture; 0 keeps none (default 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_sessions_days: Option<u64>,
+    /// at most this many ended sessions are kept, newest first (default 20)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_sessions_max: Option<usize>,
+    /// tmux options set on every session wsx creates, as `set-option -t <session>`
+    /// arguments (default: ["mouse on"])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_defaults: Option<Vec<String>>,
+    /// where a git URL typed at `p` is cloned to by default, as `{clone_dir}/{repo}` (default ~)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_dir: Option<String>,
+    /// "open with" commands offered by `O`; `{path}` is replaced by the quoted path
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_with: Vec<OpenWith>,
+    /// how often the tree, session activity and preview are re-read
+    #[serde(default, skip_serializing_if = "Timers::is_default")]
+    pub timers: Timers,
+    /// how sessions are shown in the tree
+    #[serde(default, skip_serializing_if = "SessionSettings::is_default")]
+    pub session: SessionSettings,
+    /// projects as last loaded/saved — the merge base when another instance wrote meanwhile
+    #[serde(skip)]
+    base: Vec<ProjectEntry>,
+    /// mtime of the file we last read or wrote
+    #[serde(skip)]
+    loaded_mtime: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectOrder {
+    /// the order of `projects` (set with Move mode)
+    #[default]
+    Manual,
+    /// highest frecency first; ties keep the manual order
+    Frecency,
+}
+
+impl ProjectOrder {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Manual => Self::Frecency,
+            Self::Frecency => Self::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionOrder {
+    /// creation order, or as set with Move mode
+    #[default]
+    Manual,
+    /// last attached first; never-attached sessions keep their place after them
+    Recent,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MainWorktree {
+    /// first in git's order, after any pinned worktrees
+    #[default]
+    Plain,
+    /// first, even above pinned worktrees
+    Top,
+    /// in git's order, its name greyed out
+    Dim,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeSetting {
+    /// light or dark from `COLORFGBG` or the terminal's reported background
+    #[default]
+    Auto,
+    Dark,
+    Light,
+    /// no colours, as with NO_COLOR
+    Mono,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct OpenWith {
+    pub name: String,
+    /// shell command, e.g. `code {path}`
+    pub cmd: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// `[timers]` — polling intervals in milliseconds; values under the minimum are raised to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Timers {
+    /// full rescan of worktrees and sessions (default 2000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan_ms: Option<u64>,
+    /// session activity / bell check (default 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity_ms: Option<u64>,
+    /// preview pane capture (default 500)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_ms: Option<u64>,
+    /// no background rescans: the tree only changes on `R` or after an action
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manual_refresh: bool,
+}
+
+/// `[session]` — how sessions are shown in the tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SessionSettings {
+    /// `[session.icons]` — foreground command → glyph before the session name, over the
+    /// built-in ones (≈ dev server, ✎ editor, » anything else running); "" shows none
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub icons: BTreeMap<String, String>,
+}
+
+impl SessionSettings {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// (default, minimum) for each timer.
+const RESCAN_MS: (u64, u64) = (2000, 500);
+const ACTIVITY_MS: (u64, u64) = (1000, 250);
+const CAPTURE_MS: (u64, u64) = (500, 100);
+
+impl Timers {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn rescan_ms(&self) -> u64 {
+        timer_ms(self.rescan_ms, RESCAN_MS)
+    }
+
+    pub fn activity_ms(&self) -> u64 {
+        timer_ms(self.activity_ms, ACTIVITY_MS)
+    }
+
+    pub fn capture_ms(&self) -> u64 {
+        timer_ms(self.capture_ms, CAPTURE_MS)
+    }
+
+    /// One message per value that was set below its minimum.
+    pub fn problems(&self) -> Vec<String> {
+        [
+            ("rescan_ms", self.rescan_ms, RESCAN_MS.1),
+            ("activity_ms", self.activity_ms, ACTIVITY_MS.1),
+            ("capture_ms", self.capture_ms, CAPTURE_MS.1),
+        ]
+        .into_iter()
+        .filter_map(|(name, value, min)| {
+            let value = value.filter(|&v| v < min)?;
+            Some(format!("timers.{} = {} is below the minimum — using {}", name, value, min))
+        })
+        .collect()
+    }
+}
+
+fn timer_ms(value: Option<u64>, (default, min): (u64, u64)) -> u64 {
+    value.unwrap_or(default).max(min)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ProjectEntry {
     pub name: String,
     pub path: PathBuf,
     /// branch -> alias mapping (stored at app level, independent of git)
     #[serde(default)]
-    pub aliases: std::collections::HashMap<String, String>,
+    pub aliases: HashMap<String, String>,
+    /// integration branch to use instead of the detected one (`b` in the config view)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
 }
 
+pub const SIDEBAR_MIN: u16 = 20;
+pub const SIDEBAR_MAX: u16 = 60;
+pub const SIDEBAR_DEFAULT: u16 = 36;
+const SIDEBAR_STEP: i16 = 2;
+const STALE_SESSION_DAYS_DEFAULT: u64 = 7;
+const FETCH_STALE_MINS_DEFAULT: u64 = 60;
+const ENDED_SESSIONS_DAYS_DEFAULT: u64 = 3;
+const ENDED_SESSIONS_MAX_DEFAULT: usize = 20;
+const TMUX_DEFAULTS: &[&str] = &["mouse on"];
+
 impl GlobalConfig {
     pub fn config_path() -> Option<PathBuf> {
-        dirs::config_dir().map(|d| d.join("wsx").join("config.toml"))
+        Self::profile_path()
+            .map(Path::to_path_buf)
+            .or_else(|| dirs::config_dir().map(|d| d.join("wsx").join("config.toml")))
+    }
+
+    /// Read and write `path` instead of the default config for the rest of the process.
+    /// Only the first call counts.
+    pub fn use_profile(path: PathBuf) {
+        let _ = PROFILE.set(path);
+    }
+
+    /// The config file in use when it isn't the default one.
+    pub fn profile_path() -> Option<&'static Path> {
+        PROFILE.get().map(PathBuf::as_path)
+    }
+
+    /// "work" for `work.toml`; for a `config.toml`, the directory it sits in.
+    pub fn profile_name() -> Option<String> {
+        Self::profile_path().map(profile_name)
     }
 
     pub fn load() -> Result<Self> {
         let path = Self::config_path().context("no config dir")?;
+        let mut config = Self::read(&path)?.unwrap_or_default();
+        config.base = config.projects.clone();
+        config.loaded_mtime = persist::mtime(&path);
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Option<Self>> {
         if !path.exists() {
-            return Ok(Self::default());
+            return Ok(None);
         }
-        let text = std::fs::read_to_string(&path)
+        let text = std::fs::read_to_string(path)
             .with_context(|| format!("reading {}", path.display()))?;
         let config: Self = toml::from_str(&text)
             .with_context(|| format!("parsing {}", path.display()))?;
-        Ok(config)
+        Ok(Some(config))
     }
 
-    pub fn save(&self) -> Result<()> {
+    /// Save under a lock, first merging in whatever another instance wrote since
+    /// our last load/save so neither side's changes are lost.
+    pub fn save(&mut self) -> Result<()> {
         let path = Self::config_path().context("no config dir")?;
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        let text = toml::to_string_pretty(self)?;
-        std::fs::write(&path, text)?;
+        persist::with_lock(&path, || -> Result<()> {
+            if let Some(theirs) = Self::read(&path)? {
+                self.projects = merge_projects(&self.base, &self.projects, &theirs.projects);
+            }
+            let text = toml::to_string_pretty(self)?;
+            persist::write_atomic(&path, &text)?;
+            Ok(())
+        })??;
+        self.base = self.projects.clone();
+        self.loaded_mtime = persist::mtime(&path);
         Ok(())
     }
 
+    /// True when the file was written (by another instance) after our last load/save.
+    pub fn changed_on_disk(&self) -> bool {
+        Self::config_path()
+            .map(|p| persist::mtime(&p) != self.loaded_mtime)
+            .unwrap_or(false)
+    }
+
+    pub fn sidebar_width(&self) -> u16 {
+        self.sidebar_width
+            .unwrap_or(SIDEBAR_DEFAULT)
+            .clamp(SIDEBAR_MIN, SIDEBAR_MAX)
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse.unwrap_or(true)
+    }
+
+    pub fn stale_session_days(&self) -> u64 {
+        self.stale_session_days.unwrap_or(STALE_SESSION_DAYS_DEFAULT)
+    }
+
+    /// (count, seconds) an ended session's capture is kept for; (0, 0) when turned off
+    pub fn ended_sessions_limits(&self) -> (usize, u64) {
+        match self.ended_sessions_days.unwrap_or(ENDED_SESSIONS_DAYS_DEFAULT) {
+            0 => (0, 0),
+            days => (self.ended_sessions_max.unwrap_or(ENDED_SESSIONS_MAX_DEFAULT), days.saturating_mul(86_400)),
+        }
+    }
+
+    pub fn fetch_stale_after(&self) -> Duration {
+        Duration::from_secs(self.fetch_stale_mins.unwrap_or(FETCH_STALE_MINS_DEFAULT).saturating_mul(60))
+    }
+
+    pub fn tmux_defaults(&self) -> Vec<String> {
+        match &self.tmux_defaults {
+            Some(options) => options.clone(),
+            None => TMUX_DEFAULTS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Widen (`steps > 0`) or narrow the sidebar. Returns false when already at the limit.
+    pub fn resize_sidebar(&mut self, steps: i16) -> bool {
+        let current = self.sidebar_width();
+        let next = (current as i16 + steps * SIDEBAR_STEP)
+            .clamp(SIDEBAR_MIN as i16, SIDEBAR_MAX as i16) as u16;
+        self.sidebar_width = Some(next);
+        next != current
+    }
+
     pub fn add_project(&mut self, name: String, path: PathBuf) {
         self.projects.retain(|p| p.path != path);
-        self.projects.push(ProjectEntry { name, path, aliases: Default::default() });
+        self.projects.push(ProjectEntry { name, path, aliases: Default::default(), default_branch: None });
+    }
+
+    /// `None` goes back to detecting the default branch.
+    pub fn set_default_branch(&mut self, project_path: &Path, branch: Option<String>) {
+        if let Some(entry) = self.projects.iter_mut().find(|p| p.path == project_path) {
+            entry.default_branch = branch;
+        }
     }
 
     pub fn remove_project(&mut self, path: &PathBuf) {
         self.projects.retain(|p| &p.path != path);
     }
 
+    /// Move a project entry to `new_path`; aliases and overrides stay with it.
+    pub fn set_project_path(&mut self, old_path: &Path, new_path: PathBuf) {
+        if let Some(entry) = self.projects.iter_mut().find(|p| p.path == old_path) {
+            entry.path = new_path;
+        }
+    }
+
     pub fn set_alias(&mut self, project_path: &PathBuf, branch: &str, alias: &str) {
         if let Some(entry) = self.projects.iter_mut().find(|p| &p.path == project_path) {
             if alias.is_empty() {
@@ -66,3 +385,180 @@ impl GlobalConfig {
         }
     }
 }
+
+/// Three-way merge keyed by project path: apply our changes (base → ours) on top of
+/// theirs. Our order wins; projects only they added are appended.
+fn merge_projects(
+    base: &[ProjectEntry],
+    ours: &[ProjectEntry],
+    theirs: &[ProjectEntry],
+) -> Vec<ProjectEntry> {
+    let find = |list: &[ProjectEntry], path: &Path| list.iter().find(|e| e.path == path).cloned();
+    let mut merged = Vec::new();
+
+    for entry in ours {
+        let before = find(base, &entry.path);
+        match (before, find(theirs, &entry.path)) {
+            // They removed it and we didn't touch it — honour the removal.
+            (Some(b), None) if &b == entry => {}
+            (Some(b), Some(t)) => merged.push(ProjectEntry {
+                name: if entry.name != b.name { entry.name.clone() } else { t.name.clone() },
+                path: entry.path.clone(),
+                aliases: merge_aliases(&b.aliases, &entry.aliases, &t.aliases),
+                default_branch: if entry.default_branch != b.default_branch {
+                    entry.default_branch.clone()
+                } else {
+                    t.default_branch.clone()
+                },
+            }),
+            _ => merged.push(entry.clone()),
+        }
+    }
+    for entry in theirs {
+        let removed_by_us = find(base, &entry.path).is_some() && find(ours, &entry.path).is_none();
+        if !removed_by_us && find(&merged, &entry.path).is_none() {
+            merged.push(entry.clone());
+        }
+    }
+    merged
+}
+
+fn merge_aliases(
+    base: &HashMap<String, String>,
+    ours: &HashMap<String, String>,
+    theirs: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut merged = theirs.clone();
+    for (branch, alias) in ours {
+        if base.get(branch) != Some(alias) {
+            merged.insert(branch.clone(), alias.clone());
+        }
+    }
+    for branch in base.keys() {
+        if !ours.contains_key(branch) {
+            merged.remove(branch);
+        }
+    }
+    merged
+}
+
+/// The profile to use: `--config` over `WSX_CONFIG` (ignored when empty); None for the
+/// default config. The path is made absolute from `cwd` and resolved, so the cache a
+/// profile gets doesn't depend on how it was spelled: `work.toml`, `./work.toml` and a
+/// symlink to it are one profile.
+pub fn resolve_profile(flag: Option<PathBuf>, env: Option<OsString>, cwd: &Path) -> Option<PathBuf> {
+    let path = flag.or_else(|| env.filter(|v| !v.is_empty()).map(PathBuf::from))?;
+    Some(physical_profile(&cwd.join(path)))
+}
+
+/// `canonicalize`, or for a file not written yet, its resolved directory and name.
+fn physical_profile(path: &Path) -> PathBuf {
+    if let Ok(real) = std::fs::canonicalize(path) {
+        return real;
+    }
+    // collecting the components drops `.` and doubled slashes
+    let plain: PathBuf = path.components().collect();
+    match (plain.parent().map(std::fs::canonicalize), plain.file_name()) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => plain,
+    }
+}
+
+fn profile_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default();
+    let named = if stem == "config" { path.parent().and_then(Path::file_name).unwrap_or(stem) } else { stem };
+    named.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_projects, profile_name, resolve_profile, GlobalConfig, ProjectEntry};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    fn entry(path: &str, aliases: &[(&str, &str)]) -> ProjectEntry {
+        ProjectEntry {
+            name: path.trim_start_matches('/').to_string(),
+            path: PathBuf::from(path),
+            aliases: aliases.iter().map(|(b, a)| (b.to_string(), a.to_string())).collect(),
+            default_branch: None,
+        }
+    }
+
+    fn paths(list: &[ProjectEntry]) -> Vec<&str> {
+        list.iter().map(|e| e.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn merge_keeps_project_added_elsewhere() {
+        let base = vec![entry("/a", &[])];
+        let ours = vec![entry("/a", &[("feat", "f")])];
+        let theirs = vec![entry("/a", &[]), entry("/b", &[])];
+        let merged = merge_projects(&base, &ours, &theirs);
+        assert_eq!(paths(&merged), ["/a", "/b"]);
+        assert_eq!(merged[0].aliases.get("feat").map(String::as_str), Some("f"));
+    }
+
+    #[test]
+    fn merge_honours_removals_on_both_sides() {
+        let base = vec![entry("/a", &[]), entry("/b", &[]), entry("/c", &[])];
+        let ours = vec![entry("/a", &[]), entry("/c", &[])]; // we removed /b
+        let theirs = vec![entry("/a", &[]), entry("/b", &[])]; // they removed /c
+        assert_eq!(paths(&merge_projects(&base, &ours, &theirs)), ["/a"]);
+    }
+
+    #[test]
+    fn merge_combines_alias_edits() {
+        let base = vec![entry("/a", &[("x", "old"), ("y", "why")])];
+        let ours = vec![entry("/a", &[("x", "new"), ("y", "why")])];
+        let theirs = vec![entry("/a", &[("x", "old"), ("z", "zed")])]; // they dropped y
+        let merged = merge_projects(&base, &ours, &theirs);
+        let aliases = &merged[0].aliases;
+        assert_eq!(aliases.get("x").map(String::as_str), Some("new"));
+        assert_eq!(aliases.get("y"), None);
+        assert_eq!(aliases.get("z").map(String::as_str), Some("zed"));
+    }
+
+    #[test]
+    fn tmux_defaults_can_be_replaced_or_turned_off() {
+        let parse = |text: &str| toml::from_str::<GlobalConfig>(text).unwrap().tmux_defaults();
+        assert_eq!(parse(""), ["mouse on"]);
+        assert_eq!(parse(r#"tmux_defaults = ["history-limit 50000"]"#), ["history-limit 50000"]);
+        assert!(parse("tmux_defaults = []").is_empty());
+    }
+
+    #[test]
+    fn huge_minute_and_day_settings_saturate() {
+        let config = toml::from_str::<GlobalConfig>(&format!("fetch_stale_mins = {}", i64::MAX)).unwrap();
+        assert_eq!(config.fetch_stale_after(), Duration::from_secs(u64::MAX));
+        let config = toml::from_str::<GlobalConfig>(&format!("ended_sessions_days = {}", i64::MAX)).unwrap();
+        assert_eq!(config.ended_sessions_limits().1, u64::MAX);
+    }
+
+    #[test]
+    fn timers_default_and_respect_minimums() {
+        let timers = |text: &str| toml::from_str::<GlobalConfig>(text).unwrap().timers;
+        let default = timers("");
+        assert_eq!((default.rescan_ms(), default.activity_ms(), default.capture_ms()), (2000, 1000, 500));
+        assert!(!default.manual_refresh && default.problems().is_empty());
+
+        let set = timers("[timers]\nrescan_ms = 10000\nactivity_ms = 10\nmanual_refresh = true");
+        assert_eq!((set.rescan_ms(), set.activity_ms(), set.capture_ms()), (10000, 250, 500));
+        assert!(set.manual_refresh);
+        assert_eq!(set.problems(), ["timers.activity_ms = 10 is below the minimum — using 250"]);
+    }
+
+    #[test]
+    fn profile_comes_from_the_flag_then_the_environment() {
+        let cwd = Path::new("/home/u/code");
+        let flag = Some(PathBuf::from("/etc/wsx/work.toml"));
+        let env = Some("personal.toml".into());
+        assert_eq!(resolve_profile(flag, env.clone(), cwd), Some(PathBuf::from("/etc/wsx/work.toml")));
+        assert_eq!(resolve_profile(None, env, cwd), Some(PathBuf::from("/home/u/code/personal.toml")));
+        assert_eq!(resolve_profile(None, Some("".into()), cwd), None, "an empty WSX_CONFIG is unset");
+        assert_eq!(resolve_profile(None, None, cwd), None);
+
+        assert_eq!(profile_name(Path::new("/etc/wsx/work.toml")), "work");
+        assert_eq!(profile_name(Path::new("/home/u/.config/wsx-personal/config.toml")), "wsx-personal");
+    }
+}
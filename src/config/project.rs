@@ -2,42 +2,184 @@
 // Reads via `git config -f .gtrconfig` to support multi-value keys.
 
 use crate::model::workspace::ProjectConfig;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Keys that take one value; the project's replaces the global one.
+const SINGLE_KEYS: &[&str] =
+    &["hooks.postCreate", "copy.overwrite", "session.defaultCommand", "template.portRange", "alias.pattern"];
+/// Keys that take many; the project's are added after the global ones.
+const MULTI_KEYS: &[&str] = &["copy.include", "copy.exclude", "session.env", "template.file", "worktree.hide", "action.name"];
+/// As a value, drops whatever the global file (or an earlier line) set for the key.
+const CLEAR: &str = "!clear";
+
+/// Raw values per key, as one file lists them.
+type Layer = HashMap<&'static str, Vec<String>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Global,
+    Project,
+}
+
+/// Defaults every project's `.gtrconfig` is read on top of.
+pub fn global_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("wsx").join("gtrconfig"))
+}
+
 pub fn load_project_config(repo_path: &Path) -> ProjectConfig {
-    let config_path = repo_path.join(".gtrconfig");
+    let global = global_config_path().map(|p| read_layer(&p)).unwrap_or_default();
+    let project = read_layer(&repo_path.join(".gtrconfig"));
+    build_config(&merge_layers(&global, &project))
+}
+
+fn read_layer(config_path: &Path) -> Layer {
     if !config_path.exists() {
-        return ProjectConfig::default();
+        return Layer::new();
     }
+    SINGLE_KEYS
+        .iter()
+        .chain(MULTI_KEYS)
+        .map(|&key| (key, git_config_get_all(config_path, key, key == "copy.overwrite")))
+        .filter(|(_, values)| !values.is_empty())
+        .collect()
+}
 
-    let path_str = config_path.to_string_lossy();
-    let mut pc = ProjectConfig::default();
+/// `global` under `project`, each value tagged with the file it came from.
+fn merge_layers(global: &Layer, project: &Layer) -> Merged {
+    let tagged = |layer: &Layer, key, source| -> Vec<(String, Source)> {
+        layer.get(key).into_iter().flatten().map(|v| (v.clone(), source)).collect()
+    };
+    let mut merged = HashMap::new();
+    for &key in SINGLE_KEYS {
+        let value = tagged(project, key, Source::Project)
+            .pop()
+            .or_else(|| tagged(global, key, Source::Global).pop())
+            .filter(|(v, _)| v != CLEAR);
+        if let Some(value) = value {
+            merged.insert(key, vec![value]);
+        }
+    }
+    for &key in MULTI_KEYS {
+        let mut values = tagged(global, key, Source::Global);
+        values.extend(tagged(project, key, Source::Project));
+        if let Some(last_clear) = values.iter().rposition(|(v, _)| v == CLEAR) {
+            values.drain(..=last_clear);
+        }
+        if !values.is_empty() {
+            merged.insert(key, values);
+        }
+    }
+    merged
+}
+
+type Merged = HashMap<&'static str, Vec<(String, Source)>>;
+
+fn build_config(merged: &Merged) -> ProjectConfig {
+    let mut from_global = HashMap::new();
+    let mut single = |key: &'static str| {
+        let (value, source) = merged.get(key)?.last()?;
+        if *source == Source::Global {
+            from_global.insert(key, 1);
+        }
+        Some(value.clone())
+    };
+    let post_create = single("hooks.postCreate");
+    let copy_overwrite = single("copy.overwrite").is_some_and(|v| v == "true");
+    let default_command = single("session.defaultCommand").filter(|c| !c.trim().is_empty());
+    let template_ports = single("template.portRange").and_then(|r| parse_port_range(&r));
+    let pattern = single("alias.pattern");
 
-    pc.post_create = git_config_get(&path_str, "hooks.postCreate");
-    pc.copy_includes = git_config_get_all(&path_str, "copy.include");
-    pc.copy_excludes = git_config_get_all(&path_str, "copy.exclude");
+    ProjectConfig {
+        post_create,
+        copy_includes: multi(merged, "copy.include", &mut from_global, |v| v),
+        copy_excludes: multi(merged, "copy.exclude", &mut from_global, |v| v),
+        copy_overwrite,
+        session_env: multi(merged, "session.env", &mut from_global, parse_env),
+        default_command,
+        templates: multi(merged, "template.file", &mut from_global, parse_pairs),
+        template_ports,
+        worktree_hide: multi(merged, "worktree.hide", &mut from_global, |v| v),
+        actions: multi(merged, "action.name", &mut from_global, parse_pairs),
+        from_global,
+        ..alias_pattern(pattern)
+    }
+}
 
-    pc
+/// `key`'s parsed values. Global ones come first, so parsing each side on its own
+/// keeps the count of inherited ones exact when `parse` drops bad entries.
+fn multi<T>(
+    merged: &Merged,
+    key: &'static str,
+    from_global: &mut HashMap<&'static str, usize>,
+    parse: impl Fn(Vec<String>) -> Vec<T>,
+) -> Vec<T> {
+    let values = merged.get(key).map(Vec::as_slice).unwrap_or_default();
+    let side = |source| values.iter().filter(|(_, s)| *s == source).map(|(v, _)| v.clone()).collect();
+    let mut parsed = parse(side(Source::Global));
+    if !parsed.is_empty() {
+        from_global.insert(key, parsed.len());
+    }
+    parsed.extend(parse(side(Source::Project)));
+    parsed
 }
 
-fn git_config_get(config_path: &str, key: &str) -> Option<String> {
-    let out = Command::new("git")
-        .args(["config", "-f", config_path, "--get", key])
-        .output()
-        .ok()?;
-    if out.status.success() {
-        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
-    } else {
-        None
+/// The compiled `alias.pattern`, or why it didn't compile.
+fn alias_pattern(pattern: Option<String>) -> ProjectConfig {
+    let Some(pattern) = pattern.filter(|p| !p.is_empty()) else { return ProjectConfig::default() };
+    match regex::Regex::new(&pattern) {
+        Ok(re) => ProjectConfig { alias_pattern: Some(re), ..Default::default() },
+        Err(e) => ProjectConfig {
+            alias_pattern_error: Some(format!("invalid alias.pattern '{}': {}", pattern, e)),
+            ..Default::default()
+        },
     }
 }
 
-fn git_config_get_all(config_path: &str, key: &str) -> Vec<String> {
-    let Ok(output) = Command::new("git")
-        .args(["config", "-f", config_path, "--get-all", key])
-        .output()
-    else { return vec![] };
+/// `NAME=value` entries; ones without `=` or with an invalid shell name are dropped.
+fn parse_env(entries: Vec<String>) -> Vec<(String, String)> {
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (name, value) = entry.split_once('=')?;
+            let name = name.trim();
+            let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            valid.then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `src:dest` (or `label:command`) entries, split at the first `:`; ones missing
+/// either side are dropped.
+fn parse_pairs(entries: Vec<String>) -> Vec<(String, String)> {
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (src, dest) = entry.split_once(':')?;
+            let (src, dest) = (src.trim(), dest.trim());
+            (!src.is_empty() && !dest.is_empty()).then(|| (src.to_string(), dest.to_string()))
+        })
+        .collect()
+}
+
+/// `4000-4999`, inclusive; an empty or reversed range is ignored.
+fn parse_port_range(range: &str) -> Option<(u16, u16)> {
+    let (lo, hi) = range.split_once('-')?;
+    let (lo, hi): (u16, u16) = (lo.trim().parse().ok()?, hi.trim().parse().ok()?);
+    (lo > 0 && lo <= hi).then_some((lo, hi))
+}
+
+/// Every value of `key`; `as_bool` has git spell them `true` / `false`, whichever of
+/// yes/on/1 the file used.
+fn git_config_get_all(config_path: &Path, key: &str, as_bool: bool) -> Vec<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(["config", "-f"]).arg(config_path);
+    if as_bool {
+        cmd.arg("--type=bool");
+    }
+    let Ok(output) = cmd.args(["--get-all", key]).output() else { return vec![] };
     if !output.status.success() { return vec![]; }
     String::from_utf8_lossy(&output.stdout)
         .lines()
@@ -45,3 +187,107 @@ fn git_config_get_all(config_path: &str, key: &str) -> Vec<String> {
         .filter(|l| !l.is_empty())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{alias_pattern, build_config, merge_layers, parse_env, parse_pairs, parse_port_range, Layer};
+
+    fn layer(entries: &[(&'static str, &[&str])]) -> Layer {
+        entries.iter().map(|(k, vs)| (*k, vs.iter().map(|v| v.to_string()).collect())).collect()
+    }
+
+    #[test]
+    fn parses_session_env_entries() {
+        let entries = ["AWS_PROFILE=dev", "DATABASE_URL=postgres://u:p@h/db?a=b", "bad name=x", "NOEQUALS", "_X="];
+        assert_eq!(
+            parse_env(entries.iter().map(|s| s.to_string()).collect()),
+            vec![
+                ("AWS_PROFILE".to_string(), "dev".to_string()),
+                ("DATABASE_URL".to_string(), "postgres://u:p@h/db?a=b".to_string()),
+                ("_X".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_template_entries_and_port_ranges() {
+        let entries = [".envrc.tmpl:.envrc", "ops/compose.tmpl : docker-compose.override.yml", "nodest:", ":nosrc"];
+        assert_eq!(
+            parse_pairs(entries.iter().map(|s| s.to_string()).collect()),
+            vec![
+                (".envrc.tmpl".to_string(), ".envrc".to_string()),
+                ("ops/compose.tmpl".to_string(), "docker-compose.override.yml".to_string()),
+            ]
+        );
+        assert_eq!(parse_port_range("4000-4999"), Some((4000, 4999)));
+        assert_eq!(parse_port_range(" 8080 - 8080 "), Some((8080, 8080)));
+        assert_eq!(parse_port_range("5000-4000"), None);
+        assert_eq!(parse_port_range("4000"), None);
+    }
+
+    #[test]
+    fn alias_pattern_captures_the_alias_group() {
+        let config = alias_pattern(Some(r"^\w+/(?P<alias>[A-Z]+-\d+)".into()));
+        assert_eq!(config.pattern_alias("feature/PROJ-1234-short-desc").as_deref(), Some("PROJ-1234"));
+        assert_eq!(config.pattern_alias("main"), None);
+        let first_group = alias_pattern(Some(r"^fix/(\d+)".into()));
+        assert_eq!(first_group.pattern_alias("fix/42-typo").as_deref(), Some("42"));
+
+        let bad = alias_pattern(Some("(unclosed".into()));
+        assert!(bad.alias_pattern.is_none());
+        assert!(bad.alias_pattern_error.as_deref().is_some_and(|e| e.contains("(unclosed")));
+        assert_eq!(bad.pattern_alias("feature/PROJ-1"), None);
+    }
+
+    #[test]
+    fn project_values_override_or_extend_the_global_ones() {
+        let global = layer(&[
+            ("hooks.postCreate", &["direnv allow && npm ci"]),
+            ("session.defaultCommand", &["npm run dev"]),
+            ("copy.include", &[".env", ".envrc"]),
+            ("session.env", &["AWS_PROFILE=dev", "bad name=x"]),
+        ]);
+        let project = layer(&[
+            ("session.defaultCommand", &["cargo watch"]),
+            ("copy.include", &["config/local.toml"]),
+            ("session.env", &["RUST_LOG=debug"]),
+            ("action.name", &["Seed DB: make seed URL=http://localhost", "no command"]),
+        ]);
+        let config = build_config(&merge_layers(&global, &project));
+        assert_eq!(config.post_create.as_deref(), Some("direnv allow && npm ci"));
+        assert_eq!(config.inherited("hooks.postCreate"), 1);
+        assert_eq!(config.default_command.as_deref(), Some("cargo watch"), "project wins");
+        assert_eq!(config.inherited("session.defaultCommand"), 0);
+        assert_eq!(config.copy_includes, [".env", ".envrc", "config/local.toml"]);
+        assert_eq!(config.inherited("copy.include"), 2);
+        let names: Vec<&str> = config.session_env.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["AWS_PROFILE", "RUST_LOG"]);
+        assert_eq!(config.inherited("session.env"), 1, "counted after dropping invalid entries");
+        assert_eq!(config.actions, [("Seed DB".to_string(), "make seed URL=http://localhost".to_string())]);
+    }
+
+    #[test]
+    fn clear_drops_everything_before_it() {
+        let global = layer(&[("hooks.postCreate", &["npm ci"]), ("copy.include", &[".env", ".envrc"])]);
+        let project = layer(&[("hooks.postCreate", &["!clear"]), ("copy.include", &["a", "!clear", ".env.local"])]);
+        let config = build_config(&merge_layers(&global, &project));
+        assert_eq!(config.post_create, None);
+        assert_eq!(config.copy_includes, [".env.local"]);
+        assert_eq!(config.inherited("copy.include"), 0);
+
+        let cleared_only = layer(&[("copy.include", &["!clear"])]);
+        assert!(build_config(&merge_layers(&global, &cleared_only)).copy_includes.is_empty());
+    }
+
+    #[test]
+    fn an_empty_project_config_inherits_everything() {
+        let global = layer(&[("copy.overwrite", &["true"]), ("worktree.hide", &["ci-*"])]);
+        let config = build_config(&merge_layers(&global, &Layer::new()));
+        assert!(config.copy_overwrite);
+        assert_eq!(config.worktree_hide, ["ci-*"]);
+        assert_eq!(config.inherited("worktree.hide"), 1);
+
+        let none = build_config(&merge_layers(&Layer::new(), &Layer::new()));
+        assert!(none.post_create.is_none() && none.copy_includes.is_empty() && none.from_global.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+// Setup health checks — run at startup (popup / status hint) and by `wsx doctor`.
+
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::global::GlobalConfig;
+use crate::model::workspace::WorkspaceState;
+use crate::ops::OrphanSession;
+use crate::persist;
+use crate::tmux::monitor;
+
+/// `branch --show-current` (2.22) is the newest git feature we depend on.
+const MIN_GIT: (u32, u32) = (2, 22);
+/// Oldest tmux wsx runs on; below `monitor::FULL_FORMAT_TMUX` bells and running apps
+/// aren't detected.
+const MIN_TMUX: (u32, u32) = (2, 6);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// wsx works, but something will be surprising
+    Warning,
+    /// core features (sessions, worktrees, saving) will fail
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, message: message.into() }
+    }
+
+    fn critical(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Critical, message: message.into() }
+    }
+}
+
+pub fn doctor(config: &GlobalConfig) -> Vec<Finding> {
+    let mut findings = vec![];
+    findings.extend(check_tool("tmux", &["-V"], MIN_TMUX));
+    if let Some(version) = monitor::limited_tmux() {
+        let (major, minor) = monitor::FULL_FORMAT_TMUX;
+        findings.push(Finding::warning(format!(
+            "{} — bells and running apps need tmux {}.{}",
+            monitor::limited_note(version),
+            major,
+            minor
+        )));
+    }
+    findings.extend(check_tool("git", &["--version"], MIN_GIT));
+    if std::env::var_os("EDITOR").is_none_or(|e| e.is_empty()) {
+        findings.push(Finding::warning("$EDITOR is not set — `e` falls back to vi"));
+    }
+    if let Some(dir) = GlobalConfig::config_path().as_deref().and_then(Path::parent) {
+        if let Err(e) = check_writable(dir) {
+            findings.push(Finding::critical(format!(
+                "config dir {} is not writable ({}) — changes won't be saved",
+                dir.display(),
+                e
+            )));
+        }
+    }
+    if let Some(dir) = crate::cache::cache_path().parent() {
+        if let Err(e) = check_writable(dir) {
+            findings.push(Finding::warning(format!(
+                "cache dir {} is not writable ({}) — startup will be slower",
+                dir.display(),
+                e
+            )));
+        }
+    }
+    findings.extend(missing_projects(config));
+    findings.extend(config.timers.problems().into_iter().map(Finding::warning));
+    findings
+}
+
+fn check_tool(name: &str, args: &[&str], min: (u32, u32)) -> Option<Finding> {
+    let Ok(out) = Command::new(name).args(args).output() else {
+        return Some(Finding::critical(format!("{} not found on PATH", name)));
+    };
+    let text = String::from_utf8_lossy(&out.stdout);
+    match parse_version(&text) {
+        Some(v) if v < min => Some(Finding::critical(format!(
+            "{} is too old ({}) — wsx needs {}.{} or newer",
+            name,
+            text.trim(),
+            min.0,
+            min.1
+        ))),
+        Some(_) => None,
+        // Dev builds ("tmux master", "tmux next-3.5") are assumed new enough.
+        None if out.status.success() => None,
+        None => Some(Finding::critical(format!("`{} {}` failed", name, args.join(" ")))),
+    }
+}
+
+/// First `major.minor` in a version banner: "tmux 3.3a", "git version 2.39.2 (Apple Git-143)".
+pub(crate) fn parse_version(text: &str) -> Option<(u32, u32)> {
+    text.split_whitespace().find_map(|word| {
+        let mut parts = word.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor: String = parts.next()?.chars().take_while(char::is_ascii_digit).collect();
+        Some((major, minor.parse().ok()?))
+    })
+}
+
+/// Whether wsx could write in `dir`, or create it, asked of the nearest existing
+/// ancestor with access(2) — a check shouldn't leave a directory or probe file behind.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let existing = dir.ancestors().find(|d| d.exists()).unwrap_or(dir);
+    let c_path = std::ffi::CString::new(existing.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::access(c_path.as_ptr(), libc::W_OK | libc::X_OK) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn missing_projects(config: &GlobalConfig) -> Vec<Finding> {
+    config
+        .projects
+        .iter()
+        .filter(|p| !p.path.is_dir())
+        .map(|p| {
+            Finding::warning(format!(
+                "project '{}' points at a missing path: {}",
+                p.name,
+                p.path.display()
+            ))
+        })
+        .collect()
+}
+
+/// `.gtrconfig` problems found while loading projects.
+pub fn project_config_findings(workspace: &WorkspaceState) -> Vec<Finding> {
+    workspace
+        .projects
+        .iter()
+        .filter_map(|p| {
+            let err = p.config.as_ref()?.alias_pattern_error.as_ref()?;
+            Some(Finding::warning(format!("project '{}': {}", p.name, err)))
+        })
+        .collect()
+}
+
+/// `--debug`: tmux sessions that appear nowhere in the tree, and why.
+pub fn orphan_session_findings(orphans: &[OrphanSession]) -> Vec<Finding> {
+    orphans
+        .iter()
+        .map(|o| Finding::warning(format!("session '{}' matched no worktree: {}", o.name, o.reason)))
+        .collect()
+}
+
+/// True when the critical findings differ from those already shown in a startup popup,
+/// and records them — each distinct problem pops up once rather than on every launch.
+pub fn critical_unseen(findings: &[Finding]) -> bool {
+    let path = crate::cache::cache_path().with_file_name("doctor-seen");
+    let critical: Vec<&str> = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Critical)
+        .map(|f| f.message.as_str())
+        .collect();
+    if critical.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return false;
+    }
+    let text = critical.join("\n");
+    if std::fs::read_to_string(&path).is_ok_and(|seen| seen == text) {
+        return false;
+    }
+    let _ = persist::write_atomic(&path, &text);
+    true
+}
+
+/// Plain-text report for `wsx doctor`.
+pub fn report(findings: &[Finding]) -> String {
+    if findings.is_empty() {
+        return "wsx doctor: all checks passed".to_string();
+    }
+    findings
+        .iter()
+        .map(|f| match f.severity {
+            Severity::Critical => format!("✖ {}", f.message),
+            Severity::Warning => format!("! {}", f.message),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_writable, missing_projects, parse_version, report, Finding};
+    use crate::config::global::GlobalConfig;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_tool_version_banners() {
+        assert_eq!(parse_version("tmux 3.3a"), Some((3, 3)));
+        assert_eq!(parse_version("tmux 2.9"), Some((2, 9)));
+        assert_eq!(parse_version("git version 2.39.2 (Apple Git-143)"), Some((2, 39)));
+        assert_eq!(parse_version("tmux next-3.5"), None);
+        assert_eq!(parse_version("tmux master"), None);
+    }
+
+    #[test]
+    fn reports_missing_project_paths() {
+        let mut config = GlobalConfig::default();
+        config.add_project("gone".into(), PathBuf::from("/nonexistent/wsx-doctor"));
+        config.add_project("tmp".into(), std::env::temp_dir());
+        let findings = missing_projects(&config);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.starts_with("project 'gone'"));
+        let untouched = std::env::temp_dir().join(format!("wsx-doctor {}", std::process::id()));
+        assert!(check_writable(&untouched.join("config")).is_ok());
+        assert!(!untouched.exists(), "checking leaves nothing behind");
+        assert_eq!(
+            report(&[Finding::critical("a"), Finding::warning("b")]),
+            "✖ a\n! b"
+        );
+    }
+}
@@ -20,6 +20,9 @@ pub fn poll_event(timeout: Duration, in_input: bool) -> Result<Option<Action>> {
 
 /// Input mode: only special keys are translated; all chars go to the buffer.
 fn translate_input_key(key: KeyEvent) -> Action {
+    if is_ctrl_c(key) {
+        return Action::Interrupt;
+    }
     match key.code {
         KeyCode::Enter => Action::Select,
         KeyCode::Esc => Action::InputEscape,
@@ -29,6 +32,8 @@ fn translate_input_key(key: KeyEvent) -> Action {
         KeyCode::Up => Action::NavigateUp,
         KeyCode::Left => Action::NavigateLeft,
         KeyCode::Right => Action::NavigateRight,
+        KeyCode::PageUp => Action::ScrollPreview(-10),
+        KeyCode::PageDown => Action::ScrollPreview(10),
         KeyCode::Char(c) => Action::InputChar(c),
         _ => Action::None,
     }
@@ -37,12 +42,25 @@ fn translate_input_key(key: KeyEvent) -> Action {
 fn translate_mouse(mouse: MouseEvent) -> Action {
     match mouse.kind {
         MouseEventKind::Down(MouseButton::Left) => Action::MouseClick { col: mouse.column, row: mouse.row },
+        MouseEventKind::Drag(MouseButton::Left) => Action::MouseDrag { col: mouse.column, row: mouse.row },
+        MouseEventKind::Up(MouseButton::Left) => Action::MouseRelease,
+        MouseEventKind::ScrollUp => Action::MouseScroll { col: mouse.column, row: mouse.row, delta: -3 },
+        MouseEventKind::ScrollDown => Action::MouseScroll { col: mouse.column, row: mouse.row, delta: 3 },
         _ => Action::None,
     }
 }
 
+fn is_ctrl_c(key: KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c')
+}
+
 fn translate_key(key: KeyEvent) -> Action {
+    if is_ctrl_c(key) {
+        return Action::Interrupt;
+    }
     match (key.modifiers, key.code) {
+        (KeyModifiers::CONTROL, KeyCode::Char('r')) => Action::Respawn,
+        (KeyModifiers::CONTROL, KeyCode::Char('g')) => Action::LocatePath,
         (KeyModifiers::NONE, KeyCode::Char('q')) => Action::Quit,
         (KeyModifiers::NONE, KeyCode::Char('j')) | (KeyModifiers::NONE, KeyCode::Down) => Action::NavigateDown,
         (KeyModifiers::NONE, KeyCode::Char('k')) | (KeyModifiers::NONE, KeyCode::Up) => Action::NavigateUp,
@@ -51,6 +69,7 @@ fn translate_key(key: KeyEvent) -> Action {
         (KeyModifiers::NONE, KeyCode::Enter) => Action::Select,
         (KeyModifiers::NONE, KeyCode::Char('p')) => Action::AddProject,
         (KeyModifiers::NONE, KeyCode::Char('w')) => Action::AddWorktree,
+        (KeyModifiers::NONE, KeyCode::Char(' ')) => Action::StartWork,
         (KeyModifiers::NONE, KeyCode::Char('s')) => Action::AddSession,
         (KeyModifiers::NONE, KeyCode::Char('d')) => Action::Delete,
         (KeyModifiers::NONE, KeyCode::Char('c')) => Action::Clean,
@@ -59,8 +78,10 @@ fn translate_key(key: KeyEvent) -> Action {
         (KeyModifiers::SHIFT, KeyCode::Char('R')) | (KeyModifiers::NONE, KeyCode::Char('R')) => Action::Refresh,
         (KeyModifiers::NONE, KeyCode::Char('?')) => Action::Help,
         (KeyModifiers::NONE, KeyCode::Char('y')) => Action::ConfirmYes,
-        (KeyModifiers::NONE, KeyCode::Char('n')) => Action::NextAttention,
-        (KeyModifiers::SHIFT, KeyCode::Char('N')) | (KeyModifiers::NONE, KeyCode::Char('N')) => Action::PrevAttention,
+        (KeyModifiers::NONE, KeyCode::Char('n')) => Action::SearchNext,
+        (KeyModifiers::SHIFT, KeyCode::Char('N')) | (KeyModifiers::NONE, KeyCode::Char('N')) => Action::SearchPrev,
+        (KeyModifiers::NONE, KeyCode::Char('b')) => Action::NextAttention,
+        (KeyModifiers::SHIFT, KeyCode::Char('B')) | (KeyModifiers::NONE, KeyCode::Char('B')) => Action::PrevAttention,
         (KeyModifiers::NONE, KeyCode::Char('x')) => Action::DismissAttention,
         (KeyModifiers::NONE, KeyCode::Char('m')) => Action::EnterMove,
         (KeyModifiers::NONE, KeyCode::Char(']')) => Action::JumpProjectDown,
@@ -70,6 +91,33 @@ fn translate_key(key: KeyEvent) -> Action {
         (KeyModifiers::SHIFT, KeyCode::Char('C')) | (KeyModifiers::NONE, KeyCode::Char('C')) => Action::SendCtrlC,
         (KeyModifiers::NONE, KeyCode::Char('/')) => Action::SearchStart,
         (KeyModifiers::NONE, KeyCode::Char('g')) => Action::GitPopup,
+        (KeyModifiers::NONE, KeyCode::Char('f')) => Action::FetchNow,
+        (KeyModifiers::NONE, KeyCode::Char('o')) => Action::ToggleOrder,
+        (KeyModifiers::NONE, KeyCode::Char('v')) => Action::ToggleRawNames,
+        (KeyModifiers::SHIFT, KeyCode::Char('P')) | (KeyModifiers::NONE, KeyCode::Char('P')) => Action::ToggleFullPaths,
+        (KeyModifiers::SHIFT, KeyCode::Char('W')) | (KeyModifiers::NONE, KeyCode::Char('W')) => Action::ToggleWrap,
+        (KeyModifiers::SHIFT, KeyCode::Char('F')) | (KeyModifiers::NONE, KeyCode::Char('F')) => Action::ToggleFollow,
+        (_, KeyCode::PageUp) => Action::ScrollPreview(-10),
+        (_, KeyCode::PageDown) => Action::ScrollPreview(10),
+        (_, KeyCode::Char('*')) => Action::TogglePin,
+        (_, KeyCode::Char('#')) => Action::EditNote,
+        (_, KeyCode::Char('!')) => Action::MainShell,
+        (_, KeyCode::Char('.')) => Action::ProjectActions,
+        (_, KeyCode::Char('+')) => Action::Adopt,
+        (_, KeyCode::Char('{')) => Action::CycleWindow(-1),
+        (_, KeyCode::Char('}')) => Action::CycleWindow(1),
+        (KeyModifiers::SHIFT, KeyCode::Char('K')) | (KeyModifiers::NONE, KeyCode::Char('K')) => Action::KillStale,
+        (KeyModifiers::SHIFT, KeyCode::Char('O')) | (KeyModifiers::NONE, KeyCode::Char('O')) => Action::OpenWith,
+        (KeyModifiers::SHIFT, KeyCode::Char('D')) | (KeyModifiers::NONE, KeyCode::Char('D')) => Action::Doctor,
+        (KeyModifiers::SHIFT, KeyCode::Char('I')) | (KeyModifiers::NONE, KeyCode::Char('I')) => Action::Stats,
+        (KeyModifiers::SHIFT, KeyCode::Char('A')) | (KeyModifiers::NONE, KeyCode::Char('A')) => Action::Archive,
+        (KeyModifiers::SHIFT, KeyCode::Char('L')) | (KeyModifiers::NONE, KeyCode::Char('L')) => Action::ToggleLock,
+        (KeyModifiers::SHIFT, KeyCode::Char('E')) | (KeyModifiers::NONE, KeyCode::Char('E')) => Action::EndedSessions,
+        (KeyModifiers::SHIFT, KeyCode::Char('H')) | (KeyModifiers::NONE, KeyCode::Char('H')) => Action::ToggleHidden,
+        (KeyModifiers::SHIFT, KeyCode::Char('V')) | (KeyModifiers::NONE, KeyCode::Char('V')) => Action::ViewDiff,
+        (KeyModifiers::SHIFT, KeyCode::Char('M')) | (KeyModifiers::NONE, KeyCode::Char('M')) => Action::ToggleMouse,
+        (KeyModifiers::CONTROL, KeyCode::Left) | (_, KeyCode::Char('<')) => Action::SidebarShrink,
+        (KeyModifiers::CONTROL, KeyCode::Right) | (_, KeyCode::Char('>')) => Action::SidebarGrow,
         (KeyModifiers::NONE, KeyCode::Esc) => Action::InputEscape,
         (KeyModifiers::NONE, KeyCode::Backspace) => Action::InputBackspace,
         _ => Action::None,
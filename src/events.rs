@@ -0,0 +1,297 @@
+// Event stream — `--events-fifo <path>`: newline-delimited JSON for external scripts.
+// Writes never block; with no reader, or a slow one, events are dropped.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::app::IDLE_SECS;
+use crate::model::frecency;
+use crate::model::workspace::{SessionInfo, WorkspaceState};
+
+/// Pipe writes up to this size are atomic, so a line is never half-written.
+const PIPE_BUF: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// rang the bell, or its app went quiet (● in the tree)
+    SessionAttention { project: String, worktree: PathBuf, session: String },
+    /// stopped producing output without needing attention
+    SessionIdle { project: String, worktree: PathBuf, session: String },
+    WorktreeCreated { project: String, path: PathBuf, branch: String },
+    WorktreeRemoved { project: String, path: PathBuf, branch: String },
+    /// the project's worktrees could not be listed
+    RefreshError { project: String, message: String },
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::SessionAttention { .. } => "session_attention",
+            Event::SessionIdle { .. } => "session_idle",
+            Event::WorktreeCreated { .. } => "worktree_created",
+            Event::WorktreeRemoved { .. } => "worktree_removed",
+            Event::RefreshError { .. } => "refresh_error",
+        }
+    }
+
+    /// One JSON object, no trailing newline.
+    pub fn to_json(&self, ts: u64) -> String {
+        let mut fields = vec![
+            ("event", quote(self.name())),
+            ("ts", ts.to_string()),
+        ];
+        match self {
+            Event::SessionAttention { project, worktree, session }
+            | Event::SessionIdle { project, worktree, session } => {
+                fields.push(("project", quote(project)));
+                fields.push(("worktree", quote(&worktree.to_string_lossy())));
+                fields.push(("session", quote(session)));
+            }
+            Event::WorktreeCreated { project, path, branch }
+            | Event::WorktreeRemoved { project, path, branch } => {
+                fields.push(("project", quote(project)));
+                fields.push(("path", quote(&path.to_string_lossy())));
+                fields.push(("branch", quote(branch)));
+            }
+            Event::RefreshError { project, message } => {
+                fields.push(("project", quote(project)));
+                fields.push(("message", quote(message)));
+            }
+        }
+        let body: Vec<String> = fields.iter().map(|(k, v)| format!("\"{}\":{}", k, v)).collect();
+        format!("{{{}}}", body.join(","))
+    }
+}
+
+/// `s` as a JSON string literal.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Where events go. Disabled unless `--events-fifo` was given.
+#[derive(Default)]
+pub struct EventSink {
+    path: Option<PathBuf>,
+    /// opened lazily — a FIFO can only be opened for writing once a reader is there
+    file: Option<File>,
+}
+
+impl EventSink {
+    /// Creates the FIFO if nothing exists at `path`; an existing regular file is appended to.
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        if !path.exists() {
+            let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+            if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        let mut sink = Self { path: Some(path.to_path_buf()), file: None };
+        sink.connect();
+        Ok(sink)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    fn connect(&mut self) {
+        if self.file.is_some() {
+            return;
+        }
+        // ENXIO while no one is reading the FIFO; retried on the next event.
+        self.file = self.path.as_ref().and_then(|p| {
+            OpenOptions::new()
+                .append(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(p)
+                .ok()
+        });
+    }
+
+    pub fn emit(&mut self, events: impl IntoIterator<Item = Event>) {
+        if !self.enabled() {
+            return;
+        }
+        let ts = frecency::now_secs();
+        for event in events {
+            self.connect();
+            let Some(file) = self.file.as_mut() else { return };
+            let mut line = event.to_json(ts);
+            line.push('\n');
+            if line.len() > PIPE_BUF {
+                continue;
+            }
+            match file.write(line.as_bytes()) {
+                Ok(_) => {}
+                // Reader is behind and the pipe is full: drop this one.
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                // Reader went away (EPIPE) — reopen when the next event comes.
+                Err(_) => self.file = None,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionState {
+    Active,
+    Attention,
+    Idle,
+}
+
+/// Same reading as the tree's session icon; muted sessions aren't tracked.
+fn session_state(sess: &SessionInfo) -> Option<SessionState> {
+    let active = sess.last_activity.is_some_and(|t| t.elapsed().as_secs() < IDLE_SECS);
+    if sess.muted {
+        None
+    } else if sess.has_activity {
+        Some(SessionState::Attention)
+    } else if active {
+        Some(SessionState::Active)
+    } else if sess.needs_attention() {
+        Some(SessionState::Attention)
+    } else {
+        Some(SessionState::Idle)
+    }
+}
+
+/// Per-session state, for diffing across a refresh.
+pub type SessionStates = HashMap<String, (String, PathBuf, SessionState)>;
+
+pub fn session_states(workspace: &WorkspaceState) -> SessionStates {
+    let mut states = HashMap::new();
+    for p in &workspace.projects {
+        for wt in &p.worktrees {
+            for s in &wt.sessions {
+                if let Some(state) = session_state(s) {
+                    states.insert(s.name.clone(), (p.name.clone(), wt.path.clone(), state));
+                }
+            }
+        }
+    }
+    states
+}
+
+/// Sessions that started needing attention, or went quiet after output.
+pub fn session_events(before: &SessionStates, after: &SessionStates) -> Vec<Event> {
+    let mut names: Vec<&String> = after.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (project, worktree, state) = &after[name];
+            let old = before.get(name).map(|(_, _, s)| *s);
+            let (project, worktree, session) = (project.clone(), worktree.clone(), name.clone());
+            match (old, state) {
+                (Some(SessionState::Attention), SessionState::Attention) => None,
+                (_, SessionState::Attention) => {
+                    Some(Event::SessionAttention { project, worktree, session })
+                }
+                (Some(SessionState::Active), SessionState::Idle) => {
+                    Some(Event::SessionIdle { project, worktree, session })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// (project, path, branch) of every worktree, for diffing across a refresh.
+pub fn worktree_set(workspace: &WorkspaceState) -> Vec<(String, PathBuf, String)> {
+    workspace
+        .projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter().map(|w| (p.name.clone(), w.path.clone(), w.branch.clone())))
+        .collect()
+}
+
+pub fn worktree_events(
+    before: &[(String, PathBuf, String)],
+    after: &[(String, PathBuf, String)],
+) -> Vec<Event> {
+    let removed = before
+        .iter()
+        .filter(|(_, path, _)| !after.iter().any(|(_, p, _)| p == path))
+        .map(|(project, path, branch)| Event::WorktreeRemoved {
+            project: project.clone(),
+            path: path.clone(),
+            branch: branch.clone(),
+        });
+    let created = after
+        .iter()
+        .filter(|(_, path, _)| !before.iter().any(|(_, p, _)| p == path))
+        .map(|(project, path, branch)| Event::WorktreeCreated {
+            project: project.clone(),
+            path: path.clone(),
+            branch: branch.clone(),
+        });
+    removed.chain(created).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{session_events, Event, EventSink, SessionState};
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::PathBuf;
+
+    #[test]
+    fn events_serialize_as_escaped_json_lines() {
+        let event = Event::RefreshError { project: "a\"b".into(), message: "bad\npath\\x".into() };
+        assert_eq!(
+            event.to_json(42),
+            r#"{"event":"refresh_error","ts":42,"project":"a\"b","message":"bad\npath\\x"}"#
+        );
+        let wt = PathBuf::from("/w");
+        let state = |s| HashMap::from([("s".to_string(), ("p".to_string(), wt.clone(), s))]);
+        let idle = session_events(&state(SessionState::Active), &state(SessionState::Idle));
+        assert!(matches!(&idle[..], [Event::SessionIdle { session, .. }] if session == "s"));
+        let ring = session_events(&state(SessionState::Idle), &state(SessionState::Attention));
+        assert!(matches!(&ring[..], [Event::SessionAttention { .. }]));
+        assert!(session_events(&state(SessionState::Attention), &state(SessionState::Attention)).is_empty());
+    }
+
+    #[test]
+    fn fifo_drops_events_without_a_reader_and_delivers_with_one() {
+        let dir = std::env::temp_dir().join(format!("wsx-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events");
+        let _ = std::fs::remove_file(&path);
+        let mut sink = EventSink::open(&path).unwrap();
+        let event = Event::RefreshError { project: "p".into(), message: "m".into() };
+        sink.emit([event.clone()]); // nobody reading: dropped, doesn't block
+
+        let mut reader = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&path)
+            .unwrap();
+        sink.emit([event]);
+        let mut buf = [0; 512];
+        let n = reader.read(&mut buf).unwrap();
+        let text = String::from_utf8_lossy(&buf[..n]);
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.starts_with(r#"{"event":"refresh_error""#));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
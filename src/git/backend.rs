@@ -0,0 +1,236 @@
+// GitBackend — the git operations ops/App perform, behind a trait so the
+// state machine can be driven by an in-memory fake in tests.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{
+    info,
+    ops::{self, InProgress, StashOp},
+    worktree::{self, NewWorktree, RemoteBranch, WorktreeEntry},
+};
+use crate::model::workspace::{ArchivedWorktree, Divergence, GitInfo, StashEntry};
+
+pub trait GitBackend: Send + Sync {
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeEntry>>;
+    fn current_branch(&self, path: &Path) -> Option<String>;
+    /// The worktree a directory is inside; None outside a repo.
+    fn toplevel(&self, path: &Path) -> Option<PathBuf>;
+    fn git_info(&self, worktree: &Path, default_branch: &str) -> Option<GitInfo>;
+    /// `git fetch` for a worktree; runs on a background thread. Returns success.
+    fn fetch(&self, path: &Path) -> bool;
+
+    fn create_worktree(&self, repo: &Path, target: &NewWorktree) -> Result<PathBuf>;
+    /// `force` removes the worktree even with uncommitted changes; `branch` (None when
+    /// detached) is deleted afterwards if merged.
+    fn remove_worktree(&self, repo: &Path, worktree: &Path, branch: Option<&str>, force: bool) -> Result<()>;
+    /// `git worktree move`; `to` must not exist yet.
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()>;
+    /// `git worktree lock`, with `reason` when non-empty.
+    fn lock_worktree(&self, repo: &Path, worktree: &Path, reason: Option<&str>) -> Result<()>;
+    fn unlock_worktree(&self, repo: &Path, worktree: &Path) -> Result<()>;
+    /// Modified, staged and untracked files in a worktree; an error when git can't tell.
+    fn uncommitted_changes(&self, worktree: &Path) -> Result<usize>;
+    /// Local branches merged into `default_branch`, excluding it.
+    fn merged_branches(&self, repo: &Path, default_branch: &str) -> Result<Vec<String>>;
+    fn is_branch_merged(&self, repo: &Path, branch: &str, default_branch: &str) -> bool;
+    /// When a worktree's HEAD forked off `default_branch`, without the rest of `git_info`.
+    fn divergence(&self, worktree: &Path, default_branch: &str) -> Option<Divergence>;
+    /// Local branch names, for completion.
+    fn local_branches(&self, repo: &Path) -> Vec<String>;
+    /// `refs/heads/{branch}` exists.
+    fn branch_exists(&self, repo: &Path, branch: &str) -> bool;
+    fn remote_branch(&self, repo: &Path, branch: &str) -> Option<RemoteBranch>;
+    fn delete_remote_branch(&self, repo: &Path, remote: &str, branch: &str) -> Result<()>;
+    /// `git branch -D` — only after an archive tag has the branch's commits.
+    fn delete_branch(&self, repo: &Path, branch: &str) -> Result<()>;
+    /// `wsx/archive/…` tags, newest first.
+    fn archived_worktrees(&self, repo: &Path) -> Vec<ArchivedWorktree>;
+    /// Tag the tip of `branch` as archived today; returns the tag.
+    fn archive_branch(&self, repo: &Path, branch: &str) -> Result<String>;
+    fn delete_tag(&self, repo: &Path, tag: &str) -> Result<()>;
+    /// `commit` is reachable from the worktree's HEAD (`git merge-base --is-ancestor`).
+    fn head_contains(&self, worktree: &Path, commit: &str) -> bool;
+    /// Commits on the worktree's HEAD that no branch, tag or remote has; None if git
+    /// couldn't tell.
+    fn unreferenced_commits(&self, worktree: &Path) -> Option<usize>;
+
+    fn pull(&self, path: &Path) -> Result<String>;
+    fn push(&self, path: &Path) -> Result<String>;
+    fn pull_rebase(&self, path: &Path, branch: &str) -> Result<String>;
+    fn merge_from(&self, path: &Path, source: &str) -> Result<String>;
+    fn merge_into(&self, path: &Path, target: &str) -> Result<String>;
+    /// The repo's stashes made on `branch`, keeping their `stash@{N}` indices.
+    fn stash_list(&self, path: &Path, branch: &str) -> Vec<StashEntry>;
+    fn stash(&self, path: &Path, op: StashOp, index: usize) -> Result<String>;
+    /// Move `from`'s uncommitted changes to the worktree at `to` through a stash;
+    /// returns how many files moved.
+    fn carry_changes(&self, from: &Path, to: &Path) -> Result<usize>;
+    fn conflicted_files(&self, path: &Path) -> Vec<String>;
+    /// `git diff --color=always HEAD -- file`, for the diff popup.
+    fn file_diff(&self, path: &Path, file: &str) -> Result<String>;
+    /// `git add` one conflicted file.
+    fn mark_resolved(&self, path: &Path, file: &str) -> Result<String>;
+    fn in_progress(&self, path: &Path) -> Option<InProgress>;
+    fn continue_operation(&self, path: &Path) -> Result<String>;
+    /// `git clone url dest`; runs on a background thread, reporting percent received.
+    fn clone_repo(&self, url: &str, dest: &Path, progress: &dyn Fn(u8)) -> Result<()>;
+}
+
+/// The real thing: shells out to the `git` binary.
+pub struct CliGit;
+
+impl GitBackend for CliGit {
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeEntry>> {
+        worktree::list_worktrees(repo)
+    }
+
+    fn current_branch(&self, path: &Path) -> Option<String> {
+        info::current_branch(path)
+    }
+
+    fn toplevel(&self, path: &Path) -> Option<PathBuf> {
+        info::toplevel(path)
+    }
+
+    fn git_info(&self, worktree: &Path, default_branch: &str) -> Option<GitInfo> {
+        info::get_git_info(worktree, default_branch)
+    }
+
+    fn fetch(&self, path: &Path) -> bool {
+        info::git_fetch(path)
+    }
+
+    fn create_worktree(&self, repo: &Path, target: &NewWorktree) -> Result<PathBuf> {
+        worktree::create_worktree(repo, target)
+    }
+
+    fn remove_worktree(&self, repo: &Path, worktree: &Path, branch: Option<&str>, force: bool) -> Result<()> {
+        worktree::remove_worktree(repo, worktree, branch, force)
+    }
+
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+        worktree::move_worktree(repo, from, to)
+    }
+
+    fn lock_worktree(&self, repo: &Path, worktree: &Path, reason: Option<&str>) -> Result<()> {
+        worktree::lock_worktree(repo, worktree, reason)
+    }
+
+    fn unlock_worktree(&self, repo: &Path, worktree: &Path) -> Result<()> {
+        worktree::unlock_worktree(repo, worktree)
+    }
+
+    fn uncommitted_changes(&self, worktree: &Path) -> Result<usize> {
+        worktree::uncommitted_changes(worktree)
+    }
+
+    fn merged_branches(&self, repo: &Path, default_branch: &str) -> Result<Vec<String>> {
+        worktree::merged_branches(repo, default_branch)
+    }
+
+    fn is_branch_merged(&self, repo: &Path, branch: &str, default_branch: &str) -> bool {
+        worktree::is_branch_merged(repo, branch, default_branch)
+    }
+
+    fn divergence(&self, worktree: &Path, default_branch: &str) -> Option<Divergence> {
+        info::divergence(worktree, default_branch)
+    }
+
+    fn local_branches(&self, repo: &Path) -> Vec<String> {
+        worktree::local_branches(repo)
+    }
+
+    fn branch_exists(&self, repo: &Path, branch: &str) -> bool {
+        worktree::branch_exists(repo, branch)
+    }
+
+    fn remote_branch(&self, repo: &Path, branch: &str) -> Option<RemoteBranch> {
+        worktree::remote_branch(repo, branch)
+    }
+
+    fn delete_remote_branch(&self, repo: &Path, remote: &str, branch: &str) -> Result<()> {
+        worktree::delete_remote_branch(repo, remote, branch)
+    }
+
+    fn delete_branch(&self, repo: &Path, branch: &str) -> Result<()> {
+        worktree::delete_branch(repo, branch)
+    }
+
+    fn archived_worktrees(&self, repo: &Path) -> Vec<ArchivedWorktree> {
+        worktree::archived_worktrees(repo)
+    }
+
+    fn archive_branch(&self, repo: &Path, branch: &str) -> Result<String> {
+        worktree::archive_branch(repo, branch)
+    }
+
+    fn delete_tag(&self, repo: &Path, tag: &str) -> Result<()> {
+        worktree::delete_tag(repo, tag)
+    }
+
+    fn head_contains(&self, worktree: &Path, commit: &str) -> bool {
+        worktree::head_contains(worktree, commit)
+    }
+
+    fn unreferenced_commits(&self, worktree: &Path) -> Option<usize> {
+        worktree::unreferenced_commits(worktree)
+    }
+
+    fn pull(&self, path: &Path) -> Result<String> {
+        ops::pull(path)
+    }
+
+    fn push(&self, path: &Path) -> Result<String> {
+        ops::push(path)
+    }
+
+    fn pull_rebase(&self, path: &Path, branch: &str) -> Result<String> {
+        ops::pull_rebase(path, branch)
+    }
+
+    fn merge_from(&self, path: &Path, source: &str) -> Result<String> {
+        ops::merge_from(path, source)
+    }
+
+    fn merge_into(&self, path: &Path, target: &str) -> Result<String> {
+        ops::merge_into(path, target)
+    }
+
+    fn stash_list(&self, path: &Path, branch: &str) -> Vec<StashEntry> {
+        info::stash_list(path, branch)
+    }
+
+    fn stash(&self, path: &Path, op: StashOp, index: usize) -> Result<String> {
+        ops::stash(path, op, index)
+    }
+
+    fn carry_changes(&self, from: &Path, to: &Path) -> Result<usize> {
+        ops::carry_changes(from, to)
+    }
+
+    fn conflicted_files(&self, path: &Path) -> Vec<String> {
+        ops::conflicted_files(path)
+    }
+
+    fn file_diff(&self, path: &Path, file: &str) -> Result<String> {
+        ops::file_diff(path, file)
+    }
+
+    fn mark_resolved(&self, path: &Path, file: &str) -> Result<String> {
+        ops::mark_resolved(path, file)
+    }
+
+    fn in_progress(&self, path: &Path) -> Option<InProgress> {
+        ops::in_progress(path)
+    }
+
+    fn continue_operation(&self, path: &Path) -> Result<String> {
+        ops::continue_operation(path)
+    }
+
+    fn clone_repo(&self, url: &str, dest: &Path, progress: &dyn Fn(u8)) -> Result<()> {
+        ops::clone(url, dest, progress)
+    }
+}
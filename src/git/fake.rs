@@ -0,0 +1,523 @@
+// In-memory GitBackend for tests: repos are just lists of worktree entries.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+
+use super::{
+    backend::GitBackend,
+    ops::{InProgress, StashOp},
+    worktree::{archive_tag_name, parse_archive_tag, NewWorktree, RemoteBranch, WorktreeEntry},
+};
+use crate::model::workspace::{ArchivedWorktree, Divergence, GitInfo, StashEntry};
+
+#[derive(Default)]
+struct FakeRepo {
+    default_branch: String,
+    worktrees: Vec<WorktreeEntry>,
+    merged: HashSet<String>,
+    /// branches with a copy on `origin`
+    remote: HashSet<String>,
+    /// local branches `remove_worktree` or `delete_branch` deleted, in order
+    deleted_branches: Vec<String>,
+    /// archive tags, oldest first
+    tags: Vec<String>,
+    /// branches `delete_branch` fails on; they stay listed
+    undeletable: HashSet<String>,
+    /// the ref each worktree made on a new branch started from — what its HEAD contains
+    bases: HashMap<PathBuf, String>,
+}
+
+#[derive(Default)]
+pub struct FakeGit {
+    repos: Mutex<HashMap<PathBuf, FakeRepo>>,
+    /// uncommitted change count per worktree path
+    dirty: Mutex<HashMap<PathBuf, usize>>,
+    /// stash messages per worktree path, newest first
+    stashes: Mutex<HashMap<PathBuf, Vec<String>>>,
+    /// half-done merge or rebase per worktree path, with its unresolved files
+    conflicts: Mutex<HashMap<PathBuf, (InProgress, Vec<String>)>>,
+    /// (ahead, behind) per worktree path; only these report git info
+    tracking: Mutex<HashMap<PathBuf, (usize, usize)>>,
+    /// where each worktree forked off its repo's default branch; unset ones report none
+    diverged: Mutex<HashMap<PathBuf, Divergence>>,
+    /// commits only a worktree's HEAD has, per worktree path; unset ones have none
+    unreferenced: Mutex<HashMap<PathBuf, usize>>,
+}
+
+impl FakeGit {
+    /// Register a repo whose main worktree is `repo` on `default_branch`.
+    pub fn add_repo(&self, repo: &Path, default_branch: &str) {
+        let main = entry(repo, default_branch, true);
+        self.repos.lock().unwrap().insert(
+            repo.to_path_buf(),
+            FakeRepo {
+                default_branch: default_branch.to_string(),
+                worktrees: vec![main],
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Add a linked worktree at `{repo}-{branch}` and return its path.
+    pub fn add_worktree(&self, repo: &Path, branch: &str) -> PathBuf {
+        let path = worktree_path(repo, branch);
+        self.with_repo(repo, |r| r.worktrees.push(entry(&path, branch, false)));
+        path
+    }
+
+    /// A linked worktree on `branch` wherever `path` says, as `git worktree add` run by
+    /// hand might leave it.
+    pub fn add_worktree_at(&self, repo: &Path, branch: &str, path: &Path) {
+        self.with_repo(repo, |r| r.worktrees.push(entry(path, branch, false)));
+    }
+
+    pub fn set_unreferenced(&self, worktree: &Path, commits: usize) {
+        self.unreferenced.lock().unwrap().insert(worktree.to_path_buf(), commits);
+    }
+
+    /// A linked worktree detached at `refname`, at `{repo}-{refname}`.
+    pub fn add_detached(&self, repo: &Path, refname: &str) -> PathBuf {
+        let path = worktree_path(repo, refname);
+        let mut detached = entry(&path, refname, false);
+        detached.detached = true;
+        self.with_repo(repo, |r| r.worktrees.push(detached));
+        path
+    }
+
+    pub fn deleted_branches(&self, repo: &Path) -> Vec<String> {
+        self.with_repo(repo, |r| r.deleted_branches.clone())
+    }
+
+    pub fn set_merged(&self, repo: &Path, branch: &str) {
+        self.with_repo(repo, |r| {
+            r.merged.insert(branch.to_string());
+        });
+    }
+
+    /// Make `delete_branch` fail on `branch`, which then outlives it.
+    pub fn set_undeletable(&self, repo: &Path, branch: &str) {
+        self.with_repo(repo, |r| {
+            r.undeletable.insert(branch.to_string());
+        });
+    }
+
+    pub fn set_remote(&self, repo: &Path, branch: &str) {
+        self.with_repo(repo, |r| {
+            r.remote.insert(branch.to_string());
+        });
+    }
+
+    pub fn set_dirty(&self, worktree: &Path, changes: usize) {
+        self.dirty.lock().unwrap().insert(worktree.to_path_buf(), changes);
+    }
+
+    /// Give a worktree an upstream `ahead`/`behind` commits away; pull and push settle it.
+    pub fn set_tracking(&self, worktree: &Path, ahead: usize, behind: usize) {
+        self.tracking.lock().unwrap().insert(worktree.to_path_buf(), (ahead, behind));
+    }
+
+    /// Say `worktree` forked off `trunk` at `base_ts`, which has gained `trunk_since`
+    /// commits since.
+    pub fn set_diverged(&self, worktree: &Path, trunk: &str, base_ts: u64, trunk_since: usize) {
+        let divergence = Divergence { trunk: trunk.to_string(), base_ts, trunk_since };
+        self.diverged.lock().unwrap().insert(worktree.to_path_buf(), divergence);
+    }
+
+    /// Push a stash onto a worktree's stack; it becomes `stash@{0}`.
+    pub fn add_stash(&self, worktree: &Path, message: &str) {
+        let mut stashes = self.stashes.lock().unwrap();
+        stashes.entry(worktree.to_path_buf()).or_default().insert(0, message.to_string());
+    }
+
+    pub fn add_conflict(&self, worktree: &Path, op: InProgress, file: &str) {
+        let mut conflicts = self.conflicts.lock().unwrap();
+        let entry = conflicts.entry(worktree.to_path_buf()).or_insert((op, vec![]));
+        entry.1.push(file.to_string());
+    }
+
+    pub fn branches(&self, repo: &Path) -> Vec<String> {
+        let repos = self.repos.lock().unwrap();
+        repos
+            .get(repo)
+            .map(|r| r.worktrees.iter().map(|w| w.branch.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    fn with_repo<R>(&self, repo: &Path, f: impl FnOnce(&mut FakeRepo) -> R) -> R {
+        let mut repos = self.repos.lock().unwrap();
+        f(repos.get_mut(repo).expect("unknown fake repo"))
+    }
+}
+
+fn worktree_path(repo: &Path, branch: &str) -> PathBuf {
+    let name = repo.file_name().unwrap().to_string_lossy();
+    repo.with_file_name(format!("{}-{}", name, branch.replace('/', "-")))
+}
+
+fn entry(path: &Path, branch: &str, is_main: bool) -> WorktreeEntry {
+    WorktreeEntry {
+        name: path.file_name().unwrap().to_string_lossy().to_string(),
+        path: path.to_path_buf(),
+        branch: branch.to_string(),
+        is_main,
+        detached: false,
+        locked: None,
+    }
+}
+
+impl GitBackend for FakeGit {
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<WorktreeEntry>> {
+        match self.repos.lock().unwrap().get(repo) {
+            Some(r) => Ok(r.worktrees.clone()),
+            None => bail!("not a git repository: {}", repo.display()),
+        }
+    }
+
+    fn current_branch(&self, path: &Path) -> Option<String> {
+        let repos = self.repos.lock().unwrap();
+        repos
+            .values()
+            .flat_map(|r| &r.worktrees)
+            .find(|w| w.path == path)
+            .map(|w| w.branch.clone())
+    }
+
+    fn toplevel(&self, path: &Path) -> Option<PathBuf> {
+        let repos = self.repos.lock().unwrap();
+        repos
+            .values()
+            .flat_map(|r| &r.worktrees)
+            .filter(|w| path.starts_with(&w.path))
+            .max_by_key(|w| w.path.as_os_str().len())
+            .map(|w| w.path.clone())
+    }
+
+    fn git_info(&self, worktree: &Path, _default_branch: &str) -> Option<GitInfo> {
+        let (ahead, behind) = *self.tracking.lock().unwrap().get(worktree)?;
+        let changes = self.uncommitted_changes(worktree).ok()?;
+        Some(GitInfo {
+            recent_commits: vec![],
+            modified_files: (0..changes).map(|i| format!("file{}", i)).collect(),
+            ahead,
+            behind,
+            remote_branch: Some("origin/branch".to_string()),
+            trunk_ahead: 0,
+            stashes: self.current_branch(worktree).map(|b| self.stash_list(worktree, &b)).unwrap_or_default(),
+            divergence: self.diverged.lock().unwrap().get(worktree).cloned(),
+        })
+    }
+
+    fn fetch(&self, _path: &Path) -> bool {
+        true
+    }
+
+    fn create_worktree(&self, repo: &Path, target: &NewWorktree) -> Result<PathBuf> {
+        match target {
+            NewWorktree::Detached { refname } => Ok(self.add_detached(repo, refname)),
+            NewWorktree::Branch { name, base } => {
+                if self.branches(repo).iter().any(|b| b == name) {
+                    bail!("git worktree add: '{}' is already checked out", name);
+                }
+                let fresh = !self.branch_exists(repo, name);
+                let path = self.add_worktree(repo, name);
+                if fresh {
+                    self.with_repo(repo, |r| r.bases.insert(path.clone(), base.clone()));
+                }
+                Ok(path)
+            }
+        }
+    }
+
+    fn remove_worktree(&self, repo: &Path, worktree: &Path, branch: Option<&str>, force: bool) -> Result<()> {
+        if !force && self.uncommitted_changes(worktree)? > 0 {
+            bail!("'{}' contains modified or untracked files, use --force to delete it", worktree.display());
+        }
+        self.with_repo(repo, |r| {
+            if r.worktrees.iter().any(|w| w.path == worktree && w.locked.is_some()) {
+                bail!("cannot remove a locked working tree");
+            }
+            let before = r.worktrees.len();
+            r.worktrees.retain(|w| w.is_main || w.path != worktree);
+            if r.worktrees.len() == before {
+                bail!("not a worktree: {}", worktree.display());
+            }
+            r.deleted_branches.extend(branch.map(String::from));
+            Ok(())
+        })
+    }
+
+    fn move_worktree(&self, repo: &Path, from: &Path, to: &Path) -> Result<()> {
+        self.with_repo(repo, |r| {
+            if r.worktrees.iter().any(|w| w.path == to) {
+                bail!("'{}' already exists", to.display());
+            }
+            match r.worktrees.iter_mut().find(|w| w.path == from && !w.is_main) {
+                Some(w) if w.locked.is_some() => bail!("cannot move a locked working tree"),
+                Some(w) => {
+                    *w = WorktreeEntry { detached: w.detached, ..entry(to, &w.branch, false) };
+                    Ok(())
+                }
+                None => bail!("'{}' is not a working tree", from.display()),
+            }
+        })
+    }
+
+    fn lock_worktree(&self, repo: &Path, worktree: &Path, reason: Option<&str>) -> Result<()> {
+        self.with_repo(repo, |r| match r.worktrees.iter_mut().find(|w| w.path == worktree) {
+            Some(w) if w.locked.is_some() => bail!("'{}' is already locked", worktree.display()),
+            Some(w) => {
+                w.locked = Some(reason.unwrap_or_default().to_string());
+                Ok(())
+            }
+            None => bail!("'{}' is not a working tree", worktree.display()),
+        })
+    }
+
+    fn unlock_worktree(&self, repo: &Path, worktree: &Path) -> Result<()> {
+        self.with_repo(repo, |r| match r.worktrees.iter_mut().find(|w| w.path == worktree) {
+            Some(w) if w.locked.is_some() => {
+                w.locked = None;
+                Ok(())
+            }
+            Some(_) => bail!("'{}' is not locked", worktree.display()),
+            None => bail!("'{}' is not a working tree", worktree.display()),
+        })
+    }
+
+    fn uncommitted_changes(&self, worktree: &Path) -> Result<usize> {
+        Ok(self.dirty.lock().unwrap().get(worktree).copied().unwrap_or(0))
+    }
+
+    fn merged_branches(&self, repo: &Path, default_branch: &str) -> Result<Vec<String>> {
+        Ok(self.with_repo(repo, |r| {
+            let mut merged: Vec<String> =
+                r.merged.iter().filter(|b| *b != default_branch).cloned().collect();
+            merged.sort();
+            merged
+        }))
+    }
+
+    fn is_branch_merged(&self, repo: &Path, branch: &str, default_branch: &str) -> bool {
+        branch == default_branch || self.with_repo(repo, |r| r.merged.contains(branch))
+    }
+
+    fn divergence(&self, worktree: &Path, _default_branch: &str) -> Option<Divergence> {
+        self.diverged.lock().unwrap().get(worktree).cloned()
+    }
+
+    fn local_branches(&self, repo: &Path) -> Vec<String> {
+        self.with_repo(repo, |r| {
+            let mut branches: Vec<String> = r.worktrees.iter().map(|w| w.branch.clone()).collect();
+            branches.extend(r.merged.iter().cloned());
+            branches.extend(r.undeletable.iter().cloned());
+            branches.sort();
+            branches.dedup();
+            branches
+        })
+    }
+
+    fn branch_exists(&self, repo: &Path, branch: &str) -> bool {
+        self.local_branches(repo).iter().any(|b| b == branch)
+    }
+
+    fn remote_branch(&self, repo: &Path, branch: &str) -> Option<RemoteBranch> {
+        self.with_repo(repo, |r| r.remote.contains(branch)).then(|| RemoteBranch {
+            repo: repo.to_path_buf(),
+            remote: "origin".to_string(),
+            branch: branch.to_string(),
+        })
+    }
+
+    fn delete_remote_branch(&self, repo: &Path, _remote: &str, branch: &str) -> Result<()> {
+        if !self.with_repo(repo, |r| r.remote.remove(branch)) {
+            bail!("already deleted");
+        }
+        Ok(())
+    }
+
+    fn delete_branch(&self, repo: &Path, branch: &str) -> Result<()> {
+        self.with_repo(repo, |r| {
+            if r.undeletable.contains(branch) {
+                bail!("error: cannot delete branch '{}'", branch);
+            }
+            r.deleted_branches.push(branch.to_string());
+            Ok(())
+        })
+    }
+
+    fn archived_worktrees(&self, repo: &Path) -> Vec<ArchivedWorktree> {
+        let tags = self.repos.lock().unwrap().get(repo).map(|r| r.tags.clone()).unwrap_or_default();
+        tags.iter().rev().filter_map(|t| parse_archive_tag(t)).collect()
+    }
+
+    /// Every archive is dated 2026-01-01.
+    fn archive_branch(&self, repo: &Path, branch: &str) -> Result<String> {
+        Ok(self.with_repo(repo, |r| {
+            let tag = archive_tag_name(branch, "20260101", &r.tags);
+            r.tags.push(tag.clone());
+            tag
+        }))
+    }
+
+    fn delete_tag(&self, repo: &Path, tag: &str) -> Result<()> {
+        self.with_repo(repo, |r| {
+            let before = r.tags.len();
+            r.tags.retain(|t| t != tag);
+            if r.tags.len() == before {
+                bail!("tag '{}' not found.", tag);
+            }
+            Ok(())
+        })
+    }
+
+    fn head_contains(&self, worktree: &Path, commit: &str) -> bool {
+        let repos = self.repos.lock().unwrap();
+        repos.values().any(|r| r.bases.get(worktree).is_some_and(|b| b == commit))
+    }
+
+    fn unreferenced_commits(&self, worktree: &Path) -> Option<usize> {
+        Some(self.unreferenced.lock().unwrap().get(worktree).copied().unwrap_or(0))
+    }
+
+    fn pull(&self, path: &Path) -> Result<String> {
+        if let Some((_, behind)) = self.tracking.lock().unwrap().get_mut(path) {
+            *behind = 0;
+        }
+        Ok("Already up to date.".to_string())
+    }
+
+    fn push(&self, path: &Path) -> Result<String> {
+        if let Some((ahead, _)) = self.tracking.lock().unwrap().get_mut(path) {
+            *ahead = 0;
+        }
+        Ok("Everything up-to-date".to_string())
+    }
+
+    fn pull_rebase(&self, path: &Path, _branch: &str) -> Result<String> {
+        if let Some((_, behind)) = self.tracking.lock().unwrap().get_mut(path) {
+            *behind = 0;
+        }
+        Ok("Current branch is up to date.".to_string())
+    }
+
+    /// Always makes a merge commit.
+    fn merge_from(&self, path: &Path, _source: &str) -> Result<String> {
+        if let Some((ahead, _)) = self.tracking.lock().unwrap().get_mut(path) {
+            *ahead += 1;
+        }
+        Ok("Merge made by the 'ort' strategy.".to_string())
+    }
+
+    fn merge_into(&self, path: &Path, target: &str) -> Result<String> {
+        let mut repos = self.repos.lock().unwrap();
+        let Some(repo) = repos.values_mut().find(|r| r.worktrees.iter().any(|w| w.path == path))
+        else {
+            bail!("not a worktree: {}", path.display());
+        };
+        let Some(there) = repo.worktrees.iter().find(|w| !w.detached && w.branch == target) else {
+            bail!("{} isn't checked out in any worktree", target);
+        };
+        if self.uncommitted_changes(&there.path)? > 0 {
+            bail!("{} has uncommitted changes at {}", target, there.path.display());
+        }
+        if let Some((ahead, _)) = self.tracking.lock().unwrap().get_mut(&there.path) {
+            *ahead += 1;
+        }
+        let branch = repo.worktrees.iter().find(|w| w.path == path).unwrap().branch.clone();
+        if target == repo.default_branch {
+            repo.merged.insert(branch.clone());
+        }
+        Ok(format!("merged {} into {}", branch, target))
+    }
+
+    fn stash_list(&self, path: &Path, branch: &str) -> Vec<StashEntry> {
+        let stashes = self.stashes.lock().unwrap();
+        stashes
+            .get(path)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, message)| StashEntry { index, message: message.clone(), ts: 0 })
+            .filter(|s| s.made_on(branch))
+            .collect()
+    }
+
+    fn stash(&self, path: &Path, op: StashOp, index: usize) -> Result<String> {
+        let mut stashes = self.stashes.lock().unwrap();
+        let stack = stashes.entry(path.to_path_buf()).or_default();
+        if index >= stack.len() {
+            bail!("stash@{{{}}} is not a valid reference", index);
+        }
+        match op {
+            StashOp::Apply => Ok("Changes applied".to_string()),
+            StashOp::Pop | StashOp::Drop => {
+                stack.remove(index);
+                Ok(format!("Dropped stash@{{{}}}", index))
+            }
+            StashOp::Show => Ok(" src/main.rs | 2 +-\n 1 file changed".to_string()),
+        }
+    }
+
+    /// The dirty count moves over; nothing can conflict.
+    fn carry_changes(&self, from: &Path, to: &Path) -> Result<usize> {
+        let mut dirty = self.dirty.lock().unwrap();
+        let files = dirty.remove(from).unwrap_or(0);
+        if files == 0 {
+            bail!("no uncommitted changes in {}", from.display());
+        }
+        *dirty.entry(to.to_path_buf()).or_default() += files;
+        Ok(files)
+    }
+
+    fn conflicted_files(&self, path: &Path) -> Vec<String> {
+        let conflicts = self.conflicts.lock().unwrap();
+        conflicts.get(path).map(|(_, files)| files.clone()).unwrap_or_default()
+    }
+
+    /// Any `.png` is binary; other files get one added line naming them.
+    fn file_diff(&self, _path: &Path, file: &str) -> Result<String> {
+        if file.ends_with(".png") {
+            return Ok(format!("diff --git a/{0} b/{0}\nBinary files a/{0} and b/{0} differ", file));
+        }
+        Ok(format!("diff --git a/{0} b/{0}\n+{0} changed", file))
+    }
+
+    fn mark_resolved(&self, path: &Path, file: &str) -> Result<String> {
+        let mut conflicts = self.conflicts.lock().unwrap();
+        let Some((_, files)) = conflicts.get_mut(path) else {
+            bail!("pathspec '{}' did not match any files", file);
+        };
+        files.retain(|f| f != file);
+        Ok(String::new())
+    }
+
+    fn in_progress(&self, path: &Path) -> Option<InProgress> {
+        self.conflicts.lock().unwrap().get(path).map(|(op, _)| *op)
+    }
+
+    fn continue_operation(&self, path: &Path) -> Result<String> {
+        let mut conflicts = self.conflicts.lock().unwrap();
+        match conflicts.get(path) {
+            None => bail!("no merge or rebase in progress"),
+            Some((_, files)) if !files.is_empty() => bail!("you have unmerged files"),
+            Some(_) => {
+                conflicts.remove(path);
+                Ok("Successfully continued".to_string())
+            }
+        }
+    }
+
+    /// Makes `dest` a repo on `main`; a `dest` that is already a repo fails like git does.
+    fn clone_repo(&self, _url: &str, dest: &Path, progress: &dyn Fn(u8)) -> Result<()> {
+        if self.repos.lock().unwrap().contains_key(dest) {
+            bail!("fatal: destination path '{}' already exists and is not an empty directory.", dest.display());
+        }
+        progress(50);
+        progress(100);
+        self.add_repo(dest, "main");
+        Ok(())
+    }
+}
@@ -1,31 +1,82 @@
 // Git info via CLI — branch, commits, modified files, ahead/behind
 
-use super::git_cmd;
-use crate::model::workspace::{CommitSummary, GitInfo};
-use std::path::Path;
+use super::{git_cmd, run_with_timeout, LOCAL_TIMEOUT};
+use crate::model::workspace::{CommitSummary, Divergence, GitInfo, StashEntry};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 
-pub fn get_git_info(worktree_path: &Path, _default_branch: &str) -> Option<GitInfo> {
+pub fn get_git_info(worktree_path: &Path, default_branch: &str) -> Option<GitInfo> {
     // require a valid branch (confirms we're in a real worktree)
-    current_branch(worktree_path)?;
+    let branch = current_branch(worktree_path)?;
     let recent_commits = recent_commits(worktree_path, 3);
     let modified_files = modified_files(worktree_path);
     let (ahead, behind) = ahead_behind(worktree_path);
     let remote_branch = upstream_branch(worktree_path);
+    let trunk = ahead_behind_of(worktree_path, default_branch);
+    let trunk_ahead = trunk.map(|(ahead, _)| ahead).unwrap_or(0);
+    let divergence = (trunk.is_some() && branch != default_branch)
+        .then(|| divergence(worktree_path, default_branch))
+        .flatten();
     Some(GitInfo {
         recent_commits,
         modified_files,
         ahead,
         behind,
         remote_branch,
+        trunk_ahead,
+        stashes: stash_list(worktree_path, &branch),
+        divergence,
     })
 }
 
+/// Background fetches give up sooner than a user-started pull or push.
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Field and record separators for `stash_list` — stash messages may span lines.
+const STASH_FORMAT: &str = "--format=%gd%x1f%ct%x1f%B%x1e";
+
+/// Stashes made on `branch`; the others belong to sibling worktrees.
+pub fn stash_list(path: &Path, branch: &str) -> Vec<StashEntry> {
+    let Ok(out) = run_with_timeout(git_cmd(path).args(["stash", "list", STASH_FORMAT]), LOCAL_TIMEOUT) else {
+        return vec![];
+    };
+    if !out.status.success() {
+        return vec![];
+    }
+    let mut entries = parse_stash_list(&String::from_utf8_lossy(&out.stdout));
+    entries.retain(|s| s.made_on(branch));
+    entries
+}
+
+fn parse_stash_list(text: &str) -> Vec<StashEntry> {
+    text.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(3, '\x1f');
+            let index = fields
+                .next()?
+                .strip_prefix("stash@{")?
+                .strip_suffix('}')?
+                .parse()
+                .ok()?;
+            let ts = fields.next()?.parse().ok()?;
+            let mut lines = fields.next().unwrap_or("").lines().filter(|l| !l.trim().is_empty());
+            let mut message = lines.next().unwrap_or("").trim().to_string();
+            if lines.next().is_some() {
+                message.push_str(" …");
+            }
+            Some(StashEntry { index, message, ts })
+        })
+        .collect()
+}
+
 /// Returns the upstream tracking branch name (e.g. "origin/main"), or None if untracked.
 fn upstream_branch(path: &Path) -> Option<String> {
-    let out = git_cmd(path)
-        .args(["rev-parse", "--abbrev-ref", "@{upstream}"])
-        .output()
-        .ok()?;
+    let out = run_with_timeout(
+        git_cmd(path).args(["rev-parse", "--abbrev-ref", "@{upstream}"]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
     if !out.status.success() {
         return None;
     }
@@ -37,45 +88,18 @@ fn upstream_branch(path: &Path) -> Option<String> {
     }
 }
 
-/// Run `git fetch` in the background thread — polls with timeout to avoid hanging.
+/// Run `git fetch` in the background thread — with a timeout so it can't hang.
 pub(crate) fn git_fetch(path: &Path) -> bool {
-    let Ok(mut child) = std::process::Command::new("git")
-        .args(["fetch", "--no-tags", "--quiet"])
-        .current_dir(path)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .spawn()
-    else {
-        return false;
-    };
-
-    let start = std::time::Instant::now();
-    let timeout = std::time::Duration::from_secs(10);
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) => return status.success(),
-            Ok(None) => {
-                if start.elapsed() > timeout {
-                    // Edge race: process may have exited after the previous `try_wait`.
-                    if let Ok(Some(status)) = child.try_wait() {
-                        return status.success();
-                    }
-                    let _ = child.kill();
-                    let _ = child.wait();
-                    return false;
-                }
-                std::thread::sleep(std::time::Duration::from_millis(200));
-            }
-            Err(_) => return false,
-        }
-    }
+    run_with_timeout(git_cmd(path).args(["fetch", "--no-tags", "--quiet"]), FETCH_TIMEOUT)
+        .is_ok_and(|out| out.status.success())
 }
 
 pub fn current_branch(path: &Path) -> Option<String> {
-    let out = git_cmd(path)
-        .args(["branch", "--show-current"])
-        .output()
-        .ok()?;
+    let out = run_with_timeout(
+        git_cmd(path).args(["branch", "--show-current"]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
     let branch = String::from_utf8_lossy(&out.stdout).trim().to_string();
     if branch.is_empty() {
         None
@@ -84,10 +108,22 @@ pub fn current_branch(path: &Path) -> Option<String> {
     }
 }
 
+/// `git rev-parse --show-toplevel`: the worktree `path` is inside, None outside a repo.
+pub fn toplevel(path: &Path) -> Option<PathBuf> {
+    let out = run_with_timeout(
+        git_cmd(path).args(["rev-parse", "--show-toplevel"]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
+    let top = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !top.is_empty()).then(|| PathBuf::from(top))
+}
+
 fn recent_commits(path: &Path, n: usize) -> Vec<CommitSummary> {
-    let Ok(out) = git_cmd(path)
-        .args(["log", "--oneline", &format!("-{}", n)])
-        .output()
+    let Ok(out) = run_with_timeout(
+        git_cmd(path).args(["log", "--oneline", &format!("-{}", n)]),
+        LOCAL_TIMEOUT,
+    )
     else {
         return vec![];
     };
@@ -103,7 +139,7 @@ fn recent_commits(path: &Path, n: usize) -> Vec<CommitSummary> {
 }
 
 fn modified_files(path: &Path) -> Vec<String> {
-    let Ok(out) = git_cmd(path).args(["status", "--short"]).output() else {
+    let Ok(out) = run_with_timeout(git_cmd(path).args(["status", "--short"]), LOCAL_TIMEOUT) else {
         return vec![];
     };
     String::from_utf8_lossy(&out.stdout)
@@ -120,9 +156,10 @@ fn modified_files(path: &Path) -> Vec<String> {
 }
 
 fn ahead_behind(path: &Path) -> (usize, usize) {
-    let Ok(out) = git_cmd(path)
-        .args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
-        .output()
+    let Ok(out) = run_with_timeout(
+        git_cmd(path).args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"]),
+        LOCAL_TIMEOUT,
+    )
     else {
         return (0, 0);
     };
@@ -132,3 +169,151 @@ fn ahead_behind(path: &Path) -> (usize, usize) {
     let behind = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
     (ahead, behind)
 }
+
+/// (ahead, behind) of HEAD relative to the local `default_branch`; None if that ref is missing.
+pub fn ahead_behind_of(path: &Path, default_branch: &str) -> Option<(usize, usize)> {
+    let range = format!("HEAD...refs/heads/{}", default_branch);
+    let out = run_with_timeout(
+        git_cmd(path).args(["rev-list", "--left-right", "--count", &range]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut parts = text.split_whitespace();
+    let ahead = parts.next()?.parse().ok()?;
+    let behind = parts.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// How HEAD stands against the default branch — its upstream when it has one, which a
+/// fetch keeps fresher than the local ref: when they forked and how many commits that
+/// branch has gained since. None if the ref or a merge base is missing.
+pub fn divergence(path: &Path, default_branch: &str) -> Option<Divergence> {
+    let trunk = trunk_ref(path, default_branch);
+    let out = run_with_timeout(git_cmd(path).args(["rev-parse", "HEAD", &trunk]), LOCAL_TIMEOUT).ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    // The merge base only changes when one of the two tips does.
+    let tips = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let cached = DIVERGENCE.lock().unwrap_or_else(|e| e.into_inner()).get(path).cloned();
+    if let Some((_, divergence)) = cached.filter(|(seen, _)| *seen == tips) {
+        return divergence;
+    }
+    let (head, trunk_tip) = tips.split_once('\n')?;
+    let name = trunk.strip_prefix("refs/remotes/").or(trunk.strip_prefix("refs/heads/")).unwrap_or(&trunk);
+    let divergence = merge_base_divergence(path, head, trunk_tip, name);
+    DIVERGENCE.lock().unwrap_or_else(|e| e.into_inner()).insert(path.to_path_buf(), (tips, divergence.clone()));
+    divergence
+}
+
+/// HEAD and trunk tips a `divergence` was worked out at, and what it came to.
+type DivergenceAt = (String, Option<Divergence>);
+
+/// `divergence` per worktree, kept until HEAD or the trunk moves.
+static DIVERGENCE: LazyLock<Mutex<HashMap<PathBuf, DivergenceAt>>> = LazyLock::new(Default::default);
+
+/// `refs/remotes/…` for the default branch's upstream, else `refs/heads/{default_branch}`.
+fn trunk_ref(path: &Path, default_branch: &str) -> String {
+    let upstream = format!("{}@{{upstream}}", default_branch);
+    run_with_timeout(git_cmd(path).args(["rev-parse", "--symbolic-full-name", &upstream]), LOCAL_TIMEOUT)
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("refs/heads/{}", default_branch))
+}
+
+/// The merge base of `head` and `trunk_tip`: its commit date — `git merge-base`, then its
+/// `%ct` — and the commits `trunk` has gained since.
+fn merge_base_divergence(path: &Path, head: &str, trunk_tip: &str, trunk: &str) -> Option<Divergence> {
+    let out = run_with_timeout(git_cmd(path).args(["merge-base", head, trunk_tip]), LOCAL_TIMEOUT).ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let base = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let out = run_with_timeout(git_cmd(path).args(["show", "-s", "--format=%ct", &base]), LOCAL_TIMEOUT).ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let base_ts = String::from_utf8_lossy(&out.stdout).trim().parse().ok()?;
+    let range = format!("{}..{}", base, trunk_tip);
+    let out = run_with_timeout(git_cmd(path).args(["rev-list", "--count", &range]), LOCAL_TIMEOUT).ok()?;
+    let trunk_since = String::from_utf8_lossy(&out.stdout).trim().parse().ok()?;
+    Some(Divergence { trunk: trunk.to_string(), base_ts, trunk_since })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ahead_behind_of, divergence, parse_stash_list};
+    use crate::model::workspace::StashEntry;
+    use crate::scratch::{git, init_repo, TempDir};
+
+    #[test]
+    fn parses_stash_list_with_multiline_messages() {
+        // captured from `git stash list --format=...` after `stash push -m $'fix tests\n\nmore'`
+        let text = "stash@{0}\x1f1792001788\x1fWIP on master: 954c09e init\x1e\n\
+                    stash@{1}\x1f1792001700\x1fOn master: fix tests\n\nmore detail here\x1e\n";
+        assert_eq!(
+            parse_stash_list(text),
+            vec![
+                StashEntry { index: 0, message: "WIP on master: 954c09e init".into(), ts: 1792001788 },
+                StashEntry { index: 1, message: "On master: fix tests …".into(), ts: 1792001700 },
+            ]
+        );
+        assert!(parse_stash_list("").is_empty());
+        assert!(parse_stash_list("garbage\x1e\n").is_empty());
+    }
+
+    #[test]
+    fn stashes_belong_to_the_branch_they_were_made_on() {
+        let on = |message: &str| StashEntry { index: 0, message: message.into(), ts: 0 };
+        assert!(on("WIP on master: 954c09e init").made_on("master"));
+        assert!(on("On master: fix tests").made_on("master"));
+        assert!(!on("WIP on master-2: 954c09e init").made_on("master"));
+        assert!(!on("On feature: master: tidy").made_on("master"));
+        assert!(on("WIP on (no branch): 954c09e init").made_on("(no branch)"));
+        assert!(!on("fix tests").made_on("master"));
+    }
+
+    #[test]
+    fn counts_divergence_from_the_default_branch_or_its_upstream() {
+        let repo = TempDir::new("wsx info");
+        init_repo(&repo);
+        let git = |args: &[&str]| git(&repo, args);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&["checkout", "-q", "-b", "feat"]);
+        for m in ["a", "b", "c"] {
+            git(&["commit", "-q", "--allow-empty", "-m", m]);
+        }
+        assert_eq!(ahead_behind_of(&repo, "main"), Some((3, 0)));
+        git(&["checkout", "-q", "main"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "trunk"]);
+        assert_eq!(ahead_behind_of(&repo, "main"), Some((0, 0)));
+        git(&["checkout", "-q", "feat"]);
+        assert_eq!(ahead_behind_of(&repo, "main"), Some((3, 1)));
+        assert_eq!(ahead_behind_of(&repo, "no-such-branch"), None);
+        let forked = divergence(&repo, "main").unwrap();
+        assert_eq!((forked.trunk.as_str(), forked.trunk_since), ("main", 1));
+        assert!(forked.base_ts > 0 && forked.base_ts <= crate::model::frecency::now_secs(), "{:?}", forked);
+        assert_eq!(divergence(&repo, "no-such-branch"), None);
+
+        // A fetched upstream two commits past the local main is what counts.
+        git(&["checkout", "-q", "main"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "upstream"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "upstream 2"]);
+        git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+        git(&["reset", "-q", "--hard", "HEAD~2"]);
+        git(&["remote", "add", "origin", "/nonexistent/origin"]);
+        git(&["config", "branch.main.remote", "origin"]);
+        git(&["config", "branch.main.merge", "refs/heads/main"]);
+        git(&["checkout", "-q", "feat"]);
+        let forked = divergence(&repo, "main").unwrap();
+        assert_eq!((forked.trunk.as_str(), forked.trunk_since), ("origin/main", 3));
+        git(&["commit", "-q", "--allow-empty", "-m", "d"]);
+        assert_eq!(divergence(&repo, "main"), Some(forked), "worked out again once HEAD moves: same base");
+    }
+}
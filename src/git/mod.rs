@@ -1,13 +1,131 @@
+pub mod backend;
+#[cfg(test)]
+pub mod fake;
 pub mod worktree;
 pub mod info;
 pub mod ops;
+pub mod queue;
 
+pub use backend::{CliGit, GitBackend};
+
+use std::io::Read;
+use std::os::unix::process::CommandExt;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Reads of local state (worktree list, status, log) — a hung mount shouldn't freeze the UI.
+pub const LOCAL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Network operations, and anything that runs hooks (merge, commit, checkout).
+pub const LONG_TIMEOUT: Duration = Duration::from_secs(120);
 
-/// Base git command scoped to `repo` via `-C`.
+/// Base git command scoped to `repo` via `-C`. Never prompts for credentials: a
+/// credential helper may still answer, otherwise git fails instead of waiting.
 pub fn git_cmd(repo: &Path) -> Command {
     let mut cmd = Command::new("git");
     cmd.arg("-C").arg(repo);
+    cmd.env("GIT_TERMINAL_PROMPT", "0").env("GIT_ASKPASS", "true");
     cmd
 }
+
+/// `cmd.output()`, killed after `timeout` with an `ErrorKind::TimedOut` error.
+/// The child runs in its own session, without a controlling terminal, so ssh can't
+/// prompt on /dev/tty either; on timeout the whole session (git, ssh, helpers) is killed.
+pub fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> std::io::Result<Output> {
+    without_tty(cmd);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drain both pipes as it runs, so a chatty child never blocks on a full pipe.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let timed_out = || {
+        std::io::Error::new(std::io::ErrorKind::TimedOut, format!("timed out after {}s", timeout.as_secs()))
+    };
+    let mut pause = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // Still unreaped, so the pid (and its session id) can't have been reused yet.
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        std::thread::sleep(pause.min(deadline.saturating_duration_since(Instant::now())));
+        pause = (pause * 2).min(Duration::from_millis(20));
+    };
+    // Something it left running may still hold the pipes open; that's a timeout too.
+    let rest = |rx: mpsc::Receiver<Vec<u8>>| {
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now())).map_err(|_| timed_out())
+    };
+    Ok(Output { status, stdout: rest(stdout)?, stderr: rest(stderr)? })
+}
+
+/// Read `pipe` to its end on a thread of its own.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        let _ = tx.send(buf);
+    });
+    rx
+}
+
+/// Run the child in its own session, so nothing it starts can prompt on our terminal.
+pub(crate) fn without_tty(cmd: &mut Command) {
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+}
+
+/// Whether git stopped because it wanted a password, token or ssh key it couldn't ask for.
+pub fn needs_credentials(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    [
+        "terminal prompts disabled",
+        "could not read username",
+        "could not read password",
+        "authentication failed",
+        "permission denied (publickey",
+        "host key verification failed",
+    ]
+    .iter()
+    .any(|s| stderr.contains(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{needs_credentials, run_with_timeout};
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn timed_out_commands_are_killed() {
+        let start = Instant::now();
+        let err = run_with_timeout(Command::new("sleep").arg("5"), Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        let out = run_with_timeout(Command::new("echo").arg("hi"), Duration::from_secs(5)).unwrap();
+        assert_eq!(out.stdout, b"hi\n");
+        let start = Instant::now();
+        let err = run_with_timeout(Command::new("sh").args(["-c", "sleep 5 & echo hi"]), Duration::from_millis(200));
+        assert_eq!(err.unwrap_err().kind(), std::io::ErrorKind::TimedOut, "a leftover holds stdout open");
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(needs_credentials(
+            "fatal: could not read Username for 'https://github.com': terminal prompts disabled"
+        ));
+        assert!(!needs_credentials("error: failed to push some refs"));
+    }
+}
@@ -1,15 +1,32 @@
-// Git operations: pull, push, rebase, merge
+// Git operations: pull, push, rebase, merge, stash, conflict resolution
 
-use super::{git_cmd, info::current_branch};
+use super::{
+    git_cmd, info::current_branch, needs_credentials, run_with_timeout, without_tty,
+    worktree::list_worktrees, LOCAL_TIMEOUT, LONG_TIMEOUT,
+};
 use anyhow::{bail, Result};
+use std::io::Read;
 use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use crate::ui::path::display_path;
 
-fn run(cmd: &mut std::process::Command) -> Result<String> {
-    let out = cmd.output()?;
+/// Output of a git command; failures (and timeouts, and missing credentials) become
+/// one-line errors fit for the status bar.
+fn run(cmd: &mut std::process::Command, timeout: Duration) -> Result<String> {
+    let out = match run_with_timeout(cmd, timeout) {
+        Ok(out) => out,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            bail!("{} — run it in a session", e)
+        }
+        Err(e) => return Err(e.into()),
+    };
     let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
     let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
     if out.status.success() {
         Ok(if stdout.is_empty() { stderr } else { stdout })
+    } else if needs_credentials(&stderr) {
+        bail!("needs credentials — run it in a session")
     } else {
         let msg = if !stderr.is_empty() { stderr } else { stdout };
         bail!("{}", msg.lines().next().unwrap_or("git error"))
@@ -17,18 +34,18 @@ fn run(cmd: &mut std::process::Command) -> Result<String> {
 }
 
 pub fn pull(path: &Path) -> Result<String> {
-    run(git_cmd(path).args(["pull"]))
+    run(git_cmd(path).args(["pull"]), LONG_TIMEOUT)
 }
 
 pub fn push(path: &Path) -> Result<String> {
-    let result = run(git_cmd(path).args(["push"]));
+    let result = run(git_cmd(path).args(["push"]), LONG_TIMEOUT);
     match result {
         Ok(s) => Ok(s),
         Err(e) => {
             let msg = e.to_string();
             if msg.contains("no upstream") || msg.contains("--set-upstream") {
                 let branch = current_branch(path).unwrap_or_else(|| "HEAD".to_string());
-                run(git_cmd(path).args(["push", "-u", "origin", &branch]))
+                run(git_cmd(path).args(["push", "-u", "origin", &branch]), LONG_TIMEOUT)
             } else {
                 Err(e)
             }
@@ -37,20 +54,443 @@ pub fn push(path: &Path) -> Result<String> {
 }
 
 pub fn pull_rebase(path: &Path, branch: &str) -> Result<String> {
-    run(git_cmd(path).args(["pull", "--rebase", "origin", branch]))
+    run(git_cmd(path).args(["pull", "--rebase", "origin", branch]), LONG_TIMEOUT)
 }
 
 pub fn merge_from(path: &Path, source: &str) -> Result<String> {
-    run(git_cmd(path).args(["merge", source]))
+    run(git_cmd(path).args(["merge", source]), LONG_TIMEOUT)
 }
 
+/// Merge this worktree's branch into `target`, in the worktree that has `target` checked
+/// out — git won't check a branch out twice, and switching this one around would leave it
+/// on the wrong branch if anything failed. That worktree must have no uncommitted changes;
+/// a conflicted merge is aborted there, so it is never left half-merged.
 pub fn merge_into(path: &Path, target: &str) -> Result<String> {
     let current = current_branch(path).ok_or_else(|| anyhow::anyhow!("not on a branch"))?;
-    // checkout target
-    run(git_cmd(path).args(["checkout", target]))?;
-    // merge current into target; on failure, checkout back
-    let merge_result = run(git_cmd(path).args(["merge", &current]));
-    // ! must always return to original branch regardless of merge outcome
-    run(git_cmd(path).args(["checkout", &current]))?;
-    merge_result.map(|_| format!("Merged {} into {}, returned to {}", current, target, current))
+    if current == target {
+        bail!("{} is this worktree's own branch", target);
+    }
+    let worktrees = list_worktrees(path)?;
+    let Some(there) = worktrees.iter().find(|w| !w.detached && w.branch == target) else {
+        bail!("{} isn't checked out in any worktree — add one for it (w) and merge from there", target);
+    };
+    let at = display_path(&there.path, false, usize::MAX);
+    let dirty = run(git_cmd(&there.path).args(["status", "--porcelain", "--untracked-files=no"]), LOCAL_TIMEOUT)?;
+    if !dirty.is_empty() {
+        bail!("{} has uncommitted changes at {} — commit or stash them first", target, at);
+    }
+    if let Err(e) = run(git_cmd(&there.path).args(["merge", "--no-edit", &current]), LONG_TIMEOUT) {
+        let aborted = run(git_cmd(&there.path).args(["merge", "--abort"]), LONG_TIMEOUT).is_ok();
+        if aborted {
+            bail!("{} — merge aborted, {} unchanged at {}", e, target, at);
+        }
+        if in_progress(&there.path) == Some(InProgress::Merge) {
+            bail!("{} — {} at {} is mid-merge and couldn't be aborted", e, target, at);
+        }
+        bail!("{} — the merge didn't start, {} unchanged at {}", e, target, at);
+    }
+    Ok(format!("merged {} into {} at {}", current, target, at))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StashOp {
+    Apply,
+    Pop,
+    Drop,
+    /// diffstat of the stash against its base
+    Show,
+}
+
+impl StashOp {
+    pub fn verb(self) -> &'static str {
+        match self {
+            StashOp::Apply => "apply",
+            StashOp::Pop => "pop",
+            StashOp::Drop => "drop",
+            StashOp::Show => "show",
+        }
+    }
+}
+
+/// Run `git stash <op>` on `stash@{index}`; `Show` returns the full `--stat` output.
+pub fn stash(path: &Path, op: StashOp, index: usize) -> Result<String> {
+    let refname = format!("stash@{{{}}}", index);
+    match op {
+        StashOp::Show => run(git_cmd(path).args(["stash", "show", "--stat", &refname]), LOCAL_TIMEOUT),
+        _ => run(git_cmd(path).args(["stash", op.verb(), &refname]), LOCAL_TIMEOUT),
+    }
+}
+
+/// Move the uncommitted changes in `from` — staged, unstaged and untracked — to the
+/// worktree at `to`: stash them in `from`, apply that stash in `to` (worktrees share the
+/// stash), then drop it. Returns how many files moved. When applying fails the stash is
+/// kept and the error names it, so nothing is lost and `from` stays clean either way.
+pub fn carry_changes(from: &Path, to: &Path) -> Result<usize> {
+    let files = run(git_cmd(from).args(["status", "--porcelain"]), LOCAL_TIMEOUT)?.lines().count();
+    if files == 0 {
+        bail!("no uncommitted changes in {}", display_path(from, false, usize::MAX));
+    }
+    let message = format!("wsx: carried to {}", display_path(to, false, usize::MAX));
+    run(git_cmd(from).args(["stash", "push", "--include-untracked", "-m", &message]), LOCAL_TIMEOUT)?;
+    let sha = run(git_cmd(from).args(["rev-parse", "--verify", "refs/stash"]), LOCAL_TIMEOUT)?;
+    let index = |sha: &str| {
+        run(git_cmd(from).args(["stash", "list", "--format=%H"]), LOCAL_TIMEOUT)
+            .ok()
+            .and_then(|list| list.lines().position(|l| l == sha))
+            .unwrap_or(0)
+    };
+    if let Err(e) = run(git_cmd(to).args(["stash", "apply", "--index", &sha]), LOCAL_TIMEOUT) {
+        let conflicts = conflicted_files(to);
+        let why = if conflicts.is_empty() { e.to_string() } else { format!("conflicts in {}", conflicts.join(", ")) };
+        bail!(
+            "changes didn't apply cleanly at {}: {} — they're kept as stash@{{{}}}",
+            display_path(to, false, usize::MAX),
+            why,
+            index(&sha)
+        );
+    }
+    stash(from, StashOp::Drop, index(&sha))?;
+    Ok(files)
+}
+
+/// `file`'s uncommitted changes, staged or not, against HEAD, coloured; an untracked
+/// file shows as all added.
+pub fn file_diff(path: &Path, file: &str) -> Result<String> {
+    let diff = run(git_cmd(path).args(["diff", "--color=always", "HEAD", "--", file]), LOCAL_TIMEOUT)?;
+    if !diff.is_empty() {
+        return Ok(diff);
+    }
+    // `--no-index` exits 1 when the files differ, which `run` would report as a failure.
+    let out = run_with_timeout(
+        git_cmd(path).args(["diff", "--color=always", "--no-index", "--", "/dev/null", file]),
+        LOCAL_TIMEOUT,
+    )?;
+    Ok(String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+/// A merge or rebase stopped on conflicts, waiting to be finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InProgress {
+    Merge,
+    Rebase,
+}
+
+impl InProgress {
+    /// What `continue_operation` runs for it.
+    pub fn continue_command(self) -> &'static str {
+        match self {
+            InProgress::Merge => "git commit --no-edit",
+            InProgress::Rebase => "git rebase --continue",
+        }
+    }
+}
+
+/// Files with unresolved conflicts.
+pub fn conflicted_files(path: &Path) -> Vec<String> {
+    run(git_cmd(path).args(["diff", "--name-only", "--diff-filter=U"]), LOCAL_TIMEOUT)
+        .map(|out| out.lines().filter(|l| !l.is_empty()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+pub fn mark_resolved(path: &Path, file: &str) -> Result<String> {
+    run(git_cmd(path).args(["add", "--", file]), LOCAL_TIMEOUT)
+}
+
+/// Which operation is half-done, from the state files git leaves in the (worktree's) git dir.
+pub fn in_progress(path: &Path) -> Option<InProgress> {
+    let exists = |name: &str| {
+        run(git_cmd(path).args(["rev-parse", "--git-path", name]), LOCAL_TIMEOUT)
+            .is_ok_and(|p| path.join(p).exists())
+    };
+    if exists("rebase-merge") || exists("rebase-apply") {
+        Some(InProgress::Rebase)
+    } else if exists("MERGE_HEAD") {
+        Some(InProgress::Merge)
+    } else {
+        None
+    }
+}
+
+/// Finish whatever stopped on conflicts: commit the merge with its prepared message,
+/// or let the rebase carry on (without opening an editor for the message).
+pub fn continue_operation(path: &Path) -> Result<String> {
+    match in_progress(path) {
+        Some(InProgress::Merge) => run(git_cmd(path).args(["commit", "--no-edit"]), LONG_TIMEOUT),
+        Some(InProgress::Rebase) => {
+            run(git_cmd(path).env("GIT_EDITOR", "true").args(["rebase", "--continue"]), LONG_TIMEOUT)
+        }
+        None => bail!("no merge or rebase in progress"),
+    }
+}
+
+/// Whether `s` names a remote repository rather than a local path:
+/// `git@host:owner/repo`, `ssh://…`, `git://…`, or an `https://…` / `http://…` URL.
+pub fn is_clone_url(s: &str) -> bool {
+    let s = s.trim();
+    s.starts_with("git@")
+        || ["ssh://", "git://", "https://", "http://"].iter().any(|p| s.starts_with(p))
+}
+
+/// Directory name `git clone` would pick: the last path segment without `.git`.
+pub fn clone_dir_name(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let last = url.rsplit(['/', ':']).next().unwrap_or(url);
+    last.strip_suffix(".git").unwrap_or(last).to_string()
+}
+
+/// The percentage from a `Receiving objects:  42% (420/1000)` progress line.
+pub fn clone_progress(line: &str) -> Option<u8> {
+    let rest = line.trim().strip_prefix("Receiving objects:")?;
+    rest.trim_start().split('%').next()?.parse().ok()
+}
+
+/// `git clone --progress url dest`, passing each "Receiving objects" percentage to
+/// `progress` as git reports it. No timeout — a big clone takes as long as it takes, and
+/// with prompts disabled a missing credential fails instead of waiting. On failure git's
+/// last stderr line is the error, and a `dest` that didn't exist before is removed again.
+pub fn clone(url: &str, dest: &Path, progress: &dyn Fn(u8)) -> Result<()> {
+    let existed = dest.exists();
+    let mut cmd = git_cmd(Path::new("."));
+    cmd.args(["clone", "--progress", url])
+        .arg(dest)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    without_tty(&mut cmd);
+    let mut child = cmd.spawn()?;
+
+    // Progress lines end in \r, everything else in \n.
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let mut last = String::new();
+    let mut line = Vec::new();
+    let mut buf = [0u8; 4096];
+    let mut on_line = |line: &[u8]| {
+        let text = String::from_utf8_lossy(line).trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        if let Some(pct) = clone_progress(&text) {
+            progress(pct);
+        }
+        last = text;
+    };
+    loop {
+        let n = stderr.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            if b == b'\r' || b == b'\n' {
+                on_line(&line);
+                line.clear();
+            } else {
+                line.push(b);
+            }
+        }
+    }
+    on_line(&line);
+    if child.wait()?.success() {
+        return Ok(());
+    }
+
+    let msg = if needs_credentials(&last) { "needs credentials — clone it in a session".to_string() } else { last };
+    if !existed && dest.exists() && std::fs::remove_dir_all(dest).is_err() {
+        bail!("{} (partial clone left at {})", msg, dest.display());
+    }
+    bail!("{}", if msg.is_empty() { "git clone failed" } else { &msg })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        carry_changes, clone, clone_dir_name, clone_progress, conflicted_files, continue_operation, file_diff, in_progress,
+        is_clone_url, mark_resolved, merge_into, stash, InProgress, StashOp,
+    };
+    use crate::git::info::stash_list;
+    use crate::scratch::{git, git_ok, init_repo, TempDir};
+
+    #[test]
+    fn stash_round_trip() {
+        let repo = TempDir::new("wsx stash");
+        init_repo(&repo);
+        let git = |args: &[&str]| git(&repo, args);
+        std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+        git(&["add", "a.txt"]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(repo.join("a.txt"), "a\nb\n").unwrap();
+        git(&["stash", "push", "-q", "-m", "first line\n\nsecond"]);
+        std::fs::write(repo.join("a.txt"), "c\n").unwrap();
+        git(&["stash", "push", "-q"]);
+
+        let entries = stash_list(&repo, "main");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].message, "On main: first line …");
+        let stat = stash(&repo, StashOp::Show, 1).unwrap();
+        assert!(stat.contains("a.txt") && stat.contains("1 insertion"), "{}", stat);
+
+        stash(&repo, StashOp::Drop, 0).unwrap();
+        stash(&repo, StashOp::Pop, 0).unwrap();
+        assert!(stash_list(&repo, "main").is_empty());
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "a\nb\n");
+        assert!(stash(&repo, StashOp::Apply, 0).is_err());
+    }
+
+    #[test]
+    fn file_diff_covers_tracked_and_untracked_files() {
+        let repo = TempDir::new("wsx file diff");
+        init_repo(&repo);
+        let git = |args: &[&str]| git_ok(&repo, args);
+        std::fs::write(repo.join("a.txt"), "base\n").unwrap();
+        assert!(git(&["add", "a.txt"]) && git(&["commit", "-q", "-m", "base"]));
+
+        std::fs::write(repo.join("a.txt"), "changed\n").unwrap();
+        let diff = file_diff(&repo, "a.txt").unwrap();
+        assert!(diff.contains("changed") && diff.contains('\x1b'), "coloured: {:?}", diff);
+        std::fs::write(repo.join("new.txt"), "fresh\n").unwrap();
+        assert!(file_diff(&repo, "new.txt").unwrap().contains("fresh"), "untracked shows as added");
+        std::fs::write(repo.join("blob.bin"), [0u8, 159, 146, 150, 0]).unwrap();
+        assert!(file_diff(&repo, "blob.bin").unwrap().contains("Binary files"));
+    }
+
+    #[test]
+    fn resolves_a_conflicted_merge() {
+        let repo = TempDir::new("wsx conflict");
+        init_repo(&repo);
+        let git = |args: &[&str]| git_ok(&repo, args);
+        std::fs::write(repo.join("a.txt"), "base\n").unwrap();
+        assert!(git(&["add", "a.txt"]) && git(&["commit", "-q", "-m", "base"]));
+        assert!(git(&["checkout", "-q", "-b", "topic"]));
+        std::fs::write(repo.join("a.txt"), "topic\n").unwrap();
+        assert!(git(&["commit", "-q", "-am", "topic"]));
+        assert!(git(&["checkout", "-q", "main"]));
+        std::fs::write(repo.join("a.txt"), "main\n").unwrap();
+        assert!(git(&["commit", "-q", "-am", "main"]));
+        assert_eq!(in_progress(&repo), None);
+        assert!(!git(&["merge", "-q", "topic"]), "merge should conflict");
+
+        assert_eq!(conflicted_files(&repo), ["a.txt"]);
+        assert_eq!(in_progress(&repo), Some(InProgress::Merge));
+        std::fs::write(repo.join("a.txt"), "both\n").unwrap();
+        mark_resolved(&repo, "a.txt").unwrap();
+        assert!(conflicted_files(&repo).is_empty());
+        continue_operation(&repo).unwrap();
+        assert_eq!(in_progress(&repo), None);
+        assert!(continue_operation(&repo).is_err());
+    }
+
+    #[test]
+    fn recognises_clone_urls_and_their_directory() {
+        for url in ["git@github.com:vlwkaos/wsx.git", "https://github.com/vlwkaos/wsx.git", "ssh://git@host/x/wsx"] {
+            assert!(is_clone_url(url), "{}", url);
+            assert_eq!(clone_dir_name(url), "wsx");
+        }
+        assert!(!is_clone_url("~/code/wsx"));
+        assert!(!is_clone_url("/srv/git/wsx.git"));
+        assert_eq!(clone_dir_name("https://example.com/a/b/"), "b");
+    }
+
+    #[test]
+    fn parses_clone_progress() {
+        assert_eq!(clone_progress("Receiving objects:  42% (420/1000), 1.20 MiB | 2.00 MiB/s"), Some(42));
+        assert_eq!(clone_progress("Receiving objects: 100% (1000/1000), done."), Some(100));
+        assert_eq!(clone_progress("remote: Counting objects:  10% (1/10)"), None);
+        assert_eq!(clone_progress("Cloning into 'wsx'..."), None);
+    }
+
+    #[test]
+    fn clone_reports_the_last_error_line_and_cleans_up() {
+        let base = TempDir::new("wsx clone");
+        let origin = base.join("origin");
+        init_repo(&origin);
+        let git = |args: &[&str]| git_ok(&origin, args);
+        std::fs::write(origin.join("a.txt"), "a\n").unwrap();
+        assert!(git(&["add", "a.txt"]) && git(&["commit", "-q", "-m", "init"]));
+
+        let dest = base.join("copy");
+        clone(origin.to_str().unwrap(), &dest, &|_| {}).unwrap();
+        assert!(dest.join("a.txt").exists());
+
+        let missing = base.join("nowhere");
+        let dest = base.join("failed");
+        let err = clone(missing.to_str().unwrap(), &dest, &|_| {}).unwrap_err().to_string();
+        assert!(err.contains("does not exist") || err.contains("not appear to be"), "{}", err);
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn merge_into_runs_in_the_target_worktree() {
+        let base = TempDir::new("wsx merge-into");
+        let main = base.join("api");
+        let feature = base.join("api-feature");
+        init_repo(&main);
+        let git = git_ok;
+        std::fs::write(main.join("a.txt"), "a\n").unwrap();
+        assert!(git(&main, &["add", "a.txt"]) && git(&main, &["commit", "-q", "-m", "init"]));
+        assert!(git(&main, &["worktree", "add", "-q", "-b", "feature", feature.to_str().unwrap()]));
+        std::fs::write(feature.join("b.txt"), "b\n").unwrap();
+        assert!(git(&feature, &["add", "b.txt"]) && git(&feature, &["commit", "-q", "-m", "b"]));
+
+        // A dirty main worktree is left alone.
+        std::fs::write(main.join("a.txt"), "edited\n").unwrap();
+        let err = merge_into(&feature, "main").unwrap_err().to_string();
+        assert!(err.starts_with("main has uncommitted changes at"), "{}", err);
+        assert!(!main.join("b.txt").exists());
+
+        assert!(git(&main, &["checkout", "-q", "--", "a.txt"]));
+        // An untracked b.txt there stops the merge before it starts: nothing to abort.
+        std::fs::write(main.join("b.txt"), "mine\n").unwrap();
+        let err = merge_into(&feature, "main").unwrap_err().to_string();
+        assert!(err.contains("the merge didn't start, main unchanged at"), "{}", err);
+        std::fs::remove_file(main.join("b.txt")).unwrap();
+
+        let msg = merge_into(&feature, "main").unwrap();
+        assert!(msg.starts_with("merged feature into main at") && msg.ends_with("api"), "{}", msg);
+        assert!(main.join("b.txt").exists());
+        assert_eq!(crate::git::info::current_branch(&feature).as_deref(), Some("feature"));
+
+        let err = merge_into(&feature, "release").unwrap_err().to_string();
+        assert!(err.starts_with("release isn't checked out in any worktree"), "{}", err);
+    }
+
+    #[test]
+    fn carry_changes_moves_staged_unstaged_and_untracked_files() {
+        let base = TempDir::new("wsx carry");
+        let main = base.join("api");
+        let feature = base.join("api-feature");
+        let other = base.join("api-other");
+        init_repo(&main);
+        let git = git_ok;
+        let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap();
+        std::fs::write(main.join("a.txt"), "a\n").unwrap();
+        std::fs::write(main.join("b.txt"), "b\n").unwrap();
+        assert!(git(&main, &["add", "."]) && git(&main, &["commit", "-q", "-m", "init"]));
+        assert!(git(&main, &["worktree", "add", "-q", "-b", "feature", feature.to_str().unwrap()]));
+
+        std::fs::write(main.join("a.txt"), "a staged\n").unwrap();
+        assert!(git(&main, &["add", "a.txt"]));
+        std::fs::write(main.join("b.txt"), "b unstaged\n").unwrap();
+        std::fs::write(main.join("new.txt"), "untracked\n").unwrap();
+        assert_eq!(carry_changes(&main, &feature).unwrap(), 3);
+
+        assert_eq!(read(main.join("a.txt")), "a\n");
+        assert!(!main.join("new.txt").exists());
+        assert!(stash_list(&main, "main").is_empty(), "the stash is dropped once applied");
+        assert_eq!(read(feature.join("a.txt")), "a staged\n");
+        assert_eq!(read(feature.join("b.txt")), "b unstaged\n");
+        assert_eq!(read(feature.join("new.txt")), "untracked\n");
+        let status = crate::scratch::git(&feature, &["status", "--porcelain"]);
+        assert!(status.contains("M  a.txt") && status.contains(" M b.txt") && status.contains("?? new.txt"), "{}", status);
+        assert!(carry_changes(&main, &feature).unwrap_err().to_string().starts_with("no uncommitted changes"));
+
+        // A branch where a.txt moved on: the apply conflicts and the stash stays.
+        assert!(git(&main, &["branch", "other"]));
+        assert!(git(&main, &["worktree", "add", "-q", other.to_str().unwrap(), "other"]));
+        std::fs::write(other.join("a.txt"), "a elsewhere\n").unwrap();
+        assert!(git(&other, &["commit", "-q", "-am", "other"]));
+        std::fs::write(main.join("a.txt"), "a again\n").unwrap();
+        let err = carry_changes(&main, &other).unwrap_err().to_string();
+        assert!(err.contains("a.txt") && err.ends_with("kept as stash@{0}"), "{}", err);
+        assert_eq!(read(main.join("a.txt")), "a\n");
+        assert_eq!(stash_list(&main, "main").len(), 1);
+    }
 }
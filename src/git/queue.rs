@@ -0,0 +1,149 @@
+// One git operation per repository at a time. git takes repo-wide locks (index, refs,
+// worktree admin files), so a background fetch and a pull or clean on the same repo
+// would trip over each other; different repositories still run side by side.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use super::LONG_TIMEOUT;
+
+/// How long an operation waits for its repository before giving up. Every git command
+/// is killed at `LONG_TIMEOUT` at the latest, so a holder past this is stuck for good.
+pub const QUEUE_TIMEOUT: Duration = LONG_TIMEOUT.saturating_add(Duration::from_secs(30));
+
+#[derive(Default)]
+struct Lane {
+    /// label of the operation holding the repository
+    running: Option<String>,
+    waiting: usize,
+}
+
+/// A repository that is busy, as the stats popup lists it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaneState {
+    pub repo: PathBuf,
+    pub running: String,
+    pub waiting: usize,
+}
+
+#[derive(Default)]
+pub struct RepoQueue {
+    lanes: Mutex<HashMap<PathBuf, Lane>>,
+    freed: Condvar,
+}
+
+impl RepoQueue {
+    /// How many operations on `repo` one starting now would wait behind.
+    pub fn ahead(&self, repo: &Path) -> usize {
+        let lanes = self.lanes.lock().unwrap();
+        lanes.get(repo).map_or(0, |l| usize::from(l.running.is_some()) + l.waiting)
+    }
+
+    /// Hold `repo` until the returned turn drops, waiting up to `max_wait` for it.
+    /// `None` when it stayed busy that long.
+    pub fn acquire(self: &Arc<Self>, repo: &Path, label: &str, max_wait: Duration) -> Option<RepoTurn> {
+        let deadline = Instant::now() + max_wait;
+        let mut lanes = self.lanes.lock().unwrap();
+        lanes.entry(repo.to_path_buf()).or_default().waiting += 1;
+        while lanes[repo].running.is_some() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                let lane = lanes.get_mut(repo).unwrap();
+                lane.waiting -= 1;
+                if lane.running.is_none() && lane.waiting == 0 {
+                    lanes.remove(repo);
+                }
+                return None;
+            }
+            lanes = self.freed.wait_timeout(lanes, left).unwrap().0;
+        }
+        let lane = lanes.get_mut(repo).unwrap();
+        lane.waiting -= 1;
+        lane.running = Some(label.to_string());
+        Some(RepoTurn { queue: Arc::clone(self), repo: repo.to_path_buf() })
+    }
+
+    /// Busy repositories, in path order.
+    pub fn lanes(&self) -> Vec<LaneState> {
+        let lanes = self.lanes.lock().unwrap();
+        let mut busy: Vec<LaneState> = lanes
+            .iter()
+            .filter_map(|(repo, lane)| {
+                let running = lane.running.clone()?;
+                Some(LaneState { repo: repo.clone(), running, waiting: lane.waiting })
+            })
+            .collect();
+        busy.sort_by(|a, b| a.repo.cmp(&b.repo));
+        busy
+    }
+}
+
+/// A repository held by one operation; dropping it lets the next one in.
+pub struct RepoTurn {
+    queue: Arc<RepoQueue>,
+    repo: PathBuf,
+}
+
+impl Drop for RepoTurn {
+    fn drop(&mut self) {
+        let mut lanes = self.queue.lanes.lock().unwrap();
+        if let Some(lane) = lanes.get_mut(&self.repo) {
+            lane.running = None;
+            if lane.waiting == 0 {
+                lanes.remove(&self.repo);
+            }
+        }
+        self.queue.freed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LaneState, RepoQueue};
+    use std::path::{Path, PathBuf};
+    use std::sync::{mpsc, Arc};
+    use std::time::Duration;
+
+    const LONG: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn same_repo_waits_and_other_repos_run_alongside() {
+        let queue = Arc::new(RepoQueue::default());
+        let api = Path::new("/w/api");
+        let fetch = queue.acquire(api, "fetch", LONG).unwrap();
+        assert!(queue.acquire(Path::new("/w/web"), "pull", LONG).is_some(), "another repo");
+
+        let (tx, rx) = mpsc::channel();
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            std::thread::spawn(move || {
+                let turn = queue.acquire(Path::new("/w/api"), "clean", LONG);
+                tx.send(()).unwrap();
+                turn.is_some()
+            })
+        };
+        while queue.ahead(api) < 2 {
+            std::thread::yield_now();
+        }
+        assert_eq!(
+            queue.lanes(),
+            [LaneState { repo: PathBuf::from("/w/api"), running: "fetch".into(), waiting: 1 }]
+        );
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err(), "queued behind the fetch");
+        drop(fetch);
+        assert!(waiter.join().unwrap());
+        assert_eq!(queue.ahead(api), 0);
+        assert!(queue.lanes().is_empty());
+    }
+
+    #[test]
+    fn a_stuck_holder_times_out_its_waiters() {
+        let queue = Arc::new(RepoQueue::default());
+        let api = Path::new("/w/api");
+        let _stuck = queue.acquire(api, "pull", LONG).unwrap();
+        assert!(queue.acquire(api, "fetch", Duration::from_millis(20)).is_none());
+        assert_eq!(queue.ahead(api), 1, "the waiter left the queue");
+    }
+}
@@ -1,65 +1,189 @@
 // Worktree CRUD — all via git CLI
 // ref: git-worktree(1) — https://git-scm.com/docs/git-worktree
 
-use super::git_cmd;
-use crate::model::workspace::WorktreeInfo;
+use super::{git_cmd, needs_credentials, run_with_timeout, LOCAL_TIMEOUT, LONG_TIMEOUT};
+use crate::model::workspace::{ArchivedWorktree, ProjectConfig, WorktreeInfo};
 use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::sync::{LazyLock, Mutex};
 
+#[derive(Debug, thiserror::Error)]
+pub enum CreateWorktreeError {
+    /// The branch exists and is checked out in another worktree.
+    #[error("branch '{branch}' is already checked out at {}", path.display())]
+    AlreadyCheckedOut { branch: String, path: PathBuf },
+}
+
+/// A branch on a remote, captured before local cleanup deletes the tracking config.
+#[derive(Debug, Clone)]
+pub struct RemoteBranch {
+    pub repo: PathBuf,
+    pub remote: String,
+    pub branch: String,
+}
+
+impl std::fmt::Display for RemoteBranch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.remote, self.branch)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct WorktreeEntry {
     pub name: String,
     pub path: PathBuf,
+    /// for a detached worktree, the tag at HEAD or its short SHA instead
     pub branch: String,
     pub is_main: bool,
+    pub detached: bool,
+    /// `git worktree lock` in effect, with its reason ("" when none was given)
+    pub locked: Option<String>,
+}
+
+/// What a new worktree checks out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewWorktree {
+    /// `name`, branched off `base` unless it exists already
+    Branch { name: String, base: String },
+    /// `--detach` at a tag, commit or other ref — no branch is created
+    Detached { refname: String },
 }
 
-/// List worktrees via `git worktree list --porcelain`.
+impl NewWorktree {
+    /// Add Worktree input: `@v1.2.3` is a detached worktree at that ref, anything else a branch.
+    pub fn parse(input: &str, base: &str) -> Self {
+        match input.strip_prefix('@') {
+            Some(refname) => NewWorktree::Detached { refname: refname.to_string() },
+            None => NewWorktree::Branch { name: input.to_string(), base: base.to_string() },
+        }
+    }
+
+    /// The branch or ref; names the worktree directory and so its sessions.
+    pub fn name(&self) -> &str {
+        match self {
+            NewWorktree::Branch { name, .. } => name,
+            NewWorktree::Detached { refname } => refname,
+        }
+    }
+}
+
+/// Short SHA shown for a detached HEAD that no tag points at.
+const SHORT_SHA: usize = 7;
+
+/// List worktrees via `git worktree list --porcelain -z` (git < 2.36: without `-z`).
+/// Paths are taken as raw bytes so spaces and non-UTF-8 names survive.
 pub fn list_worktrees(repo_path: &Path) -> Result<Vec<WorktreeEntry>> {
-    let output = git_cmd(repo_path)
-        .args(["worktree", "list", "--porcelain"])
-        .output()
+    let output = run_with_timeout(
+        git_cmd(repo_path).args(["worktree", "list", "--porcelain", "-z"]),
+        LOCAL_TIMEOUT,
+    )
+    .context("git worktree list failed")?;
+    let mut entries = if output.status.success() {
+        parse_porcelain_output(&output.stdout, b'\0', repo_path)?
+    } else {
+        let output = run_with_timeout(
+            git_cmd(repo_path).args(["worktree", "list", "--porcelain"]),
+            LOCAL_TIMEOUT,
+        )
         .context("git worktree list failed")?;
-    parse_porcelain_output(&String::from_utf8_lossy(&output.stdout), repo_path)
+        parse_porcelain_output(&output.stdout, b'\n', repo_path)?
+    };
+    let mut tags = EXACT_TAGS.lock().unwrap_or_else(|e| e.into_inner());
+    for entry in &mut entries {
+        if !entry.detached {
+            tags.remove(&entry.path);
+            continue;
+        }
+        // `branch` is the short SHA here; describe again only once HEAD has moved.
+        let tag = match tags.get(&entry.path) {
+            Some((sha, tag)) if *sha == entry.branch => tag.clone(),
+            _ => {
+                let tag = exact_tag(&entry.path);
+                tags.insert(entry.path.clone(), (entry.branch.clone(), tag.clone()));
+                tag
+            }
+        };
+        if let Some(tag) = tag {
+            entry.branch = tag;
+        }
+    }
+    tags.retain(|path, _| path.exists());
+    Ok(entries)
+}
+
+/// short SHA of a detached HEAD, and the tag `exact_tag` found at it
+type TagAt = (String, Option<String>);
+
+/// `exact_tag` per detached worktree, kept until its HEAD moves.
+static EXACT_TAGS: LazyLock<Mutex<HashMap<PathBuf, TagAt>>> = LazyLock::new(Default::default);
+
+/// The tag pointing exactly at HEAD, if any.
+fn exact_tag(worktree: &Path) -> Option<String> {
+    let out = run_with_timeout(
+        git_cmd(worktree).args(["describe", "--tags", "--exact-match", "HEAD"]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
+    let tag = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !tag.is_empty()).then_some(tag)
 }
 
-fn parse_porcelain_output(output: &str, repo_path: &Path) -> Result<Vec<WorktreeEntry>> {
+fn parse_porcelain_output(output: &[u8], sep: u8, repo_path: &Path) -> Result<Vec<WorktreeEntry>> {
     let mut entries = Vec::new();
     let mut current_path: Option<PathBuf> = None;
     let mut current_branch: Option<String> = None;
+    let mut current_head: Option<String> = None;
+    let mut detached = false;
+    let mut locked: Option<String> = None;
     let mut first = true;
 
-    for line in output.lines() {
+    let mut finish = |path: PathBuf, branch: Option<String>, head: Option<String>, detached: bool, locked: Option<String>, first: bool| {
+        let branch = match (branch, head) {
+            (Some(b), _) => b,
+            (None, Some(sha)) if detached => sha.chars().take(SHORT_SHA).collect(),
+            _ => "HEAD".to_string(),
+        };
+        let name = derive_name(&path, &branch, first);
+        entries.push(WorktreeEntry {
+            name,
+            path,
+            branch,
+            is_main: first,
+            detached,
+            locked,
+        });
+    };
+
+    for line in output.split(|&b| b == sep) {
         if line.is_empty() {
             if let Some(path) = current_path.take() {
-                let branch = current_branch.take().unwrap_or_else(|| "HEAD".to_string());
-                let name = derive_name(&path, &branch, first);
-                entries.push(WorktreeEntry {
-                    name,
-                    path,
-                    branch,
-                    is_main: first,
-                });
+                finish(path, current_branch.take(), current_head.take(), detached, locked.take(), first);
+                detached = false;
                 first = false;
             }
-        } else if let Some(p) = line.strip_prefix("worktree ") {
-            current_path = Some(PathBuf::from(p.trim()));
-        } else if let Some(b) = line.strip_prefix("branch ") {
-            let b = b.trim().strip_prefix("refs/heads/").unwrap_or(b.trim());
-            current_branch = Some(b.to_string());
+        } else if let Some(p) = line.strip_prefix(b"worktree ") {
+            current_path = Some(PathBuf::from(OsStr::from_bytes(p)));
+        } else if let Some(h) = line.strip_prefix(b"HEAD ") {
+            current_head = Some(String::from_utf8_lossy(h).trim().to_string());
+        } else if line == b"detached" {
+            detached = true;
+        } else if line == b"locked" {
+            locked = Some(String::new());
+        } else if let Some(r) = line.strip_prefix(b"locked ") {
+            locked = Some(String::from_utf8_lossy(r).trim().to_string());
+        } else if let Some(b) = line.strip_prefix(b"branch ") {
+            let b = String::from_utf8_lossy(b);
+            let b = b.trim();
+            current_branch = Some(b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
         }
     }
 
     // Last entry (no trailing blank line)
     if let Some(path) = current_path {
-        let branch = current_branch.unwrap_or_else(|| "HEAD".to_string());
-        let name = derive_name(&path, &branch, first);
-        entries.push(WorktreeEntry {
-            name,
-            path,
-            branch,
-            is_main: first,
-        });
+        finish(path, current_branch, current_head, detached, locked, first);
     }
 
     if entries.is_empty() {
@@ -68,6 +192,8 @@ fn parse_porcelain_output(output: &str, repo_path: &Path) -> Result<Vec<Worktree
             path: repo_path.to_path_buf(),
             branch: "main".to_string(),
             is_main: true,
+            detached: false,
+            locked: None,
         });
     }
 
@@ -83,124 +209,666 @@ fn derive_name(path: &Path, branch: &str, is_main: bool) -> String {
         .unwrap_or_else(|| branch.replace('/', "-"))
 }
 
+/// The main repository when `path` is a linked worktree: its `.git` is a file
+/// ("gitdir: <repo>/.git/worktrees/<name>") rather than a directory.
+/// None for a main worktree, or a worktree of a bare repository.
+pub fn main_repo_of(path: &Path) -> Option<PathBuf> {
+    let dot_git = std::fs::read_to_string(path.join(".git")).ok()?;
+    let gitdir = path.join(dot_git.strip_prefix("gitdir:")?.trim());
+    let common = match std::fs::read_to_string(gitdir.join("commondir")) {
+        Ok(rel) => gitdir.join(rel.trim()),
+        Err(_) => gitdir.parent().filter(|d| d.ends_with("worktrees"))?.parent()?.to_path_buf(),
+    };
+    let common = std::fs::canonicalize(common).ok()?;
+    if common.file_name() != Some(OsStr::new(".git")) {
+        return None;
+    }
+    common.parent().map(Path::to_path_buf)
+}
+
+/// The configured alias for `branch`, else the one `alias.pattern` derives (flagged true).
+/// An explicit alias always wins.
+pub fn effective_alias(
+    aliases: &std::collections::HashMap<String, String>,
+    config: Option<&ProjectConfig>,
+    branch: &str,
+) -> (Option<String>, bool) {
+    match aliases.get(branch) {
+        Some(alias) => (Some(alias.clone()), false),
+        None => {
+            let derived = config.and_then(|c| c.pattern_alias(branch));
+            let is_derived = derived.is_some();
+            (derived, is_derived)
+        }
+    }
+}
+
 /// Convert WorktreeEntry list to WorktreeInfo list (no sessions yet — populated by refresh_all).
 pub fn to_worktree_infos(
+    repo: &Path,
     entries: Vec<WorktreeEntry>,
     aliases: &std::collections::HashMap<String, String>,
+    config: Option<&ProjectConfig>,
 ) -> Vec<WorktreeInfo> {
     entries
         .into_iter()
         .map(|e| {
-            let alias = aliases.get(&e.branch).cloned();
+            let (alias, alias_derived) = effective_alias(aliases, config, &e.branch);
+            let hidden = !e.is_main && config.is_some_and(|c| c.hides_worktree(&e.branch, &e.path));
+            let external = is_external(repo, &e);
             WorktreeInfo {
                 name: e.name,
                 branch: e.branch,
                 path: e.path,
                 is_main: e.is_main,
+                detached: e.detached,
                 alias,
+                alias_derived,
                 sessions: Vec::new(),
                 expanded: true,
                 git_info: None,
                 fetch_failed: false,
                 last_fetched: None,
+                last_fetch_ok: None,
+                pinned: false,
+                hidden,
+                locked: e.locked,
+                note: None,
+                external,
             }
         })
         .collect()
 }
 
-/// `git worktree add -b {branch} {path} {base_branch}`
-pub fn create_worktree(repo_path: &Path, branch: &str, base_branch: &str) -> Result<PathBuf> {
-    let parent = repo_path.parent().context("repo has no parent dir")?;
-    let repo_name = repo_path
-        .file_name()
-        .context("repo has no name")?
-        .to_string_lossy();
-    let slug = branch.replace('/', "-").replace(
-        |c: char| !c.is_alphanumeric() && c != '-' && c != '_' && c != '.',
-        "-",
-    );
-    let wt_path = parent.join(format!("{}-{}", repo_name, slug));
+/// `git worktree add -b {branch} {path} {base}`, or `git worktree add {path} {branch}`
+/// when the branch already exists, or `git worktree add --detach {path} {ref}`.
+/// Fails with `CreateWorktreeError::AlreadyCheckedOut` if another worktree holds the branch.
+pub fn create_worktree(repo_path: &Path, target: &NewWorktree) -> Result<PathBuf> {
+    let wt_path = conventional_path(repo_path, target.name()).context("repo has no parent dir or name")?;
 
-    let status = git_cmd(repo_path)
-        .args([
-            "worktree",
-            "add",
-            "-b",
-            branch,
-            &wt_path.to_string_lossy(),
-            base_branch,
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
+    let mut cmd = git_cmd(repo_path);
+    let branch = match target {
+        NewWorktree::Detached { refname } => {
+            cmd.args(["worktree", "add", "--detach"]).arg(&wt_path).arg(refname);
+            refname
+        }
+        NewWorktree::Branch { name, .. } if branch_exists(repo_path, name) => {
+            cmd.args(["worktree", "add"]).arg(&wt_path).arg(name);
+            name
+        }
+        NewWorktree::Branch { name, base } => {
+            cmd.args(["worktree", "add", "-b", name]).arg(&wt_path).arg(base);
+            name
+        }
+    };
+    let output = run_with_timeout(&mut cmd, LONG_TIMEOUT)
         .context("git worktree add failed")?;
 
-    if !status.success() {
-        bail!("git worktree add exited {}", status);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(held_at) = parse_checked_out_path(&stderr) {
+            // Prefer git's own view of the worktree list; stderr is the fallback.
+            let path = list_worktrees(repo_path)
+                .ok()
+                .and_then(|entries| entries.into_iter().find(|e| e.branch == *branch))
+                .map(|e| e.path)
+                .unwrap_or(held_at);
+            return Err(CreateWorktreeError::AlreadyCheckedOut {
+                branch: branch.to_string(),
+                path,
+            }
+            .into());
+        }
+        // Skip progress chatter like "Preparing worktree (new branch ...)".
+        let reason = stderr
+            .lines()
+            .find_map(|l| l.strip_prefix("fatal: "))
+            .or_else(|| stderr.lines().map(str::trim).find(|l| !l.is_empty()))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("exited {}", output.status));
+        bail!("git worktree add: {}", reason);
     }
     Ok(wt_path)
 }
 
-/// `git worktree remove --force {path}` then `git branch -d {branch}`
-pub fn remove_worktree(repo_path: &Path, worktree_path: &Path, branch: &str) -> Result<()> {
-    let status = git_cmd(repo_path)
-        .args([
-            "worktree",
-            "remove",
-            "--force",
-            &worktree_path.to_string_lossy(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
+/// Where `create_worktree` puts the worktree for `name` (a branch or ref): `{repo}-{slug}`
+/// beside the repo. None for a repo at `/`.
+pub fn conventional_path(repo_path: &Path, name: &str) -> Option<PathBuf> {
+    let parent = repo_path.parent()?;
+    let slug = name.replace('/', "-").replace(
+        |c: char| !c.is_alphanumeric() && c != '-' && c != '_' && c != '.',
+        "-",
+    );
+    let mut dir_name = OsString::from(repo_path.file_name()?);
+    dir_name.push("-");
+    dir_name.push(&slug);
+    Some(parent.join(dir_name))
+}
+
+/// A linked worktree that isn't where `create_worktree` would have put it — made with a
+/// plain `git worktree add` somewhere else. Never a detached one: its label is a tag or
+/// SHA that moves with HEAD, not what its directory was named after.
+pub fn is_external(repo_path: &Path, entry: &WorktreeEntry) -> bool {
+    if entry.is_main || entry.detached {
+        return false;
+    }
+    let Some(expected) = conventional_path(repo_path, &entry.branch) else { return true };
+    // git may list the path resolved (/private/tmp for /tmp); only then touch the disk.
+    let same_dir = |a: Option<&Path>, b: Option<&Path>| {
+        a == b || matches!((a, b), (Some(x), Some(y)) if crate::ops::physical_path(x) == crate::ops::physical_path(y))
+    };
+    expected.file_name() != entry.path.file_name() || !same_dir(expected.parent(), entry.path.parent())
+}
+
+/// `git worktree move`: the checkout and git's record of it go to `to` together.
+pub fn move_worktree(repo_path: &Path, from: &Path, to: &Path) -> Result<()> {
+    run_git(repo_path, &[OsStr::new("worktree"), OsStr::new("move"), from.as_os_str(), to.as_os_str()], "git worktree move")
+}
+
+pub fn branch_exists(repo_path: &Path, branch: &str) -> bool {
+    run_with_timeout(
+        git_cmd(repo_path)
+            .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)]),
+        LOCAL_TIMEOUT,
+    )
+    .is_ok_and(|o| o.status.success())
+}
+
+/// Extract the holding worktree from git's refusal, e.g.
+/// `fatal: 'fix/login' is already checked out at '/work/api-fix-login'` (older git) or
+/// `fatal: 'fix/login' is already used by worktree at '/work/api-fix-login'`.
+fn parse_checked_out_path(stderr: &str) -> Option<PathBuf> {
+    stderr.lines().find_map(|line| {
+        let (_, rest) = line
+            .split_once("is already checked out at '")
+            .or_else(|| line.split_once("is already used by worktree at '"))?;
+        let path = rest.strip_suffix('\'').unwrap_or(rest);
+        Some(PathBuf::from(path))
+    })
+}
+
+/// `git worktree remove [--force] {path}` then `git branch -d {branch}`; a detached
+/// worktree (`branch` None) has no branch to delete.
+/// Without `force`, git refuses to remove a worktree with modified or untracked files.
+pub fn remove_worktree(repo_path: &Path, worktree_path: &Path, branch: Option<&str>, force: bool) -> Result<()> {
+    let mut cmd = git_cmd(repo_path);
+    cmd.args(["worktree", "remove"]);
+    if force {
+        cmd.arg("--force");
+    }
+    let output = run_with_timeout(cmd.arg(worktree_path), LONG_TIMEOUT)
         .context("git worktree remove failed")?;
 
-    if !status.success() {
-        bail!("git worktree remove exited {}", status);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.lines().map(|l| l.trim_start_matches("fatal: ").trim()).find(|l| !l.is_empty()) {
+            Some(reason) => bail!("{}", reason),
+            None => bail!("git worktree remove exited {}", output.status),
+        }
     }
 
     // Best-effort branch deletion
-    let _ = git_cmd(repo_path)
-        .args(["branch", "-d", branch])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
+    if let Some(branch) = branch {
+        let _ = run_with_timeout(git_cmd(repo_path).args(["branch", "-d", branch]), LOCAL_TIMEOUT);
+    }
 
     Ok(())
 }
 
-/// Delete worktrees whose branches are merged into default_branch.
-pub fn clean_merged(repo_path: &Path, default_branch: &str) -> Result<Vec<String>> {
-    let output = git_cmd(repo_path)
-        .args(["branch", "--merged", default_branch])
-        .output()
-        .context("git branch --merged failed")?;
+/// Number of entries in `git status --porcelain` — modified, staged and untracked files.
+/// An error when git couldn't say, so a timeout never passes for a clean worktree.
+pub fn uncommitted_changes(worktree_path: &Path) -> Result<usize> {
+    let out = run_with_timeout(
+        git_cmd(worktree_path).args(["status", "--porcelain"]),
+        LOCAL_TIMEOUT,
+    )?;
+    if !out.status.success() {
+        bail!("git status failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).lines().count())
+}
 
-    let merged: Vec<String> = String::from_utf8_lossy(&output.stdout)
+/// Remote copy of `branch`, if one is known locally: the branch's configured
+/// remote (default `origin`) with an existing `refs/remotes/{remote}/{branch}`.
+pub fn remote_branch(repo_path: &Path, branch: &str) -> Option<RemoteBranch> {
+    let out = run_with_timeout(
+        git_cmd(repo_path).args(["config", "--get", &format!("branch.{}.remote", branch)]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
+    let configured = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let remote = if configured.is_empty() || configured == "." {
+        "origin".to_string()
+    } else {
+        configured
+    };
+    let tracking = format!("refs/remotes/{}/{}", remote, branch);
+    let exists = run_with_timeout(
+        git_cmd(repo_path).args(["rev-parse", "--verify", "--quiet", &tracking]),
+        LOCAL_TIMEOUT,
+    )
+    .is_ok_and(|o| o.status.success());
+    exists.then(|| RemoteBranch {
+        repo: repo_path.to_path_buf(),
+        remote,
+        branch: branch.to_string(),
+    })
+}
+
+/// `git push {remote} --delete {branch}`, with git's refusal reduced to a short reason.
+pub fn delete_remote_branch(repo_path: &Path, remote: &str, branch: &str) -> Result<()> {
+    let output = match run_with_timeout(
+        git_cmd(repo_path).args(["push", remote, "--delete", branch]),
+        LONG_TIMEOUT,
+    ) {
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => bail!("{} — run it in a session", e),
+        result => result.context("git push --delete failed")?,
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    bail!("{}", push_delete_reason(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// The short reason a `git push --delete` failed, from its stderr. Credentials are
+/// checked before "permission": an ssh key prompt says "Permission denied (publickey)".
+fn push_delete_reason(stderr: &str) -> String {
+    let lower = stderr.to_lowercase();
+    if lower.contains("protected branch") || lower.contains("gh006") {
+        "protected branch".to_string()
+    } else if needs_credentials(&lower) {
+        "needs credentials — run it in a session".to_string()
+    } else if lower.contains("permission") || lower.contains("403") || lower.contains("denied") {
+        "permission denied".to_string()
+    } else if lower.contains("remote ref does not exist") {
+        "already deleted on remote".to_string()
+    } else if lower.contains("could not resolve host") || lower.contains("could not read from remote") {
+        "remote unreachable".to_string()
+    } else {
+        stderr
+            .lines()
+            .map(|l| l.trim_start_matches("error: ").trim_start_matches("fatal: ").trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("git push failed")
+            .to_string()
+    }
+}
+
+/// A worktree removed by `ops::clean_merged`; `remote` is set when its branch had a remote copy.
+#[derive(Debug)]
+pub struct Cleaned {
+    pub path: PathBuf,
+    pub branch: String,
+    pub remote: Option<RemoteBranch>,
+}
+
+/// Local branches merged into default_branch (default_branch itself excluded).
+pub fn merged_branches(repo_path: &Path, default_branch: &str) -> Result<Vec<String>> {
+    let output = run_with_timeout(
+        git_cmd(repo_path).args(["branch", "--merged", default_branch]),
+        LOCAL_TIMEOUT,
+    )
+    .context("git branch --merged failed")?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
-        .map(|l| l.trim().trim_start_matches('*').trim().to_string())
+        .map(|l| l.trim().trim_start_matches(['*', '+']).trim().to_string())
         .filter(|b| !b.is_empty() && b != default_branch && !b.starts_with("HEAD"))
-        .collect();
+        .collect())
+}
+
+pub fn local_branches(repo_path: &Path) -> Vec<String> {
+    run_with_timeout(
+        git_cmd(repo_path).args(["for-each-ref", "--format=%(refname:short)", "refs/heads"]),
+        LOCAL_TIMEOUT,
+    )
+    .map(|o| String::from_utf8_lossy(&o.stdout).lines().map(String::from).collect())
+    .unwrap_or_default()
+}
 
-    let entries = list_worktrees(repo_path)?;
-    let mut removed = Vec::new();
+/// Check if branch is an ancestor of default_branch (i.e., merged).
+pub fn is_branch_merged(repo_path: &Path, branch: &str, default_branch: &str) -> bool {
+    run_with_timeout(
+        git_cmd(repo_path).args(["merge-base", "--is-ancestor", branch, default_branch]),
+        LOCAL_TIMEOUT,
+    )
+    .is_ok_and(|o| o.status.success())
+}
 
-    for entry in entries.iter().filter(|e| !e.is_main) {
-        if merged.contains(&entry.branch) {
-            if remove_worktree(repo_path, &entry.path, &entry.branch).is_ok() {
-                removed.push(entry.branch.clone());
-            }
+/// `commit` is reachable from the worktree's HEAD.
+pub fn head_contains(worktree_path: &Path, commit: &str) -> bool {
+    run_with_timeout(
+        git_cmd(worktree_path).args(["merge-base", "--is-ancestor", commit, "HEAD"]),
+        LOCAL_TIMEOUT,
+    )
+    .is_ok_and(|o| o.status.success())
+}
+
+/// `git rev-list --count HEAD --not --branches --tags --remotes`: what deleting a
+/// detached worktree would lose. Not `--all`, which counts the worktree's own HEAD.
+pub fn unreferenced_commits(worktree_path: &Path) -> Option<usize> {
+    let out = run_with_timeout(
+        git_cmd(worktree_path).args(["rev-list", "--count", "HEAD", "--not", "--branches", "--tags", "--remotes"]),
+        LOCAL_TIMEOUT,
+    )
+    .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&out.stdout).trim().parse().ok()
+}
+
+// ── Archived worktrees ───────────────────────────────────────────────────────
+
+/// Tags under this prefix hold archived worktrees: `wsx/archive/<branch>-<yyyymmdd>`.
+pub const ARCHIVE_PREFIX: &str = "wsx/archive/";
+
+/// An archive tag wsx made, or None for other tags under the prefix.
+pub fn parse_archive_tag(tag: &str) -> Option<ArchivedWorktree> {
+    let (branch, stamp) = tag.strip_prefix(ARCHIVE_PREFIX)?.rsplit_once('-')?;
+    let day = stamp.split_once('.').map_or(stamp, |(day, _)| day);
+    if branch.is_empty() || day.len() != 8 || !day.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(ArchivedWorktree {
+        tag: tag.to_string(),
+        branch: branch.to_string(),
+        date: format!("{}-{}-{}", &day[..4], &day[4..6], &day[6..]),
+        sessions: vec![],
+    })
+}
+
+/// The tag for archiving `branch` on `day` (`yyyymmdd`), numbered past any in `taken`.
+pub fn archive_tag_name(branch: &str, day: &str, taken: &[String]) -> String {
+    let base = format!("{}{}-{}", ARCHIVE_PREFIX, branch, day);
+    (1..)
+        .map(|n| if n == 1 { base.clone() } else { format!("{}.{}", base, n) })
+        .find(|tag| !taken.contains(tag))
+        .unwrap_or(base)
+}
+
+/// Archive tags in `repo_path`, newest first.
+pub fn archived_worktrees(repo_path: &Path) -> Vec<ArchivedWorktree> {
+    let out = run_with_timeout(
+        git_cmd(repo_path).args([
+            "for-each-ref",
+            "--format=%(refname:strip=2)",
+            &format!("refs/tags/{}", ARCHIVE_PREFIX.trim_end_matches('/')),
+        ]),
+        LOCAL_TIMEOUT,
+    );
+    let mut archived: Vec<ArchivedWorktree> = out
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().filter_map(parse_archive_tag).collect())
+        .unwrap_or_default();
+    archived.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.tag.cmp(&a.tag)));
+    archived
+}
+
+/// Tag the tip of `branch` as archived today; returns the tag.
+pub fn archive_branch(repo_path: &Path, branch: &str) -> Result<String> {
+    let taken: Vec<String> = archived_worktrees(repo_path).into_iter().map(|a| a.tag).collect();
+    let tag = archive_tag_name(branch, &today_stamp(), &taken);
+    run_git(repo_path, &["tag", &tag, &format!("refs/heads/{}", branch)], "git tag")?;
+    Ok(tag)
+}
+
+pub fn delete_tag(repo_path: &Path, tag: &str) -> Result<()> {
+    run_git(repo_path, &["tag", "-d", tag], "git tag -d")
+}
+
+/// `git branch -D` — for a branch whose commits an archive tag keeps.
+pub fn delete_branch(repo_path: &Path, branch: &str) -> Result<()> {
+    run_git(repo_path, &["branch", "-D", branch], "git branch -D")
+}
+
+/// `git worktree lock`, so `git worktree remove` and `prune` leave it alone until unlocked.
+pub fn lock_worktree(repo_path: &Path, worktree_path: &Path, reason: Option<&str>) -> Result<()> {
+    let mut args = vec![OsStr::new("worktree"), OsStr::new("lock")];
+    if let Some(reason) = reason.filter(|r| !r.is_empty()) {
+        args.extend([OsStr::new("--reason"), OsStr::new(reason)]);
+    }
+    args.push(worktree_path.as_os_str());
+    run_git(repo_path, &args, "git worktree lock")
+}
+
+pub fn unlock_worktree(repo_path: &Path, worktree_path: &Path) -> Result<()> {
+    run_git(repo_path, &[OsStr::new("worktree"), OsStr::new("unlock"), worktree_path.as_os_str()], "git worktree unlock")
+}
+
+/// Run a quick local git command; a failure is reported as git's first line of complaint.
+fn run_git<S: AsRef<OsStr>>(repo_path: &Path, args: &[S], what: &str) -> Result<()> {
+    let output = run_with_timeout(git_cmd(repo_path).args(args), LOCAL_TIMEOUT)
+        .with_context(|| format!("{} failed", what))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(|l| l.trim_start_matches("fatal: ").trim_start_matches("error: ").trim())
+            .find(|l| !l.is_empty());
+        match reason {
+            Some(reason) => bail!("{}", reason),
+            None => bail!("{} exited {}", what, output.status),
         }
     }
+    Ok(())
+}
 
-    Ok(removed)
+/// Today (UTC) as `yyyymmdd`.
+fn today_stamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (y, m, d) = civil_date(secs / 86_400);
+    format!("{:04}{:02}{:02}", y, m, d)
 }
 
-/// Check if branch is an ancestor of default_branch (i.e., merged).
-pub fn is_branch_merged(repo_path: &Path, branch: &str, default_branch: &str) -> bool {
-    git_cmd(repo_path)
-        .args(["merge-base", "--is-ancestor", branch, default_branch])
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+/// (year, month, day) of a day count since 1970-01-01 — Howard Hinnant's `civil_from_days`.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + u64::from(m <= 2), m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        archive_branch, archive_tag_name, archived_worktrees, civil_date, create_worktree, delete_branch,
+        delete_tag, head_contains, list_worktrees, lock_worktree, parse_checked_out_path, parse_porcelain_output, main_repo_of,
+        parse_archive_tag, remove_worktree, uncommitted_changes, unlock_worktree, NewWorktree, conventional_path,
+        is_external, move_worktree, push_delete_reason, unreferenced_commits,
+    };
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use crate::scratch::{git, init_repo, TempDir};
+
+    #[test]
+    fn parses_checked_out_path_from_either_git_wording() {
+        let old = "fatal: 'fix/login' is already checked out at '/work/api-fix-login'\n";
+        let new = "Preparing worktree (checking out 'fix/login')\n\
+                   fatal: 'fix/login' is already used by worktree at '/work/api fix'\n";
+        assert_eq!(parse_checked_out_path(old), Some(PathBuf::from("/work/api-fix-login")));
+        assert_eq!(parse_checked_out_path(new), Some(PathBuf::from("/work/api fix")));
+        assert_eq!(parse_checked_out_path("fatal: invalid reference: nope"), None);
+    }
+
+    #[test]
+    fn porcelain_keeps_raw_path_bytes() {
+        let main = b"worktree /work/My Repo\0HEAD 1234\0branch refs/heads/main\0\0";
+        let linked = b"worktree /work/My Repo-f\xffx [1]\0HEAD 5678\0branch refs/heads/fix/x\0\0";
+        let out = [&main[..], &linked[..]].concat();
+        let entries = parse_porcelain_output(&out, b'\0', Path::new("/work/My Repo")).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, Path::new("/work/My Repo"));
+        assert_eq!(entries[1].path.as_os_str(), OsStr::from_bytes(b"/work/My Repo-f\xffx [1]"));
+        assert_eq!(entries[1].branch, "fix/x");
+
+        let detached = b"worktree /work/My Repo-v1\0HEAD 0123456789abcdef\0detached\0\0";
+        let out = [&out[..], &detached[..]].concat();
+        let entries = parse_porcelain_output(&out, b'\0', Path::new("/work/My Repo")).unwrap();
+        assert!(entries[2].detached && !entries[1].detached);
+        assert_eq!(entries[2].branch, "0123456", "short SHA until a tag is looked up");
+
+        let newline = String::from_utf8_lossy(&out).replace('\0', "\n");
+        let entries = parse_porcelain_output(newline.as_bytes(), b'\n', Path::new("/r")).unwrap();
+        assert_eq!(entries[0].path, Path::new("/work/My Repo"));
+    }
+
+    #[test]
+    fn porcelain_reads_lock_reasons() {
+        let out = b"worktree /r\0HEAD 1\0branch refs/heads/main\0\0\
+worktree /r-a\0HEAD 2\0branch refs/heads/a\0locked\0\0\
+worktree /r-b\0HEAD 3\0branch refs/heads/b\0locked on a USB disk\0\0\
+worktree /r-c\0HEAD 4\0branch refs/heads/c\0";
+        let entries = parse_porcelain_output(out, b'\0', Path::new("/r")).unwrap();
+        let locked: Vec<Option<&str>> = entries.iter().map(|e| e.locked.as_deref()).collect();
+        assert_eq!(locked, [None, Some(""), Some("on a USB disk"), None]);
+    }
+
+    #[test]
+    fn worktree_round_trip_under_path_with_spaces_and_brackets() {
+        let base = TempDir::new("wsx git [x] dir");
+        let repo = base.join("My Repo [x]");
+        init_repo(&repo);
+        let git = |args: &[&str]| git(&repo, args);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+
+        let wt = create_worktree(&repo, &NewWorktree::parse("feat/a", "main")).unwrap();
+        assert_eq!(wt, base.join("My Repo [x]-feat-a"));
+        let entries = list_worktrees(&repo).unwrap();
+        let linked = entries.iter().find(|e| !e.is_main).unwrap();
+        assert_eq!(std::fs::canonicalize(&linked.path).unwrap(), std::fs::canonicalize(&wt).unwrap());
+        assert_eq!(linked.branch, "feat/a");
+        assert_eq!(main_repo_of(&wt), Some(std::fs::canonicalize(&repo).unwrap()));
+        assert_eq!(main_repo_of(&repo), None);
+        assert!(!is_external(&repo, linked), "where create_worktree puts it");
+
+        let by_hand = base.join("scratch");
+        git(&["worktree", "add", "-q", "-b", "hotfix", by_hand.to_str().unwrap()]);
+        let hotfix = |entries: Vec<super::WorktreeEntry>| entries.into_iter().find(|e| e.branch == "hotfix").unwrap();
+        assert!(is_external(&repo, &hotfix(list_worktrees(&repo).unwrap())));
+        let home = conventional_path(&repo, "hotfix").unwrap();
+        move_worktree(&repo, &by_hand, &home).unwrap();
+        assert!(!is_external(&repo, &hotfix(list_worktrees(&repo).unwrap())));
+        remove_worktree(&repo, &home, Some("hotfix"), true).unwrap();
+
+        std::fs::write(wt.join("notes.txt"), "wip").unwrap();
+        assert_eq!(uncommitted_changes(&wt).unwrap(), 1);
+        assert!(remove_worktree(&repo, &wt, Some("feat/a"), false).is_err());
+        assert!(wt.exists());
+        remove_worktree(&repo, &wt, Some("feat/a"), true).unwrap();
+        assert!(!wt.exists());
+        assert_eq!(list_worktrees(&repo).unwrap().len(), 1);
+
+        git(&["tag", "v1.0"]);
+        let tagged = create_worktree(&repo, &NewWorktree::parse("@v1.0", "main")).unwrap();
+        assert_eq!(tagged, base.join("My Repo [x]-v1.0"));
+        let entries = list_worktrees(&repo).unwrap();
+        let linked = entries.iter().find(|e| !e.is_main).unwrap();
+        assert!(linked.detached);
+        assert_eq!(linked.branch, "v1.0", "labelled by the tag at HEAD");
+        assert_eq!(unreferenced_commits(&tagged), Some(0));
+        crate::scratch::git(&tagged, &["commit", "-q", "--allow-empty", "-m", "x"]);
+        assert_eq!(unreferenced_commits(&tagged), Some(1), "only the detached HEAD has it");
+        let entries = list_worktrees(&repo).unwrap();
+        assert_eq!(entries[1].branch.len(), 7, "HEAD moved off the tag: {}", entries[1].branch);
+        assert!(!is_external(&repo, &entries[1]), "named after the tag it was made at");
+        lock_worktree(&repo, &tagged, Some("keep [me]")).unwrap();
+        let entries = list_worktrees(&repo).unwrap();
+        assert_eq!(entries[1].locked.as_deref(), Some("keep [me]"));
+        assert!(remove_worktree(&repo, &tagged, None, true).is_err(), "one --force doesn't override a lock");
+        unlock_worktree(&repo, &tagged).unwrap();
+        remove_worktree(&repo, &tagged, None, false).unwrap();
+        assert_eq!(list_worktrees(&repo).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn archive_tags_name_and_parse() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(20_741), (2026, 10, 15));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+
+        let taken = vec!["wsx/archive/feat/x-20261015".to_string()];
+        assert_eq!(archive_tag_name("feat/x", "20261015", &[]), "wsx/archive/feat/x-20261015");
+        assert_eq!(archive_tag_name("feat/x", "20261015", &taken), "wsx/archive/feat/x-20261015.2");
+
+        let a = parse_archive_tag("wsx/archive/fix-login-bug-20261015.2").unwrap();
+        assert_eq!((a.branch.as_str(), a.date.as_str()), ("fix-login-bug", "2026-10-15"));
+        assert_eq!(parse_archive_tag("wsx/archive/handmade"), None);
+        assert_eq!(parse_archive_tag("v1.0"), None);
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let repo = TempDir::new("wsx archive");
+        init_repo(&repo);
+        let git = |args: &[&str]| git(&repo, args);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&["checkout", "-q", "-b", "feat/x"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "unmerged work"]);
+        let tip = git(&["rev-parse", "HEAD"]);
+        git(&["checkout", "-q", "main"]);
+
+        let first = archive_branch(&repo, "feat/x").unwrap();
+        let second = archive_branch(&repo, "feat/x").unwrap();
+        assert_eq!(second, format!("{}.2", first));
+        delete_branch(&repo, "feat/x").unwrap();
+        assert_eq!(git(&["rev-parse", &first]), tip, "the tag keeps the unmerged commit");
+        assert!(!head_contains(&repo, &first), "main never had it");
+
+        let archived = archived_worktrees(&repo);
+        assert_eq!(archived.iter().map(|a| a.tag.as_str()).collect::<Vec<_>>(), [second.as_str(), first.as_str()]);
+        assert!(archived.iter().all(|a| a.branch == "feat/x"));
+        delete_tag(&repo, &second).unwrap();
+        assert_eq!(archived_worktrees(&repo).len(), 1);
+        assert!(delete_tag(&repo, &second).is_err());
+    }
+
+    #[test]
+    fn push_delete_failures_read_as_short_reasons() {
+        let cases = [
+            (
+                "remote: error: GH006: Protected branch update failed for refs/heads/release.\n\
+                 To github.com:acme/api.git\n ! [remote rejected] release (protected branch hook declined)\n\
+                 error: failed to push some refs to 'github.com:acme/api.git'",
+                "protected branch",
+            ),
+            (
+                "git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository.\n\n\
+                 Please make sure you have the correct access rights\nand the repository exists.",
+                "needs credentials — run it in a session",
+            ),
+            (
+                "fatal: could not read Username for 'https://github.com': terminal prompts disabled",
+                "needs credentials — run it in a session",
+            ),
+            (
+                "remote: Permission to acme/api.git denied to someone.\n\
+                 fatal: unable to access 'https://github.com/acme/api.git/': The requested URL returned error: 403",
+                "permission denied",
+            ),
+            (
+                "error: unable to delete 'feature': remote ref does not exist\n\
+                 error: failed to push some refs to 'github.com:acme/api.git'",
+                "already deleted on remote",
+            ),
+            (
+                "ssh: Could not resolve hostname github.com: Name or service not known\n\
+                 fatal: Could not read from remote repository.",
+                "remote unreachable",
+            ),
+            ("fatal: 'upstream' does not appear to be a git repository\nfatal: Could not read from remote", "remote unreachable"),
+            ("\nerror: src refspec feature does not match any\nerror: failed to push some refs", "src refspec feature does not match any"),
+            ("", "git push failed"),
+        ];
+        for (stderr, reason) in cases {
+            assert_eq!(push_delete_reason(stderr), reason, "{}", stderr);
+        }
+    }
 }
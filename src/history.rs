@@ -0,0 +1,63 @@
+// Command history — commands given to new sessions and `S`, offered again on ↑ in those prompts.
+
+use std::path::PathBuf;
+
+use crate::persist;
+
+/// Oldest entries fall off past this.
+const MAX_ENTRIES: usize = 100;
+
+pub struct CommandHistory {
+    /// newest first
+    pub entries: Vec<String>,
+    /// None: in-memory only (tests)
+    path: Option<PathBuf>,
+}
+
+impl CommandHistory {
+    /// One command per line next to the cache; missing or unreadable means empty.
+    pub fn load() -> Self {
+        let path = crate::cache::cache_path().with_file_name("history");
+        let entries = std::fs::read_to_string(&path)
+            .map(|text| text.lines().filter(|l| !l.is_empty()).map(String::from).collect())
+            .unwrap_or_default();
+        Self { entries, path: Some(path) }
+    }
+
+    pub fn disabled() -> Self {
+        Self { entries: vec![], path: None }
+    }
+
+    /// Move `cmd` to the front and save; a failed write just loses the entry on restart.
+    pub fn record(&mut self, cmd: &str) {
+        let cmd = cmd.trim();
+        if cmd.is_empty() || cmd.contains('\n') {
+            return;
+        }
+        self.entries.retain(|e| e != cmd);
+        self.entries.insert(0, cmd.to_string());
+        self.entries.truncate(MAX_ENTRIES);
+        if let Some(path) = &self.path {
+            let _ = persist::with_lock(path, || persist::write_atomic(path, &self.entries.join("\n")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandHistory, MAX_ENTRIES};
+
+    #[test]
+    fn record_dedupes_newest_first_and_caps() {
+        let mut history = CommandHistory::disabled();
+        for cmd in ["cargo test", "npm run dev", " cargo test ", ""] {
+            history.record(cmd);
+        }
+        assert_eq!(history.entries, ["cargo test", "npm run dev"]);
+        for i in 0..MAX_ENTRIES {
+            history.record(&format!("cmd {}", i));
+        }
+        assert_eq!(history.entries.len(), MAX_ENTRIES);
+        assert_eq!(history.entries[0], format!("cmd {}", MAX_ENTRIES - 1));
+    }
+}
@@ -1,49 +1,437 @@
 // Post-create hooks and .env file copying (ported from gtr).
 
 use anyhow::{bail, Context, Result};
-use glob::glob;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use glob::Pattern;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 use crate::model::workspace::ProjectConfig;
 
-pub fn copy_env_files(src: &Path, dest: &Path, config: &ProjectConfig) -> Result<()> {
+/// What `copy_env_files` did (or, from `plan_env_copy`, would do). Paths are relative to the repo.
+#[derive(Debug, Default, PartialEq)]
+pub struct CopySummary {
+    pub copied: Vec<PathBuf>,
+    /// matched, but the destination already exists and `copy.overwrite` is off
+    pub skipped: Vec<PathBuf>,
+    /// matched an include and an exclude
+    pub excluded: Vec<PathBuf>,
+    /// written from a `template.file`; destinations relative to the new worktree
+    pub rendered: Vec<PathBuf>,
+}
+
+impl CopySummary {
+    /// "copied 2 (.env, config/app.env), kept 1 existing (.env.local)"; empty when nothing matched.
+    pub fn describe(&self, max_names: usize) -> String {
+        let list = |paths: &[PathBuf]| {
+            let mut names: Vec<String> =
+                paths.iter().take(max_names).map(|p| p.display().to_string()).collect();
+            if paths.len() > max_names {
+                names.push("…".to_string());
+            }
+            names.join(", ")
+        };
+        let mut parts = vec![];
+        if !self.copied.is_empty() {
+            parts.push(format!("copied {} ({})", self.copied.len(), list(&self.copied)));
+        }
+        if !self.rendered.is_empty() {
+            parts.push(format!("rendered {} ({})", self.rendered.len(), list(&self.rendered)));
+        }
+        if !self.skipped.is_empty() {
+            parts.push(format!("kept {} existing ({})", self.skipped.len(), list(&self.skipped)));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Copy files matching `copy.include` (minus `copy.exclude`) from `src` into `dest`.
+/// Existing destination files are left alone unless `copy.overwrite` is set.
+pub fn copy_env_files(src: &Path, dest: &Path, config: &ProjectConfig) -> Result<CopySummary> {
+    let summary = plan_env_copy(src, Some(dest), config)?;
+    for rel in &summary.copied {
+        let (src_file, dest_file) = (src.join(rel), dest.join(rel));
+        if let Some(parent) = dest_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&src_file, &dest_file)
+            .with_context(|| format!("copying {} to {}", src_file.display(), dest_file.display()))?;
+    }
+    Ok(summary)
+}
+
+/// Dry run of `copy_env_files`: nothing is written; `dest` is None before the worktree
+/// exists. Patterns are relative to `src` and matched a path component at a time, so
+/// the repo path is taken literally — spaces, `[...]` and bytes that aren't UTF-8 alike.
+pub fn plan_env_copy(src: &Path, dest: Option<&Path>, config: &ProjectConfig) -> Result<CopySummary> {
+    let mut summary = CopySummary::default();
+    let excludes: Vec<Pattern> = config
+        .copy_excludes
+        .iter()
+        .map(|ex| Pattern::new(ex).with_context(|| format!("invalid exclude pattern: {}", ex)))
+        .collect::<Result<_>>()?;
+
     for pattern in &config.copy_includes {
-        let full_pattern = src.join(pattern);
-        let full_pattern_str = full_pattern.to_string_lossy();
-
-        for entry in glob(&full_pattern_str).context("invalid glob pattern")? {
-            let src_file = entry?;
-            let rel = src_file.strip_prefix(src)?;
-
-            let excluded = config.copy_excludes.iter().any(|ex| {
-                let ex_pattern = src.join(ex);
-                glob(&ex_pattern.to_string_lossy())
-                    .ok()
-                    .and_then(|mut g| g.next())
-                    .and_then(|r| r.ok())
-                    .map(|p| p == src_file)
-                    .unwrap_or(false)
-            });
-            if excluded { continue; }
-
-            let dest_file = dest.join(rel);
-            if let Some(parent) = dest_file.parent() {
-                std::fs::create_dir_all(parent)?;
+        let parts: Vec<&str> = pattern.split('/').filter(|p| !p.is_empty()).collect();
+        let mut matched = vec![];
+        glob_under(src, Path::new(""), &parts, &mut matched)?;
+        for rel in matched {
+            let seen = [&summary.copied, &summary.skipped, &summary.excluded];
+            if seen.iter().any(|list| list.contains(&rel)) { continue; } // matched an earlier include
+
+            if excludes.iter().any(|ex| ex.matches_path(&rel)) {
+                summary.excluded.push(rel);
+            } else if dest.is_some_and(|d| d.join(&rel).exists()) && !config.copy_overwrite {
+                summary.skipped.push(rel);
+            } else {
+                summary.copied.push(rel);
             }
-            std::fs::copy(&src_file, &dest_file)
-                .with_context(|| format!("copying {} to {}", src_file.display(), dest_file.display()))?;
         }
     }
+    Ok(summary)
+}
+
+/// Files in `dir` (at `rel` under the repo) that the glob components `parts` match, in
+/// name order; `**` stands for any number of directories.
+fn glob_under(dir: &Path, rel: &Path, parts: &[&str], out: &mut Vec<PathBuf>) -> Result<()> {
+    let Some((part, rest)) = parts.split_first() else { return Ok(()) };
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| (e.file_name(), e.path()))
+        .collect();
+    entries.sort();
+    if *part == "**" {
+        glob_under(dir, rel, rest, out)?;
+        for (name, path) in entries.iter().filter(|(_, path)| path.is_dir()) {
+            glob_under(path, &rel.join(name), parts, out)?;
+        }
+        return Ok(());
+    }
+    let pattern = Pattern::new(part).with_context(|| format!("invalid glob pattern: {}", part))?;
+    for (name, path) in entries {
+        if !name.to_str().is_some_and(|n| pattern.matches(n)) {
+            continue;
+        }
+        match rest.is_empty() {
+            true if path.is_file() => out.push(rel.join(name)),
+            false if path.is_dir() => glob_under(&path, &rel.join(name), rest, out)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// What `{{branch}}`, `{{slug}}`, `{{worktree_path}}` and `{{port}}` stand for in a template.
+pub struct TemplateVars {
+    pub branch: String,
+    pub slug: String,
+    pub worktree_path: PathBuf,
+    pub port: u16,
+}
+
+impl TemplateVars {
+    pub fn new(branch: &str, slug: String, worktree_path: &Path, ports: (u16, u16)) -> Self {
+        Self {
+            branch: branch.to_string(),
+            slug,
+            worktree_path: worktree_path.to_path_buf(),
+            port: stable_port(branch, ports),
+        }
+    }
+}
+
+/// A port in `lo..=hi` derived from the branch name: the same branch always gets the
+/// same port, different branches rarely collide. FNV-1a, so it's stable across builds.
+pub fn stable_port(branch: &str, (lo, hi): (u16, u16)) -> u16 {
+    let hash = branch
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    let span = (hi - lo) as u64 + 1;
+    lo + (hash % span) as u16
+}
+
+/// Fill in the known placeholders; anything else in braces is left as written.
+pub fn substitute(text: &str, vars: &TemplateVars) -> String {
+    let worktree_path = vars.worktree_path.to_string_lossy();
+    [
+        ("{{branch}}", vars.branch.as_str()),
+        ("{{slug}}", vars.slug.as_str()),
+        ("{{worktree_path}}", &worktree_path),
+        ("{{port}}", &vars.port.to_string()),
+    ]
+    .iter()
+    .fold(text.to_string(), |out, (key, value)| out.replace(key, value))
+}
+
+/// Render each `template.file` from `src` into `dest`, after the env copy. Existing
+/// destinations follow `copy.overwrite`; a template or destination outside its tree is
+/// an error.
+pub fn render_templates(
+    src: &Path,
+    dest: &Path,
+    config: &ProjectConfig,
+    vars: &TemplateVars,
+    summary: &mut CopySummary,
+) -> Result<()> {
+    // Relative and without `..`: the config comes from the repo, which may not be ours.
+    let escapes = |p: &Path| p.is_absolute() || p.components().any(|c| c == std::path::Component::ParentDir);
+    for (template, target) in &config.templates {
+        if escapes(Path::new(template)) {
+            bail!("template must be a file inside the repo: {}", template);
+        }
+        let rel = PathBuf::from(target);
+        if escapes(&rel) {
+            bail!("template destination must stay inside the worktree: {}", target);
+        }
+        let dest_file = dest.join(&rel);
+        if dest_file.exists() && !config.copy_overwrite {
+            summary.skipped.push(rel);
+            continue;
+        }
+        let text = std::fs::read_to_string(src.join(template))
+            .with_context(|| format!("reading template {}", template))?;
+        if let Some(parent) = dest_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&dest_file, substitute(&text, vars))
+            .with_context(|| format!("writing {}", dest_file.display()))?;
+        summary.rendered.push(rel);
+    }
     Ok(())
 }
 
-pub fn run_post_create(dir: &Path, cmd: &str) -> Result<()> {
-    let status = Command::new("sh")
+/// A running postCreate hook, for `cancel_post_create`.
+#[derive(Clone)]
+pub struct HookChild(Arc<Mutex<Child>>);
+
+/// Run the postCreate hook `cmd` in `dir`, in its own process group and without stdin.
+/// `on_start` gets the hook (for `cancel_post_create`); each line the hook prints, on
+/// stdout or stderr, goes to `on_line` as it arrives. Returns once the hook has exited —
+/// background jobs it leaves holding the pipes don't keep it waiting.
+pub fn run_post_create(
+    dir: &Path,
+    cmd: &str,
+    on_start: impl FnOnce(HookChild),
+    mut on_line: impl FnMut(String),
+) -> Result<()> {
+    let mut child = Command::new("sh")
         .arg("-c").arg(cmd)
         .current_dir(dir)
-        .stdout(Stdio::null()).stderr(Stdio::null())
-        .status()
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
         .with_context(|| format!("running postCreate: {}", cmd))?;
+
+    enum Out { Line(String), Exited(std::io::Result<ExitStatus>) }
+    let (tx, rx) = mpsc::channel();
+    let pipes: [Option<Box<dyn Read + Send>>; 2] = [
+        child.stdout.take().map(|p| Box::new(p) as _),
+        child.stderr.take().map(|p| Box::new(p) as _),
+    ];
+    for pipe in pipes.into_iter().flatten() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            for chunk in BufReader::new(pipe).split(b'\n').map_while(|c| c.ok()) {
+                // Progress bars redraw with \r; the last redraw is the one to keep.
+                let chunk = chunk.rsplit(|&b| b == b'\r').next().unwrap_or_default();
+                let line = String::from_utf8_lossy(chunk).trim_end().to_string();
+                if tx.send(Out::Line(line)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    let child = HookChild(Arc::new(Mutex::new(child)));
+    on_start(child.clone());
+    std::thread::spawn(move || {
+        // Polled rather than waited on, so the lock is free for `cancel_post_create`.
+        let status = loop {
+            match child.0.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(e) => break Err(e),
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        let _ = tx.send(Out::Exited(status));
+    });
+
+    let status = loop {
+        match rx.recv() {
+            Ok(Out::Line(line)) => on_line(line),
+            Ok(Out::Exited(status)) => break status.context("waiting for postCreate")?,
+            Err(_) => bail!("postCreate hook vanished"),
+        }
+    };
+    // Output still in the pipes when the hook exited.
+    while let Ok(Out::Line(line)) = rx.recv_timeout(Duration::from_millis(50)) {
+        on_line(line);
+    }
     if !status.success() { bail!("postCreate hook exited {}", status); }
     Ok(())
 }
+
+/// Stop a running postCreate hook and everything it started. A hook that has already
+/// been reaped is left alone: its group id may belong to another process by now.
+pub fn cancel_post_create(hook: &HookChild) {
+    let mut child = hook.0.lock().unwrap();
+    if let Ok(None) = child.try_wait() {
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cancel_post_create, copy_env_files, plan_env_copy, render_templates, run_post_create, stable_port,
+        substitute, CopySummary, TemplateVars,
+    };
+    use crate::model::workspace::ProjectConfig;
+    use crate::scratch::TempDir;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    #[test]
+    fn copies_env_files_from_path_with_spaces_and_brackets() {
+        let base = TempDir::new("wsx hooks [x]");
+        let (src, dest) = (base.join("My Repo [main]"), base.join("My Repo [wt]"));
+        std::fs::create_dir_all(src.join("config")).unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+        for f in [".env", ".env.local", "config/app.env", "README.md"] {
+            std::fs::write(src.join(f), f).unwrap();
+        }
+        let config = ProjectConfig {
+            copy_includes: vec![".env*".into(), "config/*.env".into()],
+            copy_excludes: vec![".env.local".into()],
+            ..Default::default()
+        };
+
+        let summary = copy_env_files(&src, &dest, &config).unwrap();
+        assert_eq!(summary.copied, [PathBuf::from(".env"), PathBuf::from("config/app.env")]);
+        assert_eq!(summary.excluded, [PathBuf::from(".env.local")]);
+
+        assert_eq!(std::fs::read_to_string(dest.join(".env")).unwrap(), ".env");
+        assert!(dest.join("config/app.env").exists());
+        assert!(!dest.join(".env.local").exists());
+        assert!(!dest.join("README.md").exists());
+
+        let odd = base.join(OsStr::from_bytes(b"caf\xe9 repo"));
+        std::fs::create_dir_all(&odd).unwrap();
+        std::fs::write(odd.join(".env"), "latin-1").unwrap();
+        let summary = copy_env_files(&odd, &dest, &ProjectConfig { copy_overwrite: true, ..config }).unwrap();
+        assert_eq!(summary.copied, [PathBuf::from(".env")], "a repo path that isn't UTF-8");
+        assert_eq!(std::fs::read_to_string(dest.join(".env")).unwrap(), "latin-1");
+    }
+
+    #[test]
+    fn keeps_existing_files_unless_overwrite_is_set() {
+        let base = TempDir::new("wsx hooks overwrite");
+        let (src, dest) = (base.join("repo"), base.join("wt"));
+        std::fs::create_dir_all(src.join("gen")).unwrap();
+        std::fs::create_dir_all(dest.join("gen")).unwrap();
+        for f in [".env", "gen/schema.env", "gen/new.env"] {
+            std::fs::write(src.join(f), "from repo").unwrap();
+        }
+        std::fs::write(dest.join("gen/schema.env"), "generated").unwrap();
+        let mut config = ProjectConfig {
+            copy_includes: vec![".env".into(), "**/*.env".into(), "gen/*".into()],
+            ..Default::default()
+        };
+
+        let plan = plan_env_copy(&src, Some(&dest), &config).unwrap();
+        assert!(!dest.join(".env").exists(), "dry run writes nothing");
+        assert_eq!(
+            plan,
+            CopySummary {
+                copied: vec![".env".into(), "gen/new.env".into()],
+                skipped: vec!["gen/schema.env".into()],
+                excluded: vec![],
+                rendered: vec![],
+            }
+        );
+        assert_eq!(copy_env_files(&src, &dest, &config).unwrap(), plan);
+        assert_eq!(std::fs::read_to_string(dest.join("gen/schema.env")).unwrap(), "generated");
+        assert_eq!(plan.describe(1), "copied 2 (.env, …), kept 1 existing (gen/schema.env)");
+
+        config.copy_overwrite = true;
+        let forced = copy_env_files(&src, &dest, &config).unwrap();
+        assert_eq!(forced.copied.len(), 3);
+        assert_eq!(std::fs::read_to_string(dest.join("gen/schema.env")).unwrap(), "from repo");
+    }
+
+    #[test]
+    fn substitutes_placeholders_and_keeps_ports_stable() {
+        let vars = TemplateVars::new("feature/login", "login".into(), std::path::Path::new("/w/api-login"), (4000, 4099));
+        assert_eq!(vars.port, stable_port("feature/login", (4000, 4099)));
+        assert!((4000..=4099).contains(&vars.port));
+        assert_ne!(stable_port("feature/login", (4000, 4999)), stable_port("feature/logout", (4000, 4999)));
+        assert_eq!(stable_port("x", (8080, 8080)), 8080);
+        assert_eq!(
+            substitute("export BRANCH={{branch}} DIR={{worktree_path}}/{{slug}} {{unknown}}", &vars),
+            "export BRANCH=feature/login DIR=/w/api-login/login {{unknown}}"
+        );
+        assert_eq!(substitute("PORT={{port}}", &vars), format!("PORT={}", vars.port));
+    }
+
+    #[test]
+    fn renders_templates_into_the_worktree() {
+        let base = TempDir::new("wsx hooks templates");
+        let (src, dest) = (base.join("repo"), base.join("wt"));
+        std::fs::create_dir_all(src.join("tmpl")).unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(src.join("tmpl/envrc"), "export BRANCH={{branch}}\n").unwrap();
+        std::fs::write(dest.join("kept"), "mine").unwrap();
+        let mut config = ProjectConfig {
+            templates: vec![
+                ("tmpl/envrc".into(), ".envrc".into()),
+                ("tmpl/envrc".into(), "kept".into()),
+            ],
+            ..Default::default()
+        };
+        let vars = TemplateVars::new("dev", "dev".into(), &dest, config.template_ports());
+
+        let mut summary = CopySummary::default();
+        render_templates(&src, &dest, &config, &vars, &mut summary).unwrap();
+        assert_eq!(std::fs::read_to_string(dest.join(".envrc")).unwrap(), "export BRANCH=dev\n");
+        assert_eq!(std::fs::read_to_string(dest.join("kept")).unwrap(), "mine");
+        assert_eq!((summary.rendered, summary.skipped), (vec![".envrc".into()], vec!["kept".into()]));
+
+        config.templates = vec![("tmpl/envrc".into(), "../escape".into())];
+        assert!(render_templates(&src, &dest, &config, &vars, &mut CopySummary::default()).is_err());
+        assert!(!base.join("escape").exists());
+        std::fs::write(base.join("secret"), "outside").unwrap();
+        for template in ["../secret", "/etc/hostname"] {
+            config.templates = vec![(template.into(), "leaked".into())];
+            let err = render_templates(&src, &dest, &config, &vars, &mut CopySummary::default()).unwrap_err();
+            assert!(err.to_string().starts_with("template must be a file inside the repo"), "{}", err);
+        }
+        assert!(!dest.join("leaked").exists());
+    }
+
+    #[test]
+    fn post_create_streams_both_pipes_and_can_be_cancelled() {
+        let dir = std::env::temp_dir();
+        let mut lines = vec![];
+        let err = run_post_create(&dir, "echo out; echo err >&2; printf '10%%\\r100%%\\n'; exit 3", |_| {}, |l| lines.push(l))
+            .unwrap_err();
+        assert!(err.to_string().contains("exited"), "{}", err);
+        lines.sort();
+        assert_eq!(lines, ["100%", "err", "out"]);
+
+        let started = std::time::Instant::now();
+        let hook = std::cell::RefCell::new(None);
+        let cancel = |_| cancel_post_create(hook.borrow().as_ref().unwrap());
+        let result = run_post_create(&dir, "echo ready; sleep 30", |h| *hook.borrow_mut() = Some(h), cancel);
+        assert!(result.is_err());
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // Once the hook is reaped, cancelling doesn't signal whatever has its id now.
+        let hook = std::cell::RefCell::new(None);
+        run_post_create(&dir, "true", |h| *hook.borrow_mut() = Some(h), |_| {}).unwrap();
+        cancel_post_create(hook.borrow().as_ref().unwrap());
+    }
+}
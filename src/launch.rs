@@ -0,0 +1,70 @@
+// "Open with" — user-defined commands (editor, file manager, …) run detached on a path.
+
+use anyhow::{Context, Result};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+
+/// Replace every `{path}` in `template` with `path`, single-quoted for `sh`.
+pub fn expand(template: &str, path: &Path) -> String {
+    template.replace("{path}", &shell_quote(&path.to_string_lossy()))
+}
+
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Start `template` via `sh -c` in its own process group, so it outlives wsx and
+/// never reads the TUI's stdin. A non-zero exit is reported later on `failures`.
+pub fn spawn(name: &str, template: &str, path: &Path, failures: mpsc::Sender<String>) -> Result<()> {
+    let cmd = expand(template, path);
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&cmd)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .with_context(|| format!("starting {}", name))?;
+    let name = name.to_string();
+    std::thread::spawn(move || {
+        let Ok(out) = child.wait_with_output() else { return };
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let reason = stderr
+                .lines()
+                .rfind(|l| !l.trim().is_empty())
+                .map(|l| l.trim().to_string())
+                .unwrap_or_else(|| out.status.to_string());
+            let _ = failures.send(format!("{} failed: {}", name, reason));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, spawn};
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn expand_quotes_path() {
+        let path = Path::new("/tmp/it's [a] $HOME");
+        assert_eq!(expand("code {path}", path), r"code '/tmp/it'\''s [a] $HOME'");
+        assert_eq!(expand("open -R {path}", Path::new("/x")), "open -R '/x'");
+    }
+
+    #[test]
+    fn reports_missing_binary() {
+        let (tx, rx) = mpsc::channel();
+        spawn("Nope", "wsx-no-such-binary {path}", &std::env::temp_dir(), tx).unwrap();
+        let msg = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(msg.starts_with("Nope failed: "), "{}", msg);
+        assert!(msg.contains("not found"), "{}", msg);
+    }
+}
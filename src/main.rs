@@ -4,37 +4,80 @@
 mod action;
 mod app;
 mod cache;
+mod clean;
+mod cli;
 mod config;
+mod doctor;
 mod event;
+mod events;
 mod git;
+mod history;
 mod hooks;
+mod launch;
 mod model;
 mod ops;
+mod persist;
+#[cfg(test)]
+mod scratch;
 mod tmux;
 mod tui;
 mod ui;
 
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
 use app::App;
 
 fn main() -> Result<()> {
+    let args = cli::parse()?;
+    let cwd = std::env::current_dir().unwrap_or_default();
+    if let Some(profile) = config::global::resolve_profile(args.config.clone(), std::env::var_os("WSX_CONFIG"), &cwd) {
+        config::global::GlobalConfig::use_profile(profile);
+    }
+
+    if args.doctor {
+        let findings = doctor::doctor(&config::global::GlobalConfig::load()?);
+        println!("{}", doctor::report(&findings));
+        let critical = findings.iter().any(|f| f.severity == doctor::Severity::Critical);
+        std::process::exit(if critical { 1 } else { 0 });
+    }
+
+    if let Some(clean) = args.clean {
+        let config = config::global::GlobalConfig::load()?;
+        let results = clean::clean_all(&git::CliGit, &config, clean.dry_run);
+        if clean.json {
+            println!("{}", clean::json_report(&results, clean.dry_run));
+        } else {
+            println!("{}", clean::text_report(&results, clean.dry_run));
+        }
+        std::process::exit(clean::exit_code(&results));
+    }
+
     // Require tmux
     if !tmux::session::is_available() {
         eprintln!("wsx requires tmux — https://github.com/tmux/tmux/wiki/Installing");
         std::process::exit(1);
     }
 
-    let mut terminal = tui::init().context("terminal init failed")?;
+    // --pick-path keeps stdout clean for the selected path; the TUI draws on /dev/tty.
+    let mut terminal = tui::init(args.pick_path).context("terminal init failed")?;
 
-    let result = run(&mut terminal);
+    let result = run(&mut terminal, &args);
 
     // Always restore terminal, even on error
     let _ = tui::restore(&mut terminal);
 
-    result
+    match result? {
+        Some(path) => println!("{}", path.display()),
+        None if args.pick_path => std::process::exit(1),
+        None => {}
+    }
+    Ok(())
 }
 
-fn run(terminal: &mut tui::Tui) -> Result<()> {
-    let mut app = App::new()?;
-    app.run(terminal)
+/// Returns the worktree path picked in `--pick-path` mode, if any.
+fn run(terminal: &mut tui::Tui, args: &cli::Args) -> Result<Option<PathBuf>> {
+    let mut app = App::new(args)?;
+    app.run(terminal)?;
+    Ok(app.picked_path)
 }
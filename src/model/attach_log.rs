@@ -0,0 +1,102 @@
+// Attach history — when each session was attached, for usage stats and "recent" ordering.
+
+use serde::{Deserialize, Serialize};
+
+/// Only the newest attaches are kept.
+const MAX_ATTACHES: usize = 300;
+const WEEK_SECS: u64 = 7 * 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Attach {
+    /// unix seconds
+    pub ts: u64,
+    pub session: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct AttachLog {
+    /// oldest first
+    entries: Vec<Attach>,
+}
+
+/// What the session preview shows about one session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachStats {
+    pub last: u64,
+    pub this_week: usize,
+}
+
+impl AttachLog {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn record(&mut self, session: &str, now: u64) {
+        self.entries.push(Attach { ts: now, session: session.to_string() });
+        self.prune();
+    }
+
+    /// Union with another instance's log, e.g. when merging the on-disk cache.
+    pub fn merge(&mut self, other: AttachLog) {
+        self.entries.extend(other.entries);
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        self.entries.sort();
+        self.entries.dedup();
+        let excess = self.entries.len().saturating_sub(MAX_ATTACHES);
+        self.entries.drain(..excess);
+    }
+
+    /// Keep a renamed session's history.
+    pub fn rename(&mut self, old: &str, new: &str) {
+        for attach in self.entries.iter_mut().filter(|a| a.session == old) {
+            attach.session = new.to_string();
+        }
+    }
+
+    pub fn last_attached(&self, session: &str) -> Option<u64> {
+        self.entries.iter().rev().find(|a| a.session == session).map(|a| a.ts)
+    }
+
+    pub fn stats(&self, session: &str, now: u64) -> Option<AttachStats> {
+        let last = self.last_attached(session)?;
+        let since = now.saturating_sub(WEEK_SECS);
+        let this_week = self
+            .entries
+            .iter()
+            .filter(|a| a.session == session && a.ts >= since)
+            .count();
+        Some(AttachStats { last, this_week })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttachLog, AttachStats, MAX_ATTACHES, WEEK_SECS};
+
+    #[test]
+    fn oldest_attaches_are_pruned_past_the_cap() {
+        let mut log = AttachLog::default();
+        log.record("old", 1);
+        for ts in 0..MAX_ATTACHES as u64 {
+            log.record("busy", 100 + ts);
+        }
+        assert_eq!(log.entries.len(), MAX_ATTACHES);
+        assert_eq!(log.last_attached("old"), None);
+
+        let mut theirs = AttachLog::default();
+        theirs.record("busy", 100 + MAX_ATTACHES as u64 - 1); // already ours
+        theirs.record("other", 10_000);
+        log.merge(theirs);
+        assert_eq!(log.entries.len(), MAX_ATTACHES);
+        assert_eq!(log.last_attached("other"), Some(10_000));
+        assert_eq!(log.entries[0].ts, 101);
+
+        let now = 10_000 + WEEK_SECS;
+        assert_eq!(log.stats("other", now), Some(AttachStats { last: 10_000, this_week: 1 }));
+        assert_eq!(log.stats("busy", now), Some(AttachStats { last: 399, this_week: 0 }));
+    }
+}
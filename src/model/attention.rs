@@ -0,0 +1,138 @@
+// Why a session wants a look. The tree's ● covers a tmux bell, an app that went quiet
+// and a command that finished; the reasons behind it are kept apart so the preview and
+// the status bar can say which, and `x` can clear them one at a time.
+
+/// One reason; a session can have several (a bell from a test run that then went quiet).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttentionReason {
+    /// tmux latched a bell newer than the last acknowledgement
+    Bell,
+    /// this foreground command has printed nothing for `IDLE_SECS`
+    RunningAppIdle(String),
+    /// the foreground app exited while nobody was attached, back at the shell
+    Finished,
+}
+
+/// The reasons a session has, in `AttentionReason` order without repeats.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttentionSet(Vec<AttentionReason>);
+
+impl AttentionSet {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, reason: &AttentionReason) -> bool {
+        self.0.contains(reason)
+    }
+
+    pub fn insert(&mut self, reason: AttentionReason) {
+        if let Err(at) = self.0.binary_search(&reason) {
+            self.0.insert(at, reason);
+        }
+    }
+
+    pub fn remove(&mut self, reason: &AttentionReason) {
+        self.0.retain(|r| r != reason);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AttentionReason> {
+        self.0.iter()
+    }
+
+    /// What `n` / `N` stop at: a quiet app or a finished one. A bell alone only marks the row.
+    pub fn waiting(&self) -> bool {
+        self.0.iter().any(|r| matches!(r, AttentionReason::RunningAppIdle(_) | AttentionReason::Finished))
+    }
+}
+
+/// A session as one activity poll (or a state change between polls) leaves it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Poll<'a> {
+    pub muted: bool,
+    /// a bell newer than the last acknowledgement
+    pub bell: bool,
+    /// the foreground app, when it isn't a bare shell
+    pub app: Option<&'a str>,
+    /// output within `IDLE_SECS`
+    pub active: bool,
+    /// `x` on the quiet app, until it prints again
+    pub app_dismissed: bool,
+    /// an app was in the foreground at the previous poll
+    pub was_running: bool,
+    /// a client is attached, so whoever started it saw it end
+    pub attached: bool,
+}
+
+/// The transition table: the reasons after `poll`, given those before it. Bell and
+/// RunningAppIdle follow the live state; Finished is set when an app exits unwatched and
+/// stays until dismissed or another app starts.
+pub fn next_reasons(prev: &AttentionSet, poll: Poll) -> AttentionSet {
+    let mut next = AttentionSet::default();
+    if poll.muted {
+        return next;
+    }
+    if poll.bell {
+        next.insert(AttentionReason::Bell);
+    }
+    match poll.app {
+        Some(app) if !poll.active && !poll.app_dismissed => {
+            next.insert(AttentionReason::RunningAppIdle(app.to_string()))
+        }
+        Some(_) => {}
+        None if prev.contains(&AttentionReason::Finished) || (poll.was_running && !poll.attached) => {
+            next.insert(AttentionReason::Finished)
+        }
+        None => {}
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_reasons, AttentionReason::*, AttentionSet, Poll};
+
+    fn set<const N: usize>(reasons: [super::AttentionReason; N]) -> AttentionSet {
+        let mut set = AttentionSet::default();
+        for r in reasons {
+            set.insert(r);
+        }
+        set
+    }
+
+    #[test]
+    fn transitions() {
+        let none = AttentionSet::default();
+        let pytest = RunningAppIdle("pytest".into());
+        let running = Poll { app: Some("pytest"), was_running: true, ..Default::default() };
+
+        // (before, poll, after)
+        let table = [
+            (none.clone(), Poll { bell: true, ..Default::default() }, set([Bell])),
+            (none.clone(), Poll { active: true, ..running }, none.clone()),
+            (none.clone(), running, set([pytest.clone()])),
+            (set([pytest.clone()]), Poll { bell: true, ..running }, set([Bell, pytest.clone()])),
+            (set([pytest.clone()]), Poll { app_dismissed: true, ..running }, none.clone()),
+            (set([Bell, pytest.clone()]), Poll { muted: true, bell: true, ..running }, none.clone()),
+            // the app exits: unwatched it's Finished, watched it's nothing
+            (set([pytest.clone()]), Poll { was_running: true, active: true, ..Default::default() }, set([Finished])),
+            (none.clone(), Poll { was_running: true, attached: true, ..Default::default() }, none.clone()),
+            // Finished stays through output and bells until another app starts
+            (set([Finished]), Poll { active: true, bell: true, ..Default::default() }, set([Bell, Finished])),
+            (set([Finished]), Poll { active: true, ..running }, none.clone()),
+            (none.clone(), Poll::default(), none.clone()),
+        ];
+        for (i, (before, poll, after)) in table.into_iter().enumerate() {
+            assert_eq!(next_reasons(&before, poll), after, "row {}", i);
+        }
+    }
+
+    #[test]
+    fn set_keeps_order_without_repeats() {
+        let mut s = set([Finished, Bell, Bell]);
+        assert_eq!(s.iter().cloned().collect::<Vec<_>>(), [Bell, Finished]);
+        assert!(s.waiting());
+        s.remove(&Finished);
+        assert!(!s.waiting() && !s.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+// Recently ended sessions — the last pane capture of a session that went away, kept for
+// a look at what it printed before it crashed or was killed.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndedSession {
+    /// unix seconds the refresh noticed it gone
+    pub ts: u64,
+    /// tmux session name
+    pub session: String,
+    pub display_name: String,
+    pub project: String,
+    pub worktree: PathBuf,
+    /// last pane capture, with its ANSI colours; its last `CAPTURE_MAX_BYTES` at most
+    pub capture: String,
+}
+
+/// Most of a capture kept, so a pane full of escape codes can't bloat the cache.
+const CAPTURE_MAX_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct EndedLog {
+    /// oldest first
+    entries: Vec<EndedSession>,
+}
+
+impl EndedLog {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn record(&mut self, mut ended: EndedSession) {
+        ended.capture = tail(ended.capture, CAPTURE_MAX_BYTES);
+        self.entries.push(ended);
+    }
+
+    /// Union with another instance's log, e.g. when merging the on-disk cache.
+    pub fn merge(&mut self, other: EndedLog) {
+        self.entries.extend(other.entries);
+        self.entries.sort_by(|a, b| (a.ts, &a.session).cmp(&(b.ts, &b.session)));
+        self.entries.dedup_by(|a, b| a.ts == b.ts && a.session == b.session);
+    }
+
+    /// Drop entries older than `max_age_secs`, then all but the newest `max`.
+    pub fn expire(&mut self, max: usize, max_age_secs: u64, now: u64) {
+        self.entries.retain(|e| now.saturating_sub(e.ts) <= max_age_secs);
+        let excess = self.entries.len().saturating_sub(max);
+        self.entries.drain(..excess);
+    }
+
+    /// Newest first, only those from worktree `under` when given.
+    pub fn recent(&self, under: Option<&Path>) -> Vec<EndedSession> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| under.is_none_or(|p| e.worktree == p))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The whole lines at the end of `text` that fit in `max` bytes.
+fn tail(text: String, max: usize) -> String {
+    if text.len() <= max {
+        return text;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let start = text[start..].find('\n').map_or(start, |i| start + i + 1);
+    text[start..].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EndedLog, EndedSession, CAPTURE_MAX_BYTES};
+    use std::path::{Path, PathBuf};
+
+    fn ended(ts: u64, session: &str, worktree: &str) -> EndedSession {
+        EndedSession {
+            ts,
+            session: session.into(),
+            display_name: session.into(),
+            project: "app".into(),
+            worktree: PathBuf::from(worktree),
+            capture: format!("{} crashed", session),
+        }
+    }
+
+    #[test]
+    fn expires_by_age_then_count_and_merges_without_duplicates() {
+        let mut log = EndedLog::default();
+        log.record(ended(100, "old", "/w/app"));
+        for ts in 1_000..1_005 {
+            log.record(ended(ts, &format!("s{}", ts), "/w/app-x"));
+        }
+        log.expire(3, 500, 1_100);
+        let names: Vec<String> = log.recent(None).into_iter().map(|e| e.session).collect();
+        assert_eq!(names, ["s1004", "s1003", "s1002"]);
+
+        let mut theirs = EndedLog::default();
+        theirs.record(ended(1_003, "s1003", "/w/app-x")); // already ours
+        theirs.record(ended(1_010, "dev", "/w/app"));
+        log.merge(theirs);
+        assert_eq!(log.recent(None).len(), 4);
+        let here: Vec<String> = log.recent(Some(Path::new("/w/app"))).into_iter().map(|e| e.session).collect();
+        assert_eq!(here, ["dev"]);
+    }
+
+    #[test]
+    fn long_captures_keep_their_last_whole_lines() {
+        let mut log = EndedLog::default();
+        let line = "\x1b[31mé error\x1b[0m\n";
+        log.record(EndedSession { capture: line.repeat(10_000), ..ended(1, "dev", "/w/app") });
+        let capture = &log.recent(None)[0].capture;
+        assert!(capture.len() <= CAPTURE_MAX_BYTES && capture.len() > CAPTURE_MAX_BYTES - line.len());
+        assert!(capture.starts_with(line) && capture.ends_with(line));
+    }
+}
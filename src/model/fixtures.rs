@@ -0,0 +1,33 @@
+// Test builders for the workspace model: the plain value, tweaked with `..` where a
+// test needs more.
+
+use std::path::PathBuf;
+
+use super::workspace::{Project, SessionInfo, WorktreeInfo};
+
+/// An expanded project at `/w/{name}` on `main`, with no worktrees.
+pub fn project(name: &str) -> Project {
+    Project {
+        name: name.into(),
+        path: PathBuf::from(format!("/w/{}", name)),
+        default_branch: "main".into(),
+        expanded: true,
+        ..Default::default()
+    }
+}
+
+/// An expanded worktree at `/w/{name}` on branch `name`, with no sessions.
+pub fn worktree(name: &str) -> WorktreeInfo {
+    WorktreeInfo {
+        name: name.into(),
+        branch: name.into(),
+        path: PathBuf::from(format!("/w/{}", name)),
+        expanded: true,
+        ..Default::default()
+    }
+}
+
+/// A quiet session shown under its tmux name.
+pub fn session(name: &str) -> SessionInfo {
+    SessionInfo { name: name.into(), display_name: name.into(), ..Default::default() }
+}
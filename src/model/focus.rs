@@ -0,0 +1,75 @@
+// Whether anybody is looking at wsx. After a switch-client away its tmux session sits
+// in the background, and polling git and tmux for a screen nobody sees only costs
+// battery; while hidden the tick only asks tmux, now and then, if it's shown again —
+// and, for `--events-fifo` listeners, what the sessions are up to.
+
+use std::time::{Duration, Instant};
+
+/// How often a hidden wsx asks tmux whether its session is shown again.
+pub const PROBE_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// How often a hidden wsx still polls session activity while `--events-fifo` is open:
+/// attention and idle events matter most when nobody is looking at wsx.
+pub const HIDDEN_ACTIVITY_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Focus {
+    /// when the last probe ran (or focus was lost); None while shown
+    hidden_since_probe: Option<Instant>,
+}
+
+impl Focus {
+    pub fn hidden(&self) -> bool {
+        self.hidden_since_probe.is_some()
+    }
+
+    /// wsx switched the client away, or an activity poll saw its session unattached.
+    /// The first probe waits a full interval.
+    pub fn lose(&mut self, now: Instant) {
+        if self.hidden_since_probe.is_none() {
+            self.hidden_since_probe = Some(now);
+        }
+    }
+
+    /// While hidden: whether a probe is due, restarting the interval when it is.
+    pub fn probe_due(&mut self, now: Instant) -> bool {
+        match self.hidden_since_probe {
+            Some(last) if now.duration_since(last) >= PROBE_INTERVAL => {
+                self.hidden_since_probe = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Shown again (a probe saw a client, or a key arrived). True when it had been
+    /// hidden — the caller owes the tree a full refresh.
+    pub fn regain(&mut self) -> bool {
+        self.hidden_since_probe.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Focus, PROBE_INTERVAL};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn probes_are_spaced_and_regaining_is_reported_once() {
+        let t0 = Instant::now();
+        let mut focus = Focus::default();
+        assert!(!focus.probe_due(t0 + PROBE_INTERVAL), "nothing to probe while shown");
+        assert!(!focus.regain());
+
+        focus.lose(t0);
+        focus.lose(t0 + Duration::from_secs(1)); // a second sighting doesn't push the probe back
+        assert!(focus.hidden());
+        assert!(!focus.probe_due(t0 + PROBE_INTERVAL / 2));
+        assert!(focus.probe_due(t0 + PROBE_INTERVAL));
+        assert!(!focus.probe_due(t0 + PROBE_INTERVAL + PROBE_INTERVAL / 2), "the interval restarts");
+        assert!(focus.probe_due(t0 + PROBE_INTERVAL * 2));
+
+        assert!(focus.regain());
+        assert!(!focus.hidden() && !focus.regain());
+    }
+}
@@ -0,0 +1,85 @@
+// Frecency — a use count that decays with age, for "recently used" project ordering —
+// and the unix clock and age formatting the rest of wsx counts time with.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A bump is worth half as much after this long.
+const HALF_LIFE_SECS: f64 = 3.0 * 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct Frecency {
+    /// score as of `updated`
+    pub score: f64,
+    /// unix seconds of the last bump
+    pub updated: u64,
+}
+
+impl Frecency {
+    pub fn score_at(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.updated) as f64;
+        self.score * 0.5f64.powf(age / HALF_LIFE_SECS)
+    }
+
+    /// Record one use at `now`.
+    pub fn bump(&mut self, now: u64) {
+        self.score = self.score_at(now) + 1.0;
+        self.updated = now;
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// "42s", "5m", "3h", "2d": the largest whole unit.
+pub fn fmt_idle(d: Duration) -> String {
+    let s = d.as_secs();
+    match s {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+/// `fmt_idle` for ages counted in days and weeks, spelled out: "5h", "1 day", "47 days",
+/// "9 weeks". Days up to eight weeks, where "6 weeks" would round too much away.
+pub fn fmt_age(d: Duration) -> String {
+    match d.as_secs() / 86_400 {
+        0 => fmt_idle(d),
+        1 => "1 day".to_string(),
+        days if days < 56 => format!("{} days", days),
+        days => format!("{} weeks", days / 7),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Frecency, HALF_LIFE_SECS};
+
+    #[test]
+    fn score_halves_every_half_life() {
+        let mut f = Frecency::default();
+        f.bump(1_000);
+        f.bump(1_000);
+        assert_eq!(f.score_at(1_000), 2.0);
+        let later = 1_000 + HALF_LIFE_SECS as u64;
+        assert!((f.score_at(later) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recent_use_beats_old_heavy_use() {
+        let mut old = Frecency::default();
+        for _ in 0..5 {
+            old.bump(0);
+        }
+        let mut recent = Frecency::default();
+        let now = 4 * HALF_LIFE_SECS as u64;
+        recent.bump(now);
+        assert!(recent.score_at(now) > old.score_at(now));
+    }
+}
@@ -1 +1,14 @@
+pub mod attach_log;
+pub mod attention;
+pub mod ended;
+#[cfg(test)]
+pub mod fixtures;
+pub mod focus;
+pub mod frecency;
+pub mod project_actions;
+pub mod session_import;
+pub mod setup;
+pub mod stats;
+pub mod sticky_error;
+pub mod tree_nav;
 pub mod workspace;
@@ -0,0 +1,107 @@
+// `.` on a project — the built-in chores plus the `.gtrconfig` `action.name` entries, as
+// the one list its picker shows. Every entry runs at the project's main worktree.
+
+use crate::model::workspace::Project;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectAction {
+    /// `git pull` in the main worktree
+    PullMain,
+    /// what `c` on the project row does
+    CleanMerged,
+    /// what `!` does
+    MainShell,
+    /// `action.name = label:command`: `command` in a new session that exits with it
+    Run { label: String, command: String },
+}
+
+impl ProjectAction {
+    /// The picker row; `branch` is the project's default branch.
+    pub fn label(&self, branch: &str) -> String {
+        match self {
+            ProjectAction::PullMain => format!("Pull {}", branch),
+            ProjectAction::CleanMerged => "Clean merged worktrees".to_string(),
+            ProjectAction::MainShell => "Shell in the main worktree".to_string(),
+            ProjectAction::Run { label, command } => format!("{} · {}", label, command),
+        }
+    }
+}
+
+/// What the menu offers for `project`: nothing for a missing one, no pull or shell
+/// without a main worktree, then the configured entries in file order.
+pub fn project_actions(project: &Project) -> Vec<ProjectAction> {
+    if project.missing {
+        return vec![];
+    }
+    let has_main = project.worktrees.iter().any(|w| w.is_main);
+    let mut actions = Vec::new();
+    if has_main {
+        actions.push(ProjectAction::PullMain);
+    }
+    actions.push(ProjectAction::CleanMerged);
+    if has_main {
+        actions.push(ProjectAction::MainShell);
+        let configured = project.config.as_ref().map(|c| c.actions.as_slice()).unwrap_or_default();
+        actions.extend(
+            configured
+                .iter()
+                .map(|(label, command)| ProjectAction::Run { label: label.clone(), command: command.clone() }),
+        );
+    }
+    actions
+}
+
+/// tmux-friendly session name for a `Run` entry: "Seed DB" → "seed-db".
+pub fn run_session_name(label: &str) -> String {
+    let words: Vec<String> = label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() { "action".to_string() } else { words.join("-") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project_actions, run_session_name, ProjectAction};
+    use crate::model::fixtures;
+    use crate::model::workspace::{Project, ProjectConfig, WorktreeInfo};
+
+    fn project(with_main: bool, actions: &[(&str, &str)]) -> Project {
+        let main = WorktreeInfo { branch: "main".into(), is_main: true, ..fixtures::worktree("app") };
+        Project {
+            worktrees: if with_main { vec![main] } else { vec![] },
+            config: Some(ProjectConfig {
+                actions: actions.iter().map(|(l, c)| (l.to_string(), c.to_string())).collect(),
+                ..Default::default()
+            }),
+            ..fixtures::project("app")
+        }
+    }
+
+    #[test]
+    fn built_ins_then_configured_entries() {
+        let p = project(true, &[("Seed DB", "make seed"), ("Deploy", "./deploy.sh staging")]);
+        let actions = project_actions(&p);
+        assert_eq!(
+            actions,
+            [
+                ProjectAction::PullMain,
+                ProjectAction::CleanMerged,
+                ProjectAction::MainShell,
+                ProjectAction::Run { label: "Seed DB".into(), command: "make seed".into() },
+                ProjectAction::Run { label: "Deploy".into(), command: "./deploy.sh staging".into() },
+            ]
+        );
+        let labels: Vec<String> = actions.iter().map(|a| a.label(&p.default_branch)).collect();
+        assert_eq!(labels[0], "Pull main");
+        assert_eq!(labels[3], "Seed DB · make seed");
+
+        assert_eq!(project_actions(&project(false, &[("Seed DB", "make seed")])), [ProjectAction::CleanMerged]);
+        let missing = Project { missing: true, ..project(true, &[]) };
+        assert!(project_actions(&missing).is_empty());
+
+        assert_eq!(run_session_name("Seed DB: local"), "seed-db-local");
+        assert_eq!(run_session_name("!!"), "action");
+    }
+}
@@ -0,0 +1,88 @@
+// First run with nothing registered but tmux already full of sessions: the repositories
+// those sessions sit in are the projects to offer, so the tree starts out populated
+// instead of empty.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// A repository some tmux sessions run in, as `p` would register it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredProject {
+    /// the main worktree; a session in a linked worktree counts for its main repo
+    pub repo: PathBuf,
+    /// tmux names of the sessions found in it, in list order
+    pub sessions: Vec<String>,
+}
+
+/// Group `sessions` (name, start directory) by repository. `toplevel` is the worktree a
+/// directory is in (None outside git), `main_repo` the main repo of a linked worktree
+/// (None for a main one); each distinct directory is looked up once. Most sessions
+/// first, then by path.
+pub fn infer_projects(
+    sessions: &[(String, PathBuf)],
+    mut toplevel: impl FnMut(&Path) -> Option<PathBuf>,
+    mut main_repo: impl FnMut(&Path) -> Option<PathBuf>,
+) -> Vec<InferredProject> {
+    let mut repo_of: HashMap<&Path, Option<PathBuf>> = HashMap::new();
+    let mut groups: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for (name, dir) in sessions {
+        if dir.as_os_str().is_empty() {
+            continue;
+        }
+        let repo = repo_of.entry(dir.as_path()).or_insert_with(|| {
+            let top = toplevel(dir)?;
+            Some(main_repo(&top).unwrap_or(top))
+        });
+        if let Some(repo) = repo {
+            groups.entry(repo.clone()).or_default().push(name.clone());
+        }
+    }
+    let mut projects: Vec<InferredProject> =
+        groups.into_iter().map(|(repo, sessions)| InferredProject { repo, sessions }).collect();
+    projects.sort_by(|a, b| b.sessions.len().cmp(&a.sessions.len()).then_with(|| a.repo.cmp(&b.repo)));
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer_projects, InferredProject};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn groups_sessions_by_main_repo() {
+        let sessions: Vec<(String, PathBuf)> = [
+            ("notes", "/home/u/notes"),
+            ("api", "/code/api"),
+            ("api-tests", "/code/api/tests"),
+            ("api-feat", "/code/api-feat/src"),
+            ("web", "/code/web"),
+            ("scratch", "/tmp"),
+            ("detached", ""),
+        ]
+        .iter()
+        .map(|(n, d)| (n.to_string(), PathBuf::from(d)))
+        .collect();
+        let mut lookups = Vec::new();
+        let toplevel = |dir: &Path| {
+            lookups.push(dir.to_path_buf());
+            ["/code/api-feat", "/code/api", "/code/web"]
+                .iter()
+                .map(PathBuf::from)
+                .find(|top| dir.starts_with(top))
+        };
+        let main_repo = |top: &Path| (top == Path::new("/code/api-feat")).then(|| PathBuf::from("/code/api"));
+
+        let projects = infer_projects(&sessions, toplevel, main_repo);
+        assert_eq!(
+            projects,
+            [
+                InferredProject {
+                    repo: "/code/api".into(),
+                    sessions: vec!["api".into(), "api-tests".into(), "api-feat".into()],
+                },
+                InferredProject { repo: "/code/web".into(), sessions: vec!["web".into()] },
+            ]
+        );
+        assert_eq!(lookups.len(), 6, "one lookup per directory, none for an empty one");
+    }
+}
@@ -0,0 +1,60 @@
+// What setting up a worktree did — the env copy and the postCreate hook — kept per
+// worktree in the cache, so the preview can say whether the hook ran and how it went.
+
+use serde::{Deserialize, Serialize};
+
+/// Output lines a hook run keeps, from the end.
+pub const TAIL_LINES: usize = 20;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorktreeSetup {
+    /// files the env copy brought over or rendered from templates
+    pub env_files: usize,
+    /// the latest postCreate run; None when the project has no hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<HookRun>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HookRun {
+    pub command: String,
+    /// unix seconds it ended
+    pub ts: u64,
+    pub secs: u64,
+    /// why it failed (non-zero exit, cancelled); None when it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// the last `TAIL_LINES` non-blank output lines
+    #[serde(default)]
+    pub tail: Vec<String>,
+}
+
+impl HookRun {
+    /// Keep `line` if it says anything, dropping the oldest past `TAIL_LINES`.
+    pub fn push_line(tail: &mut Vec<String>, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        if tail.len() == TAIL_LINES {
+            tail.remove(0);
+        }
+        tail.push(line.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HookRun, TAIL_LINES};
+
+    #[test]
+    fn tail_keeps_the_last_lines_that_say_something() {
+        let mut tail = Vec::new();
+        for i in 0..TAIL_LINES + 5 {
+            HookRun::push_line(&mut tail, &format!("line {}", i));
+            HookRun::push_line(&mut tail, "   ");
+        }
+        assert_eq!(tail.len(), TAIL_LINES);
+        assert_eq!(tail[0], "line 5");
+        assert_eq!(tail.last().map(String::as_str), Some("line 24"));
+    }
+}
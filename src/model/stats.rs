@@ -0,0 +1,134 @@
+// Workspace-wide numbers for the `I` popup — only what wsx already holds; git info that
+// hasn't been read yet stays unknown rather than being gathered.
+
+use crate::model::workspace::{SessionInfo, SessionMark, WorkspaceState};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct WorkspaceStats {
+    pub projects: usize,
+    pub main_worktrees: usize,
+    pub linked_worktrees: usize,
+    pub sessions: SessionCounts,
+    /// worktrees whose git info has been read; the git numbers below cover only these
+    pub git_known: usize,
+    pub dirty: usize,
+    pub ahead: usize,
+    pub behind: usize,
+    /// "project › worktree" with the earliest session, and that session's creation time —
+    /// wsx doesn't know when a worktree itself was made
+    pub oldest: Option<(String, u64)>,
+}
+
+/// Sessions by the state their tree icon shows.
+#[derive(Debug, Default, PartialEq)]
+pub struct SessionCounts {
+    pub active: usize,
+    pub idle: usize,
+    pub muted: usize,
+    pub attention: usize,
+}
+
+impl SessionCounts {
+    pub fn total(&self) -> usize {
+        self.active + self.idle + self.muted + self.attention
+    }
+
+    /// By `SessionInfo::mark`, as the tree's icon shows it; a quiet app counts as attention.
+    fn count(&mut self, sess: &SessionInfo) {
+        let slot = match sess.mark() {
+            SessionMark::Muted => &mut self.muted,
+            SessionMark::Attention | SessionMark::Finished => &mut self.attention,
+            SessionMark::Active => &mut self.active,
+            SessionMark::Idle => &mut self.idle,
+        };
+        *slot += 1;
+    }
+}
+
+pub fn workspace_stats(workspace: &WorkspaceState) -> WorkspaceStats {
+    let mut stats = WorkspaceStats { projects: workspace.projects.len(), ..Default::default() };
+    for p in &workspace.projects {
+        for wt in &p.worktrees {
+            if wt.is_main {
+                stats.main_worktrees += 1;
+            } else {
+                stats.linked_worktrees += 1;
+            }
+            for sess in &wt.sessions {
+                stats.sessions.count(sess);
+            }
+            if let Some(info) = &wt.git_info {
+                stats.git_known += 1;
+                stats.dirty += usize::from(!info.modified_files.is_empty());
+                stats.ahead += info.ahead;
+                stats.behind += info.behind;
+            }
+            let first = wt.sessions.iter().map(|s| s.created_ts).filter(|&ts| ts > 0).min();
+            if let Some(ts) = first.filter(|&ts| stats.oldest.as_ref().is_none_or(|(_, old)| ts < *old)) {
+                stats.oldest = Some((format!("{} › {}", p.name, wt.display_name()), ts));
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{workspace_stats, SessionCounts, WorkspaceStats};
+    use crate::model::fixtures;
+    use crate::model::workspace::{GitInfo, Project, SessionInfo, WorkspaceState, WorktreeInfo};
+    use std::time::Instant;
+
+    fn worktree(name: &str, is_main: bool, sessions: Vec<SessionInfo>, git: Option<(usize, usize, bool)>) -> WorktreeInfo {
+        WorktreeInfo {
+            is_main,
+            sessions,
+            git_info: git.map(|(ahead, behind, dirty)| GitInfo {
+                modified_files: if dirty { vec!["a.rs".into()] } else { vec![] },
+                ahead,
+                behind,
+                ..Default::default()
+            }),
+            ..fixtures::worktree(name)
+        }
+    }
+
+    fn session(created_ts: u64, tweak: impl FnOnce(&mut SessionInfo)) -> SessionInfo {
+        let mut s = SessionInfo { created_ts, ..fixtures::session(&format!("s{}", created_ts)) };
+        tweak(&mut s);
+        s
+    }
+
+    #[test]
+    fn counts_what_is_known_and_leaves_the_rest_out() {
+        let project = |name: &str, worktrees| Project { worktrees, ..fixtures::project(name) };
+        let workspace = WorkspaceState {
+            projects: vec![
+                project("api", vec![
+                    worktree("api", true, vec![session(500, |_| {}), session(0, |s| s.muted = true)], Some((2, 0, true))),
+                    worktree("login", false, vec![session(300, |s| s.has_activity = true)], None),
+                ]),
+                project("web", vec![
+                    worktree("web", true, vec![session(400, |s| s.last_activity = Some(Instant::now()))], Some((1, 3, false))),
+                ]),
+                project("gone", vec![]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            workspace_stats(&workspace),
+            WorkspaceStats {
+                projects: 3,
+                main_worktrees: 2,
+                linked_worktrees: 1,
+                sessions: SessionCounts { active: 1, idle: 1, muted: 1, attention: 1 },
+                git_known: 2,
+                dirty: 1,
+                ahead: 3,
+                behind: 3,
+                oldest: Some(("api › login".into(), 300)),
+            }
+        );
+        assert_eq!(workspace_stats(&WorkspaceState::default()).oldest, None);
+    }
+}
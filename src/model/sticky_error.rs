@@ -0,0 +1,68 @@
+// Errors outlive the 4-second status line: the banner stays until Esc or a different
+// error replaces it, and the same error coming back (a refresh failing on every rescan)
+// bumps a count instead of flashing up anew.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StickyError {
+    pub message: String,
+    /// times `message` was reported in a row
+    pub count: usize,
+    /// unix seconds of the latest report
+    pub last: u64,
+}
+
+impl StickyError {
+    /// Put `message` in `slot`, counting it when it's the one already there.
+    pub fn report(slot: &mut Option<StickyError>, message: String, now: u64) {
+        match slot {
+            Some(current) if current.message == message => {
+                current.count += 1;
+                current.last = now;
+            }
+            _ => *slot = Some(StickyError { message, count: 1, last: now }),
+        }
+    }
+
+    /// The banner line: "refresh failing for project api: … (x12, last 14:03)".
+    pub fn text(&self) -> String {
+        if self.count > 1 {
+            format!("{} (x{}, last {})", self.message, self.count, clock(self.last))
+        } else {
+            self.message.clone()
+        }
+    }
+}
+
+/// `secs` as local `HH:MM`.
+fn clock(secs: u64) -> String {
+    let t = secs as libc::time_t;
+    // SAFETY: `tm` is plain data that localtime_r fills in; both pointers are valid.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+        return format!("{:02}:{:02}", secs / 3600 % 24, secs / 60 % 60);
+    }
+    format!("{:02}:{:02}", tm.tm_hour, tm.tm_min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StickyError;
+
+    #[test]
+    fn repeats_count_and_a_different_error_starts_over() {
+        let mut slot = None;
+        StickyError::report(&mut slot, "refresh failing for project api: boom".into(), 100);
+        assert_eq!(slot.as_ref().unwrap().text(), "refresh failing for project api: boom");
+        for now in 101..112 {
+            StickyError::report(&mut slot, "refresh failing for project api: boom".into(), now);
+        }
+        let banner = slot.as_ref().unwrap();
+        assert_eq!((banner.count, banner.last), (12, 111));
+        let text = banner.text();
+        assert!(text.starts_with("refresh failing for project api: boom (x12, last "), "{}", text);
+        assert_eq!(text.len() - text.rfind("last ").unwrap(), "last 00:00)".len());
+
+        StickyError::report(&mut slot, "pull failed: no upstream".into(), 200);
+        assert_eq!(slot.map(|e| (e.message, e.count)), Some(("pull failed: no upstream".into(), 1)));
+    }
+}
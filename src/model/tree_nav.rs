@@ -0,0 +1,247 @@
+// Cursor math for the flattened tree — which row a key lands on, given the rows and what
+// is selected. App owns `tree_selected` / `tree_scroll` and calls these; nothing here
+// mutates. Moves that expand a parent first name their target by identity and look the
+// row up again after the rebuild, since expanding shifts every row below it.
+
+use crate::model::workspace::{FlatEntry, Selection, WorkspaceState};
+use std::time::Instant;
+
+/// (project, worktree, session) indices — a session's identity across flat rebuilds.
+pub type SessionId = (usize, usize, usize);
+
+/// One row up or down, stopping at either end.
+pub fn step(selected: usize, len: usize, dir: isize) -> usize {
+    if dir < 0 {
+        selected.saturating_sub(1)
+    } else {
+        clamp(selected + 1, len)
+    }
+}
+
+/// `selected` pulled back onto the tree after rows went away.
+pub fn clamp(selected: usize, len: usize) -> usize {
+    selected.min(len.saturating_sub(1))
+}
+
+pub fn row_of(flat: &[FlatEntry], entry: &FlatEntry) -> Option<usize> {
+    flat.iter().position(|e| e == entry)
+}
+
+/// Row for selection `sel`, moved under project `pi` — its index may have changed since.
+pub fn entry_in(pi: usize, sel: &Selection) -> FlatEntry {
+    match *sel {
+        Selection::Worktree(_, wi) => FlatEntry::Worktree { project_idx: pi, worktree_idx: wi },
+        Selection::Session(_, wi, si) => FlatEntry::Session { project_idx: pi, worktree_idx: wi, session_idx: si },
+        Selection::Archive(_, None) => FlatEntry::ArchiveHeader { project_idx: pi },
+        Selection::Archive(_, Some(ai)) => FlatEntry::Archived { project_idx: pi, archive_idx: ai },
+        Selection::Project(_) | Selection::None => FlatEntry::Project { idx: pi },
+    }
+}
+
+/// Row of the entry `entry` sits under: a window's session, a session's worktree, a
+/// worktree's or the archive header's project, an archive tag's header.
+pub fn parent_row(flat: &[FlatEntry], entry: &FlatEntry) -> Option<usize> {
+    let parent = match *entry {
+        FlatEntry::Project { .. } => return None,
+        FlatEntry::Worktree { project_idx, .. } | FlatEntry::ArchiveHeader { project_idx } => {
+            FlatEntry::Project { idx: project_idx }
+        }
+        FlatEntry::Session { project_idx, worktree_idx, .. } => FlatEntry::Worktree { project_idx, worktree_idx },
+        FlatEntry::Window { project_idx, worktree_idx, session_idx, .. } => {
+            FlatEntry::Session { project_idx, worktree_idx, session_idx }
+        }
+        FlatEntry::Archived { project_idx, .. } => FlatEntry::ArchiveHeader { project_idx },
+    };
+    row_of(flat, &parent)
+}
+
+/// `]` / `[`: the next project row below `selected`, or the previous one above it.
+pub fn jump_project(flat: &[FlatEntry], selected: usize, dir: isize) -> Option<usize> {
+    let is_project = |&(_, e): &(usize, &FlatEntry)| matches!(e, FlatEntry::Project { .. });
+    if dir > 0 {
+        flat.iter().enumerate().skip(selected + 1).find(is_project).map(|(i, _)| i)
+    } else {
+        flat.iter().enumerate().take(selected).rev().find(is_project).map(|(i, _)| i)
+    }
+}
+
+/// First of `rows` (ascending) below `selected`, wrapping to the top.
+pub fn next_after(rows: &[usize], selected: usize) -> Option<usize> {
+    rows.iter().copied().find(|&r| r > selected).or_else(|| rows.first().copied())
+}
+
+/// The entry after `current` in `ring` (before it with a negative `dir`), wrapping. Off the
+/// ring, forward starts at the first and backward at the last.
+pub fn cycle<T: Copy + PartialEq>(ring: &[T], current: Option<T>, dir: isize) -> Option<T> {
+    let n = ring.len();
+    if n == 0 {
+        return None;
+    }
+    let at = current.and_then(|c| ring.iter().position(|&r| r == c));
+    let i = match at {
+        Some(pos) if dir >= 0 => (pos + 1) % n,
+        Some(pos) => (pos + n - 1) % n,
+        None if dir >= 0 => 0,
+        None => n - 1,
+    };
+    Some(ring[i])
+}
+
+/// Every session in tree order as if all were expanded; hidden worktrees only while shown.
+pub fn tree_sessions(workspace: &WorkspaceState) -> Vec<SessionId> {
+    let mut ids = Vec::new();
+    for (pi, project) in workspace.projects.iter().enumerate() {
+        for wi in project.worktree_order(workspace.main_first) {
+            let wt = &project.worktrees[wi];
+            if wt.hidden && !workspace.show_hidden {
+                continue;
+            }
+            ids.extend(workspace.session_order(wt).into_iter().map(|si| (pi, wi, si)));
+        }
+    }
+    ids
+}
+
+/// `n` / `N` order: sessions needing attention, the longest waiting first — including
+/// those under collapsed rows. Not yet stamped sorts last; ties keep tree order.
+pub fn attention_ring(workspace: &WorkspaceState) -> Vec<SessionId> {
+    let mut waiting: Vec<(Option<Instant>, usize, SessionId)> = tree_sessions(workspace)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(order, (pi, wi, si))| {
+            let sess = workspace.session(pi, wi, si)?;
+            sess.needs_attention().then_some((sess.attention_since, order, (pi, wi, si)))
+        })
+        .collect();
+    waiting.sort_by_key(|&(since, order, _)| (since.is_none(), since, order));
+    waiting.into_iter().map(|(_, _, id)| id).collect()
+}
+
+/// Scroll offset keeping `selected` visible: the view moves up once the cursor is in its
+/// top quarter and down once past three quarters, so there's always some lookahead.
+pub fn compute_scroll(selected: usize, visible_height: usize, current_offset: usize) -> usize {
+    let up_pad = (visible_height / 4).max(1);
+    let down_pad = (visible_height * 3 / 4).max(1);
+    if selected < current_offset + up_pad {
+        selected.saturating_sub(up_pad - 1)
+    } else if selected >= current_offset + down_pad {
+        selected.saturating_sub(down_pad - 1)
+    } else {
+        current_offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures;
+    use crate::model::workspace::{flatten_tree, Project, SessionInfo, WorktreeInfo};
+    use std::time::Duration;
+
+    fn session(name: &str, waiting_secs: Option<u64>) -> SessionInfo {
+        let mut sess = SessionInfo {
+            has_running_app: waiting_secs.is_some(),
+            attention_since: waiting_secs.map(|s| Instant::now() - Duration::from_secs(s)),
+            ..fixtures::session(name)
+        };
+        sess.track_attention();
+        sess
+    }
+
+    fn worktree(name: &str, sessions: Vec<SessionInfo>) -> WorktreeInfo {
+        WorktreeInfo { sessions, ..fixtures::worktree(name) }
+    }
+
+    fn project(name: &str, worktrees: Vec<WorktreeInfo>) -> Project {
+        Project { worktrees, ..fixtures::project(name) }
+    }
+
+    /// Rows: 0 api, 1 api, 2 s1, 3 login, 4 s2 (waiting 10s), 5 web, 6 web, 7 s3 (waiting 60s)
+    fn workspace() -> WorkspaceState {
+        WorkspaceState {
+            projects: vec![
+                project("api", vec![
+                    worktree("api", vec![session("s1", None)]),
+                    worktree("login", vec![session("s2", Some(10))]),
+                ]),
+                project("web", vec![worktree("web", vec![session("s3", Some(60))])]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn steps_and_clamps_within_the_tree() {
+        assert_eq!(step(0, 3, -1), 0);
+        assert_eq!(step(1, 3, 1), 2);
+        assert_eq!(step(2, 3, 1), 2, "stops on the last row");
+        assert_eq!(step(0, 0, 1), 0, "an empty tree stays at 0");
+        assert_eq!(clamp(7, 5), 4, "rows removed under the cursor");
+        assert_eq!(clamp(3, 5), 3);
+        assert_eq!(clamp(3, 0), 0);
+    }
+
+    #[test]
+    fn jumps_between_projects_and_to_parents() {
+        let flat = flatten_tree(&workspace());
+        assert_eq!(jump_project(&flat, 0, 1), Some(5));
+        assert_eq!(jump_project(&flat, 4, 1), Some(5));
+        assert_eq!(jump_project(&flat, 5, 1), None, "no project below the last");
+        assert_eq!(jump_project(&flat, 7, -1), Some(5));
+        assert_eq!(jump_project(&flat, 5, -1), Some(0));
+        assert_eq!(jump_project(&flat, 0, -1), None);
+
+        assert_eq!(parent_row(&flat, &flat[4]), Some(3), "session → its worktree");
+        assert_eq!(parent_row(&flat, &flat[3]), Some(0), "worktree → its project");
+        assert_eq!(parent_row(&flat, &flat[0]), None);
+        let window = FlatEntry::Window { project_idx: 1, worktree_idx: 0, session_idx: 0, window_idx: 1 };
+        assert_eq!(parent_row(&flat, &window), Some(7));
+        assert_eq!(row_of(&flat, &entry_in(1, &Selection::Session(0, 0, 0))), Some(7), "re-resolved under a moved project");
+    }
+
+    #[test]
+    fn attention_reaches_sessions_under_collapsed_parents() {
+        let mut ws = workspace();
+        ws.projects[0].worktrees[1].expanded = false;
+        ws.projects[1].expanded = false;
+        let flat = flatten_tree(&ws);
+        assert_eq!(flat.len(), 5, "neither waiting session has a row");
+
+        let ring = attention_ring(&ws);
+        assert_eq!(ring, [(1, 0, 0), (0, 1, 0)], "longest waiting first");
+        assert_eq!(cycle(&ring, None, 1), Some((1, 0, 0)));
+        assert_eq!(cycle(&ring, None, -1), Some((0, 1, 0)));
+        assert_eq!(cycle(&ring, Some((1, 0, 0)), 1), Some((0, 1, 0)));
+        assert_eq!(cycle(&ring, Some((0, 1, 0)), 1), Some((1, 0, 0)), "wraps");
+        assert_eq!(cycle(&ring, Some((0, 0, 0)), 1), Some((1, 0, 0)), "off the ring starts over");
+        assert_eq!(cycle::<SessionId>(&[], None, 1), None);
+
+        ws.projects[0].worktrees[1].hidden = true;
+        assert_eq!(attention_ring(&ws), [(1, 0, 0)], "hidden worktrees stay out of reach");
+        ws.show_hidden = true;
+        assert_eq!(attention_ring(&ws).len(), 2);
+    }
+
+    #[test]
+    fn next_after_wraps_to_the_top() {
+        assert_eq!(next_after(&[2, 5, 9], 5), Some(9));
+        assert_eq!(next_after(&[2, 5, 9], 9), Some(2));
+        assert_eq!(next_after(&[], 0), None);
+    }
+
+    #[test]
+    fn scroll_keeps_lookahead_at_the_edges() {
+        // 20 rows visible: scrolls up within the top 5, down past row 15.
+        assert_eq!(compute_scroll(0, 20, 0), 0);
+        assert_eq!(compute_scroll(14, 20, 0), 0);
+        assert_eq!(compute_scroll(15, 20, 0), 1, "5 rows of lookahead below the cursor");
+        assert_eq!(compute_scroll(40, 20, 0), 26, "a jump lands the cursor at the 3/4 line");
+        assert_eq!(compute_scroll(30, 20, 26), 26);
+        assert_eq!(compute_scroll(30, 20, 27), 26, "4 rows of lookahead above");
+        assert_eq!(compute_scroll(2, 20, 26), 0, "never above the top");
+        // Tiny panes still follow the cursor.
+        assert_eq!(compute_scroll(5, 1, 0), 5);
+        assert_eq!(compute_scroll(3, 1, 5), 3);
+        assert_eq!(compute_scroll(3, 0, 0), 3);
+    }
+}
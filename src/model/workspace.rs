@@ -1,18 +1,53 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone)]
+use crate::model::attention::{next_reasons, AttentionSet, Poll};
+use crate::model::attach_log::AttachLog;
+use crate::model::ended::EndedLog;
+use crate::model::frecency::Frecency;
+use crate::model::setup::WorktreeSetup;
+use crate::ops::IDLE_SECS;
+use crate::tmux::naming;
+
+#[derive(Debug, Clone, Default)]
 pub struct WorkspaceState {
     pub projects: Vec<Project>,
+    /// recent session attaches, kept in the cache
+    pub attaches: AttachLog,
+    /// sessions that went away, with their last capture; kept in the cache
+    pub ended: EndedLog,
+    /// worktree path → what its env copy and postCreate hook did; kept in the cache
+    pub setup: HashMap<PathBuf, WorktreeSetup>,
+    /// list `hidden` worktrees (dimmed) instead of leaving them out; not saved
+    pub show_hidden: bool,
+    /// the main worktree stays first, above pinned ones (`main_worktree = "top"`)
+    pub main_first: bool,
+    /// list sessions most recently attached first (`session_order = "recent"`)
+    pub recent_sessions: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Project {
     pub name: String,
     pub path: PathBuf,
     pub default_branch: String,
+    /// `default_branch` comes from the config, not detection
+    pub default_branch_override: bool,
+    /// the registered path is gone (moved or renamed); shown as a placeholder row
+    /// with no worktrees until it's relocated or removed
+    pub missing: bool,
+    /// drawn from the startup cache; its live git data (worktrees, default branch,
+    /// .gtrconfig) is still loading in the background
+    pub syncing: bool,
     pub worktrees: Vec<WorktreeInfo>,
     pub config: Option<ProjectConfig>,
     pub expanded: bool,
+    /// attach/create usage, drives `ProjectOrder::Frecency`
+    pub frecency: Frecency,
+    /// archive tags, newest first; listed under an "Archived" row after the worktrees
+    pub archived: Vec<ArchivedWorktree>,
+    /// the "Archived" row is open (collapsed on every start)
+    pub archived_expanded: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -20,9 +55,62 @@ pub struct ProjectConfig {
     pub post_create: Option<String>,
     pub copy_includes: Vec<String>,
     pub copy_excludes: Vec<String>,
+    /// `copy.overwrite = true`: replace files that already exist in the new worktree
+    pub copy_overwrite: bool,
+    /// `session.env = NAME=value`, set in every session created for the project;
+    /// values may use `{worktree_path}` and `{branch}`
+    pub session_env: Vec<(String, String)>,
+    /// `session.defaultCommand`: what the `dev` session started by `Space` runs
+    pub default_command: Option<String>,
+    /// `template.file = src:dest`: `src` (in the main worktree) is rendered to `dest`
+    /// in each new worktree, with `{{branch}}`, `{{slug}}`, `{{worktree_path}}`, `{{port}}`
+    pub templates: Vec<(String, String)>,
+    /// `template.portRange = 4000-4999`: where `{{port}}` is picked from
+    pub template_ports: Option<(u16, u16)>,
+    /// `alias.pattern`: regex over the branch name; its `alias` group (or first group)
+    /// names worktrees that have no alias in the config
+    pub alias_pattern: Option<regex::Regex>,
+    /// `alias.pattern` didn't compile — reported when the project loads
+    pub alias_pattern_error: Option<String>,
+    /// `worktree.hide = ci-cache-*`: globs over branch or directory name for worktrees
+    /// wsx leaves out of the tree and never removes
+    pub worktree_hide: Vec<String>,
+    /// `action.name = label:command`: extra entries for the `.` project menu, run in a
+    /// session at the main worktree that ends with the command
+    pub actions: Vec<(String, String)>,
+    /// key → how many of its values (the leading ones) come from the global gtrconfig
+    pub from_global: HashMap<&'static str, usize>,
 }
 
-#[derive(Debug, Clone)]
+impl ProjectConfig {
+    pub fn template_ports(&self) -> (u16, u16) {
+        self.template_ports.unwrap_or((3000, 3999))
+    }
+
+    /// Alias `alias.pattern` derives from `branch`, if it matches.
+    pub fn pattern_alias(&self, branch: &str) -> Option<String> {
+        let caps = self.alias_pattern.as_ref()?.captures(branch)?;
+        let m = caps.name("alias").or_else(|| caps.get(1)).unwrap_or_else(|| caps.get(0).unwrap());
+        Some(m.as_str().to_string()).filter(|a| !a.is_empty())
+    }
+
+    /// How many of `key`'s values are inherited from the global gtrconfig; for a
+    /// single-valued key, 1 when its value is.
+    pub fn inherited(&self, key: &str) -> usize {
+        self.from_global.get(key).copied().unwrap_or(0)
+    }
+
+    /// A linked worktree on `branch` at `path` matches a `worktree.hide` glob.
+    pub fn hides_worktree(&self, branch: &str, path: &Path) -> bool {
+        let dir = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        self.worktree_hide
+            .iter()
+            .filter_map(|p| glob::Pattern::new(p).ok())
+            .any(|p| p.matches(branch) || p.matches(&dir))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct SessionInfo {
     pub name: String,         // full tmux session name
     pub display_name: String, // shown in UI (strips wt_slug prefix)
@@ -32,23 +120,244 @@ pub struct SessionInfo {
     pub has_running_app: bool, // foreground process is not a bare shell
     pub running_app_suppressed: bool, // user dismissed the running-app notification
     pub muted: bool,           // user silenced — no activity updates, shown as ⊘
+    pub commands: Vec<String>, // non-shell foreground commands, from the activity snapshot
+    pub windows: Vec<WindowInfo>, // listed under the session in the tree when there are 2+
+    pub pinned: bool,          // listed first under its worktree, shown with ★
+    pub created_ts: u64,       // unix seconds the tmux session was created, 0 if unknown
+    pub attention_since: Option<std::time::Instant>, // when needs_attention() last became true
+    pub origin: SessionOrigin, // where and how it was started, for the preview header
+    pub bell_ack: Option<u64>, // unix seconds the bell was last acknowledged (x or attach)
+    pub bell_since: Option<u64>, // unix seconds wsx first saw tmux's current bell flag up
+    pub attention: AttentionSet, // why it wants a look, re-derived at every poll
 }
 
-#[derive(Debug, Clone)]
+/// A session's state as its tree marker shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMark {
+    /// tmux bell
+    Attention,
+    /// output within the last `IDLE_SECS`
+    Active,
+    /// an app is open but has gone quiet — done, or waiting for input
+    Finished,
+    Idle,
+    /// no activity tracking
+    Muted,
+}
+
+impl SessionMark {
+    pub const ALL: [SessionMark; 5] = [
+        SessionMark::Attention,
+        SessionMark::Active,
+        SessionMark::Finished,
+        SessionMark::Idle,
+        SessionMark::Muted,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SessionMark::Attention => "bell — needs attention",
+            SessionMark::Active => "output in the last few seconds",
+            SessionMark::Finished => "app open but quiet — finished or waiting",
+            SessionMark::Idle => "idle",
+            SessionMark::Muted => "muted (x)",
+        }
+    }
+}
+
+/// The bell after a poll at `now` that found tmux's flag `up`: (since, ack, unacknowledged).
+/// The bell dates from when the flag went up, so output after an acknowledgment doesn't
+/// ring it again; once tmux drops the flag (the windows were visited) the ack has done its
+/// job. An ack restored from the cache with the flag still up is taken to be for this bell.
+pub fn poll_bell(up: bool, since: Option<u64>, ack: Option<u64>, now: u64) -> (Option<u64>, Option<u64>, bool) {
+    if !up {
+        return (None, None, false);
+    }
+    let since = since.or(ack).unwrap_or(now);
+    (Some(since), ack, ack.is_none_or(|ack| since > ack))
+}
+
+impl SessionInfo {
+    /// Muting also drops whatever was latched (a bell, a quiet app) so ● clears at once;
+    /// unmuting picks the live state up again on the next activity poll.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        if muted {
+            self.has_activity = false;
+            self.has_running_app = false;
+            self.running_app_suppressed = false;
+            self.last_activity = None;
+            self.attention_since = None;
+            self.attention = AttentionSet::default();
+        }
+    }
+
+    /// The current bell has been seen: ● clears until tmux raises the bell flag again.
+    pub fn acknowledge_bell(&mut self, now: u64) {
+        self.bell_ack = Some(now);
+        self.has_activity = false;
+        self.track_attention();
+    }
+
+    /// A quiet or finished app the user hasn't dismissed — what `n` / `N` jump between.
+    pub fn needs_attention(&self) -> bool {
+        self.attention.waiting()
+    }
+
+    /// The display name, and apart from it the `_N` wsx added because the name it was
+    /// asked for was taken: ("dev", Some(2)) for `dev_2` made as `dev`. A `_N` the user
+    /// chose ("build_2") is part of the name.
+    pub fn name_parts(&self) -> (&str, Option<u32>) {
+        match naming::split_suffix(&self.name) {
+            (base, Some(n)) if self.origin.made_as.as_deref() == Some(base) => {
+                let shown = self.display_name.strip_suffix(&format!("_{}", n)).unwrap_or(&self.display_name);
+                (shown, Some(n))
+            }
+            _ => (&self.display_name, None),
+        }
+    }
+
+    /// "dev ·2" for `dev_2`, for titles where the suffix can't be dimmed as in the tree.
+    pub fn shown_name(&self) -> String {
+        match self.name_parts() {
+            (name, Some(n)) => format!("{} ·{}", name, n),
+            (name, None) => name.to_string(),
+        }
+    }
+
+    /// The state its tree marker shows, most urgent first: ⊘, a bell, output, a quiet app.
+    pub fn mark(&self) -> SessionMark {
+        let active = self.last_activity.is_some_and(|t| t.elapsed().as_secs() < IDLE_SECS);
+        if self.muted {
+            SessionMark::Muted
+        } else if self.has_activity {
+            SessionMark::Attention
+        } else if active {
+            SessionMark::Active
+        } else if self.needs_attention() {
+            SessionMark::Finished
+        } else {
+            SessionMark::Idle
+        }
+    }
+
+    /// Re-derive the reasons from the current state after a change between polls.
+    pub fn track_attention(&mut self) {
+        self.observe(self.has_running_app, false);
+    }
+
+    /// Re-derive the reasons after an activity poll — `was_running` is whether an app was
+    /// in the foreground before it — then start the waiting clock when attention is first
+    /// needed, or stop it once it isn't.
+    pub fn observe(&mut self, was_running: bool, attached: bool) {
+        let poll = Poll {
+            muted: self.muted,
+            bell: self.has_activity,
+            app: self.has_running_app.then(|| self.commands.first().map_or("app", String::as_str)),
+            active: self.last_activity.is_some_and(|t| t.elapsed().as_secs() < IDLE_SECS),
+            app_dismissed: self.running_app_suppressed,
+            was_running,
+            attached,
+        };
+        self.attention = next_reasons(&self.attention, poll);
+        if self.needs_attention() {
+            self.attention_since.get_or_insert_with(std::time::Instant::now);
+        } else {
+            self.attention_since = None;
+        }
+    }
+
+    /// Seconds since the session was created, if tmux reported it.
+    pub fn age_secs(&self, now: u64) -> Option<u64> {
+        (self.created_ts > 0).then(|| now.saturating_sub(self.created_ts))
+    }
+}
+
+/// Where a session started and with what — what attaching would land in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionOrigin {
+    /// tmux's session_path
+    pub start_dir: Option<PathBuf>,
+    /// the active pane's cwd, when it has moved away from `start_dir`
+    pub current_dir: Option<PathBuf>,
+    /// the command wsx sent at creation; None for plain shells and sessions made elsewhere
+    pub init_command: Option<String>,
+    /// the tmux name wsx was asked to create it under; None when wsx didn't make it
+    pub made_as: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub index: u32,
+    /// `#{window_id}` ("@3"): unlike the index, kept while windows are moved or renumbered
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct WorktreeInfo {
     pub name: String,
     pub branch: String,
     pub path: PathBuf,
     pub is_main: bool,
+    /// checked out at a tag or commit; `branch` holds that ref's name instead
+    pub detached: bool,
     pub alias: Option<String>,
+    /// `alias` came from the project's `alias.pattern`, not the config
+    pub alias_derived: bool,
     pub sessions: Vec<SessionInfo>,
     pub expanded: bool,
     pub git_info: Option<GitInfo>,
     pub fetch_failed: bool,
+    /// last fetch attempt, successful or not (throttles the background fetch)
     pub last_fetched: Option<std::time::Instant>,
+    /// last fetch that worked — how fresh ahead/behind is
+    pub last_fetch_ok: Option<std::time::Instant>,
+    /// listed first under its project, shown with ★
+    pub pinned: bool,
+    /// `git worktree lock` reason ("" for none); remove and clean refuse it while set
+    pub locked: Option<String>,
+    /// matched by the project's `worktree.hide`: out of the tree unless revealed (`H`)
+    pub hidden: bool,
+    /// free text set with `#` ("waiting on review"), kept in the cache
+    pub note: Option<String>,
+    /// linked worktree outside the `{repo}-{slug}` layout wsx creates — made by hand
+    pub external: bool,
+}
+
+impl Project {
+    /// Worktree indices in display order: pinned first, each group in git's order —
+    /// which starts with the main worktree. `main_first` keeps it ahead of the pins too.
+    pub fn worktree_order(&self, main_first: bool) -> Vec<usize> {
+        pinned_first(&self.worktrees, |w| w.pinned || (main_first && w.is_main))
+    }
+
+    /// Another worktree that `alias` on worktree `wi` would be indistinguishable from:
+    /// one with the same alias, or whose directory or branch slug matches it.
+    pub fn alias_conflict(&self, wi: usize, alias: &str) -> Option<&WorktreeInfo> {
+        let slug = sanitize_slug(alias);
+        self.worktrees
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != wi)
+            .map(|(_, w)| w)
+            .find(|w| {
+                let taken = [
+                    w.alias.as_deref().map(sanitize_slug),
+                    Some(canonical_session_slug(&self.name, &w.path)),
+                    Some(legacy_branch_slug(&w.branch)),
+                ];
+                taken.into_iter().flatten().any(|t| t.eq_ignore_ascii_case(&slug))
+            })
+    }
 }
 
 impl WorktreeInfo {
+    /// Session indices in display order: pinned first, each group in the manual order.
+    pub fn session_order(&self) -> Vec<usize> {
+        pinned_first(&self.sessions, |s| s.pinned)
+    }
+
     pub fn display_name(&self) -> &str {
         self.alias.as_deref().unwrap_or(&self.name)
     }
@@ -58,6 +367,14 @@ impl WorktreeInfo {
     }
 }
 
+/// Pins float to the top without touching the underlying order, so unpinning
+/// puts an item straight back where it was.
+fn pinned_first<T>(items: &[T], pinned: impl Fn(&T) -> bool) -> Vec<usize> {
+    let (mut order, rest): (Vec<usize>, Vec<usize>) = (0..items.len()).partition(|&i| pinned(&items[i]));
+    order.extend(rest);
+    order
+}
+
 fn sanitize_slug(raw: &str) -> String {
     raw.replace(|c: char| !c.is_alphanumeric() && c != '-' && c != '_', "-")
 }
@@ -76,6 +393,29 @@ pub fn canonical_session_slug(project_name: &str, worktree_path: &Path) -> Strin
     sanitize_slug(short_name)
 }
 
+/// Six hex digits that stay the same for `path` across runs and builds (FNV-1a), to tell
+/// apart projects that share a name.
+pub fn path_tag(path: &Path) -> String {
+    let hash = path
+        .to_string_lossy()
+        .bytes()
+        .fold(0x811c_9dc5u32, |h, b| (h ^ u32::from(b)).wrapping_mul(0x0100_0193));
+    format!("{:06x}", hash & 0xff_ffff)
+}
+
+/// `tmux_name` after `{project}-`, or after `{project}_{tag}-` as sessions of projects
+/// that share a name get (`WorkspaceState::session_prefix`).
+fn after_project_prefix<'a>(tmux_name: &'a str, project_name: &str) -> Option<&'a str> {
+    let rest = tmux_name.strip_prefix(project_name)?;
+    if let Some(rest) = rest.strip_prefix('-') {
+        return Some(rest);
+    }
+    let tagged = rest.strip_prefix('_')?;
+    let (tag, rest) = tagged.split_at_checked(6)?;
+    tag.bytes().all(|b| b.is_ascii_hexdigit()).then_some(())?;
+    rest.strip_prefix('-')
+}
+
 pub fn session_display_name_from_tmux(
     tmux_name: &str,
     project_name: &str,
@@ -83,42 +423,88 @@ pub fn session_display_name_from_tmux(
     branch: &str,
     alias: Option<&str>,
 ) -> String {
-    let canonical = format!(
-        "{}-{}-",
-        project_name,
-        canonical_session_slug(project_name, worktree_path)
-    );
-    if let Some(rest) = tmux_name.strip_prefix(&canonical) {
-        return rest.to_string();
-    }
-
+    let Some(rest) = after_project_prefix(tmux_name, project_name) else {
+        return tmux_name.to_string();
+    };
     // Backward compatibility: older builds prefixed by branch/alias slug.
-    let legacy_branch = format!("{}-{}-", project_name, legacy_branch_slug(branch));
-    if let Some(rest) = tmux_name.strip_prefix(&legacy_branch) {
-        return rest.to_string();
-    }
-
-    if let Some(alias) = alias {
-        let legacy_alias = format!("{}-{}-", project_name, sanitize_slug(alias));
-        if let Some(rest) = tmux_name.strip_prefix(&legacy_alias) {
-            return rest.to_string();
+    let slugs = [
+        Some(canonical_session_slug(project_name, worktree_path)),
+        Some(legacy_branch_slug(branch)),
+        alias.map(sanitize_slug),
+    ];
+    for slug in slugs.into_iter().flatten() {
+        if let Some(display) = rest.strip_prefix(&slug).and_then(|r| r.strip_prefix('-')) {
+            return display.to_string();
         }
     }
 
     // Last-resort compatibility for historical `{project}-{any_slug}-{display}` names.
-    if let Some(rest) = tmux_name.strip_prefix(&format!("{}-", project_name)) {
-        if let Some((_, display)) = rest.split_once('-') {
-            return display.to_string();
-        }
+    match rest.split_once('-') {
+        Some((_, display)) => display.to_string(),
+        None => tmux_name.to_string(),
     }
-
-    tmux_name.to_string()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{canonical_session_slug, session_display_name_from_tmux};
-    use std::path::Path;
+    use super::{
+        canonical_session_slug, flatten_tree, session_display_name_from_tmux, FlatEntry, Project, SessionInfo,
+        SessionOrigin, WorkspaceState, WorktreeInfo,
+    };
+    use crate::model::fixtures;
+    use std::path::{Path, PathBuf};
+
+    fn worktree(path: &str, sessions: &[&str]) -> WorktreeInfo {
+        WorktreeInfo {
+            path: PathBuf::from(path),
+            sessions: sessions.iter().map(|s| fixtures::session(s)).collect(),
+            ..fixtures::worktree(path.rsplit('/').next().unwrap())
+        }
+    }
+
+    #[test]
+    fn finds_worktrees_and_sessions_across_projects() {
+        let project = |name: &str, worktrees| Project { worktrees, expanded: false, ..fixtures::project(name) };
+        let mut workspace = WorkspaceState {
+            projects: vec![
+                project("a", vec![worktree("/nonexistent/a", &["a-1"])]),
+                project("b", vec![worktree("/nonexistent/b", &[]), worktree("/nonexistent/b-x", &["b-x-1", "b-x-2"])]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(workspace.find_worktree(Path::new("/nonexistent/b-x")), Some((1, 1)));
+        assert_eq!(workspace.find_worktree(Path::new("/nonexistent/c")), None);
+        assert_eq!(workspace.find_session("b-x-2"), Some((1, 1, 1)));
+        assert_eq!(workspace.find_session("b-x"), None);
+
+        // Pins reorder the flat tree but never the indices it points at.
+        workspace.projects[1].expanded = true;
+        workspace.projects[1].worktrees[1].sessions[1].pinned = true;
+        let flat = flatten_tree(&workspace);
+        let pos = |e: FlatEntry| flat.iter().position(|f| *f == e);
+        assert_eq!(pos(FlatEntry::Session { project_idx: 1, worktree_idx: 1, session_idx: 1 }), Some(4));
+        assert_eq!(pos(FlatEntry::Session { project_idx: 1, worktree_idx: 1, session_idx: 0 }), Some(5));
+
+        // A pinned worktree goes above the main one unless `main_first` keeps it on top.
+        workspace.projects[1].worktrees[0].is_main = true;
+        workspace.projects[1].worktrees[1].pinned = true;
+        assert_eq!(workspace.projects[1].worktree_order(false), [1, 0]);
+        assert_eq!(workspace.projects[1].worktree_order(true), [0, 1]);
+        workspace.main_first = true;
+        assert_eq!(flatten_tree(&workspace)[2], FlatEntry::Worktree { project_idx: 1, worktree_idx: 0 });
+
+        // `recent_sessions` sorts the view by last attach, under the pins; `sessions` keeps its order.
+        workspace.recent_sessions = true;
+        workspace.attaches.record("b-x-1", 100);
+        let wt = &workspace.projects[1].worktrees[1];
+        assert_eq!(workspace.session_order(wt), [1, 0], "the pin stays on top");
+        workspace.projects[1].worktrees[1].sessions[1].pinned = false;
+        let wt = &workspace.projects[1].worktrees[1];
+        assert_eq!(workspace.session_order(wt), [0, 1]);
+        workspace.attaches.record("b-x-2", 200);
+        assert_eq!(workspace.session_order(wt), [1, 0]);
+        assert_eq!(wt.sessions[0].name, "b-x-1");
+    }
 
     #[test]
     fn canonical_slug_uses_worktree_dir_for_main() {
@@ -168,6 +554,19 @@ mod tests {
         assert_eq!(display, "agent");
     }
 
+    #[test]
+    fn display_name_survives_alias_change() {
+        // The tmux prefix comes from the worktree dir, so setting an alias after
+        // the session exists must not change how its name is stripped.
+        let path = Path::new("/tmp/wsx-feature-auth");
+        let tmux_name = format!("wsx-{}-dev", canonical_session_slug("wsx", path));
+        for alias in [None, Some("auth"), Some("login flow")] {
+            let display =
+                session_display_name_from_tmux(&tmux_name, "wsx", path, "feature/auth", alias);
+            assert_eq!(display, "dev");
+        }
+    }
+
     #[test]
     fn display_name_falls_back_to_project_slug_pattern() {
         let display = session_display_name_from_tmux(
@@ -179,15 +578,50 @@ mod tests {
         );
         assert_eq!(display, "agent");
     }
+
+    #[test]
+    fn only_a_suffix_wsx_added_is_set_apart() {
+        let session = |made_as: Option<&str>| SessionInfo {
+            name: "api-main-build_2".into(),
+            display_name: "build_2".into(),
+            origin: SessionOrigin { made_as: made_as.map(String::from), ..Default::default() },
+            ..Default::default()
+        };
+        let deduped = session(Some("api-main-build"));
+        assert_eq!(deduped.name_parts(), ("build", Some(2)));
+        assert_eq!(deduped.shown_name(), "build ·2");
+        for named in [session(Some("api-main-build_2")), session(None)] {
+            assert_eq!(named.name_parts(), ("build_2", None));
+            assert_eq!(named.shown_name(), "build_2");
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GitInfo {
     pub recent_commits: Vec<CommitSummary>,
     pub modified_files: Vec<String>,
     pub ahead: usize,
     pub behind: usize,
     pub remote_branch: Option<String>,
+    /// commits on this branch that the local default branch doesn't have
+    pub trunk_ahead: usize,
+    /// newest first, as `git stash list` prints them
+    pub stashes: Vec<StashEntry>,
+    /// when this branch left the default branch; None on the default branch itself
+    pub divergence: Option<Divergence>,
+}
+
+/// Where a branch forked off the default branch, from `git merge-base`: how long ago,
+/// and how far the default branch has moved on since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// the default branch it's measured against, or its upstream ("origin/main")
+    pub trunk: String,
+    /// unix seconds of the merge base's commit
+    pub base_ts: u64,
+    /// commits on the default branch since the merge base
+    pub trunk_since: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -196,6 +630,42 @@ pub struct CommitSummary {
     pub message: String,
 }
 
+/// A worktree archived as a `wsx/archive/<branch>-<yyyymmdd>` tag on its branch's last
+/// commit; listed under its project until restored or purged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedWorktree {
+    /// full tag name; `.2`, `.3`… follow the date when a branch is archived twice a day
+    pub tag: String,
+    pub branch: String,
+    /// `yyyy-mm-dd` it was archived
+    pub date: String,
+    /// display names of the sessions it had, made again on restore (kept in the cache)
+    pub sessions: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StashEntry {
+    /// N in `stash@{N}`
+    pub index: usize,
+    /// first line of the stash message, "…" appended if there were more
+    pub message: String,
+    /// unix seconds the stash was made
+    pub ts: u64,
+}
+
+impl StashEntry {
+    pub fn refname(&self) -> String {
+        format!("stash@{{{}}}", self.index)
+    }
+
+    /// Made on `branch` ("(no branch)" when detached): the stash stack is shared by
+    /// every worktree of a repo, and git prefixes each message with where it came from.
+    pub fn made_on(&self, branch: &str) -> bool {
+        let rest = self.message.strip_prefix("WIP on ").or_else(|| self.message.strip_prefix("On "));
+        rest.and_then(|r| r.strip_prefix(branch)).is_some_and(|r| r.starts_with(':'))
+    }
+}
+
 /// Flat tree entry for rendering and 3-level navigation.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FlatEntry {
@@ -211,6 +681,23 @@ pub enum FlatEntry {
         worktree_idx: usize,
         session_idx: usize,
     },
+    /// A window row under a multi-window session. Selects as its session;
+    /// attach, send and kill target the window.
+    Window {
+        project_idx: usize,
+        worktree_idx: usize,
+        session_idx: usize,
+        window_idx: usize,
+    },
+    /// "Archived (N)" under a project's worktrees, when it has archive tags
+    ArchiveHeader {
+        project_idx: usize,
+    },
+    /// An archive tag, listed while its project's "Archived" row is open
+    Archived {
+        project_idx: usize,
+        archive_idx: usize,
+    },
 }
 
 /// Flatten workspace into visible tree entries based on expand state.
@@ -219,21 +706,45 @@ pub fn flatten_tree(workspace: &WorkspaceState) -> Vec<FlatEntry> {
     for (pi, project) in workspace.projects.iter().enumerate() {
         result.push(FlatEntry::Project { idx: pi });
         if project.expanded {
-            for (wi, wt) in project.worktrees.iter().enumerate() {
+            for wi in project.worktree_order(workspace.main_first) {
+                let wt = &project.worktrees[wi];
+                if wt.hidden && !workspace.show_hidden {
+                    continue;
+                }
                 result.push(FlatEntry::Worktree {
                     project_idx: pi,
                     worktree_idx: wi,
                 });
                 if wt.expanded {
-                    for (si, _) in wt.sessions.iter().enumerate() {
+                    for si in workspace.session_order(wt) {
+                        let sess = &wt.sessions[si];
                         result.push(FlatEntry::Session {
                             project_idx: pi,
                             worktree_idx: wi,
                             session_idx: si,
                         });
+                        if sess.windows.len() > 1 {
+                            result.extend((0..sess.windows.len()).map(|window_idx| {
+                                FlatEntry::Window {
+                                    project_idx: pi,
+                                    worktree_idx: wi,
+                                    session_idx: si,
+                                    window_idx,
+                                }
+                            }));
+                        }
                     }
                 }
             }
+            if !project.archived.is_empty() {
+                result.push(FlatEntry::ArchiveHeader { project_idx: pi });
+                if project.archived_expanded {
+                    result.extend((0..project.archived.len()).map(|archive_idx| FlatEntry::Archived {
+                        project_idx: pi,
+                        archive_idx,
+                    }));
+                }
+            }
         }
     }
     result
@@ -245,14 +756,22 @@ pub enum Selection {
     Project(usize),
     Worktree(usize, usize),
     Session(usize, usize, usize),
+    /// A project's "Archived" row (None) or one of its archive tags
+    Archive(usize, Option<usize>),
     None,
 }
 
 impl WorkspaceState {
-    pub fn empty() -> Self {
-        Self {
-            projects: Vec::new(),
+    /// A worktree's session indices in display order: `WorktreeInfo::session_order`, or
+    /// with `recent_sessions` the most recently attached first (pins still on top). Only
+    /// the view is sorted; `sessions` keeps the manual order.
+    pub fn session_order(&self, wt: &WorktreeInfo) -> Vec<usize> {
+        let mut order = wt.session_order();
+        if self.recent_sessions {
+            let last = |si: usize| self.attaches.last_attached(&wt.sessions[si].name);
+            order.sort_by_key(|&si| (!wt.sessions[si].pinned, std::cmp::Reverse(last(si))));
         }
+        order
     }
 
     pub fn worktree(&self, pi: usize, wi: usize) -> Option<&WorktreeInfo> {
@@ -276,6 +795,62 @@ impl WorkspaceState {
             .get_mut(si)
     }
 
+    /// What tmux names of project `pi`'s sessions start with: its name, plus a tag of its
+    /// path when another project in the workspace has the same name.
+    pub fn session_prefix(&self, pi: usize) -> String {
+        let project = &self.projects[pi];
+        let shared = self
+            .projects
+            .iter()
+            .enumerate()
+            .any(|(i, p)| i != pi && p.name == project.name);
+        if shared {
+            format!("{}_{}", project.name, path_tag(&project.path))
+        } else {
+            project.name.clone()
+        }
+    }
+
+    /// `{prefix}-{worktree slug}`, what a session's tmux name has before the name it was given.
+    pub fn session_base(&self, pi: usize, wi: usize) -> String {
+        let project = &self.projects[pi];
+        let slug = project.worktrees[wi].session_slug(&project.name);
+        format!("{}-{}", self.session_prefix(pi), slug)
+    }
+
+    /// (project, worktree) indices of the worktree checked out at `path`.
+    pub fn find_worktree(&self, path: &Path) -> Option<(usize, usize)> {
+        let find = |matches: &dyn Fn(&Path) -> bool| {
+            self.projects.iter().enumerate().find_map(|(pi, p)| {
+                p.worktrees.iter().position(|w| matches(&w.path)).map(|wi| (pi, wi))
+            })
+        };
+        // git may list a path differently from how it was created (symlinks, `..`).
+        find(&|p| p == path).or_else(|| {
+            let canonical = std::fs::canonicalize(path).ok()?;
+            find(&|p| std::fs::canonicalize(p).is_ok_and(|c| c == canonical))
+        })
+    }
+
+    /// (session name, worktree path) for every session in the tree: what tmux
+    /// would list if nothing had changed since the last refresh.
+    pub fn session_paths(&self) -> Vec<(String, PathBuf)> {
+        self.projects
+            .iter()
+            .flat_map(|p| &p.worktrees)
+            .flat_map(|w| w.sessions.iter().map(|s| (s.name.clone(), w.path.clone())))
+            .collect()
+    }
+
+    /// (project, worktree, session) indices of the tmux session `name`.
+    pub fn find_session(&self, name: &str) -> Option<(usize, usize, usize)> {
+        self.projects.iter().enumerate().find_map(|(pi, p)| {
+            p.worktrees.iter().enumerate().find_map(|(wi, w)| {
+                w.sessions.iter().position(|s| s.name == name).map(|si| (pi, wi, si))
+            })
+        })
+    }
+
     /// Resolve flat index to Selection using a pre-computed flat slice.
     pub fn get_selection(&self, flat_idx: usize, flat: &[FlatEntry]) -> Selection {
         match flat.get(flat_idx) {
@@ -288,7 +863,17 @@ impl WorkspaceState {
                 project_idx,
                 worktree_idx,
                 session_idx,
+            })
+            | Some(FlatEntry::Window {
+                project_idx,
+                worktree_idx,
+                session_idx,
+                ..
             }) => Selection::Session(*project_idx, *worktree_idx, *session_idx),
+            Some(FlatEntry::ArchiveHeader { project_idx }) => Selection::Archive(*project_idx, None),
+            Some(FlatEntry::Archived { project_idx, archive_idx }) => {
+                Selection::Archive(*project_idx, Some(*archive_idx))
+            }
             None => Selection::None,
         }
     }
@@ -3,38 +3,101 @@
 // tested and reasoned about independently of the TUI state machine.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 
 use crate::{
-    config::global::GlobalConfig,
-    git::{info as git_info, worktree as git_worktree},
-    hooks,
+    config::global::{GlobalConfig, ProjectEntry, ProjectOrder},
+    git::{
+        worktree::{self as git_worktree, Cleaned, NewWorktree},
+        GitBackend,
+    },
+    hooks::{self, CopySummary},
+    model::ended::EndedSession,
+    model::attention::AttentionSet,
+    model::frecency,
     model::workspace::{
-        session_display_name_from_tmux, GitInfo, Project, ProjectConfig, SessionInfo,
+        canonical_session_slug, poll_bell, ArchivedWorktree, session_display_name_from_tmux, Divergence, GitInfo, Project, ProjectConfig, SessionInfo,
         WorkspaceState, WorktreeInfo,
     },
-    tmux::{monitor::SessionStatus, session},
+    tmux::{monitor::SessionStatus, naming, session, TmuxBackend},
+    ui::path::display_path,
 };
 
-// (pane_capture, running_app_suppressed, muted)
-type PaneSnap = HashMap<String, (Option<String>, bool, bool)>;
+/// What a session carries across a refresh: its last capture, what the user toggled,
+/// and the attention state the next poll builds on.
+#[derive(Clone, Default)]
+struct PaneSnap {
+    pane_capture: Option<String>,
+    running_app_suppressed: bool,
+    muted: bool,
+    pinned: bool,
+    attention_since: Option<Instant>,
+    bell_ack: Option<u64>,
+    bell_since: Option<u64>,
+    attention: AttentionSet,
+    /// an app was in the foreground at the last poll
+    was_running: bool,
+}
+
 // session_order preserves user-defined sort across refresh
 type WorktreeSnap = HashMap<PathBuf, WorktreeSnapEntry>;
 
 struct WorktreeSnapEntry {
     git_info: Option<GitInfo>,
     expanded: bool,
-    panes: PaneSnap,
+    panes: HashMap<String, PaneSnap>,
     session_order: Vec<String>,
     last_fetched: Option<Instant>,
+    last_fetch_ok: Option<Instant>,
     fetch_failed: bool,
+    pinned: bool,
+    note: Option<String>,
 }
 
 pub const IDLE_SECS: u64 = 3;
 
+/// Physical paths of worktree and session directories (and worktrees' parents, for
+/// `is_external`), resolved once per rescan: the refresh compares every session with
+/// every worktree, and `canonicalize` is a syscall. `refresh_workspace` empties it, so
+/// it holds one rescan's paths at most and a repointed symlink is seen next time.
+static PHYSICAL_PATHS: LazyLock<Mutex<HashMap<PathBuf, PathBuf>>> = LazyLock::new(Default::default);
+
+pub(crate) fn physical_path(path: &Path) -> PathBuf {
+    let mut cache = PHYSICAL_PATHS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(real) = cache.get(path) {
+        return real.clone();
+    }
+    match std::fs::canonicalize(path) {
+        Ok(real) => {
+            cache.insert(path.to_path_buf(), real.clone());
+            real
+        }
+        // Gone, or not created yet: compare as given and try again next time.
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+fn forget_physical_paths() {
+    PHYSICAL_PATHS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Whether a session started in `session_dir` belongs to the worktree at `wt_path`.
+/// tmux reports the physical path, while git may list a symlinked one — or, on
+/// macOS's case-insensitive filesystems, one spelled with different case.
+pub(crate) fn session_in_worktree(session_dir: &Path, wt_path: &Path) -> bool {
+    if session_dir == wt_path {
+        return true;
+    }
+    let (a, b) = (physical_path(session_dir), physical_path(wt_path));
+    a == b
+        || (cfg!(target_os = "macos")
+            && a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase())
+}
+
 // ── Refresh helpers ───────────────────────────────────────────────────────────
 
 fn unix_ts_to_instant(unix_ts: u64) -> Option<Instant> {
@@ -47,12 +110,18 @@ fn unix_ts_to_instant(unix_ts: u64) -> Option<Instant> {
 }
 
 /// Rebuild all worktrees + sessions for every project from live data.
+/// Returns (project name, error) for projects whose worktrees couldn't be listed;
+/// those keep their previous worktrees.
 pub fn refresh_workspace(
+    git: &dyn GitBackend,
     workspace: &mut WorkspaceState,
     config: &GlobalConfig,
     sessions_with_paths: &[(String, PathBuf)],
     activity: &HashMap<String, SessionStatus>,
-) {
+) -> Vec<(String, String)> {
+    forget_physical_paths();
+    let mut errors = vec![];
+    let now = frecency::now_secs();
     let aliases_by_path: Vec<(PathBuf, HashMap<String, String>)> = config
         .projects
         .iter()
@@ -62,6 +131,22 @@ pub fn refresh_workspace(
     for i in 0..workspace.projects.len() {
         let path = workspace.projects[i].path.clone();
         let proj_name = workspace.projects[i].name.clone();
+        if workspace.projects[i].syncing {
+            // Its live load is still running on the startup thread.
+            continue;
+        }
+        if workspace.projects[i].missing {
+            if !path.exists() {
+                continue;
+            }
+            // The directory is back (remounted, moved back): load it as usual.
+            let project = &mut workspace.projects[i];
+            project.missing = false;
+            project.config = Some(crate::config::project::load_project_config(&path));
+            if !project.default_branch_override {
+                project.default_branch = detect_default_branch(git, &path);
+            }
+        }
         let aliases = aliases_by_path
             .iter()
             .find(|(p, _)| p == &path)
@@ -76,10 +161,18 @@ pub fn refresh_workspace(
                     .sessions
                     .iter()
                     .map(|s| {
-                        (
-                            s.name.clone(),
-                            (s.pane_capture.clone(), s.running_app_suppressed, s.muted),
-                        )
+                        let pane = PaneSnap {
+                            pane_capture: s.pane_capture.clone(),
+                            running_app_suppressed: s.running_app_suppressed,
+                            muted: s.muted,
+                            pinned: s.pinned,
+                            attention_since: s.attention_since,
+                            bell_ack: s.bell_ack,
+                            bell_since: s.bell_since,
+                            attention: s.attention.clone(),
+                            was_running: s.has_running_app,
+                        };
+                        (s.name.clone(), pane)
                     })
                     .collect();
                 let order = w.sessions.iter().map(|s| s.name.clone()).collect();
@@ -91,112 +184,165 @@ pub fn refresh_workspace(
                         panes,
                         session_order: order,
                         last_fetched: w.last_fetched,
+                        last_fetch_ok: w.last_fetch_ok,
                         fetch_failed: w.fetch_failed,
+                        pinned: w.pinned,
+                        note: w.note.clone(),
                     },
                 )
             })
             .collect();
 
-        if let Ok(entries) = git_worktree::list_worktrees(&path) {
-            let mut new_worktrees = Vec::new();
-            for entry in entries {
-                let alias = aliases.get(&entry.branch).cloned();
-                let wt_path = entry.path.clone();
-                let prev = snapshot.get(&entry.path);
+        let entries = match git.list_worktrees(&path) {
+            Ok(entries) => entries,
+            Err(_) if !path.exists() => {
+                let project = &mut workspace.projects[i];
+                project.missing = true;
+                project.worktrees.clear();
+                errors.push((proj_name, format!("path missing: {}", display_path(&path, false, usize::MAX))));
+                continue;
+            }
+            Err(e) => {
+                errors.push((proj_name, e.to_string()));
+                continue;
+            }
+        };
+        let mut new_worktrees = Vec::new();
+        let proj_config = workspace.projects[i].config.clone();
+        for entry in entries {
+            let (alias, alias_derived) =
+                git_worktree::effective_alias(&aliases, proj_config.as_ref(), &entry.branch);
+            let wt_path = entry.path.clone();
+            let prev = snapshot.get(&entry.path);
 
-                let prev_order: &[String] = prev
-                    .map(|snap| snap.session_order.as_slice())
-                    .unwrap_or(&[]);
+            let prev_order: &[String] = prev
+                .map(|snap| snap.session_order.as_slice())
+                .unwrap_or(&[]);
 
-                let mut sessions: Vec<SessionInfo> = sessions_with_paths
-                    .iter()
-                    .filter(|(_, sp)| sp == &wt_path)
-                    .map(|(name, _)| {
-                        let display_name = session_display_name_from_tmux(
-                            name,
-                            &proj_name,
-                            &wt_path,
-                            &entry.branch,
-                            alias.as_deref(),
-                        );
-                        let prev_pane = prev.and_then(|snap| snap.panes.get(name));
-                        let (pane_capture, prev_suppressed, muted) = prev_pane
-                            .map(|(p, s, m)| (p.clone(), *s, *m))
-                            .unwrap_or((None, false, false));
-                        // Muted sessions skip all activity tracking.
-                        let (has_activity, has_running_app, last_activity, running_app_suppressed) =
-                            if muted {
-                                (false, false, None, false)
+            let mut sessions: Vec<SessionInfo> = sessions_with_paths
+                .iter()
+                .filter(|(_, sp)| session_in_worktree(sp, &wt_path))
+                .map(|(name, _)| {
+                    let display_name = session_display_name_from_tmux(
+                        name,
+                        &proj_name,
+                        &wt_path,
+                        &entry.branch,
+                        alias.as_deref(),
+                    );
+                    let PaneSnap {
+                        pane_capture,
+                        running_app_suppressed: prev_suppressed,
+                        muted,
+                        pinned,
+                        attention_since,
+                        bell_ack: prev_ack,
+                        bell_since: prev_since,
+                        attention,
+                        was_running,
+                    } = prev.and_then(|snap| snap.panes.get(name)).cloned().unwrap_or_default();
+                    let status = activity.get(name.as_str());
+                    let bell_up = status.is_some_and(|s| s.has_bell);
+                    // Muted sessions skip all activity tracking.
+                    let (bell_since, bell_ack, has_activity) =
+                        if muted { (prev_since, prev_ack, false) } else { poll_bell(bell_up, prev_since, prev_ack, now) };
+                    let (has_running_app, last_activity, running_app_suppressed) =
+                        if muted {
+                            (false, None, false)
+                        } else {
+                            let has_running_app =
+                                status.map(|s| s.has_running_app).unwrap_or(false);
+                            let last_activity = status
+                                .filter(|s| s.last_activity_ts > 0)
+                                .and_then(|s| unix_ts_to_instant(s.last_activity_ts));
+                            let currently_active = last_activity
+                                .map(|t| t.elapsed().as_secs() < IDLE_SECS)
+                                .unwrap_or(false);
+                            // Reset suppressed when new activity arrives.
+                            let running_app_suppressed = if currently_active {
+                                false
                             } else {
-                                let status = activity.get(name.as_str());
-                                let has_activity = status.map(|s| s.has_bell).unwrap_or(false);
-                                let has_running_app =
-                                    status.map(|s| s.has_running_app).unwrap_or(false);
-                                let last_activity = status
-                                    .filter(|s| s.last_activity_ts > 0)
-                                    .and_then(|s| unix_ts_to_instant(s.last_activity_ts));
-                                let currently_active = last_activity
-                                    .map(|t| t.elapsed().as_secs() < IDLE_SECS)
-                                    .unwrap_or(false);
-                                // Reset suppressed when new activity arrives.
-                                let running_app_suppressed = if currently_active {
-                                    false
-                                } else {
-                                    prev_suppressed
-                                };
-                                (
-                                    has_activity,
-                                    has_running_app,
-                                    last_activity,
-                                    running_app_suppressed,
-                                )
+                                prev_suppressed
                             };
-                        SessionInfo {
-                            name: name.clone(),
-                            display_name,
-                            has_activity,
-                            pane_capture,
-                            last_activity,
-                            has_running_app,
-                            running_app_suppressed,
-                            muted,
-                        }
-                    })
-                    .collect();
-                sessions.sort_by_key(|s| {
-                    prev_order
-                        .iter()
-                        .position(|n| n == &s.name)
-                        .unwrap_or(usize::MAX)
-                });
-
-                let (git_info, expanded, last_fetched, fetch_failed) = prev
-                    .map(|snap| {
-                        (
-                            snap.git_info.clone(),
-                            snap.expanded,
-                            snap.last_fetched,
-                            snap.fetch_failed,
-                        )
-                    })
-                    .unwrap_or((None, true, None, false));
-
-                new_worktrees.push(WorktreeInfo {
-                    name: entry.name,
-                    branch: entry.branch,
-                    path: entry.path,
-                    is_main: entry.is_main,
-                    alias,
-                    sessions,
-                    expanded,
-                    git_info,
-                    fetch_failed,
-                    last_fetched,
-                });
-            }
-            workspace.projects[i].worktrees = new_worktrees;
+                            (
+                                has_running_app,
+                                last_activity,
+                                running_app_suppressed,
+                            )
+                        };
+                    let (commands, windows, created_ts, origin) = status
+                        .map(|s| (s.commands.clone(), s.windows.clone(), s.created_ts, s.origin()))
+                        .unwrap_or_default();
+                    let mut sess = SessionInfo {
+                        name: name.clone(),
+                        display_name,
+                        has_activity,
+                        pane_capture,
+                        last_activity,
+                        has_running_app,
+                        running_app_suppressed,
+                        muted,
+                        commands,
+                        windows,
+                        pinned,
+                        created_ts,
+                        attention_since,
+                        origin,
+                        bell_ack,
+                        bell_since,
+                        attention,
+                    };
+                    sess.observe(was_running, status.is_some_and(|s| s.attached));
+                    sess
+                })
+                .collect();
+            sessions.sort_by_key(|s| {
+                prev_order
+                    .iter()
+                    .position(|n| n == &s.name)
+                    .unwrap_or(usize::MAX)
+            });
+
+            let (git_info, expanded, (last_fetched, last_fetch_ok), fetch_failed, (pinned, note)) = prev
+                .map(|snap| {
+                    (
+                        snap.git_info.clone(),
+                        snap.expanded,
+                        (snap.last_fetched, snap.last_fetch_ok),
+                        snap.fetch_failed,
+                        (snap.pinned, snap.note.clone()),
+                    )
+                })
+                .unwrap_or((None, true, (None, None), false, (false, None)));
+
+            let hidden = !entry.is_main
+                && proj_config.as_ref().is_some_and(|c| c.hides_worktree(&entry.branch, &entry.path));
+            let external = git_worktree::is_external(&path, &entry);
+            new_worktrees.push(WorktreeInfo {
+                name: entry.name,
+                branch: entry.branch,
+                path: entry.path,
+                is_main: entry.is_main,
+                detached: entry.detached,
+                alias,
+                alias_derived,
+                sessions,
+                expanded,
+                git_info,
+                fetch_failed,
+                last_fetched,
+                last_fetch_ok,
+                pinned,
+                hidden,
+                locked: entry.locked,
+                note,
+                external,
+            });
         }
+        workspace.projects[i].worktrees = new_worktrees;
+        reload_archived(git, &mut workspace.projects[i]);
     }
+    errors
 }
 
 /// Update session activity state from live tmux data. Returns true if any field changed.
@@ -205,30 +351,56 @@ pub fn update_activity(
     activity: &HashMap<String, SessionStatus>,
 ) -> bool {
     let mut changed = false;
+    let now = frecency::now_secs();
     for project in &mut workspace.projects {
         for wt in &mut project.worktrees {
             for sess in &mut wt.sessions {
+                let Some(status) = activity.get(&sess.name) else { continue };
+                // What's running is shown even for muted sessions; it isn't activity.
+                if sess.commands != status.commands {
+                    sess.commands = status.commands.clone();
+                    changed = true;
+                }
+                if sess.windows != status.windows {
+                    sess.windows = status.windows.clone();
+                    changed = true;
+                }
+                if sess.created_ts != status.created_ts {
+                    sess.created_ts = status.created_ts;
+                    changed = true;
+                }
+                let origin = status.origin();
+                if sess.origin != origin {
+                    sess.origin = origin;
+                    changed = true;
+                }
                 if sess.muted {
                     continue;
                 }
-                if let Some(status) = activity.get(&sess.name) {
-                    let old_bell = sess.has_activity;
-                    let old_running = sess.has_running_app;
-                    sess.has_activity = status.has_bell;
-                    sess.has_running_app = status.has_running_app;
-                    sess.last_activity = Some(status.last_activity_ts)
-                        .filter(|&ts| ts > 0)
-                        .and_then(|ts| unix_ts_to_instant(ts));
-                    let currently_active = sess
-                        .last_activity
-                        .map(|t| t.elapsed().as_secs() < IDLE_SECS)
-                        .unwrap_or(false);
-                    if currently_active {
-                        sess.running_app_suppressed = false;
-                    }
-                    if sess.has_activity != old_bell || sess.has_running_app != old_running {
-                        changed = true;
-                    }
+                let old_bell = sess.has_activity;
+                let old_running = sess.has_running_app;
+                (sess.bell_since, sess.bell_ack, sess.has_activity) =
+                    poll_bell(status.has_bell, sess.bell_since, sess.bell_ack, now);
+                sess.has_running_app = status.has_running_app;
+                sess.last_activity = Some(status.last_activity_ts)
+                    .filter(|&ts| ts > 0)
+                    .and_then(unix_ts_to_instant);
+                let currently_active = sess
+                    .last_activity
+                    .map(|t| t.elapsed().as_secs() < IDLE_SECS)
+                    .unwrap_or(false);
+                if currently_active {
+                    sess.running_app_suppressed = false;
+                }
+                let was_waiting = sess.attention_since.is_some();
+                let old_attention = sess.attention.clone();
+                sess.observe(old_running, status.attached);
+                if sess.has_activity != old_bell
+                    || sess.has_running_app != old_running
+                    || sess.attention_since.is_some() != was_waiting
+                    || sess.attention != old_attention
+                {
+                    changed = true;
                 }
             }
         }
@@ -238,57 +410,341 @@ pub fn update_activity(
 
 // ── Workspace loading ─────────────────────────────────────────────────────────
 
-pub fn load_workspace(config: &GlobalConfig) -> WorkspaceState {
-    if config.projects.is_empty() {
-        return WorkspaceState::empty();
+/// A project whose directory is gone comes back as a `missing` placeholder.
+pub fn load_project(git: &dyn GitBackend, entry: &ProjectEntry) -> Project {
+    let path = &entry.path;
+    if !path.exists() {
+        return Project {
+            name: entry.name.clone(),
+            path: path.clone(),
+            default_branch: entry.default_branch.clone().unwrap_or_else(|| "main".into()),
+            default_branch_override: entry.default_branch.is_some(),
+            missing: true,
+            syncing: false,
+            worktrees: vec![],
+            config: None,
+            expanded: true,
+            frecency: Default::default(),
+            archived: vec![],
+            archived_expanded: false,
+        };
     }
 
-    let projects = config
-        .projects
-        .iter()
-        .filter_map(|entry| {
-            let path = &entry.path;
-            if !path.exists() {
-                return None;
-            }
+    let default_branch = entry
+        .default_branch
+        .clone()
+        .unwrap_or_else(|| detect_default_branch(git, path));
+    let proj_config = crate::config::project::load_project_config(path);
+    let entries = git.list_worktrees(path).unwrap_or_default();
+    let worktrees = git_worktree::to_worktree_infos(path, entries, &entry.aliases, Some(&proj_config));
 
-            let default_branch = detect_default_branch(path);
-            let proj_config = crate::config::project::load_project_config(path);
-            let entries = git_worktree::list_worktrees(path).unwrap_or_default();
-            let worktrees = git_worktree::to_worktree_infos(entries, &entry.aliases);
-
-            Some(Project {
-                name: entry.name.clone(),
-                path: path.clone(),
-                default_branch,
-                worktrees,
-                config: Some(proj_config),
-                expanded: true,
-            })
-        })
-        .collect();
+    Project {
+        name: entry.name.clone(),
+        path: path.clone(),
+        default_branch,
+        default_branch_override: entry.default_branch.is_some(),
+        missing: false,
+        syncing: false,
+        worktrees,
+        config: Some(proj_config),
+        expanded: true,
+        frecency: Default::default(),
+        archived: git.archived_worktrees(path),
+        archived_expanded: false,
+    }
+}
 
-    WorkspaceState { projects }
+/// Put a project loaded in the background in place of its cached stand-in, keeping what
+/// changed on screen meanwhile: expand state, usage, and each surviving worktree's
+/// sessions, pins and note. False when the project was removed while it loaded.
+pub fn adopt_loaded(workspace: &mut WorkspaceState, mut loaded: Project) -> bool {
+    let Some(slot) = workspace.projects.iter_mut().find(|p| p.path == loaded.path) else {
+        return false;
+    };
+    loaded.name = slot.name.clone();
+    loaded.expanded = slot.expanded;
+    loaded.frecency = slot.frecency;
+    for wt in &mut loaded.worktrees {
+        if let Some(old) = slot.worktrees.iter_mut().find(|w| w.path == wt.path) {
+            wt.expanded = old.expanded;
+            wt.pinned = old.pinned;
+            wt.note = old.note.take();
+            wt.sessions = std::mem::take(&mut old.sessions);
+        }
+    }
+    *slot = loaded;
+    true
+}
+
+/// Bring the project list in line with `config` after it changed underneath us
+/// (another instance saved). Keeps loaded state for projects that stay; returns
+/// true if the list changed.
+pub fn sync_projects(
+    git: &dyn GitBackend,
+    workspace: &mut WorkspaceState,
+    config: &GlobalConfig,
+) -> bool {
+    let before: Vec<PathBuf> = workspace.projects.iter().map(|p| p.path.clone()).collect();
+    let mut old = std::mem::take(&mut workspace.projects);
+    for entry in &config.projects {
+        if let Some(pos) = old.iter().position(|p| p.path == entry.path) {
+            let mut project = old.remove(pos);
+            project.name = entry.name.clone();
+            set_default_branch(git, &mut project, entry.default_branch.clone());
+            workspace.projects.push(project);
+        } else {
+            workspace.projects.push(load_project(git, entry));
+        }
+    }
+    order_projects(workspace, config);
+    let after: Vec<PathBuf> = workspace.projects.iter().map(|p| p.path.clone()).collect();
+    before != after
+}
+
+/// Sort projects per `config.project_order`. Manual restores the config order exactly;
+/// frecency sorts on top of it, so equal scores keep their manual positions.
+pub fn order_projects(workspace: &mut WorkspaceState, config: &GlobalConfig) {
+    let rank = |p: &Project| config.projects.iter().position(|e| e.path == p.path);
+    workspace.projects.sort_by_key(|p| rank(p).unwrap_or(usize::MAX));
+    if config.project_order == ProjectOrder::Frecency {
+        let now = frecency::now_secs();
+        workspace
+            .projects
+            .sort_by(|a, b| b.frecency.score_at(now).total_cmp(&a.frecency.score_at(now)));
+    }
+}
+
+/// Make the `SessionOrder::Recent` view the manual order, so switching to Manual
+/// doesn't reshuffle the tree.
+pub fn adopt_recent_order(workspace: &mut WorkspaceState) {
+    let attaches = &workspace.attaches;
+    for wt in workspace.projects.iter_mut().flat_map(|p| p.worktrees.iter_mut()) {
+        wt.sessions.sort_by_key(|s| std::cmp::Reverse(attaches.last_attached(&s.name)));
+    }
 }
 
 pub fn expand_path(s: &str) -> PathBuf {
-    if s.starts_with("~/") {
+    if let Some(rest) = s.strip_prefix("~/") {
         if let Some(home) = dirs::home_dir() {
-            return home.join(&s[2..]);
+            return home.join(rest);
         }
     }
     PathBuf::from(s)
 }
 
-pub fn detect_default_branch(path: &std::path::Path) -> String {
-    git_info::current_branch(path).unwrap_or_else(|| "main".into())
+/// A path pasted from a stack trace or CI log, placed in the tree by `Ctrl+G`.
+#[derive(Debug, PartialEq)]
+pub struct LocatedPath {
+    pub project_idx: usize,
+    pub worktree_idx: usize,
+    /// the path inside the worktree; empty for the worktree itself
+    pub relative: PathBuf,
+    /// a `:42:7` the trace put after the file, kept for the status line
+    pub position: String,
+}
+
+/// The registered worktree an absolute `input` is inside — the deepest, since worktrees
+/// may nest — comparing physical paths on both sides. `~` expands and a trailing
+/// `:line[:col]` is set aside. None when no worktree contains it, as when it was in one
+/// deleted since.
+pub fn locate_path(workspace: &WorkspaceState, input: &str) -> Option<LocatedPath> {
+    let (file, position) = split_position(input.trim());
+    let wanted = physical_within(&expand_path(file));
+    let (_, project_idx, worktree_idx, relative) = workspace
+        .projects
+        .iter()
+        .enumerate()
+        .flat_map(|(pi, p)| p.worktrees.iter().enumerate().map(move |(wi, w)| (pi, wi, w)))
+        .filter_map(|(pi, wi, w)| {
+            let root = physical_path(&w.path);
+            let relative = wanted.strip_prefix(&root).ok()?.to_path_buf();
+            Some((root.components().count(), pi, wi, relative))
+        })
+        .max_by_key(|(depth, ..)| *depth)?;
+    Some(LocatedPath { project_idx, worktree_idx, relative, position: position.to_string() })
+}
+
+/// ("src/lib.rs", ":42:7") for "src/lib.rs:42:7".
+fn split_position(s: &str) -> (&str, &str) {
+    let mut end = s.len();
+    for _ in 0..2 {
+        match s[..end].rsplit_once(':') {
+            Some((head, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => end = head.len(),
+            _ => break,
+        }
+    }
+    (&s[..end], &s[end..])
+}
+
+/// `path` with its deepest existing ancestor resolved, so a file that's gone (or a
+/// typo) still compares by where its directory really is.
+fn physical_within(path: &Path) -> PathBuf {
+    let mut rest = Vec::new();
+    let mut base = path;
+    loop {
+        if let Ok(real) = std::fs::canonicalize(base) {
+            return rest.iter().rev().fold(real, |p, name| p.join(name));
+        }
+        match (base.parent(), base.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                base = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+pub fn detect_default_branch(git: &dyn GitBackend, path: &Path) -> String {
+    git.current_branch(path).unwrap_or_else(|| "main".into())
+}
+
+/// Apply a configured default branch, or go back to the detected one for `None`.
+pub fn set_default_branch(git: &dyn GitBackend, project: &mut Project, branch: Option<String>) {
+    match branch {
+        Some(branch) => {
+            project.default_branch = branch;
+            project.default_branch_override = true;
+        }
+        None if project.default_branch_override => {
+            project.default_branch = detect_default_branch(git, &project.path);
+            project.default_branch_override = false;
+        }
+        None => {}
+    }
 }
 
 // ── Project registration ──────────────────────────────────────────────────────
 
+#[derive(Debug, thiserror::Error)]
+pub enum RegisterProjectError {
+    /// The path is a linked worktree; `main` is the repository it belongs to.
+    #[error("{} is a worktree of {}", display_path(path, false, usize::MAX), display_path(main, false, usize::MAX))]
+    LinkedWorktree { path: PathBuf, main: PathBuf },
+    /// The same repository is already a project.
+    #[error("{} is already registered as '{name}'", display_path(path, false, usize::MAX))]
+    AlreadyRegistered { name: String, path: PathBuf },
+}
+
+/// A tmux session that matched no worktree, for the `--debug` doctor listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanSession {
+    pub name: String,
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Sessions from `sessions_with_paths` that the last refresh placed under no worktree.
+pub fn orphan_sessions(workspace: &WorkspaceState, sessions_with_paths: &[(String, PathBuf)]) -> Vec<OrphanSession> {
+    sessions_with_paths
+        .iter()
+        .filter(|(name, _)| workspace.find_session(name).is_none())
+        .map(|(name, path)| {
+            let real = physical_path(path);
+            // The innermost one, for worktrees nested inside the main checkout.
+            let worktree = workspace
+                .projects
+                .iter()
+                .flat_map(|p| p.worktrees.iter().map(move |w| (p, physical_path(&w.path))))
+                .filter(|(_, w)| real.starts_with(w))
+                .max_by_key(|(_, w)| w.as_os_str().len());
+            let project = workspace.projects.iter().find(|p| real.starts_with(physical_path(&p.path)));
+            let reason = if !path.exists() {
+                "its directory no longer exists".to_string()
+            } else if let Some((p, w)) = worktree {
+                format!("started in a subdirectory of '{}' worktree {}", p.name, display_path(&w, false, usize::MAX))
+            } else if let Some(p) = project {
+                format!("under project '{}' but not in a worktree git lists", p.name)
+            } else {
+                "not under any registered project".to_string()
+            };
+            OrphanSession { name: name.clone(), path: path.clone(), reason }
+        })
+        .collect()
+}
+
+/// Sessions in the tree that tmux no longer lists, with the capture they were last
+/// previewed with; ones never captured have nothing worth keeping.
+pub fn ended_sessions(workspace: &WorkspaceState, sessions_with_paths: &[(String, PathBuf)], now: u64) -> Vec<EndedSession> {
+    let mut ended = vec![];
+    for p in &workspace.projects {
+        for wt in &p.worktrees {
+            for sess in &wt.sessions {
+                if sessions_with_paths.iter().any(|(name, _)| name == &sess.name) {
+                    continue;
+                }
+                if let Some(capture) = sess.pane_capture.as_ref().filter(|c| !c.trim().is_empty()) {
+                    ended.push(EndedSession {
+                        ts: now,
+                        session: sess.name.clone(),
+                        display_name: sess.display_name.clone(),
+                        project: p.name.clone(),
+                        worktree: wt.path.clone(),
+                        capture: capture.clone(),
+                    });
+                }
+            }
+        }
+    }
+    ended
+}
+
+/// `a` and `b` name the same directory, even through symlinks or `..`.
+pub(crate) fn same_dir(a: &Path, b: &Path) -> bool {
+    a == b || std::fs::canonicalize(a).is_ok_and(|a| std::fs::canonicalize(b).is_ok_and(|b| a == b))
+}
+
+/// Point a `missing` project at where its repository lives now. `new_path` is checked
+/// like in `register_project`; the config entry (aliases, branch override) moves
+/// with it — caller must call `config.save()` — and the project is reloaded in
+/// place, keeping its frecency and expand state.
+pub fn relocate_project(
+    git: &dyn GitBackend,
+    config: &mut GlobalConfig,
+    project: &mut Project,
+    new_path: PathBuf,
+) -> Result<()> {
+    if new_path.as_os_str().is_empty() {
+        bail!("empty path");
+    }
+    if !new_path.join(".git").exists() {
+        bail!("not a git repository: {}", display_path(&new_path, false, usize::MAX));
+    }
+    let other = config
+        .projects
+        .iter()
+        .find(|e| e.path != project.path && same_dir(&e.path, &new_path));
+    if let Some(existing) = other {
+        return Err(RegisterProjectError::AlreadyRegistered {
+            name: existing.name.clone(),
+            path: existing.path.clone(),
+        }
+        .into());
+    }
+    if let Some(main) = git_worktree::main_repo_of(&new_path) {
+        return Err(RegisterProjectError::LinkedWorktree { path: new_path, main }.into());
+    }
+    config.set_project_path(&project.path, new_path.clone());
+    let entry = config
+        .projects
+        .iter()
+        .find(|e| e.path == new_path)
+        .context("project is no longer registered")?;
+    let (frecency, expanded) = (project.frecency, project.expanded);
+    *project = load_project(git, entry);
+    project.frecency = frecency;
+    project.expanded = expanded;
+    Ok(())
+}
+
 /// Register a new project at `path`. Returns the constructed `Project` and
 /// mutates `config` (caller must call `config.save()`).
-pub fn register_project(path: PathBuf, config: &mut GlobalConfig) -> Result<Project> {
+/// Fails with `RegisterProjectError` for a linked worktree or an already registered repo.
+pub fn register_project(
+    git: &dyn GitBackend,
+    path: PathBuf,
+    config: &mut GlobalConfig,
+) -> Result<Project> {
     if path.as_os_str().is_empty() {
         bail!("empty path");
     }
@@ -298,22 +754,33 @@ pub fn register_project(path: PathBuf, config: &mut GlobalConfig) -> Result<Proj
     if !path.join(".git").exists() {
         bail!("not a git repository: {}", path.display());
     }
+    if let Some(existing) = config.projects.iter().find(|e| same_dir(&e.path, &path)) {
+        return Err(RegisterProjectError::AlreadyRegistered {
+            name: existing.name.clone(),
+            path: existing.path.clone(),
+        }
+        .into());
+    }
+    if let Some(main) = git_worktree::main_repo_of(&path) {
+        return Err(RegisterProjectError::LinkedWorktree { path, main }.into());
+    }
 
     let name = path
         .file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    let default_branch = detect_default_branch(&path);
+    let default_branch = detect_default_branch(git, &path);
     let proj_config = crate::config::project::load_project_config(&path);
-    let entries = git_worktree::list_worktrees(&path).unwrap_or_default();
+    let entries = git.list_worktrees(&path).unwrap_or_default();
     let aliases = config
         .projects
         .iter()
         .find(|e| e.path == path)
         .map(|e| e.aliases.clone())
         .unwrap_or_default();
-    let worktrees = git_worktree::to_worktree_infos(entries, &aliases);
+    let worktrees = git_worktree::to_worktree_infos(&path, entries, &aliases, Some(&proj_config));
+    let archived = git.archived_worktrees(&path);
 
     config.add_project(name.clone(), path.clone());
 
@@ -321,9 +788,15 @@ pub fn register_project(path: PathBuf, config: &mut GlobalConfig) -> Result<Proj
         name,
         path,
         default_branch,
+        default_branch_override: false,
+        missing: false,
+        syncing: false,
         worktrees,
+        archived,
         config: Some(proj_config),
         expanded: true,
+        frecency: Default::default(),
+        archived_expanded: false,
     })
 }
 
@@ -334,56 +807,370 @@ pub fn unregister_project(path: &PathBuf, config: &mut GlobalConfig) {
 
 // ── Worktree operations ───────────────────────────────────────────────────────
 
-/// Create a new git worktree under `repo_path` for `branch`.
-/// Runs hooks (env copy, post_create) and returns the new worktree path.
-/// Returns a warning string if a hook failed (non-fatal).
+pub struct CreatedWorktree {
+    pub path: PathBuf,
+    /// `copy.include` files brought over from the main worktree
+    pub copy: CopySummary,
+    /// a hook failed (non-fatal)
+    pub warning: Option<String>,
+    /// the project's postCreate command, not run yet — the caller streams it
+    pub post_create: Option<String>,
+    /// files moved over from `carry_from`, or why they couldn't be
+    pub carried: Option<Result<usize>>,
+}
+
+/// Create a new git worktree under `repo_path` for `target`, move `carry_from`'s
+/// uncommitted changes to it, then copy env files and render templates — after the
+/// carry, so a copied file never blocks the stash from applying. postCreate is left to
+/// the caller (`CreatedWorktree::post_create`).
 pub fn create_worktree(
-    repo_path: &PathBuf,
-    default_branch: &str,
+    git: &dyn GitBackend,
+    repo_path: &Path,
     proj_config: &ProjectConfig,
-    branch: &str,
-) -> Result<(PathBuf, Option<String>)> {
-    let wt_path = git_worktree::create_worktree(repo_path, branch, default_branch)?;
+    target: &NewWorktree,
+    carry_from: Option<&Path>,
+) -> Result<CreatedWorktree> {
+    let wt_path = git.create_worktree(repo_path, target)?;
+    let carried = carry_from.map(|from| git.carry_changes(from, &wt_path));
 
     let mut warning: Option<String> = None;
 
-    if let Err(e) = hooks::copy_env_files(repo_path, &wt_path, proj_config) {
+    let mut copy = hooks::copy_env_files(repo_path, &wt_path, proj_config).unwrap_or_else(|e| {
         warning = Some(format!("Warning: .env copy: {}", e));
+        CopySummary::default()
+    });
+    if !proj_config.templates.is_empty() {
+        let repo_name = repo_path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        let slug = canonical_session_slug(&repo_name, &wt_path);
+        let vars = hooks::TemplateVars::new(target.name(), slug, &wt_path, proj_config.template_ports());
+        if let Err(e) = hooks::render_templates(repo_path, &wt_path, proj_config, &vars, &mut copy) {
+            warning = Some(format!("Warning: template: {}", e));
+        }
     }
-    if let Some(ref cmd) = proj_config.post_create {
-        if let Err(e) = hooks::run_post_create(&wt_path, cmd) {
-            warning = Some(format!("Warning: postCreate: {}", e));
+
+    Ok(CreatedWorktree { path: wt_path, copy, warning, post_create: proj_config.post_create.clone(), carried })
+}
+
+/// Confirm text for creating `target`, previewing which env files would be copied.
+pub fn create_worktree_message(repo_path: &Path, proj_config: &ProjectConfig, target: &NewWorktree) -> String {
+    let what = match target {
+        NewWorktree::Branch { name, .. } => format!("worktree '{}'", name),
+        NewWorktree::Detached { refname } => format!("detached worktree at '{}'", refname),
+    };
+    let plan = hooks::plan_env_copy(repo_path, None, proj_config);
+    match plan.map(|p| p.copied) {
+        Ok(files) if !files.is_empty() => {
+            let names: Vec<String> = files.iter().take(3).map(|f| f.display().to_string()).collect();
+            let more = if files.len() > 3 { format!(" +{}", files.len() - 3) } else { String::new() };
+            format!("Create {}? Copies {}{}", what, names.join(", "), more)
         }
+        _ => format!("Create {}?", what),
     }
+}
 
-    Ok((wt_path, warning))
+/// Confirmation text for deleting a worktree, including what its sessions are running.
+/// Returns (message, danger) — danger is set when a session has a client attached.
+pub fn delete_worktree_message(
+    wt_name: &str,
+    merged: bool,
+    session_names: &[String],
+    activity: &HashMap<String, SessionStatus>,
+) -> (String, bool) {
+    let mut msg = if merged {
+        format!("Delete worktree '{}'?", wt_name)
+    } else {
+        format!("Delete UNMERGED worktree '{}'? Changes will be lost!", wt_name)
+    };
+    if session_names.is_empty() {
+        return (msg, false);
+    }
+
+    let statuses: Vec<&SessionStatus> = session_names
+        .iter()
+        .filter_map(|n| activity.get(n))
+        .collect();
+    let running = statuses
+        .iter()
+        .filter(|s| s.has_running_app || !s.commands.is_empty())
+        .count();
+    let mut commands: Vec<&str> = statuses
+        .iter()
+        .flat_map(|s| s.commands.iter().map(String::as_str))
+        .collect();
+    commands.sort_unstable();
+    commands.dedup();
+    let attached = statuses.iter().filter(|s| s.attached).count();
+
+    let count = session_names.len();
+    msg.push_str(&format!(
+        " Kills {} session{}",
+        count,
+        if count == 1 { "" } else { "s" }
+    ));
+    if running > 0 {
+        msg.push_str(&format!(" ({} running: {})", running, commands.join(", ")));
+    }
+    msg.push('.');
+    if attached > 0 {
+        msg.push_str(&format!(
+            " {} attached by a client!",
+            if attached == 1 { "One is".to_string() } else { format!("{} are", attached) }
+        ));
+    }
+    (msg, attached > 0)
+}
+
+/// "diverged from main 47 days ago, 23 commits on main since", for the preview, the
+/// delete confirm and `wsx clean --dry-run`.
+pub fn divergence_text(divergence: &Divergence, now: u64) -> String {
+    let ago = frecency::fmt_age(Duration::from_secs(now.saturating_sub(divergence.base_ts)));
+    let since = match divergence.trunk_since {
+        0 => "nothing".to_string(),
+        1 => "1 commit".to_string(),
+        n => format!("{} commits", n),
+    };
+    format!("diverged from {0} {1} ago, {2} on {0} since", divergence.trunk, ago, since)
 }
 
 /// Remove a git worktree and kill any associated tmux sessions.
+/// `force` discards uncommitted changes; without it a dirty worktree is an error.
+/// `branch` is None for a detached worktree, which has none to delete.
 pub fn delete_worktree(
-    repo_path: &PathBuf,
-    wt_path: &PathBuf,
-    branch: &str,
+    git: &dyn GitBackend,
+    tmux: &dyn TmuxBackend,
+    repo_path: &Path,
+    wt_path: &Path,
+    branch: Option<&str>,
     session_names: &[String],
+    force: bool,
 ) -> Result<()> {
-    git_worktree::remove_worktree(repo_path, wt_path, branch)?;
+    git.remove_worktree(repo_path, wt_path, branch, force)?;
     for sess in session_names {
-        let _ = session::kill_session(sess);
+        let _ = tmux.kill_session(sess);
     }
     Ok(())
 }
 
+/// Archive a worktree: tag its branch tip, remove the worktree and kill its sessions,
+/// then drop the branch — the tag keeps its commits. Returns the tag, and a warning
+/// when the branch couldn't be dropped. A worktree git won't remove (uncommitted
+/// changes) leaves no tag behind.
+pub fn archive_worktree(
+    git: &dyn GitBackend,
+    tmux: &dyn TmuxBackend,
+    repo_path: &Path,
+    wt_path: &Path,
+    branch: &str,
+    session_names: &[String],
+) -> Result<(String, Option<String>)> {
+    let tag = git.archive_branch(repo_path, branch)?;
+    if let Err(e) = git.remove_worktree(repo_path, wt_path, None, false) {
+        let _ = git.delete_tag(repo_path, &tag);
+        return Err(e);
+    }
+    for sess in session_names {
+        let _ = tmux.kill_session(sess);
+    }
+    let warning = git
+        .delete_branch(repo_path, branch)
+        .err()
+        .map(|e| format!("Warning: branch '{}' kept: {}", branch, e));
+    Ok((tag, warning))
+}
+
+/// Bring `archived` back as a worktree on its branch, recreated at the tag, then drop
+/// the tag once the worktree's HEAD is shown to contain it. A branch by that name that
+/// exists again would be checked out instead of the tag, so that's refused.
+pub fn restore_archived(
+    git: &dyn GitBackend,
+    repo_path: &Path,
+    proj_config: &ProjectConfig,
+    archived: &ArchivedWorktree,
+) -> Result<CreatedWorktree> {
+    if git.branch_exists(repo_path, &archived.branch) {
+        bail!(
+            "A branch named '{}' exists — rename or delete it to restore {}",
+            archived.branch,
+            archived.tag
+        );
+    }
+    let target = NewWorktree::Branch { name: archived.branch.clone(), base: archived.tag.clone() };
+    let mut created = create_worktree(git, repo_path, proj_config, &target, None)?;
+    if !git.head_contains(&created.path, &archived.tag) {
+        created.warning = Some(format!("Warning: archive tag kept: '{}' doesn't contain it", archived.branch));
+    } else if let Err(e) = git.delete_tag(repo_path, &archived.tag) {
+        created.warning = Some(format!("Warning: archive tag kept: {}", e));
+    }
+    Ok(created)
+}
+
+/// Re-read `project`'s archive tags, keeping the session names already known for each.
+pub fn reload_archived(git: &dyn GitBackend, project: &mut Project) {
+    let mut archived = git.archived_worktrees(&project.path);
+    for entry in &mut archived {
+        if let Some(prev) = project.archived.iter().find(|p| p.tag == entry.tag) {
+            entry.sessions = prev.sessions.clone();
+        }
+    }
+    project.archived = archived;
+}
+
+/// What `clean_merged` would do: the merged worktrees it removes and those it leaves.
+#[derive(Debug, Default)]
+pub struct CleanPlan {
+    /// (path, branch) of each worktree to remove
+    pub remove: Vec<(PathBuf, String)>,
+    /// merged worktrees left in place because they have uncommitted changes
+    pub skipped_dirty: usize,
+    /// merged worktrees left in place because they are `git worktree lock`ed
+    pub skipped_locked: usize,
+    /// (path, branch) of the worktrees left because their branch isn't merged
+    pub unmerged: Vec<(PathBuf, String)>,
+}
+
+/// Outcome of `clean_merged`.
+#[derive(Debug, Default)]
+pub struct CleanReport {
+    pub removed: Vec<Cleaned>,
+    /// (branch, error) for planned removals git refused
+    pub failed: Vec<(String, String)>,
+    pub skipped_dirty: usize,
+    pub skipped_locked: usize,
+}
+
+/// Every non-main worktree whose branch is merged into `default_branch`, less the
+/// locked ones and those with uncommitted changes. Detached worktrees have no branch to
+/// merge and are never cleaned, nor are those `config`'s `worktree.hide` matches.
+pub fn clean_plan(
+    git: &dyn GitBackend,
+    repo_path: &Path,
+    default_branch: &str,
+    config: Option<&ProjectConfig>,
+) -> Result<CleanPlan> {
+    let merged = git.merged_branches(repo_path, default_branch)?;
+    let entries = git.list_worktrees(repo_path)?;
+    let mut plan = CleanPlan::default();
+
+    for entry in entries.iter().filter(|e| !e.is_main && !e.detached) {
+        if config.is_some_and(|c| c.hides_worktree(&entry.branch, &entry.path)) {
+            continue;
+        }
+        if !merged.contains(&entry.branch) {
+            plan.unmerged.push((entry.path.clone(), entry.branch.clone()));
+            continue;
+        }
+        if entry.locked.is_some() {
+            plan.skipped_locked += 1;
+            continue;
+        }
+        // One git couldn't check is left alone, as if dirty.
+        if !matches!(git.uncommitted_changes(&entry.path), Ok(0)) {
+            plan.skipped_dirty += 1;
+            continue;
+        }
+        plan.remove.push((entry.path.clone(), entry.branch.clone()));
+    }
+
+    Ok(plan)
+}
+
+/// Remove what `plan` lists, each with its branch.
+pub fn execute_clean(git: &dyn GitBackend, repo_path: &Path, plan: CleanPlan) -> CleanReport {
+    let mut report = CleanReport {
+        skipped_dirty: plan.skipped_dirty,
+        skipped_locked: plan.skipped_locked,
+        ..Default::default()
+    };
+    for (path, branch) in plan.remove {
+        // Read before removal — deleting the local branch drops its remote config.
+        let remote = git.remote_branch(repo_path, &branch);
+        match git.remove_worktree(repo_path, &path, Some(&branch), false) {
+            Ok(()) => report.removed.push(Cleaned { path, branch, remote }),
+            Err(e) => report.failed.push((branch, e.to_string())),
+        }
+    }
+    report
+}
+
+/// `c`: `clean_plan`, then `execute_clean`. `wsx clean --all` runs the same two steps.
+pub fn clean_merged(
+    git: &dyn GitBackend,
+    repo_path: &Path,
+    default_branch: &str,
+    config: Option<&ProjectConfig>,
+) -> Result<CleanReport> {
+    let plan = clean_plan(git, repo_path, default_branch, config)?;
+    Ok(execute_clean(git, repo_path, plan))
+}
+
+/// Longest diff the diff popup shows; the rest is cut off with a note.
+pub const DIFF_MAX_LINES: usize = 2000;
+
+/// The path `git status --short` listed, as git diff takes it: a rename's new name,
+/// without the quotes git puts around unusual names.
+pub fn status_path(entry: &str) -> &str {
+    let path = entry.rsplit_once(" -> ").map_or(entry, |(_, new)| new);
+    path.strip_prefix('"').and_then(|p| p.strip_suffix('"')).unwrap_or(path)
+}
+
+/// What the diff popup shows for `raw` (`git diff --color=always` output).
+pub fn diff_text(raw: &str, max_lines: usize) -> String {
+    if raw.trim().is_empty() {
+        return "(no changes against HEAD)".to_string();
+    }
+    // Only the file header says so; a text line reading the same sits inside a hunk.
+    let binary = raw
+        .lines()
+        .map(strip_sgr)
+        .take_while(|l| !l.starts_with("@@"))
+        .any(|l| l.starts_with("Binary files ") && l.ends_with(" differ"));
+    if binary {
+        return "Binary file — no text diff".to_string();
+    }
+    let total = raw.lines().count();
+    if total <= max_lines {
+        return raw.to_string();
+    }
+    let mut text: Vec<&str> = raw.lines().take(max_lines).collect();
+    let note = format!("… {} more lines not shown — run git diff in a session", total - max_lines);
+    text.push(&note);
+    text.join("\n")
+}
+
+/// `line` without its leading colour codes, as `--color=always` starts a line.
+fn strip_sgr(mut line: &str) -> &str {
+    while let Some(rest) = line.strip_prefix("\x1b[") {
+        let Some(end) = rest.find('m') else { break };
+        line = &rest[end + 1..];
+    }
+    line
+}
+
 // ── Session operations ────────────────────────────────────────────────────────
 
+/// Why a name typed for a new session (or `+window`) can't be used, if it can't.
+/// tmux turns '.' and ':' in session names into '_', and wsx would lose track of it.
+pub fn session_name_error(name: &str) -> Option<&'static str> {
+    match name.strip_prefix('+') {
+        Some(window) if window.trim().is_empty() => Some("window name missing after '+'"),
+        Some(_) => None,
+        None if name.contains(['.', ':']) => Some("session names can't contain '.' or ':'"),
+        None => None,
+    }
+}
+
 /// Create a named tmux session at `wt_path` and optionally send an initial command.
-/// Returns (tmux_name, display_name). Tmux name is prefixed with `{proj_name}-{wt_slug}-`;
-/// display_name is the user-visible part (what the user typed).
+/// Returns (tmux_name, display_name). Tmux name is prefixed with `{session_base}-`
+/// (`WorkspaceState::session_base`); display_name is the user-visible part (what the user
+/// typed), and `proj_name` stands in for it when neither a name nor a command is given.
 pub fn create_session(
+    tmux: &dyn TmuxBackend,
     proj_name: &str,
-    wt_slug: &str,
-    wt_path: &PathBuf,
+    session_base: &str,
+    wt_path: &Path,
     session_name: Option<String>,
     command: Option<String>,
+    env: &[(String, String)],
 ) -> Result<(String, String)> {
     // display name priority: explicit > command first word > proj_name
     let base_display = match &session_name {
@@ -397,26 +1184,158 @@ pub fn create_session(
             None => proj_name.to_string(),
         },
     };
-    let base_tmux = format!("{}-{}-{}", proj_name, wt_slug, base_display);
-    let tmux_name = session::unique_session_name(&base_tmux);
-    // strip "{proj_name}-{wt_slug}-" prefix to get display name
-    let prefix_len = proj_name.len() + 1 + wt_slug.len() + 1;
-    let display_name = tmux_name[prefix_len..].to_string();
-    session::create_session(&tmux_name, wt_path)?;
+    let base_tmux = format!("{}-{}", session_base, base_display);
+    let tmux_name = naming::unique_session_name(&base_tmux, |n| tmux.session_exists(n));
+    // strip "{session_base}-" prefix to get display name
+    let prefix = format!("{}-", session_base);
+    let display_name = tmux_name.strip_prefix(&prefix).unwrap_or(&tmux_name).to_string();
+    tmux.create_session(&tmux_name, wt_path, env)?;
+    // marks it as wsx's, for the preview's origin lines
+    tmux.set_session_option(&tmux_name, "@wsx_made", &base_tmux);
     if let Some(cmd) = command {
-        session::send_keys(&tmux_name, &cmd)?;
+        // kept on the session so the preview can say what it was started with
+        tmux.set_session_option(&tmux_name, "@wsx_cmd", &cmd);
+        tmux.send_keys(&tmux_name, &cmd)?;
     }
     Ok((tmux_name, display_name))
 }
 
+/// How `respawn_session` got the session's command running again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Respawn {
+    /// Ctrl+C brought back the shell; the init command was typed again
+    Resent,
+    /// back at the shell, but there's no init command to run
+    Interrupted,
+    /// the pane had exited; `respawn-pane` started a shell over and typed the command
+    Revived,
+    /// Ctrl+C is sent and the command hasn't stopped yet; `respawn_settled` says when it has
+    Stopping,
+}
+
+/// Restart what session `name` runs without replacing the session: Ctrl+C, then `command`
+/// typed again once the shell is back — `Stopping` until then, for `respawn_settled` to
+/// finish. A dead pane is started over with a shell in `start_dir` instead.
+pub fn respawn_session(tmux: &dyn TmuxBackend, name: &str, start_dir: &Path, command: Option<&str>) -> Result<Respawn> {
+    let Some(pane) = tmux.pane_state(name) else { bail!("session {} is gone", name) };
+    if pane.dead {
+        restart_pane(tmux, name, start_dir, command)?;
+        return Ok(Respawn::Revived);
+    }
+    if !pane.at_shell() {
+        tmux.send_ctrl_c(name)?;
+        return Ok(Respawn::Stopping);
+    }
+    resend(tmux, name, command)
+}
+
+/// One look at a `Stopping` respawn: None while the command still runs, else how it ended.
+pub fn respawn_settled(
+    tmux: &dyn TmuxBackend,
+    name: &str,
+    start_dir: &Path,
+    command: Option<&str>,
+) -> Result<Option<Respawn>> {
+    match tmux.pane_state(name) {
+        None => bail!("session {} is gone", name),
+        Some(pane) if pane.dead => restart_pane(tmux, name, start_dir, command).map(|_| Some(Respawn::Revived)),
+        Some(pane) if pane.at_shell() => resend(tmux, name, command).map(Some),
+        Some(_) => Ok(None),
+    }
+}
+
+/// Kill whatever session `name`'s pane runs, start a shell over in `start_dir` and type
+/// `command` into it — so the pane outlives the command, as a fresh session's does.
+pub fn restart_pane(tmux: &dyn TmuxBackend, name: &str, start_dir: &Path, command: Option<&str>) -> Result<()> {
+    tmux.respawn_pane(name, start_dir)?;
+    if let Some(cmd) = command {
+        tmux.send_keys(name, cmd)?;
+    }
+    Ok(())
+}
+
+fn resend(tmux: &dyn TmuxBackend, name: &str, command: Option<&str>) -> Result<Respawn> {
+    match command {
+        Some(cmd) => {
+            tmux.send_keys(name, cmd)?;
+            Ok(Respawn::Resent)
+        }
+        None => Ok(Respawn::Interrupted),
+    }
+}
+
+/// The project's `session.env` with `{worktree_path}` and `{branch}` filled in.
+pub fn session_env(config: Option<&ProjectConfig>, wt_path: &Path, branch: &str) -> Vec<(String, String)> {
+    let path = wt_path.to_string_lossy();
+    config
+        .map(|c| c.session_env.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|(key, value)| {
+            let value = value.replace("{worktree_path}", &path).replace("{branch}", branch);
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+/// Add a window to `session`, named after `name` or the command's first word.
+/// Returns the new window's index.
+pub fn create_window(
+    tmux: &dyn TmuxBackend,
+    session: &str,
+    wt_path: &Path,
+    name: &str,
+    command: Option<String>,
+) -> Result<u32> {
+    let name = match (name, &command) {
+        ("", Some(cmd)) => cmd.split_whitespace().next().unwrap_or(""),
+        _ => name,
+    };
+    let index = tmux.new_window(session, wt_path, name)?;
+    if let Some(cmd) = command {
+        tmux.send_keys(&session::window_target(session, index), &cmd)?;
+    }
+    Ok(index)
+}
+
 /// Kill a tmux session by name.
-pub fn delete_session(name: &str) -> Result<()> {
-    session::kill_session(name)
+pub fn delete_session(tmux: &dyn TmuxBackend, name: &str) -> Result<()> {
+    tmux.kill_session(name)
+}
+
+/// Sessions (tmux name, display name) older than `min_age_secs` that nobody is
+/// using: a bare shell in the foreground, no recent output, no client attached,
+/// not pinned. Limited to project `only` when given.
+pub fn stale_sessions(
+    workspace: &WorkspaceState,
+    only: Option<usize>,
+    activity: &HashMap<String, SessionStatus>,
+    min_age_secs: u64,
+    now: u64,
+) -> Vec<(String, String)> {
+    workspace
+        .projects
+        .iter()
+        .enumerate()
+        .filter(|(pi, _)| only.is_none_or(|o| o == *pi))
+        .flat_map(|(_, p)| p.worktrees.iter().flat_map(|w| &w.sessions))
+        .filter(|s| {
+            let status = activity.get(&s.name);
+            let quiet = s.last_activity.is_none_or(|t| t.elapsed().as_secs() >= IDLE_SECS);
+            s.age_secs(now).is_some_and(|age| age > min_age_secs)
+                && s.commands.is_empty()
+                && !s.has_running_app
+                && !s.pinned
+                && quiet
+                && !status.is_some_and(|st| st.attached)
+        })
+        .map(|s| (s.name.clone(), s.display_name.clone()))
+        .collect()
 }
 
 /// Rename a tmux session from `old_name` to `new_name`.
-pub fn rename_session(old_name: &str, new_name: &str) -> Result<()> {
-    session::rename_session(old_name, new_name)
+pub fn rename_session(tmux: &dyn TmuxBackend, old_name: &str, new_name: &str) -> Result<()> {
+    tmux.rename_session(old_name, new_name)
 }
 
 // ── Alias operations ──────────────────────────────────────────────────────────
@@ -425,3 +1344,581 @@ pub fn rename_session(old_name: &str, new_name: &str) -> Result<()> {
 pub fn set_alias(config: &mut GlobalConfig, proj_path: &PathBuf, branch: &str, alias: &str) {
     config.set_alias(proj_path, branch, alias);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clean_merged, clean_plan, create_session, create_window, create_worktree, delete_worktree_message, diff_text, divergence_text,
+        execute_clean, locate_path, order_projects, orphan_sessions, refresh_workspace, register_project, relocate_project, respawn_session, respawn_settled, restart_pane,
+        session_env, status_path, sync_projects, RegisterProjectError, Respawn,
+    };
+    use crate::config::global::{GlobalConfig, ProjectOrder};
+    use crate::git::{fake::FakeGit, worktree::NewWorktree, CliGit, GitBackend};
+    use crate::model::fixtures;
+    use crate::model::frecency::{self, Frecency};
+    use crate::model::workspace::{canonical_session_slug, Divergence, Project, ProjectConfig, WorkspaceState};
+    use crate::scratch::{git, init_repo, TempDir};
+    use crate::tmux::{fake::FakeTmux, monitor::SessionStatus, TmuxBackend};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    fn status(commands: &[&str], attached: bool) -> SessionStatus {
+        SessionStatus {
+            has_bell: false,
+            last_activity_ts: 0,
+            has_running_app: false,
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            attached,
+            windows: vec![],
+            created_ts: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn carried_changes_land_before_env_files_are_copied() {
+        let dir = TempDir::new("wsx-carry-copy");
+        let repo = dir.join("app");
+        init_repo(&repo);
+        std::fs::write(repo.join("README.md"), "app").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-qm", "init"]);
+        // Both an uncommitted change to carry and a file `copy.include` picks up.
+        std::fs::write(repo.join(".env"), "TOKEN=mine").unwrap();
+        let config = ProjectConfig { copy_includes: vec![".env".into()], ..Default::default() };
+        let target = NewWorktree::parse("feat/x", "main");
+        let created = create_worktree(&CliGit, &repo, &config, &target, Some(&repo)).unwrap();
+        assert_eq!(created.carried.unwrap().unwrap(), 1, "the copy didn't get in the stash's way");
+        assert_eq!(std::fs::read_to_string(created.path.join(".env")).unwrap(), "TOKEN=mine");
+    }
+
+    fn names(n: &[&str]) -> Vec<String> {
+        n.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn delete_message_without_sessions() {
+        let (msg, danger) = delete_worktree_message("wsx-auth", true, &[], &HashMap::new());
+        assert_eq!(msg, "Delete worktree 'wsx-auth'?");
+        assert!(!danger);
+    }
+
+    #[test]
+    fn delete_message_lists_running_commands() {
+        let mut activity = HashMap::new();
+        activity.insert("a".to_string(), status(&["node"], false));
+        activity.insert("b".to_string(), status(&["cargo", "node"], false));
+        activity.insert("c".to_string(), status(&[], false));
+        let (msg, danger) =
+            delete_worktree_message("wsx-auth", false, &names(&["a", "b", "c"]), &activity);
+        assert_eq!(
+            msg,
+            "Delete UNMERGED worktree 'wsx-auth'? Changes will be lost! \
+             Kills 3 sessions (2 running: cargo, node)."
+        );
+        assert!(!danger);
+    }
+
+    #[test]
+    fn delete_message_escalates_when_attached() {
+        let mut activity = HashMap::new();
+        activity.insert("a".to_string(), status(&[], true));
+        let (msg, danger) = delete_worktree_message("wsx-auth", true, &names(&["a"]), &activity);
+        assert_eq!(
+            msg,
+            "Delete worktree 'wsx-auth'? Kills 1 session. One is attached by a client!"
+        );
+        assert!(danger);
+    }
+
+    #[test]
+    fn located_paths_go_to_the_deepest_worktree_through_symlinks() {
+        let root = std::env::temp_dir().join(format!("wsx-locate-{}", std::process::id()));
+        let (repo, nested) = (root.join("api"), root.join("api/.worktrees/fix"));
+        std::fs::create_dir_all(nested.join("src")).unwrap();
+        std::fs::write(nested.join("src/lib.rs"), "").unwrap();
+        std::os::unix::fs::symlink(&repo, root.join("link")).unwrap();
+        let git = FakeGit::default();
+        git.add_repo(&repo, "main");
+        git.add_worktree_at(&repo, "fix", &nested);
+        let mut config = GlobalConfig::default();
+        config.add_project("api".into(), repo.clone());
+        let mut workspace = WorkspaceState { projects: vec![project("api", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.clone();
+        refresh_workspace(&git, &mut workspace, &config, &[], &HashMap::new());
+
+        let found = |input: &str| locate_path(&workspace, input).map(|l| (l.worktree_idx, l.relative, l.position));
+        let linked = format!("{}/.worktrees/fix/src/lib.rs:42:7", root.join("link").display());
+        assert_eq!(found(&linked), Some((1, PathBuf::from("src/lib.rs"), ":42:7".into())));
+        assert_eq!(found(&format!("{}/README.md", repo.display())), Some((0, "README.md".into(), String::new())));
+        assert_eq!(found(&format!(" {}/src/gone.rs ", nested.display())), Some((1, "src/gone.rs".into(), String::new())));
+        assert_eq!(found(&format!("{}/api-old/src/lib.rs", root.display())), None, "a deleted sibling worktree");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn divergence_reads_in_days_then_weeks() {
+        let day = 86_400;
+        let now = 1_000 * day;
+        let forked = |days_ago: u64, trunk_since: usize| Divergence {
+            trunk: "main".into(),
+            base_ts: now - days_ago * day,
+            trunk_since,
+        };
+        assert_eq!(divergence_text(&forked(47, 23), now), "diverged from main 47 days ago, 23 commits on main since");
+        assert_eq!(divergence_text(&forked(1, 1), now), "diverged from main 1 day ago, 1 commit on main since");
+        assert_eq!(divergence_text(&forked(70, 0), now), "diverged from main 10 weeks ago, nothing on main since");
+        let hours = Divergence { base_ts: now - 5 * 3600, ..forked(0, 2) };
+        assert_eq!(divergence_text(&hours, now), "diverged from main 5h ago, 2 commits on main since");
+    }
+
+    fn project(name: &str, score: f64) -> Project {
+        Project {
+            path: PathBuf::from(format!("/tmp/{}", name)),
+            frecency: Frecency { score, updated: frecency::now_secs() },
+            ..fixtures::project(name)
+        }
+    }
+
+    fn order(workspace: &WorkspaceState) -> Vec<&str> {
+        workspace.projects.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn frecency_order_round_trips_to_manual() {
+        let mut config = GlobalConfig::default();
+        for name in ["a", "b", "c", "d"] {
+            config.add_project(name.to_string(), PathBuf::from(format!("/tmp/{}", name)));
+        }
+        let mut workspace = WorkspaceState {
+            projects: vec![project("a", 0.0), project("b", 1.0), project("c", 0.0), project("d", 3.0)],
+            ..Default::default()
+        };
+
+        config.project_order = ProjectOrder::Frecency;
+        order_projects(&mut workspace, &config);
+        // Unused projects keep their manual order after the scored ones.
+        assert_eq!(order(&workspace), ["d", "b", "a", "c"]);
+
+        config.project_order = ProjectOrder::Manual;
+        order_projects(&mut workspace, &config);
+        assert_eq!(order(&workspace), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn sessions_follow_their_path_when_worktree_slugs_collide() {
+        let git = FakeGit::default();
+        let repo = Path::new("/tmp/wsx-test/app");
+        git.add_repo(repo, "main");
+        // Both directories sanitize to the slug "feat-x".
+        let dotted = git.add_worktree(repo, "feat.x");
+        let dashed = git.add_worktree(repo, "feat-x");
+        let mut config = GlobalConfig::default();
+        config.add_project("app".into(), repo.to_path_buf());
+        let mut workspace = WorkspaceState { projects: vec![project("app", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.to_path_buf();
+
+        let sessions = vec![
+            ("app-feat-x-dev".to_string(), dashed.clone()),
+            ("app-feat-x-dev_2".to_string(), dotted.clone()),
+        ];
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &HashMap::new());
+
+        let project = &workspace.projects[0];
+        let names = |path: &Path| -> Vec<String> {
+            let wt = project.worktrees.iter().find(|w| w.path == path).unwrap();
+            wt.sessions.iter().map(|s| s.display_name.clone()).collect()
+        };
+        assert_eq!(names(&dotted), ["dev_2"]);
+        assert_eq!(names(&dashed), ["dev"]);
+        // …and an alias can't make a third worktree look like them.
+        assert_eq!(project.alias_conflict(0, "Feat-X").map(|w| w.branch.as_str()), Some("feat.x"));
+        assert!(project.alias_conflict(0, "api").is_none());
+    }
+
+    #[test]
+    fn sessions_match_worktrees_listed_through_a_symlink() {
+        let base = std::env::temp_dir().join(format!("wsx-symlink-{}", std::process::id()));
+        let real = base.join("real");
+        std::fs::create_dir_all(real.join("sub")).unwrap();
+        let link = base.join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let git = FakeGit::default();
+        git.add_repo(&link, "main"); // git lists the path the repo was opened by
+        let mut config = GlobalConfig::default();
+        config.add_project("app".into(), link.clone());
+        let mut workspace = WorkspaceState { projects: vec![project("app", 0.0)], ..Default::default() };
+        workspace.projects[0].path = link.clone();
+
+        // tmux reports physical paths.
+        let physical = std::fs::canonicalize(&real).unwrap();
+        let sessions = vec![
+            ("app-main-dev".to_string(), physical.clone()),
+            ("app-main-sub".to_string(), physical.join("sub")),
+            ("elsewhere".to_string(), base.join("gone")),
+        ];
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &HashMap::new());
+        let main = &workspace.projects[0].worktrees[0];
+        assert_eq!(main.sessions.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["app-main-dev"]);
+
+        let orphans = orphan_sessions(&workspace, &sessions);
+        let reasons: Vec<(&str, &str)> = orphans.iter().map(|o| (o.name.as_str(), o.reason.as_str())).collect();
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(reasons[0].0, "app-main-sub");
+        assert!(reasons[0].1.starts_with("started in a subdirectory of 'app' worktree"), "{}", reasons[0].1);
+        assert_eq!(reasons[1], ("elsewhere", "its directory no longer exists"));
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn default_branch_override_follows_the_config() {
+        let git = FakeGit::default();
+        let repo = Path::new("/tmp/wsx-test/branch");
+        git.add_repo(repo, "main");
+        let mut config = GlobalConfig::default();
+        config.add_project("branch".into(), repo.to_path_buf());
+        let mut workspace = WorkspaceState { projects: vec![project("branch", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.to_path_buf();
+
+        config.set_default_branch(repo, Some("develop".into()));
+        sync_projects(&git, &mut workspace, &config);
+        let project = &workspace.projects[0];
+        assert_eq!((project.default_branch.as_str(), project.default_branch_override), ("develop", true));
+
+        config.set_default_branch(repo, None);
+        sync_projects(&git, &mut workspace, &config);
+        let project = &workspace.projects[0];
+        assert_eq!((project.default_branch.as_str(), project.default_branch_override), ("main", false));
+    }
+
+    #[test]
+    fn alias_pattern_names_worktrees_without_an_explicit_alias() {
+        let git = FakeGit::default();
+        let repo = Path::new("/tmp/wsx-test/pattern");
+        git.add_repo(repo, "main");
+        git.add_worktree(repo, "feature/PROJ-12-login");
+        git.add_worktree(repo, "feature/PROJ-34-logout");
+        let mut config = GlobalConfig::default();
+        config.add_project("pattern".into(), repo.to_path_buf());
+        config.set_alias(&repo.to_path_buf(), "feature/PROJ-34-logout", "bye");
+        let mut workspace = WorkspaceState { projects: vec![project("pattern", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.to_path_buf();
+        workspace.projects[0].config = Some(ProjectConfig {
+            alias_pattern: Some(regex::Regex::new(r"^\w+/(?P<alias>[A-Z]+-\d+)").unwrap()),
+            ..Default::default()
+        });
+
+        refresh_workspace(&git, &mut workspace, &config, &[], &HashMap::new());
+        let aliases: Vec<(Option<&str>, bool)> = workspace.projects[0]
+            .worktrees
+            .iter()
+            .map(|w| (w.alias.as_deref(), w.alias_derived))
+            .collect();
+        assert_eq!(aliases, [(None, false), (Some("PROJ-12"), true), (Some("bye"), false)]);
+    }
+
+    #[test]
+    fn refresh_merges_live_state_into_existing_tree() {
+        let git = FakeGit::default();
+        let repo = Path::new("/tmp/wsx-test/ops");
+        git.add_repo(repo, "main");
+        let feature = git.add_worktree(repo, "feature");
+        let mut config = GlobalConfig::default();
+        config.add_project("ops".into(), repo.to_path_buf());
+        config.set_alias(&repo.to_path_buf(), "feature", "feat");
+        let mut workspace = WorkspaceState { projects: vec![project("ops", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.to_path_buf();
+
+        let sessions = vec![
+            ("ops-ops-x".to_string(), repo.to_path_buf()),
+            ("ops-ops-y".to_string(), repo.to_path_buf()),
+            ("ops-feature-z".to_string(), feature.clone()),
+        ];
+        let mut activity = HashMap::new();
+        activity.insert("ops-ops-y".to_string(), SessionStatus { has_bell: true, ..Default::default() });
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &activity);
+
+        let wts = &workspace.projects[0].worktrees;
+        assert_eq!(wts.len(), 2);
+        assert_eq!(wts[1].display_name(), "feat");
+        assert_eq!(wts[1].sessions[0].display_name, "z");
+        assert!(wts[0].sessions[1].has_activity);
+
+        // Local state the user set must survive the next refresh.
+        {
+            let main = &mut workspace.projects[0].worktrees[0];
+            main.expanded = false;
+            main.sessions.swap(0, 1);
+            main.sessions[0].muted = true;
+            main.sessions[1].pane_capture = Some("$ ls".into());
+        }
+        git.add_worktree(repo, "new");
+        git.remove_worktree(repo, &feature, Some("feature"), false).unwrap();
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &activity);
+
+        let wts = &workspace.projects[0].worktrees;
+        let branches: Vec<&str> = wts.iter().map(|w| w.branch.as_str()).collect();
+        assert_eq!(branches, ["main", "new"]);
+        assert!(!wts[0].expanded);
+        let names: Vec<&str> = wts[0].sessions.iter().map(|s| s.display_name.as_str()).collect();
+        assert_eq!(names, ["y", "x"], "manual session order kept");
+        assert!(wts[0].sessions[0].muted && !wts[0].sessions[0].has_activity, "muted ignores bells");
+        assert_eq!(wts[0].sessions[1].pane_capture.as_deref(), Some("$ ls"));
+        assert!(wts[1].expanded, "new worktrees start expanded");
+    }
+
+    #[test]
+    fn clean_merged_removes_only_merged_linked_worktrees() {
+        let git = FakeGit::default();
+        let repo = Path::new("/tmp/wsx-test/clean");
+        git.add_repo(repo, "main");
+        git.add_worktree(repo, "done");
+        git.add_worktree(repo, "wip");
+        let dirty = git.add_worktree(repo, "edited");
+        git.set_merged(repo, "main");
+        git.set_merged(repo, "done");
+        git.set_merged(repo, "edited");
+        git.set_dirty(&dirty, 2);
+        git.set_remote(repo, "done");
+        git.add_worktree(repo, "ci-cache-1");
+        git.set_merged(repo, "ci-cache-1");
+        let config = ProjectConfig { worktree_hide: vec!["ci-cache-*".into()], ..Default::default() };
+
+        let report = clean_merged(&git, repo, "main", Some(&config)).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.skipped_dirty, 1);
+        let cleaned = &report.removed;
+        assert_eq!(cleaned[0].branch, "done");
+        assert_eq!(cleaned[0].remote.as_ref().map(|r| r.to_string()).as_deref(), Some("origin/done"));
+        assert_eq!(git.branches(repo), ["main", "wip", "edited", "ci-cache-1"], "hidden worktrees are never cleaned");
+        assert!(clean_merged(&git, repo, "main", Some(&config)).unwrap().removed.is_empty());
+
+        // A worktree that goes between planning and removal is reported, not skipped silently.
+        let late = git.add_worktree(repo, "late");
+        git.set_merged(repo, "late");
+        let plan = clean_plan(&git, repo, "main", Some(&config)).unwrap();
+        assert_eq!(plan.remove, [(late.clone(), "late".to_string())]);
+        git.remove_worktree(repo, &late, None, false).unwrap();
+        let report = execute_clean(&git, repo, plan);
+        assert!(report.removed.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "late");
+    }
+
+    #[test]
+    fn diff_text_notes_binaries_and_cuts_long_diffs() {
+        assert_eq!(diff_text("", 10), "(no changes against HEAD)");
+        assert_eq!(diff_text("diff --git a/x.png b/x.png\nBinary files a/x.png and b/x.png differ", 10), "Binary file — no text diff");
+        let colored = "\x1b[1mdiff --git a/x.png b/x.png\x1b[m\nBinary files a/x.png and b/x.png differ";
+        assert_eq!(diff_text(colored, 10), "Binary file — no text diff");
+        let quoting = "diff --git a/t.txt b/t.txt\n@@ -1 +1 @@\n-x\n+Binary files a and b differ";
+        assert_eq!(diff_text(quoting, 10), quoting, "a text line mentioning it isn't a binary diff");
+        let long: Vec<String> = (0..12).map(|i| format!("+{}", i)).collect();
+        let cut = diff_text(&long.join("\n"), 10);
+        assert_eq!(cut.lines().count(), 11);
+        assert!(cut.ends_with("… 2 more lines not shown — run git diff in a session"), "{}", cut);
+        assert_eq!(diff_text("+a\n+b", 10), "+a\n+b");
+
+        assert_eq!(status_path("src/main.rs"), "src/main.rs");
+        assert_eq!(status_path("old name.rs -> \"new name.rs\""), "new name.rs");
+    }
+
+    #[test]
+    fn respawn_reruns_the_command_or_restarts_the_pane() {
+        let tmux = FakeTmux::default();
+        let dir = Path::new("/tmp/wsx-test/respawn");
+        tmux.add_session("app-main-npm", dir);
+        let sent = |tmux: &FakeTmux| tmux.sent.lock().unwrap().drain(..).map(|(_, k)| k).collect::<Vec<_>>();
+
+        tmux.set_pane("app-main-npm", "npm", false);
+        assert_eq!(respawn_session(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap(), Respawn::Stopping);
+        assert_eq!(sent(&tmux), ["C-c"]);
+        assert_eq!(respawn_settled(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap(), Some(Respawn::Resent));
+        assert_eq!(sent(&tmux), ["npm run dev"]);
+        assert_eq!(respawn_session(&tmux, "app-main-npm", dir, None).unwrap(), Respawn::Interrupted);
+        assert!(sent(&tmux).is_empty(), "an idle shell gets neither Ctrl+C nor a command");
+
+        tmux.set_pane("app-main-npm", "npm", true);
+        assert_eq!(respawn_session(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap(), Respawn::Revived);
+        assert_eq!(sent(&tmux), ["npm run dev"], "typed into a fresh shell, which outlives it");
+        assert_eq!(tmux.pane_state("app-main-npm").unwrap().command, "zsh");
+
+        tmux.set_pane("app-main-npm", "npm", false);
+        tmux.ignores_ctrl_c.lock().unwrap().push("app-main-npm".into());
+        assert_eq!(respawn_session(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap(), Respawn::Stopping);
+        assert_eq!(respawn_settled(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap(), None);
+        assert_eq!(tmux.respawned.lock().unwrap().len(), 1, "nothing killed without asking");
+        restart_pane(&tmux, "app-main-npm", dir, Some("npm run dev")).unwrap();
+        let respawned = tmux.respawned.lock().unwrap().clone();
+        let expected = ("app-main-npm".to_string(), dir.to_path_buf());
+        assert_eq!(respawned, [expected.clone(), expected]);
+        assert_eq!(tmux.session_names(), ["app-main-npm"], "same session throughout");
+        assert!(respawn_session(&tmux, "gone", dir, None).is_err());
+        assert!(respawn_settled(&tmux, "gone", dir, None).is_err());
+    }
+
+    #[test]
+    fn create_session_picks_unique_name_and_sends_command() {
+        let tmux = FakeTmux::default();
+        let path = Path::new("/tmp/wsx-test/sess");
+        let first = create_session(&tmux, "wsx", "wsx-main", path, None, Some("cargo watch".into()), &[]).unwrap();
+        assert_eq!(first, ("wsx-main-cargo".to_string(), "cargo".to_string()));
+        let second = create_session(&tmux, "wsx", "wsx-main", path, None, Some("cargo test".into()), &[]).unwrap();
+        assert_eq!(second.1, "cargo_2");
+        assert!(tmux.session_exists("wsx-main-cargo_2"));
+        assert_eq!(tmux.sent.lock().unwrap()[1], ("wsx-main-cargo_2".into(), "cargo test".into()));
+        let origin = tmux.session_activity().unwrap()["wsx-main-cargo_2"].origin();
+        assert_eq!(origin.start_dir.as_deref(), Some(path));
+        assert_eq!(origin.init_command.as_deref(), Some("cargo test"), "recorded for the preview");
+        assert_eq!(origin.current_dir, None, "still where it started");
+    }
+
+    #[test]
+    fn multibyte_project_and_session_names_round_trip() {
+        let git = FakeGit::default();
+        let tmux = FakeTmux::default();
+        let repo = Path::new("/tmp/wsx-test/日本-app");
+        git.add_repo(repo, "main");
+        let feature = git.add_worktree(repo, "機能");
+        let slug = canonical_session_slug("日本-app", &feature);
+        let (tmux_name, display) =
+            create_session(&tmux, "日本-app", &format!("日本-app-{}", slug), &feature, Some("🚀 deploy".into()), None, &[]).unwrap();
+        assert_eq!(tmux_name, "日本-app-機能-🚀 deploy");
+        assert_eq!(display, "🚀 deploy");
+
+        let mut config = GlobalConfig::default();
+        config.add_project("日本-app".into(), repo.to_path_buf());
+        let mut workspace = WorkspaceState { projects: vec![project("日本-app", 0.0)], ..Default::default() };
+        workspace.projects[0].path = repo.to_path_buf();
+        let sessions = vec![(tmux_name, feature.clone())];
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &HashMap::new());
+        assert_eq!(workspace.projects[0].worktrees[1].sessions[0].display_name, "🚀 deploy");
+    }
+
+    #[test]
+    fn projects_sharing_a_name_get_distinct_session_names() {
+        let git = FakeGit::default();
+        let tmux = FakeTmux::default();
+        let (work, home) = (Path::new("/tmp/wsx-test/work/api"), Path::new("/tmp/wsx-test/home/api"));
+        let mut config = GlobalConfig::default();
+        let mut workspace = WorkspaceState::default();
+        for repo in [work, home] {
+            git.add_repo(repo, "main");
+            config.add_project("api".into(), repo.to_path_buf());
+            let mut p = project("api", 0.0);
+            p.path = repo.to_path_buf();
+            workspace.projects.push(p);
+        }
+        refresh_workspace(&git, &mut workspace, &config, &[], &HashMap::new());
+        assert_ne!(workspace.session_prefix(0), workspace.session_prefix(1));
+        assert!(workspace.session_prefix(0).starts_with("api_"));
+
+        let mut sessions = Vec::new();
+        for (pi, repo) in [(0, work), (1, home)] {
+            let base = workspace.session_base(pi, 0);
+            let (name, display) = create_session(&tmux, "api", &base, repo, Some("dev".into()), None, &[]).unwrap();
+            assert_eq!(display, "dev", "no _2 — the names don't clash");
+            sessions.push((name, repo.to_path_buf()));
+        }
+        assert_ne!(sessions[0].0, sessions[1].0);
+        // one made before the prefix was tagged
+        sessions.push(("api-api-old".into(), home.to_path_buf()));
+        refresh_workspace(&git, &mut workspace, &config, &sessions, &HashMap::new());
+        let names = |pi: usize| -> Vec<(String, String)> {
+            workspace.projects[pi].worktrees[0].sessions.iter().map(|s| (s.name.clone(), s.display_name.clone())).collect()
+        };
+        assert_eq!(names(0), [(sessions[0].0.clone(), "dev".into())]);
+        assert_eq!(names(1), [(sessions[1].0.clone(), "dev".into()), ("api-api-old".into(), "old".into())]);
+
+        // With the namesake gone the prefix is the bare name again; tagged names still read.
+        workspace.projects.remove(0);
+        assert_eq!(workspace.session_prefix(0), "api");
+        config.remove_project(&work.to_path_buf());
+        refresh_workspace(&git, &mut workspace, &config, &sessions[1..], &HashMap::new());
+        assert_eq!(workspace.projects[0].worktrees[0].sessions[0].display_name, "dev");
+    }
+
+    #[test]
+    fn sessions_get_project_env_with_placeholders_filled() {
+        let tmux = FakeTmux::default();
+        let path = Path::new("/tmp/wsx-test/env");
+        let config = ProjectConfig {
+            session_env: vec![
+                ("AWS_PROFILE".into(), "dev".into()),
+                ("LOG_DIR".into(), "{worktree_path}/logs/{branch}".into()),
+            ],
+            ..Default::default()
+        };
+        let env = session_env(Some(&config), path, "feat/x");
+        assert_eq!(env[1].1, "/tmp/wsx-test/env/logs/feat/x");
+        assert!(session_env(None, path, "main").is_empty());
+        let (name, _) = create_session(&tmux, "wsx", "wsx-main", path, None, None, &env).unwrap();
+        assert_eq!(tmux.env.lock().unwrap()[&name], env);
+        assert!(tmux.sent.lock().unwrap().is_empty(), "nothing typed into the shell");
+    }
+
+    #[test]
+    fn create_window_names_it_after_command_and_targets_it() {
+        let tmux = FakeTmux::default();
+        let path = Path::new("/tmp/wsx-test/win");
+        tmux.add_session("wsx-main-dev", path);
+        let index = create_window(&tmux, "wsx-main-dev", path, "", Some("cargo watch".into())).unwrap();
+        assert_eq!(index, 1);
+        let windows = &tmux.session_activity().unwrap()["wsx-main-dev"].windows;
+        assert_eq!(windows[1].name, "cargo");
+        assert_eq!(tmux.sent.lock().unwrap()[0], ("wsx-main-dev:1".into(), "cargo watch".into()));
+        assert!(create_window(&tmux, "nope", path, "x", None).is_err());
+    }
+
+    #[test]
+    fn registering_a_worktree_points_at_the_main_repo() {
+        let base = std::env::temp_dir().join(format!("wsx-register-{}", std::process::id()));
+        let (repo, wt) = (base.join("api"), base.join("api-feature-x"));
+        std::fs::create_dir_all(repo.join(".git/worktrees/feature-x")).unwrap();
+        std::fs::create_dir_all(&wt).unwrap();
+        std::fs::write(repo.join(".git/worktrees/feature-x/commondir"), "../..\n").unwrap();
+        let gitdir = repo.join(".git/worktrees/feature-x");
+        std::fs::write(wt.join(".git"), format!("gitdir: {}\n", gitdir.display())).unwrap();
+        let git = FakeGit::default();
+        let mut config = GlobalConfig::default();
+
+        let err = register_project(&git, wt.clone(), &mut config).unwrap_err();
+        match err.downcast_ref() {
+            Some(RegisterProjectError::LinkedWorktree { main, .. }) => {
+                assert_eq!(main, &std::fs::canonicalize(&repo).unwrap())
+            }
+            other => panic!("expected LinkedWorktree, got {:?}", other),
+        }
+        assert!(config.projects.is_empty());
+
+        register_project(&git, repo.clone(), &mut config).unwrap();
+        let again = register_project(&git, base.join("./api"), &mut config).unwrap_err();
+        assert!(matches!(
+            again.downcast_ref(),
+            Some(RegisterProjectError::AlreadyRegistered { name, .. }) if name == "api"
+        ));
+        assert_eq!(config.projects.len(), 1);
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn relocating_a_missing_project_keeps_its_entry() {
+        let base = std::env::temp_dir().join(format!("wsx-relocate-{}", std::process::id()));
+        let (old, new) = (base.join("old/api"), base.join("new/api"));
+        std::fs::create_dir_all(new.join(".git")).unwrap();
+        let git = FakeGit::default();
+        let mut config = GlobalConfig::default();
+        config.add_project("api".into(), old.clone());
+        config.set_alias(&old, "feature", "web");
+        let mut project = super::load_project(&git, &config.projects[0]);
+        assert!(project.missing);
+
+        assert!(relocate_project(&git, &mut config, &mut project, base.join("old")).is_err());
+        relocate_project(&git, &mut config, &mut project, new.clone()).unwrap();
+        assert!(!project.missing);
+        assert_eq!(project.path, new);
+        assert_eq!(config.projects[0].path, new);
+        assert_eq!(config.projects[0].aliases.get("feature").map(String::as_str), Some("web"));
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
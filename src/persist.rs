@@ -0,0 +1,51 @@
+// File persistence shared by config + cache — several wsx instances may write
+// the same files, so writes are locked and atomic.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Write `content` via a temp file + rename so a crash mid-write can't leave
+/// a truncated file behind.
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = sibling(path, &format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// Run `f` while holding an exclusive advisory lock on `.{name}.lock` next to `path`.
+/// The lock lives in a separate file because `write_atomic` replaces the target inode.
+pub fn with_lock<R>(path: &Path, f: impl FnOnce() -> R) -> io::Result<R> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, "lock"))?;
+    lock.lock()?;
+    let result = f();
+    let _ = lock.unlock();
+    Ok(result)
+}
+
+/// Modification time, used to notice writes from other instances.
+pub fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// `~/.cache/wsx/workspace.toml` + "lock" → `~/.cache/wsx/.workspace.toml.lock`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
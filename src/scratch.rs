@@ -0,0 +1,62 @@
+// Test scratch space: a temp directory that's removed when dropped — a failing assert
+// included — and git run in it under a fixed identity.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `{name} {pid}` under the temp dir, emptied first in case an aborted run left it.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{} {}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// `git -C dir args…`; whether it succeeded.
+pub fn git_ok(dir: &Path, args: &[&str]) -> bool {
+    command(dir, args).output().unwrap().status.success()
+}
+
+/// `git -C dir args…`, which must succeed; its trimmed stdout.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let out = command(dir, args).output().unwrap();
+    assert!(out.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&out.stderr));
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
+
+/// A new repo on `main` at `dir` (created if need be), with no commits yet. The
+/// identity goes in its config too, for commits wsx itself makes there.
+pub fn init_repo(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "-q", "-b", "main"]);
+    git(dir, &["config", "user.name", "wsx"]);
+    git(dir, &["config", "user.email", "wsx@example.com"]);
+}
+
+fn command(dir: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=wsx", "-c", "user.email=wsx@example.com"])
+        .args(args);
+    cmd
+}
@@ -0,0 +1,112 @@
+// TmuxBackend — the tmux queries and session operations ops/App perform,
+// behind a trait so they can run against an in-memory fake in tests.
+// Attaching stays a free function in `session`: it hands the terminal over.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{
+    capture,
+    monitor::{self, SessionStatus},
+    session::{self, PaneState},
+    NoServer,
+};
+
+pub trait TmuxBackend: Send + Sync {
+    /// (session_name, session_path) for every live session.
+    fn list_sessions_with_paths(&self) -> Result<Vec<(String, PathBuf)>, NoServer>;
+    fn session_activity(&self) -> Result<HashMap<String, SessionStatus>, NoServer>;
+    fn session_exists(&self, name: &str) -> bool;
+    /// A client is showing `name` — one cheap query, for when activity isn't polled.
+    fn session_attached(&self, name: &str) -> bool;
+    /// `env` is set in the new session's environment.
+    fn create_session(&self, name: &str, start_dir: &Path, env: &[(String, String)]) -> Result<()>;
+    fn kill_session(&self, name: &str) -> Result<()>;
+    /// New detached window in `session`; returns its index.
+    fn new_window(&self, session: &str, start_dir: &Path, name: &str) -> Result<u32>;
+    /// `target` is `session:index`.
+    fn kill_window(&self, target: &str) -> Result<()>;
+    fn rename_session(&self, old_name: &str, new_name: &str) -> Result<()>;
+    fn send_keys(&self, session: &str, keys: &str) -> Result<()>;
+    /// A session option, e.g. `@wsx_cmd`; read back through `session_activity`.
+    fn set_session_option(&self, session: &str, key: &str, value: &str);
+    /// `tmux_defaults` entries, each a `set-option` argument list; returns those rejected.
+    fn set_options(&self, session: &str, entries: &[String]) -> Vec<String>;
+    fn send_ctrl_c(&self, session: &str) -> Result<()>;
+    fn capture_pane(&self, session: &str) -> Option<String>;
+    /// None when the session is gone.
+    fn pane_state(&self, target: &str) -> Option<PaneState>;
+    /// Kill the pane's process and start a shell in `start_dir` in its place.
+    fn respawn_pane(&self, target: &str, start_dir: &Path) -> Result<()>;
+}
+
+/// The real thing: shells out to the `tmux` binary.
+pub struct CliTmux;
+
+impl TmuxBackend for CliTmux {
+    fn list_sessions_with_paths(&self) -> Result<Vec<(String, PathBuf)>, NoServer> {
+        session::list_sessions_with_paths()
+    }
+
+    fn session_activity(&self) -> Result<HashMap<String, SessionStatus>, NoServer> {
+        monitor::session_activity()
+    }
+
+    fn session_exists(&self, name: &str) -> bool {
+        session::session_exists(name)
+    }
+
+    fn session_attached(&self, name: &str) -> bool {
+        session::session_attached(name)
+    }
+
+    fn create_session(&self, name: &str, start_dir: &Path, env: &[(String, String)]) -> Result<()> {
+        session::create_session(name, start_dir, env)
+    }
+
+    fn kill_session(&self, name: &str) -> Result<()> {
+        session::kill_session(name)
+    }
+
+    fn new_window(&self, session: &str, start_dir: &Path, name: &str) -> Result<u32> {
+        session::new_window(session, start_dir, name)
+    }
+
+    fn kill_window(&self, target: &str) -> Result<()> {
+        session::kill_window(target)
+    }
+
+    fn rename_session(&self, old_name: &str, new_name: &str) -> Result<()> {
+        session::rename_session(old_name, new_name)
+    }
+
+    fn send_keys(&self, session: &str, keys: &str) -> Result<()> {
+        session::send_keys(session, keys)
+    }
+
+    fn set_session_option(&self, session: &str, key: &str, value: &str) {
+        session::set_session_opt(session, key, value)
+    }
+
+    fn set_options(&self, session: &str, entries: &[String]) -> Vec<String> {
+        session::set_options(session, entries)
+    }
+
+    fn send_ctrl_c(&self, session: &str) -> Result<()> {
+        session::send_ctrl_c(session)
+    }
+
+    fn capture_pane(&self, session: &str) -> Option<String> {
+        capture::capture_pane(session)
+    }
+
+    fn pane_state(&self, target: &str) -> Option<PaneState> {
+        session::pane_state(target)
+    }
+
+    fn respawn_pane(&self, target: &str, start_dir: &Path) -> Result<()> {
+        session::respawn_pane(target, start_dir)
+    }
+}
@@ -0,0 +1,205 @@
+// In-memory TmuxBackend for tests: sessions plus whatever activity a test sets.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+
+use super::{backend::TmuxBackend, monitor::SessionStatus, session::{self, PaneState}, NoServer};
+use crate::model::workspace::WindowInfo;
+
+#[derive(Default)]
+pub struct FakeTmux {
+    sessions: Mutex<Vec<(String, PathBuf)>>,
+    activity: Mutex<HashMap<String, SessionStatus>>,
+    /// (session, keys) in the order they were sent
+    pub sent: Mutex<Vec<(String, String)>>,
+    /// environment each session was created with
+    pub env: Mutex<HashMap<String, Vec<(String, String)>>>,
+    /// the server was killed; creating a session starts it again
+    down: Mutex<bool>,
+    /// foreground command per session; a plain `zsh` when unset
+    panes: Mutex<HashMap<String, PaneState>>,
+    /// sessions whose foreground command shrugs off Ctrl+C
+    pub ignores_ctrl_c: Mutex<Vec<String>>,
+    /// (session, start dir) per `respawn_pane`
+    pub respawned: Mutex<Vec<(String, PathBuf)>>,
+    /// (session, words) per option `set_options` accepted
+    pub options: Mutex<Vec<(String, Vec<String>)>>,
+    /// option names `set_options` refuses, as tmux does unknown ones
+    pub unknown_options: Mutex<Vec<String>>,
+}
+
+impl FakeTmux {
+    pub fn add_session(&self, name: &str, path: &Path) {
+        self.sessions.lock().unwrap().push((name.to_string(), path.to_path_buf()));
+    }
+
+    pub fn set_status(&self, name: &str, status: SessionStatus) {
+        self.activity.lock().unwrap().insert(name.to_string(), status);
+    }
+
+    /// Like `tmux kill-server`: every session goes with it.
+    pub fn kill_server(&self) {
+        self.sessions.lock().unwrap().clear();
+        *self.down.lock().unwrap() = true;
+    }
+
+    fn server(&self) -> Result<(), NoServer> {
+        if *self.down.lock().unwrap() { Err(NoServer) } else { Ok(()) }
+    }
+
+    pub fn set_pane(&self, name: &str, command: &str, dead: bool) {
+        let state = PaneState { command: command.to_string(), dead };
+        self.panes.lock().unwrap().insert(name.to_string(), state);
+    }
+
+    pub fn session_names(&self) -> Vec<String> {
+        self.sessions.lock().unwrap().iter().map(|(n, _)| n.clone()).collect()
+    }
+}
+
+impl TmuxBackend for FakeTmux {
+    fn list_sessions_with_paths(&self) -> Result<Vec<(String, PathBuf)>, NoServer> {
+        self.server()?;
+        Ok(self.sessions.lock().unwrap().clone())
+    }
+
+    fn session_activity(&self) -> Result<HashMap<String, SessionStatus>, NoServer> {
+        self.server()?;
+        let live = self.list_sessions_with_paths()?;
+        let mut activity = self.activity.lock().unwrap().clone();
+        activity.retain(|name, _| live.iter().any(|(n, _)| n == name));
+        for (name, path) in live {
+            if let Some(status) = activity.get_mut(&name) {
+                status.start_dir = path.display().to_string();
+            }
+        }
+        Ok(activity)
+    }
+
+    fn session_exists(&self, name: &str) -> bool {
+        self.session_names().iter().any(|n| n == name)
+    }
+
+    /// The `attached` of the status a test set.
+    fn session_attached(&self, name: &str) -> bool {
+        self.activity.lock().unwrap().get(name).is_some_and(|s| s.attached)
+    }
+
+    fn create_session(&self, name: &str, start_dir: &Path, env: &[(String, String)]) -> Result<()> {
+        if self.session_exists(name) {
+            bail!("tmux new-session failed for {}", name);
+        }
+        *self.down.lock().unwrap() = false;
+        self.add_session(name, start_dir);
+        self.env.lock().unwrap().insert(name.to_string(), env.to_vec());
+        Ok(())
+    }
+
+    fn kill_session(&self, name: &str) -> Result<()> {
+        self.sessions.lock().unwrap().retain(|(n, _)| n != name);
+        Ok(())
+    }
+
+    fn new_window(&self, session: &str, _start_dir: &Path, name: &str) -> Result<u32> {
+        if !self.session_exists(session) {
+            bail!("tmux new-window failed for {}", session);
+        }
+        let mut activity = self.activity.lock().unwrap();
+        // window ids are unique across the server, as in tmux
+        let mut next_id = activity
+            .values()
+            .flat_map(|s| &s.windows)
+            .filter_map(|w| w.id.strip_prefix('@')?.parse::<u32>().ok())
+            .max()
+            .map_or(0, |id| id + 1);
+        let windows = &mut activity.entry(session.to_string()).or_default().windows;
+        if windows.is_empty() {
+            windows.push(WindowInfo { index: 0, id: format!("@{}", next_id), name: "zsh".into() });
+            next_id += 1;
+        }
+        let index = windows.last().map(|w| w.index + 1).unwrap_or(0);
+        windows.push(WindowInfo { index, id: format!("@{}", next_id), name: name.to_string() });
+        Ok(index)
+    }
+
+    fn kill_window(&self, target: &str) -> Result<()> {
+        let Some((session, index)) = target.rsplit_once(':') else {
+            bail!("bad window target: {}", target);
+        };
+        let index: u32 = index.parse()?;
+        if let Some(status) = self.activity.lock().unwrap().get_mut(session) {
+            status.windows.retain(|w| w.index != index);
+        }
+        Ok(())
+    }
+
+    fn rename_session(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.iter_mut().find(|(n, _)| n == old_name) {
+            Some(s) => s.0 = new_name.to_string(),
+            None => bail!("can't find session: {}", old_name),
+        }
+        Ok(())
+    }
+
+    fn send_keys(&self, session: &str, keys: &str) -> Result<()> {
+        self.sent.lock().unwrap().push((session.to_string(), keys.to_string()));
+        Ok(())
+    }
+
+    /// Only `@wsx_cmd` and `@wsx_made` are kept, as the status's `init_command` and `made_as`.
+    fn set_session_option(&self, session: &str, key: &str, value: &str) {
+        let mut activity = self.activity.lock().unwrap();
+        match key {
+            "@wsx_cmd" => activity.entry(session.to_string()).or_default().init_command = value.to_string(),
+            "@wsx_made" => activity.entry(session.to_string()).or_default().made_as = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn set_options(&self, session: &str, entries: &[String]) -> Vec<String> {
+        let mut rejected = Vec::new();
+        for entry in entries {
+            let words = session::split_words(entry);
+            match words.first() {
+                Some(name) if self.unknown_options.lock().unwrap().contains(name) => rejected.push(entry.clone()),
+                Some(_) => self.options.lock().unwrap().push((session.to_string(), words)),
+                None => {}
+            }
+        }
+        rejected
+    }
+
+    /// Stops the foreground command — back to the shell — unless told it won't.
+    fn send_ctrl_c(&self, session: &str) -> Result<()> {
+        if !self.ignores_ctrl_c.lock().unwrap().iter().any(|s| s == session) {
+            self.panes.lock().unwrap().remove(session);
+        }
+        self.send_keys(session, "C-c")
+    }
+
+    fn capture_pane(&self, session: &str) -> Option<String> {
+        self.session_exists(session).then(|| format!("$ # {}\n", session))
+    }
+
+    fn pane_state(&self, target: &str) -> Option<PaneState> {
+        let session = target.split(':').next().unwrap_or(target);
+        if !self.session_exists(session) {
+            return None;
+        }
+        let panes = self.panes.lock().unwrap();
+        Some(panes.get(session).cloned().unwrap_or_else(|| PaneState { command: "zsh".into(), dead: false }))
+    }
+
+    fn respawn_pane(&self, target: &str, start_dir: &Path) -> Result<()> {
+        if !self.session_exists(target) {
+            bail!("tmux respawn-pane failed for {}", target);
+        }
+        self.respawned.lock().unwrap().push((target.to_string(), start_dir.to_path_buf()));
+        self.set_pane(target, "zsh", false);
+        Ok(())
+    }
+}
@@ -1,8 +1,35 @@
+pub mod backend;
+#[cfg(test)]
+pub mod fake;
+pub mod naming;
 pub mod session;
 pub mod capture;
 pub mod monitor;
 
-use std::process::{Command, Stdio};
+pub use backend::{CliTmux, TmuxBackend};
+
+/// tmux ran but found no server to talk to — killed, crashed, or not started yet.
+/// Distinct from a server with no sessions, which answers with an empty list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("tmux server not running")]
+pub struct NoServer;
+
+use std::process::{Command, Output, Stdio};
+
+/// Whether a query's output is usable. `NoServer` only when nothing answers on the
+/// socket — "no server running on …" when it's stale, "error connecting to …" when
+/// it's gone; any other failure is `Ok(false)` and reads as no sessions.
+pub fn check_server(output: &Output) -> Result<bool, NoServer> {
+    if output.status.success() {
+        return Ok(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if ["no server running", "error connecting to", "server exited unexpectedly"].iter().any(|m| stderr.contains(m)) {
+        Err(NoServer)
+    } else {
+        Ok(false)
+    }
+}
 
 /// tmux command with pre-set args.
 pub fn tmux_cmd(args: &[&str]) -> Command {
@@ -17,3 +44,22 @@ pub fn tmux_silent(args: &[&str]) -> Command {
     cmd.args(args).stdout(Stdio::null()).stderr(Stdio::null());
     cmd
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_server, NoServer};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command, ExitStatus, Output};
+
+    #[test]
+    fn only_a_missing_server_counts_as_no_server() {
+        let socket = format!("wsx-test-{}", std::process::id());
+        let Ok(output) = Command::new("tmux").args(["-L", &socket, "list-sessions"]).output() else {
+            return;
+        };
+        assert_eq!(check_server(&output), Err(NoServer), "{}", String::from_utf8_lossy(&output.stderr));
+        let failed = |stderr: &str| Output { status: ExitStatus::from_raw(1 << 8), stdout: vec![], stderr: stderr.into() };
+        assert_eq!(check_server(&failed("no server running on /tmp/tmux-501/default\n")), Err(NoServer));
+        assert_eq!(check_server(&failed("protocol version mismatch (client 8, server 7)\n")), Ok(false));
+    }
+}
@@ -2,16 +2,94 @@
 // ref: tmux(1) — list-windows, session_alerts, window_activity
 
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
-use super::tmux_cmd;
+use super::{check_server, tmux_cmd, NoServer};
+use std::path::PathBuf;
+use crate::model::workspace::{SessionOrigin, WindowInfo};
 
+#[derive(Debug, Clone, Default)]
 pub struct SessionStatus {
     pub has_bell: bool,
     pub last_activity_ts: u64,  // Unix timestamp, 0 if unknown
     pub has_running_app: bool,  // foreground process is not a bare shell
+    pub commands: Vec<String>,  // distinct non-shell foreground commands across windows
+    pub attached: bool,         // at least one client is attached to the session
+    pub windows: Vec<WindowInfo>, // in window-index order
+    pub created_ts: u64,        // Unix timestamp of session creation, 0 if unknown
+    pub start_dir: String,      // #{session_path}, "" if unknown
+    pub current_dir: String,    // the active window's pane_current_path, "" if unknown
+    pub init_command: String,   // the `@wsx_cmd` session option wsx set at creation, "" if none
+    pub made_as: String,        // the `@wsx_made` option: the name wsx created it for, "" if made elsewhere
 }
 
-fn is_shell(cmd: &str) -> bool {
+impl SessionStatus {
+    /// First foreground command that takes over the terminal, where a surprise
+    /// attach could interrupt someone (editor, pager, remote shell, agent).
+    pub fn interactive_app(&self) -> Option<&str> {
+        self.commands
+            .iter()
+            .map(String::as_str)
+            .find(|c| is_interactive(c))
+    }
+
+    /// The start directory, the cwd only when it differs, and the recorded init command.
+    pub fn origin(&self) -> SessionOrigin {
+        let start_dir = Some(PathBuf::from(&self.start_dir)).filter(|_| !self.start_dir.is_empty());
+        let current_dir = Some(PathBuf::from(&self.current_dir))
+            .filter(|cwd| !self.current_dir.is_empty() && start_dir.as_ref() != Some(cwd));
+        let init_command = Some(self.init_command.clone()).filter(|c| !c.is_empty());
+        let made_as = Some(self.made_as.clone()).filter(|n| !n.is_empty());
+        SessionOrigin { start_dir, current_dir, init_command, made_as }
+    }
+}
+
+/// What a session's foreground commands say it is, for its glyph in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    /// nothing but a shell
+    Shell,
+    Editor,
+    /// a dev server or watch-mode runtime
+    Server,
+    /// anything else in the foreground — a build, tests, a script
+    Run,
+}
+
+impl SessionKind {
+    /// From `SessionStatus::commands` (shells already left out); an editor in any window
+    /// wins over a server, a server over a one-off run.
+    pub fn of(commands: &[String]) -> Self {
+        if commands.iter().any(|c| is_editor(c)) {
+            Self::Editor
+        } else if commands.iter().any(|c| is_watch_mode(c)) {
+            Self::Server
+        } else if commands.iter().any(|c| !is_shell(c)) {
+            Self::Run
+        } else {
+            Self::Shell
+        }
+    }
+
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Self::Shell => "",
+            Self::Editor => "✎",
+            Self::Server => "≈",
+            Self::Run => "»",
+        }
+    }
+}
+
+fn is_editor(cmd: &str) -> bool {
+    matches!(cmd.trim(), "vim" | "nvim" | "vi" | "hx" | "helix" | "emacs" | "nano" | "kak" | "micro")
+}
+
+fn is_interactive(cmd: &str) -> bool {
+    matches!(cmd.trim(), "vim" | "nvim" | "vi" | "less" | "ssh" | "claude")
+}
+
+pub(crate) fn is_shell(cmd: &str) -> bool {
     matches!(cmd.trim(), "bash" | "zsh" | "sh" | "fish" | "csh" | "tcsh" | "ksh" | "dash" | "elvish")
 }
 
@@ -36,39 +114,172 @@ fn is_passive(cmd: &str) -> bool {
     )
 }
 
-/// Single tmux call: returns bell flag, last window_activity timestamp, and foreground
-/// process per session. has_running_app is true if any window's active pane is not a shell.
-pub fn session_activity() -> HashMap<String, SessionStatus> {
-    let Ok(output) = tmux_cmd(&[
-        "list-windows", "-a", "-F",
-        "#{session_name}\t#{session_alerts}\t#{window_activity}\t#{pane_current_command}",
-    ]).output()
-    else { return HashMap::new() };
+/// Older tmux leaves `#{session_alerts}` / `#{pane_current_command}` unexpanded or empty.
+pub const FULL_FORMAT_TMUX: (u32, u32) = (3, 0);
+
+const FULL_FORMAT: &str = "#{session_name}\t#{session_alerts}\t#{window_activity}\t#{session_attached}\t#{pane_current_command}\t#{session_created}\t#{window_index}\t#{session_path}\t#{window_active}\t#{pane_current_path}\t#{@wsx_cmd}\t#{@wsx_made}\t#{window_id}\t#{window_name}";
+/// Same columns with alerts and command left empty: activity timestamps only.
+const REDUCED_FORMAT: &str = "#{session_name}\t\t#{window_activity}\t#{session_attached}\t\t#{session_created}\t#{window_index}\t#{session_path}\t#{window_active}\t#{pane_current_path}\t#{@wsx_cmd}\t#{@wsx_made}\t#{window_id}\t#{window_name}";
+
+/// The tmux version, probed once, when it is older than `FULL_FORMAT_TMUX`.
+pub fn limited_tmux() -> Option<(u32, u32)> {
+    static LIMITED: OnceLock<Option<(u32, u32)>> = OnceLock::new();
+    *LIMITED.get_or_init(|| {
+        let out = tmux_cmd(&["-V"]).output().ok()?;
+        crate::doctor::parse_version(&String::from_utf8_lossy(&out.stdout)).filter(|&v| v < FULL_FORMAT_TMUX)
+    })
+}
+
+pub fn limited_note((major, minor): (u32, u32)) -> String {
+    format!("attention detection limited (tmux {}.{})", major, minor)
+}
+
+/// Single tmux call: returns bell flag, last window_activity timestamp, foreground
+/// processes and attach state per session. has_running_app is true if any window's
+/// active pane is not a shell or passive watcher. On tmux older than `FULL_FORMAT_TMUX`
+/// only activity, attach state and windows are read — no bells, no running apps.
+pub fn session_activity() -> Result<HashMap<String, SessionStatus>, NoServer> {
+    let format = if limited_tmux().is_some() { REDUCED_FORMAT } else { FULL_FORMAT };
+    let Ok(output) = tmux_cmd(&["list-windows", "-a", "-F", format]).output()
+    else { return Ok(HashMap::new()) };
+    if !check_server(&output)? {
+        return Ok(HashMap::new());
+    }
 
     let now_ts = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
+    Ok(parse_windows(&String::from_utf8_lossy(&output.stdout), now_ts))
+}
 
+/// A format field, or "" when tmux didn't know the variable and echoed `#{…}` back.
+fn field(raw: &str) -> &str {
+    let raw = raw.trim();
+    if raw.contains("#{") { "" } else { raw }
+}
+
+/// One `list-windows -a` line per window, folded into per-session status.
+fn parse_windows(text: &str, now_ts: u64) -> HashMap<String, SessionStatus> {
     let mut result: HashMap<String, SessionStatus> = HashMap::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        let mut parts = line.splitn(4, '\t');
+    for line in text.lines() {
+        let mut parts = line.splitn(14, '\t');
         let Some(name)     = parts.next() else { continue };
-        let Some(alerts)   = parts.next() else { continue };
-        let Some(ts_str)   = parts.next() else { continue };
-        let cmd            = parts.next().unwrap_or("").trim();
+        let Some(alerts)   = parts.next().map(field) else { continue };
+        let Some(ts_str)   = parts.next().map(field) else { continue };
+        let attached       = parts.next().map_or("0", field);
+        let cmd            = parts.next().map_or("", field);
+        let created_ts     = parts.next().and_then(|t| field(t).parse().ok()).unwrap_or(0);
+        let window_index   = parts.next().and_then(|i| field(i).parse().ok());
+        let start_dir      = parts.next().map_or("", field);
+        let window_active  = parts.next().map_or("", field) == "1";
+        let current_dir    = parts.next().map_or("", field);
+        let init_command   = parts.next().map_or("", field);
+        let made_as        = parts.next().map_or("", field);
+        let window_id      = parts.next().map_or("", field);
+        let window_name    = parts.next().unwrap_or("");
         let name = name.trim().to_string();
-        let has_bell = !alerts.trim().is_empty() && alerts.trim() != "0";
-        let ts = ts_str.trim().parse::<u64>().unwrap_or(0);
+        let has_bell = !alerts.is_empty() && alerts != "0";
+        let ts = ts_str.parse::<u64>().unwrap_or(0);
         let entry = result.entry(name).or_insert(SessionStatus {
             has_bell: false,
             last_activity_ts: 0,
             has_running_app: false,
+            commands: Vec::new(),
+            attached: false,
+            windows: Vec::new(),
+            created_ts,
+            start_dir: start_dir.to_string(),
+            current_dir: String::new(),
+            init_command: init_command.to_string(),
+            made_as: made_as.to_string(),
         });
+        if window_active || entry.current_dir.is_empty() {
+            entry.current_dir = current_dir.to_string();
+        }
         entry.has_bell |= has_bell;
+        entry.attached |= !attached.is_empty() && attached != "0";
         if ts > entry.last_activity_ts { entry.last_activity_ts = ts; }
         if is_watch_mode(cmd) && now_ts > entry.last_activity_ts { entry.last_activity_ts = now_ts; }
         if !cmd.is_empty() && !is_shell(cmd) && !is_passive(cmd) { entry.has_running_app = true; }
+        if !cmd.is_empty() && !is_shell(cmd) && !entry.commands.iter().any(|c| c == cmd) {
+            entry.commands.push(cmd.to_string());
+        }
+        if let Some(index) = window_index {
+            entry.windows.push(WindowInfo { index, id: window_id.to_string(), name: window_name.to_string() });
+        }
+    }
+    for status in result.values_mut() {
+        status.windows.sort_by_key(|w| w.index);
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_windows, SessionKind, SessionStatus, WindowInfo};
+
+    #[test]
+    fn parse_windows_collects_each_window() {
+        let text = "api-main\t0\t100\t1\tnvim\t40\t2\t/w/api\t1\t/w/api/src\tnpm run dev\tapi-main\t@7\tedit\tor\ttabs\n\
+                    api-main\t0\t90\t1\tzsh\t40\t1\t/w/api\t0\t/w/api\tnpm run dev\tapi-main\t@3\tshell\n\
+                    old\t1\t50\t0\tzsh\n";
+        let status = parse_windows(text, 200);
+        let api = &status["api-main"];
+        assert_eq!(api.last_activity_ts, 100);
+        assert_eq!(api.created_ts, 40);
+        assert_eq!(api.commands, ["nvim"]);
+        assert_eq!(
+            api.windows,
+            [
+                WindowInfo { index: 1, id: "@3".into(), name: "shell".into() },
+                WindowInfo { index: 2, id: "@7".into(), name: "edit\tor\ttabs".into() },
+            ]
+        );
+        assert_eq!(
+            (api.start_dir.as_str(), api.current_dir.as_str(), api.init_command.as_str(), api.made_as.as_str()),
+            ("/w/api", "/w/api/src", "npm run dev", "api-main"),
+            "cwd of the active window"
+        );
+        assert!(status["old"].has_bell);
+        assert!(status["old"].windows.is_empty(), "older output without window fields");
+    }
+
+    #[test]
+    fn unexpanded_fields_from_old_tmux_count_as_missing() {
+        // tmux 2.x with the full format: unknown variables come back literally.
+        let text = "api-main\t#{session_alerts}\t100\t0\t#{pane_current_command}\t40\t1\t/w/api\t1\t/w/api\t#{@wsx_cmd}\t#{@wsx_made}\t@1\tshell\n";
+        let api = &parse_windows(text, 200)["api-main"];
+        assert!(!api.has_bell && !api.has_running_app && api.commands.is_empty());
+        assert_eq!((api.last_activity_ts, api.created_ts), (100, 40));
+        assert!(api.init_command.is_empty() && api.made_as.is_empty());
+
+        // The reduced format leaves those columns empty.
+        let text = "api-main\t\t100\t1\t\t40\t1\t/w/api\t1\t/w/api\t\t\t@1\tshell\n";
+        let api = &parse_windows(text, 200)["api-main"];
+        assert!(!api.has_bell && !api.has_running_app && api.attached);
+        assert_eq!(api.windows, [WindowInfo { index: 1, id: "@1".into(), name: "shell".into() }]);
+    }
+
+    #[test]
+    fn interactive_app_ignores_shells_and_servers() {
+        let status = |cmds: &[&str]| SessionStatus {
+            commands: cmds.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(status(&["node", "nvim"]).interactive_app(), Some("nvim"));
+        assert_eq!(status(&["cargo", "node"]).interactive_app(), None);
+        assert_eq!(status(&[]).interactive_app(), None);
+    }
+
+    #[test]
+    fn session_kind_prefers_editors_then_servers() {
+        let kind = |cmds: &[&str]| SessionKind::of(&cmds.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        assert_eq!(kind(&[]), SessionKind::Shell);
+        assert_eq!(kind(&["zsh"]), SessionKind::Shell);
+        assert_eq!(kind(&["cargo"]), SessionKind::Run);
+        assert_eq!(kind(&["cargo", "npm"]), SessionKind::Server);
+        assert_eq!(kind(&["node", "nvim"]), SessionKind::Editor);
+        assert_eq!(SessionKind::Shell.glyph(), "");
+    }
+}
@@ -0,0 +1,70 @@
+// Names for a second session under a name that's taken: `dev`, then `dev_2`, `dev_3`, …
+// The suffix is tmux's business; the tree shows it apart from the name ("dev ·2") and,
+// once `dev` itself is gone, `dev_2` can take the clean name back. Only a suffix wsx
+// added counts: the name it was asked for is kept on the session (`@wsx_made`).
+
+/// `base`, or `base_N` for the lowest N ≥ 2 that's free, so numbers freed by killed
+/// sessions are used again instead of counting up forever.
+pub fn unique_session_name(base: &str, exists: impl Fn(&str) -> bool) -> String {
+    if !exists(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}_{}", base, n))
+        .find(|candidate| !exists(candidate))
+        .expect("some suffix is free")
+}
+
+/// ("dev", Some(2)) for "dev_2"; the name itself and None when it has no suffix that
+/// `unique_session_name` could have added (`_0`, `_1`, `_02` and a bare `_2` aren't).
+pub fn split_suffix(name: &str) -> (&str, Option<u32>) {
+    let Some((base, digits)) = name.rsplit_once('_') else {
+        return (name, None);
+    };
+    match digits.parse::<u32>() {
+        Ok(n) if n >= 2 && !base.is_empty() && !digits.starts_with(['0', '+']) => (base, Some(n)),
+        _ => (name, None),
+    }
+}
+
+/// The name a suffixed session can go back to: its base, when that's free again.
+pub fn clean_name(name: &str, exists: impl Fn(&str) -> bool) -> Option<&str> {
+    match split_suffix(name) {
+        (base, Some(_)) if !exists(base) => Some(base),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean_name, split_suffix, unique_session_name};
+
+    fn taken<'a>(names: &'a [&'a str]) -> impl Fn(&str) -> bool + 'a {
+        move |n| names.contains(&n)
+    }
+
+    #[test]
+    fn unique_names_fill_the_lowest_gap() {
+        assert_eq!(unique_session_name("dev", taken(&[])), "dev");
+        assert_eq!(unique_session_name("dev", taken(&["dev"])), "dev_2");
+        assert_eq!(unique_session_name("dev", taken(&["dev", "dev_2", "dev_3"])), "dev_4");
+        // dev_2 was killed: its number comes back before dev_7
+        assert_eq!(unique_session_name("dev", taken(&["dev", "dev_3", "dev_6"])), "dev_2");
+    }
+
+    #[test]
+    fn suffixes_are_only_what_deduplication_adds() {
+        assert_eq!(split_suffix("dev_2"), ("dev", Some(2)));
+        assert_eq!(split_suffix("app-dev_12"), ("app-dev", Some(12)));
+        for plain in ["dev", "dev_1", "dev_02", "dev_x", "_2", "py_3.11", "dev_+3"] {
+            assert_eq!(split_suffix(plain), (plain, None), "{}", plain);
+        }
+    }
+
+    #[test]
+    fn clean_name_once_the_original_is_gone() {
+        assert_eq!(clean_name("dev_2", taken(&["dev", "dev_2"])), None);
+        assert_eq!(clean_name("dev_2", taken(&["dev_2"])), Some("dev"));
+        assert_eq!(clean_name("dev", taken(&[])), None);
+    }
+}
@@ -1,10 +1,14 @@
 // tmux session management via CLI
 // ref: tmux(1)
 
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::OnceLock;
 use anyhow::{bail, Result};
-use super::{tmux_cmd, tmux_silent};
+use super::{check_server, tmux_cmd, tmux_silent, NoServer};
 
 /// Check if tmux is available.
 pub fn is_available() -> bool {
@@ -17,22 +21,43 @@ pub fn is_inside_tmux() -> bool {
     std::env::var("TMUX").is_ok()
 }
 
+/// The session wsx itself runs in: the one holding `$TMUX_PANE`, which stays the
+/// same when the client is switched elsewhere. None outside tmux.
+pub fn current_session() -> Option<String> {
+    if !is_inside_tmux() {
+        return None;
+    }
+    let mut cmd = tmux_cmd(&["display-message", "-p"]);
+    if let Ok(pane) = std::env::var("TMUX_PANE") {
+        cmd.args(["-t", &pane]);
+    }
+    let output = cmd.arg("#{session_name}").stderr(Stdio::null()).output().ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
 /// Return (session_name, session_path) pairs for all active sessions.
-pub fn list_sessions_with_paths() -> Vec<(String, PathBuf)> {
+/// A missing tmux binary reads as no sessions; `wsx doctor` reports that.
+pub fn list_sessions_with_paths() -> Result<Vec<(String, PathBuf)>, NoServer> {
     let Ok(output) = tmux_cmd(&["list-sessions", "-F", "#{session_name}:#{session_path}"])
         .output()
-    else { return vec![] };
+    else { return Ok(vec![]) };
+    if !check_server(&output)? {
+        return Ok(vec![]);
+    }
 
-    String::from_utf8_lossy(&output.stdout)
-        .lines()
+    // Split raw bytes: session names can't contain ':', paths may be non-UTF-8.
+    Ok(output
+        .stdout
+        .split(|&b| b == b'\n')
         .filter_map(|line| {
-            let mut parts = line.splitn(2, ':');
-            let name = parts.next()?.trim().to_string();
-            let path = parts.next()?.trim().to_string();
+            let colon = line.iter().position(|&b| b == b':')?;
+            let name = String::from_utf8_lossy(&line[..colon]).trim().to_string();
+            let path = &line[colon + 1..];
             if name.is_empty() || path.is_empty() { return None; }
-            Some((name, PathBuf::from(path)))
+            Some((name, PathBuf::from(OsStr::from_bytes(path))))
         })
-        .collect()
+        .collect())
 }
 
 /// Return true if a named session exists.
@@ -41,11 +66,76 @@ pub fn session_exists(name: &str) -> bool {
         .status().map(|s| s.success()).unwrap_or(false)
 }
 
-/// Create a new session with starting directory, detached.
-pub fn create_session(name: &str, start_dir: &Path) -> Result<()> {
-    let status = tmux_silent(&["new-session", "-d", "-s", name, "-c", &start_dir.to_string_lossy()])
-        .status()?;
-    if !status.success() { bail!("tmux new-session failed for {}", name); }
+/// True if some client is showing `name` right now (`#{session_attached}` > 0).
+pub fn session_attached(name: &str) -> bool {
+    let Ok(output) = tmux_cmd(&["display-message", "-p", "-t", name, "#{session_attached}"])
+        .stderr(Stdio::null())
+        .output()
+    else { return false };
+    String::from_utf8_lossy(&output.stdout).trim().parse::<u32>().is_ok_and(|n| n > 0)
+}
+
+/// `new-session -e` arrived in tmux 3.2.
+fn supports_session_env() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let Ok(out) = tmux_cmd(&["-V"]).output() else { return false };
+        // Dev builds ("tmux master") have no number and are assumed new enough.
+        crate::doctor::parse_version(&String::from_utf8_lossy(&out.stdout)).is_none_or(|v| v >= (3, 2))
+    })
+}
+
+/// Create a new session with starting directory, detached. Older tmux gets `env` as
+/// `export` lines typed into the shell instead — space-prefixed to stay out of history.
+pub fn create_session(name: &str, start_dir: &Path, env: &[(String, String)]) -> Result<()> {
+    let mut cmd = tmux_silent(&["new-session", "-d", "-s", name, "-c"]);
+    cmd.arg(start_dir);
+    let typed = !env.is_empty() && !supports_session_env();
+    if !typed {
+        for (key, value) in env {
+            cmd.arg("-e").arg(format!("{}={}", key, value));
+        }
+    }
+    // Never put `env` in errors: values may be secrets and errors reach the status bar.
+    if !cmd.status()?.success() { bail!("tmux new-session failed for {}", name); }
+    apply_fallback_prefix(name);
+    if typed {
+        for (key, value) in env {
+            send_keys(name, &format!(" export {}={}", key, crate::launch::shell_quote(value)))?;
+        }
+    }
+    Ok(())
+}
+
+/// `session:index` — a window as a tmux target.
+pub fn window_target(session: &str, index: u32) -> String {
+    format!("{}:{}", session, index)
+}
+
+/// Add a detached window to `session`; returns its index. An empty `name` leaves
+/// naming to tmux (automatic-rename).
+pub fn new_window(session: &str, start_dir: &Path, name: &str) -> Result<u32> {
+    let target = format!("{}:", session);
+    let mut cmd = tmux_cmd(&["new-window", "-d", "-P", "-F", "#{window_index}", "-t", &target]);
+    if !name.is_empty() {
+        cmd.args(["-n", name]);
+    }
+    let out = cmd
+        .arg("-c")
+        .arg(start_dir)
+        .stderr(Stdio::null())
+        .output()?;
+    if !out.status.success() { bail!("tmux new-window failed for {}", session); }
+    String::from_utf8_lossy(&out.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("tmux new-window printed no index"))
+}
+
+/// Kill one window (`session:index`).
+pub fn kill_window(target: &str) -> Result<()> {
+    let status = tmux_silent(&["kill-window", "-t", target]).status()?;
+    if !status.success() { bail!("tmux kill-window failed for {}", target); }
     Ok(())
 }
 
@@ -76,24 +166,95 @@ pub enum AttachCommand {
 }
 
 /// Returns true if the user has a tmux config file (~/.tmux.conf or XDG path).
+/// Checked once per run.
 pub fn user_has_tmux_config() -> bool {
-    let xdg = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| dirs::home_dir().unwrap_or_default().join(".config"));
-    dirs::home_dir().map(|h| h.join(".tmux.conf").exists()).unwrap_or(false)
-        || xdg.join("tmux/tmux.conf").exists()
+    static FOUND: OnceLock<bool> = OnceLock::new();
+    *FOUND.get_or_init(|| {
+        let xdg = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| dirs::home_dir().unwrap_or_default().join(".config"));
+        dirs::home_dir().map(|h| h.join(".tmux.conf").exists()).unwrap_or(false)
+            || xdg.join("tmux/tmux.conf").exists()
+    })
 }
 
-/// Apply wsx runtime defaults to a session if the user has no tmux config.
-/// Best-effort, non-fatal. Skipped when user config exists (let it take over).
-pub fn apply_session_defaults(session: &str) {
-    let _ = tmux_silent(&["set-option", "-t", session, "mouse", "on"]).status();
+/// A C-a prefix on a new session when the user has no tmux config of their own.
+fn apply_fallback_prefix(session: &str) {
     if !user_has_tmux_config() {
         let _ = tmux_silent(&["set-option", "-t", session, "prefix", "C-a"]).status();
         let _ = tmux_silent(&["bind-key", "-T", "prefix", "a", "send-prefix"]).status();
     }
 }
 
+/// `set-option -t <session>` once per `tmux_defaults` entry, e.g. "mouse on". Best-effort:
+/// returns the entries tmux rejected.
+pub fn set_options(session: &str, entries: &[String]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| {
+            let words = split_words(entry);
+            !words.is_empty()
+                && !tmux_silent(&["set-option", "-t", session])
+                    .args(&words)
+                    .status()
+                    .is_ok_and(|s| s.success())
+        })
+        .cloned()
+        .collect()
+}
+
+/// `entry` split into words the way sh would: quotes group words and are dropped, and a
+/// backslash outside single quotes keeps the next char as is. `status-left '#S | %H:%M'`
+/// is two words.
+pub fn split_words(entry: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = entry.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.get_or_insert_default().push(next);
+                }
+            }
+            (Some(_), c) => word.get_or_insert_default().push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// `tmux_defaults` entries tmux rejected this run; each is reported once.
+#[derive(Default)]
+pub struct RejectedOptions {
+    seen: Vec<String>,
+    unreported: Vec<String>,
+}
+
+impl RejectedOptions {
+    pub fn record(&mut self, rejected: Vec<String>) {
+        for entry in rejected {
+            if !self.seen.contains(&entry) {
+                self.seen.push(entry.clone());
+                self.unreported.push(entry);
+            }
+        }
+    }
+
+    /// Entries rejected since the last call.
+    pub fn take_unreported(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.unreported)
+    }
+}
+
 /// switch-client (inside tmux path).
 pub fn switch_client(name: &str) -> Result<()> {
     let status = tmux_silent(&["switch-client", "-t", name]).status()?;
@@ -101,9 +262,27 @@ pub fn switch_client(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Copy `text` into a tmux paste buffer; `-w` also hands it to the outer terminal's
+/// clipboard (tmux 3.2+), so retry without it on older servers.
+pub fn set_buffer(text: &str) -> Result<()> {
+    for args in [&["set-buffer", "-w", "--"][..], &["set-buffer", "--"][..]] {
+        if tmux_silent(args).arg(text).status()?.success() {
+            return Ok(());
+        }
+    }
+    bail!("tmux set-buffer failed")
+}
+
 /// attach-session (outside tmux path) — takes over the terminal.
+/// When stdout is captured (`--pick-path`), tmux is pointed at /dev/tty instead.
 pub fn attach_foreground(name: &str) -> Result<()> {
-    tmux_cmd(&["attach-session", "-t", name]).status()?;
+    let mut cmd = tmux_cmd(&["attach-session", "-t", name]);
+    if !std::io::stdout().is_terminal() {
+        if let Ok(tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+            cmd.stdout(tty);
+        }
+    }
+    cmd.status()?;
     Ok(())
 }
 
@@ -118,19 +297,61 @@ pub fn send_keys(session: &str, keys: &str) -> Result<()> {
     Ok(())
 }
 
+/// What a session's active pane is running right now.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaneState {
+    /// `pane_current_command`: the foreground process, the shell when it's idle
+    pub command: String,
+    /// the pane's process exited and `remain-on-exit` kept it around
+    pub dead: bool,
+}
+
+impl PaneState {
+    pub fn at_shell(&self) -> bool {
+        !self.dead && super::monitor::is_shell(&self.command)
+    }
+}
+
+/// None when the session is gone.
+pub fn pane_state(target: &str) -> Option<PaneState> {
+    let output = tmux_cmd(&["display-message", "-p", "-t", target, "#{pane_dead}\t#{pane_current_command}"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (dead, command) = text.trim_end().split_once('\t')?;
+    Some(PaneState { command: command.to_string(), dead: dead == "1" })
+}
+
+/// Kill whatever the active pane runs and start the default shell over in `start_dir`.
+/// The session, its name and options stay as they were.
+pub fn respawn_pane(target: &str, start_dir: &Path) -> Result<()> {
+    let dir = start_dir.to_string_lossy();
+    let args = ["respawn-pane", "-k", "-t", target, "-c", dir.as_ref()];
+    let status = tmux_silent(&args).status()?;
+    if !status.success() { bail!("tmux respawn-pane failed for {}", target); }
+    Ok(())
+}
+
 /// Send Ctrl+C to a session's active pane (no Enter).
 pub fn send_ctrl_c(session: &str) -> Result<()> {
     tmux_silent(&["send-keys", "-t", session, "C-c"]).status()?;
     Ok(())
 }
 
-/// Generate a unique session name that doesn't conflict with existing sessions.
-pub fn unique_session_name(base: &str) -> String {
-    if !session_exists(base) { return base.to_string(); }
-    let mut n = 2;
-    loop {
-        let candidate = format!("{}_{}", base, n);
-        if !session_exists(&candidate) { return candidate; }
-        n += 1;
+#[cfg(test)]
+mod tests {
+    use super::split_words;
+
+    #[test]
+    fn option_entries_split_like_sh() {
+        assert_eq!(split_words("  mouse   on "), ["mouse", "on"]);
+        assert_eq!(split_words("status-left '#S | %H:%M'"), ["status-left", "#S | %H:%M"]);
+        assert_eq!(split_words(r#"-g status-right "it's \"up\"""#), ["-g", "status-right", r#"it's "up""#]);
+        assert_eq!(split_words(r"word\ with\ spaces ''"), ["word with spaces", ""]);
+        assert!(split_words("   ").is_empty());
     }
 }
@@ -1,7 +1,7 @@
 // Terminal init/restore wrapper
 // ref: ratatui docs — https://ratatui.rs/concepts/backends/
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
@@ -12,19 +12,157 @@ use crossterm::{
     },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io::{self, Stdout};
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Read, Write};
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+pub type Tui = Terminal<CrosstermBackend<Box<dyn Write>>>;
 
-pub fn init() -> Result<Tui> {
+/// Whether mouse capture is wanted, so leaving and re-entering the TUI around an attach
+/// or editor puts back what was there.
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(true);
+
+/// `to_tty` draws on /dev/tty instead of stdout, keeping stdout clean for `--pick-path`.
+pub fn init(to_tty: bool) -> Result<Tui> {
+    let mut out: Box<dyn Write> = if to_tty {
+        let tty = OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .context("opening /dev/tty")?;
+        Box::new(BufWriter::new(tty))
+    } else {
+        Box::new(io::stdout())
+    };
+    install_panic_hook();
+    install_signal_handlers();
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
+    execute!(out, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(out);
+    let mut terminal = Terminal::new(backend)?;
+    set_mouse_capture(&mut terminal, true)?;
     Ok(terminal)
 }
 
+/// Take the mouse (clicks, drags, wheel) or leave it to the terminal, whose click-drag
+/// selection only works while wsx doesn't capture it.
+pub fn set_mouse_capture(terminal: &mut Tui, on: bool) -> Result<()> {
+    MOUSE_CAPTURE.store(on, Ordering::SeqCst);
+    if on {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    } else {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    Ok(())
+}
+
+fn mouse_capture() -> bool {
+    MOUSE_CAPTURE.load(Ordering::SeqCst)
+}
+
+/// Leave raw mode and the alternate screen before the panic message prints,
+/// so a crash doesn't leave the shell unusable. The Tui isn't reachable from
+/// the hook; /dev/tty reaches the same terminal whichever stream it draws on.
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let mut out: Box<dyn Write> = match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(tty) => Box::new(tty),
+            Err(_) => Box::new(io::stderr()),
+        };
+        let _ = execute!(out, DisableMouseCapture, LeaveAlternateScreen, crossterm::cursor::Show);
+        previous(info);
+    }));
+}
+
+static TERMINATE: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_terminate(_: libc::c_int) {
+    TERMINATE.store(true, Ordering::SeqCst);
+}
+
+/// SIGINT / SIGTERM / SIGHUP only raise a flag; the event loop sees it within a
+/// tick and quits the normal way, restoring the terminal and saving the cache.
+fn install_signal_handlers() {
+    for sig in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+        unsafe {
+            libc::signal(sig, on_terminate as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        }
+    }
+}
+
+/// True once a termination signal has arrived.
+pub fn terminate_requested() -> bool {
+    TERMINATE.load(Ordering::SeqCst)
+}
+
+/// Ask the terminal for its background colour (OSC 11) and return the raw reply.
+/// A DA1 query follows it: every terminal answers that, so one that ignores OSC 11
+/// doesn't cost the full timeout. Call in raw mode, before the event loop reads input.
+pub fn query_background() -> Option<String> {
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    tty.flush().ok()?;
+    let deadline = Instant::now() + Duration::from_millis(200);
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 64];
+    while !da1_answered(&reply) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        let mut pfd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        // SAFETY: `pfd` is a single valid pollfd for the duration of the call.
+        if unsafe { libc::poll(&mut pfd, 1, left.as_millis() as i32) } <= 0 {
+            break;
+        }
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+        }
+    }
+    Some(String::from_utf8_lossy(&reply).into_owned())
+}
+
+/// DA1 replies look like "ESC [ ? 62 ; 22 c".
+fn da1_answered(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|i| reply[i..].contains(&b'c'))
+}
+
+/// Put `text` on the system clipboard with OSC 52. Inside tmux, which drops OSC 52
+/// from applications by default, it goes into a tmux buffer that tmux forwards instead.
+pub fn copy_to_clipboard(terminal: &mut Tui, text: &str) -> Result<()> {
+    if crate::tmux::session::is_inside_tmux() && crate::tmux::session::set_buffer(text).is_ok() {
+        return Ok(());
+    }
+    let out = terminal.backend_mut();
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()?;
+    Ok(())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
 /// Draw with synchronized output to prevent terminal from rendering partial frames.
 pub fn draw_sync<F>(terminal: &mut Tui, f: F) -> Result<()>
 where
@@ -52,7 +190,30 @@ where
     execute!(terminal.backend_mut(), DisableMouseCapture, LeaveAlternateScreen)?;
     let result = f();
     enable_raw_mode()?;
-    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    if mouse_capture() {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
     terminal.clear()?;
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, da1_answered};
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("wsx-main-claude ★".as_bytes()), "d3N4LW1haW4tY2xhdWRlIOKYhQ==");
+    }
+
+    #[test]
+    fn spots_the_da1_reply() {
+        assert!(da1_answered(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"));
+        assert!(!da1_answered(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;2"));
+    }
+}
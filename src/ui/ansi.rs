@@ -1,66 +1,142 @@
-// Minimal ANSI SGR parser → ratatui Text
-// Handles: reset, bold/dim/italic/underline, fg/bg (4-bit, 8-bit, 24-bit)
+// Minimal ANSI parser → ratatui Text
+// Handles: SGR (reset, bold/dim/italic/underline, fg/bg 4-bit, 8-bit, 24-bit), a bare \r
+// starting the line over, erase-line (ESC[K), and cursor moves along the line (ESC[nC/D/G),
+// so \r-rewritten progress bars show only their last state. Any other CSI, OSC or
+// two-byte escape is dropped whole.
 
 use ratatui::prelude::*;
 
 pub fn parse(input: &str) -> Text<'static> {
     let mut lines: Vec<Line<'static>> = Vec::new();
-    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut line = LineBuf::default();
     let mut style = Style::default();
     let mut rest = input;
 
-    while !rest.is_empty() {
-        match rest.find('\x1b') {
-            Some(0) => {
-                if rest.starts_with("\x1b[") {
-                    let after = &rest[2..];
-                    if let Some(end) = after.find(|c: char| c.is_ascii_alphabetic()) {
-                        if after.as_bytes()[end] == b'm' {
-                            style = apply_sgr(style, &after[..end]);
-                        }
-                        rest = &after[end + 1..];
-                    } else {
-                        rest = &rest[1..];
-                    }
-                } else {
-                    rest = &rest[1..];
-                }
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\x1b' => rest = escape(&rest[1..], &mut line, &mut style),
+            '\n' => {
+                lines.push(std::mem::take(&mut line).finish());
+                rest = &rest[1..];
             }
-            Some(pos) => {
-                push_text(&rest[..pos], &mut spans, &mut lines, style);
-                rest = &rest[pos..];
+            '\r' => {
+                // "\r\n" is a plain line end; a bare \r redraws the line from scratch.
+                rest = &rest[1..];
+                if !rest.starts_with('\n') {
+                    line = LineBuf::default();
+                }
             }
-            None => {
-                push_text(rest, &mut spans, &mut lines, style);
-                break;
+            _ => {
+                let end = rest.find(['\x1b', '\n', '\r']).unwrap_or(rest.len());
+                line.write(&rest[..end], style);
+                rest = &rest[end..];
             }
         }
     }
 
-    if !spans.is_empty() {
-        lines.push(Line::from(spans));
+    if !line.cells.is_empty() {
+        lines.push(line.finish());
     }
     Text::from(lines)
 }
 
-fn push_text(text: &str, spans: &mut Vec<Span<'static>>, lines: &mut Vec<Line<'static>>, style: Style) {
-    let mut s = text;
-    loop {
-        match s.find('\n') {
-            Some(nl) => {
-                if nl > 0 {
-                    spans.push(Span::styled(s[..nl].to_owned(), style));
+/// Cursor moves stop here, so a bogus ESC[999999999C can't pad a line out to it.
+const MAX_COLUMN: usize = 4096;
+
+/// The line being built, a cell per char, so later text and erases can overwrite it.
+#[derive(Default)]
+struct LineBuf {
+    cells: Vec<(char, Style)>,
+    cursor: usize,
+}
+
+impl LineBuf {
+    fn write(&mut self, text: &str, style: Style) {
+        for c in text.chars() {
+            match c {
+                '\x08' => self.cursor = self.cursor.saturating_sub(1),
+                c if c.is_control() && c != '\t' => {}
+                c => {
+                    if self.cursor < self.cells.len() {
+                        self.cells[self.cursor] = (c, style);
+                    } else {
+                        self.cells.resize(self.cursor, (' ', Style::default()));
+                        self.cells.push((c, style));
+                    }
+                    self.cursor += 1;
                 }
-                lines.push(Line::from(std::mem::take(spans)));
-                s = &s[nl + 1..];
             }
-            None => {
-                if !s.is_empty() {
-                    spans.push(Span::styled(s.to_owned(), style));
-                }
-                break;
+        }
+    }
+
+    /// ESC[K: 0 (or nothing) to the end of the line, 1 up to the cursor, 2 all of it.
+    fn erase(&mut self, mode: &str) {
+        match mode {
+            "1" => {
+                let upto = (self.cursor + 1).min(self.cells.len());
+                self.cells[..upto].fill((' ', Style::default()));
+            }
+            "2" => self.cells.clear(),
+            _ => self.cells.truncate(self.cursor),
+        }
+    }
+
+    fn finish(self) -> Line<'static> {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut run = String::new();
+        let mut run_style = Style::default();
+        for (c, style) in self.cells {
+            if style != run_style && !run.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style));
             }
+            run_style = style;
+            run.push(c);
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, run_style));
         }
+        Line::from(spans)
+    }
+}
+
+/// Act on the escape sequence at the start of `s` (just past the ESC); returns what follows it.
+fn escape<'a>(s: &'a str, line: &mut LineBuf, style: &mut Style) -> &'a str {
+    match s.as_bytes().first() {
+        Some(b'[') => {
+            // CSI: parameter and intermediate bytes, then one final byte.
+            let body = &s[1..];
+            let len = body.bytes().position(|b| !(0x20..=0x3f).contains(&b)).unwrap_or(body.len());
+            let params = &body[..len];
+            let Some(&fin) = body.as_bytes().get(len) else { return "" };
+            if !(0x40..=0x7e).contains(&fin) {
+                return &body[len..];
+            }
+            let count = || params.parse::<usize>().unwrap_or(1).max(1);
+            match fin {
+                b'm' => *style = apply_sgr(*style, params),
+                b'K' => line.erase(params),
+                b'C' => line.cursor = line.cursor.saturating_add(count()).min(MAX_COLUMN),
+                b'D' => line.cursor = line.cursor.saturating_sub(count()),
+                b'G' => line.cursor = count().saturating_sub(1).min(MAX_COLUMN),
+                _ => {}
+            }
+            &body[len + 1..]
+        }
+        Some(b']') => {
+            // OSC (window title, hyperlinks): up to BEL or ST (ESC \).
+            let body = &s[1..];
+            match body.find(['\x07', '\x1b']) {
+                Some(i) if body.as_bytes()[i] == 0x07 => &body[i + 1..],
+                Some(i) => body[i + 1..].strip_prefix('\\').unwrap_or(&body[i..]),
+                None => "",
+            }
+        }
+        Some(_) => {
+            let mut chars = s.chars();
+            chars.next();
+            chars.as_str()
+        }
+        None => "",
     }
 }
 
@@ -139,3 +215,62 @@ fn color_256(n: u8) -> Color {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, MAX_COLUMN};
+    use ratatui::prelude::*;
+
+    /// `cargo build` in a pane: progress lines rewritten with \r and ESC[K.
+    const CARGO: &str = "\x1b[1m\x1b[32m   Compiling\x1b[0m libc v0.2.153\n\
+        \x1b[1m\x1b[36m    Building\x1b[0m [=====>                  ] 12/80: serde, libc\r\x1b[K\
+        \x1b[1m\x1b[36m    Building\x1b[0m [===========>            ] 31/80: tokio\r\x1b[K\
+        \x1b[1m\x1b[32m    Finished\x1b[0m `dev` profile [unoptimized + debuginfo] target(s) in 4.21s\r\n";
+
+    /// `npm install`: a spinner redrawn with \r ESC[2K ESC[1G, cursor hidden meanwhile.
+    const NPM: &str = "\x1b[?25l\r\x1b[2K\x1b[1G\u{2819} reify:fsevents: \x1b[32mtiming\x1b[39m reifyNode Completed in 2ms\
+        \r\x1b[2K\x1b[1G\u{2839} idealTree:lib: sill placeDep ROOT\r\x1b[2K\x1b[1G\x1b[?25h\n\
+        added 214 packages, and audited 215 packages in 3s\n\
+        \x1b]0;npm install\x07found \x1b[32m\x1b[1m0\x1b[22m\x1b[39m vulnerabilities\n";
+
+    fn plain(text: &Text) -> Vec<String> {
+        text.lines.iter().map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn progress_lines_show_only_their_final_state() {
+        let cargo = parse(CARGO);
+        assert_eq!(
+            plain(&cargo),
+            [
+                "   Compiling libc v0.2.153",
+                "    Finished `dev` profile [unoptimized + debuginfo] target(s) in 4.21s",
+            ]
+        );
+        let finished = &cargo.lines[1].spans[0];
+        assert_eq!(finished.style.fg, Some(Color::Green), "colours survive the rewrites");
+
+        assert_eq!(
+            plain(&parse(NPM)),
+            ["", "added 214 packages, and audited 215 packages in 3s", "found 0 vulnerabilities"]
+        );
+    }
+
+    #[test]
+    fn cursor_moves_and_unknown_sequences_leave_no_fragments() {
+        assert_eq!(plain(&parse("a\x1b[3Cb")), ["a   b"]);
+        assert_eq!(plain(&parse("12345\x1b[3D\x1b[Kx")), ["12x"]);
+        assert_eq!(plain(&parse("x\x1b[?2004hy\x1b[200~z\x1b=!")), ["xyz!"]);
+        assert_eq!(plain(&parse("done\x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\")), ["donelink"]);
+        assert_eq!(plain(&parse("abc\x1b[3")), ["abc"], "cut off mid-sequence");
+    }
+
+    #[test]
+    fn huge_cursor_moves_are_clamped() {
+        for input in ["a\x1b[999999999Cb", "a\x1b[18446744073709551615Cb", "a\x1b[999999999Gb", "a\x1b[0Gb"] {
+            let line = &plain(&parse(input))[0];
+            assert!(line.chars().count() <= MAX_COLUMN + 1, "{:?}", input);
+            assert!(line.ends_with('b'), "{:?}", input);
+        }
+    }
+}
@@ -1,53 +1,166 @@
-// Per-project .gtrconfig editor overlay.
+// Per-project .gtrconfig editor overlay. Values inherited from the global gtrconfig
+// are marked "(global)".
 
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
-use crate::model::workspace::ProjectConfig;
+use crate::model::workspace::{Project, ProjectConfig};
 use crate::ui::popup_center;
 
-pub fn render_config_modal(frame: &mut Frame, area: Rect, config: &ProjectConfig, project_name: &str) {
-    let width = area.width.min(60).max(40);
-    let height = area.height.min(16).max(8);
+pub fn render_config_modal(frame: &mut Frame, area: Rect, config: &ProjectConfig, project: &Project) {
+    let width = area.width.clamp(40, 60);
+    let height = area.height.clamp(8, 20);
     let popup = popup_center(area, width, height);
 
     frame.render_widget(Clear, popup);
 
     let mut lines = vec![
+        Line::from(vec![
+            Span::styled("default branch: ", Style::default().fg(Color::Gray)),
+            Span::styled(project.default_branch.clone(), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                if project.default_branch_override { " (override)" } else { " (detected)" },
+                Style::default().fg(Color::Gray),
+            ),
+        ]),
         Line::from(vec![
             Span::styled("postCreate: ", Style::default().fg(Color::Gray)),
             Span::styled(
                 config.post_create.as_deref().unwrap_or("(none)"),
                 Style::default().fg(Color::White),
             ),
+            global_tag(config.inherited("hooks.postCreate") > 0),
         ]),
         Line::from(""),
         Line::from(Span::styled("copy.include:", Style::default().fg(Color::Gray))),
     ];
 
-    for inc in &config.copy_includes {
-        lines.push(Line::from(Span::styled(format!("  {}", inc), Style::default().fg(Color::Green))));
+    let inherited = config.inherited("copy.include");
+    for (i, inc) in config.copy_includes.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}", inc), Style::default().fg(Color::Green)),
+            global_tag(i < inherited),
+        ]));
     }
     if config.copy_includes.is_empty() {
         lines.push(Line::from(Span::styled("  (none)", Style::default().fg(Color::Gray))));
     }
 
     lines.push(Line::from(Span::styled("copy.exclude:", Style::default().fg(Color::Gray))));
-    for exc in &config.copy_excludes {
-        lines.push(Line::from(Span::styled(format!("  {}", exc), Style::default().fg(Color::Red))));
+    let inherited = config.inherited("copy.exclude");
+    for (i, exc) in config.copy_excludes.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}", exc), Style::default().fg(Color::Red)),
+            global_tag(i < inherited),
+        ]));
+    }
+
+    lines.push(Line::from(Span::styled("session.env:", Style::default().fg(Color::Gray))));
+    let inherited = config.inherited("session.env");
+    for (i, (name, value)) in config.session_env.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}=", name), Style::default().fg(Color::Cyan)),
+            Span::styled(shown_value(name, value), Style::default().fg(Color::White)),
+            global_tag(i < inherited),
+        ]));
+    }
+    if config.session_env.is_empty() {
+        lines.push(Line::from(Span::styled("  (none)", Style::default().fg(Color::Gray))));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("session.defaultCommand: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            config.default_command.as_deref().unwrap_or("(none)"),
+            Style::default().fg(Color::White),
+        ),
+        global_tag(config.inherited("session.defaultCommand") > 0),
+    ]));
+
+    lines.push(Line::from(Span::styled("template.file:", Style::default().fg(Color::Gray))));
+    let inherited = config.inherited("template.file");
+    for (i, (src, dest)) in config.templates.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} → {}", src, dest), Style::default().fg(Color::Green)),
+            global_tag(i < inherited),
+        ]));
+    }
+    if config.templates.is_empty() {
+        lines.push(Line::from(Span::styled("  (none)", Style::default().fg(Color::Gray))));
+    }
+
+    let derived = project.worktrees.iter().filter(|w| w.alias_derived).count();
+    let pattern = match (&config.alias_pattern, &config.alias_pattern_error) {
+        (Some(re), _) => Span::styled(
+            format!("{}  ({} derived)", re.as_str(), derived),
+            Style::default().fg(Color::White),
+        ),
+        (None, Some(err)) => Span::styled(err.clone(), Style::default().fg(Color::Red)),
+        (None, None) => Span::styled("(none)", Style::default().fg(Color::Gray)),
+    };
+    lines.push(Line::from(vec![
+        Span::styled("alias.pattern: ", Style::default().fg(Color::Gray)),
+        pattern,
+        global_tag(config.inherited("alias.pattern") > 0),
+    ]));
+
+    let hidden = project.worktrees.iter().filter(|w| w.hidden).count();
+    lines.push(Line::from(vec![
+        Span::styled("worktree.hide: ", Style::default().fg(Color::Gray)),
+        if config.worktree_hide.is_empty() {
+            Span::styled("(none)", Style::default().fg(Color::Gray))
+        } else {
+            Span::styled(
+                format!("{}  ({} hidden)", config.worktree_hide.join(", "), hidden),
+                Style::default().fg(Color::White),
+            )
+        },
+        global_tag(config.inherited("worktree.hide") > 0),
+    ]));
+
+    lines.push(Line::from(Span::styled("action.name (. menu):", Style::default().fg(Color::Gray))));
+    let inherited = config.inherited("action.name");
+    for (i, (label, command)) in config.actions.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}: ", label), Style::default().fg(Color::Cyan)),
+            Span::styled(command.clone(), Style::default().fg(Color::White)),
+            global_tag(i < inherited),
+        ]));
+    }
+    if config.actions.is_empty() {
+        lines.push(Line::from(Span::styled("  (none)", Style::default().fg(Color::Gray))));
     }
 
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "e: edit .gtrignore  Esc: close",
+        "e: edit .gtrignore  b: default branch  a: save aliases  Esc: close",
         Style::default().fg(Color::Gray),
     )));
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(format!(" Config: {} ", project_name))
+        .title(format!(" Config: {} ", project.name))
         .border_style(Style::default().fg(Color::Yellow));
     let para = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
     frame.render_widget(para, popup);
 }
+
+/// Marks a value inherited from the global gtrconfig rather than the project's own.
+fn global_tag(inherited: bool) -> Span<'static> {
+    if inherited {
+        Span::styled("  (global)", Style::default().fg(Color::DarkGray))
+    } else {
+        Span::raw("")
+    }
+}
+
+/// Values of secret-looking variables (and URLs, which often embed passwords) are masked.
+fn shown_value(name: &str, value: &str) -> String {
+    const SECRET: &[&str] = &["SECRET", "TOKEN", "PASSWORD", "PASSWD", "KEY", "CREDENTIAL", "URL", "DSN"];
+    let upper = name.to_ascii_uppercase();
+    if SECRET.iter().any(|s| upper.contains(s)) && !value.is_empty() {
+        "••••••".to_string()
+    } else {
+        value.to_string()
+    }
+}
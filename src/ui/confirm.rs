@@ -4,17 +4,34 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph},
 };
+use crate::app::ConfirmButton;
 use crate::ui::popup_upper;
+use crate::ui::wrap::wrapped_rows;
 
-pub fn render_confirm(frame: &mut Frame, area: Rect, message: &str) {
+/// `danger` escalates the styling (filled title, bold red text) for destructive
+/// confirms that affect something in active use.
+pub fn render_confirm(frame: &mut Frame, area: Rect, message: &str, danger: bool, focus: ConfirmButton) {
     let width = 60_u16.min(area.width);
-    let popup = popup_upper(area, width, 6);
+    // As tall as the wrapped message, plus the action bar and borders; three rows at least.
+    let text = Text::from(message);
+    let rows = wrapped_rows(&text, width.saturating_sub(2)).max(3) as u16;
+    let popup = popup_upper(area, width, (rows + 3).min(area.height));
 
     frame.render_widget(Clear, popup);
 
+    let (title, title_style, text_style) = if danger {
+        (
+            " ⚠ Confirm ",
+            Style::default().fg(Color::White).bg(Color::Red).bold(),
+            Style::default().fg(Color::LightRed).bold(),
+        )
+    } else {
+        (" Confirm ", Style::default(), Style::default())
+    };
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Confirm ")
+        .title(title)
+        .title_style(title_style)
         .border_style(Style::default().fg(Color::Red));
 
     let inner = block.inner(popup);
@@ -22,21 +39,54 @@ pub fn render_confirm(frame: &mut Frame, area: Rect, message: &str) {
 
     // Message (may wrap)
     let msg_area = Rect::new(inner.x, inner.y, inner.width, inner.height.saturating_sub(1));
-    let para = Paragraph::new(message)
-        .wrap(ratatui::widgets::Wrap { trim: true });
+    let para = Paragraph::new(text)
+        .style(text_style)
+        .wrap(ratatui::widgets::Wrap { trim: false });
     frame.render_widget(para, msg_area);
 
     // Action bar pinned to bottom
-    render_confirm_actions(frame, Rect::new(inner.x, inner.y + inner.height.saturating_sub(1), inner.width, 1));
+    let actions = Rect::new(inner.x, inner.y + inner.height.saturating_sub(1), inner.width, 1);
+    render_confirm_actions(frame, actions, focus);
 }
 
-/// Reusable confirm/cancel action bar: `[y/Enter] Confirm  [n/Esc] Cancel`
-pub fn render_confirm_actions(frame: &mut Frame, area: Rect) {
+/// Confirm/cancel action bar: `[y] Confirm  [n/Esc] Cancel`; Enter presses the
+/// focused button, shown reversed.
+pub fn render_confirm_actions(frame: &mut Frame, area: Rect, focus: ConfirmButton) {
+    let button = |label: &'static str, which: ConfirmButton| {
+        let style = Style::default().bold();
+        Span::styled(label, if focus == which { style.reversed() } else { style })
+    };
     let line = Line::from(vec![
-        Span::styled("[y/Enter]", Style::default().fg(Color::Green).bold()),
-        Span::raw(" Confirm  "),
+        Span::styled("[y]", Style::default().fg(Color::Green).bold()),
+        Span::raw(" "),
+        button(" Confirm ", ConfirmButton::Confirm),
+        Span::raw("  "),
         Span::styled("[n/Esc]", Style::default().fg(Color::Red).bold()),
-        Span::raw(" Cancel"),
+        Span::raw(" "),
+        button(" Cancel ", ConfirmButton::Cancel),
+        Span::styled("   ←/→ Enter", Style::default().fg(Color::DarkGray)),
     ]);
     frame.render_widget(Paragraph::new(line), area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::render_confirm;
+    use crate::app::ConfirmButton;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn long_messages_grow_the_popup() {
+        let message = "Delete worktree 'feature'? Its branch is merged. It diverged from main 47 days ago, \
+            23 commits on main since. 3 uncommitted changes lost! This will terminate wsx itself!";
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| render_confirm(frame, frame.area(), message, true, ConfirmButton::Cancel)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..30)
+            .map(|y| (0..80).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(screen.contains("itself!"), "{}", screen);
+        assert!(screen.contains("[n/Esc]"), "{}", screen);
+    }
+}
@@ -0,0 +1,41 @@
+// Doctor popup — setup check results.
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use crate::doctor::{Finding, Severity};
+use crate::ui::popup_center;
+
+pub fn render_doctor(frame: &mut Frame, area: Rect, findings: &[Finding]) {
+    let width = area.width.clamp(40, 72);
+    let height = (findings.len() as u16 * 2 + 4).clamp(5, area.height.max(5));
+    let popup = popup_center(area, width, height);
+
+    frame.render_widget(Clear, popup);
+
+    let mut lines = vec![Line::from("")];
+    if findings.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  All checks passed",
+            Style::default().fg(Color::Rgb(100, 200, 100)),
+        )));
+    }
+    for f in findings {
+        let (icon, color) = match f.severity {
+            Severity::Critical => ("✖", Color::Red),
+            Severity::Warning => ("!", Color::Yellow),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ", icon), Style::default().fg(color).bold()),
+            Span::raw(f.message.clone()),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Doctor ")
+        .border_style(Style::default().fg(Color::Cyan));
+    let para = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(para, popup);
+}
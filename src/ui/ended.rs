@@ -0,0 +1,64 @@
+// Recently ended sessions popup — the list on top, the selected one's last capture below.
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+};
+use crate::model::ended::EndedSession;
+use crate::model::frecency::now_secs;
+use crate::ui::picker::PickerState;
+use crate::ui::popup_center;
+use crate::ui::scrollbar::render_scrollbar;
+use crate::model::frecency::fmt_idle;
+use crate::ui::ansi;
+use crate::ui::wrap::wrapped_rows;
+
+/// List rows for `entries`: "name · project › worktree · 5m ago".
+pub fn ended_items(entries: &[EndedSession], now: u64) -> Vec<String> {
+    entries
+        .iter()
+        .map(|e| {
+            let worktree = e.worktree.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            let ago = fmt_idle(std::time::Duration::from_secs(now.saturating_sub(e.ts)));
+            format!("{} · {} › {} · {} ago", e.display_name, e.project, worktree, ago)
+        })
+        .collect()
+}
+
+/// `scroll` is clamped here; `u16::MAX` shows the end of the capture, where a crash is.
+pub fn render_ended(frame: &mut Frame, area: Rect, entries: &[EndedSession], picker: &mut PickerState, scroll: &mut u16) {
+    let width = area.width.saturating_sub(4).max(30).min(area.width);
+    let height = area.height.saturating_sub(2).max(8).min(area.height);
+    let popup = popup_center(area, width, height);
+    frame.render_widget(Clear, popup);
+
+    let list_height = (picker.items.len() as u16).min(6) + 2;
+    let [list_area, capture_area] =
+        Layout::vertical([Constraint::Length(list_height), Constraint::Min(3)]).areas(popup);
+
+    let items: Vec<ListItem> = picker.items.iter().map(|s| ListItem::new(s.as_str())).collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", picker.title))
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    frame.render_stateful_widget(list, list_area, &mut picker.list_state);
+
+    let Some(entry) = picker.selected().and_then(|i| entries.get(i)) else { return };
+    let ago = fmt_idle(std::time::Duration::from_secs(now_secs().saturating_sub(entry.ts)));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} · last capture, ended {} ago ", entry.session, ago))
+        .title_style(Style::default().bold());
+    let inner = block.inner(capture_area);
+    let text = ansi::parse(&entry.capture);
+    let total = wrapped_rows(&text, inner.width);
+    let para = Paragraph::new(text).wrap(Wrap { trim: false });
+    let max = total.saturating_sub(inner.height as usize).min(u16::MAX as usize) as u16;
+    *scroll = (*scroll).min(max);
+    frame.render_widget(para.block(block).scroll((*scroll, 0)), capture_area);
+    render_scrollbar(frame, capture_area, total, inner.height as usize, *scroll as usize);
+}
@@ -1,18 +1,15 @@
-use crate::ui::popup_center;
+use crate::ui::{ansi, popup_center, scrollbar::render_scrollbar, workspace_tree::truncate};
+use unicode_width::UnicodeWidthStr;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
 pub fn render_git_popup(frame: &mut Frame, area: Rect, default_branch: &str) {
-    let popup = popup_center(area, 36, 9);
+    let popup = popup_center(area, 36, 14);
     frame.render_widget(Clear, popup);
 
-    let def = if default_branch.len() > 10 {
-        &default_branch[..10]
-    } else {
-        default_branch
-    };
+    let def = truncate(default_branch, 10);
 
     let lines = vec![
         Line::from(""),
@@ -36,6 +33,26 @@ pub fn render_git_popup(frame: &mut Frame, area: Rect, default_branch: &str) {
             Span::styled("  (M)", Style::default().fg(Color::Yellow).bold()),
             Span::raw(format!(" Merge into {}…", def)),
         ]),
+        Line::from(vec![
+            Span::styled("  (b)", Style::default().fg(Color::Yellow).bold()),
+            Span::raw(" Branch off with changes…"),
+        ]),
+        Line::from(vec![
+            Span::styled("  (s)", Style::default().fg(Color::Yellow).bold()),
+            Span::raw(" Stashes…"),
+        ]),
+        Line::from(vec![
+            Span::styled("  (c)", Style::default().fg(Color::Yellow).bold()),
+            Span::raw(" Conflicts…"),
+        ]),
+        Line::from(vec![
+            Span::styled("  (h)", Style::default().fg(Color::Yellow).bold()),
+            Span::raw(" Re-run postCreate hook"),
+        ]),
+        Line::from(vec![
+            Span::styled("  (H)", Style::default().fg(Color::Yellow).bold()),
+            Span::raw(" Last postCreate output"),
+        ]),
         Line::from(""),
     ];
 
@@ -46,3 +63,50 @@ pub fn render_git_popup(frame: &mut Frame, area: Rect, default_branch: &str) {
     let para = Paragraph::new(lines).block(block);
     frame.render_widget(para, popup);
 }
+
+/// Read-only git output (e.g. `stash show --stat`), scrolled by `scroll` lines. `scroll`
+/// is clamped so the last page stays full — `u16::MAX` shows the tail.
+pub fn render_git_output(frame: &mut Frame, area: Rect, title: &str, text: &str, scroll: &mut u16) {
+    let width = area.width.clamp(40, 90).min(area.width);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    // Rows after wrapping; word wrap can take a little more, which only costs the tail a row.
+    let rows: usize = text.lines().map(|l| l.width().div_ceil(inner_width).max(1)).sum();
+    let rows = rows.min(u16::MAX as usize) as u16;
+    let height = rows.saturating_add(2).clamp(5, area.height.max(5));
+    *scroll = (*scroll).min(rows.saturating_sub(height - 2));
+    let popup = popup_center(area, width, height);
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", title))
+        .border_style(Style::default().fg(Color::Yellow));
+    let para = Paragraph::new(text)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((*scroll, 0));
+    frame.render_widget(para, popup);
+}
+
+/// One modified file's coloured diff, nearly full screen and clipped rather than wrapped;
+/// `scroll` is clamped here.
+pub fn render_diff(frame: &mut Frame, area: Rect, files: &[String], index: usize, text: &str, scroll: &mut u16) {
+    let width = area.width.saturating_sub(4).max(30).min(area.width);
+    let height = area.height.saturating_sub(2).max(8).min(area.height);
+    let popup = popup_center(area, width, height);
+    frame.render_widget(Clear, popup);
+
+    let file = files.get(index).map(String::as_str).unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} · {}/{} ", file, index + 1, files.len()))
+        .title_style(Style::default().bold())
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    let total = text.lines().count();
+    let max = total.saturating_sub(inner.height as usize).min(u16::MAX as usize) as u16;
+    *scroll = (*scroll).min(max);
+    let para = Paragraph::new(ansi::parse(text)).block(block).scroll((*scroll, 0));
+    frame.render_widget(para, popup);
+    render_scrollbar(frame, popup, total, inner.height as usize, *scroll as usize);
+}
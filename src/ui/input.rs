@@ -1,4 +1,5 @@
-// Input box with cursor movement, unicode support, and path completion.
+// Input box with cursor movement, unicode support, and completion (paths, words, history),
+// and multi-field forms built from them.
 
 use std::path::PathBuf;
 
@@ -6,7 +7,8 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
-use crate::ui::popup_upper;
+use unicode_width::UnicodeWidthStr;
+use crate::ui::{path::display_path, popup_upper};
 
 pub struct InputState {
     pub buffer: String,
@@ -15,26 +17,44 @@ pub struct InputState {
     pub completions: Vec<String>,
     pub completion_idx: Option<usize>,
     typed: String,     // last text the user typed (before completion navigation)
-    path_mode: bool,
+    source: CompletionSource,
+}
+
+/// Where an input's completions come from. Each prompt picks one; Tab / ↑ / ↓ then
+/// behave the same everywhere.
+pub enum CompletionSource {
+    None,
+    /// directories under the typed path
+    Path,
+    /// fixed candidates (aliases, branch names), fuzzy-matched against the typed text
+    Words(Vec<String>),
+    /// earlier entries, newest first; ↑ goes back in time like a shell. Typing filters
+    /// them: entries starting with the text first, then fuzzy matches, each newest first
+    History(Vec<String>),
 }
 
 impl InputState {
     pub fn new(prompt: impl Into<String>) -> Self {
-        Self::make(prompt.into(), String::new(), false)
+        Self::make(prompt.into(), String::new(), CompletionSource::None)
     }
 
     pub fn new_path(prompt: impl Into<String>, initial: String) -> Self {
-        let mut s = Self::make(prompt.into(), initial, true);
-        s.typed = s.buffer.clone();
-        s.completions = path_completions(&s.buffer);
+        let mut s = Self::make(prompt.into(), initial, CompletionSource::Path);
+        s.refresh_completions();
         s
     }
 
     pub fn with_value(prompt: impl Into<String>, value: String) -> Self {
-        Self::make(prompt.into(), value, false)
+        Self::make(prompt.into(), value, CompletionSource::None)
     }
 
-    fn make(prompt: String, value: String, path_mode: bool) -> Self {
+    pub fn with_source(prompt: impl Into<String>, value: String, source: CompletionSource) -> Self {
+        let mut s = Self::make(prompt.into(), value, source);
+        s.refresh_completions();
+        s
+    }
+
+    fn make(prompt: String, value: String, source: CompletionSource) -> Self {
         let cursor = value.len();
         Self {
             buffer: value.clone(),
@@ -43,18 +63,25 @@ impl InputState {
             completions: vec![],
             completion_idx: None,
             typed: value,
-            path_mode,
+            source,
         }
     }
 
+    fn refresh_completions(&mut self) {
+        self.completions = match &self.source {
+            CompletionSource::None => return,
+            CompletionSource::Path => path_completions(&self.buffer),
+            CompletionSource::Words(words) => word_completions(&self.buffer, words),
+            CompletionSource::History(entries) => history_completions(&self.buffer, entries),
+        };
+    }
+
     pub fn insert_char(&mut self, c: char) {
         self.buffer.insert(self.cursor, c);
         self.cursor += c.len_utf8();
         self.typed = self.buffer.clone();
         self.completion_idx = None;
-        if self.path_mode {
-            self.completions = path_completions(&self.buffer);
-        }
+        self.refresh_completions();
     }
 
     pub fn backspace(&mut self) {
@@ -68,9 +95,7 @@ impl InputState {
             self.cursor = prev;
             self.typed = self.buffer.clone();
             self.completion_idx = None;
-            if self.path_mode {
-                self.completions = path_completions(&self.buffer);
-            }
+            self.refresh_completions();
         }
     }
 
@@ -95,29 +120,51 @@ impl InputState {
         &self.buffer
     }
 
-    /// Move selection down; wraps around. Tab calls this too.
-    pub fn select_next(&mut self) {
+    fn is_history(&self) -> bool {
+        matches!(self.source, CompletionSource::History(_))
+    }
+
+    /// Tab: next completion, wrapping around.
+    pub fn tab(&mut self) {
+        self.select_next(true);
+    }
+
+    /// ↓: next completion (wrapping); in history, a newer entry, then back to what was typed.
+    pub fn down(&mut self) {
+        if self.is_history() { self.select_prev(false) } else { self.select_next(true) }
+    }
+
+    /// ↑: previous completion, via what was typed; in history, an older entry (stops at the oldest).
+    pub fn up(&mut self) {
+        if self.is_history() { self.select_next(false) } else { self.select_prev(true) }
+    }
+
+    fn select_next(&mut self, wrap: bool) {
         if self.completions.is_empty() { return; }
+        let last = self.completions.len() - 1;
         let next = match self.completion_idx {
             None => 0,
-            Some(i) => (i + 1) % self.completions.len(),
+            Some(i) if i < last => i + 1,
+            Some(_) if wrap => 0,
+            Some(i) => i,
         };
-        self.completion_idx = Some(next);
-        self.buffer = self.completions[next].clone();
-        self.cursor = self.buffer.len();
-        self.maybe_drill_down();
+        self.show_completion(Some(next));
     }
 
-    /// Move selection up. At index 0, goes back to typed text.
-    pub fn select_prev(&mut self) {
+    /// At index 0, goes back to the typed text.
+    fn select_prev(&mut self, wrap: bool) {
         if self.completions.is_empty() { return; }
         let prev = match self.completion_idx {
-            None => Some(self.completions.len().saturating_sub(1)),
-            Some(0) => None,
+            None if wrap => Some(self.completions.len() - 1),
+            None | Some(0) => None,
             Some(i) => Some(i - 1),
         };
-        self.completion_idx = prev;
-        self.buffer = match prev {
+        self.show_completion(prev);
+    }
+
+    fn show_completion(&mut self, idx: Option<usize>) {
+        self.completion_idx = idx;
+        self.buffer = match idx {
             None => self.typed.clone(),
             Some(i) => self.completions[i].clone(),
         };
@@ -128,7 +175,7 @@ impl InputState {
     /// If the current buffer ends with '/' and has only one child match,
     /// or was just selected as a unique completion, show children immediately.
     fn maybe_drill_down(&mut self) {
-        if self.buffer.ends_with('/') {
+        if matches!(self.source, CompletionSource::Path) && self.buffer.ends_with('/') {
             let children = path_completions(&self.buffer);
             if !children.is_empty() {
                 self.typed = self.buffer.clone();
@@ -139,7 +186,89 @@ impl InputState {
     }
 
     fn display_cursor(&self) -> usize {
-        self.buffer[..self.cursor].chars().count()
+        self.buffer[..self.cursor].width()
+    }
+}
+
+// ── Forms ────────────────────────────────────────────────────────────────────
+
+/// Several labelled inputs in one dialog. Tab cycles the fields; ↑ / ↓ move between
+/// them too, except in a history field, where they walk the history as in a prompt.
+pub struct FormState {
+    pub fields: Vec<FormField>,
+    pub focus: usize,
+    /// shown under the fields; cleared by the next edit
+    pub error: Option<String>,
+}
+
+pub struct FormField {
+    pub label: String,
+    pub input: InputState,
+}
+
+impl FormState {
+    /// `fields`: (label, input); the input's own prompt is not shown.
+    pub fn new(fields: Vec<(&str, InputState)>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|(label, input)| FormField { label: label.to_string(), input })
+            .collect();
+        Self { fields, focus: 0, error: None }
+    }
+
+    pub fn value(&self, idx: usize) -> &str {
+        self.fields[idx].input.value()
+    }
+
+    pub fn focused(&self) -> &InputState {
+        &self.fields[self.focus].input
+    }
+
+    fn focused_mut(&mut self) -> &mut InputState {
+        self.error = None;
+        &mut self.fields[self.focus].input
+    }
+
+    /// Report a validation problem and move to the field it's about.
+    pub fn fail(&mut self, idx: usize, msg: impl Into<String>) {
+        self.focus = idx;
+        self.error = Some(msg.into());
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.focused_mut().insert_char(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.focused_mut().backspace();
+    }
+
+    pub fn cursor_left(&mut self) {
+        self.fields[self.focus].input.cursor_left();
+    }
+
+    pub fn cursor_right(&mut self) {
+        self.fields[self.focus].input.cursor_right();
+    }
+
+    pub fn tab(&mut self) {
+        self.focus = (self.focus + 1) % self.fields.len();
+    }
+
+    pub fn down(&mut self) {
+        if self.focused().is_history() {
+            self.focused_mut().down();
+        } else if self.focus + 1 < self.fields.len() {
+            self.focus += 1;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.focused().is_history() {
+            self.focused_mut().up();
+        } else if self.focus > 0 {
+            self.focus -= 1;
+        }
     }
 }
 
@@ -167,6 +296,24 @@ fn fuzzy_score(query: &str, target: &str) -> Option<i32> {
     if qi == q.len() { Some(score) } else { None }
 }
 
+fn word_completions(input: &str, words: &[String]) -> Vec<String> {
+    let mut scored: Vec<(i32, &String)> = words
+        .iter()
+        .filter(|w| w.as_str() != input)
+        .filter_map(|w| Some((fuzzy_score(input, w)?, w)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+    scored.into_iter().map(|(_, w)| w.clone()).collect()
+}
+
+/// Entries starting with `input`, then the ones it fuzzy-matches; recency order within each.
+fn history_completions(input: &str, entries: &[String]) -> Vec<String> {
+    let candidates = entries.iter().filter(|e| e.as_str() != input);
+    let (prefixed, rest): (Vec<&String>, Vec<&String>) = candidates.partition(|e| e.starts_with(input));
+    let fuzzy = rest.into_iter().filter(|e| fuzzy_score(input, e).is_some());
+    prefixed.into_iter().chain(fuzzy).cloned().collect()
+}
+
 fn path_completions(input: &str) -> Vec<String> {
     let (expanded, tilde) = expand_input(input);
 
@@ -189,7 +336,7 @@ fn path_completions(input: &str) -> Vec<String> {
             let name = e.file_name().to_string_lossy().to_string();
             if name.starts_with('.') && !prefix.starts_with('.') { return None; }
             let score = fuzzy_score(&prefix, &name)?;
-            Some((score, display_path(&parent.join(&name), tilde)))
+            Some((score, format!("{}/", display_path(&parent.join(&name), !tilde, usize::MAX))))
         })
         .collect();
 
@@ -204,8 +351,8 @@ fn path_completions(input: &str) -> Vec<String> {
 
 fn expand_input(input: &str) -> (PathBuf, bool) {
     if let Some(home) = dirs::home_dir() {
-        if input.starts_with("~/") {
-            return (home.join(&input[2..]), true);
+        if let Some(rest) = input.strip_prefix("~/") {
+            return (home.join(rest), true);
         }
         if input == "~" {
             return (home, true);
@@ -214,22 +361,6 @@ fn expand_input(input: &str) -> (PathBuf, bool) {
     (PathBuf::from(if input.is_empty() { "." } else { input }), false)
 }
 
-fn display_path(path: &PathBuf, prefer_tilde: bool) -> String {
-    if prefer_tilde {
-        if let Some(home) = dirs::home_dir() {
-            if let Ok(rel) = path.strip_prefix(&home) {
-                let rel_str = rel.to_string_lossy();
-                return if rel_str.is_empty() {
-                    "~/".to_string()
-                } else {
-                    format!("~/{}/", rel_str)
-                };
-            }
-        }
-    }
-    format!("{}/", path.to_string_lossy())
-}
-
 // ── Rendering ────────────────────────────────────────────────────────────────
 
 pub fn render_input(frame: &mut Frame, area: Rect, state: &InputState, title: &str) {
@@ -246,33 +377,183 @@ pub fn render_input(frame: &mut Frame, area: Rect, state: &InputState, title: &s
     let para = Paragraph::new(display).block(block);
     frame.render_widget(para, popup);
 
-    let cursor_col = state.prompt.len() + state.display_cursor();
+    let cursor_col = state.prompt.width() + state.display_cursor();
     let cursor_x = popup.x + 1 + cursor_col as u16;
     frame.set_cursor_position((cursor_x.min(popup.x + popup.width - 2), popup.y + 1));
 
-    if !state.completions.is_empty() {
-        let max_show = 10usize.min(state.completions.len());
-        let drop_h = max_show as u16 + 2;
-        let drop_y = popup.y + 3;
-        if drop_y + drop_h <= area.y + area.height {
-            let drop = Rect::new(popup.x, drop_y, width, drop_h);
-            frame.render_widget(Clear, drop);
-
-            let items: Vec<ListItem> = state.completions.iter().take(max_show).enumerate()
-                .map(|(i, s)| {
-                    let selected = state.completion_idx == Some(i);
-                    let style = if selected {
-                        Style::default().fg(Color::Black).bg(Color::Cyan)
-                    } else {
-                        Style::default().fg(Color::Gray)
-                    };
-                    ListItem::new(format!(" {} ", s)).style(style)
-                })
-                .collect();
-
-            let list = List::new(items)
-                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Gray)));
-            frame.render_widget(list, drop);
+    render_completions(frame, area, popup, state);
+}
+
+/// Up to 10 of `state`'s completions in a list just below `popup`, if there's room.
+fn render_completions(frame: &mut Frame, area: Rect, popup: Rect, state: &InputState) {
+    if state.completions.is_empty() {
+        return;
+    }
+    let max_show = 10usize.min(state.completions.len());
+    let drop_h = max_show as u16 + 2;
+    let drop_y = popup.y + popup.height;
+    if drop_y + drop_h > area.y + area.height {
+        return;
+    }
+    let drop = Rect::new(popup.x, drop_y, popup.width, drop_h);
+    frame.render_widget(Clear, drop);
+
+    let items: Vec<ListItem> = state.completions.iter().take(max_show).enumerate()
+        .map(|(i, s)| {
+            let selected = state.completion_idx == Some(i);
+            let style = if selected {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            ListItem::new(format!(" {} ", s)).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Gray)));
+    frame.render_widget(list, drop);
+}
+
+pub fn render_form(frame: &mut Frame, area: Rect, form: &FormState, title: &str) {
+    let width = area.width.min(60);
+    let extra = if form.error.is_some() { 1 } else { 0 };
+    let popup = popup_upper(area, width, form.fields.len() as u16 + extra + 3);
+
+    frame.render_widget(Clear, popup);
+
+    let label_width = form.fields.iter().map(|f| f.label.width()).max().unwrap_or(0);
+    let mut lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let focused = i == form.focus;
+            let label_style = if focused {
+                Style::default().fg(Color::Cyan).bold()
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            Line::from(vec![
+                Span::styled(if focused { "› " } else { "  " }, label_style),
+                Span::styled(format!("{:<w$} ", field.label, w = label_width), label_style),
+                Span::styled(field.input.buffer.clone(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+    if let Some(err) = &form.error {
+        lines.push(Line::from(Span::styled(format!("  {}", err), Style::default().fg(Color::Red))));
+    }
+    lines.push(Line::from(Span::styled(
+        "  Tab: next field  Enter: submit  Esc: cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", title))
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+
+    let cursor_col = 2 + label_width + 1 + form.focused().display_cursor();
+    let cursor_x = popup.x + 1 + cursor_col as u16;
+    let cursor_y = popup.y + 1 + form.focus as u16;
+    frame.set_cursor_position((cursor_x.min(popup.x + popup.width - 2), cursor_y));
+    render_completions(frame, area, popup, form.focused());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompletionSource, FormState, InputState};
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn words_cycle_and_come_back_to_typed_text() {
+        let mut input = InputState::with_source("", String::new(), CompletionSource::Words(words(&["main", "master"])));
+        input.insert_char('m');
+        input.insert_char('a');
+        assert_eq!(input.completions, ["main", "master"]);
+        input.tab();
+        input.tab();
+        assert_eq!(input.value(), "master");
+        input.down();
+        assert_eq!(input.value(), "main", "Tab / ↓ wrap around");
+        input.up();
+        assert_eq!(input.value(), "ma", "↑ from the first goes back to what was typed");
+        input.up();
+        assert_eq!(input.value(), "master", "…and then wraps to the last");
+    }
+
+    #[test]
+    fn history_walks_back_in_time_on_up() {
+        let entries = words(&["cargo test", "npm run dev", "cargo build"]);
+        let mut input = InputState::with_source("", String::new(), CompletionSource::History(entries));
+        input.down();
+        assert_eq!(input.value(), "", "↓ with nothing newer does nothing");
+        input.up();
+        input.up();
+        assert_eq!(input.value(), "npm run dev");
+        for _ in 0..5 {
+            input.up();
         }
+        assert_eq!(input.value(), "cargo build", "stops at the oldest");
+        input.down();
+        input.down();
+        input.down();
+        assert_eq!(input.value(), "", "↓ past the newest restores the typed text");
+
+        input.insert_char('c');
+        assert_eq!(input.completions, ["cargo test", "cargo build"], "prefix-filtered");
+        input.up();
+        assert_eq!(input.value(), "cargo test");
+    }
+
+    #[test]
+    fn history_filter_puts_prefix_matches_before_fuzzy_ones() {
+        let entries = words(&["npm run dev", "cargo run", "cargo build", "rg todo"]);
+        let mut input = InputState::with_source("", String::new(), CompletionSource::History(entries));
+        for c in "r".chars() {
+            input.insert_char(c);
+        }
+        assert_eq!(input.completions, ["rg todo", "npm run dev", "cargo run", "cargo build"]);
+        for c in "un".chars() {
+            input.insert_char(c);
+        }
+        assert_eq!(input.completions, ["npm run dev", "cargo run"], "fuzzy, newest first");
+        input.up();
+        input.insert_char('!');
+        assert_eq!(input.value(), "npm run dev!", "a picked entry stays editable");
+    }
+
+    #[test]
+    fn plain_inputs_ignore_navigation() {
+        let mut input = InputState::with_value("", "typed".into());
+        input.up();
+        input.down();
+        input.tab();
+        assert_eq!(input.value(), "typed");
+    }
+
+    #[test]
+    fn form_arrows_leave_history_fields_to_the_history() {
+        let history = CompletionSource::History(words(&["cargo test"]));
+        let mut form = FormState::new(vec![
+            ("name", InputState::new("")),
+            ("command", InputState::with_source("", String::new(), history)),
+        ]);
+        form.down();
+        assert_eq!(form.focus, 1);
+        form.up();
+        assert_eq!((form.focus, form.value(1)), (1, "cargo test"), "↑ walks the history");
+        form.tab();
+        assert_eq!(form.focus, 0, "Tab wraps around");
+        form.up();
+        assert_eq!(form.focus, 0);
+        form.fail(1, "bad");
+        form.insert_char('x');
+        assert_eq!((form.value(1), form.error.as_deref()), ("cargo testx", None), "editing clears the error");
     }
 }
@@ -3,29 +3,47 @@
 pub mod ansi;
 pub mod config_modal;
 pub mod confirm;
+pub mod doctor;
+pub mod ended;
 pub mod git_popup;
 pub mod input;
+pub mod path;
 pub mod picker;
 pub mod preview;
+pub mod scrollbar;
+pub mod stats;
+pub mod theme;
 pub mod workspace_tree;
+pub mod wrap;
 
 use crate::app::{App, Mode};
-use crate::model::workspace::Selection;
+use crate::model::attention::AttentionReason;
+use crate::model::frecency::now_secs;
+use crate::model::sticky_error::StickyError;
+use crate::model::tree_nav;
+use crate::model::workspace::{Selection, SessionMark};
 use crate::ui::{
     config_modal::render_config_modal,
     confirm::render_confirm,
-    git_popup::render_git_popup,
-    input::render_input,
+    doctor::render_doctor,
+    ended::render_ended,
+    git_popup::{render_diff, render_git_output, render_git_popup},
+    input::{render_form, render_input},
+    path::display_path,
+    picker::render_picker,
     preview::{
-        render_empty_preview, render_project_preview, render_session_preview,
+        render_archive_preview, render_empty_preview, render_project_preview, render_session_preview,
         render_worktree_preview,
     },
-    workspace_tree::{compute_scroll, render_tree},
+    stats::render_stats,
+    theme::Markers,
+    workspace_tree::{render_tree, TreeStyle},
 };
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Center a popup of given size within `area`.
 pub fn popup_center(area: Rect, w: u16, h: u16) -> Rect {
@@ -37,14 +55,24 @@ pub fn popup_center(area: Rect, w: u16, h: u16) -> Rect {
 /// Place a popup in the upper third of `area`.
 pub fn popup_upper(area: Rect, w: u16, h: u16) -> Rect {
     let x = area.x + (area.width.saturating_sub(w)) / 2;
-    let y = area.y + area.height / 3;
+    // A third of the way down, moved up as far as a tall popup needs to fit.
+    let y = (area.y + area.height / 3).min(area.bottom().saturating_sub(h)).max(area.y);
     Rect::new(x, y, w, h)
 }
 
 pub fn render(frame: &mut Frame, app: &mut App) {
-    let area = frame.area();
+    let mut area = frame.area();
+    if app.tmux_down && area.height > 2 {
+        render_tmux_down_banner(frame, Rect::new(area.x, area.y, area.width, 1));
+        area = Rect::new(area.x, area.y + 1, area.width, area.height - 1);
+    }
+    if let Some(error) = app.error.as_ref().filter(|_| area.height > 2) {
+        render_error_banner(frame, Rect::new(area.x, area.y, area.width, 1), error);
+        area = Rect::new(area.x, area.y + 1, area.width, area.height - 1);
+    }
 
-    let sb_height = status_bar_height(app, area.width);
+    // Wrapped hints can outgrow a tiny terminal; never let them take more than half.
+    let sb_height = status_bar_height(app, area.width).min(area.height / 2);
     let main_area = Rect::new(
         area.x,
         area.y,
@@ -60,35 +88,64 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(36), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(app.config.sidebar_width()),
+            Constraint::Min(0),
+        ])
         .split(main_area);
 
     let visible_height = chunks[0].height.saturating_sub(2) as usize;
     app.tree_visible_height = visible_height;
-    app.tree_scroll = compute_scroll(app.tree_selected, visible_height, app.tree_scroll);
+    app.tree_scroll = tree_nav::compute_scroll(app.tree_selected, visible_height, app.tree_scroll);
     app.tree_area = chunks[0];
     app.preview_area = chunks[1];
 
-    let is_move_mode = matches!(app.mode, Mode::Move { .. } | Mode::MoveSession { .. });
+    let style = TreeStyle {
+        move_mode: matches!(app.mode, Mode::Move { .. } | Mode::MoveSession { .. }),
+        raw_names: app.raw_names,
+        own_session: app.own_session.as_deref(),
+        tmux_down: app.tmux_down,
+    };
     render_tree(
         frame,
         chunks[0],
         &app.workspace,
         app.tree_selected,
         app.tree_scroll,
-        is_move_mode,
+        style,
+        &app.config,
     );
+    let raw = app.raw_names;
+    let full_paths = app.full_paths;
+    // Titles sit in the top border, between the corners and a space either side.
+    let title_width = (chunks[1].width as usize).saturating_sub(4);
 
     let preview_area = chunks[1];
+    app.preview_sessions_top = None;
     match app.current_selection() {
         Selection::Session(pi, wi, si) => {
+            let window = app.previewed_window();
             if let Some((sess, title)) = app.workspace.projects.get(pi).and_then(|p| {
                 let wt = p.worktrees.get(wi)?;
                 let sess = wt.sessions.get(si)?;
-                let title = format!("{} › {} › {}", p.name, wt.display_name(), sess.display_name);
+                let win = window.and_then(|w| sess.windows.get(w));
+                let mut title = match (raw, win) {
+                    (true, Some(win)) => format!("{}:{}", sess.name, win.index),
+                    (true, None) => sess.name.clone(),
+                    (false, _) => {
+                        format!("{} › {} › {}", p.name, wt.display_name(), sess.shown_name())
+                    }
+                };
+                if let (false, Some(win)) = (raw, win) {
+                    title = format!("{} › {}:{}", title, win.index, win.name);
+                }
+                if let Some(cmd) = sess.commands.first().filter(|&c| c != &sess.display_name) {
+                    title = format!("{} · {}", title, cmd);
+                }
                 Some((sess.clone(), title))
             }) {
-                render_session_preview(frame, preview_area, &sess, &title);
+                let usage = app.workspace.attaches.stats(&sess.name, now_secs());
+                render_session_preview(frame, preview_area, &sess, &title, usage, &mut app.session_view, full_paths);
             } else {
                 render_empty_preview(frame, preview_area);
             }
@@ -96,30 +153,55 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         Selection::Worktree(pi, wi) => {
             if let Some((worktree, title)) = app.workspace.projects.get(pi).and_then(|p| {
                 p.worktrees.get(wi).map(|wt| {
-                    let title = format!("{} › {}", p.name, wt.display_name());
+                    let title = if raw {
+                        display_path(&wt.path, full_paths, title_width)
+                    } else {
+                        format!("{} › {}", p.name, wt.display_name())
+                    };
                     (wt.clone(), title)
                 })
             }) {
-                render_worktree_preview(frame, preview_area, &worktree, &title);
+                app.preview_sessions_top = render_worktree_preview(
+                    frame,
+                    preview_area,
+                    (&worktree, app.workspace.setup.get(&worktree.path), &app.workspace.session_order(&worktree)),
+                    &title,
+                    app.config.fetch_stale_after(),
+                    full_paths,
+                    Markers::new(app.config.accessible_markers),
+                );
             } else {
                 render_empty_preview(frame, preview_area);
             }
         }
         Selection::Project(pi) => {
             if let Some(project) = app.workspace.projects.get(pi).cloned() {
-                render_project_preview(frame, preview_area, &project);
+                let title =
+                    if raw { display_path(&project.path, full_paths, title_width) } else { project.name.clone() };
+                render_project_preview(frame, preview_area, &project, &title, full_paths);
             } else {
                 render_empty_preview(frame, preview_area);
             }
         }
+        Selection::Archive(pi, ai) => match app.workspace.projects.get(pi) {
+            Some(project) => {
+                let title = format!("{} › Archived", project.name);
+                render_archive_preview(frame, preview_area, project, ai, &title);
+            }
+            None => render_empty_preview(frame, preview_area),
+        },
         Selection::None => render_empty_preview(frame, preview_area),
     }
 
     render_status_bar(frame, status_area, app);
     render_overlay(frame, main_area, app);
-    if app.loading {
-        render_loading(frame, main_area);
+    if let Some(c) = &app.cloning {
+        let pct = c.percent.map(|p| format!(" {}%", p)).unwrap_or_default();
+        render_loading(frame, main_area, &format!("Cloning {}…{}", c.name, pct));
+    } else if app.loading {
+        render_loading(frame, main_area, "Working…");
     }
+    app.theme.apply(frame.buffer_mut());
 }
 
 fn render_overlay(frame: &mut Frame, area: Rect, app: &mut App) {
@@ -128,28 +210,39 @@ fn render_overlay(frame: &mut Frame, area: Rect, app: &mut App) {
             let title = context.title();
             render_input(frame, area, state, title);
         }
-        Mode::Confirm { message, .. } => {
-            let msg = message.clone();
-            render_confirm(frame, area, &msg);
+        Mode::Form { context, form } => render_form(frame, area, form, context.title()),
+        Mode::Confirm { message, danger, .. } => {
+            let (msg, danger) = (message.clone(), *danger);
+            render_confirm(frame, area, &msg, danger, app.confirm_focus);
         }
-        Mode::Config { project_idx } => {
-            let pi = *project_idx;
-            if let Some(project) = app.workspace.projects.get(pi) {
+        Mode::Config { project } => {
+            if let Some(project) = app.workspace.projects.iter().find(|p| &p.path == project) {
                 let config = project.config.clone().unwrap_or_default();
-                let name = project.name.clone();
-                render_config_modal(frame, area, &config, &name);
+                render_config_modal(frame, area, &config, project);
             }
         }
-        Mode::Help => render_help(frame, area),
-        Mode::GitPopup { project_idx: pi, .. } => {
+        Mode::Help => render_help(frame, area, Markers::new(app.config.accessible_markers)),
+        Mode::GitPopup { worktree } => {
             let def = app
                 .workspace
-                .projects
-                .get(*pi)
-                .map(|p| p.default_branch.clone())
+                .find_worktree(worktree)
+                .map(|(pi, _)| app.workspace.projects[pi].default_branch.clone())
                 .unwrap_or_else(|| "main".to_string());
             render_git_popup(frame, area, &def);
         }
+        Mode::OpenWith { picker, .. }
+        | Mode::ProjectActions { picker, .. }
+        | Mode::ImportProjects { picker, .. }
+        | Mode::Stashes { picker, .. }
+        | Mode::Conflicts { picker, .. }
+        | Mode::DiffFiles { picker, .. } => render_picker(frame, area, picker),
+        Mode::GitOutput { title, text, scroll } => {
+            render_git_output(frame, area, title, text, scroll)
+        }
+        Mode::Doctor { findings } => render_doctor(frame, area, findings),
+        Mode::Stats { stats } => render_stats(frame, area, stats, &app.repo_queue.lanes()),
+        Mode::Ended { entries, picker, scroll } => render_ended(frame, area, entries, picker, scroll),
+        Mode::Diff { files, index, text, scroll, .. } => render_diff(frame, area, files, *index, text, scroll),
         Mode::Normal | Mode::Move { .. } | Mode::MoveSession { .. } | Mode::Search { .. } => {}
     }
 }
@@ -157,72 +250,235 @@ fn render_overlay(frame: &mut Frame, area: Rect, app: &mut App) {
 fn get_mode_label(app: &App) -> &'static str {
     match &app.mode {
         Mode::Normal => "NORMAL",
-        Mode::Input { .. } => "INPUT",
+        Mode::Input { .. } | Mode::Form { .. } => "INPUT",
         Mode::Confirm { .. } => "CONFIRM",
         Mode::Config { .. } => "CONFIG",
         Mode::Move { .. } | Mode::MoveSession { .. } => "MOVE",
         Mode::Help => "HELP",
         Mode::Search { .. } => "SEARCH",
-        Mode::GitPopup { .. } => "GIT",
+        Mode::GitPopup { .. }
+        | Mode::Stashes { .. }
+        | Mode::Conflicts { .. }
+        | Mode::GitOutput { .. } => "GIT",
+        Mode::OpenWith { .. } => "OPEN",
+        Mode::ProjectActions { .. } => "ACTIONS",
+        Mode::ImportProjects { .. } => "IMPORT",
+        Mode::Doctor { .. } => "DOCTOR",
+        Mode::Stats { .. } => "STATS",
+        Mode::Ended { .. } => "ENDED",
+        Mode::DiffFiles { .. } | Mode::Diff { .. } => "DIFF",
     }
 }
 
-fn build_hints(app: &App) -> String {
-    let global = "(/)search  (a)ctive  ·  (n)ext (N)prev pending  ·  (e)config  (?)help";
-    match &app.mode {
-        Mode::Normal => match app.current_selection() {
-            Selection::Project(_) => format!("(m)ove  (w)orktree  (d)el  (c)lean  ·  {}", global),
-            Selection::Worktree(_, _) => format!(
-                "(s)ession  (r)alias  (d)el  ·  (w)orktree  (c)lean  ·  {}",
-                global
-            ),
-            Selection::Session(pi, wi, si) => {
-                let active = app
-                    .workspace
-                    .projects
-                    .get(pi)
-                    .and_then(|p| p.worktrees.get(wi))
-                    .and_then(|w| w.sessions.get(si))
-                    .map(|s| {
-                        s.last_activity
-                            .map(|t| t.elapsed().as_secs() < crate::app::IDLE_SECS)
-                            .unwrap_or(false)
-                    })
-                    .unwrap_or(false);
-                let dismiss = if active { "" } else { "(x)dismiss  ·  " };
-                format!("(m)ove  (r)ename  (d)kill  ·  {}(S)send cmd  (C)ctrl-c  ·  (C-a d)detach  ·  (s)ession  ·  (w)orktree  (c)lean  ·  {}", dismiss, global)
+/// A status-bar hint: (key, label). Rendered as a highlighted key followed by its label.
+type Hint = (&'static str, &'static str);
+
+const GLOBAL_HINTS: &[&[Hint]] = &[
+    &[("/", "search"), ("a", "active")],
+    &[("b", "next pending"), ("B", "prev pending")],
+    &[("e", "config"), ("?", "help")],
+];
+
+/// After the search group while a `/` search is remembered: `n` / `N` repeat it.
+const SEARCH_HINTS: &[Hint] = &[("n", "next match"), ("N", "prev match")];
+
+/// What the Normal-mode hints depend on besides the selection itself.
+#[derive(Debug, Clone, Copy, Default)]
+struct EntryState {
+    /// `--pick-path`: Enter picks the worktree
+    pick_path: bool,
+    /// the project's directory is gone
+    missing: bool,
+    /// the main worktree, which `d`, `A`, `c` and `L` refuse
+    is_main: bool,
+    /// the session had output within `IDLE_SECS` and wants no attention: `x` has nothing to do
+    active: bool,
+    /// a worktree made outside wsx, which `+` adopts
+    external: bool,
+    /// a `/` search is remembered, so `n` / `N` step through its matches
+    searching: bool,
+}
+
+impl EntryState {
+    fn of(app: &App, selection: &Selection) -> Self {
+        let project = |pi: usize| app.workspace.projects.get(pi);
+        let worktree = |pi: usize, wi: usize| app.workspace.worktree(pi, wi);
+        let active = match *selection {
+            Selection::Session(pi, wi, si) => worktree(pi, wi)
+                .and_then(|w| w.sessions.get(si))
+                .filter(|s| s.attention.is_empty())
+                .and_then(|s| s.last_activity)
+                .is_some_and(|t| t.elapsed().as_secs() < crate::app::IDLE_SECS),
+            _ => false,
+        };
+        EntryState {
+            pick_path: app.pick_path,
+            missing: matches!(*selection, Selection::Project(pi) if project(pi).is_some_and(|p| p.missing)),
+            is_main: matches!(*selection, Selection::Worktree(pi, wi) if worktree(pi, wi).is_some_and(|w| w.is_main)),
+            active,
+            external: matches!(*selection, Selection::Worktree(pi, wi) if worktree(pi, wi).is_some_and(|w| w.external)),
+            searching: app.last_search.is_some(),
+        }
+    }
+}
+
+/// Normal-mode hints for `selection`, offering only what works on it.
+fn normal_hints(selection: &Selection, entry: EntryState) -> Vec<Vec<Hint>> {
+    let with_global = |groups: &[&[Hint]]| -> Vec<Vec<Hint>> {
+        let (search, rest) = GLOBAL_HINTS.split_at(1);
+        let found: &[&[Hint]] = if entry.searching { &[SEARCH_HINTS] } else { &[] };
+        groups
+            .iter()
+            .chain(search)
+            .chain(found)
+            .chain(rest)
+            .map(|g| g.to_vec())
+            .collect()
+    };
+    match selection {
+        Selection::Project(_) if entry.missing => with_global(&[&[("Enter", "relocate"), ("d", "del")]]),
+        Selection::Project(_) => with_global(&[
+            &[("m", "move"), ("w", "worktree"), ("d", "del"), ("c", "clean")],
+            &[("Space", "start work"), ("!", "main shell")],
+        ]),
+        Selection::Worktree(_, _) if entry.pick_path && entry.is_main => with_global(&[
+            &[("Enter", "pick path")],
+            &[("s", "session"), ("r", "alias")],
+            &[("w", "worktree")],
+        ]),
+        Selection::Worktree(_, _) if entry.pick_path => with_global(&[
+            &[("Enter", "pick path")],
+            &[("s", "session"), ("r", "alias"), ("d", "del")],
+            &[("w", "worktree"), ("c", "clean")],
+        ]),
+        Selection::Worktree(_, _) if entry.is_main => with_global(&[
+            &[("s", "session"), ("r", "alias")],
+            &[("w", "worktree"), ("V", "diff")],
+        ]),
+        Selection::Worktree(_, _) if entry.external => with_global(&[
+            &[("+", "adopt")],
+            &[("s", "session"), ("r", "alias"), ("d", "del"), ("A", "archive")],
+            &[("w", "worktree"), ("c", "clean"), ("L", "lock"), ("V", "diff")],
+        ]),
+        Selection::Worktree(_, _) => with_global(&[
+            &[("s", "session"), ("r", "alias"), ("d", "del"), ("A", "archive")],
+            &[("w", "worktree"), ("c", "clean"), ("L", "lock"), ("V", "diff")],
+        ]),
+        Selection::Archive(_, None) => with_global(&[&[("Enter", "expand")]]),
+        Selection::Archive(_, Some(_)) => with_global(&[&[("Enter", "restore"), ("d", "purge")]]),
+        Selection::Session(..) => {
+            let mut groups: Vec<&[Hint]> = vec![&[("m", "move"), ("r", "rename"), ("d", "kill")]];
+            if !entry.active {
+                groups.push(&[("x", "dismiss")]);
             }
-            Selection::None => "(p) add project".to_string(),
-        },
-        Mode::Input { .. } => "Esc: cancel".to_string(),
-        Mode::Confirm { .. } => "(y)es  (n)o".to_string(),
-        Mode::Config { .. } => "(e)dit .gtrignore  Esc: close".to_string(),
-        Mode::Move { .. } | Mode::MoveSession { .. } => "(j/k) reorder  Esc: done".to_string(),
-        Mode::Help => "Esc: close".to_string(),
+            groups.extend_from_slice(&[
+                &[("S", "send cmd"), ("C", "ctrl-c")],
+                &[("C-a d", "detach")],
+                &[("s", "session")],
+                &[("w", "worktree"), ("c", "clean")],
+            ]);
+            with_global(&groups)
+        }
+        Selection::None => vec![vec![("p", "add project")]],
+    }
+}
+
+/// Hints grouped by scope. Wrapping prefers group boundaries.
+fn build_hints(app: &App) -> Vec<Vec<Hint>> {
+    match &app.mode {
+        Mode::Normal => {
+            let selection = app.current_selection();
+            normal_hints(&selection, EntryState::of(app, &selection))
+        }
+        Mode::Input { .. } => vec![vec![("Esc", "cancel")]],
+        Mode::Form { .. } => vec![vec![("Tab", "next field"), ("Enter", "submit"), ("Esc", "cancel")]],
+        Mode::Confirm { .. } => vec![vec![("y", "yes"), ("n", "no"), ("←/→", "choose"), ("Enter", "press")]],
+        Mode::Config { .. } => {
+            vec![vec![("e", "edit .gtrignore"), ("b", "default branch"), ("a", "save aliases"), ("Esc", "close")]]
+        }
+        Mode::Move { .. } | Mode::MoveSession { .. } => vec![vec![("j/k", "reorder"), ("Esc", "done")]],
+        Mode::Help => vec![vec![("Esc", "close")]],
         Mode::Search { .. } => unreachable!(),
-        Mode::GitPopup { .. } => {
-            "(p)ull  (P)ush  (r)pull-rebase  (m)erge-from  (M)erge-into  Esc: close".to_string()
+        Mode::GitPopup { .. } => vec![vec![
+            ("p", "pull"),
+            ("P", "push"),
+            ("r", "pull-rebase"),
+            ("m", "merge-from"),
+            ("M", "merge-into"),
+            ("s", "stashes"),
+            ("c", "conflicts"),
+            ("Esc", "close"),
+        ]],
+        Mode::Stashes { .. } => vec![vec![
+            ("a", "apply"),
+            ("p", "pop"),
+            ("d", "drop"),
+            ("s", "show"),
+            ("Esc", "close"),
+        ]],
+        Mode::Conflicts { .. } => vec![vec![
+            ("Enter", "edit"),
+            ("a", "mark resolved"),
+            ("Esc", "close"),
+        ]],
+        Mode::GitOutput { .. } if app.post_create.is_some() => {
+            vec![vec![("j/k", "scroll"), ("Esc", "cancel postCreate")]]
+        }
+        Mode::GitOutput { .. } => vec![vec![("j/k", "scroll"), ("Esc", "close")]],
+        Mode::OpenWith { .. } => vec![vec![("j/k", "choose"), ("Enter", "open"), ("Esc", "close")]],
+        Mode::ProjectActions { .. } => vec![vec![("j/k", "choose"), ("Enter", "run"), ("Esc", "close")]],
+        Mode::ImportProjects { .. } => {
+            vec![vec![("j/k", "choose"), ("Space", "toggle"), ("Enter", "add ticked"), ("Esc", "skip")]]
         }
+        Mode::Ended { .. } => vec![vec![("j/k", "choose"), ("PgUp/PgDn", "scroll"), ("Esc", "close")]],
+        Mode::DiffFiles { .. } => vec![vec![("j/k", "choose"), ("Enter", "diff"), ("Esc", "close")]],
+        Mode::Diff { .. } => vec![vec![("[/]", "prev/next file"), ("j/k", "scroll"), ("Esc", "close")]],
+        Mode::Doctor { .. } | Mode::Stats { .. } => vec![vec![("Esc", "close")]],
     }
 }
 
-// Split hints at "  ·  " scope separators to fit within `available_width` chars per line.
-fn wrap_hints(hints: &str, available_width: usize) -> Vec<String> {
-    let groups: Vec<&str> = hints.split("  ·  ").collect();
-    let mut lines: Vec<String> = Vec::new();
-    let mut current = String::new();
-    for group in &groups {
-        if current.is_empty() {
-            current = group.to_string();
-        } else {
-            let candidate = format!("{}  {}", current, group);
-            if candidate.len() <= available_width {
-                current = candidate;
-            } else {
-                lines.push(current);
-                current = group.to_string();
+const HINT_SEP: &str = "  ";
+/// Between scope groups (selection · session · global), drawn dim.
+const GROUP_SEP: &str = "  ·  ";
+
+/// One status-bar line: the groups (or pieces of one) that fit on it.
+type HintLine = Vec<Vec<Hint>>;
+
+fn hint_width(hint: &Hint) -> usize {
+    hint.0.width() + 1 + hint.1.width()
+}
+
+fn hints_width(hints: &[Hint]) -> usize {
+    let sep = HINT_SEP.len() * hints.len().saturating_sub(1);
+    hints.iter().map(hint_width).sum::<usize>() + sep
+}
+
+fn line_width(line: &[Vec<Hint>]) -> usize {
+    let sep = GROUP_SEP.width() * line.len().saturating_sub(1);
+    line.iter().map(|g| hints_width(g)).sum::<usize>() + sep
+}
+
+// Pack hint groups into lines of at most `available_width` chars. Groups stay whole
+// when they fit; an oversized group breaks between hints, never inside one.
+fn wrap_hints(groups: &[Vec<Hint>], available_width: usize) -> Vec<HintLine> {
+    let mut lines: Vec<HintLine> = Vec::new();
+    let mut current: HintLine = Vec::new();
+    for group in groups {
+        let joined = line_width(&current) + GROUP_SEP.width() + hints_width(group);
+        if !current.is_empty() && joined > available_width {
+            lines.push(std::mem::take(&mut current));
+        }
+        // A single group wider than the line: spill hint by hint.
+        let mut rest = group.as_slice();
+        while current.is_empty() && rest.len() > 1 && hints_width(rest) > available_width {
+            let mut split = rest.len() - 1;
+            while split > 1 && hints_width(&rest[..split]) > available_width {
+                split -= 1;
             }
+            lines.push(vec![rest[..split].to_vec()]);
+            rest = &rest[split..];
         }
+        current.push(rest.to_vec());
     }
     if !current.is_empty() {
         lines.push(current);
@@ -230,6 +486,26 @@ fn wrap_hints(hints: &str, available_width: usize) -> Vec<String> {
     lines
 }
 
+fn hint_spans(line: &[Vec<Hint>]) -> Vec<Span<'static>> {
+    let key_style = Style::default().fg(Color::Yellow).bold();
+    let label_style = Style::default().fg(Color::Gray).dim();
+    let sep_style = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![Span::raw(" ")];
+    for (g, group) in line.iter().enumerate() {
+        if g > 0 {
+            spans.push(Span::styled(GROUP_SEP, sep_style));
+        }
+        for (i, (key, label)) in group.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(HINT_SEP));
+            }
+            spans.push(Span::styled(*key, key_style));
+            spans.push(Span::styled(format!(" {}", label), label_style));
+        }
+    }
+    spans
+}
+
 fn status_bar_height(app: &App, width: u16) -> u16 {
     if matches!(app.mode, Mode::Search { .. }) || app.status_message.is_some() {
         return 1;
@@ -238,7 +514,41 @@ fn status_bar_height(app: &App, width: u16) -> u16 {
     let badge_width = label.len() + 4; // " [LABEL] "
     let available = (width as usize).saturating_sub(badge_width + 1);
     let lines = wrap_hints(&build_hints(app), available);
-    (lines.len() as u16).max(1)
+    (lines.len() as u16).max(1) + u16::from(attention_tip(app).is_some())
+}
+
+/// The line over the hints while the selected session wants attention: why, and what
+/// `x` clears first.
+fn attention_tip(app: &App) -> Option<String> {
+    if !matches!(app.mode, Mode::Normal) {
+        return None;
+    }
+    let Selection::Session(pi, wi, si) = app.current_selection() else { return None };
+    let sess = app.workspace.session(pi, wi, si)?;
+    let why = preview::attention_text(sess)?;
+    let first = match sess.attention.iter().next()? {
+        AttentionReason::Bell => "acknowledges the bell",
+        AttentionReason::RunningAppIdle(_) => "dismisses the quiet app",
+        AttentionReason::Finished => "dismisses it",
+    };
+    Some(format!(" ● attention: {} — x {}", why, first))
+}
+
+/// Stays up while there is no tmux server; the tree keeps the last sessions it saw.
+fn render_tmux_down_banner(frame: &mut Frame, area: Rect) {
+    let text = " tmux server not running — sessions will reappear when it restarts (or press s to start one)";
+    let banner = Paragraph::new(text)
+        .style(Style::default().fg(Color::Black).bg(Color::Red).bold());
+    frame.render_widget(banner, area);
+}
+
+/// The last error, until Esc in the tree or a different error; repeats count up.
+fn render_error_banner(frame: &mut Frame, area: Rect, error: &StickyError) {
+    let line = Line::from(vec![
+        Span::styled(format!(" {}", error.text()), Style::default().fg(Color::Red).bold()),
+        Span::styled("  Esc: dismiss", Style::default().fg(Color::DarkGray)),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
 }
 
 fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
@@ -264,85 +574,94 @@ fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
     let badge_width = mode_text.len();
     let badge_style = Style::default().fg(Color::Black).bg(Color::Yellow).bold();
 
-    let ver = concat!(" v", env!("CARGO_PKG_VERSION"), " ");
+    let ver = if app.debug {
+        let stats = app.preview_stats;
+        format!(
+            " captures {} (skipped {}) · git info {} · orphans {} ",
+            stats.captures,
+            stats.skipped_captures,
+            stats.git_info_loads,
+            app.orphans.len()
+        )
+    } else if !app.mouse {
+        format!(" {} ", crate::app::MOUSE_OFF)
+    } else if let Some(profile) = &app.profile {
+        format!(" {} · v{} ", profile, env!("CARGO_PKG_VERSION"))
+    } else {
+        concat!(" v", env!("CARGO_PKG_VERSION"), " ").to_string()
+    };
+    let ver = match app.queued_git() {
+        0 => ver,
+        n => format!(" {} git op{} queued ·{}", n, if n == 1 { "" } else { "s" }, ver),
+    };
     let ver_style = Style::default().fg(Color::DarkGray);
 
     let msg = app.status_message.as_deref().unwrap_or("");
     if !msg.is_empty() {
         let left = format!(" {}", msg);
-        let left_len = badge_width + left.len();
+        let left_len = badge_width + left.width();
         let pad = (area.width as usize).saturating_sub(left_len + ver.len());
         let spans = vec![
             Span::styled(mode_text, badge_style),
             Span::styled(left, Style::default().fg(Color::Cyan)),
             Span::raw(" ".repeat(pad)),
-            Span::styled(ver, ver_style),
+            Span::styled(ver.clone(), ver_style),
         ];
         frame.render_widget(Paragraph::new(Line::from(spans)), area);
         return;
     }
 
-    let hints = build_hints(app);
+    let mut area = area;
+    if let Some(tip) = attention_tip(app).filter(|_| area.height >= 2) {
+        let tip_area = Rect::new(area.x, area.y, area.width, 1);
+        frame.render_widget(Paragraph::new(tip).style(Style::default().fg(Color::Yellow)), tip_area);
+        area = Rect::new(area.x, area.y + 1, area.width, area.height - 1);
+    }
     let available = (area.width as usize).saturating_sub(badge_width + 1);
-    let hint_lines = wrap_hints(&hints, available);
-    let hint_style = Style::default().fg(Color::Gray);
+    let mut hint_lines = wrap_hints(&build_hints(app), available);
+    if area.height < 2 {
+        hint_lines.truncate(1);
+    }
+    let last = hint_lines.len().saturating_sub(1);
+    let indent = " ".repeat(badge_width);
 
-    if hint_lines.len() <= 1 || area.height < 2 {
-        let text = hint_lines.first().map(|s| s.as_str()).unwrap_or(&hints);
-        let left = format!(" {}", text);
-        let left_len = badge_width + left.len();
-        let pad = (area.width as usize).saturating_sub(left_len + ver.len());
-        let spans = vec![
-            Span::styled(mode_text, badge_style),
-            Span::styled(left, hint_style),
-            Span::raw(" ".repeat(pad)),
-            Span::styled(ver, ver_style),
-        ];
-        frame.render_widget(Paragraph::new(Line::from(spans)), area);
-    } else {
-        let indent = " ".repeat(badge_width);
-        let mut text_lines: Vec<Line> = vec![Line::from(vec![
-            Span::styled(mode_text, badge_style),
-            Span::styled(format!(" {}", hint_lines[0]), hint_style),
-        ])];
-        let last = hint_lines.len() - 1;
-        for (i, hl) in hint_lines[1..].iter().enumerate() {
-            let left = format!(" {}", hl);
-            if i + 1 == last {
-                let left_len = badge_width + left.len();
-                let pad = (area.width as usize).saturating_sub(left_len + ver.len());
-                text_lines.push(Line::from(vec![
-                    Span::raw(indent.clone()),
-                    Span::styled(left, hint_style),
-                    Span::raw(" ".repeat(pad)),
-                    Span::styled(ver, ver_style),
-                ]));
+    let text_lines: Vec<Line> = hint_lines
+        .iter()
+        .enumerate()
+        .map(|(i, hints)| {
+            let mut spans = if i == 0 {
+                vec![Span::styled(mode_text.clone(), badge_style)]
             } else {
-                text_lines.push(Line::from(vec![
-                    Span::raw(indent.clone()),
-                    Span::styled(left, hint_style),
-                ]));
+                vec![Span::raw(indent.clone())]
+            };
+            spans.extend(hint_spans(hints));
+            if i == last {
+                let left_len = badge_width + 1 + line_width(hints);
+                let pad = (area.width as usize).saturating_sub(left_len + ver.len());
+                spans.push(Span::raw(" ".repeat(pad)));
+                spans.push(Span::styled(ver.clone(), ver_style));
             }
-        }
-        frame.render_widget(Paragraph::new(Text::from(text_lines)), area);
-    }
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Text::from(text_lines)), area);
 }
 
-fn render_loading(frame: &mut Frame, area: Rect) {
-    let popup = popup_center(area, 20, 3);
+fn render_loading(frame: &mut Frame, area: Rect, text: &str) {
+    let popup = popup_center(area, text.width() as u16 + 8, 3);
     frame.render_widget(Clear, popup);
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Magenta));
-    let para = Paragraph::new("  ⏳ Working…")
+    let para = Paragraph::new(format!("  ⏳ {}", text))
         .block(block)
         .style(Style::default().fg(Color::Magenta).bold());
     frame.render_widget(para, popup);
 }
 
-fn render_help(frame: &mut Frame, area: Rect) {
-    let width = area.width.min(64).max(40);
-    let height = area.height.min(40).max(12);
+fn render_help(frame: &mut Frame, area: Rect, markers: Markers) {
+    let width = area.width.clamp(40, 64);
+    let height = area.height.clamp(12, 40);
     let popup = popup_center(area, width, height);
 
     frame.render_widget(Clear, popup);
@@ -354,27 +673,57 @@ fn render_help(frame: &mut Frame, area: Rect) {
         "  Enter         Project/Worktree: toggle  |  Session: attach",
         "",
         " Project",
-        "  p             Add project (path: prompt)",
+        "  p             Add project (path: prompt; a git URL is cloned first)",
+        "  Enter         Missing project (red): relocate to a new path",
         "  m             Move project (reorder list)",
         "  d             Unregister project",
         "  c             Clean merged worktrees (batch)",
         "  e             View .gtrconfig",
+        "  O             Open with… (open_with commands in config)",
+        "  !             Shell in the main worktree (its first session, or a new one)",
+        "  .             Actions: pull main, clean, main shell, .gtrconfig action.name",
         "",
         " Worktree",
         "  w             Add worktree (branch: prompt)",
+        "  Space         Start work: worktree + dev session (session.defaultCommand), attached",
         "  s             New persistent session (optional init command)",
-        "  r             Set alias",
+        "                +name adds a window to the first session instead",
+        "  r             Set alias (Tab completes aliases used in other projects)",
+        "  *             Pin to the top of the project (★)",
+        "  #             Note: shown in the preview and after the row, found by /",
         "  d             Delete worktree + kill all sessions",
+        "  A             Archive: tag the branch, then remove worktree + sessions",
+        "                (Archived row: Enter restores one, d purges its tag)",
         "  c             Clean this worktree if merged",
+        "  L             Lock / unlock (git worktree lock, 🔒): d, A and c refuse it",
+        "  +             Adopt a worktree made outside wsx (ext): move, alias, session",
+        "  f             Fetch now (ahead/behind is flagged after fetch_stale_mins)",
+        "  g             Git: pull / push / rebase / merge",
+        "  g b           Branch off: new worktree that takes the uncommitted changes",
+        "  g s           Stashes: apply, pop, drop or show one",
+        "  g c           Conflicts: edit, mark resolved, then commit / continue",
+        "  g h / g H     Re-run the postCreate hook / show its last output",
+        "  V             Diff of a modified file: pick one, then [ / ] to step",
         "  e             View .gtrconfig",
+        "  O             Open with…",
         "",
         " Session",
-        "  Enter         Attach",
+        "  Enter         Attach (to the previewed window)",
+        "  { / }         Preview the prev / next window of a multi-window session",
         "  S             Send command to session",
         "  C             Send Ctrl+C to session",
+        "  Ctrl+R        Respawn: Ctrl+C and re-run the session's start command",
+        "                (restarts the pane if it exited or won't stop)",
         "  r             Rename",
-        "  d             Kill session",
-        "  x             Dismiss ● (suppress running-app notification) / toggle ⊘ mute",
+        "  m             Move (pinned sessions stay above the rest)",
+        "  *             Pin to the top of the worktree (★)",
+        "  d             Kill session (on a window row: close that window)",
+        "  K             Kill idle shell sessions older than stale_session_days (asks first)",
+        "  x             Acknowledge a bell {attention} / dismiss {finished} (a finished app,",
+        "                then a quiet one — one per press) / toggle {muted} mute",
+        "  W             Preview: wrap / clip long lines",
+        "  F             Preview: follow output / pause",
+        "  PgUp / PgDn   Scroll the preview (mouse wheel too; pauses following)",
         "",
         " Inside Session (tmux)",
         "  Ctrl+a d      Detach (return to wsx)",
@@ -382,17 +731,51 @@ fn render_help(frame: &mut Frame, area: Rect) {
         "",
         " Global",
         "  [ / ]         Jump to prev / next project",
-        "  a             Jump to next active session (◉)",
-        "  n / N         Jump to next / prev session needing attention (●)",
-        "  R             Refresh",
+        "  a             Jump to next active session ({active})",
+        "  n / N         Next / prev match of the last search (Esc forgets it);",
+        "                without one, next / prev session needing attention ({attention})",
+        "  b / B         Jump to next / prev session needing attention ({attention}),",
+        "                longest waiting first",
+        "  Ctrl+G        Find the worktree a pasted file path is in (path:line is fine);",
+        "                Ctrl+G again right away opens the file in $EDITOR",
+        "  R             Refresh (the only rescan with timers.manual_refresh)",
+        "  D             Doctor (setup checks; also `wsx doctor`)",
+        "  I             Workspace stats (projects, worktrees, sessions, git)",
+        "  E             Recently ended sessions with their last capture",
+        "                (this worktree's, or all on a project)",
+        "  H             Show / hide worktrees matched by worktree.hide (dimmed)",
+        "  o             Toggle project order: manual / recently used",
+        "  v             Raw names: tmux session names and worktree paths",
+        "  P             Full paths in the preview (shortened to fit otherwise)",
+        "  M             Mouse capture on / off (off: the terminal selects text)",
+        "  y             Copy the selected name (raw value with v; full path with v or P)",
+        "  < / >         Narrow / widen sidebar (also Ctrl+Left / Ctrl+Right)",
         "  ?             Help",
-        "  q             Quit",
+        "  q             Quit (in a popup: close it, like Esc)",
+        "  Esc           Close a popup or prompt, back to the one it came from",
+        "                (in the tree: dismiss the red error banner)",
+        "  Ctrl+C ×2     Quit from anywhere",
     ];
 
+    let glyphs = [
+        ("{attention}", SessionMark::Attention),
+        ("{active}", SessionMark::Active),
+        ("{finished}", SessionMark::Finished),
+        ("{muted}", SessionMark::Muted),
+    ];
+    let entries = ENTRIES.iter().map(|entry| {
+        glyphs.iter().fold(entry.to_string(), |line, (slot, mark)| line.replace(slot, markers.glyph(*mark)))
+    });
+    // Up top, where it's never cut off: the markers as the tree currently draws them.
+    let header = format!(" Session markers (accessible_markers = {})", markers == Markers::Shapes);
+    let legend = std::iter::once(header)
+        .chain(SessionMark::ALL.map(|mark| format!("  {:<14}{}", markers.glyph(mark), mark.label())))
+        .chain([String::new()]);
+
     let inner_width = (width as usize).saturating_sub(2);
-    let lines: Vec<Line> = ENTRIES
-        .iter()
-        .flat_map(|entry| help_wrap_line(entry, inner_width))
+    let lines: Vec<Line> = legend
+        .chain(entries)
+        .flat_map(|entry| help_wrap_line(&entry, inner_width))
         .collect();
 
     let block = Block::default()
@@ -435,12 +818,11 @@ fn help_wrap_line(line: &str, width: usize) -> Vec<Line<'static>> {
         return vec![Line::from(line.to_owned())];
     };
 
-    // Measure key column display width (chars, treating all as 1-wide)
-    let key_display: usize = line[..desc_byte].chars().count();
+    let key_display: usize = line[..desc_byte].width();
     let desc_text = &line[desc_byte..];
     let desc_width = width.saturating_sub(key_display);
 
-    if desc_text.len() <= desc_width {
+    if desc_text.width() <= desc_width {
         return vec![Line::from(line.to_owned())];
     }
 
@@ -469,16 +851,19 @@ fn help_wrap_line(line: &str, width: usize) -> Vec<Line<'static>> {
     result
 }
 
-/// Split `s` at a word boundary no longer than `max_chars`. Returns (chunk, remainder).
-fn split_at_word(s: &str, max_chars: usize) -> (&str, &str) {
-    let char_count = s.chars().count();
-    if char_count <= max_chars {
+/// Split `s` at a word boundary no wider than `max_width` columns. Returns (chunk, remainder).
+fn split_at_word(s: &str, max_width: usize) -> (&str, &str) {
+    if s.width() <= max_width {
         return (s, "");
     }
-    // Find byte offset of max_chars-th char
+    // Byte offset of the first char that doesn't fit
+    let mut used = 0;
     let end_byte = s
         .char_indices()
-        .nth(max_chars)
+        .find(|(_, c)| {
+            used += c.width().unwrap_or(0);
+            used > max_width
+        })
         .map(|(i, _)| i)
         .unwrap_or(s.len());
     // Walk back to last space
@@ -488,3 +873,341 @@ fn split_at_word(s: &str, max_chars: usize) -> (&str, &str) {
         (&s[..end_byte], &s[end_byte..])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{build_hints, line_width, normal_hints, render, wrap_hints, EntryState, Hint, GLOBAL_HINTS};
+    use crate::app::App;
+    use crate::cli::Args;
+    use crate::config::global::{GlobalConfig, SIDEBAR_MAX, SIDEBAR_MIN};
+    use crate::model::fixtures;
+    use crate::model::workspace::{GitInfo, Project, Selection, SessionInfo, WorkspaceState, WorktreeInfo};
+    use super::theme::Theme;
+    use ratatui::{backend::TestBackend, style::{Color, Modifier}, Terminal};
+    use crate::git::fake::FakeGit;
+    use crate::tmux::fake::FakeTmux;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn session_hints() -> Vec<Vec<Hint>> {
+        let local: &[&[Hint]] = &[
+            &[("m", "move"), ("r", "rename"), ("d", "kill")],
+            &[("x", "dismiss")],
+            &[("S", "send cmd"), ("C", "ctrl-c")],
+            &[("C-a d", "detach")],
+            &[("s", "session")],
+            &[("w", "worktree"), ("c", "clean")],
+        ];
+        local.iter().chain(GLOBAL_HINTS).map(|g| g.to_vec()).collect()
+    }
+
+    fn assert_wrapped(width: usize, expected_lines: usize) {
+        // the session row of a real App, so a hint dropped from the bar shows up here
+        let app = sample_app(SIDEBAR_MIN);
+        let groups = build_hints(&app);
+        assert_eq!(groups, session_hints());
+        let lines = wrap_hints(&groups, width);
+        assert_eq!(lines.len(), expected_lines);
+        for line in &lines {
+            assert!(line_width(line) <= width, "line too wide: {:?}", line);
+        }
+        // Nothing dropped or reordered, and groups stay whole when they fit.
+        let flat: Vec<Vec<Hint>> = lines.concat();
+        assert_eq!(flat, groups);
+    }
+
+    #[test]
+    fn wrap_hints_at_60_columns() {
+        assert_wrapped(60, 4);
+    }
+
+    #[test]
+    fn wrap_hints_at_120_columns() {
+        assert_wrapped(120, 2);
+    }
+
+    #[test]
+    fn scope_groups_are_set_apart_by_a_dot() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        let mut terminal = Terminal::new(TestBackend::new(200, 10)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (7..10).flat_map(|y| (0..200).map(move |x| (x, y))).map(|p| buffer[p].symbol().to_string()).collect();
+        assert!(row.contains("m move  r rename  d kill  ·  x dismiss  ·  S send cmd  C ctrl-c  ·"), "{:?}", row);
+    }
+
+    #[test]
+    fn main_worktree_hints_leave_out_what_it_refuses() {
+        let keys = |entry: EntryState| -> Vec<&str> {
+            normal_hints(&Selection::Worktree(0, 0), entry).concat().iter().map(|h| h.0).collect()
+        };
+        let feature = keys(EntryState::default());
+        let main = keys(EntryState { is_main: true, ..Default::default() });
+        for key in ["d", "A", "c", "L"] {
+            assert!(feature.contains(&key) && !main.contains(&key), "{}", key);
+        }
+        assert!(main.starts_with(&["s", "r", "w", "V"]));
+        let picking = keys(EntryState { is_main: true, pick_path: true, ..Default::default() });
+        assert!(picking.starts_with(&["Enter", "s", "r", "w"]) && !picking.contains(&"d"));
+
+        let session = |active| normal_hints(&Selection::Session(0, 0, 0), EntryState { active, ..Default::default() });
+        assert_eq!(session(false), session_hints());
+        assert!(!session(true).concat().contains(&("x", "dismiss")));
+    }
+
+    #[test]
+    fn n_and_n_step_through_matches_only_while_a_search_is_remembered() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        let keys = |app: &App| -> Vec<Hint> { build_hints(app).concat() };
+        let idle = keys(&app);
+        assert!(idle.contains(&("b", "next pending")) && idle.contains(&("B", "prev pending")));
+        assert!(!idle.iter().any(|h| h.0 == "n" || h.0 == "N"), "{:?}", idle);
+
+        app.last_search = Some("feat".into());
+        let groups = build_hints(&app);
+        let search = groups.iter().position(|g| g[0] == ("/", "search")).unwrap();
+        assert_eq!(groups[search + 1], [("n", "next match"), ("N", "prev match")]);
+    }
+
+    #[test]
+    fn wrap_hints_splits_oversized_group_between_hints() {
+        let groups = vec![vec![("p", "pull"), ("P", "push"), ("r", "pull-rebase")]];
+        let lines = wrap_hints(&groups, 14);
+        assert_eq!(lines, vec![vec![vec![("p", "pull"), ("P", "push")]], vec![vec![("r", "pull-rebase")]]]);
+    }
+
+    fn sample_app(sidebar_width: u16) -> App {
+        let session = SessionInfo {
+            display_name: "claude".into(),
+            has_activity: true,
+            pane_capture: Some("$ cargo test\n".into()),
+            commands: vec!["claude".into()],
+            ..fixtures::session("wsx-main-claude")
+        };
+        let worktree = WorktreeInfo {
+            branch: "feature/with-a-rather-long-name".into(),
+            path: PathBuf::from("/tmp/wsx-feature"),
+            sessions: vec![session],
+            ..fixtures::worktree("feature-with-a-rather-long-name")
+        };
+        let workspace = WorkspaceState {
+            projects: vec![Project { path: PathBuf::from("/tmp/wsx"), worktrees: vec![worktree], ..fixtures::project("wsx") }],
+            ..Default::default()
+        };
+        let mut config = GlobalConfig::default();
+        config.sidebar_width = Some(sidebar_width);
+        App::with_workspace(
+            &Args::default(),
+            config,
+            workspace,
+            2,
+            Arc::new(FakeGit::default()),
+            Arc::new(FakeTmux::default()),
+        )
+    }
+
+    #[test]
+    fn render_at_sidebar_extremes() {
+        for sidebar in [SIDEBAR_MIN, SIDEBAR_MAX] {
+            for (w, h) in [(120, 30), (80, 24), (20, 10)] {
+                let mut app = sample_app(sidebar);
+                let mut terminal = Terminal::new(TestBackend::new(w, h)).unwrap();
+                terminal.draw(|frame| render(frame, &mut app)).unwrap();
+                assert_eq!(app.tree_area.width, sidebar.min(w), "sidebar {} in {}x{}", sidebar, w, h);
+                // Top-right corner of the tree border sits at the sidebar edge.
+                let corner = terminal.backend().buffer()[(app.tree_area.width - 1, 0)].symbol().to_string();
+                assert_eq!(corner, "┐", "sidebar {} in {}x{}", sidebar, w, h);
+            }
+        }
+    }
+
+    #[test]
+    fn worktree_preview_reports_session_rows() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.tree_selected = 1; // the worktree
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let top = app.preview_sessions_top.expect("sessions visible");
+        let buffer = terminal.backend().buffer();
+        let row: String = (app.preview_area.x..app.preview_area.right())
+            .map(|x| buffer[(x, top)].symbol().to_string())
+            .collect();
+        assert!(row.contains("● claude  claude"), "{:?}", row);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 5)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        assert_eq!(app.preview_sessions_top, None, "no room below the info");
+    }
+
+    #[test]
+    fn accessible_markers_use_shapes_in_tree_and_help() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.config.accessible_markers = true;
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..app.tree_area.width).map(|x| buffer[(x, 3)].symbol().to_string()).collect();
+        assert!(row.contains("│  ! » claude"), "{:?}", row);
+        let x = row.chars().position(|c| c == '!').unwrap() as u16;
+        assert!(buffer[(x, 3)].modifier.contains(Modifier::REVERSED), "not told apart by colour alone");
+
+        app.mode = crate::app::Mode::Help;
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("(accessible_markers = true)"));
+        assert!(text.contains("◆             output in the last few seconds"), "legend follows the mapping");
+        assert!(text.contains("✔             app open but quiet"));
+    }
+
+    #[test]
+    fn muted_sessions_are_marked_in_previews() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.workspace.projects[0].worktrees[0].sessions[0].set_muted(true);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        let row = |terminal: &Terminal<TestBackend>, app: &App, y: u16| -> String {
+            let buffer = terminal.backend().buffer();
+            (app.preview_area.x..app.preview_area.right()).map(|x| buffer[(x, y)].symbol().to_string()).collect()
+        };
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        assert!(row(&terminal, &app, 0).contains("claude ⊘ muted — activity ignored"), "{:?}", row(&terminal, &app, 0));
+
+        app.tree_selected = 1;
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let top = app.preview_sessions_top.unwrap();
+        assert!(row(&terminal, &app, top - 1).contains("Sessions:  ⊘ 1 muted"));
+        app.tree_selected = 0;
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let text: String = (0..20).map(|y| row(&terminal, &app, y)).collect();
+        assert!(text.contains("(1 session, ⊘ 1 muted)"), "{}", text);
+    }
+
+    #[test]
+    fn session_preview_wraps_and_scrolls_by_visual_lines() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        let mut capture: Vec<String> = (1..=12).map(|i| format!("L{}", i)).collect();
+        capture[10] = format!("L11{}END", "x".repeat(150));
+        app.workspace.projects[0].worktrees[0].sessions[0].pane_capture = Some(capture.join("\n"));
+        let mut terminal = Terminal::new(TestBackend::new(100, 14)).unwrap();
+        let bottom_row = |terminal: &Terminal<TestBackend>, app: &App| -> String {
+            let area = app.preview_area;
+            let buffer = terminal.backend().buffer();
+            (area.x + 1..area.right() - 1).map(|x| buffer[(x, area.bottom() - 2)].symbol().to_string()).collect()
+        };
+
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let clipped_max = app.session_view.max_offset;
+        assert!(bottom_row(&terminal, &app).starts_with("L12 "));
+
+        app.session_view.wrap = true;
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let inner_width = app.preview_area.width - 2;
+        let extra = 156_u16.div_ceil(inner_width) - 1;
+        assert_eq!(app.session_view.max_offset, clipped_max + extra, "wrapped rows are counted");
+        assert!(bottom_row(&terminal, &app).starts_with("L12 "), "still pinned to the bottom");
+
+        app.session_view.scroll(-1);
+        assert!(!app.session_view.follow);
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        assert!(bottom_row(&terminal, &app).contains("END"), "{:?}", bottom_row(&terminal, &app));
+        app.session_view.scroll(5);
+        assert!(app.session_view.follow, "scrolling back to the bottom resumes following");
+    }
+
+    #[test]
+    fn status_bar_names_the_profile_in_use() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.profile = Some("work".into());
+        let mut terminal = Terminal::new(TestBackend::new(160, 10)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..160).map(|x| buffer[(x, 9)].symbol().to_string()).collect();
+        assert!(row.trim_end().ends_with(concat!("work · v", env!("CARGO_PKG_VERSION"))), "{:?}", row);
+    }
+
+    #[test]
+    fn narrow_tree_truncates_name_before_badges() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.workspace.projects[0].worktrees[0].git_info = Some(GitInfo {
+            modified_files: vec!["src/main.rs".into()],
+            ahead: 1,
+            remote_branch: Some("origin/feature".into()),
+            trunk_ahead: 3,
+            ..Default::default()
+        });
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (1..SIDEBAR_MIN - 1).map(|x| buffer[(x, 2)].symbol().to_string()).collect();
+        assert_eq!(row, " ▾ featu…* +3 ↑1 ●", "badges keep their full width; +3 is trunk, ↑1 the remote");
+    }
+
+    #[test]
+    fn wide_names_truncate_by_columns() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        let project = &mut app.workspace.projects[0];
+        project.name = "日本-app".into();
+        let wt = &mut project.worktrees[0];
+        wt.name = "日本-app-機能ブランチ".into();
+        wt.sessions[0].display_name = "🚀 deploy".into();
+        wt.git_info = Some(GitInfo { modified_files: vec!["src/main.rs".into()], ahead: 1, ..Default::default() });
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (1..SIDEBAR_MIN - 1).map(|x| buffer[(x, y)].symbol().to_string()).collect()
+        };
+        // Wide chars take two cells (the second renders blank here); badges still fit.
+        assert_eq!(row(2), " ▾ 機 能 ブ ラ …* ↑1 ●");
+        assert_eq!(row(3), "  ● » 🚀  deploy   ", "»: running claude");
+    }
+
+    #[test]
+    fn sidebar_resize_clamps_to_limits() {
+        let mut config = GlobalConfig::default();
+        assert!(config.resize_sidebar(1));
+        assert_eq!(config.sidebar_width(), 38);
+        while config.resize_sidebar(-1) {}
+        assert_eq!(config.sidebar_width(), SIDEBAR_MIN);
+        while config.resize_sidebar(1) {}
+        assert_eq!(config.sidebar_width(), SIDEBAR_MAX);
+    }
+
+    #[test]
+    fn mono_theme_keeps_text_and_reverses_highlights() {
+        let draw = |theme: Theme| {
+            let mut app = sample_app(SIDEBAR_MIN);
+            app.theme = theme;
+            let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+            terminal.draw(|frame| render(frame, &mut app)).unwrap();
+            terminal.backend().buffer().clone()
+        };
+        let (dark, mono) = (draw(Theme::Dark), draw(Theme::Mono));
+        for (d, m) in dark.content.iter().zip(&mono.content) {
+            assert_eq!(d.symbol(), m.symbol(), "same text");
+            assert_eq!((m.fg, m.bg), (Color::Reset, Color::Reset));
+            if d.bg != Color::Reset {
+                assert!(m.modifier.contains(Modifier::REVERSED));
+            }
+        }
+        // The selected session (first tree row) is the reverse-video one.
+        assert!(mono[(2, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(!mono[(2, 2)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn light_theme_darkens_text_on_the_terminal_background() {
+        let mut app = sample_app(SIDEBAR_MIN);
+        app.theme = Theme::Light;
+        let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+        terminal.draw(|frame| render(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let washed_out = [Color::White, Color::Gray, Color::Yellow, Color::Cyan];
+        for cell in buffer.content.iter().filter(|c| c.bg == Color::Reset) {
+            assert!(!washed_out.contains(&cell.fg), "{:?} on light background", cell.fg);
+            if let Color::Rgb(r, g, b) = cell.fg {
+                assert!(r.max(g).max(b) < 200, "Rgb({}, {}, {}) too light", r, g, b);
+            }
+        }
+        assert_eq!(buffer[(2, 1)].fg, Color::Black, "highlight keeps black on its coloured bar");
+    }
+}
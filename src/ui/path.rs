@@ -0,0 +1,121 @@
+// Path display for narrow panes — `~` for $HOME, then middle-ellipsis to a width.
+// Used by the preview's Path lines and titles and the tree's missing-project rows.
+
+use std::path::Path;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// `path` as the preview shows it: whole with `full` (`P`), else shortened to `width`.
+pub fn display_path(path: &Path, full: bool, width: usize) -> String {
+    if full {
+        path.display().to_string()
+    } else {
+        shorten_path(path, dirs::home_dir().as_deref(), width)
+    }
+}
+
+/// `path` in at most `width` columns: `~` stands in for `home`, then whole middle
+/// directories become `…` (`~/…/dir/project-feature-x`), and only if the last one alone
+/// doesn't fit is it cut mid-name, keeping more of the end than the start.
+pub fn shorten_path(path: &Path, home: Option<&Path>, width: usize) -> String {
+    let full = match home.and_then(|h| path.strip_prefix(h).ok()) {
+        Some(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(rel) => format!("~/{}", rel.display()),
+        None => path.display().to_string(),
+    };
+    if full.width() <= width {
+        return full;
+    }
+
+    // Keep the first component ("~", or "" before a leading '/') and as many trailing ones as fit.
+    let parts: Vec<&str> = full.split('/').collect();
+    if parts.len() > 2 {
+        let head = parts[0];
+        let mut best = None;
+        for keep in 1..parts.len() - 1 {
+            let candidate = format!("{}/…/{}", head, parts[parts.len() - keep..].join("/"));
+            if candidate.width() > width {
+                break;
+            }
+            best = Some(candidate);
+        }
+        if let Some(best) = best {
+            return best;
+        }
+    }
+    ellipsize_middle(&full, width)
+}
+
+/// `s` cut to `width` columns with `…` in the middle; the end gets the larger share.
+fn ellipsize_middle(s: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+    let budget = width - 1;
+    let head_w = budget / 3;
+    let mut head = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > head_w {
+            break;
+        }
+        head.push(c);
+        used += w;
+    }
+    let tail_w = budget - used;
+    let mut tail = Vec::new();
+    let mut used = 0;
+    for c in s.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if used + w > tail_w {
+            break;
+        }
+        tail.push(c);
+        used += w;
+    }
+    format!("{}…{}", head, tail.into_iter().rev().collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shorten_path;
+    use std::path::Path;
+    use unicode_width::UnicodeWidthStr;
+
+    const HOME: &str = "/Users/me";
+
+    fn short(path: &str, width: usize) -> String {
+        shorten_path(Path::new(path), Some(Path::new(HOME)), width)
+    }
+
+    #[test]
+    fn fits_are_only_tilded() {
+        assert_eq!(short("/Users/me/code/wsx", 40), "~/code/wsx");
+        assert_eq!(short("/Users/me", 40), "~");
+        assert_eq!(short("/Users/meg/code", 40), "/Users/meg/code", "not under $HOME");
+    }
+
+    #[test]
+    fn drops_middle_directories_first() {
+        let path = "/Users/me/very/deep/dir/project-feature-x";
+        assert_eq!(short(path, 40), "~/very/deep/dir/project-feature-x");
+        assert_eq!(short(path, 30), "~/…/deep/dir/project-feature-x");
+        assert_eq!(short(path, 25), "~/…/dir/project-feature-x");
+        assert_eq!(short(path, 21), "~/…/project-feature-x");
+    }
+
+    #[test]
+    fn paths_outside_home_keep_the_root() {
+        let path = "/srv/checkouts/team/project-feature-x";
+        assert_eq!(short(path, 27), "/…/team/project-feature-x");
+        assert_eq!(shorten_path(Path::new(path), None, 22), "/…/project-feature-x");
+    }
+
+    #[test]
+    fn cuts_inside_the_name_when_nothing_else_fits() {
+        let cut = short("/Users/me/very/deep/dir/project-feature-x", 12);
+        assert_eq!(cut, "~/v…eature-x");
+        assert_eq!(cut.width(), 12);
+        assert_eq!(short("/Users/me/a/b", 0), "");
+    }
+}
@@ -1,5 +1,4 @@
-// Simple list picker overlay (no fuzzy filtering). Reserved for future use.
-#![allow(dead_code)]
+// Simple list picker overlay (no fuzzy filtering).
 
 use ratatui::{
     prelude::*,
@@ -36,15 +35,14 @@ impl PickerState {
         self.list_state.select(Some(next));
     }
 
-    pub fn selected_item(&self) -> Option<&str> {
-        let i = self.list_state.selected()?;
-        self.items.get(i).map(|s| s.as_str())
+    pub fn selected(&self) -> Option<usize> {
+        self.list_state.selected().filter(|&i| i < self.items.len())
     }
 }
 
 pub fn render_picker(frame: &mut Frame, area: Rect, state: &mut PickerState) {
-    let width = area.width.min(60).max(30);
-    let height = area.height.min(20).max(6);
+    let width = area.width.clamp(30, 60);
+    let height = area.height.clamp(6, 20);
     let popup = popup_center(area, width, height);
 
     frame.render_widget(Clear, popup);
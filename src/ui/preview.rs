@@ -1,64 +1,109 @@
 // Right preview pane — git info, session capture, project summary
 
+use crate::model::attach_log::AttachStats;
+use crate::model::attention::AttentionReason;
+use crate::model::frecency::now_secs;
+use crate::model::setup::WorktreeSetup;
 use crate::model::workspace::{Project, SessionInfo, WorktreeInfo};
+use crate::ops::divergence_text;
 use crate::ui::ansi;
+use crate::ui::path::display_path;
+use crate::ui::scrollbar::render_scrollbar;
+use crate::ui::theme::Markers;
+use crate::model::frecency::fmt_idle;
+use crate::ui::workspace_tree::{idle_for, session_icon};
+use crate::ui::wrap::wrapped_rows;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph, Wrap},
 };
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
 
+/// Returns the screen row of the first session line, if the Sessions section is visible —
+/// the app maps preview clicks below it to sessions, listed in `order`. `setup` is what
+/// creating it ran.
 pub fn render_worktree_preview(
     frame: &mut Frame,
     area: Rect,
-    worktree: &WorktreeInfo,
+    (worktree, setup, order): (&WorktreeInfo, Option<&WorktreeSetup>, &[usize]),
     title: &str,
-) {
+    stale_fetch: Duration,
+    full_paths: bool,
+    markers: Markers,
+) -> Option<u16> {
     let block = Block::default()
         .borders(Borders::ALL)
         .title(format!(" {} ", title))
         .title_style(Style::default().bold());
 
     let label_style = Style::default().fg(Color::Rgb(120, 120, 140));
+    let path_width = (block.inner(area).width as usize).saturating_sub("Path:    ".len());
 
     let mut lines = vec![
         Line::from(vec![
-            Span::styled("Branch:  ", label_style),
+            Span::styled(if worktree.detached { "Detached:" } else { "Branch:  " }, label_style),
             Span::styled(
-                worktree.branch.clone(),
+                if worktree.detached { format!("@{}", worktree.branch) } else { worktree.branch.clone() },
                 Style::default().fg(Color::Rgb(100, 200, 255)).bold(),
             ),
+            // made by a plain `git worktree add` outside the `{repo}-{slug}` layout
+            Span::styled(
+                if worktree.external { "  ext · + adopts" } else { "" },
+                Style::default().fg(Color::DarkGray),
+            ),
         ]),
         Line::from(vec![
             Span::styled("Path:    ", label_style),
             Span::styled(
-                worktree.path.to_string_lossy().to_string(),
+                display_path(&worktree.path, full_paths, path_width),
                 Style::default().fg(Color::Rgb(200, 200, 210)),
             ),
         ]),
     ];
+    if let Some(note) = &worktree.note {
+        lines.push(Line::from(vec![
+            Span::styled("Note:    ", label_style),
+            Span::styled(note.clone(), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    if let Some(reason) = &worktree.locked {
+        lines.push(Line::from(vec![
+            Span::styled("Locked:  ", label_style),
+            Span::styled(
+                if reason.is_empty() { "yes — L unlocks".to_string() } else { reason.clone() },
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+    }
+    if let Some(setup) = setup {
+        let (text, failed) = setup_text(setup, now_secs());
+        let color = if failed { Color::Red } else { Color::DarkGray };
+        lines.push(Line::from(vec![
+            Span::styled("Setup:   ", label_style),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+    if let Some(divergence) = worktree.git_info.as_ref().and_then(|g| g.divergence.as_ref()) {
+        lines.push(Line::from(vec![
+            Span::styled("Base:    ", label_style),
+            Span::styled(divergence_text(divergence, now_secs()), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
 
     if let Some(info) = &worktree.git_info {
         // ── Remote tracking ──────────────────────────────────────────────────
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled("Remote:", label_style)));
         if let Some(remote) = &info.remote_branch {
-            let (status_text, status_style) = match (info.behind, info.ahead) {
-                (0, 0) => (
-                    "in sync".to_string(),
-                    Style::default().fg(Color::Rgb(100, 200, 100)),
-                ),
-                (b, a) if b > 0 && a > 0 => (
-                    format!("↓{} ↑{}  diverged — pull first", b, a),
-                    Style::default().fg(Color::Magenta),
-                ),
-                (b, _) if b > 0 => (
-                    format!("↓{}  pull needed", b),
-                    Style::default().fg(Color::Red),
-                ),
-                (_, a) => (
-                    format!("↑{}  ready to push", a),
-                    Style::default().fg(Color::Cyan),
-                ),
+            let fetched_ago = worktree.last_fetch_ok.map(|t| t.elapsed());
+            let (status_text, stale) = remote_status(info.behind, info.ahead, fetched_ago, stale_fetch);
+            let status_style = match (info.behind, info.ahead) {
+                _ if stale => Style::default().fg(Color::Yellow),
+                (0, 0) => Style::default().fg(Color::Rgb(100, 200, 100)),
+                (b, a) if b > 0 && a > 0 => Style::default().fg(Color::Magenta),
+                (b, _) if b > 0 => Style::default().fg(Color::Red),
+                _ => Style::default().fg(Color::Cyan),
             };
             let fetch_suffix = if worktree.fetch_failed {
                 "  [fetch failed]"
@@ -121,6 +166,33 @@ pub fn render_worktree_preview(
             }
         }
 
+        // ── Stashes ───────────────────────────────────────────────────────────
+        if !info.stashes.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("Stashes: ", label_style),
+                Span::styled(info.stashes.len().to_string(), Style::default().fg(Color::Yellow)),
+            ]));
+            let now = now_secs();
+            for s in info.stashes.iter().take(3) {
+                let age = fmt_idle(std::time::Duration::from_secs(now.saturating_sub(s.ts)));
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {} ", s.refname()),
+                        Style::default().fg(Color::Rgb(255, 180, 80)),
+                    ),
+                    Span::styled(s.message.clone(), Style::default().fg(Color::Rgb(210, 210, 220))),
+                    Span::styled(format!("  {} ago", age), Style::default().fg(Color::DarkGray)),
+                ]));
+            }
+            if info.stashes.len() > 3 {
+                lines.push(Line::from(Span::styled(
+                    format!("  … {} more  (g s to manage)", info.stashes.len() - 3),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+
         // ── Recent commits ────────────────────────────────────────────────────
         if !info.recent_commits.is_empty() {
             lines.push(Line::from(""));
@@ -140,51 +212,311 @@ pub fn render_worktree_preview(
         }
     }
 
-    if !worktree.sessions.is_empty() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "Sessions:",
-            Style::default().fg(Color::Rgb(120, 120, 140)),
-        )));
-        for s in &worktree.sessions {
-            let dot = if s.has_activity { " ●" } else { "" };
-            lines.push(Line::from(Span::styled(
-                format!("  {}{}", s.display_name, dot),
-                Style::default().fg(Color::Rgb(100, 220, 130)),
-            )));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let lines = Text::from(lines);
+    let info_rows = (wrapped_rows(&lines, inner.width) as u16).min(inner.height);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+
+    // Sessions go below the wrapped info, one unwrapped row each, so a click row maps
+    // straight to a session index.
+    if worktree.sessions.is_empty() {
+        return None;
+    }
+    let sessions_area = Rect {
+        y: inner.y + info_rows,
+        height: inner.height - info_rows,
+        ..inner
+    };
+    if sessions_area.height < 3 {
+        return None;
+    }
+    let mut rows = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Sessions:", label_style),
+            Span::styled(muted_note(&worktree.sessions, "  "), Style::default().fg(Color::DarkGray)),
+        ]),
+    ];
+    let ordered: Vec<&SessionInfo> = order.iter().map(|&si| &worktree.sessions[si]).collect();
+    rows.extend(session_rows(&ordered, markers));
+    frame.render_widget(Paragraph::new(rows), sessions_area);
+    Some(sessions_area.y + 2)
+}
+
+/// Remote status line and whether it is stale: ahead/behind is only as fresh as the
+/// last successful fetch, so past `stale_after` (or with no fetch yet) it says so.
+pub(crate) fn remote_status(
+    behind: usize,
+    ahead: usize,
+    fetched_ago: Option<Duration>,
+    stale_after: Duration,
+) -> (String, bool) {
+    let stale = fetched_ago.is_none_or(|ago| ago >= stale_after);
+    let status = match (behind, ahead) {
+        (0, 0) if stale => "in sync?".to_string(),
+        (0, 0) => "in sync".to_string(),
+        (b, a) if b > 0 && a > 0 => format!("↓{} ↑{}  diverged — pull first", b, a),
+        (b, _) if b > 0 => format!("↓{}  pull needed", b),
+        (_, a) => format!("↑{}  ready to push", a),
+    };
+    let fetched = match fetched_ago {
+        Some(ago) if stale => format!("last fetched {} ago — press f to fetch", fmt_idle(ago)),
+        Some(ago) => format!("(fetched {} ago)", fmt_idle(ago)),
+        None => "never fetched — press f to fetch".to_string(),
+    };
+    let sep = if (behind, ahead) == (0, 0) { " " } else { "  " };
+    (format!("{}{}{}", status, sep, fetched), stale)
+}
+
+/// "postCreate ✔ 42s ago · env copy: 3 files", and whether the hook failed — then it
+/// carries the error and where to read the output.
+pub(crate) fn setup_text(setup: &WorktreeSetup, now: u64) -> (String, bool) {
+    let env = format!("env copy: {} file{}", setup.env_files, if setup.env_files == 1 { "" } else { "s" });
+    let Some(hook) = &setup.hook else {
+        return (env, false);
+    };
+    let ago = fmt_idle(Duration::from_secs(now.saturating_sub(hook.ts)));
+    match &hook.error {
+        None => (format!("postCreate ✔ {} ago · {}", ago, env), false),
+        Some(e) => (format!("postCreate ✗ {} · {} ago · {} — g H shows output", e, ago, env), true),
+    }
+}
+
+/// `icon name command idle`, with names and commands padded into columns.
+fn session_rows(sessions: &[&SessionInfo], markers: Markers) -> Vec<Line<'static>> {
+    let commands: Vec<String> = sessions.iter().map(|s| s.commands.join(", ")).collect();
+    let name_w = sessions.iter().map(|s| s.display_name.width()).max().unwrap_or(0);
+    let cmd_w = commands.iter().map(|c| c.width()).max().unwrap_or(0);
+    sessions
+        .iter()
+        .zip(commands)
+        .map(|(s, cmd)| {
+            let (icon, icon_style) = session_icon(s, markers);
+            let idle = idle_for(s).map(fmt_idle).unwrap_or_default();
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled(icon, icon_style),
+                Span::styled(if s.pinned { " ★" } else { "" }, Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!(" {}", pad(&s.display_name, name_w)),
+                    Style::default().fg(Color::Rgb(100, 220, 130)),
+                ),
+                Span::styled(
+                    format!("  {}", pad(&cmd, cmd_w)),
+                    Style::default().fg(Color::Rgb(180, 180, 200)),
+                ),
+                Span::styled(format!("  {}", idle), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect()
+}
+
+/// "{sep}⊘ N muted" when any of `sessions` is muted.
+fn muted_note(sessions: &[SessionInfo], sep: &str) -> String {
+    match sessions.iter().filter(|s| s.muted).count() {
+        0 => String::new(),
+        n => format!("{}⊘ {} muted", sep, n),
+    }
+}
+
+/// `s` padded with spaces to `width` columns; `{:<w$}` counts chars, which misaligns CJK and emoji.
+fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+/// How the session preview shows its capture; lives on App across frames.
+#[derive(Debug, Clone)]
+pub struct SessionView {
+    /// wrap long lines instead of clipping them at the pane edge
+    pub wrap: bool,
+    /// stick to the newest output; off once the user scrolls up
+    pub follow: bool,
+    /// first visible (visual) line while not following
+    pub offset: u16,
+    /// bottom-most offset at the last render
+    pub max_offset: u16,
+    /// the scrollbar's box at the last render: its track is the right border beside the
+    /// capture, below the header lines
+    pub scrollbar_area: Rect,
+    /// session shown at the last render — picking another one resumes following
+    pub session: Option<String>,
+}
+
+impl Default for SessionView {
+    fn default() -> Self {
+        Self { wrap: false, follow: true, offset: 0, max_offset: 0, scrollbar_area: Rect::default(), session: None }
+    }
+}
+
+impl SessionView {
+    /// Scroll by `delta` lines (negative = up). Scrolling up stops following;
+    /// scrolling back down to the bottom resumes it.
+    pub fn scroll(&mut self, delta: i32) {
+        if self.follow {
+            if delta >= 0 {
+                return;
+            }
+            self.follow = false;
+            self.offset = self.max_offset;
+        }
+        let offset = (self.offset as i32 + delta).clamp(0, self.max_offset as i32) as u16;
+        self.offset = offset;
+        if delta > 0 && offset >= self.max_offset {
+            self.follow = true;
         }
     }
 
-    let para = Paragraph::new(lines)
-        .block(block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(para, area);
+    /// Jump to `offset` (from the scrollbar); the bottom resumes following.
+    pub fn scroll_to(&mut self, offset: usize) {
+        self.offset = offset.min(self.max_offset as usize) as u16;
+        self.follow = self.offset >= self.max_offset;
+    }
+
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        // Pausing freezes the view where it is.
+        self.offset = self.max_offset;
+    }
 }
 
-pub fn render_session_preview(frame: &mut Frame, area: Rect, session: &SessionInfo, title: &str) {
-    let activity = if session.has_activity { " ●" } else { "" };
-    let block = Block::default()
+pub fn render_session_preview(
+    frame: &mut Frame,
+    area: Rect,
+    session: &SessionInfo,
+    title: &str,
+    usage: Option<AttachStats>,
+    view: &mut SessionView,
+    full_paths: bool,
+) {
+    if view.session.as_deref() != Some(session.name.as_str()) {
+        view.session = Some(session.name.clone());
+        view.follow = true;
+    }
+    let activity = if session.muted {
+        " ⊘ muted — activity ignored"
+    } else if session.has_activity {
+        " ●"
+    } else {
+        ""
+    };
+    let created = session
+        .age_secs(now_secs())
+        .map(|age| format!(" · created {} ago", fmt_idle(std::time::Duration::from_secs(age))))
+        .unwrap_or_default();
+    let mode = format!(
+        " {} · {} ",
+        if view.wrap { "wrap" } else { "clip" },
+        if view.follow { "follow" } else { "paused" }
+    );
+    let mut block = Block::default()
         .borders(Borders::ALL)
-        .title(format!(" {}{} ", title, activity))
+        .title(format!(" {}{}{} ", title, activity, created))
+        .title(Line::from(Span::styled(mode, Style::default().fg(Color::DarkGray))).right_aligned())
         .title_style(Style::default().bold());
+    if let Some(usage) = usage {
+        block = block.title_bottom(Span::styled(
+            format!(" {} ", fmt_usage(usage, now_secs())),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
 
     let text = session
         .pane_capture
         .as_deref()
         .map(ansi::parse)
         .unwrap_or_else(|| "(no capture)".into());
-    let inner_h = area.height.saturating_sub(2) as usize; // minus borders
-    let scroll = text.lines.len().saturating_sub(inner_h) as u16;
-    let para = Paragraph::new(text).block(block).scroll((scroll, 0));
-    frame.render_widget(para, area);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let mut header = origin_lines(session, full_paths, inner.width as usize);
+    if let Some(why) = attention_text(session) {
+        header.insert(0, Line::styled(format!("attention: {}", why), Style::default().fg(Color::Yellow)));
+    }
+    let [header_area, capture_area] =
+        Layout::vertical([Constraint::Length(header.len() as u16), Constraint::Min(0)]).areas(inner);
+    frame.render_widget(Paragraph::new(header), header_area);
+
+    // Scroll counts visual lines, so measure after wrapping at the capture's width.
+    let total = if view.wrap { wrapped_rows(&text, capture_area.width) } else { text.lines.len() };
+    let mut para = Paragraph::new(text);
+    if view.wrap {
+        para = para.wrap(Wrap { trim: false });
+    }
+    view.max_offset = total.saturating_sub(capture_area.height as usize).min(u16::MAX as usize) as u16;
+    let scroll = if view.follow { view.max_offset } else { view.offset.min(view.max_offset) };
+    frame.render_widget(para.scroll((scroll, 0)), capture_area);
+    // A row either side of the capture, standing in for the borders `render_scrollbar` skips.
+    view.scrollbar_area = Rect { y: capture_area.y - 1, height: capture_area.height + 2, ..area };
+    render_scrollbar(frame, view.scrollbar_area, total, capture_area.height as usize, scroll as usize);
+}
+
+/// The dim "dir" / "cmd" header above a session's capture; nothing until tmux has
+/// reported the session's start directory.
+fn origin_lines(session: &SessionInfo, full_paths: bool, width: usize) -> Vec<Line<'static>> {
+    let origin = &session.origin;
+    let Some(start_dir) = &origin.start_dir else { return vec![] };
+    let path_width = width.saturating_sub(5);
+    let dir = match &origin.current_dir {
+        Some(cwd) => format!(
+            "dir  {}  now {}",
+            display_path(start_dir, full_paths, path_width.saturating_sub(6) / 2),
+            display_path(cwd, full_paths, path_width.saturating_sub(6) / 2)
+        ),
+        None => format!("dir  {}", display_path(start_dir, full_paths, path_width)),
+    };
+    let cmd = match (&origin.init_command, &origin.made_as) {
+        (Some(cmd), _) => format!("cmd  {}", cmd),
+        (None, Some(_)) => "cmd  (shell)".to_string(),
+        (None, None) => "cmd  unknown — not started by wsx".to_string(),
+    };
+    let dim = Style::default().fg(Color::DarkGray);
+    vec![Line::styled(dir, dim), Line::styled(cmd, dim)]
+}
+
+/// Why the session wants a look: "bell, running 'pytest' idle 6m". None when it doesn't.
+pub(crate) fn attention_text(session: &SessionInfo) -> Option<String> {
+    if session.attention.is_empty() {
+        return None;
+    }
+    let reasons: Vec<String> = session
+        .attention
+        .iter()
+        .map(|r| match r {
+            AttentionReason::Bell => "bell".to_string(),
+            AttentionReason::RunningAppIdle(app) => match idle_for(session) {
+                Some(idle) => format!("running '{}' idle {}", app, fmt_idle(idle)),
+                None => format!("running '{}' quiet", app),
+            },
+            AttentionReason::Finished => "finished".to_string(),
+        })
+        .collect();
+    Some(reasons.join(", "))
+}
+
+/// "last attached 2h ago, 14 attaches this week"
+fn fmt_usage(usage: AttachStats, now: u64) -> String {
+    let ago = fmt_idle(std::time::Duration::from_secs(now.saturating_sub(usage.last)));
+    format!(
+        "last attached {} ago, {} attach{} this week",
+        ago,
+        usage.this_week,
+        if usage.this_week == 1 { "" } else { "es" }
+    )
 }
 
-pub fn render_project_preview(frame: &mut Frame, area: Rect, project: &Project) {
+pub fn render_project_preview(
+    frame: &mut Frame,
+    area: Rect,
+    project: &Project,
+    title: &str,
+    full_paths: bool,
+) {
+    let path_width = (area.width as usize).saturating_sub(2 + "Path:  ".len());
     let mut lines: Vec<Line> = vec![
         Line::from(vec![
             Span::styled("Path:  ", Style::default().fg(Color::Gray)),
             Span::styled(
-                project.path.to_string_lossy().to_string(),
+                display_path(&project.path, full_paths, path_width),
                 Style::default().fg(Color::White),
             ),
         ]),
@@ -194,10 +526,22 @@ pub fn render_project_preview(frame: &mut Frame, area: Rect, project: &Project)
                 project.default_branch.clone(),
                 Style::default().fg(Color::Cyan),
             ),
+            Span::styled(
+                if project.default_branch_override { " (override)" } else { "" },
+                Style::default().fg(Color::Gray),
+            ),
         ]),
         Line::from(""),
         Line::from(Span::styled("Worktrees:", Style::default().fg(Color::Gray))),
     ];
+    if project.missing {
+        lines.truncate(1);
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Path missing — Enter: relocate  d: remove",
+            Style::default().fg(Color::Red),
+        )));
+    }
 
     for wt in &project.worktrees {
         let main_mark = if wt.is_main { "* " } else { "  " };
@@ -214,9 +558,10 @@ pub fn render_project_preview(frame: &mut Frame, area: Rect, project: &Project)
             ),
             Span::styled(
                 format!(
-                    "  ({} session{}){}",
+                    "  ({} session{}{}){}",
                     sess_count,
                     if sess_count == 1 { "" } else { "s" },
+                    muted_note(&wt.sessions, ", "),
                     activity
                 ),
                 Style::default().fg(Color::Gray),
@@ -233,7 +578,7 @@ pub fn render_project_preview(frame: &mut Frame, area: Rect, project: &Project)
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(format!(" {} ", project.name))
+        .title(format!(" {} ", title))
         .title_style(Style::default().bold());
 
     let para = Paragraph::new(lines)
@@ -242,6 +587,52 @@ pub fn render_project_preview(frame: &mut Frame, area: Rect, project: &Project)
     frame.render_widget(para, area);
 }
 
+/// A project's "Archived" row (`archive` None) lists its archive tags; an archive tag
+/// shows what a restore would bring back.
+pub fn render_archive_preview(frame: &mut Frame, area: Rect, project: &Project, archive: Option<usize>, title: &str) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+    let mut lines: Vec<Line> = vec![];
+    match archive.and_then(|ai| project.archived.get(ai)) {
+        Some(archived) => {
+            lines.push(Line::from(vec![
+                label("Branch:   "),
+                Span::styled(archived.branch.clone(), Style::default().fg(Color::Cyan)),
+            ]));
+            lines.push(Line::from(vec![label("Tag:      "), Span::raw(archived.tag.clone())]));
+            lines.push(Line::from(vec![label("Archived: "), Span::raw(archived.date.clone())]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(label("Sessions:")));
+            if archived.sessions.is_empty() {
+                lines.push(Line::from(Span::styled("  (none recorded)", Style::default().fg(Color::Gray))));
+            }
+            for name in &archived.sessions {
+                lines.push(Line::from(Span::styled(format!("  {}", name), Style::default().fg(Color::White))));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Enter: restore worktree and sessions  d: purge tag",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        None => {
+            lines.push(Line::from(label("Archived worktrees, newest first:")));
+            for archived in &project.archived {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {}", archived.branch), Style::default().fg(Color::Cyan)),
+                    Span::styled(format!("  {}", archived.date), Style::default().fg(Color::Gray)),
+                ]));
+            }
+        }
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", title))
+        .title_style(Style::default().bold());
+    let para = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(para, area);
+}
+
 pub fn render_empty_preview(frame: &mut Frame, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
@@ -252,3 +643,78 @@ pub fn render_empty_preview(frame: &mut Frame, area: Rect) {
         .block(block);
     frame.render_widget(para, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{origin_lines, remote_status, setup_text};
+    use crate::model::fixtures;
+    use crate::model::setup::{HookRun, WorktreeSetup};
+    use crate::model::workspace::{SessionInfo, SessionOrigin};
+    use std::time::Duration;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn remote_status_shows_how_fresh_the_fetch_is() {
+        let fresh = Some(Duration::from_secs(180));
+        assert_eq!(remote_status(0, 0, fresh, HOUR), ("in sync (fetched 3m ago)".to_string(), false));
+        assert_eq!(
+            remote_status(2, 0, fresh, HOUR),
+            ("↓2  pull needed  (fetched 3m ago)".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn remote_status_doubts_in_sync_once_the_fetch_is_stale() {
+        let old = Some(Duration::from_secs(2 * 86_400));
+        assert_eq!(
+            remote_status(0, 0, old, HOUR),
+            ("in sync? last fetched 2d ago — press f to fetch".to_string(), true)
+        );
+        assert_eq!(
+            remote_status(0, 1, old, HOUR),
+            ("↑1  ready to push  last fetched 2d ago — press f to fetch".to_string(), true)
+        );
+        // The threshold itself counts as stale.
+        assert!(remote_status(0, 0, Some(HOUR), HOUR).1);
+    }
+
+    #[test]
+    fn remote_status_without_a_fetch_is_stale() {
+        assert_eq!(
+            remote_status(0, 0, None, HOUR),
+            ("in sync? never fetched — press f to fetch".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn setup_line_says_how_the_hook_went() {
+        let hook = HookRun { command: "npm ci".into(), ts: 1_000, secs: 12, ..Default::default() };
+        let ok = WorktreeSetup { env_files: 3, hook: Some(hook.clone()) };
+        assert_eq!(setup_text(&ok, 1_042), ("postCreate ✔ 42s ago · env copy: 3 files".to_string(), false));
+        let failed = WorktreeSetup { env_files: 1, hook: Some(HookRun { error: Some("exit 1".into()), ..hook }) };
+        assert_eq!(
+            setup_text(&failed, 8_200),
+            ("postCreate ✗ exit 1 · 2h ago · env copy: 1 file — g H shows output".to_string(), true)
+        );
+        assert_eq!(setup_text(&WorktreeSetup::default(), 0), ("env copy: 0 files".to_string(), false));
+    }
+
+    #[test]
+    fn origin_says_unknown_for_sessions_made_elsewhere() {
+        let cmd_line = |init_command: Option<&str>, made_as: Option<&str>| {
+            let origin = SessionOrigin {
+                start_dir: Some("/w/api".into()),
+                current_dir: None,
+                init_command: init_command.map(String::from),
+                made_as: made_as.map(String::from),
+            };
+            let session = SessionInfo { origin, ..fixtures::session("dev") };
+            let lines = origin_lines(&session, true, 80);
+            lines[1].spans.iter().map(|s| s.content.as_ref()).collect::<String>()
+        };
+        assert_eq!(cmd_line(Some("npm run dev"), Some("api-dev")), "cmd  npm run dev");
+        assert_eq!(cmd_line(None, Some("api-dev")), "cmd  (shell)");
+        assert_eq!(cmd_line(None, None), "cmd  unknown — not started by wsx");
+    }
+}
@@ -0,0 +1,57 @@
+// Vertical scrollbar drawn over the right border of a bordered block — used by the
+// workspace tree and the session preview, and clickable / draggable in both.
+
+use ratatui::{
+    prelude::*,
+    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+
+/// Nothing is drawn when `content` rows fit in `viewport`.
+pub fn render_scrollbar(frame: &mut Frame, area: Rect, content: usize, viewport: usize, offset: usize) {
+    if content <= viewport {
+        return;
+    }
+    let mut state = ScrollbarState::new(content.saturating_sub(viewport))
+        .viewport_content_length(viewport)
+        .position(offset);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_stateful_widget(scrollbar, track(area), &mut state);
+}
+
+/// Where along `0..=max` a click or drag at `pos` lands, when it's on the scrollbar
+/// column of `area`. `clamp_row` takes any row, for drags that wander off the column.
+pub fn scrollbar_target(area: Rect, pos: Position, max: usize, clamp_row: bool) -> Option<usize> {
+    let track = track(area);
+    if track.height == 0 || (!clamp_row && (pos.x != area.right().saturating_sub(1) || !track.contains(pos))) {
+        return None;
+    }
+    let row = pos.y.clamp(track.y, track.bottom() - 1) - track.y;
+    let span = (track.height - 1).max(1) as usize;
+    Some((row as usize * max + span / 2) / span)
+}
+
+/// The rows between the top and bottom borders.
+fn track(area: Rect) -> Rect {
+    area.inner(Margin { vertical: 1, horizontal: 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scrollbar_target;
+    use ratatui::layout::{Position, Rect};
+
+    #[test]
+    fn clicks_on_the_track_map_to_proportional_positions() {
+        let area = Rect::new(0, 0, 20, 12); // track rows 1..=10 on column 19
+        let at = |x, y| scrollbar_target(area, Position { x, y }, 90, false);
+        assert_eq!(at(19, 1), Some(0));
+        assert_eq!(at(19, 10), Some(90));
+        assert_eq!(at(19, 5), Some(40));
+        assert_eq!(at(18, 5), None, "not on the scrollbar column");
+        assert_eq!(at(19, 0), None, "top border");
+        assert_eq!(scrollbar_target(area, Position { x: 3, y: 30 }, 90, true), Some(90));
+    }
+}
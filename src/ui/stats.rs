@@ -0,0 +1,93 @@
+// Stats popup — workspace-wide counts, as of when it was opened, and the git operations
+// running right now.
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use crate::git::queue::LaneState;
+use crate::model::frecency::now_secs;
+use crate::model::stats::WorkspaceStats;
+use crate::ui::popup_center;
+use crate::model::frecency::fmt_idle;
+
+pub fn render_stats(frame: &mut Frame, area: Rect, stats: &WorkspaceStats, git_queue: &[LaneState]) {
+    let mut rows = stat_rows(stats, now_secs());
+    rows.push(("Git running", queue_row(git_queue)));
+    let width = area.width.clamp(40, 72);
+    let height = (rows.len() as u16 + 4).clamp(5, area.height.max(5));
+    let popup = popup_center(area, width, height);
+    frame.render_widget(Clear, popup);
+
+    let mut lines = vec![Line::from("")];
+    for (label, value) in rows {
+        let color = if value == "—" { Color::DarkGray } else { Color::White };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<17}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, Style::default().fg(color)),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Workspace ")
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+}
+
+/// "api: fetch (1 queued) · web: pull", or "—" with nothing running.
+fn queue_row(lanes: &[LaneState]) -> String {
+    if lanes.is_empty() {
+        return "—".to_string();
+    }
+    let lane = |l: &LaneState| {
+        let repo = l.repo.file_name().map_or_else(|| l.repo.display().to_string(), |n| n.to_string_lossy().into_owned());
+        match l.waiting {
+            0 => format!("{}: {}", repo, l.running),
+            n => format!("{}: {} ({} queued)", repo, l.running, n),
+        }
+    };
+    lanes.iter().map(lane).collect::<Vec<_>>().join(" · ")
+}
+
+/// (label, value) per line; "—" where wsx doesn't know yet.
+fn stat_rows(stats: &WorkspaceStats, now: u64) -> Vec<(&'static str, String)> {
+    let s = &stats.sessions;
+    let worktrees = stats.main_worktrees + stats.linked_worktrees;
+    let git_of = |value: String| {
+        if stats.git_known == 0 {
+            "—".to_string()
+        } else if stats.git_known < worktrees {
+            format!("{}  ({} of {} read)", value, stats.git_known, worktrees)
+        } else {
+            value
+        }
+    };
+    vec![
+        ("Projects", stats.projects.to_string()),
+        (
+            "Worktrees",
+            format!("{}  ({} main, {} linked)", worktrees, stats.main_worktrees, stats.linked_worktrees),
+        ),
+        (
+            "Sessions",
+            format!(
+                "{}  ({} active, {} idle, {} muted, {} need attention)",
+                s.total(),
+                s.active,
+                s.idle,
+                s.muted,
+                s.attention
+            ),
+        ),
+        ("Dirty worktrees", git_of(stats.dirty.to_string())),
+        ("Ahead / behind", git_of(format!("↑{} ↓{}", stats.ahead, stats.behind))),
+        (
+            "Oldest session",
+            stats.oldest.as_ref().map_or("—".to_string(), |(name, ts)| {
+                let age = fmt_idle(std::time::Duration::from_secs(now.saturating_sub(*ts)));
+                format!("{}  (started {} ago)", name, age)
+            }),
+        ),
+    ]
+}
@@ -0,0 +1,191 @@
+// Colour themes — the UI is drawn in the dark palette, then the finished frame is
+// adapted: darker text for light terminals, or no colour at all for NO_COLOR.
+
+use crate::config::global::ThemeSetting;
+use crate::model::workspace::SessionMark;
+use ratatui::{buffer::Buffer, style::{Color, Modifier, Style}};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// default fg/bg only; highlights become reverse video
+    Mono,
+}
+
+impl Theme {
+    /// NO_COLOR wins, then a theme pinned in config, then the detected background.
+    pub fn resolve(setting: ThemeSetting, no_color: bool, light_background: Option<bool>) -> Self {
+        if no_color {
+            return Theme::Mono;
+        }
+        match setting {
+            ThemeSetting::Dark => Theme::Dark,
+            ThemeSetting::Light => Theme::Light,
+            ThemeSetting::Mono => Theme::Mono,
+            ThemeSetting::Auto if light_background == Some(true) => Theme::Light,
+            ThemeSetting::Auto => Theme::Dark,
+        }
+    }
+
+    /// Resolve from the environment; only asks the terminal (OSC 11) when nothing else decides.
+    pub fn detect(setting: ThemeSetting) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if no_color || setting != ThemeSetting::Auto {
+            return Self::resolve(setting, no_color, None);
+        }
+        let light = std::env::var("COLORFGBG")
+            .ok()
+            .and_then(|v| colorfgbg_is_light(&v))
+            .or_else(|| {
+                let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+                if dumb {
+                    return None;
+                }
+                crate::tui::query_background().as_deref().and_then(osc11_is_light)
+            });
+        Self::resolve(setting, false, light)
+    }
+
+    /// Rewrite a rendered frame for this theme. No-op for `Dark`, the palette the UI is drawn in.
+    pub fn apply(self, buf: &mut Buffer) {
+        match self {
+            Theme::Dark => {}
+            Theme::Mono => {
+                for cell in buf.content.iter_mut() {
+                    if cell.bg != Color::Reset {
+                        cell.modifier.insert(Modifier::REVERSED);
+                    }
+                    cell.fg = Color::Reset;
+                    cell.bg = Color::Reset;
+                }
+            }
+            Theme::Light => {
+                // Coloured backgrounds (selection, badges) already carry black text.
+                for cell in buf.content.iter_mut().filter(|c| c.bg == Color::Reset) {
+                    cell.fg = on_light(cell.fg);
+                }
+            }
+        }
+    }
+}
+
+/// Glyphs for session markers. `Dots` tell states apart mostly by colour; `Shapes`
+/// (`accessible_markers = true`) give each its own glyph and text attribute as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Markers {
+    #[default]
+    Dots,
+    Shapes,
+}
+
+impl Markers {
+    pub fn new(accessible: bool) -> Self {
+        if accessible { Markers::Shapes } else { Markers::Dots }
+    }
+
+    pub fn marker(self, mark: SessionMark) -> (&'static str, Style) {
+        let (glyph, color, modifier) = match (self, mark) {
+            (Markers::Dots, SessionMark::Attention) => ("●", Color::Yellow, Modifier::empty()),
+            (Markers::Dots, SessionMark::Active) => ("◉", Color::Green, Modifier::empty()),
+            (Markers::Dots, SessionMark::Finished) => ("●", Color::Yellow, Modifier::empty()),
+            (Markers::Dots, SessionMark::Idle) => ("○", Color::Gray, Modifier::empty()),
+            (Markers::Dots, SessionMark::Muted) => ("⊘", Color::DarkGray, Modifier::empty()),
+            (Markers::Shapes, SessionMark::Attention) => ("!", Color::Yellow, Modifier::BOLD | Modifier::REVERSED),
+            (Markers::Shapes, SessionMark::Active) => ("◆", Color::Green, Modifier::BOLD),
+            (Markers::Shapes, SessionMark::Finished) => ("✔", Color::Yellow, Modifier::UNDERLINED),
+            (Markers::Shapes, SessionMark::Idle) => ("·", Color::Gray, Modifier::empty()),
+            (Markers::Shapes, SessionMark::Muted) => ("⊘", Color::DarkGray, Modifier::DIM),
+        };
+        (glyph, Style::default().fg(color).add_modifier(modifier))
+    }
+
+    pub fn glyph(self, mark: SessionMark) -> &'static str {
+        self.marker(mark).0
+    }
+}
+
+/// A foreground colour that stays readable on a light background.
+fn on_light(fg: Color) -> Color {
+    match fg {
+        Color::White => Color::Black,
+        Color::Gray => Color::DarkGray,
+        Color::Yellow | Color::LightYellow => Color::Rgb(150, 110, 0),
+        Color::Cyan | Color::LightCyan => Color::Rgb(0, 120, 140),
+        Color::LightGreen => Color::Green,
+        Color::LightRed => Color::Red,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::Rgb(r, g, b) if luminance(r, g, b) > 0.45 => {
+            Color::Rgb(r / 2, g / 2, b / 2)
+        }
+        other => other,
+    }
+}
+
+fn luminance(r: u8, g: u8, b: u8) -> f32 {
+    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+}
+
+/// `COLORFGBG` is "fg;bg" (rxvt, Konsole, …), sometimes "fg;default;bg"; 7 and 15 are light.
+fn colorfgbg_is_light(value: &str) -> Option<bool> {
+    let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    Some(matches!(bg, 7 | 15))
+}
+
+/// Parse an OSC 11 reply, "ESC ] 11 ; rgb:RRRR/GGGG/BBBB" ended by BEL or ST, into light/dark.
+fn osc11_is_light(reply: &str) -> Option<bool> {
+    let start = reply.find("]11;rgb:")? + "]11;rgb:".len();
+    let body = &reply[start..];
+    let end = body.find(['\x07', '\x1b']).unwrap_or(body.len());
+    let mut channels = body[..end].split('/').map(|hex| {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = 16u32.checked_pow(hex.len() as u32)?.checked_sub(1)?;
+        (max > 0).then(|| (value * 255 / max) as u8)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    Some(luminance(r, g, b) > 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{colorfgbg_is_light, osc11_is_light, Markers, SessionMark, Theme};
+    use crate::config::global::ThemeSetting;
+    use crate::tmux::monitor::SessionKind;
+    use std::collections::HashSet;
+
+    #[test]
+    fn parses_background_hints() {
+        assert_eq!(colorfgbg_is_light("0;15"), Some(true));
+        assert_eq!(colorfgbg_is_light("15;default;0"), Some(false));
+        assert_eq!(colorfgbg_is_light("garbage"), None);
+        assert_eq!(osc11_is_light("\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;c"), Some(true));
+        assert_eq!(osc11_is_light("\x1b]11;rgb:1e1e/1e1e/2e2e\x07"), Some(false));
+        assert_eq!(osc11_is_light("\x1b]11;rgb:fd/f6/e3\x07"), Some(true), "2-digit channels");
+        assert_eq!(osc11_is_light("\x1b[?62;c"), None, "DA1 only: OSC 11 unsupported");
+    }
+
+    #[test]
+    fn no_color_and_pinned_themes_beat_detection() {
+        assert_eq!(Theme::resolve(ThemeSetting::Light, true, Some(true)), Theme::Mono);
+        assert_eq!(Theme::resolve(ThemeSetting::Dark, false, Some(true)), Theme::Dark);
+        assert_eq!(Theme::resolve(ThemeSetting::Auto, false, Some(true)), Theme::Light);
+        assert_eq!(Theme::resolve(ThemeSetting::Auto, false, None), Theme::Dark);
+    }
+
+    #[test]
+    fn shape_markers_differ_without_colour() {
+        let marks = SessionMark::ALL.map(|m| Markers::Shapes.marker(m));
+        let glyphs: HashSet<_> = marks.iter().map(|(g, _)| g).collect();
+        let modifiers: HashSet<_> = marks.iter().map(|(_, s)| s.add_modifier).collect();
+        assert_eq!((glyphs.len(), modifiers.len()), (marks.len(), marks.len()));
+        // drawn next to the session-kind glyph, so none may pass for one
+        let kinds = [SessionKind::Editor, SessionKind::Server, SessionKind::Run].map(SessionKind::glyph);
+        assert!(marks.iter().all(|(g, _)| !kinds.contains(g)), "{:?}", marks);
+        // nor for the tree's collapse arrows
+        assert!(kinds.iter().all(|k| !["▼", "▶", "▾", "▸"].contains(k)), "{:?}", kinds);
+        // the default dots share one for both states that want attention
+        assert_eq!(Markers::Dots.glyph(SessionMark::Attention), Markers::Dots.glyph(SessionMark::Finished));
+    }
+}
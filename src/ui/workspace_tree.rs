@@ -1,11 +1,31 @@
 // Left sidebar — 3-level tree (Project -> Worktree -> Session) using ratatui List.
 
 use crate::app::IDLE_SECS;
-use crate::model::workspace::{flatten_tree, FlatEntry, WorkspaceState};
+use crate::config::global::{GlobalConfig, MainWorktree, ProjectOrder};
+use crate::model::frecency::{fmt_idle, now_secs};
+use crate::model::workspace::{flatten_tree, FlatEntry, SessionInfo, WorkspaceState};
+use crate::tmux::monitor::SessionKind;
+use crate::ui::path::shorten_path;
+use crate::ui::scrollbar::render_scrollbar;
+use crate::ui::theme::Markers;
+use std::collections::BTreeMap;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, ListState},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// How `render_tree` labels and highlights rows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeStyle<'a> {
+    pub move_mode: bool,
+    /// full tmux session names and worktree directory names instead of display names
+    pub raw_names: bool,
+    /// tmux session wsx runs in, tagged "(this)"
+    pub own_session: Option<&'a str>,
+    /// no tmux server: sessions and windows are the last ones seen, greyed out
+    pub tmux_down: bool,
+}
 
 pub fn render_tree(
     frame: &mut Frame,
@@ -13,15 +33,27 @@ pub fn render_tree(
     workspace: &WorkspaceState,
     selected: usize,
     scroll_offset: usize,
-    is_move_mode: bool,
+    style: TreeStyle<'_>,
+    config: &GlobalConfig,
 ) {
+    let order = config.project_order;
+    let markers = Markers::new(config.accessible_markers);
+    let stale_after_secs = Some(config.stale_session_days().saturating_mul(86_400)).filter(|&s| s > 0);
     let flat = flatten_tree(workspace);
+    let now = now_secs();
+    let row_width = area.width.saturating_sub(2) as usize; // inside the borders
 
     let items: Vec<ListItem> = flat
         .iter()
         .map(|entry| match entry {
             FlatEntry::Project { idx } => {
                 let p = &workspace.projects[*idx];
+                if p.missing {
+                    let prefix = format!("▶ {} — path missing: ", p.name);
+                    let room = row_width.saturating_sub(prefix.width());
+                    let label = format!("{}{}", prefix, shorten_path(&p.path, dirs::home_dir().as_deref(), room));
+                    return ListItem::new(label).style(Style::default().fg(Color::Red).bold());
+                }
                 let icon = if p.expanded { "▼" } else { "▶" };
                 let count = if p.expanded {
                     String::new()
@@ -29,7 +61,15 @@ pub fn render_tree(
                     format!(" [{}]", p.worktrees.len())
                 };
                 let label = format!("{} {}{}", icon, p.name, count);
-                ListItem::new(label).style(Style::default().fg(Color::Cyan).bold())
+                let hidden = p.worktrees.iter().filter(|w| w.hidden).count();
+                let hidden = if hidden > 0 { format!(" ({} hidden)", hidden) } else { String::new() };
+                let syncing = if p.syncing { format!(" {} syncing…", spinner()) } else { String::new() };
+                let line = Line::from(vec![
+                    Span::styled(label, Style::default().fg(Color::Cyan).bold()),
+                    Span::styled(hidden, Style::default().fg(Color::DarkGray)),
+                    Span::styled(syncing, Style::default().fg(Color::DarkGray)),
+                ]);
+                ListItem::new(line)
             }
             FlatEntry::Worktree {
                 project_idx,
@@ -55,35 +95,53 @@ pub fn render_tree(
                 };
                 let proj_prefix = format!("{}-", p.name);
                 let short_name = wt.name.strip_prefix(&proj_prefix).unwrap_or(&wt.name);
-                let display = if let Some(alias) = &wt.alias {
+                let dir_name = || wt.path.file_name().map(|n| n.to_string_lossy().into_owned());
+                let display = if style.raw_names {
+                    dir_name().unwrap_or_else(|| wt.path.display().to_string())
+                } else if let Some(alias) = &wt.alias {
                     format!("{} ({})", alias, short_name)
                 } else if wt.is_main {
                     wt.branch.clone()
+                } else if wt.detached {
+                    format!("@{}", wt.branch)
                 } else {
                     short_name.to_string()
                 };
 
                 let dirty = wt.git_info.as_ref().map(|g| !g.modified_files.is_empty()).unwrap_or(false);
 
-                let mut spans = vec![Span::raw(format!(" {} {}{}", expand_icon, main_mark, display))];
+                let pin_mark = if wt.pinned { "★ " } else { "" };
+                let lock_mark = if wt.locked.is_some() { "🔒 " } else { "" };
+                let prefix = format!(" {} {}{}{}", expand_icon, main_mark, pin_mark, lock_mark);
+                let mut badges = vec![];
 
                 // * directly after name (no space) if dirty
                 if dirty {
-                    spans.push(Span::styled("*", Style::default().fg(Color::Yellow)));
+                    badges.push(Span::styled("*", Style::default().fg(Color::Yellow)));
+                }
+
+                // commits not yet on the default branch
+                if let Some(gi) = wt.git_info.as_ref().filter(|g| g.trunk_ahead > 0) {
+                    let style = if gi.trunk_ahead >= TRUNK_AHEAD_WARN {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    badges.push(Span::styled(format!(" +{}", gi.trunk_ahead), style));
                 }
 
                 // remote tracking indicators
                 if let Some(gi) = &wt.git_info {
                     match (gi.behind, gi.ahead) {
-                        (b, a) if b > 0 && a > 0 => spans.push(Span::styled(
+                        (b, a) if b > 0 && a > 0 => badges.push(Span::styled(
                             format!(" ↓{}↑{}", b, a),
                             Style::default().fg(Color::Magenta),
                         )),
-                        (b, _) if b > 0 => spans.push(Span::styled(
+                        (b, _) if b > 0 => badges.push(Span::styled(
                             format!(" ↓{}", b),
                             Style::default().fg(Color::Red),
                         )),
-                        (_, a) if a > 0 => spans.push(Span::styled(
+                        (_, a) if a > 0 => badges.push(Span::styled(
                             format!(" ↑{}", a),
                             Style::default().fg(Color::Cyan),
                         )),
@@ -91,12 +149,38 @@ pub fn render_tree(
                     }
                 }
                 if has_activity {
-                    spans.push(Span::styled(" ●", Style::default().fg(Color::White)));
+                    badges.push(Span::styled(" ●", Style::default().fg(Color::White)));
                 }
                 if !sess_badge.is_empty() {
-                    spans.push(Span::raw(sess_badge));
+                    badges.push(Span::raw(sess_badge));
                 }
 
+                // Badges win over the name: shorten the name so they stay visible.
+                let badge_width: usize = badges.iter().map(|b| b.width()).sum();
+                let name_room = row_width.saturating_sub(prefix.width() + badge_width);
+                let name = Span::raw(format!("{}{}", prefix, truncate(&display, name_room)));
+                let name = if wt.is_main && config.main_worktree == MainWorktree::Dim {
+                    name.style(Style::default().fg(Color::DarkGray))
+                } else {
+                    name
+                };
+                let mut spans = vec![name];
+                spans.extend(badges);
+                // The note only gets what the row has left over.
+                if let Some(note) = &wt.note {
+                    let used: usize = spans.iter().map(|s| s.width()).sum();
+                    let room = row_width.saturating_sub(used + " · ".width());
+                    if room >= 2 {
+                        spans.push(Span::styled(
+                            format!(" · {}", truncate(note, room)),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                }
+
+                if wt.hidden {
+                    return ListItem::new(greyed(Line::from(spans)));
+                }
                 ListItem::new(Line::from(spans)).style(Style::default().fg(Color::White))
             }
             FlatEntry::Session {
@@ -106,30 +190,75 @@ pub fn render_tree(
             } => {
                 let sess = &workspace.projects[*project_idx].worktrees[*worktree_idx].sessions
                     [*session_idx];
-                let elapsed = sess.last_activity.map(|t| t.elapsed());
-                let active = elapsed.map(|e| e.as_secs() < IDLE_SECS).unwrap_or(false);
-                let (icon, icon_color) = if sess.muted {
-                    ("⊘", Color::DarkGray) // muted — no activity tracking
-                } else if sess.has_activity {
-                    ("●", Color::Yellow) // tmux bell — needs attention
-                } else if active {
-                    ("◉", Color::Green) // actively outputting
-                } else if sess.has_running_app && !sess.running_app_suppressed {
-                    ("●", Color::Yellow) // app open but quiet — needs attention
-                } else {
-                    ("○", Color::Gray) // truly idle
-                };
-                let idle_str = match elapsed {
-                    Some(e) if e.as_secs() >= IDLE_SECS => format!("  {}", fmt_idle(e)),
-                    _ => String::new(),
+                let (icon, icon_style) = session_icon(sess, markers);
+                let (kind, kind_color) = kind_icon(sess, &config.session.icons);
+                let waiting = sess
+                    .attention_since
+                    .filter(|_| sess.needs_attention())
+                    .map(|t| format!("  waiting {}", fmt_idle(t.elapsed())));
+                let idle_str = match waiting {
+                    Some(_) => String::new(),
+                    None => idle_for(sess).map(|e| format!("  {}", fmt_idle(e))).unwrap_or_default(),
                 };
+                let age = stale_after_secs
+                    .and_then(|min| sess.age_secs(now).filter(|&age| age > min))
+                    .map(|age| format!("  {} old", fmt_idle(std::time::Duration::from_secs(age))))
+                    .unwrap_or_default();
+                // "dev ·2": the number tmux needed to tell duplicates apart, set off from the name
+                let (name, suffix) =
+                    if style.raw_names { (sess.name.as_str(), None) } else { sess.name_parts() };
+                let suffix = suffix.map(|n| format!(" ·{}", n)).unwrap_or_default();
+                let own = style.own_session == Some(sess.name.as_str());
                 let line = Line::from(vec![
                     Span::raw("  "),
-                    Span::styled(icon, Style::default().fg(icon_color)),
+                    Span::styled(icon, icon_style),
                     Span::styled(
-                        format!(" {}{}", sess.display_name, idle_str),
-                        Style::default().fg(Color::Rgb(210, 200, 185)),
+                        if kind.is_empty() { kind } else { format!(" {}", kind) },
+                        Style::default().fg(kind_color),
                     ),
+                    Span::styled(if sess.pinned { " ★" } else { "" }, Style::default().fg(Color::Yellow)),
+                    Span::styled(format!(" {}", name), Style::default().fg(Color::Rgb(210, 200, 185))),
+                    Span::styled(suffix, Style::default().fg(Color::DarkGray)),
+                    Span::styled(idle_str, Style::default().fg(Color::Rgb(210, 200, 185))),
+                    Span::styled(waiting.unwrap_or_default(), Style::default().fg(Color::Yellow)),
+                    Span::styled(age, Style::default().fg(Color::DarkGray)),
+                    Span::styled(if own { " (this)" } else { "" }, Style::default().fg(Color::Cyan)),
+                ]);
+                ListItem::new(if style.tmux_down { greyed(line) } else { line })
+            }
+            FlatEntry::Window {
+                project_idx,
+                worktree_idx,
+                session_idx,
+                window_idx,
+            } => {
+                let win = &workspace.projects[*project_idx].worktrees[*worktree_idx].sessions
+                    [*session_idx]
+                    .windows[*window_idx];
+                let line = Line::from(vec![
+                    Span::styled("     └ ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("{}:{}", win.index, win.name),
+                        Style::default().fg(Color::Rgb(170, 160, 150)),
+                    ),
+                ]);
+                ListItem::new(if style.tmux_down { greyed(line) } else { line })
+            }
+            FlatEntry::ArchiveHeader { project_idx } => {
+                let p = &workspace.projects[*project_idx];
+                let icon = if p.archived_expanded { "▾" } else { "▸" };
+                let label = format!(" {} Archived ({})", icon, p.archived.len());
+                ListItem::new(label).style(Style::default().fg(Color::DarkGray))
+            }
+            FlatEntry::Archived { project_idx, archive_idx } => {
+                let archived = &workspace.projects[*project_idx].archived[*archive_idx];
+                let name = if style.raw_names { &archived.tag } else { &archived.branch };
+                let date = format!("  {}", archived.date);
+                let room = row_width.saturating_sub(5 + date.width());
+                let line = Line::from(vec![
+                    Span::styled("   ⌂ ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(truncate(name, room), Style::default().fg(Color::Gray)),
+                    Span::styled(date, Style::default().fg(Color::DarkGray)),
                 ]);
                 ListItem::new(line)
             }
@@ -141,8 +270,12 @@ pub fn render_tree(
         list_state.select(Some(selected.min(flat.len().saturating_sub(1))));
     }
 
-    let (block_title, highlight_bg) = if is_move_mode {
+    let (block_title, highlight_bg) = if style.move_mode {
         (" Workspaces — MOVE ", Color::Green)
+    } else if style.raw_names {
+        (" Workspaces — raw names ", Color::Yellow)
+    } else if order == ProjectOrder::Frecency {
+        (" Workspaces — recent ", Color::Yellow)
     } else {
         (" Workspaces ", Color::Yellow)
     };
@@ -156,27 +289,68 @@ pub fn render_tree(
         .highlight_style(Style::default().fg(Color::Black).bg(highlight_bg).bold())
         .highlight_symbol("");
 
+    let rows = flat.len();
     frame.render_stateful_widget(list, area, &mut list_state);
+    let viewport = area.height.saturating_sub(2) as usize;
+    render_scrollbar(frame, area, rows, viewport, list_state.offset());
 }
 
-fn fmt_idle(d: std::time::Duration) -> String {
-    let s = d.as_secs();
-    match s {
-        s if s < 60 => format!("{}s", s),
-        s if s < 3600 => format!("{}m", s / 60),
-        s => format!("{}h", s / 3600),
-    }
+fn greyed(line: Line<'_>) -> Line<'_> {
+    Line::from(line.spans.into_iter().map(|s| s.fg(Color::DarkGray)).collect::<Vec<_>>())
 }
 
-/// Compute scroll offset to keep selected item visible.
-pub fn compute_scroll(selected: usize, visible_height: usize, current_offset: usize) -> usize {
-    let up_pad = (visible_height / 4).max(1); // scroll up when cursor within top 1/4
-    let down_pad = (visible_height * 3 / 4).max(1); // scroll down when cursor past 3/4
-    if selected < current_offset + up_pad {
-        selected.saturating_sub(up_pad - 1)
-    } else if selected >= current_offset + down_pad {
-        selected.saturating_sub(down_pad - 1)
-    } else {
-        current_offset
+/// Trunk divergence at which the `+n` badge turns from dim to yellow.
+const TRUNK_AHEAD_WARN: usize = 20;
+
+/// Cut `s` to `max` terminal columns, marking the cut with `…`. Wide chars (CJK, emoji)
+/// count as two, and one that would straddle the limit is dropped whole.
+pub(crate) fn truncate(s: &str, max: usize) -> String {
+    if s.width() <= max {
+        return s.to_string();
     }
+    let room = max.saturating_sub(1);
+    let mut used = 0;
+    let mut out: String = s
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= room
+        })
+        .collect();
+    out.push('…');
+    out
+}
+
+pub(crate) fn session_icon(sess: &SessionInfo, markers: Markers) -> (&'static str, Style) {
+    markers.marker(sess.mark())
+}
+
+/// What `sess` is running, as a glyph after its activity icon: a `[session.icons]` entry
+/// for one of its commands, else the built-in glyph for its kind ("" for a plain shell).
+pub(crate) fn kind_icon(sess: &SessionInfo, icons: &BTreeMap<String, String>) -> (String, Color) {
+    let kind = SessionKind::of(&sess.commands);
+    let color = match kind {
+        SessionKind::Shell => Color::Gray,
+        SessionKind::Editor => Color::Magenta,
+        SessionKind::Server => Color::Cyan,
+        SessionKind::Run => Color::Blue,
+    };
+    let glyph = sess.commands.iter().find_map(|c| icons.get(c.trim())).cloned();
+    (glyph.unwrap_or_else(|| kind.glyph().to_string()), color)
+}
+
+/// Time since the last output, once the session counts as idle.
+pub(crate) fn idle_for(sess: &SessionInfo) -> Option<std::time::Duration> {
+    sess.last_activity
+        .map(|t| t.elapsed())
+        .filter(|e| e.as_secs() >= IDLE_SECS)
+}
+
+/// Braille spinner frame for the wall clock, ten a second.
+fn spinner() -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    FRAMES[(millis / 100 % FRAMES.len() as u128) as usize]
 }
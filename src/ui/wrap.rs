@@ -0,0 +1,115 @@
+// Rows a `Paragraph` with `Wrap { trim: false }` takes at a given width — ratatui only
+// measures that behind an unstable feature. Follows its word wrapper: whole words move
+// to the next row, a word wider than the row is broken, whitespace stays put.
+
+use std::collections::VecDeque;
+
+use ratatui::prelude::*;
+use unicode_width::UnicodeWidthStr;
+
+/// Rows `text` wraps to at `width`; none at width 0, as ratatui draws nothing there.
+pub fn wrapped_rows(text: &Text, width: u16) -> usize {
+    if width == 0 {
+        return 0;
+    }
+    text.lines.iter().map(|line| line_rows(line, width)).sum()
+}
+
+fn line_rows(line: &Line, max: u16) -> usize {
+    let mut rows = 0;
+    // Widths of the row being filled, the word being read and the whitespace before it;
+    // `row_empty` and `word_len` count zero-width symbols the widths miss.
+    let (mut row_width, mut word_width, mut space_width) = (0u16, 0u16, 0u16);
+    let (mut row_empty, mut word_len) = (true, 0);
+    let mut spaces: VecDeque<u16> = VecDeque::new();
+    let mut after_word = false;
+
+    for grapheme in line.spans.iter().flat_map(|span| span.styled_graphemes(Style::default())) {
+        let is_space = grapheme.symbol == "\u{200b}"
+            || (grapheme.symbol != "\u{a0}" && grapheme.symbol.chars().all(char::is_whitespace));
+        let width = grapheme.symbol.width() as u16;
+        if width > max {
+            continue;
+        }
+
+        // A word just ended, or one with its whitespace fills a row on its own: it joins the row.
+        if (after_word && is_space) || (row_empty && word_width + space_width + width > max) {
+            row_empty &= spaces.is_empty() && word_len == 0;
+            row_width += space_width + word_width;
+            spaces.clear();
+            (space_width, word_width, word_len) = (0, 0, 0);
+        }
+
+        if row_width >= max || (width > 0 && row_width + space_width + word_width >= max) {
+            let mut left = max.saturating_sub(row_width);
+            rows += 1;
+            (row_width, row_empty) = (0, true);
+            // Whitespace that still fit at the end of the full row stays there.
+            while let Some(&front) = spaces.front() {
+                if front > left {
+                    break;
+                }
+                space_width -= front;
+                left -= front;
+                spaces.pop_front();
+            }
+            if is_space && spaces.is_empty() {
+                continue;
+            }
+        }
+
+        if is_space {
+            space_width += width;
+            spaces.push_back(width);
+        } else {
+            word_width += width;
+            word_len += 1;
+        }
+        after_word = !is_space;
+    }
+
+    if row_empty && word_len == 0 && !spaces.is_empty() {
+        rows += 1;
+    }
+    if !row_empty || !spaces.is_empty() || word_len > 0 {
+        rows += 1;
+    }
+    rows.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wrapped_rows;
+    use ratatui::{prelude::*, widgets::{Paragraph, Wrap}};
+
+    /// Rows ratatui actually draws `text` in, to hold `wrapped_rows` against.
+    fn drawn_rows(text: &Text<'static>, width: u16) -> usize {
+        let area = Rect::new(0, 0, width, 64);
+        let mut buf = Buffer::empty(area);
+        Paragraph::new(text.clone()).wrap(Wrap { trim: false }).render(area, &mut buf);
+        (0..area.height)
+            .rposition(|y| (0..width).any(|x| buf[(x, y)].symbol() != " "))
+            .map_or(0, |y| y + 1)
+    }
+
+    #[test]
+    fn counts_rows_as_ratatui_wraps_them() {
+        let samples = [
+            "hello world",
+            "a word-that-is-much-longer-than-the-row ends it",
+            "    indented and then some words to wrap over",
+            "trailing spaces      x",
+            "機能ブランチ の 説明 を ここに 書く",
+            "one\ntwo lines\n\nafter a blank",
+        ];
+        for sample in samples {
+            // End each line in a mark so trailing blank rows are drawn too.
+            let text = Text::from(sample.lines().map(|l| Line::from(format!("{}|", l))).collect::<Vec<_>>());
+            for width in [1, 3, 5, 8, 13, 40] {
+                assert_eq!(wrapped_rows(&text, width), drawn_rows(&text, width), "{:?} at {}", sample, width);
+            }
+        }
+        assert_eq!(wrapped_rows(&Text::from("anything"), 0), 0);
+        assert_eq!(wrapped_rows(&Text::from(vec![Line::from(""), Line::from("")]), 10), 2);
+    }
+}